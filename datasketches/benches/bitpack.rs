@@ -0,0 +1,102 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Throughput of the block kernels of `datasketches::util::bitpack` and of the compressed theta
+//! format built on them.
+//!
+//! Run with `cargo bench --bench bitpack`.
+
+use std::hint::black_box;
+
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::Throughput;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+use datasketches::util::bitpack::BLOCK_WIDTH;
+use datasketches::util::bitpack::pack_bits_block;
+use datasketches::util::bitpack::unpack_bits_block;
+
+const NUM_BLOCKS: usize = 4096;
+const WIDTHS: [u8; 8] = [1, 3, 8, 13, 24, 31, 45, 63];
+
+fn values(bits: u8) -> Vec<u64> {
+    let mask = (1u64 << bits) - 1;
+    (0..NUM_BLOCKS * BLOCK_WIDTH)
+        .map(|i| (i as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c13) & mask)
+        .collect()
+}
+
+fn bench_blocks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bitpack");
+    group.throughput(Throughput::Elements((NUM_BLOCKS * BLOCK_WIDTH) as u64));
+    for bits in WIDTHS {
+        let values = values(bits);
+        let mut bytes = vec![0u8; NUM_BLOCKS * bits as usize];
+        group.bench_with_input(BenchmarkId::new("pack", bits), &bits, |b, &bits| {
+            b.iter(|| {
+                for (block, out) in values
+                    .chunks_exact(BLOCK_WIDTH)
+                    .zip(bytes.chunks_exact_mut(bits as usize))
+                {
+                    pack_bits_block(black_box(block), out, bits);
+                }
+            })
+        });
+
+        let mut unpacked = vec![0u64; values.len()];
+        group.bench_with_input(BenchmarkId::new("unpack", bits), &bits, |b, &bits| {
+            b.iter(|| {
+                for (block, out) in black_box(&bytes)
+                    .chunks_exact(bits as usize)
+                    .zip(unpacked.chunks_exact_mut(BLOCK_WIDTH))
+                {
+                    unpack_bits_block(out, block, bits);
+                }
+            })
+        });
+        assert_eq!(unpacked, values);
+    }
+    group.finish();
+}
+
+fn bench_compressed_theta(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compressed_theta");
+    for lg_k in [12u8, 16] {
+        let mut sketch = ThetaSketch::builder().lg_k(lg_k).build();
+        for i in 0..(1u64 << (lg_k + 2)) {
+            sketch.update(i);
+        }
+        let compact = sketch.compact(true);
+        let bytes = compact.serialize_compressed();
+        group.throughput(Throughput::Elements(compact.num_retained() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("serialize", lg_k),
+            &compact,
+            |b, sketch| b.iter(|| sketch.serialize_compressed()),
+        );
+        group.bench_with_input(BenchmarkId::new("deserialize", lg_k), &bytes, |b, bytes| {
+            b.iter(|| CompactThetaSketch::deserialize(bytes).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_blocks, bench_compressed_theta);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Latency percentiles of single lookups and updates on a frequent items map filled to its load
+//! threshold, where probe sequences are longest.
+//!
+//! Criterion reports means, which hide the long probes this is about, so every operation is
+//! timed on its own. Run with `cargo bench --bench frequencies_probing`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use datasketches::frequencies::FrequentItemsSketch;
+
+const NUM_OPS: usize = 1_000_000;
+
+fn filled_sketch(lg_max_map_size: u8) -> FrequentItemsSketch<u64> {
+    let mut sketch = FrequentItemsSketch::<u64>::new(1 << lg_max_map_size);
+    // one key short of the capacity, so that no purge empties the map
+    let num_keys = sketch.maximum_map_capacity() as u64 - 1;
+    for key in 0..num_keys {
+        sketch.update(key.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    }
+    assert_eq!(sketch.num_active_items() as u64, num_keys);
+    sketch
+}
+
+fn report(name: &str, mut nanos: Vec<u64>) {
+    nanos.sort_unstable();
+    let percentile = |p: f64| nanos[((nanos.len() - 1) as f64 * p) as usize];
+    println!(
+        "{name:<28} p50 {:>5} ns  p99 {:>5} ns  p99.9 {:>6} ns  max {:>7} ns",
+        percentile(0.5),
+        percentile(0.99),
+        percentile(0.999),
+        nanos[nanos.len() - 1],
+    );
+}
+
+fn time_each(mut op: impl FnMut(u64)) -> Vec<u64> {
+    (0..NUM_OPS as u64)
+        .map(|i| {
+            let start = Instant::now();
+            op(i);
+            start.elapsed().as_nanos() as u64
+        })
+        .collect()
+}
+
+fn main() {
+    for lg_max_map_size in [10u8, 14, 18] {
+        let mut sketch = filled_sketch(lg_max_map_size);
+        let num_keys = sketch.num_active_items() as u64;
+        let key = |i: u64| (i % num_keys).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+
+        report(
+            &format!("lookup hit/{lg_max_map_size}"),
+            time_each(|i| {
+                black_box(sketch.estimate(&key(i)));
+            }),
+        );
+        report(
+            &format!("lookup miss/{lg_max_map_size}"),
+            time_each(|i| {
+                black_box(sketch.estimate(&(key(i) | (1 << 63))));
+            }),
+        );
+        report(
+            &format!("update hit/{lg_max_map_size}"),
+            time_each(|i| sketch.update(key(i))),
+        );
+    }
+}
@@ -0,0 +1,198 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Merge throughput of the sketches through `common::MergeableSketch`.
+//!
+//! Each benchmark merges sketches of disjoint shards of the same workload into a copy of the
+//! first one, as a query engine does when it combines partial aggregates. Run with
+//! `cargo bench --features perf --bench merge`.
+
+use std::fmt::Debug;
+
+use criterion::BatchSize;
+use criterion::BenchmarkGroup;
+use criterion::Criterion;
+use criterion::Throughput;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::measurement::WallTime;
+use datasketches::bloom::BloomFilter;
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::common::MergeableSketch;
+use datasketches::common::Sketch;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::kll::KllSketch;
+use datasketches::perf::Uniform;
+use datasketches::perf::Zipf;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+use datasketches::theta::ThetaUnion;
+
+const NUM_SHARDS: usize = 16;
+const ITEMS_PER_SHARD: usize = 20_000;
+const SEED: u64 = 42;
+
+/// Builds one sketch per shard of `items`.
+fn shards<I: Copy, S: Sketch<I>>(items: &[I], new: impl Fn() -> S) -> Vec<S> {
+    items
+        .chunks(ITEMS_PER_SHARD)
+        .map(|shard| {
+            let mut sketch = new();
+            shard.iter().for_each(|&item| sketch.update(item));
+            sketch
+        })
+        .collect()
+}
+
+fn bench_merge<S>(group: &mut BenchmarkGroup<'_, WallTime>, name: &str, shards: &[S])
+where
+    S: MergeableSketch + Clone,
+    S::Error: Debug,
+{
+    let (first, rest) = shards.split_first().unwrap();
+    group.bench_function(name, |b| {
+        b.iter_batched(
+            || first.clone(),
+            |mut merged| {
+                rest.iter().for_each(|shard| merged.merge(shard).unwrap());
+                merged
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn bench_distinct_counting(c: &mut Criterion) {
+    let items: Vec<u64> = Uniform::new(u64::MAX, SEED)
+        .take(NUM_SHARDS * ITEMS_PER_SHARD)
+        .collect();
+    let mut group = c.benchmark_group("merge_distinct_counting");
+    group.throughput(Throughput::Elements(NUM_SHARDS as u64 - 1));
+
+    let theta: Vec<_> = shards(&items, || ThetaSketch::builder().lg_k(12).build())
+        .iter()
+        .map(|sketch| sketch.compact(true))
+        .collect();
+    bench_merge(&mut group, "theta", &theta);
+    bench_merge(
+        &mut group,
+        "hll8",
+        &shards(&items, || HllSketch::new(12, HllType::Hll8)),
+    );
+    bench_merge(
+        &mut group,
+        "hll4",
+        &shards(&items, || HllSketch::new(12, HllType::Hll4)),
+    );
+    bench_merge(&mut group, "cpc", &shards(&items, || CpcSketch::new(12)));
+    group.finish();
+}
+
+fn bench_theta_serialized(c: &mut Criterion) {
+    let items: Vec<u64> = Uniform::new(u64::MAX, SEED)
+        .take(NUM_SHARDS * ITEMS_PER_SHARD)
+        .collect();
+    let images: Vec<Vec<u8>> = shards(&items, || ThetaSketch::builder().lg_k(12).build())
+        .iter()
+        .map(|sketch| sketch.compact(true).serialize())
+        .collect();
+    let mut group = c.benchmark_group("merge_theta_serialized");
+    group.throughput(Throughput::Elements(NUM_SHARDS as u64));
+    group.bench_function("deserialize", |b| {
+        b.iter(|| {
+            let mut union = ThetaUnion::builder().lg_k(12).build();
+            for bytes in &images {
+                let sketch = CompactThetaSketch::deserialize(bytes).unwrap();
+                union.update(&sketch).unwrap();
+            }
+            union.result()
+        })
+    });
+    group.bench_function("update_bytes", |b| {
+        b.iter(|| {
+            let mut union = ThetaUnion::builder().lg_k(12).build();
+            for bytes in &images {
+                union.update_bytes(bytes).unwrap();
+            }
+            union.result()
+        })
+    });
+    group.finish();
+}
+
+fn bench_frequencies(c: &mut Criterion) {
+    let items: Vec<u64> = Zipf::new(1_000_000, 1.1, SEED)
+        .take(NUM_SHARDS * ITEMS_PER_SHARD)
+        .collect();
+    let mut group = c.benchmark_group("merge_frequencies");
+    group.throughput(Throughput::Elements(NUM_SHARDS as u64 - 1));
+    bench_merge(
+        &mut group,
+        "frequent_items",
+        &shards(&items, || FrequentItemsSketch::<u64>::new(1024)),
+    );
+    bench_merge(
+        &mut group,
+        "countmin",
+        &shards(&items, || CountMinSketch::<u64>::new(5, 1024)),
+    );
+    group.finish();
+}
+
+fn bench_quantiles(c: &mut Criterion) {
+    let items: Vec<f64> = Zipf::new(1_000_000, 1.1, SEED)
+        .take(NUM_SHARDS * ITEMS_PER_SHARD)
+        .map(|key| key as f64)
+        .collect();
+    let mut group = c.benchmark_group("merge_quantiles");
+    group.throughput(Throughput::Elements(NUM_SHARDS as u64 - 1));
+    bench_merge(
+        &mut group,
+        "kll",
+        &shards(&items, KllSketch::<f64>::default),
+    );
+    bench_merge(&mut group, "tdigest", &shards(&items, TDigestMut::default));
+    group.finish();
+}
+
+fn bench_membership(c: &mut Criterion) {
+    let items: Vec<u64> = Uniform::new(u64::MAX, SEED)
+        .take(NUM_SHARDS * ITEMS_PER_SHARD)
+        .collect();
+    let mut group = c.benchmark_group("merge_membership");
+    group.throughput(Throughput::Elements(NUM_SHARDS as u64 - 1));
+    let new = || -> BloomFilter {
+        BloomFilterBuilder::with_accuracy((NUM_SHARDS * ITEMS_PER_SHARD) as u64, 0.01).build()
+    };
+    bench_merge(&mut group, "bloom", &shards(&items, new));
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_distinct_counting,
+    bench_theta_serialized,
+    bench_frequencies,
+    bench_quantiles,
+    bench_membership
+);
+criterion_main!(benches);
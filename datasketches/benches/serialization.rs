@@ -0,0 +1,126 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Serialization and deserialization throughput of the sketches through
+//! `common::SerializableSketch`, in bytes of the serialized image.
+//!
+//! Run with `cargo bench --features perf --bench serialization`.
+
+use std::fmt::Debug;
+
+use criterion::BenchmarkGroup;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::Throughput;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::measurement::WallTime;
+use datasketches::bloom::BloomFilter;
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::common::SerializableSketch;
+use datasketches::common::Sketch;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::kll::KllSketch;
+use datasketches::perf::Uniform;
+use datasketches::perf::Zipf;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+
+const NUM_ITEMS: usize = 100_000;
+const SEED: u64 = 42;
+
+fn build<I: Copy, S: Sketch<I>>(items: &[I], mut sketch: S) -> S {
+    items.iter().for_each(|&item| sketch.update(item));
+    sketch
+}
+
+fn bench_round_trip<S>(group: &mut BenchmarkGroup<'_, WallTime>, name: &str, sketch: &S)
+where
+    S: SerializableSketch,
+    S::Error: Debug,
+{
+    let bytes = sketch.serialize();
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.bench_function(BenchmarkId::new("serialize", name), |b| {
+        b.iter(|| sketch.serialize())
+    });
+    group.bench_function(BenchmarkId::new("deserialize", name), |b| {
+        b.iter(|| S::deserialize(&bytes).unwrap())
+    });
+}
+
+fn bench_serialization(c: &mut Criterion) {
+    let uniform: Vec<u64> = Uniform::new(u64::MAX, SEED).take(NUM_ITEMS).collect();
+    let zipf: Vec<u64> = Zipf::new(1_000_000, 1.1, SEED).take(NUM_ITEMS).collect();
+    let values: Vec<f64> = zipf.iter().map(|&key| key as f64).collect();
+
+    let mut group = c.benchmark_group("serialization");
+    let theta = build(&uniform, ThetaSketch::builder().lg_k(12).build());
+    bench_round_trip(&mut group, "theta", &theta);
+    let compact = theta.compact(true);
+    bench_round_trip(&mut group, "compact_theta", &compact);
+    let compressed = compact.serialize_compressed();
+    group.throughput(Throughput::Bytes(compressed.len() as u64));
+    group.bench_function(
+        BenchmarkId::new("serialize", "compact_theta_compressed"),
+        |b| b.iter(|| compact.serialize_compressed()),
+    );
+    group.bench_function(
+        BenchmarkId::new("deserialize", "compact_theta_compressed"),
+        |b| b.iter(|| CompactThetaSketch::deserialize(&compressed).unwrap()),
+    );
+    for (name, hll_type) in [
+        ("hll4", HllType::Hll4),
+        ("hll6", HllType::Hll6),
+        ("hll8", HllType::Hll8),
+    ] {
+        let hll = build(&uniform, HllSketch::new(12, hll_type));
+        bench_round_trip(&mut group, name, &hll);
+    }
+    bench_round_trip(&mut group, "cpc", &build(&uniform, CpcSketch::new(12)));
+    bench_round_trip(
+        &mut group,
+        "frequent_items",
+        &build(&zipf, FrequentItemsSketch::<u64>::new(1024)),
+    );
+    bench_round_trip(
+        &mut group,
+        "countmin",
+        &build(&zipf, CountMinSketch::<u64>::new(5, 1024)),
+    );
+    bench_round_trip(
+        &mut group,
+        "kll",
+        &build(&values, KllSketch::<f64>::default()),
+    );
+    bench_round_trip(
+        &mut group,
+        "tdigest",
+        &build(&values, TDigestMut::default()),
+    );
+    let bloom: BloomFilter = BloomFilterBuilder::with_accuracy(NUM_ITEMS as u64, 0.01).build();
+    bench_round_trip(&mut group, "bloom", &build(&uniform, bloom));
+    group.finish();
+}
+
+criterion_group!(benches, bench_serialization);
+criterion_main!(benches);
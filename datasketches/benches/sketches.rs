@@ -0,0 +1,185 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Update throughput of the sketches over the workloads of `datasketches::perf`.
+//!
+//! Run with `cargo bench --features perf`.
+
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::Throughput;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::kll::KllSketch;
+use datasketches::perf::Uniform;
+use datasketches::perf::Weighted;
+use datasketches::perf::Zipf;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::ThetaSketch;
+
+const NUM_ITEMS: usize = 100_000;
+const HIGH_CARDINALITY_ITEMS: usize = 4_000_000;
+const SEED: u64 = 42;
+
+fn bench_distinct_counting(c: &mut Criterion) {
+    let items: Vec<u64> = Uniform::new(u64::MAX, SEED).take(NUM_ITEMS).collect();
+    let mut group = c.benchmark_group("distinct_counting");
+    group.throughput(Throughput::Elements(NUM_ITEMS as u64));
+    for lg_k in [10u8, 12, 14] {
+        group.bench_with_input(BenchmarkId::new("theta", lg_k), &lg_k, |b, &lg_k| {
+            b.iter(|| {
+                let mut sketch = ThetaSketch::builder().lg_k(lg_k).build();
+                items.iter().for_each(|item| sketch.update_quiet(item));
+                sketch
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("hll8", lg_k), &lg_k, |b, &lg_k| {
+            b.iter(|| {
+                let mut sketch = HllSketch::new(lg_k, HllType::Hll8);
+                items.iter().for_each(|item| sketch.update(item));
+                sketch
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("cpc", lg_k), &lg_k, |b, &lg_k| {
+            b.iter(|| {
+                let mut sketch = CpcSketch::new(lg_k);
+                items.iter().for_each(|item| sketch.update(item));
+                sketch
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_high_cardinality(c: &mut Criterion) {
+    // far more items than retained hashes, with tables too large for the L2 cache
+    let items: Vec<u64> = Uniform::new(u64::MAX, SEED)
+        .take(HIGH_CARDINALITY_ITEMS)
+        .collect();
+    let mut group = c.benchmark_group("high_cardinality");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(HIGH_CARDINALITY_ITEMS as u64));
+    for lg_k in [16u8, 20] {
+        group.bench_with_input(BenchmarkId::new("theta", lg_k), &lg_k, |b, &lg_k| {
+            b.iter(|| {
+                let mut sketch = ThetaSketch::builder().lg_k(lg_k).build();
+                items.iter().for_each(|item| sketch.update_quiet(item));
+                sketch
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_frequencies(c: &mut Criterion) {
+    let items: Vec<(u64, u64)> = Weighted::new(Zipf::new(1_000_000, 1.1, SEED), 100, SEED)
+        .take(NUM_ITEMS)
+        .collect();
+    let mut group = c.benchmark_group("frequencies");
+    group.throughput(Throughput::Elements(NUM_ITEMS as u64));
+    group.bench_function("frequent_items", |b| {
+        b.iter(|| {
+            let mut sketch = FrequentItemsSketch::<u64>::new(1024);
+            for &(item, weight) in &items {
+                sketch.update_with_count(item, weight);
+            }
+            sketch
+        })
+    });
+    group.bench_function("countmin", |b| {
+        b.iter(|| {
+            let mut sketch = CountMinSketch::<u64>::new(5, 1024);
+            for &(item, weight) in &items {
+                sketch.update_with_weight(item, weight);
+            }
+            sketch
+        })
+    });
+    group.finish();
+}
+
+fn bench_countmin_width(c: &mut Criterion) {
+    // wide tables spread the cells of an item over the whole table, far beyond the L2 cache
+    let items: Vec<u64> = Uniform::new(u64::MAX, SEED).take(NUM_ITEMS).collect();
+    let mut group = c.benchmark_group("countmin_width");
+    group.throughput(Throughput::Elements(NUM_ITEMS as u64));
+    for lg_buckets in [10u32, 16, 20] {
+        let mut sketch = CountMinSketch::<u64>::new(5, 1 << lg_buckets);
+        group.bench_function(BenchmarkId::new("update", lg_buckets), |b| {
+            b.iter(|| items.iter().for_each(|item| sketch.update(item)))
+        });
+        group.bench_function(BenchmarkId::new("estimate", lg_buckets), |b| {
+            b.iter(|| items.iter().map(|item| sketch.estimate(item)).sum::<u64>())
+        });
+    }
+    group.finish();
+}
+
+fn bench_quantiles(c: &mut Criterion) {
+    let items: Vec<f64> = Zipf::new(1_000_000, 1.1, SEED)
+        .take(NUM_ITEMS)
+        .map(|key| key as f64)
+        .collect();
+    let mut group = c.benchmark_group("quantiles");
+    group.throughput(Throughput::Elements(NUM_ITEMS as u64));
+    group.bench_function("kll", |b| {
+        b.iter(|| {
+            let mut sketch = KllSketch::<f64>::default();
+            items.iter().for_each(|item| sketch.update(*item));
+            sketch
+        })
+    });
+    group.bench_function("tdigest", |b| {
+        b.iter(|| {
+            let mut sketch = TDigestMut::default();
+            items.iter().for_each(|item| sketch.update(*item));
+            sketch
+        })
+    });
+    group.finish();
+}
+
+fn bench_membership(c: &mut Criterion) {
+    let items: Vec<u64> = Uniform::new(u64::MAX, SEED).take(NUM_ITEMS).collect();
+    let mut group = c.benchmark_group("membership");
+    group.throughput(Throughput::Elements(NUM_ITEMS as u64));
+    group.bench_function("bloom", |b| {
+        b.iter(|| {
+            let mut filter = BloomFilterBuilder::with_accuracy(NUM_ITEMS as u64, 0.01).build();
+            items.iter().for_each(|item| filter.insert(item));
+            filter
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_distinct_counting,
+    bench_high_cardinality,
+    bench_frequencies,
+    bench_countmin_width,
+    bench_quantiles,
+    bench_membership
+);
+criterion_main!(benches);
@@ -0,0 +1,255 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Conversions between sketch collections and Arrow binary arrays, and aggregation kernels.
+//!
+//! Columnar engines such as DataFusion and Polars store sketches as serialized bytes in binary
+//! columns. [`to_binary_array`] and [`from_binary_array`] convert between such columns and
+//! sketches of any family implementing [`SerializableSketch`], with null slots standing for
+//! missing sketches.
+//!
+//! The kernels aggregate or query a whole column at once, which is what user-defined aggregate
+//! and scalar functions need: [`theta_union_array`] and [`hll_union_array`] merge all the
+//! sketches of a column into one serialized sketch, and [`theta_estimate_array`] and
+//! [`hll_estimate_array`] map each sketch to its estimate. Theta images are read in place when
+//! they are compact, so the union kernels never hold more than one decoded sketch at a time.
+//!
+//! Null slots are skipped by the union kernels and mapped to null estimates.
+//!
+//! The arrays are those of the `arrow-array` crate, version 57, which the `arrow` crate
+//! re-exports as `arrow::array`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use datasketches::arrow::theta_estimate_array;
+//! # use datasketches::arrow::theta_union_array;
+//! # use datasketches::arrow::to_binary_array;
+//! # use datasketches::theta::CompactThetaSketch;
+//! # use datasketches::theta::ThetaSketch;
+//! let sketches: Vec<ThetaSketch> = (0..3)
+//!     .map(|day| {
+//!         let mut sketch = ThetaSketch::builder().build();
+//!         for user in 0..100 {
+//!             sketch.update(day * 50 + user);
+//!         }
+//!         sketch
+//!     })
+//!     .collect();
+//! let column = to_binary_array(sketches.iter().map(Some));
+//!
+//! let estimates = theta_estimate_array(&column).unwrap();
+//! assert_eq!(estimates.value(0), 100.0);
+//!
+//! let union = theta_union_array(&column).unwrap();
+//! let union = CompactThetaSketch::deserialize(&union).unwrap();
+//! assert_eq!(union.estimate(), 200.0);
+//! ```
+
+use arrow_array::BinaryArray;
+use arrow_array::Float64Array;
+
+use crate::codec::family::Family;
+use crate::common::SerializableSketch;
+use crate::error::Error;
+use crate::hll::HllSketch;
+use crate::hll::HllType;
+use crate::hll::HllUnion;
+use crate::theta::CompactThetaSketch;
+use crate::theta::ThetaSketch;
+use crate::theta::ThetaUnion;
+use crate::theta::WrappedCompactThetaSketch;
+
+/// Serializes the given sketches into a binary array, with a null slot for each `None`.
+///
+/// # Examples
+///
+/// ```
+/// # use arrow_array::Array;
+/// # use datasketches::arrow::to_binary_array;
+/// # use datasketches::hll::HllSketch;
+/// # use datasketches::hll::HllType;
+/// let mut sketch = HllSketch::new(12, HllType::Hll4);
+/// sketch.update("apple");
+/// let column = to_binary_array([Some(&sketch), None]);
+/// assert_eq!(column.len(), 2);
+/// assert!(column.is_null(1));
+/// ```
+pub fn to_binary_array<'a, S, I>(sketches: I) -> BinaryArray
+where
+    S: SerializableSketch + 'a,
+    I: IntoIterator<Item = Option<&'a S>>,
+{
+    sketches
+        .into_iter()
+        .map(|sketch| sketch.map(SerializableSketch::serialize))
+        .collect()
+}
+
+/// Deserializes every slot of a binary array, with `None` for each null slot.
+///
+/// # Errors
+///
+/// Returns the error of the first slot that cannot be deserialized.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::arrow::from_binary_array;
+/// # use datasketches::arrow::to_binary_array;
+/// # use datasketches::theta::ThetaSketch;
+/// let mut sketch = ThetaSketch::builder().build();
+/// sketch.update("apple");
+/// let column = to_binary_array([None, Some(&sketch)]);
+///
+/// let decoded = from_binary_array::<ThetaSketch>(&column).unwrap();
+/// assert!(decoded[0].is_none());
+/// assert_eq!(decoded[1].as_ref().unwrap().estimate(), 1.0);
+/// ```
+pub fn from_binary_array<S: SerializableSketch>(
+    array: &BinaryArray,
+) -> Result<Vec<Option<S>>, S::Error> {
+    array
+        .iter()
+        .map(|bytes| bytes.map(S::deserialize).transpose())
+        .collect()
+}
+
+/// Merges the theta sketches of a binary array into a compact theta sketch, serialized.
+///
+/// Slots may hold compact images, which are read in place, or updatable images as written by
+/// [`ThetaSketch::serialize`]. All sketches must be built with the default seed. The union
+/// uses the default configuration of [`ThetaUnion`]; use it directly for another nominal size.
+///
+/// # Errors
+///
+/// Returns an error if a slot is not a valid theta sketch built with the default seed.
+pub fn theta_union_array(array: &BinaryArray) -> Result<Vec<u8>, Error> {
+    let mut union = ThetaUnion::builder().build();
+    for bytes in array.iter().flatten() {
+        match ThetaImage::of(bytes) {
+            ThetaImage::Update => union.update(&ThetaSketch::deserialize(bytes)?)?,
+            ThetaImage::Wrappable => union.update(&WrappedCompactThetaSketch::wrap(bytes)?)?,
+            ThetaImage::Compact => union.update(&CompactThetaSketch::deserialize(bytes)?)?,
+        }
+    }
+    Ok(union.result().serialize())
+}
+
+/// Returns the estimate of each theta sketch of a binary array.
+///
+/// Slots may hold compact or updatable images, see [`theta_union_array`].
+///
+/// # Errors
+///
+/// Returns an error if a slot is not a valid theta sketch built with the default seed.
+pub fn theta_estimate_array(array: &BinaryArray) -> Result<Float64Array, Error> {
+    array
+        .iter()
+        .map(|bytes| {
+            bytes
+                .map(|bytes| match ThetaImage::of(bytes) {
+                    ThetaImage::Update => ThetaSketch::deserialize(bytes).map(|s| s.estimate()),
+                    ThetaImage::Wrappable => {
+                        WrappedCompactThetaSketch::wrap(bytes).map(|s| s.estimate())
+                    }
+                    ThetaImage::Compact => {
+                        CompactThetaSketch::deserialize(bytes).map(|s| s.estimate())
+                    }
+                })
+                .transpose()
+        })
+        .collect()
+}
+
+/// How a serialized theta sketch is read, from its family ID and serial version.
+enum ThetaImage {
+    /// An updatable image, which must be deserialized.
+    Update,
+    /// A compact image of serial version 3 or 4, which can be read in place.
+    Wrappable,
+    /// A compact image of an older serial version, or an invalid image.
+    Compact,
+}
+
+impl ThetaImage {
+    fn of(bytes: &[u8]) -> Self {
+        match (bytes.get(1), bytes.get(2)) {
+            (_, Some(&family_id)) if family_id == Family::QUICKSELECT.id => ThetaImage::Update,
+            (Some(3 | 4), _) => ThetaImage::Wrappable,
+            _ => ThetaImage::Compact,
+        }
+    }
+}
+
+/// Merges the HLL sketches of a binary array into an HLL sketch of the given type, serialized.
+///
+/// The union is configured with the largest `lg_config_k` among the sketches, as in
+/// [`estimate_union`](crate::hll::estimate_union).
+///
+/// # Errors
+///
+/// Returns an error if a slot is not a valid HLL sketch.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::arrow::hll_union_array;
+/// # use datasketches::arrow::to_binary_array;
+/// # use datasketches::hll::HllSketch;
+/// # use datasketches::hll::HllType;
+/// let mut apple = HllSketch::new(12, HllType::Hll4);
+/// apple.update("apple");
+/// let mut banana = HllSketch::new(10, HllType::Hll8);
+/// banana.update("banana");
+/// let column = to_binary_array([Some(&apple), None, Some(&banana)]);
+///
+/// let union = hll_union_array(&column, HllType::Hll4).unwrap();
+/// let union = HllSketch::deserialize(&union).unwrap();
+/// assert_eq!(union.lg_config_k(), 12);
+/// assert!((union.estimate() - 2.0).abs() < 0.01);
+/// ```
+pub fn hll_union_array(array: &BinaryArray, hll_type: HllType) -> Result<Vec<u8>, Error> {
+    // lg_config_k is the fourth byte of the preamble, validated when the image is decoded
+    let lg_max_k = array
+        .iter()
+        .flatten()
+        .filter_map(|bytes| bytes.get(3).copied())
+        .filter(|lg_k| (4..=21).contains(lg_k))
+        .max()
+        .unwrap_or(4);
+    let mut union = HllUnion::new(lg_max_k);
+    for bytes in array.iter().flatten() {
+        union.update_serialized(bytes)?;
+    }
+    Ok(union.to_sketch(hll_type).serialize())
+}
+
+/// Returns the estimate of each HLL sketch of a binary array.
+///
+/// # Errors
+///
+/// Returns an error if a slot is not a valid HLL sketch.
+pub fn hll_estimate_array(array: &BinaryArray) -> Result<Float64Array, Error> {
+    array
+        .iter()
+        .map(|bytes| {
+            bytes
+                .map(|bytes| HllSketch::deserialize(bytes).map(|sketch| sketch.estimate()))
+                .transpose()
+        })
+        .collect()
+}
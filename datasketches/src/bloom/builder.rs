@@ -16,8 +16,9 @@
 // under the License.
 
 use super::BloomFilter;
+use crate::Config;
 use crate::codec::family::Family;
-use crate::hash::DEFAULT_UPDATE_SEED;
+use crate::error::Error;
 
 /// Builder for creating [`BloomFilter`] instances.
 ///
@@ -55,6 +56,8 @@ impl BloomFilterBuilder {
     /// * `max_items`: Maximum expected number of distinct items
     /// * `fpp`: Target false positive probability (e.g., 0.01 for 1%)
     ///
+    /// The fallible version of this method is [`BloomFilterBuilder::try_with_accuracy`].
+    ///
     /// # Panics
     ///
     /// Panics if `max_items` is 0 or `fpp` is not in (0.0, 1.0].
@@ -81,7 +84,7 @@ impl BloomFilterBuilder {
         BloomFilterBuilder {
             num_bits,
             num_hashes,
-            seed: DEFAULT_UPDATE_SEED,
+            seed: Config::global().seed(),
         }
     }
 
@@ -98,6 +101,8 @@ impl BloomFilterBuilder {
     /// * `num_bits`: Total number of bits in the filter
     /// * `num_hashes`: Number of hash functions to use
     ///
+    /// The fallible version of this method is [`BloomFilterBuilder::try_with_size`].
+    ///
     /// # Panics
     ///
     /// Panics if any of:
@@ -120,7 +125,7 @@ impl BloomFilterBuilder {
         );
         assert!(
             (Self::MIN_NUM_HASHES..=Self::MAX_NUM_HASHES).contains(&num_hashes),
-            "num_bits must be between {} and {}, got {}",
+            "num_hashes must be between {} and {}, got {}",
             Self::MIN_NUM_HASHES,
             Self::MAX_NUM_HASHES,
             num_hashes
@@ -129,8 +134,64 @@ impl BloomFilterBuilder {
         BloomFilterBuilder {
             num_bits,
             num_hashes,
-            seed: DEFAULT_UPDATE_SEED,
+            seed: Config::global().seed(),
+        }
+    }
+
+    /// Creates a builder with optimal parameters for a target accuracy.
+    ///
+    /// The panicking version of this method is [`BloomFilterBuilder::with_accuracy`].
+    ///
+    /// # Errors
+    ///
+    /// If `max_items` is 0 or `fpp` is not in (0.0, 1.0].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::bloom::BloomFilterBuilder;
+    /// assert!(BloomFilterBuilder::try_with_accuracy(10_000, 0.0).is_err());
+    /// let filter = BloomFilterBuilder::try_with_accuracy(10_000, 0.01)
+    ///     .unwrap()
+    ///     .build();
+    /// ```
+    pub fn try_with_accuracy(max_items: u64, fpp: f64) -> Result<Self, Error> {
+        if max_items == 0 {
+            return Err(Error::invalid_argument("max_items must be greater than 0"));
+        }
+        if !(fpp > 0.0 && fpp <= 1.0) {
+            return Err(Error::invalid_argument(format!(
+                "fpp must be between 0.0 and 1.0 (inclusive of 1.0), got {fpp}"
+            )));
+        }
+        Ok(Self::with_accuracy(max_items, fpp))
+    }
+
+    /// Creates a builder with manual size specification.
+    ///
+    /// The panicking version of this method is [`BloomFilterBuilder::with_size`].
+    ///
+    /// # Errors
+    ///
+    /// If any of:
+    /// * `num_bits` < [`Self::MIN_NUM_BITS`] or `num_bits` > [`Self::MAX_NUM_BITS`]
+    /// * `num_hashes` < [`Self::MIN_NUM_HASHES`] or `num_hashes` > [`Self::MAX_NUM_HASHES`]
+    pub fn try_with_size(num_bits: u64, num_hashes: u16) -> Result<Self, Error> {
+        if !(Self::MIN_NUM_BITS..=Self::MAX_NUM_BITS).contains(&num_bits) {
+            return Err(Error::invalid_argument(format!(
+                "num_bits must be between {} and {}, got {num_bits}",
+                Self::MIN_NUM_BITS,
+                Self::MAX_NUM_BITS,
+            )));
+        }
+        if !(Self::MIN_NUM_HASHES..=Self::MAX_NUM_HASHES).contains(&num_hashes) {
+            return Err(Error::invalid_argument(format!(
+                "num_hashes must be between {} and {}, got {num_hashes}",
+                Self::MIN_NUM_HASHES,
+                Self::MAX_NUM_HASHES,
+            )));
         }
+        Ok(Self::with_size(num_bits, num_hashes))
     }
 
     /// Sets a custom hash seed (default: 9001).
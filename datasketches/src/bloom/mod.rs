@@ -125,3 +125,4 @@ mod sketch;
 
 pub use self::builder::BloomFilterBuilder;
 pub use self::sketch::BloomFilter;
+pub(crate) use self::sketch::inspect;
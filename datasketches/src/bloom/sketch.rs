@@ -15,21 +15,31 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::fmt;
 use std::hash::Hash;
 use std::hash::Hasher;
 
+use crate::bloom::BloomFilterBuilder;
 use crate::codec::SketchBytes;
 use crate::codec::SketchSlice;
 use crate::codec::assert::ensure_preamble_longs_in_range;
 use crate::codec::assert::ensure_serial_version_is;
 use crate::codec::assert::insufficient_data;
 use crate::codec::family::Family;
+use crate::codec::serialize_into_slice;
+use crate::common::MergeableSketch;
+use crate::common::SerializableSketch;
+use crate::common::Sketch;
+use crate::common::Summary;
 use crate::error::Error;
 use crate::hash::XxHash64;
+use crate::inspect::SketchInfo;
 
 // Serialization constants
 const SERIAL_VERSION: u8 = 1;
 const EMPTY_FLAG_MASK: u8 = 1 << 2;
+/// An empty image carries no bit array to bound the one it asks for, so its size is capped.
+const MAX_EMPTY_IMAGE_WORDS: usize = 1 << 24;
 
 /// A Bloom filter for probabilistic set membership testing.
 ///
@@ -264,6 +274,11 @@ impl BloomFilter {
         self.num_bits_set
     }
 
+    /// Returns the number of bytes of memory the filter occupies, including its bit array.
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>() + size_of_val(&*self.bit_array)
+    }
+
     /// Returns the total number of bits in the filter (capacity).
     pub fn capacity(&self) -> usize {
         self.bit_array.len() * 64
@@ -333,20 +348,48 @@ impl BloomFilter {
     /// assert!(restored.contains(&"test"));
     /// ```
     pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = SketchBytes::with_capacity(self.serialized_size_bytes());
+        self.write_to(&mut bytes);
+        bytes.into_bytes()
+    }
+
+    /// Returns the number of bytes [`BloomFilter::serialize`] produces.
+    pub fn serialized_size_bytes(&self) -> usize {
         let is_empty = self.is_empty();
         let preamble_longs = if is_empty {
             Family::BLOOMFILTER.min_pre_longs
         } else {
             Family::BLOOMFILTER.max_pre_longs
         };
-
-        let capacity = 8 * preamble_longs as usize
+        8 * preamble_longs as usize
             + if is_empty {
                 0
             } else {
                 self.bit_array.len() * 8
-            };
-        let mut bytes = SketchBytes::with_capacity(capacity);
+            }
+    }
+
+    /// Serializes this filter into the front of `buf` without allocating, returning the number of
+    /// bytes written.
+    ///
+    /// The bytes are the same as those returned by [`BloomFilter::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `buf` is shorter than [`BloomFilter::serialized_size_bytes`].
+    pub fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        serialize_into_slice(buf, self.serialized_size_bytes(), |bytes| {
+            self.write_to(bytes)
+        })
+    }
+
+    fn write_to(&self, bytes: &mut SketchBytes<'_>) {
+        let is_empty = self.is_empty();
+        let preamble_longs = if is_empty {
+            Family::BLOOMFILTER.min_pre_longs
+        } else {
+            Family::BLOOMFILTER.max_pre_longs
+        };
 
         // Preamble
         bytes.write_u8(preamble_longs); // Byte 0
@@ -371,8 +414,6 @@ impl BloomFilter {
                 bytes.write_u64_le(word);
             }
         }
-
-        bytes.into_bytes()
     }
 
     /// Deserializes a filter from bytes.
@@ -383,6 +424,7 @@ impl BloomFilter {
     /// * The data is truncated or corrupted
     /// * The family ID doesn't match (not a Bloom filter)
     /// * The serial version is unsupported
+    /// * The filter is empty and has more than 2^30 bits
     ///
     /// # Examples
     ///
@@ -424,7 +466,7 @@ impl BloomFilter {
             .read_u16_le()
             .map_err(insufficient_data("num_hashes"))?;
         if num_hashes == 0 || num_hashes > i16::MAX as u16 {
-            return Err(Error::deserial(format!(
+            return Err(Error::bad_preamble(format!(
                 "invalid num_hashes: expected [1, {}], got {}",
                 i16::MAX,
                 num_hashes
@@ -442,14 +484,26 @@ impl BloomFilter {
             .map_err(insufficient_data("num_longs"))?;
         let _unused = cursor.read_u32_le().map_err(insufficient_data("unused"))?;
 
-        if num_longs <= 0 {
-            return Err(Error::deserial(format!(
-                "invalid num_longs: expected at least 1, got {}",
-                num_longs
+        let max_num_longs = BloomFilterBuilder::MAX_NUM_BITS / 64;
+        if num_longs <= 0 || num_longs as u64 > max_num_longs {
+            return Err(Error::bad_preamble(format!(
+                "invalid num_longs: expected [1, {max_num_longs}], got {num_longs}"
             )));
         }
 
         let num_words = num_longs as usize;
+        if is_empty && num_words > MAX_EMPTY_IMAGE_WORDS {
+            return Err(Error::deserial(format!(
+                "empty filter must have at most {MAX_EMPTY_IMAGE_WORDS} words, got {num_words}"
+            )));
+        }
+        // a non-empty image carries the bit array, so its size bounds the allocation
+        if !is_empty && cursor.remaining() / 8 < num_words {
+            return Err(Error::insufficient_data(format!(
+                "expected {num_words} words of bit array, got {} bytes",
+                cursor.remaining()
+            )));
+        }
         let mut bit_array = vec![0u64; num_words].into_boxed_slice();
         let num_bits_set;
 
@@ -537,22 +591,25 @@ impl BloomFilter {
     /// ```
     ///
     /// The right shift by 1 improves bit distribution. The index `i` is 1-based.
-    fn compute_bit_index(&self, h0: u64, h1: u64, i: u16) -> usize {
-        let hash = h0.wrapping_add(u64::from(i).wrapping_mul(h1)) as usize;
-        (hash >> 1) % self.capacity()
+    ///
+    /// The index is computed in 64 bits so that filters are portable between 32-bit targets such
+    /// as `wasm32` and 64-bit hosts.
+    fn compute_bit_index(&self, h0: u64, h1: u64, i: u16) -> u64 {
+        let hash = h0.wrapping_add(u64::from(i).wrapping_mul(h1));
+        (hash >> 1) % (self.bit_array.len() as u64 * 64)
     }
 
     /// Gets the value of a single bit.
-    fn get_bit(&self, bit_index: usize) -> bool {
-        let word_index = bit_index >> 6; // Equivalent to bit_index / 64
+    fn get_bit(&self, bit_index: u64) -> bool {
+        let word_index = (bit_index >> 6) as usize; // Equivalent to bit_index / 64
         let bit_offset = bit_index & 63; // Equivalent to bit_index % 64
         let mask = 1u64 << bit_offset;
         (self.bit_array[word_index] & mask) != 0
     }
 
     /// Sets a single bit and updates the count if it wasn't already set.
-    fn set_bit(&mut self, bit_index: usize) {
-        let word_index = bit_index >> 6; // Equivalent to bit_index / 64
+    fn set_bit(&mut self, bit_index: u64) {
+        let word_index = (bit_index >> 6) as usize; // Equivalent to bit_index / 64
         let bit_offset = bit_index & 63; // Equivalent to bit_index % 64
         let mask = 1u64 << bit_offset;
 
@@ -563,6 +620,80 @@ impl BloomFilter {
     }
 }
 
+impl fmt::Display for BloomFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Summary::new(f, "BLOOM FILTER")
+            .field("num hashes", self.num_hashes())
+            .field("capacity bits", self.capacity())
+            .field("bits used", self.bits_used())
+            .field("load factor", self.load_factor())
+            .field("estimated fpp", self.estimated_fpp())
+            .field("empty", self.is_empty())
+            .field("seed", self.seed())
+            .finish()
+    }
+}
+
+impl<I: Hash> Sketch<I> for BloomFilter {
+    fn update(&mut self, item: I) {
+        self.insert(item);
+    }
+
+    fn is_empty(&self) -> bool {
+        BloomFilter::is_empty(self)
+    }
+}
+
+/// Merging is a union; unlike [`BloomFilter::union`], incompatible filters are reported as an
+/// error rather than a panic.
+impl MergeableSketch for BloomFilter {
+    type Error = Error;
+
+    fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        if !self.is_compatible(other) {
+            return Err(Error::invalid_argument(
+                "cannot merge Bloom filters with different capacities, hash counts or seeds",
+            ));
+        }
+        self.union(other);
+        Ok(())
+    }
+}
+
+impl SerializableSketch for BloomFilter {
+    type Error = Error;
+
+    fn serialize(&self) -> Vec<u8> {
+        BloomFilter::serialize(self)
+    }
+
+    fn serialized_size_bytes(&self) -> usize {
+        BloomFilter::serialized_size_bytes(self)
+    }
+
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        BloomFilter::serialize_into(self, buf)
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        BloomFilter::deserialize(bytes)
+    }
+}
+
+/// Reads the preamble of an image for [`inspect`](crate::inspect).
+pub(crate) fn inspect(
+    _preamble_longs: u8,
+    serial_version: u8,
+    mut cursor: SketchSlice<'_>,
+) -> Result<SketchInfo, Error> {
+    ensure_serial_version_is(SERIAL_VERSION, serial_version)?;
+    let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+
+    let mut info = SketchInfo::new(&Family::BLOOMFILTER, serial_version);
+    info.is_empty = (flags & EMPTY_FLAG_MASK) != 0;
+    Ok(info)
+}
+
 #[cfg(test)]
 mod tests {
     use super::BloomFilter;
@@ -24,11 +24,22 @@ pub(crate) fn insufficient_data(tag: &'static str) -> impl FnOnce(std::io::Error
     move |_| Error::insufficient_data(tag)
 }
 
+/// Like [`insufficient_data`], but keeps the cause of errors other than running out of input.
+pub(crate) fn read_error<T: std::fmt::Display>(tag: T) -> impl FnOnce(std::io::Error) -> Error {
+    move |err| {
+        if err.kind() == std::io::ErrorKind::UnexpectedEof {
+            Error::insufficient_data(tag)
+        } else {
+            Error::deserial(format!("failed to read {tag}: {err}"))
+        }
+    }
+}
+
 pub(crate) fn ensure_serial_version_is(expected: u8, actual: u8) -> Result<(), Error> {
     if expected == actual {
         Ok(())
     } else {
-        Err(Error::deserial(format!(
+        Err(Error::unsupported_version(format!(
             "unsupported serial version: expected {expected}, got {actual}"
         )))
     }
@@ -51,7 +62,7 @@ pub(crate) fn ensure_preamble_longs_in_range(
     if expected.contains(&actual) {
         Ok(())
     } else {
-        Err(Error::deserial(format!(
+        Err(Error::bad_preamble(format!(
             "invalid preamble longs: expected {}, got {actual}",
             match (start, end) {
                 (Bound::Included(a), Bound::Included(b)) => format!("[{a}, {b}]"),
@@ -19,6 +19,9 @@ use std::io;
 use std::io::Cursor;
 use std::io::Read;
 
+use crate::codec::assert::read_error;
+use crate::error::Error;
+
 /// A wrapper around a byte slice that provides methods for reading various types of data from it.
 pub struct SketchSlice<'a> {
     slice: Cursor<&'a [u8]>,
@@ -38,6 +41,12 @@ impl SketchSlice<'_> {
         self.slice.set_position(pos + n);
     }
 
+    /// Returns the number of bytes left to read.
+    pub fn remaining(&self) -> usize {
+        let len = self.slice.get_ref().len() as u64;
+        len.saturating_sub(self.slice.position()) as usize
+    }
+
     /// Reads exactly `buf.len()` bytes from the slice into `buf`.
     pub fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
         self.slice.read_exact(buf)
@@ -169,3 +178,46 @@ impl SketchSlice<'_> {
         Ok(f64::from_be_bytes(buf))
     }
 }
+
+impl Read for SketchSlice<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.slice.read(buf)
+    }
+}
+
+/// Reads one serialized sketch image from `reader`, and no further.
+///
+/// Every image starts with at least 8 bytes of preamble. `preamble_len` computes the length of
+/// the whole preamble from its first 8 bytes, and `image_len` the length of the whole image from
+/// the preamble. Both may return a short length for malformed input, which is then left to the
+/// deserializer to reject.
+pub(crate) fn read_image<R: Read>(
+    reader: &mut R,
+    preamble_len: impl FnOnce(&[u8]) -> usize,
+    image_len: impl FnOnce(&[u8]) -> usize,
+) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![0u8; 8];
+    reader
+        .read_exact(&mut bytes)
+        .map_err(read_error("preamble"))?;
+
+    let preamble_len = preamble_len(&bytes).max(8);
+    bytes.resize(preamble_len, 0);
+    reader
+        .read_exact(&mut bytes[8..])
+        .map_err(read_error("preamble"))?;
+
+    // grow with the data actually read, so that a corrupted length cannot exhaust memory
+    let image_len = image_len(&bytes).max(preamble_len);
+    reader
+        .take((image_len - preamble_len) as u64)
+        .read_to_end(&mut bytes)
+        .map_err(read_error("image"))?;
+    if bytes.len() < image_len {
+        return Err(Error::insufficient_data(format!(
+            "image: expected {image_len} bytes, got {}",
+            bytes.len()
+        )));
+    }
+    Ok(bytes)
+}
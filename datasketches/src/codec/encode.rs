@@ -15,37 +15,192 @@
 // specific language governing permissions and limitations
 // under the License.
 
-/// A simple wrapper around a `Vec<u8>` that provides methods for writing various types of data.
-pub struct SketchBytes {
-    bytes: Vec<u8>,
+use std::io;
+use std::io::Write;
+
+use crate::error::Error;
+
+/// Number of bytes buffered by a streaming `SketchBytes` before handing them to the writer.
+const WRITER_CHUNK_SIZE: usize = 8 * 1024;
+
+/// A simple wrapper around a byte buffer that provides methods for writing various types of data.
+///
+/// The buffer is either a growable `Vec<u8>` created by [`SketchBytes::with_capacity`], a
+/// caller-provided slice used by the `serialize_into` methods of the sketches, or a chunk that is
+/// handed to an [`io::Write`] whenever it fills up, used by the `serialize_into_writer` methods.
+/// When streaming, [`as_slice`](Self::as_slice), [`into_bytes`](Self::into_bytes) and
+/// [`clear`](Self::clear) only see the bytes not yet handed to the writer.
+pub struct SketchBytes<'a> {
+    buffer: Buffer<'a>,
+}
+
+enum Buffer<'a> {
+    Owned(Vec<u8>),
+    Borrowed {
+        slice: &'a mut [u8],
+        len: usize,
+        overflowed: bool,
+    },
+    Streaming {
+        writer: &'a mut dyn Write,
+        chunk: Vec<u8>,
+        flushed: usize,
+        error: Option<io::Error>,
+    },
 }
 
-impl SketchBytes {
+impl SketchBytes<'static> {
     /// Constructs an empty `SketchBytes` with at least the specified capacity.
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            bytes: Vec::with_capacity(capacity),
+            buffer: Buffer::Owned(Vec::with_capacity(capacity)),
+        }
+    }
+}
+
+impl<'a> SketchBytes<'a> {
+    /// Constructs an empty `SketchBytes` writing into `slice` from its start.
+    ///
+    /// Writes past the end of `slice` are dropped and recorded; see [`Self::overflowed`].
+    pub(crate) fn from_slice(slice: &'a mut [u8]) -> Self {
+        Self {
+            buffer: Buffer::Borrowed {
+                slice,
+                len: 0,
+                overflowed: false,
+            },
+        }
+    }
+
+    /// Constructs an empty `SketchBytes` streaming into `writer` in chunks.
+    ///
+    /// Call [`Self::finish`] to write the last chunk and to learn whether writing failed.
+    pub(crate) fn from_writer(writer: &'a mut dyn Write) -> Self {
+        Self {
+            buffer: Buffer::Streaming {
+                writer,
+                chunk: Vec::with_capacity(WRITER_CHUNK_SIZE),
+                flushed: 0,
+                error: None,
+            },
+        }
+    }
+
+    /// Returns true if a write did not fit into a caller-provided slice.
+    pub(crate) fn overflowed(&self) -> bool {
+        match &self.buffer {
+            Buffer::Borrowed { overflowed, .. } => *overflowed,
+            _ => false,
+        }
+    }
+
+    /// Hands the buffered bytes to the writer, returning the first error of any write.
+    pub(crate) fn finish(self) -> io::Result<()> {
+        match self.buffer {
+            Buffer::Streaming {
+                writer,
+                chunk,
+                error,
+                ..
+            } => match error {
+                Some(err) => Err(err),
+                None => writer.write_all(&chunk),
+            },
+            _ => Ok(()),
         }
     }
 
-    /// Consumes the `SketchBytes` and returns the underlying `Vec<u8>`.
+    /// Consumes the `SketchBytes` and returns the bytes written as a `Vec<u8>`.
+    ///
+    /// The bytes are copied if they were written into a caller-provided slice.
     pub fn into_bytes(self) -> Vec<u8> {
-        self.bytes
+        match self.buffer {
+            Buffer::Owned(bytes) => bytes,
+            Buffer::Borrowed { slice, len, .. } => slice[..len].to_vec(),
+            Buffer::Streaming { chunk, .. } => chunk,
+        }
+    }
+
+    /// Returns the bytes written so far.
+    pub fn as_slice(&self) -> &[u8] {
+        match &self.buffer {
+            Buffer::Owned(bytes) => bytes,
+            Buffer::Borrowed { slice, len, .. } => &slice[..*len],
+            Buffer::Streaming { chunk, .. } => chunk,
+        }
+    }
+
+    /// Returns the number of bytes written so far.
+    pub fn len(&self) -> usize {
+        match &self.buffer {
+            Buffer::Streaming { chunk, flushed, .. } => flushed + chunk.len(),
+            _ => self.as_slice().len(),
+        }
+    }
+
+    /// Returns true if no bytes have been written.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Discards the bytes written so far, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        match &mut self.buffer {
+            Buffer::Owned(bytes) => bytes.clear(),
+            Buffer::Borrowed {
+                len, overflowed, ..
+            } => {
+                *len = 0;
+                *overflowed = false;
+            }
+            Buffer::Streaming { chunk, .. } => chunk.clear(),
+        }
     }
 
     /// Writes the given byte slice to the `SketchBytes`.
     pub fn write(&mut self, buf: &[u8]) {
-        self.bytes.extend_from_slice(buf);
+        match &mut self.buffer {
+            Buffer::Owned(bytes) => bytes.extend_from_slice(buf),
+            Buffer::Borrowed {
+                slice,
+                len,
+                overflowed,
+            } => match slice.get_mut(*len..*len + buf.len()) {
+                Some(dst) if !*overflowed => {
+                    dst.copy_from_slice(buf);
+                    *len += buf.len();
+                }
+                _ => *overflowed = true,
+            },
+            Buffer::Streaming {
+                writer,
+                chunk,
+                flushed,
+                error,
+            } => {
+                chunk.extend_from_slice(buf);
+                if chunk.len() >= WRITER_CHUNK_SIZE {
+                    // after a failure, the remaining bytes are dropped
+                    if error.is_none() {
+                        if let Err(err) = writer.write_all(chunk) {
+                            *error = Some(err);
+                        }
+                    }
+                    *flushed += chunk.len();
+                    chunk.clear();
+                }
+            }
+        }
     }
 
     /// Writes a single byte to the `SketchBytes`.
     pub fn write_u8(&mut self, n: u8) {
-        self.bytes.push(n);
+        self.write(&[n]);
     }
 
     /// Writes a single byte to the `SketchBytes`.
     pub fn write_i8(&mut self, n: i8) {
-        self.bytes.push(n as u8);
+        self.write(&[n as u8]);
     }
 
     /// Writes a 16-bit unsigned integer to the `SketchBytes` in little-endian byte order.
@@ -128,3 +283,37 @@ impl SketchBytes {
         self.write(&n.to_be_bytes());
     }
 }
+
+/// Serializes into the front of `buf` with `write`, given the exact serialized size.
+///
+/// Returns the number of bytes written.
+pub(crate) fn serialize_into_slice(
+    buf: &mut [u8],
+    size: usize,
+    write: impl FnOnce(&mut SketchBytes<'_>),
+) -> Result<usize, Error> {
+    if buf.len() < size {
+        return Err(Error::invalid_argument(format!(
+            "buffer too small: serialized size is {size} bytes, got {}",
+            buf.len()
+        )));
+    }
+    let mut bytes = SketchBytes::from_slice(&mut buf[..size]);
+    write(&mut bytes);
+    if bytes.overflowed() || bytes.len() != size {
+        return Err(Error::invalid_argument(format!(
+            "serialized size mismatch: expected exactly {size} bytes"
+        )));
+    }
+    Ok(size)
+}
+
+/// Serializes into `writer` with `write`, handing the bytes over in chunks of a few kilobytes.
+pub(crate) fn serialize_into_writer<W: Write>(
+    writer: &mut W,
+    write: impl FnOnce(&mut SketchBytes<'_>),
+) -> io::Result<()> {
+    let mut bytes = SketchBytes::from_writer(writer);
+    write(&mut bytes);
+    bytes.finish()
+}
@@ -33,6 +33,14 @@ pub struct Family {
 }
 
 impl Family {
+    /// Updatable Theta Sketch using the QuickSelect algorithm.
+    pub const QUICKSELECT: Family = Family {
+        id: 2,
+        name: "QUICKSELECT",
+        min_pre_longs: 3,
+        max_pre_longs: 3,
+    };
+
     /// Theta Sketch for cardinality estimation.
     pub const THETA: Family = Family {
         id: 3,
@@ -57,6 +65,14 @@ impl Family {
         max_pre_longs: 4,
     };
 
+    /// KLL quantiles sketch.
+    pub const KLL: Family = Family {
+        id: 15,
+        name: "KLL",
+        min_pre_longs: 1,
+        max_pre_longs: 2,
+    };
+
     /// Compressed Probabilistic Counting (CPC) Sketch.
     pub const CPC: Family = Family {
         id: 16,
@@ -0,0 +1,244 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Serialization of the items held by item sketches.
+
+use std::io::Read;
+
+use crate::codec::SketchBytes;
+use crate::codec::SketchSlice;
+use crate::codec::assert::read_error;
+use crate::error::Error;
+
+/// Trait for serializing and deserializing the items of item sketches, one at a time.
+///
+/// This is the equivalent of `ArrayOfItemsSerDe` in datasketches-java, implemented by the item
+/// type itself. Items are written back to back without separators, so every encoding must be
+/// self-delimiting: fixed-size types write their little-endian bytes, and variable-size types
+/// write a 32-bit little-endian byte length before their bytes.
+///
+/// Implementations are provided for the primitive integer and floating point types,
+/// [`String`], `Vec<u8>`, and `uuid::Uuid` with the `uuid` feature.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::codec::ItemSerde;
+/// # use datasketches::codec::SketchBytes;
+/// # use datasketches::codec::SketchSlice;
+/// let items = [String::from("apple"), String::from("banana")];
+/// let mut bytes = SketchBytes::with_capacity(0);
+/// for item in &items {
+///     item.serialize_value(&mut bytes);
+/// }
+/// let bytes = bytes.into_bytes();
+/// assert_eq!(bytes.len(), items.iter().map(String::serialize_size).sum());
+///
+/// let mut cursor = SketchSlice::new(&bytes);
+/// assert_eq!(String::deserialize_value(&mut cursor).unwrap(), "apple");
+/// assert_eq!(String::deserialize_value(&mut cursor).unwrap(), "banana");
+/// ```
+pub trait ItemSerde: Sized {
+    /// Returns the size in bytes required to serialize the given item.
+    fn serialize_size(item: &Self) -> usize;
+    /// Serializes the item into the given byte buffer.
+    fn serialize_value(&self, bytes: &mut SketchBytes<'_>);
+    /// Deserializes an item from the given byte cursor.
+    fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error>;
+    /// Deserializes an item from the given reader, consuming exactly the bytes of the item.
+    fn read_value<R: Read>(reader: &mut R) -> Result<Self, Error>;
+}
+
+/// Writes the 32-bit length prefix of a variable-size item.
+///
+/// # Panics
+///
+/// Panics if `len` does not fit in `u32`.
+fn write_len(bytes: &mut SketchBytes<'_>, len: usize, what: &str) {
+    let len = u32::try_from(len).unwrap_or_else(|_| panic!("{what} length must fit in u32"));
+    bytes.write_u32_le(len);
+}
+
+/// Reads a length-prefixed payload from a cursor.
+fn read_framed(cursor: &mut SketchSlice<'_>, what: &str) -> Result<Vec<u8>, Error> {
+    let len = cursor
+        .read_u32_le()
+        .map_err(|_| Error::insufficient_data(format!("failed to read {what} length")))?;
+
+    // check the declared length before allocating so that a corrupted prefix cannot
+    // trigger a huge allocation
+    let len = len as usize;
+    if len > cursor.remaining() {
+        return Err(Error::insufficient_data(format!(
+            "{what} declares {len} bytes, but only {} bytes remain",
+            cursor.remaining()
+        )));
+    }
+
+    let mut payload = vec![0; len];
+    cursor
+        .read_exact(&mut payload)
+        .map_err(|_| Error::insufficient_data(format!("failed to read {what} bytes")))?;
+    Ok(payload)
+}
+
+/// Reads a length-prefixed payload from a reader.
+fn read_framed_from<R: Read>(reader: &mut R, what: &str) -> Result<Vec<u8>, Error> {
+    let mut len = [0u8; 4];
+    reader
+        .read_exact(&mut len)
+        .map_err(|err| read_error(format!("{what} length"))(err))?;
+    let len = u32::from_le_bytes(len) as usize;
+
+    // the remaining input size is unknown, so grow the buffer as bytes arrive rather than
+    // trusting the declared length for the allocation
+    let mut payload = Vec::new();
+    reader
+        .take(len as u64)
+        .read_to_end(&mut payload)
+        .map_err(|err| read_error(format!("{what} bytes"))(err))?;
+    if payload.len() < len {
+        return Err(Error::insufficient_data(format!(
+            "{what} declares {len} bytes, but only {} bytes remain",
+            payload.len()
+        )));
+    }
+    Ok(payload)
+}
+
+/// Strings are encoded as in `ArrayOfStringsSerDe` of datasketches-java: a 32-bit little-endian
+/// byte length followed by the UTF-8 bytes of the string, with no terminator or padding.
+impl ItemSerde for String {
+    fn serialize_size(item: &Self) -> usize {
+        size_of::<u32>() + item.len()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the UTF-8 encoding of the string is longer than `u32::MAX` bytes.
+    fn serialize_value(&self, bytes: &mut SketchBytes<'_>) {
+        write_len(bytes, self.len(), "string item");
+        bytes.write(self.as_bytes());
+    }
+
+    fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error> {
+        decode_utf8(read_framed(cursor, "string item")?)
+    }
+
+    fn read_value<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        decode_utf8(read_framed_from(reader, "string item")?)
+    }
+}
+
+fn decode_utf8(bytes: Vec<u8>) -> Result<String, Error> {
+    String::from_utf8(bytes).map_err(|err| {
+        Error::deserial("invalid UTF-8 string payload")
+            .with_context("valid_up_to", err.utf8_error().valid_up_to())
+    })
+}
+
+/// Byte strings are encoded like strings: a 32-bit little-endian length followed by the bytes.
+impl ItemSerde for Vec<u8> {
+    fn serialize_size(item: &Self) -> usize {
+        size_of::<u32>() + item.len()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the item is longer than `u32::MAX` bytes.
+    fn serialize_value(&self, bytes: &mut SketchBytes<'_>) {
+        write_len(bytes, self.len(), "byte item");
+        bytes.write(self);
+    }
+
+    fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error> {
+        read_framed(cursor, "byte item")
+    }
+
+    fn read_value<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        read_framed_from(reader, "byte item")
+    }
+}
+
+/// UUIDs are encoded as their 16 bytes in big-endian (RFC 9562) order.
+#[cfg(feature = "uuid")]
+#[cfg_attr(docsrs, doc(cfg(feature = "uuid")))]
+impl ItemSerde for uuid::Uuid {
+    fn serialize_size(_item: &Self) -> usize {
+        16
+    }
+
+    fn serialize_value(&self, bytes: &mut SketchBytes<'_>) {
+        bytes.write(self.as_bytes());
+    }
+
+    fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error> {
+        let mut buf = [0u8; 16];
+        cursor
+            .read_exact(&mut buf)
+            .map_err(|_| Error::insufficient_data("failed to read uuid item bytes".to_string()))?;
+        Ok(uuid::Uuid::from_bytes(buf))
+    }
+
+    fn read_value<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let mut buf = [0u8; 16];
+        reader
+            .read_exact(&mut buf)
+            .map_err(read_error("uuid item bytes"))?;
+        Ok(uuid::Uuid::from_bytes(buf))
+    }
+}
+
+macro_rules! impl_primitive {
+    ($name:ty, $read:ident, $write:ident) => {
+        impl ItemSerde for $name {
+            fn serialize_size(_item: &Self) -> usize {
+                size_of::<$name>()
+            }
+
+            fn serialize_value(&self, bytes: &mut SketchBytes<'_>) {
+                bytes.$write(*self);
+            }
+
+            fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error> {
+                cursor.$read().map_err(|_| {
+                    Error::insufficient_data(
+                        concat!("failed to read ", stringify!($name), " item bytes").to_string(),
+                    )
+                })
+            }
+
+            fn read_value<R: Read>(reader: &mut R) -> Result<Self, Error> {
+                let mut buf = [0u8; size_of::<$name>()];
+                reader
+                    .read_exact(&mut buf)
+                    .map_err(read_error(concat!(stringify!($name), " item bytes")))?;
+                Ok(<$name>::from_le_bytes(buf))
+            }
+        }
+    };
+}
+
+impl_primitive!(i8, read_i8, write_i8);
+impl_primitive!(u8, read_u8, write_u8);
+impl_primitive!(i16, read_i16_le, write_i16_le);
+impl_primitive!(u16, read_u16_le, write_u16_le);
+impl_primitive!(i32, read_i32_le, write_i32_le);
+impl_primitive!(u32, read_u32_le, write_u32_le);
+impl_primitive!(i64, read_i64_le, write_i64_le);
+impl_primitive!(u64, read_u64_le, write_u64_le);
+impl_primitive!(f32, read_f32_le, write_f32_le);
+impl_primitive!(f64, read_f64_le, write_f64_le);
@@ -20,8 +20,13 @@
 // public common codec utilities for datasketches crate
 mod decode;
 mod encode;
+mod item;
 pub use self::decode::SketchSlice;
+pub(crate) use self::decode::read_image;
 pub use self::encode::SketchBytes;
+pub(crate) use self::encode::serialize_into_slice;
+pub(crate) use self::encode::serialize_into_writer;
+pub use self::item::ItemSerde;
 
 // private to datasketches crate
 pub(crate) mod assert;
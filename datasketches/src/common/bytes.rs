@@ -0,0 +1,45 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::hash::Hash;
+use std::hash::Hasher;
+
+/// A byte slice that hashes as exactly its bytes.
+///
+/// The [`Hash`] implementations of `[u8]`, `Vec<u8>` and `usize` feed a `usize` length or value
+/// to the hasher, whose width depends on the target: sketches updated with such items on
+/// `wasm32` cannot be merged with sketches updated with the same items on a 64-bit host. Wrapping
+/// the bytes in `RawBytes` hashes them without a length prefix, so the result is identical on
+/// every platform and matches updating the datasketches-java sketches with a `byte[]`.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::common::RawBytes;
+/// # use datasketches::countmin::CountMinSketch;
+/// let mut sketch = CountMinSketch::<u64>::new(4, 128);
+/// sketch.update(RawBytes(b"apple"));
+/// assert_eq!(sketch.estimate(RawBytes(b"apple")), 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawBytes<'a>(pub &'a [u8]);
+
+impl Hash for RawBytes<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(self.0);
+    }
+}
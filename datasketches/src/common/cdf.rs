@@ -0,0 +1,42 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+/// A point on an approximate cumulative distribution function (CDF) curve.
+///
+/// Produced by the `to_cdf_points` methods of the quantile sketches. With the `serde` feature
+/// enabled, points serialize as plain `{"value": .., "rank": ..}` records, so they can be handed
+/// to plotting tools without exposing sketch internals.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CdfPoint<T> {
+    /// An item of the input domain.
+    pub value: T,
+    /// The approximate normalized rank of `value`, i.e., the fraction of the input stream that is
+    /// less than or equal to it.
+    pub rank: f64,
+}
+
+/// Returns `n` evenly spaced normalized ranks from 0.0 to 1.0 inclusive.
+///
+/// # Panics
+///
+/// Panics if `n` is less than 2.
+pub(crate) fn evenly_spaced_ranks(n: usize) -> impl Iterator<Item = f64> {
+    assert!(n >= 2, "n must be at least 2, got {n}");
+    let last = (n - 1) as f64;
+    (0..n).map(move |i| if i == n - 1 { 1.0 } else { i as f64 / last })
+}
@@ -0,0 +1,66 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+/// The family of the cardinality sketch an [`EstimateSummary`] was produced from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CardinalityFamily {
+    /// A [`ThetaSketch`](crate::theta::ThetaSketch) or
+    /// [`CompactThetaSketch`](crate::theta::CompactThetaSketch).
+    Theta,
+    /// An [`HllSketch`](crate::hll::HllSketch).
+    Hll,
+    /// A [`CpcSketch`](crate::cpc::CpcSketch).
+    Cpc,
+}
+
+/// The estimate of a cardinality sketch with its confidence bounds.
+///
+/// Produced by the `estimate_summary` methods of the cardinality sketches, so that APIs can
+/// return the same result whatever the family of the sketch behind it. With the `serde` feature
+/// enabled, summaries serialize as plain records, e.g. `{"estimate": .., "lb": .., "ub": ..,
+/// "retained": .., "family": "Hll"}`.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::common::CardinalityFamily;
+/// # use datasketches::common::NumStdDev;
+/// # use datasketches::hll::HllSketch;
+/// # use datasketches::hll::HllType;
+/// let mut sketch = HllSketch::new(12, HllType::Hll8);
+/// sketch.update("apple");
+/// let summary = sketch.estimate_summary(NumStdDev::Two);
+/// assert_eq!(summary.family, CardinalityFamily::Hll);
+/// assert!(summary.lb <= summary.estimate && summary.estimate <= summary.ub);
+/// assert_eq!(summary.retained, 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EstimateSummary {
+    /// The estimate of the number of distinct items.
+    pub estimate: f64,
+    /// The lower bound of the confidence interval.
+    pub lb: f64,
+    /// The upper bound of the confidence interval.
+    pub ub: f64,
+    /// The number of entries the sketch retains: hashes for theta, coupons for CPC, and coupons
+    /// or, once the sketch has grown into an array, registers for HLL.
+    pub retained: u64,
+    /// The family of the sketch.
+    pub family: CardinalityFamily,
+}
@@ -0,0 +1,148 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::hash::Hash;
+use std::hash::Hasher;
+
+use crate::common::RawBytes;
+use crate::common::canonical_double_bytes;
+
+/// A value that the sketches hash the way datasketches-java does.
+///
+/// The [`Hash`] implementations of the standard library are not designed for cross-language
+/// sketches: `str` appends a `0xff` terminator, `i32` hashes 4 bytes where Java widens to a
+/// `long`, `usize` depends on the target, and `-0.0` and NaN payloads hash differently from
+/// `0.0` and the canonical NaN. Implementors of this trait feed the hasher exactly the bytes the
+/// `update` methods of datasketches-java hash:
+///
+/// * `&str` and `String` as their UTF-8 bytes,
+/// * `&[u8]` and [`RawBytes`] as-is,
+/// * integers widened to 64 bits, as 8 little-endian bytes,
+/// * `f32` and `f64` widened to `f64` and canonicalized, as the 8 little-endian bytes of
+///   `Double.doubleToLongBits`.
+///
+/// Wrap a value in [`SketchInput`] to pass it to any method that accepts a [`Hash`] item, or use
+/// the `update_input` methods of [`ThetaSketch`](crate::theta::ThetaSketch),
+/// [`HllSketch`](crate::hll::HllSketch), [`CpcSketch`](crate::cpc::CpcSketch) and
+/// [`CountMinSketch`](crate::countmin::CountMinSketch).
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::common::SketchInput;
+/// # use datasketches::theta::ThetaSketch;
+/// let mut a = ThetaSketch::builder().build();
+/// a.update_input(42i32);
+/// a.update_input(-0.0f64);
+/// let mut b = ThetaSketch::builder().build();
+/// b.update(SketchInput(42u64));
+/// b.update(SketchInput(0.0f32));
+/// assert!(a.iter().eq(b.iter()));
+/// ```
+pub trait IntoSketchInput {
+    /// Feeds the canonical bytes of this value to the hasher.
+    fn hash_input<H: Hasher>(&self, state: &mut H);
+}
+
+/// A value that hashes as its [`IntoSketchInput`] bytes.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::common::SketchInput;
+/// # use datasketches::countmin::CountMinSketch;
+/// let mut sketch = CountMinSketch::<u64>::new(4, 128);
+/// sketch.update(SketchInput("apple"));
+/// assert_eq!(sketch.estimate(SketchInput("apple".to_string())), 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SketchInput<T>(pub T);
+
+impl<T: IntoSketchInput> Hash for SketchInput<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash_input(state);
+    }
+}
+
+impl<T: IntoSketchInput + ?Sized> IntoSketchInput for &T {
+    fn hash_input<H: Hasher>(&self, state: &mut H) {
+        (**self).hash_input(state);
+    }
+}
+
+impl IntoSketchInput for str {
+    fn hash_input<H: Hasher>(&self, state: &mut H) {
+        state.write(self.as_bytes());
+    }
+}
+
+impl IntoSketchInput for String {
+    fn hash_input<H: Hasher>(&self, state: &mut H) {
+        state.write(self.as_bytes());
+    }
+}
+
+impl IntoSketchInput for [u8] {
+    fn hash_input<H: Hasher>(&self, state: &mut H) {
+        state.write(self);
+    }
+}
+
+impl IntoSketchInput for RawBytes<'_> {
+    fn hash_input<H: Hasher>(&self, state: &mut H) {
+        state.write(self.0);
+    }
+}
+
+macro_rules! impl_signed_input {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoSketchInput for $ty {
+                fn hash_input<H: Hasher>(&self, state: &mut H) {
+                    state.write(&(*self as i64).to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_unsigned_input {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoSketchInput for $ty {
+                fn hash_input<H: Hasher>(&self, state: &mut H) {
+                    state.write(&(*self as u64).to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_signed_input!(i8, i16, i32, i64, isize);
+impl_unsigned_input!(u8, u16, u32, u64, usize);
+
+impl IntoSketchInput for f64 {
+    fn hash_input<H: Hasher>(&self, state: &mut H) {
+        state.write(&canonical_double_bytes(*self));
+    }
+}
+
+impl IntoSketchInput for f32 {
+    fn hash_input<H: Hasher>(&self, state: &mut H) {
+        (*self as f64).hash_input(state);
+    }
+}
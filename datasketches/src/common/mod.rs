@@ -18,14 +18,32 @@
 //! Data structures and functions that may be used across all the sketch families.
 
 // public common components for datasketches crate
+mod bytes;
+mod cdf;
+mod estimate;
+mod input;
 mod num_std_dev;
 mod resize;
+mod sketch;
+pub use self::bytes::RawBytes;
+pub use self::cdf::CdfPoint;
+pub use self::estimate::CardinalityFamily;
+pub use self::estimate::EstimateSummary;
+pub use self::input::IntoSketchInput;
+pub use self::input::SketchInput;
 pub use self::num_std_dev::NumStdDev;
 pub use self::resize::ResizeFactor;
+pub use self::sketch::MergeableSketch;
+pub use self::sketch::SerializableSketch;
+pub use self::sketch::Sketch;
 
 // private to datasketches crate
 pub(crate) mod binomial_bounds;
+pub(crate) use self::cdf::evenly_spaced_ranks;
 pub(crate) mod inv_pow2_table;
+pub(crate) mod random;
+mod summary;
+pub(crate) use self::summary::Summary;
 
 /// Canonicalize double value for compatibility with Java
 pub(crate) fn canonical_double(value: f64) -> u64 {
@@ -39,3 +57,46 @@ pub(crate) fn canonical_double(value: f64) -> u64 {
         (value + 0.0).to_bits()
     }
 }
+
+/// Returns true if the estimates `a` and `b` differ by at most `epsilon` relative to the larger
+/// one; two zero estimates are always within.
+///
+/// # Panics
+///
+/// Panics if `epsilon` is negative or NaN.
+pub(crate) fn estimates_within(a: f64, b: f64, epsilon: f64) -> bool {
+    assert!(
+        epsilon >= 0.0,
+        "epsilon must be non-negative, got {epsilon}"
+    );
+    (a - b).abs() <= epsilon * a.abs().max(b.abs())
+}
+
+/// Returns the bytes the sketches hash for a double value.
+///
+/// These are the 8 little-endian bytes of `Double.doubleToLongBits` in datasketches-java: `-0.0`
+/// is normalized to `0.0` and every NaN to the canonical quiet NaN `0x7ff8000000000000`. The
+/// `update_f64` methods and [`SketchInput`] of `f64` hash exactly these bytes, so applications
+/// that hash doubles themselves, e.g. with [`murmur3_128`](crate::hash::murmur3_128) before
+/// calling `update_hash`, build sketches identical to those of datasketches-java and
+/// datasketches-cpp.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::common::canonical_double_bytes;
+/// # use datasketches::hash::DEFAULT_UPDATE_SEED;
+/// # use datasketches::hash::murmur3_128;
+/// # use datasketches::theta::ThetaSketch;
+/// assert_eq!(canonical_double_bytes(-0.0), canonical_double_bytes(0.0));
+/// assert_eq!(canonical_double_bytes(-f64::NAN), 0x7ff8000000000000u64.to_le_bytes());
+///
+/// let mut a = ThetaSketch::builder().build();
+/// a.update_f64(-0.0);
+/// let mut b = ThetaSketch::builder().build();
+/// b.update_hash(murmur3_128(&canonical_double_bytes(-0.0), DEFAULT_UPDATE_SEED));
+/// assert!(a.iter().eq(b.iter()));
+/// ```
+pub fn canonical_double_bytes(value: f64) -> [u8; 8] {
+    canonical_double(value).to_le_bytes()
+}
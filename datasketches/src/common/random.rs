@@ -0,0 +1,76 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A small, fast pseudo-random number generator for the randomized parts of the sketches.
+//!
+//! The sketches only need a cheap source of unbiased bits and uniform doubles, not a
+//! cryptographically secure one, so this avoids pulling in an external dependency.
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+/// A xorshift64* generator.
+#[derive(Debug, Clone)]
+pub(crate) struct RandomSource {
+    state: u64,
+}
+
+impl RandomSource {
+    /// Creates a generator seeded from the per-process randomness of the standard library.
+    pub fn new() -> Self {
+        Self::with_seed(RandomState::new().hash_one(0u64))
+    }
+
+    /// Creates a generator with a fixed seed, producing a reproducible sequence.
+    pub fn with_seed(seed: u64) -> Self {
+        // run the seed through a splitmix64 step so that similar seeds yield unrelated
+        // sequences, and the state is never zero
+        let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^= z >> 31;
+        Self {
+            state: if z == 0 { 0x9E3779B97F4A7C15 } else { z },
+        }
+    }
+
+    /// Returns the next pseudo-random 64-bit value.
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    /// Returns a pseudo-random bit, i.e., either 0 or 1.
+    pub fn next_bit(&mut self) -> usize {
+        (self.next_u64() >> 63) as usize
+    }
+
+    /// Returns a pseudo-random double uniformly distributed in `[0.0, 1.0)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+impl Default for RandomSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
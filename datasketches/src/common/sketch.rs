@@ -0,0 +1,121 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::error::Error;
+
+/// A sketch that summarizes a stream of items of type `I`.
+///
+/// Together with [`MergeableSketch`] and [`SerializableSketch`], this lets generic code, such as
+/// a sketch registry or an aggregation operator, drive any sketch family uniformly. Hash-based
+/// sketches accept any [`Hash`](std::hash::Hash) item, while quantile sketches accept their
+/// ordered item type.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::common::Sketch;
+/// # use datasketches::hll::HllSketch;
+/// # use datasketches::hll::HllType;
+/// # use datasketches::theta::ThetaSketch;
+/// fn feed<S: Sketch<u64>>(sketch: &mut S, items: &[u64]) {
+///     for &item in items {
+///         sketch.update(item);
+///     }
+/// }
+///
+/// let mut theta = ThetaSketch::builder().build();
+/// let mut hll = HllSketch::new(12, HllType::Hll8);
+/// feed(&mut theta, &[1, 2, 3]);
+/// feed(&mut hll, &[1, 2, 3]);
+/// assert_eq!(theta.estimate(), 3.0);
+/// assert!(!Sketch::<u64>::is_empty(&hll));
+/// ```
+pub trait Sketch<I> {
+    /// Updates the sketch with the given item.
+    fn update(&mut self, item: I);
+
+    /// Returns true if the sketch has not seen any data.
+    fn is_empty(&self) -> bool;
+}
+
+/// A sketch that can absorb another sketch of the same type.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::common::MergeableSketch;
+/// # use datasketches::kll::KllSketch;
+/// fn merge_all<S: MergeableSketch>(first: S, rest: &[S]) -> Result<S, S::Error> {
+///     let mut merged = first;
+///     for sketch in rest {
+///         merged.merge(sketch)?;
+///     }
+///     Ok(merged)
+/// }
+///
+/// let mut left = KllSketch::<f64>::new(200);
+/// left.update(1.0);
+/// let mut right = KllSketch::<f64>::new(200);
+/// right.update(2.0);
+/// let merged = merge_all(left, &[right]).unwrap();
+/// assert_eq!(merged.n(), 2);
+/// ```
+pub trait MergeableSketch {
+    /// The error returned when the sketches cannot be merged, e.g., because they were configured
+    /// differently; [`Infallible`](std::convert::Infallible) for sketches that always merge.
+    type Error;
+
+    /// Merges `other` into this sketch.
+    fn merge(&mut self, other: &Self) -> Result<(), Self::Error>;
+}
+
+/// A sketch with a binary serialization form.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::common::SerializableSketch;
+/// # use datasketches::cpc::CpcSketch;
+/// fn copy<S: SerializableSketch>(sketch: &S) -> Result<S, S::Error> {
+///     S::deserialize(&sketch.serialize())
+/// }
+///
+/// let mut sketch = CpcSketch::new(10);
+/// sketch.update("apple");
+/// assert_eq!(copy(&sketch).unwrap().estimate(), sketch.estimate());
+/// ```
+pub trait SerializableSketch: Sized {
+    /// The error returned when bytes cannot be deserialized.
+    type Error;
+
+    /// Serializes this sketch to bytes.
+    fn serialize(&self) -> Vec<u8>;
+
+    /// Returns the number of bytes [`serialize`](Self::serialize) produces.
+    fn serialized_size_bytes(&self) -> usize;
+
+    /// Serializes this sketch into the front of `buf` without allocating, returning the number
+    /// of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// If `buf` is shorter than [`serialized_size_bytes`](Self::serialized_size_bytes).
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error>;
+
+    /// Deserializes a sketch from bytes produced by [`serialize`](Self::serialize).
+    fn deserialize(bytes: &[u8]) -> Result<Self, Self::Error>;
+}
@@ -0,0 +1,48 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::fmt;
+
+/// A helper for `Display` implementations that print a sketch summary in the layout of
+/// `toString()` in datasketches-java: a header line, one `label : value` line per field, and a
+/// footer line.
+pub(crate) struct Summary<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    result: fmt::Result,
+}
+
+impl<'a, 'b> Summary<'a, 'b> {
+    /// Writes the header line for a sketch of the given kind, e.g. `"HLL SKETCH"`.
+    pub(crate) fn new(f: &'a mut fmt::Formatter<'b>, name: &str) -> Self {
+        let result = writeln!(f, "### {name} SUMMARY:");
+        Summary { f, result }
+    }
+
+    /// Writes one field line.
+    pub(crate) fn field(&mut self, label: &str, value: impl fmt::Display) -> &mut Self {
+        if self.result.is_ok() {
+            self.result = writeln!(self.f, "   {label:<24}: {value}");
+        }
+        self
+    }
+
+    /// Writes the footer line.
+    pub(crate) fn finish(&mut self) -> fmt::Result {
+        self.result?;
+        write!(self.f, "### END SKETCH SUMMARY")
+    }
+}
@@ -0,0 +1,149 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Serialization format compatibility matrix.
+//!
+//! [`supported_formats`] enumerates, per sketch family, the serial versions and modes this crate
+//! can read and write. Orchestration tooling can consult it to check that every node of a fleet
+//! can decode the images produced by the others before rolling out an upgrade.
+//!
+//! # Cross-language targets
+//!
+//! The theta, HLL, KLL and frequent items deserializers target the images written by
+//! datasketches-java [`TARGET_JAVA_RELEASE`] and datasketches-cpp [`TARGET_CPP_RELEASE`], and
+//! the generator of the serialization test corpus is pinned to those releases. The corpus itself
+//! has not been regenerated from them yet, so reading images of these exact releases is targeted
+//! rather than tested; moving to a newer release is a deliberate change that bumps these
+//! constants together with the generator.
+//!
+//! # Examples
+//!
+//! ```
+//! # use datasketches::compat::supported_formats;
+//! let writable: Vec<_> = supported_formats()
+//!     .iter()
+//!     .filter(|format| format.family() == "THETA" && format.can_write())
+//!     .map(|format| format.serial_version())
+//!     .collect();
+//! assert_eq!(writable, [Some(3), Some(4)]);
+//! ```
+
+use crate::codec::family::Family;
+
+/// The datasketches-java release whose serialized images this crate targets.
+pub const TARGET_JAVA_RELEASE: &str = "9.0.0";
+
+/// The datasketches-cpp release whose serialized images this crate targets.
+pub const TARGET_CPP_RELEASE: &str = "5.2.0";
+
+/// A serialized format of one sketch family, and whether this crate can read and write it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatSupport {
+    family: &'static str,
+    family_id: u8,
+    serial_version: Option<u8>,
+    mode: &'static str,
+    can_read: bool,
+    can_write: bool,
+}
+
+impl FormatSupport {
+    const fn read_write(family: &Family, serial_version: u8, mode: &'static str) -> Self {
+        FormatSupport {
+            family: family.name,
+            family_id: family.id,
+            serial_version: Some(serial_version),
+            mode,
+            can_read: true,
+            can_write: true,
+        }
+    }
+
+    const fn read_only(family: &Family, serial_version: Option<u8>, mode: &'static str) -> Self {
+        FormatSupport {
+            family: family.name,
+            family_id: family.id,
+            serial_version,
+            mode,
+            can_read: true,
+            can_write: false,
+        }
+    }
+
+    /// Returns the family name, e.g. `"THETA"`, as used by datasketches-java and datasketches-cpp.
+    pub fn family(&self) -> &'static str {
+        self.family
+    }
+
+    /// Returns the family ID stored in the preamble of serialized images.
+    pub fn family_id(&self) -> u8 {
+        self.family_id
+    }
+
+    /// Returns the serial version stored in the preamble of serialized images; `None` for legacy
+    /// formats without a datasketches preamble.
+    pub fn serial_version(&self) -> Option<u8> {
+        self.serial_version
+    }
+
+    /// Returns a short description of the layout variant, e.g. `"compact"` or `"hll4"`.
+    pub fn mode(&self) -> &'static str {
+        self.mode
+    }
+
+    /// Returns true if this crate can deserialize the format.
+    pub fn can_read(&self) -> bool {
+        self.can_read
+    }
+
+    /// Returns true if this crate can produce the format.
+    pub fn can_write(&self) -> bool {
+        self.can_write
+    }
+}
+
+const SUPPORTED_FORMATS: &[FormatSupport] = &[
+    FormatSupport::read_write(&Family::QUICKSELECT, 3, "updatable"),
+    FormatSupport::read_only(&Family::THETA, Some(1), "compact"),
+    FormatSupport::read_only(&Family::THETA, Some(2), "compact"),
+    FormatSupport::read_write(&Family::THETA, 3, "compact"),
+    FormatSupport::read_write(&Family::THETA, 4, "compressed compact"),
+    FormatSupport::read_write(&Family::HLL, 1, "list"),
+    FormatSupport::read_write(&Family::HLL, 1, "set"),
+    FormatSupport::read_write(&Family::HLL, 1, "hll4"),
+    FormatSupport::read_write(&Family::HLL, 1, "hll6"),
+    FormatSupport::read_write(&Family::HLL, 1, "hll8"),
+    FormatSupport::read_write(&Family::FREQUENCY, 1, "items"),
+    FormatSupport::read_write(&Family::KLL, 1, "empty or full"),
+    FormatSupport::read_write(&Family::KLL, 2, "single item"),
+    FormatSupport::read_write(&Family::KLL, 1, "multi-dimensional"),
+    FormatSupport::read_write(&Family::CPC, 1, "compressed"),
+    FormatSupport::read_write(&Family::COUNTMIN, 1, "counters"),
+    FormatSupport::read_write(&Family::TDIGEST, 1, "centroids"),
+    FormatSupport::read_only(&Family::TDIGEST, None, "reference double"),
+    FormatSupport::read_only(&Family::TDIGEST, None, "reference float"),
+    FormatSupport::read_write(&Family::BLOOMFILTER, 1, "bits"),
+];
+
+/// Returns every serialized format this crate can read or write, grouped by family.
+///
+/// The `"multi-dimensional"` KLL format is specific to this crate; all the others are shared with
+/// datasketches-java and datasketches-cpp. The `"reference"` t-digest formats are the big-endian
+/// layouts of the original t-digest library, which carry no serial version.
+pub fn supported_formats() -> &'static [FormatSupport] {
+    SUPPORTED_FORMATS
+}
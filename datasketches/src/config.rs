@@ -0,0 +1,141 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Crate-wide defaults consulted by the sketch builders.
+
+use std::sync::PoisonError;
+use std::sync::RwLock;
+
+use crate::common::ResizeFactor;
+use crate::hash::DEFAULT_UPDATE_SEED;
+use crate::theta::DEFAULT_LG_K;
+use crate::theta::MAX_LG_K;
+use crate::theta::MIN_LG_K;
+
+static GLOBAL: RwLock<Config> = RwLock::new(Config::new());
+
+/// Defaults for the parameters that builders leave unset.
+///
+/// Builders start from the configuration installed with [`Config::set_global`] at the time they
+/// are created, so an application can choose its lg_k, hash seed and resize factor once at
+/// startup instead of at every construction site. Explicit builder calls still take precedence:
+///
+/// * [`ThetaSketch::builder`] and [`ThetaUnion::builder`] use all three defaults;
+/// * [`BloomFilterBuilder`] uses the seed.
+///
+/// Sketches constructed without a builder, and deserialization without an explicit seed, keep
+/// the crate defaults. After installing a non-default seed, read theta sketches back with
+/// `deserialize_with_seed(bytes, Config::global().seed())`.
+///
+/// [`ThetaSketch::builder`]: crate::theta::ThetaSketch::builder
+/// [`ThetaUnion::builder`]: crate::theta::ThetaUnion::builder
+/// [`BloomFilterBuilder`]: crate::bloom::BloomFilterBuilder
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::Config;
+/// # use datasketches::common::ResizeFactor;
+/// # use datasketches::theta::ThetaSketch;
+/// Config::new()
+///     .with_lg_k(14)
+///     .with_resize_factor(ResizeFactor::X2)
+///     .set_global();
+/// assert_eq!(ThetaSketch::builder().build().lg_k(), 14);
+/// assert_eq!(ThetaSketch::builder().lg_k(10).build().lg_k(), 10);
+/// # Config::new().set_global();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    lg_k: u8,
+    seed: u64,
+    resize_factor: ResizeFactor,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config::new()
+    }
+}
+
+impl Config {
+    /// Creates a configuration with the crate defaults: lg_k 12, [`DEFAULT_UPDATE_SEED`] and
+    /// [`ResizeFactor::X8`].
+    pub const fn new() -> Self {
+        Config {
+            lg_k: DEFAULT_LG_K,
+            seed: DEFAULT_UPDATE_SEED,
+            resize_factor: ResizeFactor::X8,
+        }
+    }
+
+    /// Returns the configuration currently installed for the process.
+    pub fn global() -> Config {
+        *GLOBAL.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Installs this configuration for the process, replacing the previous one.
+    ///
+    /// Builders created before the call are not affected.
+    pub fn set_global(self) {
+        *GLOBAL.write().unwrap_or_else(PoisonError::into_inner) = self;
+    }
+
+    /// Sets the default log2 of the nominal number of entries.
+    ///
+    /// # Panics
+    ///
+    /// If lg_k is not in range [5, 26]
+    pub fn with_lg_k(mut self, lg_k: u8) -> Self {
+        assert!(
+            (MIN_LG_K..=MAX_LG_K).contains(&lg_k),
+            "lg_k must be in [{}, {}], got {}",
+            MIN_LG_K,
+            MAX_LG_K,
+            lg_k
+        );
+        self.lg_k = lg_k;
+        self
+    }
+
+    /// Sets the default hash seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the default resize factor of hash tables.
+    pub fn with_resize_factor(mut self, resize_factor: ResizeFactor) -> Self {
+        self.resize_factor = resize_factor;
+        self
+    }
+
+    /// Returns the default log2 of the nominal number of entries.
+    pub fn lg_k(&self) -> u8 {
+        self.lg_k
+    }
+
+    /// Returns the default hash seed.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the default resize factor of hash tables.
+    pub fn resize_factor(&self) -> ResizeFactor {
+        self.resize_factor
+    }
+}
@@ -0,0 +1,121 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::countmin::CountMinValue;
+use crate::error::Error;
+
+/// How a Count-Min sketch combines weights that land in the same cell.
+///
+/// The aggregation determines what [`CountMinSketch::estimate`] answers and in
+/// which direction it errs:
+///
+/// * [`Sum`](Self::Sum) is the classic Count-Min sketch. The estimate is the frequency (total
+///   weight) of an item and, for non-negative weights, never underestimates it. The overestimate is
+///   at most `relative_error() * total_weight()` with the configured confidence.
+/// * [`Max`](Self::Max) (Count-Max) keeps the largest weight observed per cell. The estimate is an
+///   upper bound on the largest weight ever observed for the item; it is exact unless every row
+///   collides with a heavier item.
+/// * [`Min`](Self::Min) (Count-Min-of-minima) keeps the smallest weight observed per cell. The
+///   estimate is a lower bound on the smallest weight ever observed for the item; it is exact
+///   unless every row collides with a lighter item.
+///
+/// For `Max` and `Min`, an item that was never observed reports the
+/// aggregation's identity (`T::MIN` and `T::MAX` respectively) unless it
+/// collides with observed items in every row.
+///
+/// [`CountMinSketch::estimate`]: crate::countmin::CountMinSketch::estimate
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CountMinAggregation {
+    /// Cells accumulate the sum of weights.
+    #[default]
+    Sum,
+    /// Cells keep the maximum weight.
+    Max,
+    /// Cells keep the minimum weight.
+    Min,
+}
+
+impl CountMinAggregation {
+    /// Returns the value of a cell that has not absorbed any weight.
+    pub(super) fn identity<T: CountMinValue>(self) -> T {
+        match self {
+            CountMinAggregation::Sum => T::ZERO,
+            CountMinAggregation::Max => T::MIN,
+            CountMinAggregation::Min => T::MAX,
+        }
+    }
+
+    /// Combines a cell value with an incoming weight or another cell value.
+    pub(super) fn combine<T: CountMinValue>(self, cell: T, value: T) -> T {
+        match self {
+            CountMinAggregation::Sum => cell.add(value),
+            CountMinAggregation::Max => cell.max_of(value),
+            CountMinAggregation::Min => cell.min_of(value),
+        }
+    }
+
+    /// Picks the estimate of an item from its cells, one per row.
+    ///
+    /// Collisions only ever push a cell away from the true value, so the best
+    /// estimate is the cell closest to the aggregation's identity.
+    pub(super) fn estimate<T: CountMinValue>(self, cells: impl Iterator<Item = T>) -> T {
+        let estimate = match self {
+            CountMinAggregation::Sum | CountMinAggregation::Max => cells.reduce(T::min_of),
+            CountMinAggregation::Min => cells.reduce(T::max_of),
+        };
+        estimate.expect("sketch has at least one hash function")
+    }
+
+    /// Returns the lower bound of an item given a way to compute its estimate.
+    pub(super) fn lower_bound<T: CountMinValue>(self, estimate: impl FnOnce() -> T) -> T {
+        match self {
+            CountMinAggregation::Sum | CountMinAggregation::Min => estimate(),
+            CountMinAggregation::Max => T::MIN,
+        }
+    }
+
+    /// Returns the upper bound of an item given a way to compute its estimate and
+    /// the additive error of a [`Sum`](Self::Sum) sketch.
+    pub(super) fn upper_bound<T: CountMinValue>(
+        self,
+        estimate: impl FnOnce() -> T,
+        sum_error: impl FnOnce() -> T,
+    ) -> T {
+        match self {
+            CountMinAggregation::Sum => estimate().saturating_add(sum_error()),
+            CountMinAggregation::Max => estimate(),
+            CountMinAggregation::Min => T::MAX,
+        }
+    }
+
+    pub(super) fn id(self) -> u8 {
+        match self {
+            CountMinAggregation::Sum => 0,
+            CountMinAggregation::Max => 1,
+            CountMinAggregation::Min => 2,
+        }
+    }
+
+    pub(super) fn from_id(id: u8) -> Result<Self, Error> {
+        match id {
+            0 => Ok(CountMinAggregation::Sum),
+            1 => Ok(CountMinAggregation::Max),
+            2 => Ok(CountMinAggregation::Min),
+            _ => Err(Error::deserial(format!("unknown aggregation id: {id}"))),
+        }
+    }
+}
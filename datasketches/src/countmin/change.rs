@@ -0,0 +1,107 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::hash::Hash;
+
+use crate::countmin::CountMinAggregation;
+use crate::countmin::CountMinSketch;
+use crate::countmin::CountMinValue;
+use crate::error::Error;
+
+/// Compares two epochs of a stream summarized by Count-Min sketches to find heavy changes.
+///
+/// A heavy change is an item whose frequency differs between the epochs by at least a threshold,
+/// e.g. a source address whose traffic suddenly spikes or drops. Count-Min sketches do not record
+/// their items, so the detector checks a set of candidates, typically the keys seen in the
+/// current epoch or those reported by a [`FrequentItemsSketch`] of either epoch; the deltas are
+/// estimated with [`CountMinSketch::estimate_delta`].
+///
+/// [`FrequentItemsSketch`]: crate::frequencies::FrequentItemsSketch
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::countmin::CountMinChangeDetector;
+/// # use datasketches::countmin::CountMinSketch;
+/// let mut previous = CountMinSketch::<u64>::new(5, 512);
+/// let mut current = CountMinSketch::<u64>::new(5, 512);
+/// for host in 0..100 {
+///     previous.update_with_weight(host, 50);
+///     current.update_with_weight(host, 50);
+/// }
+/// current.update_with_weight(42, 5_000);
+///
+/// let detector = CountMinChangeDetector::try_new(&previous, &current).unwrap();
+/// let changes = detector.heavy_changes(0..100, 1_000.0);
+/// assert_eq!(changes, vec![(42, 5_000.0)]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct CountMinChangeDetector<'a, T: CountMinValue> {
+    previous: &'a CountMinSketch<T>,
+    current: &'a CountMinSketch<T>,
+}
+
+impl<'a, T: CountMinValue> CountMinChangeDetector<'a, T> {
+    /// Creates a detector comparing the `current` epoch against the `previous` one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sketches are not [compatible](CountMinSketch::is_compatible), or
+    /// if they do not use [`CountMinAggregation::Sum`].
+    pub fn try_new(
+        previous: &'a CountMinSketch<T>,
+        current: &'a CountMinSketch<T>,
+    ) -> Result<Self, Error> {
+        if !current.is_compatible(previous) {
+            return Err(Error::invalid_argument(
+                "cannot compare CountMin sketches with different configurations",
+            ));
+        }
+        if current.aggregation() != CountMinAggregation::Sum {
+            return Err(Error::invalid_argument(format!(
+                "deltas are only defined for sum sketches, got {:?}",
+                current.aggregation()
+            )));
+        }
+        Ok(CountMinChangeDetector { previous, current })
+    }
+
+    /// Returns the estimated change in the frequency of `item` between the epochs.
+    pub fn delta<I: Hash>(&self, item: I) -> f64 {
+        self.current.estimate_delta(self.previous, item)
+    }
+
+    /// Returns the candidates whose estimated change is at least `threshold` in absolute value,
+    /// with their changes, sorted by descending absolute change.
+    ///
+    /// Both increases and decreases are reported; the sign of the change tells them apart.
+    pub fn heavy_changes<I: Hash>(
+        &self,
+        candidates: impl IntoIterator<Item = I>,
+        threshold: f64,
+    ) -> Vec<(I, f64)> {
+        let mut changes: Vec<(I, f64)> = candidates
+            .into_iter()
+            .filter_map(|item| {
+                let delta = self.delta(&item);
+                (delta.abs() >= threshold).then_some((item, delta))
+            })
+            .collect();
+        changes.sort_by(|(_, a), (_, b)| b.abs().total_cmp(&a.abs()));
+        changes
+    }
+}
@@ -38,12 +38,93 @@
 //! let hashes = CountMinSketch::<i64>::suggest_num_hashes(0.99);
 //! let _sketch = CountMinSketch::<i64>::new(hashes, buckets);
 //! ```
+//!
+//! [`CountMinSketch::with_error`] combines both and reports invalid targets as errors.
+//!
+//! ```
+//! # use datasketches::countmin::CountMinSketch;
+//! let sketch = CountMinSketch::<i64>::with_error(0.01, 0.99).unwrap();
+//! assert!(sketch.relative_error() <= 0.01);
+//! ```
+//!
+//! # Counter Types
+//!
+//! The counter type is any [`CountMinValue`]: signed and unsigned integers up to 64 bits, or
+//! `f64` for fractional weights. `u32` counters halve the footprint of `u64` ones when the
+//! total weight is known to fit.
+//!
+//! ```
+//! # use datasketches::countmin::CountMinSketch;
+//! let mut sketch = CountMinSketch::<f64>::new(5, 256);
+//! sketch.update_with_weight("bytes:/video", 1.5e9);
+//! assert!(sketch.estimate("bytes:/video") >= 1.5e9);
+//! ```
+//!
+//! # Read-only Views
+//!
+//! [`CountMinWrapper`] answers queries directly from a serialized image, such as
+//! a memory-mapped file, without copying the counter matrix.
+//!
+//! ```
+//! # use datasketches::countmin::CountMinSketch;
+//! # use datasketches::countmin::CountMinWrapper;
+//! # let mut sketch = CountMinSketch::<i64>::new(5, 256);
+//! # sketch.update_with_weight("banana", 3);
+//! let bytes = sketch.serialize();
+//! let wrapper = CountMinWrapper::<i64>::new(&bytes).unwrap();
+//! assert!(wrapper.estimate("banana") >= 3);
+//! ```
+//!
+//! # Max and Min Aggregation
+//!
+//! Cells can keep the maximum or minimum weight instead of the sum, which turns
+//! the sketch into an approximate "largest (or smallest) weight seen per key"
+//! structure. See [`CountMinAggregation`] for the error semantics.
+//!
+//! ```
+//! # use datasketches::countmin::CountMinAggregation;
+//! # use datasketches::countmin::CountMinSketch;
+//! let mut sketch = CountMinSketch::<u64>::with_aggregation(4, 128, CountMinAggregation::Max);
+//! sketch.update_with_weight("latency:/home", 120);
+//! sketch.update_with_weight("latency:/home", 80);
+//! assert!(sketch.estimate("latency:/home") >= 120);
+//! ```
+//!
+//! # Change Detection
+//!
+//! Two sum sketches of consecutive epochs, built with the same configuration, estimate how much
+//! the frequency of an item changed with [`CountMinSketch::estimate_delta`].
+//! [`CountMinChangeDetector`] reports the candidates whose frequency changed by at least a
+//! threshold, e.g. for anomaly detection over network flows.
+//!
+//! # Table Layout
+//!
+//! Counters are stored row by row, as in the serialized image, and each row picks the bucket of an
+//! item with its own hash. An update or estimate therefore touches `num_hashes` unrelated cache
+//! lines, which shows once the table outgrows the CPU caches. In the `countmin_width` group of the
+//! `sketches` benchmark, an update of a table with 5 rows takes about 90 ns on a Xeon core at 2^10
+//! buckets, where the table fits in L1 and hashing is the whole cost, and about 430 ns at 2^20
+//! buckets. Keeping the cells of an item in one or two cache lines would need the buckets of all
+//! rows to be derived from a single block; storing the same cells in another order does not help,
+//! as the buckets of the rows stay unrelated. Deriving them from a block changes the cells an item
+//! maps to, so images of datasketches-cpp and of earlier versions would answer queries for the
+//! wrong cells. The layout is kept for that reason.
+
+mod aggregation;
+pub use self::aggregation::CountMinAggregation;
+
+mod change;
+pub use self::change::CountMinChangeDetector;
 
 mod serialization;
 
 mod sketch;
+pub(crate) use self::serialization::inspect;
 pub use self::sketch::CountMinSketch;
 
 mod value;
 pub use self::value::CountMinValue;
 pub use self::value::UnsignedCountMinValue;
+
+mod wrapper;
+pub use self::wrapper::CountMinWrapper;
@@ -15,7 +15,40 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use crate::codec::SketchSlice;
+use crate::codec::assert::ensure_serial_version_is;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
+use crate::error::Error;
+use crate::inspect::SketchInfo;
+
 pub(super) const PREAMBLE_LONGS_SHORT: u8 = 2;
 pub(super) const SERIAL_VERSION: u8 = 1;
 pub(super) const FLAGS_IS_EMPTY: u8 = 1 << 0;
 pub(super) const LONG_SIZE_BYTES: usize = 8;
+
+/// Returns the length of a serialized sketch from its preamble.
+pub(super) fn image_len(preamble: &[u8]) -> usize {
+    if preamble[3] & FLAGS_IS_EMPTY != 0 {
+        return preamble.len();
+    }
+    let num_buckets = u32::from_le_bytes(preamble[8..12].try_into().unwrap()) as usize;
+    let num_hashes = preamble[12] as usize;
+    // total weight and counters
+    let entries = num_hashes.saturating_mul(num_buckets);
+    preamble.len() + LONG_SIZE_BYTES + entries.saturating_mul(LONG_SIZE_BYTES)
+}
+
+/// Reads the preamble of an image for [`inspect`](crate::inspect).
+pub(crate) fn inspect(
+    _preamble_longs: u8,
+    serial_version: u8,
+    mut cursor: SketchSlice<'_>,
+) -> Result<SketchInfo, Error> {
+    ensure_serial_version_is(SERIAL_VERSION, serial_version)?;
+    let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+
+    let mut info = SketchInfo::new(&Family::COUNTMIN, serial_version);
+    info.is_empty = (flags & FLAGS_IS_EMPTY) != 0;
+    Ok(info)
+}
@@ -15,17 +15,29 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::fmt;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::io;
+use std::io::Read;
+use std::io::Write;
 
 use crate::codec::SketchBytes;
-use crate::codec::SketchSlice;
-use crate::codec::assert::ensure_preamble_longs_in;
-use crate::codec::assert::ensure_serial_version_is;
-use crate::codec::assert::insufficient_data;
 use crate::codec::family::Family;
+use crate::codec::read_image;
+use crate::codec::serialize_into_slice;
+use crate::codec::serialize_into_writer;
+use crate::common::IntoSketchInput;
+use crate::common::MergeableSketch;
+use crate::common::SerializableSketch;
+use crate::common::Sketch;
+use crate::common::SketchInput;
+use crate::common::Summary;
+use crate::countmin::CountMinAggregation;
 use crate::countmin::CountMinValue;
+use crate::countmin::CountMinWrapper;
 use crate::countmin::UnsignedCountMinValue;
+use crate::countmin::serialization;
 use crate::countmin::serialization::FLAGS_IS_EMPTY;
 use crate::countmin::serialization::LONG_SIZE_BYTES;
 use crate::countmin::serialization::PREAMBLE_LONGS_SHORT;
@@ -34,19 +46,25 @@ use crate::error::Error;
 use crate::hash::DEFAULT_UPDATE_SEED;
 use crate::hash::MurmurHash3X64128;
 use crate::hash::compute_seed_hash;
+use crate::hash::try_compute_seed_hash;
 
 const MAX_TABLE_ENTRIES: usize = 1 << 30;
+/// An empty image carries no counters to bound the table it asks for, so its size is capped.
+const MAX_EMPTY_IMAGE_ENTRIES: usize = 1 << 24;
 
 /// Count-Min sketch for estimating item frequencies.
 ///
 /// The sketch provides upper and lower bounds on estimated item frequencies
-/// with configurable relative error and confidence.
+/// with configurable relative error and confidence. Cells sum weights by
+/// default; see [`CountMinAggregation`] for sketches that track the maximum or
+/// minimum weight per item instead.
 #[derive(Debug, Clone, PartialEq)]
 pub struct CountMinSketch<T: CountMinValue> {
     num_hashes: u8,
     num_buckets: u32,
     seed: u64,
     seed_hash: u16,
+    aggregation: CountMinAggregation,
     total_weight: T,
     counts: Vec<T>,
     hash_seeds: Vec<u64>,
@@ -55,6 +73,8 @@ pub struct CountMinSketch<T: CountMinValue> {
 impl<T: CountMinValue> CountMinSketch<T> {
     /// Creates a new Count-Min sketch with the default seed.
     ///
+    /// The fallible version of this method is [`CountMinSketch::try_new`].
+    ///
     /// # Panics
     ///
     /// Panics if `num_hashes` is 0, `num_buckets` is less than 3, or the
@@ -73,6 +93,13 @@ impl<T: CountMinValue> CountMinSketch<T> {
 
     /// Creates a new Count-Min sketch with the provided seed.
     ///
+    /// The seed selects the hash functions of the rows, see [`hash_seeds`](Self::hash_seeds).
+    /// Serialized images record its 16-bit hash: they must be deserialized with
+    /// [`deserialize_with_seed`](Self::deserialize_with_seed) and the same seed, and sketches
+    /// built with different seeds cannot be merged.
+    ///
+    /// The fallible version of this method is [`CountMinSketch::try_with_seed`].
+    ///
     /// # Panics
     ///
     /// Panics if any of:
@@ -90,7 +117,153 @@ impl<T: CountMinValue> CountMinSketch<T> {
     /// ```
     pub fn with_seed(num_hashes: u8, num_buckets: u32, seed: u64) -> Self {
         let entries = entries_for_config(num_hashes, num_buckets);
-        Self::make(num_hashes, num_buckets, seed, entries)
+        Self::make(
+            num_hashes,
+            num_buckets,
+            seed,
+            CountMinAggregation::Sum,
+            entries,
+        )
+    }
+
+    /// Creates a new Count-Min sketch with the default seed that combines
+    /// weights with the given aggregation.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`CountMinSketch::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinAggregation;
+    /// # use datasketches::countmin::CountMinSketch;
+    /// let mut sketch = CountMinSketch::<i64>::with_aggregation(4, 128, CountMinAggregation::Min);
+    /// sketch.update_with_weight("apple", 7);
+    /// sketch.update_with_weight("apple", 3);
+    /// assert!(sketch.estimate("apple") <= 3);
+    /// ```
+    pub fn with_aggregation(
+        num_hashes: u8,
+        num_buckets: u32,
+        aggregation: CountMinAggregation,
+    ) -> Self {
+        Self::with_seed_and_aggregation(num_hashes, num_buckets, DEFAULT_UPDATE_SEED, aggregation)
+    }
+
+    /// Creates a new Count-Min sketch with the provided seed that combines
+    /// weights with the given aggregation.
+    ///
+    /// The fallible version of this method is
+    /// [`CountMinSketch::try_with_seed_and_aggregation`].
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`CountMinSketch::with_seed`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinAggregation;
+    /// # use datasketches::countmin::CountMinSketch;
+    /// let sketch =
+    ///     CountMinSketch::<u64>::with_seed_and_aggregation(4, 64, 42, CountMinAggregation::Max);
+    /// assert_eq!(sketch.aggregation(), CountMinAggregation::Max);
+    /// ```
+    pub fn with_seed_and_aggregation(
+        num_hashes: u8,
+        num_buckets: u32,
+        seed: u64,
+        aggregation: CountMinAggregation,
+    ) -> Self {
+        let entries = entries_for_config(num_hashes, num_buckets);
+        Self::make(num_hashes, num_buckets, seed, aggregation, entries)
+    }
+
+    /// Creates a new Count-Min sketch with the default seed.
+    ///
+    /// The panicking version of this method is [`CountMinSketch::new`].
+    ///
+    /// # Errors
+    ///
+    /// If `num_hashes` is 0, `num_buckets` is less than 3, or the total table size exceeds the
+    /// supported limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// assert!(CountMinSketch::<i64>::try_new(0, 128).is_err());
+    /// let sketch = CountMinSketch::<i64>::try_new(4, 128).unwrap();
+    /// assert_eq!(sketch.num_buckets(), 128);
+    /// ```
+    pub fn try_new(num_hashes: u8, num_buckets: u32) -> Result<Self, Error> {
+        Self::try_with_seed(num_hashes, num_buckets, DEFAULT_UPDATE_SEED)
+    }
+
+    /// Creates a new Count-Min sketch with the provided seed.
+    ///
+    /// The panicking version of this method is [`CountMinSketch::with_seed`].
+    ///
+    /// # Errors
+    ///
+    /// Under the same conditions as [`CountMinSketch::try_new`], or if the computed seed hash is
+    /// zero.
+    pub fn try_with_seed(num_hashes: u8, num_buckets: u32, seed: u64) -> Result<Self, Error> {
+        Self::try_with_seed_and_aggregation(num_hashes, num_buckets, seed, CountMinAggregation::Sum)
+    }
+
+    /// Creates a new Count-Min sketch with the provided seed that combines
+    /// weights with the given aggregation.
+    ///
+    /// The panicking version of this method is [`CountMinSketch::with_seed_and_aggregation`].
+    ///
+    /// # Errors
+    ///
+    /// Under the same conditions as [`CountMinSketch::try_with_seed`].
+    pub fn try_with_seed_and_aggregation(
+        num_hashes: u8,
+        num_buckets: u32,
+        seed: u64,
+        aggregation: CountMinAggregation,
+    ) -> Result<Self, Error> {
+        let entries = entries_for_config_checked(num_hashes, num_buckets)
+            .map_err(|err| Error::invalid_argument(err.message()))?;
+        try_compute_seed_hash(seed)?;
+        Ok(Self::make(
+            num_hashes,
+            num_buckets,
+            seed,
+            aggregation,
+            entries,
+        ))
+    }
+
+    /// Creates a new Count-Min sketch with the default seed, sized for the given relative error
+    /// and confidence.
+    ///
+    /// The dimensions are those of [`try_suggest_num_buckets`](Self::try_suggest_num_buckets)
+    /// and [`try_suggest_num_hashes`](Self::try_suggest_num_hashes).
+    ///
+    /// # Errors
+    ///
+    /// If `relative_error` is not positive, `confidence` is not in `(0, 1)`, or the resulting
+    /// table size exceeds the supported limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// let sketch = CountMinSketch::<i64>::with_error(0.01, 0.99).unwrap();
+    /// assert_eq!(sketch.num_buckets(), 272);
+    /// assert_eq!(sketch.num_hashes(), 5);
+    /// assert!(CountMinSketch::<i64>::with_error(0.0, 0.99).is_err());
+    /// assert!(CountMinSketch::<i64>::with_error(0.01, 1.0).is_err());
+    /// ```
+    pub fn with_error(relative_error: f64, confidence: f64) -> Result<Self, Error> {
+        let num_buckets = Self::try_suggest_num_buckets(relative_error)?;
+        let num_hashes = Self::try_suggest_num_hashes(confidence)?;
+        Self::try_new(num_hashes, num_buckets)
     }
 
     /// Returns the number of hash functions used by the sketch.
@@ -108,33 +281,116 @@ impl<T: CountMinValue> CountMinSketch<T> {
         self.seed
     }
 
+    /// Returns the 16-bit hash of the seed, which is all that serialized images record of it.
+    pub fn seed_hash(&self) -> u16 {
+        self.seed_hash
+    }
+
+    /// Returns the seeds of the hash functions of the rows, one per row.
+    ///
+    /// They are derived deterministically from [`seed`](Self::seed), so sketches built with
+    /// different seeds use independent hash families, e.g. to keep tenants from being able to
+    /// craft collisions against each other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// let sketch = CountMinSketch::<u64>::with_seed(4, 64, 42);
+    /// let other = CountMinSketch::<u64>::with_seed(4, 64, 43);
+    /// assert_eq!(sketch.hash_seeds().len(), 4);
+    /// assert_ne!(sketch.hash_seeds(), other.hash_seeds());
+    /// ```
+    pub fn hash_seeds(&self) -> &[u64] {
+        &self.hash_seeds
+    }
+
+    /// Returns how the sketch combines weights that land in the same cell.
+    pub fn aggregation(&self) -> CountMinAggregation {
+        self.aggregation
+    }
+
     /// Returns the total weight inserted into the sketch.
+    ///
+    /// This is the sum of absolute weights regardless of the aggregation.
     pub fn total_weight(&self) -> T {
         self.total_weight
     }
 
     /// Returns the relative error (epsilon) implied by the number of buckets.
+    ///
+    /// For sum sketches, the estimate of any item overshoots its true weight by at most
+    /// `relative_error() * total_weight()` with probability at least `1 - e^-num_hashes`; this
+    /// is the a-posteriori error that [`upper_bound`](Self::upper_bound) and
+    /// [`lower_bound`](Self::lower_bound) account for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// let mut sketch = CountMinSketch::<u64>::new(5, 272);
+    /// for i in 0..10_000u64 {
+    ///     sketch.update(i % 1_000);
+    /// }
+    /// let max_error = sketch.relative_error() * sketch.total_weight() as f64;
+    /// assert!(max_error <= 100.0);
+    /// assert!(sketch.estimate(7u64) as f64 <= 10.0 + max_error);
+    /// ```
     pub fn relative_error(&self) -> f64 {
         std::f64::consts::E / self.num_buckets as f64
     }
 
     /// Returns true if the sketch has not seen any updates.
     pub fn is_empty(&self) -> bool {
-        self.total_weight == T::ZERO
+        match self.aggregation {
+            CountMinAggregation::Sum => self.total_weight == T::ZERO,
+            // Zero weights are meaningful observations for max/min, so look at
+            // the cells rather than the total weight.
+            aggregation => {
+                let identity = aggregation.identity::<T>();
+                self.counts.iter().all(|c| *c == identity)
+            }
+        }
+    }
+
+    /// Returns the number of bytes of memory the sketch occupies, including its counter array
+    /// and hash seeds.
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>()
+            + self.counts.capacity() * size_of::<T>()
+            + self.hash_seeds.capacity() * size_of::<u64>()
     }
 
     /// Suggests the number of buckets to achieve the given relative error.
     ///
+    /// This is `ceil(e / relative_error)`, the smallest number of buckets whose
+    /// [`relative_error`](Self::relative_error) does not exceed the target, but at least the
+    /// minimum of 3 buckets. The error is relative to the total weight, so the suggestion is the
+    /// same for integer and `f64` counters.
+    ///
     /// # Panics
     ///
     /// Panics if `relative_error` is negative.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// let buckets = CountMinSketch::<f64>::suggest_num_buckets(0.001);
+    /// assert_eq!(buckets, 2719);
+    /// let sketch = CountMinSketch::<f64>::new(5, buckets);
+    /// assert!(sketch.relative_error() <= 0.001);
+    /// assert_eq!(CountMinSketch::<f64>::suggest_num_buckets(1.0), 3);
+    /// ```
     pub fn suggest_num_buckets(relative_error: f64) -> u32 {
         assert!(relative_error >= 0.0, "relative_error must be at least 0");
-        (std::f64::consts::E / relative_error).ceil() as u32
+        ((std::f64::consts::E / relative_error).ceil() as u32).max(3)
     }
 
     /// Suggests the number of hashes to achieve the given confidence.
     ///
+    /// This is `ceil(ln(1 / (1 - confidence)))`, between 1 and 127.
+    ///
     /// # Panics
     ///
     /// Panics if `confidence` is not in `[0, 1]`.
@@ -147,7 +403,59 @@ impl<T: CountMinValue> CountMinSketch<T> {
             return 127;
         }
         let hashes = (1.0 / (1.0 - confidence)).ln().ceil();
-        hashes.min(127.0) as u8
+        hashes.clamp(1.0, 127.0) as u8
+    }
+
+    /// Suggests the number of buckets to achieve the given relative error.
+    ///
+    /// The fallible version of [`suggest_num_buckets`](Self::suggest_num_buckets), which rejects
+    /// targets that cannot be met instead of suggesting a degenerate table.
+    ///
+    /// # Errors
+    ///
+    /// If `relative_error` is not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// assert_eq!(CountMinSketch::<i64>::try_suggest_num_buckets(0.01).unwrap(), 272);
+    /// assert!(CountMinSketch::<i64>::try_suggest_num_buckets(0.0).is_err());
+    /// assert!(CountMinSketch::<i64>::try_suggest_num_buckets(f64::NAN).is_err());
+    /// ```
+    pub fn try_suggest_num_buckets(relative_error: f64) -> Result<u32, Error> {
+        if relative_error.is_nan() || relative_error <= 0.0 {
+            return Err(Error::invalid_argument(format!(
+                "relative_error must be greater than 0, got {relative_error}"
+            )));
+        }
+        Ok(Self::suggest_num_buckets(relative_error))
+    }
+
+    /// Suggests the number of hashes to achieve the given confidence.
+    ///
+    /// The fallible version of [`suggest_num_hashes`](Self::suggest_num_hashes), which rejects
+    /// the trivial confidences 0 and 1 instead of clamping them.
+    ///
+    /// # Errors
+    ///
+    /// If `confidence` is not in `(0, 1)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// assert_eq!(CountMinSketch::<i64>::try_suggest_num_hashes(0.99).unwrap(), 5);
+    /// assert!(CountMinSketch::<i64>::try_suggest_num_hashes(1.0).is_err());
+    /// assert!(CountMinSketch::<i64>::try_suggest_num_hashes(0.0).is_err());
+    /// ```
+    pub fn try_suggest_num_hashes(confidence: f64) -> Result<u8, Error> {
+        if !(confidence > 0.0 && confidence < 1.0) {
+            return Err(Error::invalid_argument(format!(
+                "confidence must be between 0 and 1 (exclusive), got {confidence}"
+            )));
+        }
+        Ok(Self::suggest_num_hashes(confidence))
     }
 
     /// Updates the sketch with a single occurrence of the item.
@@ -164,8 +472,33 @@ impl<T: CountMinValue> CountMinSketch<T> {
         self.update_with_weight(item, T::ONE);
     }
 
+    /// Updates the sketch with a single occurrence of an item hashed like datasketches-java
+    /// does, see [`IntoSketchInput`].
+    ///
+    /// Query the item with [`SketchInput`], so that it is hashed the same way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::common::SketchInput;
+    /// # use datasketches::countmin::CountMinSketch;
+    /// let mut sketch = CountMinSketch::<i64>::new(4, 128);
+    /// sketch.update_input(7i32);
+    /// assert!(sketch.estimate(SketchInput(7i64)) >= 1);
+    /// ```
+    pub fn update_input<I: IntoSketchInput>(&mut self, item: I) {
+        self.update(SketchInput(item));
+    }
+
     /// Updates the sketch with the given item and weight.
     ///
+    /// With [`CountMinAggregation::Sum`] a zero weight is a no-op; with `Max`
+    /// and `Min` it is recorded like any other observed weight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is NaN.
+    ///
     /// # Examples
     ///
     /// ```
@@ -175,21 +508,26 @@ impl<T: CountMinValue> CountMinSketch<T> {
     /// assert!(sketch.estimate("banana") >= 3);
     /// ```
     pub fn update_with_weight<I: Hash>(&mut self, item: I, weight: T) {
-        if weight == T::ZERO {
+        assert!(!weight.is_nan(), "weight must not be NaN");
+        if weight == T::ZERO && self.aggregation == CountMinAggregation::Sum {
             return;
         }
         let abs_weight = weight.abs();
         self.total_weight = self.total_weight.add(abs_weight);
         let num_buckets = self.num_buckets as usize;
         for (row, seed) in self.hash_seeds.iter().enumerate() {
-            let bucket = self.bucket_index(&item, *seed);
+            let bucket = bucket_index(&item, *seed, self.num_buckets);
             let index = row * num_buckets + bucket;
-            self.counts[index] = self.counts[index].add(weight);
+            self.counts[index] = self.aggregation.combine(self.counts[index], weight);
         }
     }
 
     /// Returns the estimated frequency of the given item.
     ///
+    /// With [`CountMinAggregation::Max`] this is instead an upper bound on the
+    /// largest weight observed for the item, and with
+    /// [`CountMinAggregation::Min`] a lower bound on the smallest one.
+    ///
     /// # Examples
     ///
     /// ```
@@ -200,35 +538,137 @@ impl<T: CountMinValue> CountMinSketch<T> {
     /// ```
     pub fn estimate<I: Hash>(&self, item: I) -> T {
         let num_buckets = self.num_buckets as usize;
-        let mut min = T::MAX;
-        for (row, seed) in self.hash_seeds.iter().enumerate() {
-            let bucket = self.bucket_index(&item, *seed);
-            let index = row * num_buckets + bucket;
-            let value = self.counts[index];
-            if value < min {
-                min = value;
-            }
-        }
-        min
+        let cells = self.hash_seeds.iter().enumerate().map(|(row, seed)| {
+            let bucket = bucket_index(&item, *seed, self.num_buckets);
+            self.counts[row * num_buckets + bucket]
+        });
+        self.aggregation.estimate(cells)
     }
 
-    /// Returns the lower bound on the true frequency of the given item.
+    /// Returns the lower bound on the true value of the given item.
+    ///
+    /// For [`CountMinAggregation::Sum`] with non-negative weights this is the estimate itself,
+    /// as in datasketches-cpp, since collisions only ever add to a cell. For
+    /// [`CountMinAggregation::Max`] no bound tighter than `T::MIN` exists.
     pub fn lower_bound<I: Hash>(&self, item: I) -> T {
-        self.estimate(item)
+        self.aggregation.lower_bound(|| self.estimate(item))
     }
 
-    /// Returns the upper bound on the true frequency of the given item.
+    /// Returns the upper bound on the true value of the given item.
+    ///
+    /// For [`CountMinAggregation::Sum`] this is `estimate + relative_error * total_weight`, as in
+    /// datasketches-cpp, and holds with probability at least `1 - e^-num_hashes`; it saturates
+    /// at `T::MAX` instead of overflowing. For [`CountMinAggregation::Min`] no bound tighter
+    /// than `T::MAX` exists.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// let mut sketch = CountMinSketch::<u64>::new(3, 272);
+    /// sketch.update_with_weight("apple", 100);
+    /// sketch.update_with_weight("pear", 900);
+    /// // relative_error = e / 272 ~= 0.01, so the range is at most 10 wide
+    /// let (lower, upper) = (sketch.lower_bound("apple"), sketch.upper_bound("apple"));
+    /// assert!(lower <= 100 && 100 <= upper);
+    /// assert_eq!(upper - lower, 9);
+    /// ```
     pub fn upper_bound<I: Hash>(&self, item: I) -> T {
-        let estimate = self.estimate(item);
-        let error = T::from_f64(self.relative_error() * self.total_weight.to_f64());
-        estimate.add(error)
+        self.aggregation.upper_bound(
+            || self.estimate(item),
+            || T::from_f64(self.relative_error() * self.total_weight.to_f64()),
+        )
+    }
+
+    /// Returns the estimated change in the frequency of the given item since `previous`, a
+    /// snapshot of the same stream taken in an earlier epoch.
+    ///
+    /// The difference of two sum sketches is the sketch of the difference of their streams, but
+    /// its cells may be negative, so collisions no longer push every cell in the same direction.
+    /// The estimate is therefore the median of the cell differences of the rows, as in the
+    /// Count-Median sketch, rather than their minimum. It errs in either direction, by at most a
+    /// small multiple of the total change divided by [`num_buckets`](Self::num_buckets) for most
+    /// items.
+    ///
+    /// See [`CountMinChangeDetector`](crate::countmin::CountMinChangeDetector) to find the items
+    /// whose frequency changed the most.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sketches are not [compatible](Self::is_compatible), or if they do not use
+    /// [`CountMinAggregation::Sum`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// let mut previous = CountMinSketch::<u64>::new(5, 256);
+    /// previous.update_with_weight("10.0.0.1", 100);
+    /// previous.update_with_weight("10.0.0.2", 100);
+    ///
+    /// let mut current = previous.clone();
+    /// current.update_with_weight("10.0.0.1", 900);
+    /// assert_eq!(current.estimate_delta(&previous, "10.0.0.1"), 900.0);
+    /// assert_eq!(current.estimate_delta(&previous, "10.0.0.2"), 0.0);
+    /// ```
+    pub fn estimate_delta<I: Hash>(&self, previous: &Self, item: I) -> f64 {
+        assert!(
+            self.is_compatible(previous),
+            "cannot compare CountMin sketches with different configurations"
+        );
+        assert_eq!(
+            self.aggregation,
+            CountMinAggregation::Sum,
+            "deltas are only defined for sum sketches"
+        );
+        let num_buckets = self.num_buckets as usize;
+        let mut deltas: Vec<f64> = self
+            .hash_seeds
+            .iter()
+            .enumerate()
+            .map(|(row, seed)| {
+                let cell = row * num_buckets + bucket_index(&item, *seed, self.num_buckets);
+                self.counts[cell].to_f64() - previous.counts[cell].to_f64()
+            })
+            .collect();
+        deltas.sort_by(f64::total_cmp);
+        let mid = deltas.len() / 2;
+        if deltas.len() % 2 == 1 {
+            deltas[mid]
+        } else {
+            (deltas[mid - 1] + deltas[mid]) / 2.0
+        }
+    }
+
+    /// Checks if two sketches are compatible for merging.
+    ///
+    /// Sketches are compatible if they have the same:
+    /// * Number of hash functions
+    /// * Number of buckets
+    /// * Seed
+    /// * Aggregation
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// let left = CountMinSketch::<i64>::new(4, 128);
+    /// assert!(left.is_compatible(&CountMinSketch::new(4, 128)));
+    /// assert!(!left.is_compatible(&CountMinSketch::new(4, 256)));
+    /// assert!(!left.is_compatible(&CountMinSketch::with_seed(4, 128, 7)));
+    /// ```
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.num_hashes == other.num_hashes
+            && self.num_buckets == other.num_buckets
+            && self.seed == other.seed
+            && self.aggregation == other.aggregation
     }
 
     /// Merges another sketch into this one.
     ///
     /// # Panics
     ///
-    /// Panics if the sketches have incompatible configurations.
+    /// Panics if the sketches are not [compatible](Self::is_compatible).
     ///
     /// # Examples
     ///
@@ -249,17 +689,24 @@ impl<T: CountMinValue> CountMinSketch<T> {
         }
         assert_eq!(self.num_hashes, other.num_hashes);
         assert_eq!(self.num_buckets, other.num_buckets);
-        assert_eq!(self.seed, other.seed);
+        assert_eq!(
+            self.seed, other.seed,
+            "cannot merge CountMin sketches built with different seeds"
+        );
+        assert_eq!(self.aggregation, other.aggregation);
         assert_eq!(self.counts.len(), other.counts.len());
         let counts_len = self.counts.len();
         for i in 0..counts_len {
-            self.counts[i] = self.counts[i].add(other.counts[i]);
+            self.counts[i] = self.aggregation.combine(self.counts[i], other.counts[i]);
         }
         self.total_weight = self.total_weight.add(other.total_weight);
     }
 
     /// Serializes this sketch into the DataSketches Count-Min format.
     ///
+    /// The aggregation is stored in a byte that other DataSketches
+    /// implementations leave unused, so only sum sketches are readable by them.
+    ///
     /// # Examples
     ///
     /// ```
@@ -271,6 +718,13 @@ impl<T: CountMinValue> CountMinSketch<T> {
     /// assert!(decoded.estimate("apple") >= 1);
     /// ```
     pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = SketchBytes::with_capacity(self.serialized_size_bytes());
+        self.write_to(&mut bytes);
+        bytes.into_bytes()
+    }
+
+    /// Returns the number of bytes [`CountMinSketch::serialize`] produces.
+    pub fn serialized_size_bytes(&self) -> usize {
         let header_size = PREAMBLE_LONGS_SHORT as usize * LONG_SIZE_BYTES;
         let value_size = LONG_SIZE_BYTES;
         let payload_size = if self.is_empty() {
@@ -278,13 +732,31 @@ impl<T: CountMinValue> CountMinSketch<T> {
         } else {
             value_size + (self.counts.len() * value_size)
         };
-        let mut bytes = SketchBytes::with_capacity(header_size + payload_size);
+        header_size + payload_size
+    }
+
+    /// Serializes this sketch into the front of `buf` without allocating, returning the number of
+    /// bytes written.
+    ///
+    /// The bytes are the same as those returned by [`CountMinSketch::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `buf` is shorter than [`CountMinSketch::serialized_size_bytes`].
+    pub fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        serialize_into_slice(buf, self.serialized_size_bytes(), |bytes| {
+            self.write_to(bytes)
+        })
+    }
 
+    fn write_to(&self, bytes: &mut SketchBytes<'_>) {
         bytes.write_u8(PREAMBLE_LONGS_SHORT);
         bytes.write_u8(SERIAL_VERSION);
         bytes.write_u8(Family::COUNTMIN.id);
         bytes.write_u8(if self.is_empty() { FLAGS_IS_EMPTY } else { 0 });
-        bytes.write_u32_le(0); // unused
+        bytes.write_u8(self.aggregation.id());
+        bytes.write_u8(0); // unused
+        bytes.write_u16_le(0); // unused
 
         bytes.write_u32_le(self.num_buckets);
         bytes.write_u8(self.num_hashes);
@@ -293,18 +765,46 @@ impl<T: CountMinValue> CountMinSketch<T> {
         bytes.write_u8(0);
 
         if self.is_empty() {
-            return bytes.into_bytes();
+            return;
         }
 
         bytes.write(&self.total_weight.to_bytes());
         for count in &self.counts {
             bytes.write(&count.to_bytes());
         }
-        bytes.into_bytes()
+    }
+
+    /// Serializes this sketch into the given writer.
+    ///
+    /// The output is identical to [`serialize`](Self::serialize), but is written in chunks of a
+    /// few kilobytes, so large sketches can be written to files, compression streams or sockets
+    /// without building the whole byte vector in memory first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the writer fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// # let mut sketch = CountMinSketch::<i64>::new(4, 128);
+    /// # sketch.update("apple");
+    /// let mut buf = Vec::new();
+    /// sketch.serialize_into_writer(&mut buf).unwrap();
+    /// assert_eq!(buf, sketch.serialize());
+    /// ```
+    pub fn serialize_into_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_into_writer(writer, |bytes| self.write_to(bytes))
     }
 
     /// Deserializes a sketch from bytes using the default seed.
     ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid sketch, or if they hold an empty sketch
+    /// with more than 2^24 cells.
+    ///
     /// # Examples
     ///
     /// ```
@@ -332,63 +832,76 @@ impl<T: CountMinValue> CountMinSketch<T> {
     /// assert!(decoded.estimate("apple") >= 1);
     /// ```
     pub fn deserialize_with_seed(bytes: &[u8], seed: u64) -> Result<Self, Error> {
-        fn read_value<T: CountMinValue>(
-            cursor: &mut SketchSlice<'_>,
-            tag: &'static str,
-        ) -> Result<T, Error> {
-            let mut bs = [0u8; 8];
-            cursor.read_exact(&mut bs).map_err(insufficient_data(tag))?;
-            T::try_from_bytes(bs)
-        }
-
-        let mut cursor = SketchSlice::new(bytes);
-        let preamble_longs = cursor
-            .read_u8()
-            .map_err(insufficient_data("preamble_longs"))?;
-        let serial_version = cursor
-            .read_u8()
-            .map_err(insufficient_data("serial_version"))?;
-        let family_id = cursor.read_u8().map_err(insufficient_data("family_id"))?;
-        let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
-        cursor
-            .read_u32_le()
-            .map_err(insufficient_data("<unused>"))?;
-
-        Family::COUNTMIN.validate_id(family_id)?;
-        ensure_serial_version_is(SERIAL_VERSION, serial_version)?;
-        ensure_preamble_longs_in(&[PREAMBLE_LONGS_SHORT], preamble_longs)?;
-
-        let num_buckets = cursor
-            .read_u32_le()
-            .map_err(insufficient_data("num_buckets"))?;
-        let num_hashes = cursor.read_u8().map_err(insufficient_data("num_hashes"))?;
-        let seed_hash = cursor
-            .read_u16_le()
-            .map_err(insufficient_data("seed_hash"))?;
-        cursor.read_u8().map_err(insufficient_data("unused8"))?;
-
-        let expected_seed_hash = compute_seed_hash(seed);
-        if seed_hash != expected_seed_hash {
+        let wrapper = CountMinWrapper::<T>::with_seed(bytes, seed)?;
+        let entries = wrapper.num_hashes() as usize * wrapper.num_buckets() as usize;
+        if wrapper.is_empty() && entries > MAX_EMPTY_IMAGE_ENTRIES {
             return Err(Error::deserial(format!(
-                "incompatible seed hash: expected {expected_seed_hash}, got {seed_hash}",
+                "empty sketch must have at most {MAX_EMPTY_IMAGE_ENTRIES} cells, got {entries}"
             )));
         }
-
-        let entries = entries_for_config_checked(num_hashes, num_buckets)?;
-        let mut sketch = Self::make(num_hashes, num_buckets, seed, entries);
-        if (flags & FLAGS_IS_EMPTY) != 0 {
-            return Ok(sketch);
-        }
-
-        sketch.total_weight = read_value(&mut cursor, "total_weight")?;
-        for count in &mut sketch.counts {
-            *count = read_value(&mut cursor, "counts")?;
+        let mut sketch = Self::make(
+            wrapper.num_hashes(),
+            wrapper.num_buckets(),
+            seed,
+            wrapper.aggregation(),
+            entries,
+        );
+        sketch.total_weight = wrapper.total_weight();
+        for (count, cell) in sketch.counts.iter_mut().zip(wrapper.cells()) {
+            *count = cell;
         }
         Ok(sketch)
     }
 
-    fn make(num_hashes: u8, num_buckets: u32, seed: u64, entries: usize) -> Self {
-        let counts = vec![T::ZERO; entries];
+    /// Deserializes a sketch from the given reader, using the default seed.
+    ///
+    /// See [`deserialize_from_reader_with_seed`](Self::deserialize_from_reader_with_seed).
+    pub fn deserialize_from_reader<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        Self::deserialize_from_reader_with_seed(reader, DEFAULT_UPDATE_SEED)
+    }
+
+    /// Deserializes a sketch from the given reader using the provided seed.
+    ///
+    /// This accepts the same formats as [`deserialize_with_seed`](Self::deserialize_with_seed),
+    /// and reads no further than the end of the sketch, so the reader may continue with other
+    /// data. Wrap unbuffered readers in a [`BufReader`](std::io::BufReader).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the reader fails or the data is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::countmin::CountMinSketch;
+    /// # let mut sketch = CountMinSketch::<i64>::with_seed(4, 64, 7);
+    /// # sketch.update("apple");
+    /// # let mut buf = Vec::new();
+    /// # sketch.serialize_into_writer(&mut buf).unwrap();
+    /// let mut reader = buf.as_slice();
+    /// let decoded = CountMinSketch::<i64>::deserialize_from_reader_with_seed(&mut reader, 7).unwrap();
+    /// assert!(decoded.estimate("apple") >= 1);
+    /// ```
+    pub fn deserialize_from_reader_with_seed<R: Read>(
+        reader: &mut R,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        let bytes = read_image(
+            reader,
+            |_| PREAMBLE_LONGS_SHORT as usize * LONG_SIZE_BYTES,
+            serialization::image_len,
+        )?;
+        Self::deserialize_with_seed(&bytes, seed)
+    }
+
+    fn make(
+        num_hashes: u8,
+        num_buckets: u32,
+        seed: u64,
+        aggregation: CountMinAggregation,
+        entries: usize,
+    ) -> Self {
+        let counts = vec![aggregation.identity(); entries];
         let seed_hash = compute_seed_hash(seed);
         let hash_seeds = make_hash_seeds(seed, num_hashes);
         CountMinSketch {
@@ -396,24 +909,19 @@ impl<T: CountMinValue> CountMinSketch<T> {
             num_buckets,
             seed,
             seed_hash,
+            aggregation,
             total_weight: T::ZERO,
             counts,
             hash_seeds,
         }
     }
-
-    fn bucket_index<I: Hash>(&self, item: &I, seed: u64) -> usize {
-        let mut hasher = MurmurHash3X64128::with_seed(seed);
-        item.hash(&mut hasher);
-        let (h1, _) = hasher.finish128();
-        (h1 % self.num_buckets as u64) as usize
-    }
 }
 
 impl<T: UnsignedCountMinValue> CountMinSketch<T> {
     /// Divides every counter by two, truncating toward zero.
     ///
     /// Useful for exponential decay where counts represent recent activity.
+    /// Cells that have not absorbed any weight are left untouched.
     ///
     /// # Examples
     ///
@@ -425,7 +933,8 @@ impl<T: UnsignedCountMinValue> CountMinSketch<T> {
     /// assert!(sketch.estimate("apple") >= 1);
     /// ```
     pub fn halve(&mut self) {
-        for c in &mut self.counts {
+        let identity = self.aggregation.identity::<T>();
+        for c in self.counts.iter_mut().filter(|c| **c != identity) {
             *c = c.halve()
         }
         self.total_weight = self.total_weight.halve();
@@ -451,7 +960,8 @@ impl<T: UnsignedCountMinValue> CountMinSketch<T> {
     /// Panics if `decay` is not finite or is outside `(0, 1]`.
     pub fn decay(&mut self, decay: f64) {
         assert!(decay > 0.0 && decay <= 1.0, "decay must be within (0, 1]");
-        for c in &mut self.counts {
+        let identity = self.aggregation.identity::<T>();
+        for c in self.counts.iter_mut().filter(|c| **c != identity) {
             *c = c.decay(decay)
         }
         self.total_weight = self.total_weight.decay(decay);
@@ -472,25 +982,25 @@ fn entries_for_config(num_hashes: u8, num_buckets: u32) -> usize {
     entries
 }
 
-fn entries_for_config_checked(num_hashes: u8, num_buckets: u32) -> Result<usize, Error> {
+pub(super) fn entries_for_config_checked(num_hashes: u8, num_buckets: u32) -> Result<usize, Error> {
     if num_hashes == 0 {
-        return Err(Error::deserial("num_hashes must be at least 1"));
+        return Err(Error::bad_preamble("num_hashes must be at least 1"));
     }
     if num_buckets < 3 {
-        return Err(Error::deserial("num_buckets must be at least 3"));
+        return Err(Error::bad_preamble("num_buckets must be at least 3"));
     }
     let entries = (num_hashes as usize)
         .checked_mul(num_buckets as usize)
-        .ok_or_else(|| Error::deserial("num_hashes * num_buckets overflows usize"))?;
+        .ok_or_else(|| Error::bad_preamble("num_hashes * num_buckets overflows usize"))?;
     if entries >= MAX_TABLE_ENTRIES {
-        return Err(Error::deserial(format!(
+        return Err(Error::bad_preamble(format!(
             "num_hashes * num_buckets must be < {MAX_TABLE_ENTRIES}",
         )));
     }
     Ok(entries)
 }
 
-fn make_hash_seeds(seed: u64, num_hashes: u8) -> Vec<u64> {
+pub(super) fn make_hash_seeds(seed: u64, num_hashes: u8) -> Vec<u64> {
     let mut seeds = Vec::with_capacity(num_hashes as usize);
     for i in 0..num_hashes {
         // Derive per-row hash seeds deterministically from the sketch seed.
@@ -501,3 +1011,69 @@ fn make_hash_seeds(seed: u64, num_hashes: u8) -> Vec<u64> {
     }
     seeds
 }
+
+pub(super) fn bucket_index<I: Hash>(item: &I, seed: u64, num_buckets: u32) -> usize {
+    let mut hasher = MurmurHash3X64128::with_seed(seed);
+    item.hash(&mut hasher);
+    let (h1, _) = hasher.finish128();
+    (h1 % num_buckets as u64) as usize
+}
+
+impl<T: CountMinValue + fmt::Display> fmt::Display for CountMinSketch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Summary::new(f, "COUNTMIN SKETCH")
+            .field("num hashes", self.num_hashes())
+            .field("num buckets", self.num_buckets())
+            .field("aggregation", format_args!("{:?}", self.aggregation()))
+            .field("empty", self.is_empty())
+            .field("total weight", self.total_weight())
+            .field("relative error", self.relative_error())
+            .finish()
+    }
+}
+
+impl<T: CountMinValue, I: Hash> Sketch<I> for CountMinSketch<T> {
+    fn update(&mut self, item: I) {
+        CountMinSketch::update(self, item);
+    }
+
+    fn is_empty(&self) -> bool {
+        CountMinSketch::is_empty(self)
+    }
+}
+
+/// Unlike [`CountMinSketch::merge`], sketches with different configurations are reported as an
+/// error rather than a panic.
+impl<T: CountMinValue> MergeableSketch for CountMinSketch<T> {
+    type Error = Error;
+
+    fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        if !self.is_compatible(other) {
+            return Err(Error::invalid_argument(
+                "cannot merge CountMin sketches with different configurations",
+            ));
+        }
+        CountMinSketch::merge(self, other);
+        Ok(())
+    }
+}
+
+impl<T: CountMinValue> SerializableSketch for CountMinSketch<T> {
+    type Error = Error;
+
+    fn serialize(&self) -> Vec<u8> {
+        CountMinSketch::serialize(self)
+    }
+
+    fn serialized_size_bytes(&self) -> usize {
+        CountMinSketch::serialized_size_bytes(self)
+    }
+
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        CountMinSketch::serialize_into(self, buf)
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        CountMinSketch::deserialize(bytes)
+    }
+}
@@ -23,7 +23,11 @@ mod private {
 }
 
 /// Value type supported in a Count-Min sketch.
-pub trait CountMinValue: private::Sealed + Copy + Ord {
+///
+/// Implemented for the signed and unsigned integer types up to 64 bits, and for `f64`. The
+/// table holds one value per cell, so narrower types shrink the sketch in memory; serialized
+/// images always store 8 bytes per cell.
+pub trait CountMinValue: private::Sealed + Copy + PartialOrd {
     /// Zero value for counters and weights.
     const ZERO: Self;
 
@@ -33,16 +37,31 @@ pub trait CountMinValue: private::Sealed + Copy + Ord {
     /// Maximum representable value for initializing minima.
     const MAX: Self;
 
+    /// Minimum representable value for initializing maxima.
+    const MIN: Self;
+
     /// Performs the + operation.
     fn add(self, other: Self) -> Self;
 
+    /// Performs the + operation, saturating at the numeric bounds.
+    fn saturating_add(self, other: Self) -> Self;
+
+    /// Returns the larger of `self` and `other`.
+    fn max_of(self, other: Self) -> Self;
+
+    /// Returns the smaller of `self` and `other`.
+    fn min_of(self, other: Self) -> Self;
+
     /// Computes the absolute value of `self`.
     fn abs(self) -> Self;
 
+    /// Returns true if the value is NaN; always false for integer types.
+    fn is_nan(self) -> bool;
+
     /// Converts into `f64`.
     fn to_f64(self) -> f64;
 
-    /// Converts from `f64` by truncating toward zero.
+    /// Converts from `f64`, truncating toward zero for integer types.
     fn from_f64(value: f64) -> Self;
 
     /// Returns the raw transmutation in little-endian 8 bytes.
@@ -69,12 +88,33 @@ macro_rules! impl_signed {
             const ZERO: Self = 0;
             const ONE: Self = 1;
             const MAX: Self = $max;
+            const MIN: Self = $min;
 
             #[inline(always)]
             fn add(self, other: Self) -> Self {
                 self + other
             }
 
+            #[inline(always)]
+            fn saturating_add(self, other: Self) -> Self {
+                <$name>::saturating_add(self, other)
+            }
+
+            #[inline(always)]
+            fn max_of(self, other: Self) -> Self {
+                Ord::max(self, other)
+            }
+
+            #[inline(always)]
+            fn min_of(self, other: Self) -> Self {
+                Ord::min(self, other)
+            }
+
+            #[inline(always)]
+            fn is_nan(self) -> bool {
+                false
+            }
+
             #[inline(always)]
             fn abs(self) -> Self {
                 if self >= 0 { self } else { -self }
@@ -125,12 +165,33 @@ macro_rules! impl_unsigned {
             const ZERO: Self = 0;
             const ONE: Self = 1;
             const MAX: Self = $max;
+            const MIN: Self = 0;
 
             #[inline(always)]
             fn add(self, other: Self) -> Self {
                 self + other
             }
 
+            #[inline(always)]
+            fn saturating_add(self, other: Self) -> Self {
+                <$name>::saturating_add(self, other)
+            }
+
+            #[inline(always)]
+            fn max_of(self, other: Self) -> Self {
+                Ord::max(self, other)
+            }
+
+            #[inline(always)]
+            fn min_of(self, other: Self) -> Self {
+                Ord::min(self, other)
+            }
+
+            #[inline(always)]
+            fn is_nan(self) -> bool {
+                false
+            }
+
             #[inline(always)]
             fn abs(self) -> Self {
                 self
@@ -185,3 +246,66 @@ impl_unsigned!(u8, u8::MAX);
 impl_unsigned!(u16, u16::MAX);
 impl_unsigned!(u32, u32::MAX);
 impl_unsigned!(u64, u64::MAX);
+
+impl private::Sealed for f64 {}
+
+impl CountMinValue for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+    const MAX: Self = f64::MAX;
+    const MIN: Self = f64::MIN;
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        self + other
+    }
+
+    #[inline(always)]
+    fn saturating_add(self, other: Self) -> Self {
+        (self + other).clamp(f64::MIN, f64::MAX)
+    }
+
+    #[inline(always)]
+    fn max_of(self, other: Self) -> Self {
+        f64::max(self, other)
+    }
+
+    #[inline(always)]
+    fn min_of(self, other: Self) -> Self {
+        f64::min(self, other)
+    }
+
+    #[inline(always)]
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    #[inline(always)]
+    fn is_nan(self) -> bool {
+        f64::is_nan(self)
+    }
+
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    #[inline(always)]
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    #[inline(always)]
+    fn to_bytes(self) -> [u8; 8] {
+        self.to_le_bytes()
+    }
+
+    #[inline(always)]
+    fn try_from_bytes(bytes: [u8; 8]) -> Result<Self, Error> {
+        let value = f64::from_le_bytes(bytes);
+        if value.is_nan() {
+            return Err(Error::deserial("value NaN out of range for f64"));
+        }
+        Ok(value)
+    }
+}
@@ -0,0 +1,243 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::fmt;
+use std::hash::Hash;
+
+use crate::codec::SketchSlice;
+use crate::codec::assert::ensure_preamble_longs_in;
+use crate::codec::assert::ensure_serial_version_is;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
+use crate::countmin::CountMinAggregation;
+use crate::countmin::CountMinValue;
+use crate::countmin::serialization::FLAGS_IS_EMPTY;
+use crate::countmin::serialization::LONG_SIZE_BYTES;
+use crate::countmin::serialization::PREAMBLE_LONGS_SHORT;
+use crate::countmin::serialization::SERIAL_VERSION;
+use crate::countmin::sketch::bucket_index;
+use crate::countmin::sketch::entries_for_config_checked;
+use crate::countmin::sketch::make_hash_seeds;
+use crate::error::Error;
+use crate::hash::DEFAULT_UPDATE_SEED;
+use crate::hash::try_compute_seed_hash;
+
+/// A read-only view of a serialized image of a [`CountMinSketch`].
+///
+/// The wrapper answers queries directly from the borrowed bytes without copying the counter
+/// matrix, so it can sit on top of a memory-mapped file. Construction validates the preamble and
+/// every counter in a single pass; queries then only touch `num_hashes` counters.
+///
+/// [`CountMinSketch`]: crate::countmin::CountMinSketch
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::countmin::CountMinSketch;
+/// # use datasketches::countmin::CountMinWrapper;
+/// let mut sketch = CountMinSketch::<u64>::new(4, 128);
+/// sketch.update_with_weight("apple", 3);
+/// let bytes = sketch.serialize();
+///
+/// let wrapper = CountMinWrapper::<u64>::new(&bytes).unwrap();
+/// assert_eq!(wrapper.estimate("apple"), sketch.estimate("apple"));
+/// ```
+#[derive(Clone)]
+pub struct CountMinWrapper<'a, T: CountMinValue> {
+    num_hashes: u8,
+    num_buckets: u32,
+    aggregation: CountMinAggregation,
+    total_weight: T,
+    /// Little-endian counters, row by row; empty if the sketch is empty.
+    counts: &'a [u8],
+    hash_seeds: Vec<u64>,
+}
+
+impl<'a, T: CountMinValue> CountMinWrapper<'a, T> {
+    /// Creates a new `CountMinWrapper` from the given byte slice using the default seed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid serialized Count-Min sketch of `T`.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, Error> {
+        Self::with_seed(bytes, DEFAULT_UPDATE_SEED)
+    }
+
+    /// Creates a new `CountMinWrapper` from the given byte slice using the provided seed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid serialized Count-Min sketch of `T`, or if
+    /// the sketch was built with a seed whose hash differs from that of `seed`.
+    pub fn with_seed(bytes: &'a [u8], seed: u64) -> Result<Self, Error> {
+        let mut cursor = SketchSlice::new(bytes);
+        let preamble_longs = cursor
+            .read_u8()
+            .map_err(insufficient_data("preamble_longs"))?;
+        let serial_version = cursor
+            .read_u8()
+            .map_err(insufficient_data("serial_version"))?;
+        let family_id = cursor.read_u8().map_err(insufficient_data("family_id"))?;
+        let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+        let aggregation_id = cursor.read_u8().map_err(insufficient_data("aggregation"))?;
+        cursor.read_u8().map_err(insufficient_data("<unused>"))?;
+        cursor
+            .read_u16_le()
+            .map_err(insufficient_data("<unused>"))?;
+
+        Family::COUNTMIN.validate_id(family_id)?;
+        ensure_serial_version_is(SERIAL_VERSION, serial_version)?;
+        ensure_preamble_longs_in(&[PREAMBLE_LONGS_SHORT], preamble_longs)?;
+        let aggregation = CountMinAggregation::from_id(aggregation_id)?;
+
+        let num_buckets = cursor
+            .read_u32_le()
+            .map_err(insufficient_data("num_buckets"))?;
+        let num_hashes = cursor.read_u8().map_err(insufficient_data("num_hashes"))?;
+        let seed_hash = cursor
+            .read_u16_le()
+            .map_err(insufficient_data("seed_hash"))?;
+        cursor.read_u8().map_err(insufficient_data("unused8"))?;
+
+        let expected_seed_hash = try_compute_seed_hash(seed)?;
+        if seed_hash != expected_seed_hash {
+            return Err(Error::seed_hash_mismatch(expected_seed_hash, seed_hash));
+        }
+
+        let entries = entries_for_config_checked(num_hashes, num_buckets)?;
+        let hash_seeds = make_hash_seeds(seed, num_hashes);
+        if (flags & FLAGS_IS_EMPTY) != 0 {
+            return Ok(CountMinWrapper {
+                num_hashes,
+                num_buckets,
+                aggregation,
+                total_weight: T::ZERO,
+                counts: &[],
+                hash_seeds,
+            });
+        }
+
+        let mut total_weight = [0u8; LONG_SIZE_BYTES];
+        cursor
+            .read_exact(&mut total_weight)
+            .map_err(insufficient_data("total_weight"))?;
+        let total_weight = T::try_from_bytes(total_weight)?;
+
+        let start = bytes.len() - cursor.remaining();
+        let counts = bytes
+            .get(start..start + entries * LONG_SIZE_BYTES)
+            .ok_or_else(|| Error::insufficient_data("counts"))?;
+        for cell in counts.chunks_exact(LONG_SIZE_BYTES) {
+            T::try_from_bytes(cell.try_into().expect("chunk has 8 bytes"))?;
+        }
+
+        Ok(CountMinWrapper {
+            num_hashes,
+            num_buckets,
+            aggregation,
+            total_weight,
+            counts,
+            hash_seeds,
+        })
+    }
+
+    /// Returns the number of hash functions.
+    pub fn num_hashes(&self) -> u8 {
+        self.num_hashes
+    }
+
+    /// Returns the number of buckets per hash function.
+    pub fn num_buckets(&self) -> u32 {
+        self.num_buckets
+    }
+
+    /// Returns how cells combine weights.
+    pub fn aggregation(&self) -> CountMinAggregation {
+        self.aggregation
+    }
+
+    /// Returns the total weight of the wrapped sketch.
+    pub fn total_weight(&self) -> T {
+        self.total_weight
+    }
+
+    /// Returns the relative error (epsilon) of the wrapped sketch.
+    pub fn relative_error(&self) -> f64 {
+        std::f64::consts::E / self.num_buckets as f64
+    }
+
+    /// Returns true if the wrapped sketch has not seen any updates.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns the estimated frequency of the given item.
+    ///
+    /// See [`CountMinSketch::estimate`](crate::countmin::CountMinSketch::estimate).
+    pub fn estimate<I: Hash>(&self, item: I) -> T {
+        if self.is_empty() {
+            return self.aggregation.identity();
+        }
+        let num_buckets = self.num_buckets as usize;
+        let cells = self.hash_seeds.iter().enumerate().map(|(row, seed)| {
+            let bucket = bucket_index(&item, *seed, self.num_buckets);
+            self.cell(row * num_buckets + bucket)
+        });
+        self.aggregation.estimate(cells)
+    }
+
+    /// Returns the lower bound on the true value of the given item.
+    ///
+    /// See [`CountMinSketch::lower_bound`](crate::countmin::CountMinSketch::lower_bound).
+    pub fn lower_bound<I: Hash>(&self, item: I) -> T {
+        self.aggregation.lower_bound(|| self.estimate(item))
+    }
+
+    /// Returns the upper bound on the true value of the given item.
+    ///
+    /// See [`CountMinSketch::upper_bound`](crate::countmin::CountMinSketch::upper_bound).
+    pub fn upper_bound<I: Hash>(&self, item: I) -> T {
+        self.aggregation.upper_bound(
+            || self.estimate(item),
+            || T::from_f64(self.relative_error() * self.total_weight.to_f64()),
+        )
+    }
+
+    /// Returns an iterator over all counters, row by row; empty if the sketch is empty.
+    pub(super) fn cells(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.counts.len() / LONG_SIZE_BYTES).map(|index| self.cell(index))
+    }
+
+    fn cell(&self, index: usize) -> T {
+        let offset = index * LONG_SIZE_BYTES;
+        let bytes = self.counts[offset..offset + LONG_SIZE_BYTES]
+            .try_into()
+            .expect("slice has 8 bytes");
+        T::try_from_bytes(bytes).expect("counters are validated on construction")
+    }
+}
+
+impl<T: CountMinValue + fmt::Debug> fmt::Debug for CountMinWrapper<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CountMinWrapper")
+            .field("num_hashes", &self.num_hashes)
+            .field("num_buckets", &self.num_buckets)
+            .field("aggregation", &self.aggregation)
+            .field("total_weight", &self.total_weight)
+            .finish_non_exhaustive()
+    }
+}
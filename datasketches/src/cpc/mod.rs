@@ -45,6 +45,7 @@ mod sketch;
 mod union;
 mod wrapper;
 
+pub(crate) use self::serialization::inspect;
 pub use self::sketch::CpcSketch;
 pub use self::union::CpcUnion;
 pub use self::wrapper::CpcWrapper;
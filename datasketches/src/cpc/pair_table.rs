@@ -75,6 +75,10 @@ impl PairTable {
         &self.slots
     }
 
+    pub fn heap_size_bytes(&self) -> usize {
+        self.slots.capacity() * size_of::<u32>()
+    }
+
     pub fn clear(&mut self) {
         self.slots.fill(u32::MAX);
         self.num_items = 0;
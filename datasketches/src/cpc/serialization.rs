@@ -15,6 +15,13 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use crate::codec::SketchSlice;
+use crate::codec::assert::ensure_serial_version_is;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
+use crate::error::Error;
+use crate::inspect::SketchInfo;
+
 pub(super) const SERIAL_VERSION: u8 = 1;
 pub(super) const FLAG_COMPRESSED: u8 = 1;
 pub(super) const FLAG_HAS_HIP: u8 = 2;
@@ -46,3 +53,35 @@ pub(super) fn make_preamble_ints(
     }
     preamble_ints
 }
+
+/// Reads the preamble of an image for [`inspect`](crate::inspect).
+pub(crate) fn inspect(
+    _preamble_ints: u8,
+    serial_version: u8,
+    mut cursor: SketchSlice<'_>,
+) -> Result<SketchInfo, Error> {
+    ensure_serial_version_is(SERIAL_VERSION, serial_version)?;
+    let lg_k = cursor.read_u8().map_err(insufficient_data("lg_k"))?;
+    cursor
+        .read_u8()
+        .map_err(insufficient_data("first_interesting_column"))?;
+    let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+    cursor
+        .read_u16_le()
+        .map_err(insufficient_data("seed_hash"))?;
+    let has_table = flags & (1 << FLAG_HAS_TABLE) != 0;
+    let has_window = flags & (1 << FLAG_HAS_WINDOW) != 0;
+    let num_coupons = if has_table || has_window {
+        cursor
+            .read_u32_le()
+            .map_err(insufficient_data("num_coupons"))?
+    } else {
+        0
+    };
+
+    let mut info = SketchInfo::new(&Family::CPC, serial_version);
+    info.lg_k = Some(lg_k);
+    info.is_empty = num_coupons == 0;
+    info.num_retained = Some(num_coupons as u64);
+    Ok(info)
+}
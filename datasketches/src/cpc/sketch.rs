@@ -15,6 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::fmt;
 use std::hash::Hash;
 
 use crate::codec::SketchBytes;
@@ -23,9 +24,21 @@ use crate::codec::assert::ensure_preamble_longs_in;
 use crate::codec::assert::ensure_serial_version_is;
 use crate::codec::assert::insufficient_data;
 use crate::codec::family::Family;
+use crate::codec::serialize_into_slice;
+use crate::common::CardinalityFamily;
+use crate::common::EstimateSummary;
+use crate::common::IntoSketchInput;
+use crate::common::MergeableSketch;
 use crate::common::NumStdDev;
+use crate::common::RawBytes;
+use crate::common::SerializableSketch;
+use crate::common::Sketch;
+use crate::common::SketchInput;
+use crate::common::Summary;
 use crate::common::canonical_double;
+use crate::common::estimates_within;
 use crate::common::inv_pow2_table::INVERSE_POWERS_OF_2;
+use crate::cpc::CpcUnion;
 use crate::cpc::DEFAULT_LG_K;
 use crate::cpc::Flavor;
 use crate::cpc::MAX_LG_K;
@@ -46,10 +59,10 @@ use crate::cpc::serialization::FLAG_HAS_WINDOW;
 use crate::cpc::serialization::SERIAL_VERSION;
 use crate::cpc::serialization::make_preamble_ints;
 use crate::error::Error;
-use crate::error::ErrorKind;
 use crate::hash::DEFAULT_UPDATE_SEED;
 use crate::hash::MurmurHash3X64128;
 use crate::hash::compute_seed_hash;
+use crate::hash::try_compute_seed_hash;
 
 /// A Compressed Probabilistic Counting sketch.
 ///
@@ -95,6 +108,8 @@ impl Default for CpcSketch {
 impl CpcSketch {
     /// Creates a new `CpcSketch` with the given `lg_k` and default seed.
     ///
+    /// The fallible version of this method is [`CpcSketch::try_new`].
+    ///
     /// # Panics
     ///
     /// Panics if `lg_k` is not in the range `[4, 26]`.
@@ -104,6 +119,8 @@ impl CpcSketch {
 
     /// Creates a new `CpcSketch` with the given `lg_k` and `seed`.
     ///
+    /// The fallible version of this method is [`CpcSketch::try_with_seed`].
+    ///
     /// # Panics
     ///
     /// Panics if `lg_k` is not in the range `[4, 26]`, or the computed seed hash is zero.
@@ -128,6 +145,43 @@ impl CpcSketch {
         }
     }
 
+    /// Creates a new `CpcSketch` with the given `lg_k` and default seed.
+    ///
+    /// The panicking version of this method is [`CpcSketch::new`].
+    ///
+    /// # Errors
+    ///
+    /// If `lg_k` is not in the range `[4, 26]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::cpc::CpcSketch;
+    /// assert!(CpcSketch::try_new(27).is_err());
+    /// let sketch = CpcSketch::try_new(11).unwrap();
+    /// assert_eq!(sketch.lg_k(), 11);
+    /// ```
+    pub fn try_new(lg_k: u8) -> Result<Self, Error> {
+        Self::try_with_seed(lg_k, DEFAULT_UPDATE_SEED)
+    }
+
+    /// Creates a new `CpcSketch` with the given `lg_k` and `seed`.
+    ///
+    /// The panicking version of this method is [`CpcSketch::with_seed`].
+    ///
+    /// # Errors
+    ///
+    /// If `lg_k` is not in the range `[4, 26]`, or the computed seed hash is zero.
+    pub fn try_with_seed(lg_k: u8, seed: u64) -> Result<Self, Error> {
+        if !(MIN_LG_K..=MAX_LG_K).contains(&lg_k) {
+            return Err(Error::invalid_argument(format!(
+                "lg_k out of range; got {lg_k}"
+            )));
+        }
+        try_compute_seed_hash(seed)?;
+        Ok(Self::with_seed(lg_k, seed))
+    }
+
     /// Return the parameter lg_k.
     pub fn lg_k(&self) -> u8 {
         self.lg_k
@@ -170,14 +224,62 @@ impl CpcSketch {
         self.num_coupons == 0
     }
 
+    /// Returns true if `other` has the same lg_k and seed as this sketch and their estimates
+    /// differ by at most `epsilon` relative to the larger one.
+    ///
+    /// See [`HllSketch::approximately_equal`](crate::hll::HllSketch::approximately_equal).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `epsilon` is negative or NaN.
+    pub fn approximately_equal(&self, other: &CpcSketch, epsilon: f64) -> bool {
+        self.lg_k == other.lg_k
+            && self.seed == other.seed
+            && estimates_within(self.estimate(), other.estimate(), epsilon)
+    }
+
+    /// Returns the estimate and the bounds at `kappa` standard deviations.
+    ///
+    /// The number of retained entries is the number of coupons.
+    pub fn estimate_summary(&self, kappa: NumStdDev) -> EstimateSummary {
+        EstimateSummary {
+            estimate: self.estimate(),
+            lb: self.lower_bound(kappa),
+            ub: self.upper_bound(kappa),
+            retained: self.num_coupons as u64,
+            family: CardinalityFamily::Cpc,
+        }
+    }
+
+    /// Returns the number of bytes of memory the sketch occupies, including the capacity of the
+    /// surprising value table and of the sliding window.
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>() + self.heap_size_bytes()
+    }
+
+    pub(super) fn heap_size_bytes(&self) -> usize {
+        let table = self
+            .surprising_value_table
+            .as_ref()
+            .map_or(0, PairTable::heap_size_bytes);
+        table + self.sliding_window.capacity()
+    }
+
     /// Update the sketch with a hashable value.
     ///
     /// For `f32`/`f64` values, use `update_f32`/`update_f64` instead.
     pub fn update<T: Hash>(&mut self, value: T) {
         let mut hasher = MurmurHash3X64128::with_seed(self.seed);
         value.hash(&mut hasher);
-        let (h1, h2) = hasher.finish128();
+        self.update_hash(hasher.finish128());
+    }
 
+    /// Update the sketch with a pre-computed 128-bit hash.
+    ///
+    /// `hash` must be the result of [`murmur3_128`](crate::hash::murmur3_128) with the seed of
+    /// this sketch, in which case this is equivalent to [`update_bytes`](Self::update_bytes)
+    /// with the same bytes.
+    pub fn update_hash(&mut self, (h1, h2): (u64, u64)) {
         let k = 1 << self.lg_k;
         let col = h2.leading_zeros(); // 0 <= col <= 64
         let col = if col > 63 { 63 } else { col as u8 }; // clip so that 0 <= col <= 63
@@ -203,6 +305,20 @@ impl CpcSketch {
         self.update_f64(value as f64);
     }
 
+    /// Update the sketch with a value hashed like datasketches-java does, see
+    /// [`IntoSketchInput`].
+    pub fn update_input<T: IntoSketchInput>(&mut self, value: T) {
+        self.update(SketchInput(value));
+    }
+
+    /// Update the sketch with the raw bytes of a value.
+    ///
+    /// The bytes are hashed as-is, see [`RawBytes`], so the result does not depend on the
+    /// platform.
+    pub fn update_bytes(&mut self, bytes: &[u8]) {
+        self.update(RawBytes(bytes));
+    }
+
     pub(super) fn flavor(&self) -> Flavor {
         determine_flavor(self.lg_k, self.num_coupons)
     }
@@ -450,11 +566,80 @@ impl CpcSketch {
 impl CpcSketch {
     /// Serializes this CpcSketch to bytes.
     pub fn serialize(&self) -> Vec<u8> {
-        let mut bytes = SketchBytes::with_capacity(256);
+        let compressed = self.compressed();
+        let mut bytes = SketchBytes::with_capacity(self.serialized_size_of(&compressed));
+        self.write_to(&compressed, &mut bytes);
+        bytes.into_bytes()
+    }
+
+    /// Returns the number of bytes [`CpcSketch::serialize`] produces.
+    ///
+    /// The size depends on how well the sketch compresses, so this compresses the sketch.
+    pub fn serialized_size_bytes(&self) -> usize {
+        self.serialized_size_of(&self.compressed())
+    }
+
+    /// Serializes this CpcSketch into the front of `buf`, returning the number of bytes
+    /// written.
+    ///
+    /// The bytes are the same as those returned by [`CpcSketch::serialize`]. The output is not
+    /// allocated, but the sketch is still compressed into temporary buffers first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `buf` is shorter than [`CpcSketch::serialized_size_bytes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::cpc::CpcSketch;
+    /// let mut sketch = CpcSketch::new(10);
+    /// sketch.update("apple");
+    /// let mut buf = vec![0u8; CpcSketch::max_serialized_bytes(10)];
+    /// let len = sketch.serialize_into(&mut buf).unwrap();
+    /// let decoded = CpcSketch::deserialize(&buf[..len]).unwrap();
+    /// assert_eq!(decoded.estimate(), sketch.estimate());
+    /// ```
+    pub fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let compressed = self.compressed();
+        serialize_into_slice(buf, self.serialized_size_of(&compressed), |bytes| {
+            self.write_to(&compressed, bytes)
+        })
+    }
 
+    fn compressed(&self) -> CompressedState {
         let mut compressed = CompressedState::default();
         compressed.compress(self);
+        compressed
+    }
+
+    fn serialized_size_of(&self, compressed: &CompressedState) -> usize {
+        let has_hip = !self.merge_flag;
+        let has_table = !compressed.table_data.is_empty();
+        let has_window = !compressed.window_data.is_empty();
+        // 8 bytes preamble for all cases
+        let mut size = 8;
+        if !self.is_empty() {
+            // num_coupons, table_num_entries, table_data_words and window_data_words
+            size += 4;
+            if has_table && has_window {
+                size += 4;
+            }
+            if has_table {
+                size += 4 + compressed.table_data_words * 4;
+            }
+            if has_window {
+                size += 4 + compressed.window_data_words * 4;
+            }
+            // kxp and hip_est_accum
+            if has_hip {
+                size += 16;
+            }
+        }
+        size
+    }
 
+    fn write_to(&self, compressed: &CompressedState, bytes: &mut SketchBytes<'_>) {
         let has_hip = !self.merge_flag;
         let has_table = !compressed.table_data.is_empty();
         let has_window = !compressed.window_data.is_empty();
@@ -479,7 +664,7 @@ impl CpcSketch {
                 // HIP values can be in two different places in the sequence of fields
                 // this is the first HIP decision point
                 if has_hip {
-                    self.write_hip(&mut bytes);
+                    self.write_hip(bytes);
                 }
             }
             if has_table {
@@ -492,7 +677,7 @@ impl CpcSketch {
             }
             // this is the second HIP decision point
             if has_hip && !(has_table && has_window) {
-                self.write_hip(&mut bytes);
+                self.write_hip(bytes);
             }
             if has_window {
                 for i in 0..compressed.window_data_words {
@@ -505,7 +690,6 @@ impl CpcSketch {
                 }
             }
         }
-        bytes.into_bytes()
     }
 
     /// Deserializes a CpcSketch from bytes.
@@ -530,6 +714,18 @@ impl CpcSketch {
         let first_interesting_column = cursor
             .read_u8()
             .map_err(insufficient_data("first_interesting_column"))?;
+        if !(MIN_LG_K..=MAX_LG_K).contains(&lg_k) {
+            return Err(Error::bad_preamble(format!(
+                "lg_k out of range; got {}",
+                lg_k
+            )));
+        }
+        if first_interesting_column > 63 {
+            return Err(Error::bad_preamble(format!(
+                "first_interesting_column out of range; got {}",
+                first_interesting_column
+            )));
+        }
 
         let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
         let seed_hash = cursor
@@ -537,8 +733,7 @@ impl CpcSketch {
             .map_err(insufficient_data("seed_hash"))?;
         let is_compressed = flags & (1 << FLAG_COMPRESSED) != 0;
         if !is_compressed {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
+            return Err(Error::bad_preamble(
                 "only compressed sketches are supported",
             ));
         }
@@ -584,6 +779,15 @@ impl CpcSketch {
                     .read_f64_le()
                     .map_err(insufficient_data("hip_est_accum"))?;
             }
+            let data_words =
+                compressed.window_data_words as u64 + compressed.table_data_words as u64;
+            if (cursor.remaining() as u64) < data_words * 4 {
+                return Err(Error::insufficient_data(format!(
+                    "expected {} bytes of compressed data, got {}",
+                    data_words * 4,
+                    cursor.remaining()
+                )));
+            }
             if has_window {
                 for _ in 0..compressed.window_data_words {
                     let word = cursor
@@ -608,30 +812,24 @@ impl CpcSketch {
         let expected_preamble_ints =
             make_preamble_ints(num_coupons, has_hip, has_table, has_window);
         ensure_preamble_longs_in(&[expected_preamble_ints], preamble_ints)?;
-        if seed_hash != compute_seed_hash(seed) {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "seed hash mismatch: expected {}, got {}",
-                    compute_seed_hash(seed),
-                    seed_hash
-                ),
-            ));
+        let expected_seed_hash = try_compute_seed_hash(seed)?;
+        if seed_hash != expected_seed_hash {
+            return Err(Error::seed_hash_mismatch(expected_seed_hash, seed_hash));
         }
-        if !(MIN_LG_K..=MAX_LG_K).contains(&lg_k) {
-            return Err(Error::invalid_argument(format!(
-                "lg_k out of range; got {}",
-                lg_k
+        // every coupon is a distinct (row, column) pair of a k x 64 matrix
+        if num_coupons as u64 > (1u64 << lg_k) * 64 {
+            return Err(Error::deserial(format!(
+                "num_coupons out of range for lg_k {lg_k}; got {num_coupons}"
             )));
         }
-        if first_interesting_column > 63 {
-            return Err(Error::invalid_argument(format!(
-                "first_interesting_column out of range; got {}",
-                first_interesting_column
+        if compressed.table_num_entries > num_coupons {
+            return Err(Error::deserial(format!(
+                "table_num_entries exceeds num_coupons: {} > {num_coupons}",
+                compressed.table_num_entries
             )));
         }
 
-        let uncompressed = compressed.uncompress(lg_k, num_coupons);
+        let uncompressed = compressed.uncompress(lg_k, num_coupons)?;
         Ok(CpcSketch {
             lg_k,
             seed,
@@ -647,7 +845,7 @@ impl CpcSketch {
         })
     }
 
-    fn write_hip(&self, bytes: &mut SketchBytes) {
+    fn write_hip(&self, bytes: &mut SketchBytes<'_>) {
         bytes.write_f64_le(self.kxp);
         bytes.write_f64_le(self.hip_est_accum);
     }
@@ -724,3 +922,65 @@ impl CpcSketch {
         self.num_coupons
     }
 }
+
+impl fmt::Display for CpcSketch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Summary::new(f, "CPC SKETCH")
+            .field("lg k", self.lg_k())
+            .field("num coupons", self.num_coupons())
+            .field("empty", self.is_empty())
+            .field("estimate", self.estimate())
+            .field("lower bound 95% conf", self.lower_bound(NumStdDev::Two))
+            .field("upper bound 95% conf", self.upper_bound(NumStdDev::Two))
+            .finish()
+    }
+}
+
+impl<I: Hash> Sketch<I> for CpcSketch {
+    fn update(&mut self, item: I) {
+        CpcSketch::update(self, item);
+    }
+
+    fn is_empty(&self) -> bool {
+        CpcSketch::is_empty(self)
+    }
+}
+
+/// Merging is performed as by a [`CpcUnion`](crate::cpc::CpcUnion) configured with the lg_k of
+/// this sketch.
+impl MergeableSketch for CpcSketch {
+    type Error = Error;
+
+    fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        if self.seed() != other.seed() {
+            return Err(Error::invalid_argument(
+                "cannot merge CPC sketches built with different seeds",
+            ));
+        }
+        let mut union = CpcUnion::with_seed(self.lg_k(), self.seed());
+        union.update(self);
+        union.update(other);
+        *self = union.to_sketch();
+        Ok(())
+    }
+}
+
+impl SerializableSketch for CpcSketch {
+    type Error = Error;
+
+    fn serialize(&self) -> Vec<u8> {
+        CpcSketch::serialize(self)
+    }
+
+    fn serialized_size_bytes(&self) -> usize {
+        CpcSketch::serialized_size_bytes(self)
+    }
+
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        CpcSketch::serialize_into(self, buf)
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        CpcSketch::deserialize(bytes)
+    }
+}
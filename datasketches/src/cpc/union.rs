@@ -67,6 +67,7 @@ use crate::cpc::Flavor;
 use crate::cpc::count_bits_set_in_matrix;
 use crate::cpc::determine_correct_offset;
 use crate::cpc::pair_table::PairTable;
+use crate::error::Error;
 use crate::hash::DEFAULT_UPDATE_SEED;
 
 /// The union (merge) operation for the CPC sketches.
@@ -89,6 +90,8 @@ impl Default for CpcUnion {
 impl CpcUnion {
     /// Creates a new `CpcUnion` with the given `lg_k` and default seed.
     ///
+    /// The fallible version of this method is [`CpcUnion::try_new`].
+    ///
     /// # Panics
     ///
     /// Panics if `lg_k` is not in the range `[4, 26]`.
@@ -98,9 +101,11 @@ impl CpcUnion {
 
     /// Creates a new `CpcUnion` with the given `lg_k` and `seed`.
     ///
+    /// The fallible version of this method is [`CpcUnion::try_with_seed`].
+    ///
     /// # Panics
     ///
-    /// Panics if `lg_k` is not in the range `[4, 26]`.
+    /// Panics if `lg_k` is not in the range `[4, 26]`, or the computed seed hash is zero.
     pub fn with_seed(lg_k: u8, seed: u64) -> Self {
         // We begin with the accumulator holding an EMPTY_MERGED sketch object.
         let sketch = CpcSketch::with_seed(lg_k, seed);
@@ -108,6 +113,30 @@ impl CpcUnion {
         Self { lg_k, seed, state }
     }
 
+    /// Creates a new `CpcUnion` with the given `lg_k` and default seed.
+    ///
+    /// The panicking version of this method is [`CpcUnion::new`].
+    ///
+    /// # Errors
+    ///
+    /// If `lg_k` is not in the range `[4, 26]`.
+    pub fn try_new(lg_k: u8) -> Result<Self, Error> {
+        Self::try_with_seed(lg_k, DEFAULT_UPDATE_SEED)
+    }
+
+    /// Creates a new `CpcUnion` with the given `lg_k` and `seed`.
+    ///
+    /// The panicking version of this method is [`CpcUnion::with_seed`].
+    ///
+    /// # Errors
+    ///
+    /// If `lg_k` is not in the range `[4, 26]`, or the computed seed hash is zero.
+    pub fn try_with_seed(lg_k: u8, seed: u64) -> Result<Self, Error> {
+        let sketch = CpcSketch::try_with_seed(lg_k, seed)?;
+        let state = UnionState::Accumulator(sketch);
+        Ok(Self { lg_k, seed, state })
+    }
+
     /// Return the parameter lg_k.
     ///
     /// Note that due to merging with source sketches that may have a lower value of lg_k, this
@@ -116,6 +145,16 @@ impl CpcUnion {
         self.lg_k
     }
 
+    /// Returns the number of bytes of memory the union occupies, either as its accumulator
+    /// sketch or as the bit matrix it switches to once inputs become dense.
+    pub fn memory_usage_bytes(&self) -> usize {
+        let heap = match &self.state {
+            UnionState::Accumulator(sketch) => sketch.heap_size_bytes(),
+            UnionState::BitMatrix(matrix) => matrix.capacity() * size_of::<u64>(),
+        };
+        size_of::<Self>() + heap
+    }
+
     /// Get the union result as a new sketch.
     ///
     /// # Examples
@@ -301,6 +340,20 @@ impl CpcUnion {
         }
     }
 
+    /// Update this union with every sketch of the given sequence.
+    ///
+    /// Equivalent to calling [`update`](Self::update) for each sketch, for reducing many
+    /// per-partition sketches in one call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the seed of any provided sketch does not match the seed of this union.
+    pub fn union_many<'a, I: IntoIterator<Item = &'a CpcSketch>>(&mut self, sketches: I) {
+        for sketch in sketches {
+            self.update(sketch);
+        }
+    }
+
     fn reduce_k(&mut self, new_lg_k: u8) {
         match &mut self.state {
             UnionState::Accumulator(sketch) => {
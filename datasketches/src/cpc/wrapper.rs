@@ -33,7 +33,6 @@ use crate::cpc::serialization::FLAG_HAS_WINDOW;
 use crate::cpc::serialization::SERIAL_VERSION;
 use crate::cpc::serialization::make_preamble_ints;
 use crate::error::Error;
-use crate::error::ErrorKind;
 
 /// A read-only view of a serialized image of a CpcSketch.
 #[derive(Debug, Clone)]
@@ -63,13 +62,13 @@ impl CpcWrapper {
             .read_u8()
             .map_err(insufficient_data("first_interesting_column"))?;
         if !(MIN_LG_K..=MAX_LG_K).contains(&lg_k) {
-            return Err(Error::invalid_argument(format!(
+            return Err(Error::bad_preamble(format!(
                 "lg_k out of range; got {}",
                 lg_k
             )));
         }
         if first_interesting_column > 63 {
-            return Err(Error::invalid_argument(format!(
+            return Err(Error::bad_preamble(format!(
                 "first_interesting_column out of range; got {}",
                 first_interesting_column
             )));
@@ -78,8 +77,7 @@ impl CpcWrapper {
         let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
         let is_compressed = flags & (1 << FLAG_COMPRESSED) != 0;
         if !is_compressed {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
+            return Err(Error::bad_preamble(
                 "only compressed sketches are supported",
             ));
         }
@@ -0,0 +1,461 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Self-describing containers for partial aggregates of any sketch family.
+//!
+//! Stream-processing topologies often ship partial aggregates of different sketch families over
+//! the same topic, and the operator that combines them only sees bytes. A [`SketchEnvelope`]
+//! carries a serialized sketch together with everything needed to merge it without knowing its
+//! type statically: the family and serial version of the image, its parameters as reported by
+//! [`inspect`](crate::inspect), the item type for families whose images do not record it, and the
+//! hash seed. [`merge_envelope`] dispatches to the union operator of the family.
+//!
+//! # Examples
+//!
+//! ```
+//! # use datasketches::envelope::ItemType;
+//! # use datasketches::envelope::SketchEnvelope;
+//! # use datasketches::envelope::merge_envelope;
+//! # use datasketches::kll::KllSketch;
+//! let mut left = KllSketch::<f64>::new(200);
+//! left.update(1.0);
+//! let mut right = KllSketch::<f64>::new(200);
+//! right.update(2.0);
+//!
+//! // producers
+//! let left = SketchEnvelope::new(left.serialize(), Some(ItemType::F64)).unwrap();
+//! let right = SketchEnvelope::new(right.serialize(), Some(ItemType::F64)).unwrap();
+//! let (left, right) = (left.serialize(), right.serialize());
+//!
+//! // aggregator
+//! let merged = merge_envelope(
+//!     &SketchEnvelope::deserialize(&left).unwrap(),
+//!     &SketchEnvelope::deserialize(&right).unwrap(),
+//! )
+//! .unwrap();
+//! assert_eq!(merged.info().family(), "KLL");
+//! let sketch = KllSketch::<f64>::deserialize(merged.sketch_bytes()).unwrap();
+//! assert_eq!(sketch.n(), 2);
+//! ```
+
+use crate::bloom::BloomFilter;
+use crate::codec::SketchBytes;
+use crate::codec::SketchSlice;
+use crate::codec::assert::ensure_preamble_longs_in;
+use crate::codec::assert::ensure_serial_version_is;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
+use crate::countmin::CountMinSketch;
+use crate::countmin::CountMinValue;
+use crate::cpc::CpcSketch;
+use crate::cpc::CpcUnion;
+use crate::error::Error;
+use crate::frequencies::FrequentItemValue;
+use crate::frequencies::FrequentItemsSketch;
+use crate::hash::DEFAULT_UPDATE_SEED;
+use crate::hll::HllSketch;
+use crate::hll::HllUnion;
+use crate::inspect::SketchInfo;
+use crate::inspect::inspect;
+use crate::kll::KllItem;
+use crate::kll::KllSketch;
+use crate::tdigest::TDigestMut;
+use crate::theta::CompactThetaSketch;
+use crate::theta::MAX_LG_K;
+use crate::theta::MIN_LG_K;
+use crate::theta::ThetaSketch;
+use crate::theta::ThetaUnion;
+
+const PREAMBLE_LONGS: u8 = 2;
+const SERIAL_VERSION: u8 = 1;
+const HEADER_BYTES: usize = PREAMBLE_LONGS as usize * 8;
+
+/// The item type of a sketch whose serialized image does not record it.
+///
+/// KLL sketches need one of the float types, frequent items sketches any type but the floats, and
+/// CountMin sketches the type of their counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ItemType {
+    /// `i8`
+    I8,
+    /// `i16`
+    I16,
+    /// `i32`
+    I32,
+    /// `i64`
+    I64,
+    /// `u8`
+    U8,
+    /// `u16`
+    U16,
+    /// `u32`
+    U32,
+    /// `u64`
+    U64,
+    /// `f32`
+    F32,
+    /// `f64`
+    F64,
+    /// `String`
+    String,
+    /// `Vec<u8>`
+    Bytes,
+}
+
+impl ItemType {
+    const ALL: [ItemType; 12] = [
+        ItemType::I8,
+        ItemType::I16,
+        ItemType::I32,
+        ItemType::I64,
+        ItemType::U8,
+        ItemType::U16,
+        ItemType::U32,
+        ItemType::U64,
+        ItemType::F32,
+        ItemType::F64,
+        ItemType::String,
+        ItemType::Bytes,
+    ];
+
+    fn code(self) -> u8 {
+        ItemType::ALL.iter().position(|&t| t == self).unwrap() as u8 + 1
+    }
+
+    fn from_code(code: u8) -> Result<Option<ItemType>, Error> {
+        match code {
+            0 => Ok(None),
+            _ => ItemType::ALL
+                .get(code as usize - 1)
+                .map(|&item_type| Some(item_type))
+                .ok_or_else(|| Error::bad_preamble(format!("unknown item type code: {code}"))),
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, ItemType::F32 | ItemType::F64)
+    }
+
+    fn is_integer(self) -> bool {
+        !self.is_float() && !matches!(self, ItemType::String | ItemType::Bytes)
+    }
+}
+
+/// A serialized sketch of any family, with the metadata needed to merge it.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SketchEnvelope {
+    info: SketchInfo,
+    item_type: Option<ItemType>,
+    seed: u64,
+    bytes: Vec<u8>,
+}
+
+impl SketchEnvelope {
+    /// Wraps a serialized sketch built with the default seed.
+    ///
+    /// `item_type` must be given for KLL, frequent items and CountMin sketches, and must be
+    /// `None` for the other families.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the preamble of `bytes` cannot be [inspected](crate::inspect), or if
+    /// `item_type` does not fit the family.
+    pub fn new(bytes: Vec<u8>, item_type: Option<ItemType>) -> Result<Self, Error> {
+        Self::with_seed(bytes, item_type, DEFAULT_UPDATE_SEED)
+    }
+
+    /// Wraps a serialized sketch built with the given seed.
+    ///
+    /// The seed is only used by the families that hash their items with it: theta, CPC and
+    /// CountMin sketches.
+    ///
+    /// # Errors
+    ///
+    /// See [`new`](Self::new).
+    pub fn with_seed(
+        bytes: Vec<u8>,
+        item_type: Option<ItemType>,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        let info = inspect(&bytes)?;
+        check_item_type(&info, item_type)?;
+        Ok(SketchEnvelope {
+            info,
+            item_type,
+            seed,
+            bytes,
+        })
+    }
+
+    /// Returns the family, serial version and parameters read from the preamble of the sketch.
+    pub fn info(&self) -> &SketchInfo {
+        &self.info
+    }
+
+    /// Returns the item type of the sketch, if its family needs one.
+    pub fn item_type(&self) -> Option<ItemType> {
+        self.item_type
+    }
+
+    /// Returns the seed the sketch was built with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the serialized sketch.
+    pub fn sketch_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the serialized sketch, consuming the envelope.
+    pub fn into_sketch_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Serializes the envelope: a 16-byte header followed by the serialized sketch.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = SketchBytes::with_capacity(HEADER_BYTES + self.bytes.len());
+        bytes.write_u8(PREAMBLE_LONGS);
+        bytes.write_u8(SERIAL_VERSION);
+        bytes.write_u8(self.info.family_id());
+        bytes.write_u8(self.info.serial_version());
+        bytes.write_u8(self.item_type.map_or(0, ItemType::code));
+        bytes.write(&[0; 3]);
+        bytes.write_u64_le(self.seed);
+        bytes.write(&self.bytes);
+        bytes.into_bytes()
+    }
+
+    /// Deserializes an envelope produced by [`serialize`](Self::serialize).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is invalid or does not describe the wrapped sketch.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        let mut cursor = SketchSlice::new(bytes);
+        let preamble_longs = cursor
+            .read_u8()
+            .map_err(insufficient_data("preamble_longs"))?;
+        let serial_version = cursor
+            .read_u8()
+            .map_err(insufficient_data("serial_version"))?;
+        ensure_preamble_longs_in(&[PREAMBLE_LONGS], preamble_longs)?;
+        ensure_serial_version_is(SERIAL_VERSION, serial_version)?;
+        let family_id = cursor.read_u8().map_err(insufficient_data("family_id"))?;
+        let sketch_serial_version = cursor
+            .read_u8()
+            .map_err(insufficient_data("sketch_serial_version"))?;
+        let item_type = cursor.read_u8().map_err(insufficient_data("item_type"))?;
+        let item_type = ItemType::from_code(item_type)?;
+        cursor.advance(3);
+        let seed = cursor.read_u64_le().map_err(insufficient_data("seed"))?;
+
+        let envelope = Self::with_seed(bytes[HEADER_BYTES..].to_vec(), item_type, seed)?;
+        if envelope.info.family_id() != family_id
+            || envelope.info.serial_version() != sketch_serial_version
+        {
+            return Err(Error::deserial(format!(
+                "envelope describes family {family_id} version {sketch_serial_version}, \
+                 but wraps family {} version {}",
+                envelope.info.family_id(),
+                envelope.info.serial_version()
+            )));
+        }
+        Ok(envelope)
+    }
+}
+
+/// Merges two envelopes of the same family with the union operator of that family.
+///
+/// Theta sketches are merged by a [`ThetaUnion`] configured with the largest nominal entries among
+/// the inputs, and the result is an ordered compact sketch. HLL and CPC sketches are merged by a
+/// union configured with the largest `lg_k` among the inputs, keeping the HLL type of `left`.
+/// The other families merge as their [`MergeableSketch`](crate::common::MergeableSketch)
+/// implementation does.
+///
+/// # Errors
+///
+/// Returns an error if the envelopes hold different families, item types or seeds, if a sketch
+/// cannot be deserialized, or if the sketches are incompatible, e.g., CountMin sketches of
+/// different shapes.
+pub fn merge_envelope(
+    left: &SketchEnvelope,
+    right: &SketchEnvelope,
+) -> Result<SketchEnvelope, Error> {
+    let (family, other) = (left.info.family_id(), right.info.family_id());
+    if family != other && !(is_theta(family) && is_theta(other)) {
+        return Err(Error::invalid_argument(format!(
+            "cannot merge a {} sketch with a {} sketch",
+            left.info.family(),
+            right.info.family()
+        )));
+    }
+    if left.item_type != right.item_type {
+        return Err(Error::invalid_argument(format!(
+            "cannot merge sketches of item types {:?} and {:?}",
+            left.item_type, right.item_type
+        )));
+    }
+    if left.seed != right.seed {
+        return Err(Error::invalid_argument(format!(
+            "cannot merge sketches built with seeds {} and {}",
+            left.seed, right.seed
+        )));
+    }
+
+    let (a, b, seed) = (left.sketch_bytes(), right.sketch_bytes(), left.seed);
+    let lg_k = left.info.lg_k().max(right.info.lg_k());
+    let bytes = match family {
+        _ if is_theta(family) => merge_theta(a, b, lg_k, seed)?,
+        _ if family == Family::HLL.id => {
+            let (a, b) = (HllSketch::deserialize(a)?, HllSketch::deserialize(b)?);
+            let mut union = HllUnion::try_new(a.lg_config_k().max(b.lg_config_k()))?;
+            union.update(&a);
+            union.update(&b);
+            union.to_sketch(a.target_type()).serialize()
+        }
+        _ if family == Family::CPC.id => {
+            let a = CpcSketch::deserialize_with_seed(a, seed)?;
+            let b = CpcSketch::deserialize_with_seed(b, seed)?;
+            let mut union = CpcUnion::try_with_seed(a.lg_k().max(b.lg_k()), seed)?;
+            union.update(&a);
+            union.update(&b);
+            union.to_sketch().serialize()
+        }
+        _ if family == Family::KLL.id => match left.item_type {
+            Some(ItemType::F32) => merge_kll::<f32>(a, b)?,
+            Some(ItemType::F64) => merge_kll::<f64>(a, b)?,
+            _ => unreachable!("item type checked on construction"),
+        },
+        _ if family == Family::FREQUENCY.id => match left.item_type {
+            Some(ItemType::I8) => merge_frequencies::<i8>(a, b)?,
+            Some(ItemType::I16) => merge_frequencies::<i16>(a, b)?,
+            Some(ItemType::I32) => merge_frequencies::<i32>(a, b)?,
+            Some(ItemType::I64) => merge_frequencies::<i64>(a, b)?,
+            Some(ItemType::U8) => merge_frequencies::<u8>(a, b)?,
+            Some(ItemType::U16) => merge_frequencies::<u16>(a, b)?,
+            Some(ItemType::U32) => merge_frequencies::<u32>(a, b)?,
+            Some(ItemType::U64) => merge_frequencies::<u64>(a, b)?,
+            Some(ItemType::String) => merge_frequencies::<String>(a, b)?,
+            Some(ItemType::Bytes) => merge_frequencies::<Vec<u8>>(a, b)?,
+            _ => unreachable!("item type checked on construction"),
+        },
+        _ if family == Family::COUNTMIN.id => match left.item_type {
+            Some(ItemType::I8) => merge_countmin::<i8>(a, b, seed)?,
+            Some(ItemType::I16) => merge_countmin::<i16>(a, b, seed)?,
+            Some(ItemType::I32) => merge_countmin::<i32>(a, b, seed)?,
+            Some(ItemType::I64) => merge_countmin::<i64>(a, b, seed)?,
+            Some(ItemType::U8) => merge_countmin::<u8>(a, b, seed)?,
+            Some(ItemType::U16) => merge_countmin::<u16>(a, b, seed)?,
+            Some(ItemType::U32) => merge_countmin::<u32>(a, b, seed)?,
+            Some(ItemType::U64) => merge_countmin::<u64>(a, b, seed)?,
+            Some(ItemType::F64) => merge_countmin::<f64>(a, b, seed)?,
+            _ => unreachable!("item type checked on construction"),
+        },
+        _ if family == Family::TDIGEST.id => {
+            let mut merged = TDigestMut::deserialize(a, false)?;
+            merged.merge(&TDigestMut::deserialize(b, false)?);
+            merged.serialize()
+        }
+        _ if family == Family::BLOOMFILTER.id => {
+            let mut merged = BloomFilter::deserialize(a)?;
+            let other = BloomFilter::deserialize(b)?;
+            if !merged.is_compatible(&other) {
+                return Err(Error::invalid_argument(
+                    "cannot merge Bloom filters with different capacities, hash counts or seeds",
+                ));
+            }
+            merged.union(&other);
+            merged.serialize()
+        }
+        _ => unreachable!("family checked on construction"),
+    };
+    SketchEnvelope::with_seed(bytes, left.item_type, seed)
+}
+
+fn is_theta(family_id: u8) -> bool {
+    family_id == Family::QUICKSELECT.id || family_id == Family::THETA.id
+}
+
+fn check_item_type(info: &SketchInfo, item_type: Option<ItemType>) -> Result<(), Error> {
+    let family_id = info.family_id();
+    let valid = match item_type {
+        None => ![Family::KLL.id, Family::FREQUENCY.id, Family::COUNTMIN.id].contains(&family_id),
+        Some(item_type) if family_id == Family::KLL.id => item_type.is_float(),
+        Some(item_type) if family_id == Family::FREQUENCY.id => !item_type.is_float(),
+        Some(item_type) if family_id == Family::COUNTMIN.id => {
+            item_type.is_integer() || item_type == ItemType::F64
+        }
+        Some(_) => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::invalid_argument(format!(
+            "item type {item_type:?} does not fit a {} sketch",
+            info.family()
+        )))
+    }
+}
+
+fn merge_theta(a: &[u8], b: &[u8], lg_k: Option<u8>, seed: u64) -> Result<Vec<u8>, Error> {
+    let mut builder = ThetaUnion::builder().seed(seed);
+    if let Some(lg_k) = lg_k {
+        if !(MIN_LG_K..=MAX_LG_K).contains(&lg_k) {
+            return Err(Error::deserial(format!(
+                "lg_k must be in [{MIN_LG_K}, {MAX_LG_K}], got {lg_k}"
+            )));
+        }
+        builder = builder.lg_k(lg_k);
+    }
+    let mut union = builder.try_build()?;
+    for bytes in [a, b] {
+        if bytes.get(2) == Some(&Family::QUICKSELECT.id) {
+            union.update(&ThetaSketch::deserialize_with_seed(bytes, seed)?)?;
+        } else {
+            union.update(&CompactThetaSketch::deserialize_with_seed(bytes, seed)?)?;
+        }
+    }
+    Ok(union.result().serialize())
+}
+
+fn merge_kll<T: KllItem>(a: &[u8], b: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut merged = KllSketch::<T>::deserialize(a)?;
+    merged.merge(&KllSketch::<T>::deserialize(b)?);
+    Ok(merged.serialize())
+}
+
+fn merge_frequencies<T: FrequentItemValue>(a: &[u8], b: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut merged = FrequentItemsSketch::<T>::deserialize(a)?;
+    merged.merge(&FrequentItemsSketch::<T>::deserialize(b)?);
+    Ok(merged.serialize())
+}
+
+fn merge_countmin<T: CountMinValue>(a: &[u8], b: &[u8], seed: u64) -> Result<Vec<u8>, Error> {
+    let mut merged = CountMinSketch::<T>::deserialize_with_seed(a, seed)?;
+    let other = CountMinSketch::<T>::deserialize_with_seed(b, seed)?;
+    if !merged.is_compatible(&other) {
+        return Err(Error::invalid_argument(
+            "cannot merge CountMin sketches with different configurations",
+        ));
+    }
+    merged.merge(&other);
+    Ok(merged.serialize())
+}
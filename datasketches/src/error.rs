@@ -27,6 +27,15 @@ pub enum ErrorKind {
     InvalidArgument,
     /// The sketch data deserializing is malformed.
     InvalidData,
+    /// The preamble of the serialized sketch is malformed, e.g. the family id, preamble size or
+    /// a header field is out of range.
+    BadPreamble,
+    /// The serialized sketch uses a serial version that this library cannot read.
+    UnsupportedVersion,
+    /// The serialized sketch was built with a different hash seed than the one expected.
+    SeedHashMismatch,
+    /// The serialized sketch ends before all the data described by its preamble.
+    Truncated,
 }
 
 impl ErrorKind {
@@ -35,6 +44,10 @@ impl ErrorKind {
         match self {
             ErrorKind::InvalidArgument => "InvalidArgument",
             ErrorKind::InvalidData => "InvalidData",
+            ErrorKind::BadPreamble => "BadPreamble",
+            ErrorKind::UnsupportedVersion => "UnsupportedVersion",
+            ErrorKind::SeedHashMismatch => "SeedHashMismatch",
+            ErrorKind::Truncated => "Truncated",
         }
     }
 }
@@ -99,22 +112,40 @@ impl Error {
         Self::new(ErrorKind::InvalidData, msg)
     }
 
+    pub(crate) fn bad_preamble(msg: impl Into<String>) -> Self {
+        Self::new(ErrorKind::BadPreamble, msg)
+    }
+
+    pub(crate) fn unsupported_version(msg: impl Into<String>) -> Self {
+        Self::new(ErrorKind::UnsupportedVersion, msg)
+    }
+
+    pub(crate) fn seed_hash_mismatch(expected: u16, actual: u16) -> Self {
+        Self::new(
+            ErrorKind::SeedHashMismatch,
+            format!("incompatible seed hash: expected {expected}, got {actual}"),
+        )
+    }
+
     pub(crate) fn insufficient_data(msg: impl fmt::Display) -> Self {
-        Self::deserial(format!("insufficient data: {msg}"))
+        Self::new(ErrorKind::Truncated, format!("insufficient data: {msg}"))
     }
 
     pub(crate) fn insufficient_data_of(context: &'static str, msg: impl fmt::Display) -> Self {
-        Self::deserial(format!("insufficient data ({context}): {msg}"))
+        Self::new(
+            ErrorKind::Truncated,
+            format!("insufficient data ({context}): {msg}"),
+        )
     }
 
     pub(crate) fn invalid_family(expected: u8, actual: u8, name: &'static str) -> Self {
-        Self::deserial(format!(
+        Self::bad_preamble(format!(
             "invalid family: expected {expected} ({name}), got {actual}"
         ))
     }
 
     pub(crate) fn invalid_preamble_longs(expected: &[u8], actual: u8) -> Self {
-        Error::deserial(format!(
+        Error::bad_preamble(format!(
             "invalid preamble longs: expected {expected:?}, got {actual}"
         ))
     }
@@ -0,0 +1,198 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Sketch families and deserialization of sketches of any family.
+//!
+//! Services that store or merge sketches of several families often only know the bytes of a
+//! sketch. [`Family`] names the family recorded in the preamble of a serialized image, and
+//! [`deserialize_any`] detects it and deserializes the image into an [`AnySketch`], so such a
+//! service can dispatch on the variant instead of on out-of-band metadata.
+//!
+//! # Examples
+//!
+//! ```
+//! # use datasketches::family::AnySketch;
+//! # use datasketches::family::Family;
+//! # use datasketches::family::deserialize_any;
+//! # use datasketches::hll::HllSketch;
+//! # use datasketches::hll::HllType;
+//! let mut sketch = HllSketch::new(12, HllType::Hll8);
+//! sketch.update("apple");
+//! let any = deserialize_any(&sketch.serialize()).unwrap();
+//! assert_eq!(any.family(), Family::Hll);
+//! match any {
+//!     AnySketch::Hll(hll) => assert_eq!(hll.estimate(), sketch.estimate()),
+//!     _ => unreachable!(),
+//! }
+//! ```
+
+use crate::bloom::BloomFilter;
+use crate::codec::SketchSlice;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family;
+use crate::cpc::CpcSketch;
+use crate::error::Error;
+use crate::hll::HllSketch;
+use crate::tdigest::TDigestMut;
+use crate::theta::CompactThetaSketch;
+use crate::theta::ThetaSketch;
+
+/// The family of a sketch, as recorded by the family ID in its serialized preamble.
+///
+/// The IDs are shared with datasketches-java and datasketches-cpp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Family {
+    /// Updatable theta sketches, see [`ThetaSketch`].
+    QuickSelect,
+    /// Compact theta sketches, see [`CompactThetaSketch`].
+    Theta,
+    /// HLL sketches, see [`HllSketch`].
+    Hll,
+    /// Frequent items sketches, see [`FrequentItemsSketch`](crate::frequencies::FrequentItemsSketch).
+    Frequency,
+    /// KLL sketches, see [`KllSketch`](crate::kll::KllSketch).
+    Kll,
+    /// CPC sketches, see [`CpcSketch`].
+    Cpc,
+    /// CountMin sketches, see [`CountMinSketch`](crate::countmin::CountMinSketch).
+    CountMin,
+    /// T-digests, see [`TDigestMut`].
+    TDigest,
+    /// Bloom filters, see [`BloomFilter`].
+    BloomFilter,
+}
+
+impl Family {
+    const ALL: [Family; 9] = [
+        Family::QuickSelect,
+        Family::Theta,
+        Family::Hll,
+        Family::Frequency,
+        Family::Kll,
+        Family::Cpc,
+        Family::CountMin,
+        Family::TDigest,
+        Family::BloomFilter,
+    ];
+
+    /// Returns the family with the given ID, or `None` if the ID is unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::family::Family;
+    /// assert_eq!(Family::from_id(7), Some(Family::Hll));
+    /// assert_eq!(Family::from_id(0), None);
+    /// ```
+    pub fn from_id(id: u8) -> Option<Family> {
+        Family::ALL.into_iter().find(|family| family.id() == id)
+    }
+
+    /// Returns the family ID stored in serialized preambles.
+    pub fn id(self) -> u8 {
+        self.codec().id
+    }
+
+    /// Returns the family name, e.g. `"THETA"`, as used by datasketches-java and datasketches-cpp.
+    pub fn name(self) -> &'static str {
+        self.codec().name
+    }
+
+    fn codec(self) -> &'static family::Family {
+        match self {
+            Family::QuickSelect => &family::Family::QUICKSELECT,
+            Family::Theta => &family::Family::THETA,
+            Family::Hll => &family::Family::HLL,
+            Family::Frequency => &family::Family::FREQUENCY,
+            Family::Kll => &family::Family::KLL,
+            Family::Cpc => &family::Family::CPC,
+            Family::CountMin => &family::Family::COUNTMIN,
+            Family::TDigest => &family::Family::TDIGEST,
+            Family::BloomFilter => &family::Family::BLOOMFILTER,
+        }
+    }
+}
+
+/// A deserialized sketch of any family whose serialized image is self-describing.
+///
+/// Returned by [`deserialize_any`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AnySketch {
+    /// An updatable theta sketch.
+    UpdateTheta(ThetaSketch),
+    /// A compact theta sketch.
+    CompactTheta(CompactThetaSketch),
+    /// An HLL sketch.
+    Hll(HllSketch),
+    /// A CPC sketch.
+    Cpc(CpcSketch),
+    /// A t-digest of doubles.
+    TDigest(TDigestMut),
+    /// A Bloom filter.
+    BloomFilter(BloomFilter),
+}
+
+impl AnySketch {
+    /// Returns the family of the sketch.
+    pub fn family(&self) -> Family {
+        match self {
+            AnySketch::UpdateTheta(_) => Family::QuickSelect,
+            AnySketch::CompactTheta(_) => Family::Theta,
+            AnySketch::Hll(_) => Family::Hll,
+            AnySketch::Cpc(_) => Family::Cpc,
+            AnySketch::TDigest(_) => Family::TDigest,
+            AnySketch::BloomFilter(_) => Family::BloomFilter,
+        }
+    }
+}
+
+/// Deserializes a sketch of any family, detected from the family ID in its preamble.
+///
+/// Sketches that hash their items are deserialized with the default seed. T-digests are read as
+/// digests of doubles.
+///
+/// KLL, frequent items and CountMin images do not record their item type, so they cannot be
+/// deserialized without knowing it statically; use the `deserialize` method of the sketch, or
+/// carry the item type in a [`SketchEnvelope`](crate::envelope::SketchEnvelope).
+///
+/// # Errors
+///
+/// Returns an error if the family ID is unknown, if the family is one of those above that need
+/// an item type, or if the image cannot be deserialized as a sketch of its family.
+pub fn deserialize_any(bytes: &[u8]) -> Result<AnySketch, Error> {
+    let mut cursor = SketchSlice::new(bytes);
+    cursor.advance(2);
+    let family_id = cursor.read_u8().map_err(insufficient_data("family_id"))?;
+    let family = Family::from_id(family_id)
+        .ok_or_else(|| Error::bad_preamble(format!("unknown family ID: {family_id}")))?;
+    match family {
+        Family::QuickSelect => ThetaSketch::deserialize(bytes).map(AnySketch::UpdateTheta),
+        Family::Theta => CompactThetaSketch::deserialize(bytes).map(AnySketch::CompactTheta),
+        Family::Hll => HllSketch::deserialize(bytes).map(AnySketch::Hll),
+        Family::Cpc => CpcSketch::deserialize(bytes).map(AnySketch::Cpc),
+        Family::TDigest => TDigestMut::deserialize(bytes, false).map(AnySketch::TDigest),
+        Family::BloomFilter => BloomFilter::deserialize(bytes).map(AnySketch::BloomFilter),
+        Family::Frequency | Family::Kll | Family::CountMin => {
+            Err(Error::invalid_argument(format!(
+                "{} sketches do not record their item type and cannot be deserialized without it",
+                family.name()
+            )))
+        }
+    }
+}
@@ -0,0 +1,274 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Frequent items sketch whose counts decay over time.
+
+use std::hash::Hash;
+
+use crate::error::Error;
+use crate::frequencies::ErrorType;
+use crate::frequencies::FrequentItemsSketch;
+use crate::frequencies::Row;
+
+/// Counts are kept in fixed point with this many fractional bits.
+const FRACTION_BITS: u32 = 20;
+const UNIT: f64 = (1u64 << FRACTION_BITS) as f64;
+
+/// A [`FrequentItemsSketch`] whose counts decay exponentially over time.
+///
+/// Every [`decay`](Self::decay) multiplies all counts by the configured decay factor, so an
+/// update `t` ticks ago contributes `decay_factor^t` of its weight. The heavy hitters are then
+/// the items that are frequent *now*, without restarting the sketch every window: with a factor
+/// of `0.5`, a burst halves in weight every tick.
+///
+/// Counts are real numbers held with a resolution of 2<sup>-20</sup>. Lower bounds hold exactly;
+/// upper bounds hold up to one unit of that resolution per decay, and an item whose count decays
+/// below it stops being tracked. The total weight of the stream, decayed, must stay below about
+/// 1.7 × 10<sup>13</sup>.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::frequencies::DecayedFrequentItems;
+/// # use datasketches::frequencies::ErrorType;
+/// let mut sketch = DecayedFrequentItems::new(64, 0.5);
+/// sketch.update_with_weight("old news", 100.0);
+/// for _ in 0..4 {
+///     sketch.decay();
+/// }
+/// sketch.update_with_weight("breaking", 10.0);
+///
+/// assert_eq!(sketch.estimate(&"old news"), 6.25);
+/// let top = sketch.top_k(1, ErrorType::NoFalsePositives);
+/// assert_eq!(*top[0].item(), "breaking");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DecayedFrequentItems<T> {
+    sketch: FrequentItemsSketch<T>,
+    decay_factor: f64,
+}
+
+/// A row of [`DecayedFrequentItems`] query results.
+///
+/// Like [`Row`], but with decayed, hence fractional, counts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecayedRow<T> {
+    item: T,
+    estimate: f64,
+    upper_bound: f64,
+    lower_bound: f64,
+}
+
+impl<T> DecayedRow<T> {
+    fn from_scaled(row: Row<T>) -> Self {
+        DecayedRow {
+            estimate: to_weight(row.estimate()),
+            upper_bound: to_weight(row.upper_bound()),
+            lower_bound: to_weight(row.lower_bound()),
+            item: row.into_item(),
+        }
+    }
+
+    /// Returns the item value.
+    pub fn item(&self) -> &T {
+        &self.item
+    }
+
+    /// Returns the estimated decayed frequency.
+    pub fn estimate(&self) -> f64 {
+        self.estimate
+    }
+
+    /// Returns the upper bound for the decayed frequency.
+    pub fn upper_bound(&self) -> f64 {
+        self.upper_bound
+    }
+
+    /// Returns the lower bound for the decayed frequency.
+    pub fn lower_bound(&self) -> f64 {
+        self.lower_bound
+    }
+}
+
+impl<T: Eq + Hash> DecayedFrequentItems<T> {
+    /// Creates a new sketch with the given maximum map size (power of two) and the factor every
+    /// [`decay`](Self::decay) multiplies the counts by.
+    ///
+    /// The fallible version of this method is [`DecayedFrequentItems::try_new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `decay_factor` is not in `(0, 1]`, or under the same conditions as
+    /// [`FrequentItemsSketch::new`].
+    pub fn new(max_map_size: usize, decay_factor: f64) -> Self {
+        match Self::try_new(max_map_size, decay_factor) {
+            Ok(sketch) => sketch,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Creates a new sketch with the given maximum map size (power of two) and the factor every
+    /// [`decay`](Self::decay) multiplies the counts by.
+    ///
+    /// The panicking version of this method is [`DecayedFrequentItems::new`].
+    ///
+    /// # Errors
+    ///
+    /// If `decay_factor` is not in `(0, 1]`, or under the same conditions as
+    /// [`FrequentItemsSketch::try_new`].
+    pub fn try_new(max_map_size: usize, decay_factor: f64) -> Result<Self, Error> {
+        if !(decay_factor > 0.0 && decay_factor <= 1.0) {
+            return Err(Error::invalid_argument("decay_factor must be in (0, 1]")
+                .with_context("decay_factor", decay_factor));
+        }
+        Ok(Self {
+            sketch: FrequentItemsSketch::try_new(max_map_size)?,
+            decay_factor,
+        })
+    }
+
+    /// Returns the factor every [`decay`](Self::decay) multiplies the counts by.
+    pub fn decay_factor(&self) -> f64 {
+        self.decay_factor
+    }
+
+    /// Returns true if the sketch tracks no item.
+    pub fn is_empty(&self) -> bool {
+        self.sketch.is_empty()
+    }
+
+    /// Returns the number of items being tracked.
+    pub fn num_active_items(&self) -> usize {
+        self.sketch.num_active_items()
+    }
+
+    /// Returns the decayed total weight of the stream.
+    pub fn total_weight(&self) -> f64 {
+        to_weight(self.sketch.total_weight())
+    }
+
+    /// Updates the sketch with a weight of one.
+    pub fn update(&mut self, item: T) {
+        self.update_with_weight(item, 1.0);
+    }
+
+    /// Updates the sketch with an item and weight.
+    ///
+    /// A weight below the resolution of the counts is a no-op.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is negative or not finite, or if the decayed total weight of the
+    /// stream overflows.
+    pub fn update_with_weight(&mut self, item: T, weight: f64) {
+        assert!(
+            weight.is_finite() && weight >= 0.0,
+            "weight must be finite and non-negative, got {weight}"
+        );
+        self.sketch
+            .update_with_count(item, (weight * UNIT).round() as u64);
+    }
+
+    /// Multiplies all counts by the decay factor, i.e. advances the sketch by one tick.
+    pub fn decay(&mut self) {
+        self.sketch.scale(self.decay_factor);
+    }
+
+    /// Advances the sketch by `ticks` ticks at once, multiplying all counts by the decay factor
+    /// to the power of `ticks`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::DecayedFrequentItems;
+    /// let mut sketch = DecayedFrequentItems::new(64, 0.5);
+    /// sketch.update_with_weight(1, 8.0);
+    /// sketch.advance(3);
+    /// assert_eq!(sketch.estimate(&1), 1.0);
+    /// ```
+    pub fn advance(&mut self, ticks: u32) {
+        if ticks > 0 {
+            self.sketch.scale(self.decay_factor.powf(f64::from(ticks)));
+        }
+    }
+
+    /// Returns the estimated decayed frequency of an item, zero if the item is not tracked.
+    pub fn estimate(&self, item: &T) -> f64 {
+        to_weight(self.sketch.estimate(item))
+    }
+
+    /// Returns the lower bound for the decayed frequency of an item.
+    pub fn lower_bound(&self, item: &T) -> f64 {
+        to_weight(self.sketch.lower_bound(item))
+    }
+
+    /// Returns the upper bound for the decayed frequency of an item.
+    pub fn upper_bound(&self, item: &T) -> f64 {
+        to_weight(self.sketch.upper_bound(item))
+    }
+
+    /// Returns the maximum error of [`estimate`](Self::estimate) for any item.
+    pub fn maximum_error(&self) -> f64 {
+        to_weight(self.sketch.maximum_error())
+    }
+
+    /// Merges another sketch into this one, adding its current decayed counts.
+    ///
+    /// Both sketches should have been decayed up to the same tick. The merged sketch keeps the
+    /// decay factor of this one.
+    pub fn merge(&mut self, other: &Self)
+    where
+        T: Clone,
+    {
+        self.sketch.merge(&other.sketch);
+    }
+
+    /// Returns frequent items using the sketch maximum error as threshold, sorted by descending
+    /// estimate.
+    pub fn frequent_items(&self, error_type: ErrorType) -> Vec<DecayedRow<T>>
+    where
+        T: Clone,
+    {
+        self.sketch
+            .frequent_items(error_type)
+            .into_iter()
+            .map(DecayedRow::from_scaled)
+            .collect()
+    }
+
+    /// Returns the `k` items currently ranked highest by their lower bounds, as in
+    /// [`FrequentItemsSketch::top_k`].
+    pub fn top_k(&self, k: usize, error_type: ErrorType) -> Vec<DecayedRow<T>>
+    where
+        T: Clone,
+    {
+        self.sketch
+            .top_k(k, error_type)
+            .into_iter()
+            .map(DecayedRow::from_scaled)
+            .collect()
+    }
+
+    /// Resets the sketch to an empty state.
+    pub fn reset(&mut self) {
+        self.sketch.reset();
+    }
+}
+
+fn to_weight(count: u64) -> f64 {
+    count as f64 / UNIT
+}
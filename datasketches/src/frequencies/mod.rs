@@ -35,8 +35,15 @@
 //! * Return a global maximum error that holds for all items in the stream.
 //! * Return an array of frequent items that qualify either [`ErrorType::NoFalsePositives`] or
 //!   [`ErrorType::NoFalseNegatives`].
+//! * Return the top-k items ranked by their lower bounds.
 //! * Merge itself with another sketch created from this module.
-//! * Serialize to bytes, or deserialize from bytes, for storage or transmission.
+//! * Spread concurrent updates over several sketches with [`ShardedFrequentItems`].
+//! * Keep a payload, e.g. a last-seen timestamp, for every tracked item with
+//!   [`FrequentItemsWithPayload`].
+//! * Find the items that are frequent now rather than over the whole stream, with counts that decay
+//!   exponentially per tick, using [`DecayedFrequentItems`].
+//! * Serialize to bytes, or deserialize from bytes, for storage or transmission, either in memory
+//!   or streaming through [`std::io::Write`] and [`std::io::Read`].
 //!
 //! # Accuracy
 //!
@@ -61,6 +68,7 @@
 //! probability is extremely small. For example, if the stream causes one table purge (rebuild),
 //! our proof of the worst case bound applies with probability at least `1 - 1E-14`. If the stream
 //! causes `1E9` purges, our proof applies with probability at least `1 - 1E-5`.
+//! [`PurgeStrategy::ExactMedian`] trades this sampling for an exact median on every purge.
 //!
 //! # Background
 //!
@@ -98,11 +106,25 @@
 //! assert!(decoded.estimate(&42) >= 2);
 //! ```
 
+mod decayed;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod payload;
 mod reverse_purge_item_hash_map;
 mod serialization;
+mod sharded;
 mod sketch;
 
+pub use self::decayed::DecayedFrequentItems;
+pub use self::decayed::DecayedRow;
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub use self::parallel::parallel_merge;
+pub use self::payload::FrequentItemsWithPayload;
 pub use self::serialization::FrequentItemValue;
+pub(crate) use self::serialization::inspect;
+pub use self::sharded::ShardedFrequentItems;
 pub use self::sketch::ErrorType;
 pub use self::sketch::FrequentItemsSketch;
+pub use self::sketch::PurgeStrategy;
 pub use self::sketch::Row;
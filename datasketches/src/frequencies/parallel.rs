@@ -0,0 +1,73 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::hash::Hash;
+
+use rayon::prelude::*;
+
+use crate::frequencies::FrequentItemsSketch;
+
+/// Merges frequent items sketches in parallel on the rayon thread pool.
+///
+/// Every worker merges a share of the input into a copy of its first sketch, and the partial
+/// results are merged pairwise as a tree. The error guarantees are those of
+/// [`FrequentItemsSketch::merge`]; which items survive a purge may differ from a serial merge.
+///
+/// Returns `None` if `sketches` is empty.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::frequencies::FrequentItemsSketch;
+/// # use datasketches::frequencies::parallel_merge;
+/// let sketches: Vec<_> = (0..10)
+///     .map(|_| {
+///         let mut sketch = FrequentItemsSketch::<i64>::new(64);
+///         sketch.update(7);
+///         sketch
+///     })
+///     .collect();
+/// let merged = parallel_merge(&sketches).unwrap();
+/// assert_eq!(merged.estimate(&7), 10);
+/// ```
+pub fn parallel_merge<T>(sketches: &[FrequentItemsSketch<T>]) -> Option<FrequentItemsSketch<T>>
+where
+    T: Eq + Hash + Clone + Send + Sync,
+{
+    sketches
+        .par_iter()
+        .fold(
+            || None,
+            |merged: Option<FrequentItemsSketch<T>>, sketch| match merged {
+                None => Some(sketch.clone()),
+                Some(mut merged) => {
+                    merged.merge(sketch);
+                    Some(merged)
+                }
+            },
+        )
+        .reduce(
+            || None,
+            |left, right| match (left, right) {
+                (Some(mut left), Some(right)) => {
+                    left.merge(&right);
+                    Some(left)
+                }
+                (left, right) => left.or(right),
+            },
+        )
+}
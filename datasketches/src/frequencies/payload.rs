@@ -0,0 +1,222 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Frequent items sketch that keeps a payload per tracked item.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::error::Error;
+use crate::frequencies::ErrorType;
+use crate::frequencies::FrequentItemsSketch;
+use crate::frequencies::Row;
+
+/// A [`FrequentItemsSketch`] that keeps a payload, e.g. a last-seen timestamp or an example
+/// record, for every item it tracks.
+///
+/// Every update with a positive count stores its payload for the item, replacing the previous
+/// one; use [`update_with`](Self::update_with) to combine them instead. Payloads of items evicted
+/// by a purge are dropped with them, so the memory footprint stays proportional to the maximum
+/// map size, and heavy-hitter reports carry the context of their items without a second lookup
+/// store.
+///
+/// Payloads are not part of the serialized form; [`into_sketch`](Self::into_sketch) returns the
+/// underlying sketch for serialization.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::frequencies::ErrorType;
+/// # use datasketches::frequencies::FrequentItemsWithPayload;
+/// let mut sketch = FrequentItemsWithPayload::<&str, u64>::new(64);
+/// for (timestamp, page) in ["home", "cart", "home", "search", "home"]
+///     .into_iter()
+///     .enumerate()
+/// {
+///     sketch.update(page, timestamp as u64);
+/// }
+/// let (row, last_seen) = sketch
+///     .frequent_items(ErrorType::NoFalsePositives)
+///     .into_iter()
+///     .next()
+///     .unwrap();
+/// assert_eq!(*row.item(), "home");
+/// assert_eq!(last_seen, 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrequentItemsWithPayload<T, P> {
+    sketch: FrequentItemsSketch<T>,
+    payloads: HashMap<T, P>,
+}
+
+impl<T: Eq + Hash + Clone, P> FrequentItemsWithPayload<T, P> {
+    /// Creates a new sketch with the given maximum map size (power of two).
+    ///
+    /// The fallible version of this method is [`FrequentItemsWithPayload::try_new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`FrequentItemsSketch::new`].
+    pub fn new(max_map_size: usize) -> Self {
+        Self {
+            sketch: FrequentItemsSketch::new(max_map_size),
+            payloads: HashMap::new(),
+        }
+    }
+
+    /// Creates a new sketch with the given maximum map size (power of two).
+    ///
+    /// The panicking version of this method is [`FrequentItemsWithPayload::new`].
+    ///
+    /// # Errors
+    ///
+    /// Under the same conditions as [`FrequentItemsSketch::try_new`].
+    pub fn try_new(max_map_size: usize) -> Result<Self, Error> {
+        Ok(Self {
+            sketch: FrequentItemsSketch::try_new(max_map_size)?,
+            payloads: HashMap::new(),
+        })
+    }
+
+    /// Returns the underlying sketch, e.g. for its bounds and configuration.
+    pub fn sketch(&self) -> &FrequentItemsSketch<T> {
+        &self.sketch
+    }
+
+    /// Consumes the sketch and returns the underlying sketch without the payloads.
+    pub fn into_sketch(self) -> FrequentItemsSketch<T> {
+        self.sketch
+    }
+
+    /// Returns true if the sketch is empty.
+    pub fn is_empty(&self) -> bool {
+        self.sketch.is_empty()
+    }
+
+    /// Returns the payload of an item, or `None` if the item is not tracked.
+    pub fn payload(&self, item: &T) -> Option<&P> {
+        self.payloads.get(item)
+    }
+
+    /// Updates the sketch with a count of one, replacing the payload of the item.
+    pub fn update(&mut self, item: T, payload: P) {
+        self.update_with_count(item, 1, payload);
+    }
+
+    /// Updates the sketch with an item and count, replacing the payload of the item.
+    ///
+    /// A count of zero is a no-op and drops `payload`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total weight of the stream overflows `u64`.
+    pub fn update_with_count(&mut self, item: T, count: u64, payload: P) {
+        self.update_with(item, count, |_| payload);
+    }
+
+    /// Updates the sketch with an item and count, and sets the payload of the item to what
+    /// `payload` returns given the current one, `None` if the item is not tracked.
+    ///
+    /// A count of zero is a no-op and does not call `payload`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total weight of the stream overflows `u64`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsWithPayload;
+    /// // keep the first example record of every item
+    /// let mut sketch = FrequentItemsWithPayload::<u32, String>::new(64);
+    /// for (user, record) in [(1, "a"), (2, "b"), (1, "c")] {
+    ///     sketch.update_with(user, 1, |first| first.unwrap_or_else(|| record.to_string()));
+    /// }
+    /// assert_eq!(sketch.payload(&1).unwrap(), "a");
+    /// ```
+    pub fn update_with(&mut self, item: T, count: u64, payload: impl FnOnce(Option<P>) -> P) {
+        if count == 0 {
+            return;
+        }
+        let offset = self.sketch.maximum_error();
+        self.sketch.update_with_count(item.clone(), count);
+        if self.sketch.maximum_error() != offset {
+            // a purge evicted items, possibly including this one
+            self.drop_untracked();
+            if self.sketch.lower_bound(&item) == 0 {
+                return;
+            }
+        }
+        let current = self.payloads.remove(&item);
+        self.payloads.insert(item, payload(current));
+    }
+
+    /// Merges another sketch into this one.
+    ///
+    /// Counts merge as in [`FrequentItemsSketch::merge`]. Items tracked by both sketches keep
+    /// the payload of this one.
+    pub fn merge(&mut self, other: &Self)
+    where
+        P: Clone,
+    {
+        self.sketch.merge(&other.sketch);
+        for (item, payload) in &other.payloads {
+            if !self.payloads.contains_key(item) {
+                self.payloads.insert(item.clone(), payload.clone());
+            }
+        }
+        self.drop_untracked();
+    }
+
+    /// Returns frequent items with their payloads, using the sketch maximum error as threshold.
+    ///
+    /// Rows are sorted by descending estimate, as in [`FrequentItemsSketch::frequent_items`].
+    pub fn frequent_items(&self, error_type: ErrorType) -> Vec<(Row<T>, P)>
+    where
+        P: Clone,
+    {
+        self.sketch
+            .frequent_items(error_type)
+            .into_iter()
+            .map(|row| {
+                let payload = self.payloads[row.item()].clone();
+                (row, payload)
+            })
+            .collect()
+    }
+
+    /// Returns an iterator over the rows of all tracked items with their payloads.
+    ///
+    /// Rows are yielded in no particular order and borrow their items and payloads from the
+    /// sketch.
+    pub fn iter(&self) -> impl Iterator<Item = (Row<&T>, &P)> + '_ {
+        self.sketch
+            .iter()
+            .map(|row| (row.clone(), &self.payloads[*row.item()]))
+    }
+
+    /// Resets the sketch to an empty state, dropping all payloads.
+    pub fn reset(&mut self) {
+        self.sketch.reset();
+        self.payloads.clear();
+    }
+
+    fn drop_untracked(&mut self) {
+        let sketch = &self.sketch;
+        self.payloads.retain(|item, _| sketch.lower_bound(item) > 0);
+    }
+}
@@ -17,9 +17,13 @@
 
 use std::hash::Hash;
 
-use crate::codec::SketchBytes;
+use crate::codec::ItemSerde;
 use crate::codec::SketchSlice;
+use crate::codec::assert::ensure_serial_version_is;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
 use crate::error::Error;
+use crate::inspect::SketchInfo;
 
 /// Serialization version.
 pub const SERIAL_VERSION: u8 = 1;
@@ -32,63 +36,52 @@ pub const PREAMBLE_LONGS_NONEMPTY: u8 = 4;
 /// Empty flag mask (both bits for compatibility).
 pub const EMPTY_FLAG_MASK: u8 = 5;
 
-/// Trait for serializing and deserializing frequent item values.
-pub trait FrequentItemValue: Sized + Eq + Hash + Clone {
-    /// Returns the size in bytes required to serialize the given item.
-    fn serialize_size(item: &Self) -> usize;
-    /// Serializes the item into the given byte buffer.
-    fn serialize_value(&self, bytes: &mut SketchBytes);
-    /// Deserializes an item from the given byte cursor.
-    fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error>;
-}
-
-impl FrequentItemValue for String {
-    fn serialize_size(item: &Self) -> usize {
-        size_of::<u32>() + item.len()
-    }
-
-    fn serialize_value(&self, bytes: &mut SketchBytes) {
-        let bs = self.as_bytes();
-        bytes.write_u32_le(bs.len() as u32);
-        bytes.write(bs);
-    }
+/// Reads the preamble of an image for [`inspect`](crate::inspect).
+pub(crate) fn inspect(
+    _pre_longs: u8,
+    serial_version: u8,
+    mut cursor: SketchSlice<'_>,
+) -> Result<SketchInfo, Error> {
+    ensure_serial_version_is(SERIAL_VERSION, serial_version)?;
+    let lg_max = cursor
+        .read_u8()
+        .map_err(insufficient_data("lg_max_map_size"))?;
+    cursor
+        .read_u8()
+        .map_err(insufficient_data("lg_cur_map_size"))?;
+    let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+    cursor
+        .read_u16_le()
+        .map_err(insufficient_data("<unused>"))?;
 
-    fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error> {
-        let len = cursor.read_u32_le().map_err(|_| {
-            Error::insufficient_data("failed to read string item length".to_string())
-        })?;
-
-        let mut slice = vec![0; len as usize];
-        cursor.read_exact(&mut slice).map_err(|_| {
-            Error::insufficient_data("failed to read string item bytes".to_string())
-        })?;
-
-        String::from_utf8(slice)
-            .map_err(|_| Error::deserial("invalid UTF-8 string payload".to_string()))
+    let mut info = SketchInfo::new(&Family::FREQUENCY, serial_version);
+    info.lg_k = Some(lg_max);
+    info.is_empty = (flags & EMPTY_FLAG_MASK) != 0;
+    if info.is_empty {
+        info.is_estimation_mode = Some(false);
+        info.num_retained = Some(0);
+        return Ok(info);
     }
+    let active_items = cursor
+        .read_u32_le()
+        .map_err(insufficient_data("active_items"))?;
+    cursor
+        .read_u32_le()
+        .map_err(insufficient_data("<unused>"))?;
+    cursor
+        .read_u64_le()
+        .map_err(insufficient_data("stream_weight"))?;
+    let offset = cursor.read_u64_le().map_err(insufficient_data("offset"))?;
+    info.is_estimation_mode = Some(offset > 0);
+    info.num_retained = Some(active_items as u64);
+    Ok(info)
 }
 
-macro_rules! impl_primitive {
-    ($name:ty, $read:ident, $write:ident) => {
-        impl FrequentItemValue for $name {
-            fn serialize_size(_item: &Self) -> usize {
-                size_of::<$name>()
-            }
-
-            fn serialize_value(&self, bytes: &mut SketchBytes) {
-                bytes.$write(*self);
-            }
-
-            fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error> {
-                cursor.$read().map_err(|_| {
-                    Error::insufficient_data(
-                        concat!("failed to read ", stringify!($name), " item bytes").to_string(),
-                    )
-                })
-            }
-        }
-    };
-}
+/// Trait for the item types of [`FrequentItemsSketch`](crate::frequencies::FrequentItemsSketch).
+///
+/// Items are serialized with [`ItemSerde`], and this trait is implemented for every type that
+/// implements it along with [`Eq`], [`Hash`] and [`Clone`]. Only the encodings of `i64`, `u64`
+/// and [`String`] match the item serdes of datasketches-java.
+pub trait FrequentItemValue: ItemSerde + Eq + Hash + Clone {}
 
-impl_primitive!(i64, read_i64_le, write_i64_le);
-impl_primitive!(u64, read_u64_le, write_u64_le);
+impl<T: ItemSerde + Eq + Hash + Clone> FrequentItemValue for T {}
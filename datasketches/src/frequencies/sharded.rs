@@ -0,0 +1,246 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Frequent items sketch sharded for concurrent updates.
+
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+
+use crate::error::Error;
+use crate::frequencies::ErrorType;
+use crate::frequencies::FrequentItemsSketch;
+use crate::frequencies::Row;
+use crate::hash::MurmurHash3X64128;
+
+/// Heavy-hitters aggregator that spreads items over several [`FrequentItemsSketch`] shards.
+///
+/// Every item is routed by its hash to exactly one shard, and each shard sits behind its own
+/// lock, so threads updating different items rarely contend. All methods take `&self`, so the
+/// aggregator can be shared between threads directly or through an `Arc`.
+///
+/// Since the shards see disjoint sets of items, the bounds of an item are those of its own
+/// shard, and the maximum error over all items is the largest maximum error of any shard, not
+/// the sum. Each shard is configured with `max_map_size`, so the memory footprint is up to
+/// `num_shards` times that of a single sketch, and the error of a shard is relative to the
+/// weight of the items routed to it.
+///
+/// # Examples
+///
+/// ```
+/// # use std::thread;
+/// # use datasketches::frequencies::ErrorType;
+/// # use datasketches::frequencies::ShardedFrequentItems;
+/// let sketch = ShardedFrequentItems::<u64>::new(4, 64);
+/// thread::scope(|s| {
+///     for t in 0..4 {
+///         let sketch = &sketch;
+///         s.spawn(move || {
+///             for i in 0..1000 {
+///                 sketch.update(i % 10 + t);
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(sketch.total_weight(), 4000);
+/// let rows = sketch.frequent_items(ErrorType::NoFalsePositives);
+/// assert!(rows.iter().any(|row| *row.item() == 5));
+/// ```
+#[derive(Debug)]
+pub struct ShardedFrequentItems<T> {
+    shards: Box<[Mutex<FrequentItemsSketch<T>>]>,
+}
+
+impl<T: Eq + Hash> ShardedFrequentItems<T> {
+    /// Creates a new aggregator with `num_shards` shards of the given maximum map size.
+    ///
+    /// The fallible version of this method is [`ShardedFrequentItems::try_new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_shards` is zero or if `max_map_size` is not a valid map size for
+    /// [`FrequentItemsSketch::new`].
+    pub fn new(num_shards: usize, max_map_size: usize) -> Self {
+        assert!(num_shards > 0, "num_shards must be positive");
+        let shards = (0..num_shards)
+            .map(|_| Mutex::new(FrequentItemsSketch::new(max_map_size)))
+            .collect();
+        Self { shards }
+    }
+
+    /// Creates a new aggregator with `num_shards` shards of the given maximum map size.
+    ///
+    /// The panicking version of this method is [`ShardedFrequentItems::new`].
+    ///
+    /// # Errors
+    ///
+    /// If `num_shards` is zero or if `max_map_size` is not a valid map size for
+    /// [`FrequentItemsSketch::try_new`].
+    pub fn try_new(num_shards: usize, max_map_size: usize) -> Result<Self, Error> {
+        if num_shards == 0 {
+            return Err(Error::invalid_argument("num_shards must be positive"));
+        }
+        FrequentItemsSketch::<T>::try_new(max_map_size)?;
+        Ok(Self::new(num_shards, max_map_size))
+    }
+
+    /// Returns the number of shards.
+    pub fn num_shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns true if no shard has seen any item.
+    pub fn is_empty(&self) -> bool {
+        (0..self.shards.len()).all(|i| self.lock(i).is_empty())
+    }
+
+    /// Returns the total weight of the stream over all shards.
+    ///
+    /// The sum saturates at `u64::MAX`.
+    pub fn total_weight(&self) -> u64 {
+        (0..self.shards.len())
+            .map(|i| self.lock(i).total_weight())
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Returns an upper bound on the maximum error of [`estimate`](Self::estimate) for any item.
+    ///
+    /// This is the largest maximum error of any shard.
+    pub fn maximum_error(&self) -> u64 {
+        (0..self.shards.len())
+            .map(|i| self.lock(i).maximum_error())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Updates the aggregator with a count of one.
+    pub fn update(&self, item: T) {
+        self.update_with_count(item, 1);
+    }
+
+    /// Updates the aggregator with an item and count.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total weight of the shard of `item` overflows `u64`.
+    pub fn update_with_count(&self, item: T, count: u64) {
+        self.lock(self.shard_of(&item))
+            .update_with_count(item, count);
+    }
+
+    /// Updates the aggregator with an item and count, failing if the total weight of the shard
+    /// of `item` would overflow.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the total weight of the shard would exceed `u64::MAX`.
+    pub fn try_update_with_count(&self, item: T, count: u64) -> Result<(), Error> {
+        self.lock(self.shard_of(&item))
+            .try_update_with_count(item, count)
+    }
+
+    /// Returns the estimated frequency for an item.
+    pub fn estimate(&self, item: &T) -> u64 {
+        self.lock(self.shard_of(item)).estimate(item)
+    }
+
+    /// Returns the guaranteed lower bound frequency for an item.
+    pub fn lower_bound(&self, item: &T) -> u64 {
+        self.lock(self.shard_of(item)).lower_bound(item)
+    }
+
+    /// Returns the guaranteed upper bound frequency for an item.
+    pub fn upper_bound(&self, item: &T) -> u64 {
+        self.lock(self.shard_of(item)).upper_bound(item)
+    }
+
+    /// Returns frequent items of all shards using [`maximum_error`](Self::maximum_error) as
+    /// threshold.
+    ///
+    /// Rows carry the bounds of their own shard and are sorted by descending estimate.
+    pub fn frequent_items(&self, error_type: ErrorType) -> Vec<Row<T>>
+    where
+        T: Clone,
+    {
+        self.frequent_items_with_threshold(error_type, self.maximum_error())
+    }
+
+    /// Returns frequent items of all shards using a custom threshold.
+    ///
+    /// If `threshold` is less than the maximum error of a shard, that maximum error is used
+    /// instead for the items of the shard. See
+    /// [`FrequentItemsSketch::frequent_items_with_threshold`].
+    pub fn frequent_items_with_threshold(
+        &self,
+        error_type: ErrorType,
+        threshold: u64,
+    ) -> Vec<Row<T>>
+    where
+        T: Clone,
+    {
+        let mut rows = vec![];
+        for i in 0..self.shards.len() {
+            rows.extend(
+                self.lock(i)
+                    .frequent_items_with_threshold(error_type, threshold),
+            );
+        }
+        rows.sort_by_key(|row| std::cmp::Reverse(row.estimate()));
+        rows
+    }
+
+    /// Merges all shards into a single sketch, e.g., for serialization.
+    ///
+    /// The result is configured with the maximum map size of the shards. Merging may purge
+    /// items, so its bounds can be looser than those reported by the aggregator itself.
+    pub fn to_sketch(&self) -> FrequentItemsSketch<T>
+    where
+        T: Clone,
+    {
+        let mut merged = self.lock(0).clone();
+        for i in 1..self.shards.len() {
+            merged.merge(&self.lock(i));
+        }
+        merged
+    }
+
+    /// Consumes the aggregator and returns its shards.
+    pub fn into_shards(self) -> Vec<FrequentItemsSketch<T>> {
+        self.shards
+            .into_vec()
+            .into_iter()
+            .map(|shard| shard.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .collect()
+    }
+
+    fn shard_of(&self, item: &T) -> usize {
+        // The shard maps probe with the low half of the same hash, so route with the high half
+        // to keep the items of one shard spread over its map.
+        let mut hasher = MurmurHash3X64128::default();
+        item.hash(&mut hasher);
+        let (_, high) = hasher.finish128();
+        (high % self.shards.len() as u64) as usize
+    }
+
+    fn lock(&self, index: usize) -> MutexGuard<'_, FrequentItemsSketch<T>> {
+        // A panicking update leaves the sketch unchanged, so a poisoned shard is still valid.
+        self.shards[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
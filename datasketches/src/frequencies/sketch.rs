@@ -17,14 +17,27 @@
 
 //! Frequent items sketch implementations.
 
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
 use std::hash::Hash;
+use std::io;
+use std::io::Read;
+use std::io::Write;
 
 use crate::codec::SketchBytes;
 use crate::codec::SketchSlice;
 use crate::codec::assert::ensure_preamble_longs_in;
 use crate::codec::assert::ensure_serial_version_is;
 use crate::codec::assert::insufficient_data;
+use crate::codec::assert::read_error;
 use crate::codec::family::Family;
+use crate::codec::serialize_into_slice;
+use crate::codec::serialize_into_writer;
+use crate::common::MergeableSketch;
+use crate::common::SerializableSketch;
+use crate::common::Sketch;
+use crate::common::Summary;
 use crate::error::Error;
 use crate::frequencies::FrequentItemValue;
 use crate::frequencies::reverse_purge_item_hash_map::ReversePurgeItemHashMap;
@@ -33,15 +46,15 @@ use crate::frequencies::serialization::PREAMBLE_LONGS_EMPTY;
 use crate::frequencies::serialization::PREAMBLE_LONGS_NONEMPTY;
 use crate::frequencies::serialization::SERIAL_VERSION;
 
-type CountSerializeSize<T> = fn(&[T]) -> usize;
-type SerializeItems<T> = fn(&mut SketchBytes, &[T]);
-type DeserializeItems<T> = fn(SketchSlice<'_>, usize) -> Result<Vec<T>, Error>;
-
 const LG_MIN_MAP_SIZE: u8 = 3;
+/// Largest supported map size, matching the limit of the reference implementations.
+const LG_MAX_MAP_SIZE: u8 = 30;
 const SAMPLE_SIZE: usize = 1024;
 const EPSILON_FACTOR: f64 = 3.5;
 const LOAD_FACTOR_NUMERATOR: usize = 3;
 const LOAD_FACTOR_DENOMINATOR: usize = 4;
+/// Upper bound of the number of weights preallocated while deserializing untrusted input.
+const MAX_PREALLOCATED_ITEMS: usize = 1 << 16;
 
 /// Error guarantees for frequent item queries.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -52,15 +65,47 @@ pub enum ErrorType {
     NoFalsePositives,
 }
 
+/// How a [`FrequentItemsSketch`] picks the amount to subtract from every counter when its map is
+/// full.
+///
+/// Every purge subtracts the median counter and evicts the counters that drop to zero; the
+/// strategies differ in which counters the median is taken over.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PurgeStrategy {
+    /// Take the median of the counters in the first 1024 occupied slots of the map, as
+    /// datasketches-java and datasketches-cpp do.
+    ///
+    /// This is cheap, but the sample depends on where the items landed in the map, so two
+    /// sketches holding the same counters in a different layout, e.g. after
+    /// [`shrink_to_fit`](FrequentItemsSketch::shrink_to_fit) or a serialization round trip,
+    /// may purge differently.
+    #[default]
+    Sampled,
+    /// Take the exact median of all counters.
+    ///
+    /// Purges depend only on the counters, not on the layout of the map, which makes sketch
+    /// states reproducible for tests and audited pipelines. Each purge costs a scan and a
+    /// selection over all counters instead of at most 1024 of them.
+    ExactMedian,
+}
+
 /// Result row for frequent item queries.
 ///
-/// Each row includes an estimate and upper and lower bounds on the true frequency.
+/// Each row includes an estimate and upper and lower bounds on the true frequency, and whether
+/// the item is [guaranteed](Row::is_guaranteed) to be frequent. With the `serde` feature enabled,
+/// rows serialize as flat
+/// `{"item": .., "estimate": .., "upper_bound": .., "lower_bound": .., "guaranteed": ..}`
+/// records, so query results can be returned from an API as they are; rows borrowed from
+/// [`FrequentItemsSketch::iter`] serialize the same way.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Row<T> {
     item: T,
     estimate: u64,
     upper_bound: u64,
     lower_bound: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    guaranteed: bool,
 }
 
 impl<T> Row<T> {
@@ -83,6 +128,35 @@ impl<T> Row<T> {
     pub fn lower_bound(&self) -> u64 {
         self.lower_bound
     }
+
+    /// Returns true if the lower bound exceeds the threshold the row was selected with, so the
+    /// item is certainly frequent; otherwise the item is only possibly frequent.
+    ///
+    /// Rows of [`frequent_items`](FrequentItemsSketch::frequent_items) and
+    /// [`iter`](FrequentItemsSketch::iter) are compared against
+    /// [`threshold`](FrequentItemsSketch::threshold), rows of
+    /// [`frequent_items_with_threshold`](FrequentItemsSketch::frequent_items_with_threshold)
+    /// against the threshold it applied.
+    pub fn is_guaranteed(&self) -> bool {
+        self.guaranteed
+    }
+
+    pub(super) fn into_item(self) -> T {
+        self.item
+    }
+}
+
+impl<T: Clone> Row<&T> {
+    /// Maps a `Row<&T>` to a `Row<T>` by cloning the item.
+    pub fn cloned(self) -> Row<T> {
+        Row {
+            item: self.item.clone(),
+            estimate: self.estimate,
+            upper_bound: self.upper_bound,
+            lower_bound: self.lower_bound,
+            guaranteed: self.guaranteed,
+        }
+    }
 }
 
 /// Frequent items sketch for generic item types.
@@ -98,6 +172,7 @@ pub struct FrequentItemsSketch<T> {
     offset: u64,
     stream_weight: u64,
     sample_size: usize,
+    purge_strategy: PurgeStrategy,
     hash_map: ReversePurgeItemHashMap<T>,
 }
 
@@ -105,11 +180,14 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
     /// Creates a new sketch with the given maximum map size (power of two).
     ///
     /// The maximum map capacity is `0.75 * max_map_size`, and the internal map grows
-    /// from a small starting size up to the maximum as needed.
+    /// from a small starting size up to the maximum as needed. Full maps are purged with
+    /// [`PurgeStrategy::Sampled`].
+    ///
+    /// The fallible version of this method is [`FrequentItemsSketch::try_new`].
     ///
     /// # Panics
     ///
-    /// Panics if `max_map_size` is not a power of two.
+    /// Panics if `max_map_size` is not a power of two, or is larger than `2^30`.
     ///
     /// # Examples
     ///
@@ -126,9 +204,167 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
             "max_map_size must be power of 2"
         );
         let lg_max_map_size = max_map_size.trailing_zeros() as u8;
+        assert!(
+            lg_max_map_size <= LG_MAX_MAP_SIZE,
+            "max_map_size must not exceed 2^{LG_MAX_MAP_SIZE}"
+        );
         Self::with_lg_map_sizes(lg_max_map_size, LG_MIN_MAP_SIZE)
     }
 
+    /// Creates a new sketch with the given maximum map size (power of two).
+    ///
+    /// The panicking version of this method is [`FrequentItemsSketch::new`].
+    ///
+    /// # Errors
+    ///
+    /// If `max_map_size` is not a power of two, or is larger than `2^30`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// assert!(FrequentItemsSketch::<i64>::try_new(100).is_err());
+    /// let sketch = FrequentItemsSketch::<i64>::try_new(64).unwrap();
+    /// assert_eq!(sketch.lg_max_map_size(), 6);
+    /// ```
+    pub fn try_new(max_map_size: usize) -> Result<Self, Error> {
+        if !max_map_size.is_power_of_two() {
+            return Err(Error::invalid_argument(format!(
+                "max_map_size must be power of 2, got {max_map_size}"
+            )));
+        }
+        if max_map_size.trailing_zeros() > LG_MAX_MAP_SIZE as u32 {
+            return Err(Error::invalid_argument(format!(
+                "max_map_size must not exceed 2^{LG_MAX_MAP_SIZE}, got {max_map_size}"
+            )));
+        }
+        Ok(Self::new(max_map_size))
+    }
+
+    /// Creates a new sketch with the given maximum map size (power of two) that purges its map
+    /// with the given strategy.
+    ///
+    /// The fallible version of this method is [`FrequentItemsSketch::try_with_purge_strategy`].
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`FrequentItemsSketch::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// # use datasketches::frequencies::PurgeStrategy;
+    /// let mut sketch =
+    ///     FrequentItemsSketch::<i64>::with_purge_strategy(64, PurgeStrategy::ExactMedian);
+    /// for i in 0..1_000 {
+    ///     sketch.update(i % 100);
+    /// }
+    /// assert_eq!(sketch.purge_strategy(), PurgeStrategy::ExactMedian);
+    /// ```
+    pub fn with_purge_strategy(max_map_size: usize, purge_strategy: PurgeStrategy) -> Self {
+        let mut sketch = Self::new(max_map_size);
+        sketch.purge_strategy = purge_strategy;
+        sketch
+    }
+
+    /// Creates a new sketch with the given maximum map size (power of two) that purges its map
+    /// with the given strategy.
+    ///
+    /// The panicking version of this method is [`FrequentItemsSketch::with_purge_strategy`].
+    ///
+    /// # Errors
+    ///
+    /// Under the same conditions as [`FrequentItemsSketch::try_new`].
+    pub fn try_with_purge_strategy(
+        max_map_size: usize,
+        purge_strategy: PurgeStrategy,
+    ) -> Result<Self, Error> {
+        let mut sketch = Self::try_new(max_map_size)?;
+        sketch.purge_strategy = purge_strategy;
+        Ok(sketch)
+    }
+
+    /// Creates a new sketch with the given maximum map size (power of two) whose map starts at
+    /// `2^initial_lg_size` slots.
+    ///
+    /// [`new`](Self::new) starts from a small map that doubles as items arrive. A smaller start
+    /// keeps sketches that only ever see a few items small, while starting at a larger size,
+    /// up to `max_map_size`, skips the rehashes of the early growth on heavy streams. Sizes
+    /// below the minimum of 8 slots are raised to it. This is the equivalent of the
+    /// `ItemsSketch(lgMaxMapSize, lgCurMapSize)` constructor of datasketches-java.
+    ///
+    /// The fallible version of this method is [`FrequentItemsSketch::try_with_initial_capacity`].
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`FrequentItemsSketch::new`], or if
+    /// `2^initial_lg_size` exceeds `max_map_size`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let mut sketch = FrequentItemsSketch::<i64>::with_initial_capacity(1 << 16, 16);
+    /// assert_eq!(sketch.lg_cur_map_size(), 16);
+    /// for i in 0..10_000 {
+    ///     sketch.update(i);
+    /// }
+    /// assert_eq!(sketch.lg_cur_map_size(), 16);
+    /// ```
+    pub fn with_initial_capacity(max_map_size: usize, initial_lg_size: u8) -> Self {
+        let sketch = Self::new(max_map_size);
+        assert!(
+            initial_lg_size <= sketch.lg_max_map_size,
+            "initial_lg_size must not exceed lg of max_map_size {}, got {initial_lg_size}",
+            sketch.lg_max_map_size
+        );
+        Self::with_lg_map_sizes(sketch.lg_max_map_size, initial_lg_size)
+    }
+
+    /// Creates a new sketch with the given maximum map size (power of two) whose map starts at
+    /// `2^initial_lg_size` slots.
+    ///
+    /// The panicking version of this method is [`FrequentItemsSketch::with_initial_capacity`].
+    ///
+    /// # Errors
+    ///
+    /// Under the same conditions as [`FrequentItemsSketch::try_new`], or if `2^initial_lg_size`
+    /// exceeds `max_map_size`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// assert!(FrequentItemsSketch::<i64>::try_with_initial_capacity(64, 7).is_err());
+    /// let sketch = FrequentItemsSketch::<i64>::try_with_initial_capacity(64, 6).unwrap();
+    /// assert_eq!(sketch.lg_cur_map_size(), 6);
+    /// ```
+    pub fn try_with_initial_capacity(
+        max_map_size: usize,
+        initial_lg_size: u8,
+    ) -> Result<Self, Error> {
+        let sketch = Self::try_new(max_map_size)?;
+        if initial_lg_size > sketch.lg_max_map_size {
+            return Err(Error::invalid_argument(format!(
+                "initial_lg_size must not exceed lg of max_map_size {}, got {initial_lg_size}",
+                sketch.lg_max_map_size
+            )));
+        }
+        Ok(Self::with_lg_map_sizes(
+            sketch.lg_max_map_size,
+            initial_lg_size,
+        ))
+    }
+
+    /// Returns the strategy used to purge the map when it is full.
+    ///
+    /// Serialized images do not record it: deserialized sketches use
+    /// [`PurgeStrategy::Sampled`].
+    pub fn purge_strategy(&self) -> PurgeStrategy {
+        self.purge_strategy
+    }
+
     /// Returns true if the sketch is empty.
     pub fn is_empty(&self) -> bool {
         self.hash_map.num_active() == 0
@@ -139,6 +375,14 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
         self.hash_map.num_active()
     }
 
+    /// Returns the number of bytes of memory the sketch occupies, counting every slot of the
+    /// current hash map rather than only the active items.
+    ///
+    /// Memory owned by the items themselves, such as the contents of a `String`, is not counted.
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>() + self.hash_map.heap_size_bytes()
+    }
+
     /// Returns the total weight of the stream.
     ///
     /// This is the sum of all counts passed to `update` and `update_with_count`.
@@ -160,7 +404,11 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
     /// ```
     pub fn estimate(&self, item: &T) -> u64 {
         let value = self.hash_map.get(item);
-        if value > 0 { value + self.offset } else { 0 }
+        if value > 0 {
+            value.saturating_add(self.offset)
+        } else {
+            0
+        }
     }
 
     /// Returns the guaranteed lower bound frequency for an item.
@@ -176,7 +424,7 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
     /// This value is guaranteed to be no smaller than the true frequency. If the item is tracked,
     /// this is `item_count + offset`.
     pub fn upper_bound(&self, item: &T) -> u64 {
-        self.hash_map.get(item) + self.offset
+        self.hash_map.get(item).saturating_add(self.offset)
     }
 
     /// Returns an upper bound on the maximum error of [`FrequentItemsSketch::estimate`]
@@ -188,6 +436,31 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
         self.offset
     }
 
+    /// Returns the frequency threshold of [`frequent_items`](Self::frequent_items): items whose
+    /// upper bound exceeds it are possibly frequent, and items whose lower bound exceeds it are
+    /// [guaranteed](Row::is_guaranteed) to be.
+    ///
+    /// This is the [`maximum_error`](Self::maximum_error) of the sketch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::ErrorType;
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let mut sketch = FrequentItemsSketch::<i64>::new(8);
+    /// sketch.update_with_count(0, 100);
+    /// for i in 1..=20 {
+    ///     sketch.update(i);
+    /// }
+    /// for row in sketch.frequent_items(ErrorType::NoFalseNegatives) {
+    ///     assert_eq!(row.is_guaranteed(), row.lower_bound() > sketch.threshold());
+    /// }
+    /// assert!(sketch.threshold() > 0);
+    /// ```
+    pub fn threshold(&self) -> u64 {
+        self.offset
+    }
+
     /// Returns epsilon for this sketch.
     pub fn epsilon(&self) -> f64 {
         Self::epsilon_for_lg(self.lg_max_map_size)
@@ -198,9 +471,48 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
         EPSILON_FACTOR / (1u64 << lg_max_map_size) as f64
     }
 
-    /// Returns the a priori error estimate.
-    pub fn apriori_error(lg_max_map_size: u8, estimated_total_weight: i64) -> f64 {
-        Self::epsilon_for_lg(lg_max_map_size) * estimated_total_weight as f64
+    /// Returns epsilon for a sketch configured with `max_map_size`.
+    ///
+    /// This is `3.5 / max_map_size`, and can be computed before any data is ingested.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_map_size` is not a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let epsilon = FrequentItemsSketch::<i64>::epsilon_for_map_size(1024);
+    /// assert_eq!(epsilon, 3.5 / 1024.0);
+    /// ```
+    pub fn epsilon_for_map_size(max_map_size: usize) -> f64 {
+        assert!(
+            max_map_size.is_power_of_two(),
+            "max_map_size must be power of 2"
+        );
+        EPSILON_FACTOR / max_map_size as f64
+    }
+
+    /// Returns the a priori error estimate for a sketch configured with `max_map_size`.
+    ///
+    /// This is the maximum error that the sketch is expected to have after ingesting a stream
+    /// with a total weight of `estimated_total_weight`, i.e.,
+    /// `epsilon_for_map_size(max_map_size) * estimated_total_weight`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_map_size` is not a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let error = FrequentItemsSketch::<i64>::apriori_error(1024, 1_000_000);
+    /// assert!(error < 3500.0);
+    /// ```
+    pub fn apriori_error(max_map_size: usize, estimated_total_weight: u64) -> f64 {
+        Self::epsilon_for_map_size(max_map_size) * estimated_total_weight as f64
     }
 
     /// Returns the maximum map capacity for this sketch.
@@ -245,6 +557,11 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
     ///
     /// A count of zero is a no-op.
     ///
+    /// # Panics
+    ///
+    /// Panics if the total weight of the stream overflows `u64`. Use
+    /// [`try_update_with_count`](Self::try_update_with_count) to handle this case.
+    ///
     /// # Examples
     ///
     /// ```
@@ -254,19 +571,59 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
     /// assert!(sketch.estimate(&10) >= 3);
     /// ```
     pub fn update_with_count(&mut self, item: T, count: u64) {
+        if let Err(err) = self.try_update_with_count(item, count) {
+            panic!("{err}");
+        }
+    }
+
+    /// Updates the sketch with an item and count, failing if the total weight would overflow.
+    ///
+    /// A count of zero is a no-op. Since every item count and estimate is bounded by the total
+    /// weight of the stream, a successful update never overflows any of them. On error, the
+    /// sketch is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the total weight of the stream would exceed `u64::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let mut sketch = FrequentItemsSketch::<i64>::new(64);
+    /// sketch.try_update_with_count(1, u64::MAX - 1).unwrap();
+    /// assert!(sketch.try_update_with_count(2, 2).is_err());
+    /// assert_eq!(sketch.total_weight(), u64::MAX - 1);
+    /// ```
+    pub fn try_update_with_count(&mut self, item: T, count: u64) -> Result<(), Error> {
         if count == 0 {
-            return;
+            return Ok(());
         }
-        assert!(count > 0, "count may not be negative");
-        self.stream_weight += count;
+        let stream_weight = self.stream_weight.checked_add(count).ok_or_else(|| {
+            Error::invalid_argument("total weight of the stream overflows u64")
+                .with_context("total_weight", self.stream_weight)
+                .with_context("count", count)
+        })?;
+        self.stream_weight = stream_weight;
         self.hash_map.adjust_or_put_value(item, count);
         self.maybe_resize_or_purge();
+        Ok(())
     }
 
     /// Merges another sketch into this one.
     ///
-    /// The other sketch may have a different map size. The merged sketch respects the
-    /// larger error tolerance of the inputs.
+    /// The other sketch may have a different map size. The merged sketch keeps the
+    /// configuration of this one, and respects the larger error tolerance of the inputs.
+    ///
+    /// The counters of `other` are added to this sketch as if they were updates, which may purge
+    /// the map. The [`maximum_error`](Self::maximum_error) of the result is the sum of the
+    /// maximum errors of both inputs plus whatever those purges subtract, so bounds remain
+    /// guaranteed across any tree of merges. The total weight is the sum of both total weights.
+    /// Weights, counters and the offset saturate at `u64::MAX` instead of overflowing, so
+    /// merging never panics. A saturated sketch still reports every lower bound correctly.
+    ///
+    /// Use [`merge_owned`](Self::merge_owned) to avoid cloning the items of `other` when it is
+    /// no longer needed.
     ///
     /// # Examples
     ///
@@ -286,17 +643,186 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
         if other.is_empty() {
             return;
         }
-        let merged_total = self.stream_weight + other.stream_weight;
         for (item, count) in other.hash_map.iter() {
-            self.update_with_count(item.clone(), count);
+            self.hash_map.adjust_or_put_value(item.clone(), count);
+            self.maybe_resize_or_purge();
+        }
+        self.offset = self.offset.saturating_add(other.offset);
+        self.stream_weight = self.stream_weight.saturating_add(other.stream_weight);
+    }
+
+    /// Merges two sketches, consuming both.
+    ///
+    /// This is equivalent to [`merge`](Self::merge), including the propagation of the maximum
+    /// error and the configuration of the result, which is that of `self`. Items of `other`
+    /// are moved rather than cloned, which makes this the cheaper choice for rollups of
+    /// sketches that are not used afterwards, e.g. in a fold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let partitions = (0..4).map(|p| {
+    ///     let mut sketch = FrequentItemsSketch::<String>::new(64);
+    ///     sketch.update_with_count(format!("item-{p}"), 10);
+    ///     sketch.update_with_count("common".to_string(), 5);
+    ///     sketch
+    /// });
+    ///
+    /// let merged = partitions.reduce(FrequentItemsSketch::merge_owned).unwrap();
+    /// assert_eq!(merged.estimate(&"common".to_string()), 20);
+    /// assert_eq!(merged.total_weight(), 60);
+    /// ```
+    pub fn merge_owned(mut self, other: Self) -> Self {
+        if other.is_empty() {
+            return self;
+        }
+        for (item, count) in other.hash_map.into_entries() {
+            self.hash_map.adjust_or_put_value(item, count);
+            self.maybe_resize_or_purge();
+        }
+        self.offset = self.offset.saturating_add(other.offset);
+        self.stream_weight = self.stream_weight.saturating_add(other.stream_weight);
+        self
+    }
+
+    /// Merges several sketches into this one with a single purge at the end.
+    ///
+    /// The counters of this sketch and of `others` are first summed exactly, then the result is
+    /// reduced to the [`maximum_map_capacity`](Self::maximum_map_capacity) of this sketch by
+    /// subtracting the largest counter that does not fit from every counter. Sequential
+    /// [`merge`](Self::merge) calls may purge once per input, each time subtracting the median
+    /// of the map, so for a wide fan-in this gives a smaller [`maximum_error`](Self::maximum_error)
+    /// and avoids the repeated purges.
+    ///
+    /// The result keeps the configuration of this sketch, and its bounds are guaranteed as with
+    /// [`merge`](Self::merge). Weights saturate at `u64::MAX` as they do there.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let partitions: Vec<_> = (0..8)
+    ///     .map(|p| {
+    ///         let mut sketch = FrequentItemsSketch::<i64>::new(16);
+    ///         for i in 0..12 {
+    ///             sketch.update_with_count(p * 100 + i, 1);
+    ///         }
+    ///         sketch.update_with_count(-1, 10);
+    ///         sketch
+    ///     })
+    ///     .collect();
+    ///
+    /// let mut merged = FrequentItemsSketch::<i64>::new(16);
+    /// merged.merge_many(&partitions.iter().collect::<Vec<_>>());
+    /// assert_eq!(merged.total_weight(), 8 * 22);
+    /// assert!(merged.lower_bound(&-1) <= 80 && 80 <= merged.upper_bound(&-1));
+    /// ```
+    pub fn merge_many(&mut self, others: &[&Self])
+    where
+        T: Clone,
+    {
+        if others.iter().all(|other| other.is_empty()) {
+            return;
+        }
+        let map_size = self.hash_map.len();
+        let own = std::mem::replace(&mut self.hash_map, ReversePurgeItemHashMap::new(map_size));
+        let mut counts: HashMap<T, u64> = own.into_entries().collect();
+        for other in others {
+            for (item, count) in other.hash_map.iter() {
+                let sum = counts.entry(item.clone()).or_insert(0);
+                *sum = sum.saturating_add(count);
+            }
+            self.offset = self.offset.saturating_add(other.offset);
+            self.stream_weight = self.stream_weight.saturating_add(other.stream_weight);
+        }
+
+        let max_map_cap = self.maximum_map_capacity();
+        if counts.len() > max_map_cap {
+            let mut values: Vec<u64> = counts.values().copied().collect();
+            let (_, &mut delta, _) = values.select_nth_unstable_by(max_map_cap, |a, b| b.cmp(a));
+            counts.retain(|_, count| {
+                *count -= delta.min(*count);
+                *count > 0
+            });
+            self.offset = self.offset.saturating_add(delta);
+        }
+        for (item, count) in counts {
+            self.hash_map.adjust_or_put_value(item, count);
+            self.maybe_resize_or_purge();
         }
-        self.offset += other.offset;
-        self.stream_weight = merged_total;
     }
 
     /// Resets the sketch to an empty state.
     pub fn reset(&mut self) {
+        let purge_strategy = self.purge_strategy;
         *self = Self::with_lg_map_sizes(self.lg_max_map_size, LG_MIN_MAP_SIZE);
+        self.purge_strategy = purge_strategy;
+    }
+
+    /// Resets the sketch to an empty state, keeping the map at its current size.
+    ///
+    /// Unlike [`reset`](Self::reset), which releases the memory of a grown map, this reuses it,
+    /// so a sketch rebuilt for every window of a stream does not reallocate and regrow its map
+    /// each time. Use [`shrink_to_fit`](Self::shrink_to_fit) to release the memory later.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let mut sketch = FrequentItemsSketch::<i64>::new(1024);
+    /// for i in 0..500 {
+    ///     sketch.update(i);
+    /// }
+    /// let lg_cur_map_size = sketch.lg_cur_map_size();
+    /// sketch.clear();
+    /// assert!(sketch.is_empty());
+    /// assert_eq!(sketch.total_weight(), 0);
+    /// assert_eq!(sketch.lg_cur_map_size(), lg_cur_map_size);
+    /// ```
+    pub fn clear(&mut self) {
+        self.hash_map.clear();
+        self.offset = 0;
+        self.stream_weight = 0;
+    }
+
+    /// Shrinks the internal map to the smallest size that holds the active items.
+    ///
+    /// The map only ever grows while items arrive, so a long-lived sketch whose counters were
+    /// mostly purged, e.g. after [`merge`](Self::merge)s or a shift in the stream, keeps the
+    /// memory of its peak. Shrinking releases it without changing any estimate or bound; the
+    /// map grows again as needed, up to the configured maximum.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let mut sketch = FrequentItemsSketch::<i64>::new(1024);
+    /// for i in 0..10 {
+    ///     sketch.update_with_count(i, 100);
+    /// }
+    /// // a burst of one-off items fills the map until a purge evicts them
+    /// for i in 10..780 {
+    ///     sketch.update(i);
+    /// }
+    /// assert_eq!(sketch.lg_cur_map_size(), 10);
+    /// assert!(sketch.num_active_items() < 24);
+    ///
+    /// let estimate = sketch.estimate(&3);
+    /// sketch.shrink_to_fit();
+    /// assert_eq!(sketch.lg_cur_map_size(), 5);
+    /// assert_eq!(sketch.estimate(&3), estimate);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        let num_active = self.hash_map.num_active();
+        let mut lg_size = LG_MIN_MAP_SIZE;
+        while (1usize << lg_size) * LOAD_FACTOR_NUMERATOR / LOAD_FACTOR_DENOMINATOR < num_active {
+            lg_size += 1;
+        }
+        if lg_size < self.hash_map.lg_length() {
+            self.hash_map.resize(1 << lg_size);
+            self.cur_map_cap = self.hash_map.capacity();
+        }
     }
 
     /// Returns frequent items using the sketch maximum error as threshold.
@@ -321,6 +847,31 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
         self.frequent_items_with_threshold(error_type, self.offset)
     }
 
+    /// Returns an iterator over the rows that [`frequent_items`](Self::frequent_items) would
+    /// return, without allocating or cloning items.
+    ///
+    /// Rows are yielded in no particular order and borrow their items from the sketch, which
+    /// suits hot paths that poll the sketch repeatedly, e.g. to count heavy hitters or look for
+    /// a specific one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::ErrorType;
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let mut sketch = FrequentItemsSketch::<String>::new(64);
+    /// sketch.update_with_count("apple".to_string(), 5);
+    /// sketch.update("banana".to_string());
+    /// let heaviest = sketch
+    ///     .frequent_items_iter(ErrorType::NoFalsePositives)
+    ///     .max_by_key(|row| row.lower_bound())
+    ///     .unwrap();
+    /// assert_eq!(heaviest.item().as_str(), "apple");
+    /// ```
+    pub fn frequent_items_iter(&self, error_type: ErrorType) -> impl Iterator<Item = Row<&T>> + '_ {
+        self.rows_above(error_type, self.offset)
+    }
+
     /// Returns frequent items using a custom threshold.
     ///
     /// If `threshold` is less than `maximum_error`, `maximum_error` is used instead.
@@ -348,35 +899,121 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
         T: Clone,
     {
         let threshold = threshold.max(self.offset);
-        let mut rows = vec![];
-        for (item, count) in self.hash_map.iter() {
-            let lower = count;
-            let upper = count + self.offset;
-            let include = match error_type {
-                ErrorType::NoFalseNegatives => upper > threshold,
-                ErrorType::NoFalsePositives => lower > threshold,
-            };
-            if include {
-                rows.push(Row {
-                    item: item.clone(),
-                    estimate: upper,
-                    upper_bound: upper,
-                    lower_bound: lower,
-                });
-            }
-        }
+        let mut rows: Vec<Row<T>> = self
+            .rows_above(error_type, threshold)
+            .map(Row::cloned)
+            .collect();
         rows.sort_by_key(|row| std::cmp::Reverse(row.estimate));
         rows
     }
 
+    /// Returns the `k` rows with the highest lower bounds that qualify for `error_type`.
+    ///
+    /// Rows are selected using the sketch maximum error as threshold, as in
+    /// [`frequent_items`](Self::frequent_items), and are sorted by descending lower bound, with
+    /// ties broken by descending upper bound. Fewer than `k` rows are returned if fewer items
+    /// qualify.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::ErrorType;
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let mut sketch = FrequentItemsSketch::<i64>::new(64);
+    /// sketch.update_with_count(1, 5);
+    /// sketch.update_with_count(2, 3);
+    /// sketch.update(3);
+    /// let rows = sketch.top_k(2, ErrorType::NoFalseNegatives);
+    /// assert_eq!(rows.len(), 2);
+    /// assert_eq!(*rows[0].item(), 1);
+    /// assert_eq!(*rows[1].item(), 2);
+    /// ```
+    pub fn top_k(&self, k: usize, error_type: ErrorType) -> Vec<Row<T>>
+    where
+        T: Clone,
+    {
+        if k == 0 {
+            return vec![];
+        }
+        let mut rows: Vec<Row<&T>> = self.frequent_items_iter(error_type).collect();
+        let by_bounds = |a: &Row<&T>, b: &Row<&T>| {
+            b.lower_bound
+                .cmp(&a.lower_bound)
+                .then(b.upper_bound.cmp(&a.upper_bound))
+        };
+        if rows.len() > k {
+            rows.select_nth_unstable_by(k - 1, by_bounds);
+            rows.truncate(k);
+        }
+        rows.sort_by(by_bounds);
+        rows.into_iter().map(Row::cloned).collect()
+    }
+
+    /// Returns an iterator over the rows of all items currently tracked by the sketch.
+    ///
+    /// Rows are yielded in no particular order and borrow their items from the sketch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// let mut sketch = FrequentItemsSketch::<i64>::new(64);
+    /// sketch.update_with_count(1, 5);
+    /// sketch.update(2);
+    /// let total: u64 = sketch.iter().map(|row| row.lower_bound()).sum();
+    /// assert_eq!(total, 6);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = Row<&T>> + '_ {
+        let offset = self.offset;
+        self.hash_map.iter().map(move |(item, count)| Row {
+            item,
+            estimate: count.saturating_add(offset),
+            upper_bound: count.saturating_add(offset),
+            lower_bound: count,
+            guaranteed: count > offset,
+        })
+    }
+
+    /// Returns the rows whose bound selected by `error_type` exceeds `threshold`.
+    fn rows_above(
+        &self,
+        error_type: ErrorType,
+        threshold: u64,
+    ) -> impl Iterator<Item = Row<&T>> + '_ {
+        self.iter()
+            .filter(move |row| match error_type {
+                ErrorType::NoFalseNegatives => row.upper_bound > threshold,
+                ErrorType::NoFalsePositives => row.lower_bound > threshold,
+            })
+            .map(move |row| Row {
+                guaranteed: row.lower_bound > threshold,
+                ..row
+            })
+    }
+
+    /// Multiplies every counter, the offset and the total weight by `factor` in `[0, 1]`.
+    ///
+    /// Counters round down and leave the map at zero while the offset rounds up, so the lower
+    /// bounds keep holding exactly and the upper bounds up to one unit per call. The offset never
+    /// exceeds the total weight, which bounds every frequency anyway.
+    pub(super) fn scale(&mut self, factor: f64) {
+        self.hash_map.scale_values(factor);
+        self.stream_weight = (self.stream_weight as f64 * factor) as u64;
+        self.offset = ((self.offset as f64 * factor).ceil() as u64).min(self.stream_weight);
+    }
+
     fn maybe_resize_or_purge(&mut self) {
         if self.hash_map.num_active() > self.cur_map_cap {
             if self.hash_map.lg_length() < self.lg_max_map_size {
                 self.hash_map.resize(self.hash_map.len() * 2);
                 self.cur_map_cap = self.hash_map.capacity();
             } else {
-                let delta = self.hash_map.purge(self.sample_size);
-                self.offset += delta;
+                let sample_size = match self.purge_strategy {
+                    PurgeStrategy::Sampled => self.sample_size,
+                    PurgeStrategy::ExactMedian => self.hash_map.num_active(),
+                };
+                let delta = self.hash_map.purge(sample_size);
+                self.offset = self.offset.saturating_add(delta);
                 if self.hash_map.num_active() > self.maximum_map_capacity() {
                     panic!("purge did not reduce number of active items");
                 }
@@ -401,36 +1038,120 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
             offset: 0,
             stream_weight: 0,
             sample_size,
+            purge_strategy: PurgeStrategy::Sampled,
             hash_map: map,
         }
     }
+}
 
-    fn serialize_inner(
-        &self,
-        count_serialize_size: CountSerializeSize<T>,
-        serialize_items: SerializeItems<T>,
-    ) -> Vec<u8>
-    where
-        T: Clone, // for self.hash_map.active_keys()
-    {
+impl<T: FrequentItemValue> FrequentItemsSketch<T> {
+    /// Serializes this sketch into a byte vector.
+    ///
+    /// # Examples
+    ///
+    /// Built-in support for `i64`:
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// # let mut sketch = FrequentItemsSketch::<i64>::new(64);
+    /// # sketch.update_with_count(7, 2);
+    /// let bytes = sketch.serialize();
+    /// let decoded = FrequentItemsSketch::<i64>::deserialize(&bytes).unwrap();
+    /// assert!(decoded.estimate(&7) >= 2);
+    /// ```
+    ///
+    /// Built-in support for `String`:
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// # let mut sketch = FrequentItemsSketch::<String>::new(64);
+    /// # let apple = "apple".to_string();
+    /// # sketch.update_with_count(apple.clone(), 2);
+    /// let bytes = sketch.serialize();
+    /// let decoded = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap();
+    /// assert!(decoded.estimate(&apple) >= 2);
+    /// ```
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = SketchBytes::with_capacity(self.serialized_size_bytes());
+        self.write_to(&mut bytes);
+        bytes.into_bytes()
+    }
+
+    /// Returns the number of bytes [`FrequentItemsSketch::serialize`] produces.
+    pub fn serialized_size_bytes(&self) -> usize {
+        if self.is_empty() {
+            PREAMBLE_LONGS_EMPTY as usize * 8
+        } else {
+            PREAMBLE_LONGS_NONEMPTY as usize * 8
+                + self
+                    .hash_map
+                    .active_entries()
+                    .map(|(item, _)| size_of::<u64>() + T::serialize_size(item))
+                    .sum::<usize>()
+        }
+    }
+
+    /// Serializes this sketch into the front of `buf` without allocating, returning the number
+    /// of bytes written.
+    ///
+    /// The bytes are the same as those returned by [`FrequentItemsSketch::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `buf` is shorter than [`FrequentItemsSketch::serialized_size_bytes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// # let mut sketch = FrequentItemsSketch::<i64>::new(64);
+    /// # sketch.update_with_count(7, 2);
+    /// let mut buf = vec![0u8; sketch.serialized_size_bytes()];
+    /// let len = sketch.serialize_into(&mut buf).unwrap();
+    /// assert_eq!(&buf[..len], sketch.serialize());
+    /// ```
+    pub fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        serialize_into_slice(buf, self.serialized_size_bytes(), |bytes| {
+            self.write_to(bytes)
+        })
+    }
+
+    /// Serializes this sketch into the given writer.
+    ///
+    /// The output is identical to [`serialize`](Self::serialize), but is written in chunks of a
+    /// few kilobytes, so large sketches can be written to files or sockets without building the
+    /// whole byte vector in memory first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the writer fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// # let mut sketch = FrequentItemsSketch::<i64>::new(64);
+    /// # sketch.update_with_count(7, 2);
+    /// let mut buf = Vec::new();
+    /// sketch.serialize_into_writer(&mut buf).unwrap();
+    /// assert_eq!(buf, sketch.serialize());
+    /// ```
+    pub fn serialize_into_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_into_writer(writer, |bytes| self.write_to(bytes))
+    }
+
+    fn write_to(&self, bytes: &mut SketchBytes<'_>) {
         if self.is_empty() {
-            let mut bytes = SketchBytes::with_capacity(8);
             bytes.write_u8(PREAMBLE_LONGS_EMPTY);
             bytes.write_u8(SERIAL_VERSION);
             bytes.write_u8(Family::FREQUENCY.id);
             bytes.write_u8(self.lg_max_map_size);
             bytes.write_u8(self.hash_map.lg_length());
             bytes.write_u8(EMPTY_FLAG_MASK);
-            return bytes.into_bytes();
+            bytes.write_u16_le(0); // unused
+            return;
         }
 
-        let active_items = self.num_active_items();
-        let values = self.hash_map.active_values();
-        let keys = self.hash_map.active_keys();
-        let total_bytes =
-            PREAMBLE_LONGS_NONEMPTY as usize * 8 + (active_items * 8) + count_serialize_size(&keys);
-
-        let mut bytes = SketchBytes::with_capacity(total_bytes);
         bytes.write_u8(PREAMBLE_LONGS_NONEMPTY);
         bytes.write_u8(SERIAL_VERSION);
         bytes.write_u8(Family::FREQUENCY.id);
@@ -439,24 +1160,92 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
         bytes.write_u8(0); // flags
         bytes.write_u16_le(0); // unused
 
-        bytes.write_u32_le(active_items as u32);
+        bytes.write_u32_le(self.num_active_items() as u32);
         bytes.write_u32_le(0); // unused
         bytes.write_u64_le(self.stream_weight);
         bytes.write_u64_le(self.offset);
 
-        for value in values {
+        for (_, value) in self.hash_map.active_entries() {
             bytes.write_u64_le(value);
         }
-        serialize_items(&mut bytes, &keys);
+        for (item, _) in self.hash_map.active_entries() {
+            item.serialize_value(bytes);
+        }
+    }
 
-        bytes.into_bytes()
+    /// Deserializes a sketch from bytes.
+    ///
+    /// # Compatibility
+    ///
+    /// `i64` and `String` sketches written by `ItemsSketch` and `LongsSketch` in datasketches-java
+    /// and by `frequent_items_sketch` in datasketches-cpp are read as-is. This is checked against
+    /// the serialization test corpus; [`compat`](crate::compat) names the target releases.
+    ///
+    /// # Examples
+    ///
+    /// Built-in support for `i64`:
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// # let mut sketch = FrequentItemsSketch::<i64>::new(64);
+    /// # sketch.update_with_count(7, 2);
+    /// # let bytes = sketch.serialize();
+    /// let decoded = FrequentItemsSketch::<i64>::deserialize(&bytes).unwrap();
+    /// assert!(decoded.estimate(&7) >= 2);
+    /// ```
+    ///
+    /// Built-in support for `String`:
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// # let mut sketch = FrequentItemsSketch::<String>::new(64);
+    /// # let apple = "apple".to_string();
+    /// # sketch.update_with_count(apple.clone(), 2);
+    /// # let bytes = sketch.serialize();
+    /// let decoded = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap();
+    /// assert!(decoded.estimate(&apple) >= 2);
+    /// ```
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        let mut cursor = SketchSlice::new(bytes);
+        Self::deserialize_inner(&mut cursor, T::deserialize_value)
+    }
+
+    /// Deserializes a sketch from the given reader.
+    ///
+    /// This accepts the same format as [`deserialize`](Self::deserialize), and reads no further
+    /// than the end of the sketch, so the reader may continue with other data. Items are decoded
+    /// as they are read, so large sketches can be read from files or sockets without buffering
+    /// the whole input first. Wrap unbuffered readers in a [`BufReader`](std::io::BufReader).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the reader fails or the data is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::frequencies::FrequentItemsSketch;
+    /// # let mut sketch = FrequentItemsSketch::<String>::new(64);
+    /// # sketch.update_with_count("apple".to_string(), 2);
+    /// # let mut buf = Vec::new();
+    /// # sketch.serialize_into_writer(&mut buf).unwrap();
+    /// let mut reader = buf.as_slice();
+    /// let decoded = FrequentItemsSketch::<String>::deserialize_from_reader(&mut reader).unwrap();
+    /// assert!(decoded.estimate(&"apple".to_string()) >= 2);
+    /// ```
+    pub fn deserialize_from_reader<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        Self::deserialize_inner(reader, T::read_value)
     }
 
-    fn deserialize_inner(
-        bytes: &[u8],
-        deserialize_items: DeserializeItems<T>,
+    fn deserialize_inner<R: Read>(
+        reader: &mut R,
+        mut read_item: impl FnMut(&mut R) -> Result<T, Error>,
     ) -> Result<Self, Error> {
-        let mut cursor = SketchSlice::new(bytes);
+        let mut preamble = [0u8; PREAMBLE_LONGS_EMPTY as usize * 8];
+        reader
+            .read_exact(&mut preamble)
+            .map_err(read_error("preamble"))?;
+        let mut cursor = SketchSlice::new(&preamble);
         let pre_longs = cursor.read_u8().map_err(insufficient_data("pre_longs"))?;
         let pre_longs = pre_longs & 0x3F;
         let serial_version = cursor
@@ -477,7 +1266,14 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
         Family::FREQUENCY.validate_id(family)?;
         ensure_serial_version_is(SERIAL_VERSION, serial_version)?;
         if lg_cur > lg_max {
-            return Err(Error::deserial("lg_cur_map_size exceeds lg_max_map_size"));
+            return Err(Error::bad_preamble(
+                "lg_cur_map_size exceeds lg_max_map_size",
+            ));
+        }
+        if lg_max > LG_MAX_MAP_SIZE {
+            return Err(Error::bad_preamble(format!(
+                "lg_max_map_size must not exceed {LG_MAX_MAP_SIZE}, got {lg_max}"
+            )));
         }
 
         let is_empty = (flags & EMPTY_FLAG_MASK) != 0;
@@ -487,6 +1283,11 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
         }
 
         ensure_preamble_longs_in(&[PREAMBLE_LONGS_NONEMPTY], pre_longs)?;
+        let mut preamble = [0u8; (PREAMBLE_LONGS_NONEMPTY - PREAMBLE_LONGS_EMPTY) as usize * 8];
+        reader
+            .read_exact(&mut preamble)
+            .map_err(read_error("preamble"))?;
+        let mut cursor = SketchSlice::new(&preamble);
         let active_items = cursor
             .read_u32_le()
             .map_err(insufficient_data("active_items"))?;
@@ -499,25 +1300,35 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
             .map_err(insufficient_data("stream_weight"))?;
         let offset_val = cursor.read_u64_le().map_err(insufficient_data("offset"))?;
 
-        let mut values = Vec::with_capacity(active_items);
+        let mut values = Vec::with_capacity(active_items.min(MAX_PREALLOCATED_ITEMS));
         for i in 0..active_items {
-            values.push(cursor.read_u64_le().map_err(|_| {
-                Error::insufficient_data(format!(
-                    "expected {active_items} weights, failed at index {i}"
-                ))
-            })?);
-        }
-
-        let items = deserialize_items(cursor, active_items)?;
-        if items.len() != active_items {
-            return Err(Error::deserial(
-                "item count mismatch during deserialization",
-            ));
+            let mut value = [0u8; 8];
+            reader.read_exact(&mut value).map_err(|err| {
+                read_error("weights")(err)
+                    .with_context("num_weights", active_items)
+                    .with_context("weight_index", i)
+            })?;
+            values.push(u64::from_le_bytes(value));
         }
 
         let mut sketch = Self::with_lg_map_sizes(lg_max, lg_cur);
-        for (item, value) in items.into_iter().zip(values) {
-            sketch.update_with_count(item, value);
+        for (i, value) in values.into_iter().enumerate() {
+            let item = read_item(reader).map_err(|err| {
+                err.with_context("num_items", active_items)
+                    .with_context("item_index", i)
+            })?;
+            sketch.try_update_with_count(item, value).map_err(|_| {
+                Error::deserial("weights overflow the total weight").with_context("item_index", i)
+            })?;
+        }
+        if sketch.stream_weight > stream_weight {
+            return Err(Error::deserial("weights exceed the total weight")
+                .with_context("stream_weight", stream_weight));
+        }
+        if offset_val > stream_weight {
+            return Err(Error::deserial("offset exceeds the total weight")
+                .with_context("offset", offset_val)
+                .with_context("stream_weight", stream_weight));
         }
         sketch.stream_weight = stream_weight;
         sketch.offset = offset_val;
@@ -525,82 +1336,54 @@ impl<T: Eq + Hash> FrequentItemsSketch<T> {
     }
 }
 
-impl<T: FrequentItemValue> FrequentItemsSketch<T> {
-    /// Serializes this sketch into a byte vector.
-    ///
-    /// # Examples
-    ///
-    /// Built-in support for `i64`:
-    ///
-    /// ```
-    /// # use datasketches::frequencies::FrequentItemsSketch;
-    /// # let mut sketch = FrequentItemsSketch::<i64>::new(64);
-    /// # sketch.update_with_count(7, 2);
-    /// let bytes = sketch.serialize();
-    /// let decoded = FrequentItemsSketch::<i64>::deserialize(&bytes).unwrap();
-    /// assert!(decoded.estimate(&7) >= 2);
-    /// ```
-    ///
-    /// Built-in support for `String`:
-    ///
-    /// ```
-    /// # use datasketches::frequencies::FrequentItemsSketch;
-    /// # let mut sketch = FrequentItemsSketch::<String>::new(64);
-    /// # let apple = "apple".to_string();
-    /// # sketch.update_with_count(apple.clone(), 2);
-    /// let bytes = sketch.serialize();
-    /// let decoded = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap();
-    /// assert!(decoded.estimate(&apple) >= 2);
-    /// ```
-    pub fn serialize(&self) -> Vec<u8> {
-        self.serialize_inner(
-            |items| items.iter().map(T::serialize_size).sum(),
-            |bytes, items| {
-                for item in items {
-                    item.serialize_value(bytes);
-                }
-            },
-        )
+impl<T: Eq + Hash> fmt::Display for FrequentItemsSketch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Summary::new(f, "FREQUENT ITEMS SKETCH")
+            .field("lg max map size", self.lg_max_map_size())
+            .field("lg cur map size", self.lg_cur_map_size())
+            .field("num active items", self.num_active_items())
+            .field("empty", self.is_empty())
+            .field("total weight", self.total_weight())
+            .field("maximum error", self.maximum_error())
+            .finish()
     }
+}
 
-    /// Deserializes a sketch from bytes.
-    ///
-    /// # Examples
-    ///
-    /// Built-in support for `i64`:
-    ///
-    /// ```
-    /// # use datasketches::frequencies::FrequentItemsSketch;
-    /// # let mut sketch = FrequentItemsSketch::<i64>::new(64);
-    /// # sketch.update_with_count(7, 2);
-    /// # let bytes = sketch.serialize();
-    /// let decoded = FrequentItemsSketch::<i64>::deserialize(&bytes).unwrap();
-    /// assert!(decoded.estimate(&7) >= 2);
-    /// ```
-    ///
-    /// Built-in support for `String`:
-    ///
-    /// ```
-    /// # use datasketches::frequencies::FrequentItemsSketch;
-    /// # let mut sketch = FrequentItemsSketch::<String>::new(64);
-    /// # let apple = "apple".to_string();
-    /// # sketch.update_with_count(apple.clone(), 2);
-    /// # let bytes = sketch.serialize();
-    /// let decoded = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap();
-    /// assert!(decoded.estimate(&apple) >= 2);
-    /// ```
-    pub fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
-        Self::deserialize_inner(bytes, |mut cursor, num_items| {
-            let mut items = Vec::with_capacity(num_items);
-            for i in 0..num_items {
-                let item = T::deserialize_value(&mut cursor).map_err(|_| {
-                    Error::insufficient_data(format!(
-                        "expected {num_items} items, failed to read item at index {i}"
-                    ))
-                })?;
-                items.push(item);
-            }
-            Ok(items)
-        })
+impl<T: Eq + Hash> Sketch<T> for FrequentItemsSketch<T> {
+    fn update(&mut self, item: T) {
+        FrequentItemsSketch::update(self, item);
+    }
+
+    fn is_empty(&self) -> bool {
+        FrequentItemsSketch::is_empty(self)
+    }
+}
+
+impl<T: Eq + Hash + Clone> MergeableSketch for FrequentItemsSketch<T> {
+    type Error = Infallible;
+
+    fn merge(&mut self, other: &Self) -> Result<(), Infallible> {
+        FrequentItemsSketch::merge(self, other);
+        Ok(())
+    }
+}
+
+impl<T: FrequentItemValue> SerializableSketch for FrequentItemsSketch<T> {
+    type Error = Error;
+
+    fn serialize(&self) -> Vec<u8> {
+        FrequentItemsSketch::serialize(self)
+    }
+
+    fn serialized_size_bytes(&self) -> usize {
+        FrequentItemsSketch::serialized_size_bytes(self)
+    }
+
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        FrequentItemsSketch::serialize_into(self, buf)
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        FrequentItemsSketch::deserialize(bytes)
     }
 }
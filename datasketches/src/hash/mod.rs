@@ -15,11 +15,19 @@
 // specific language governing permissions and limitations
 // under the License.
 
+//! Hash functions used by the sketches.
+//!
+//! [`murmur3_128`] computes the same 128-bit MurmurHash3 the sketches compute for an update, so
+//! applications can hash values once, e.g. off the hot path or on another machine, and feed the
+//! result to the `update_hash` methods of [`ThetaSketch`](crate::theta::ThetaSketch),
+//! [`HllSketch`](crate::hll::HllSketch) and [`CpcSketch`](crate::cpc::CpcSketch).
+
 mod murmurhash;
 mod xxhash;
 
 pub(crate) use self::murmurhash::MurmurHash3X64128;
 pub(crate) use self::xxhash::XxHash64;
+use crate::error::Error;
 
 /// The seed 9001 used in the sketch update methods is a prime number that was chosen very early
 /// on in experimental testing.
@@ -34,7 +42,35 @@ pub(crate) use self::xxhash::XxHash64;
 /// and seed are identical for both sketches, otherwise the assumed 1:1 relationship between the
 /// original source key value and the hashed bit string would be violated. Once you have developed
 /// a history of stored sketches you are stuck with it.
-pub(crate) const DEFAULT_UPDATE_SEED: u64 = 9001;
+pub const DEFAULT_UPDATE_SEED: u64 = 9001;
+
+/// Computes the 128-bit MurmurHash3 (x64 variant) of `bytes` with the given seed.
+///
+/// Returns the two 64-bit halves `(h1, h2)` as produced by the reference implementation, i.e.
+/// `MurmurHash3.hash(byte[], seed)` of datasketches-java. Hashing the raw bytes of a value with
+/// the seed of a sketch yields exactly the hash that sketch computes in `update_bytes`.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::hash::DEFAULT_UPDATE_SEED;
+/// # use datasketches::hash::murmur3_128;
+/// # use datasketches::theta::ThetaSketch;
+/// let hash = murmur3_128(b"apple", DEFAULT_UPDATE_SEED);
+///
+/// let mut a = ThetaSketch::builder().build();
+/// a.update_bytes(b"apple");
+/// let mut b = ThetaSketch::builder().build();
+/// b.update_hash(hash);
+/// assert!(a.iter().eq(b.iter()));
+/// ```
+pub fn murmur3_128(bytes: &[u8], seed: u64) -> (u64, u64) {
+    use std::hash::Hasher;
+
+    let mut hasher = MurmurHash3X64128::with_seed(seed);
+    hasher.write(bytes);
+    hasher.finish128()
+}
 
 /// Computes and checks the 16-bit seed hash from the given long seed.
 ///
@@ -45,14 +81,34 @@ pub(crate) const DEFAULT_UPDATE_SEED: u64 = 9001;
 ///
 /// Panics if the computed seed hash is zero.
 pub(crate) fn compute_seed_hash(seed: u64) -> u16 {
+    let seed_hash = raw_seed_hash(seed);
+    assert_ne!(seed_hash, 0);
+    seed_hash
+}
+
+/// Computes and checks the 16-bit seed hash from the given long seed.
+///
+/// The fallible version of [`compute_seed_hash`], used where the seed is supplied by the caller.
+///
+/// # Errors
+///
+/// If the computed seed hash is zero.
+pub(crate) fn try_compute_seed_hash(seed: u64) -> Result<u16, Error> {
+    match raw_seed_hash(seed) {
+        0 => Err(Error::invalid_argument(format!(
+            "seed {seed} produces a zero seed hash"
+        ))),
+        seed_hash => Ok(seed_hash),
+    }
+}
+
+fn raw_seed_hash(seed: u64) -> u16 {
     use std::hash::Hasher;
 
     let mut hasher = MurmurHash3X64128::with_seed(0);
     hasher.write(&seed.to_le_bytes());
     let (h1, _) = hasher.finish128();
-    let seed_hash = (h1 & 0xffff) as u16;
-    assert_ne!(seed_hash, 0);
-    seed_hash
+    (h1 & 0xffff) as u16
 }
 
 /// Reads an u64 from a byte slice in little-endian order.
@@ -232,10 +232,10 @@ impl Array4 {
             let mut new_aux = None;
 
             for (slot, old_actual_val) in old_aux.into_iter() {
-                debug_assert_ne!(
+                debug_assert_eq!(
                     self.get_raw(slot),
                     AUX_TOKEN,
-                    "AuxMap contains slow without AUX_TOKEN"
+                    "AuxMap contains slot without AUX_TOKEN"
                 );
 
                 let new_shifted = old_actual_val - new_cur_min;
@@ -262,6 +262,17 @@ impl Array4 {
             .estimate(self.lg_config_k, self.cur_min, self.num_at_cur_min)
     }
 
+    /// Get the composite estimate, regardless of whether the HIP estimate is valid
+    pub fn composite_estimate(&self) -> f64 {
+        self.estimator
+            .get_composite_estimate(self.lg_config_k, self.cur_min, self.num_at_cur_min)
+    }
+
+    /// Get the raw HLL estimate, without bias correction or linear counting
+    pub fn raw_estimate(&self) -> f64 {
+        self.estimator.get_raw_estimate(self.lg_config_k)
+    }
+
     /// Get upper bound for cardinality estimate
     pub fn upper_bound(&self, num_std_dev: NumStdDev) -> f64 {
         self.estimator.upper_bound(
@@ -297,12 +308,14 @@ impl Array4 {
     /// Deserialize Array4 from HLL mode bytes
     ///
     /// Expects full HLL preamble (40 bytes) followed by packed 4-bit data and optional aux map.
+    /// If `rebuild` is set, num_at_cur_min and the KxQ registers in the preamble are recomputed
+    /// from the data rather than trusted.
     pub fn deserialize(
         mut cursor: SketchSlice,
         cur_min: u8,
         lg_config_k: u8,
-        compact: bool,
         ooo: bool,
+        rebuild: bool,
     ) -> Result<Self, Error> {
         let num_bytes = 1 << (lg_config_k - 1); // k/2 bytes for 4-bit packing
 
@@ -323,16 +336,18 @@ impl Array4 {
 
         // Read packed 4-bit byte array
         let mut data = vec![0u8; num_bytes];
-        if !compact {
-            cursor
-                .read_exact(&mut data)
-                .map_err(insufficient_data("data"))?;
-        } else {
-            cursor.advance(num_bytes as u64);
-        }
+        cursor
+            .read_exact(&mut data)
+            .map_err(insufficient_data("data"))?;
 
         // Read aux map if present
         let mut aux_map = None;
+        if aux_count > 1 << lg_config_k {
+            return Err(Error::bad_preamble(format!(
+                "aux_count must be at most {}, got {aux_count}",
+                1u32 << lg_config_k
+            )));
+        }
         if aux_count > 0 {
             let mut aux = AuxMap::new(lg_config_k);
             for i in 0..aux_count {
@@ -343,6 +358,10 @@ impl Array4 {
                 })?;
                 let slot = get_slot(coupon) & ((1 << lg_config_k) - 1);
                 let value = get_value(coupon);
+                if aux.get(slot).is_some() {
+                    return Err(Error::deserial(format!("duplicate aux slot {slot}"))
+                        .with_context("aux_index", i));
+                }
                 aux.insert(slot, value);
             }
             aux_map = Some(aux);
@@ -355,33 +374,80 @@ impl Array4 {
         estimator.set_kxq1(kxq1);
         estimator.set_out_of_order(ooo);
 
-        Ok(Self {
+        let mut array = Self {
             lg_config_k,
             bytes: data.into_boxed_slice(),
             cur_min,
             num_at_cur_min,
             aux_map,
             estimator,
-        })
+        };
+        if rebuild {
+            let k = 1u32 << lg_config_k;
+            array.num_at_cur_min = (0..k).filter(|&slot| array.get_raw(slot) == 0).count() as u32;
+            let values: Vec<u8> = (0..k).map(|slot| array.get(slot)).collect();
+            array.estimator.rebuild_kxq(values);
+        }
+        array.check_invariants()?;
+        Ok(array)
     }
 
-    /// Serialize Array4 to bytes
-    ///
-    /// Produces full HLL preamble (40 bytes) followed by packed 4-bit data and optional aux map.
-    pub fn serialize(&self, lg_config_k: u8) -> Vec<u8> {
-        let num_bytes = 1 << (lg_config_k - 1); // k/2 bytes for 4-bit packing
+    /// Checks the invariants that [`Self::update`] relies on, which a corrupted image may break.
+    fn check_invariants(&self) -> Result<(), Error> {
+        // register values are at most 64 - lg_k + 1
+        if self.cur_min > 64 - self.lg_config_k {
+            return Err(Error::bad_preamble(format!(
+                "cur_min must be at most {}, got {}",
+                64 - self.lg_config_k,
+                self.cur_min
+            )));
+        }
+        let k = 1u32 << self.lg_config_k;
+        let num_at_cur_min = (0..k).filter(|&slot| self.get_raw(slot) == 0).count();
+        if num_at_cur_min != self.num_at_cur_min as usize {
+            return Err(Error::deserial(format!(
+                "num_at_cur_min {} does not match {num_at_cur_min} registers at cur_min",
+                self.num_at_cur_min
+            )));
+        }
+        let num_exceptions = (0..k)
+            .filter(|&slot| self.get_raw(slot) == AUX_TOKEN)
+            .count();
+        if num_exceptions != self.aux_count() {
+            return Err(Error::deserial(format!(
+                "{num_exceptions} registers are exceptions, but the aux map has {} entries",
+                self.aux_count()
+            )));
+        }
+        for (slot, value) in self.aux_map.iter().flat_map(|aux| aux.iter()) {
+            if self.get_raw(slot) != AUX_TOKEN || value < self.cur_min + AUX_TOKEN {
+                return Err(Error::deserial(format!(
+                    "invalid aux entry for slot {slot} with value {value}"
+                )));
+            }
+        }
+        Ok(())
+    }
 
-        // Collect aux map entries if present
-        let aux_entries: Vec<(u32, u8)> = if let Some(aux) = &self.aux_map {
-            aux.iter().collect()
-        } else {
-            vec![]
-        };
+    /// Bytes allocated on the heap for the packed values and the aux map, if any
+    pub fn heap_size_bytes(&self) -> usize {
+        self.bytes.len() + self.aux_map.as_ref().map_or(0, AuxMap::heap_size_bytes)
+    }
 
-        let aux_count = aux_entries.len() as u32;
-        let total_size = HLL_PREAMBLE_SIZE + num_bytes + (aux_count as usize * COUPON_SIZE_BYTES);
-        let mut bytes = SketchBytes::with_capacity(total_size);
+    /// Returns the number of bytes written by [`Self::write_to`].
+    pub fn serialized_size_bytes(&self, lg_config_k: u8) -> usize {
+        let num_bytes = 1 << (lg_config_k - 1); // k/2 bytes for 4-bit packing
+        HLL_PREAMBLE_SIZE + num_bytes + self.aux_count() * COUPON_SIZE_BYTES
+    }
 
+    fn aux_count(&self) -> usize {
+        self.aux_map.as_ref().map_or(0, |aux| aux.iter().count())
+    }
+
+    /// Serializes Array4 into `bytes`
+    ///
+    /// Produces full HLL preamble (40 bytes) followed by packed 4-bit data and optional aux map.
+    pub fn write_to(&self, lg_config_k: u8, bytes: &mut SketchBytes<'_>) {
         // Write standard header
         bytes.write_u8(HLL_PREINTS);
         bytes.write_u8(SERIAL_VERSION);
@@ -411,18 +477,16 @@ impl Array4 {
         bytes.write_u32_le(self.num_at_cur_min);
 
         // Write aux_count
-        bytes.write_u32_le(aux_count);
+        bytes.write_u32_le(self.aux_count() as u32);
 
         // Write packed 4-bit byte array
         bytes.write(&self.bytes);
 
         // Write aux map entries if present
-        for (slot, value) in aux_entries.iter().copied() {
+        for (slot, value) in self.aux_map.iter().flat_map(|aux| aux.iter()) {
             let coupon = pack_coupon(slot, value);
             bytes.write_u32_le(coupon);
         }
-
-        bytes.into_bytes()
     }
 }
 
@@ -507,4 +571,31 @@ mod tests {
             "kxq1 should be small (1/2^40 is tiny)"
         );
     }
+
+    #[test]
+    fn test_aux_map_survives_cur_min_shift() {
+        let mut arr = Array4::new(4); // 16 buckets
+
+        // Both values are exceptions while cur_min is 0
+        arr.update(pack_coupon(0, 20));
+        arr.update(pack_coupon(1, 15));
+        assert_eq!(arr.get_raw(0), AUX_TOKEN);
+        assert_eq!(arr.get_raw(1), AUX_TOKEN);
+        assert_eq!(arr.aux_map.as_ref().map_or(0, |aux| aux.iter().count()), 2);
+
+        // Raising every other slot to 1 shifts cur_min
+        for slot in 2..16 {
+            arr.update(pack_coupon(slot, 1));
+        }
+        assert_eq!(arr.cur_min, 1);
+        assert_eq!(arr.num_at_cur_min, 14);
+
+        // Slot 1 moves back into the 4-bit array, slot 0 stays an exception
+        assert_eq!(arr.get_raw(0), AUX_TOKEN);
+        assert_eq!(arr.get_raw(1), 14);
+        assert_eq!(arr.aux_map.as_ref().map_or(0, |aux| aux.iter().count()), 1);
+        assert_eq!(arr.get(0), 20);
+        assert_eq!(arr.get(1), 15);
+        assert_eq!(arr.get(2), 1);
+    }
 }
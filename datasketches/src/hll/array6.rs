@@ -152,6 +152,17 @@ impl Array6 {
         self.estimator.estimate(self.lg_config_k, 0, self.num_zeros)
     }
 
+    /// Get the composite estimate, regardless of whether the HIP estimate is valid
+    pub fn composite_estimate(&self) -> f64 {
+        self.estimator
+            .get_composite_estimate(self.lg_config_k, 0, self.num_zeros)
+    }
+
+    /// Get the raw HLL estimate, without bias correction or linear counting
+    pub fn raw_estimate(&self) -> f64 {
+        self.estimator.get_raw_estimate(self.lg_config_k)
+    }
+
     /// Get upper bound for cardinality estimate
     pub fn upper_bound(&self, num_std_dev: NumStdDev) -> f64 {
         self.estimator
@@ -178,12 +189,14 @@ impl Array6 {
 
     /// Deserialize Array6 from HLL mode bytes
     ///
-    /// Expects full HLL preamble (40 bytes) followed by packed 6-bit data.
+    /// Expects full HLL preamble (40 bytes) followed by packed 6-bit data. If `rebuild` is set,
+    /// num_zeros and the KxQ registers in the preamble are recomputed from the data rather
+    /// than trusted.
     pub fn deserialize(
         mut cursor: SketchSlice,
         lg_config_k: u8,
-        compact: bool,
         ooo: bool,
+        rebuild: bool,
     ) -> Result<Self, Error> {
         let k = 1 << lg_config_k;
         let num_bytes = num_bytes_for_k(k);
@@ -205,13 +218,9 @@ impl Array6 {
 
         // Read packed byte array from offset HLL_BYTE_ARR_START
         let mut data = vec![0u8; num_bytes];
-        if !compact {
-            cursor
-                .read_exact(&mut data)
-                .map_err(insufficient_data("data"))?;
-        } else {
-            cursor.advance(num_bytes as u64);
-        }
+        cursor
+            .read_exact(&mut data)
+            .map_err(insufficient_data("data"))?;
 
         // Create estimator and restore state
         let mut estimator = HipEstimator::new(lg_config_k);
@@ -220,23 +229,39 @@ impl Array6 {
         estimator.set_kxq1(kxq1);
         estimator.set_out_of_order(ooo);
 
-        Ok(Self {
+        let mut array = Self {
             lg_config_k,
             bytes: data.into_boxed_slice(),
             num_zeros,
             estimator,
-        })
+        };
+        let actual_zeros = (0..k).filter(|&slot| array.get_raw(slot) == 0).count();
+        if rebuild {
+            array.num_zeros = actual_zeros as u32;
+            let values: Vec<u8> = (0..k).map(|slot| array.get_raw(slot)).collect();
+            array.estimator.rebuild_kxq(values);
+        } else if actual_zeros != num_zeros as usize {
+            return Err(Error::deserial(format!(
+                "num_zeros {num_zeros} does not match {actual_zeros} zero registers"
+            )));
+        }
+        Ok(array)
     }
 
-    /// Serialize Array6 to bytes
+    /// Bytes allocated on the heap for the packed values
+    pub fn heap_size_bytes(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns the number of bytes written by [`Self::write_to`].
+    pub fn serialized_size_bytes(&self, lg_config_k: u8) -> usize {
+        HLL_PREAMBLE_SIZE + num_bytes_for_k(1 << lg_config_k)
+    }
+
+    /// Serializes Array6 into `bytes`
     ///
     /// Produces full HLL preamble (40 bytes) followed by packed 6-bit data.
-    pub fn serialize(&self, lg_config_k: u8) -> Vec<u8> {
-        let k = 1 << lg_config_k;
-        let num_bytes = num_bytes_for_k(k);
-        let total_size = HLL_PREAMBLE_SIZE + num_bytes;
-        let mut bytes = SketchBytes::with_capacity(total_size);
-
+    pub fn write_to(&self, lg_config_k: u8, bytes: &mut SketchBytes<'_>) {
         // Write standard header
         bytes.write_u8(HLL_PREINTS);
         bytes.write_u8(SERIAL_VERSION);
@@ -270,8 +295,6 @@ impl Array6 {
 
         // Write packed byte array
         bytes.write(&self.bytes);
-
-        bytes.into_bytes()
     }
 }
 
@@ -106,6 +106,17 @@ impl Array8 {
         self.estimator.estimate(self.lg_config_k, 0, self.num_zeros)
     }
 
+    /// Get the composite estimate, regardless of whether the HIP estimate is valid
+    pub fn composite_estimate(&self) -> f64 {
+        self.estimator
+            .get_composite_estimate(self.lg_config_k, 0, self.num_zeros)
+    }
+
+    /// Get the raw HLL estimate, without bias correction or linear counting
+    pub fn raw_estimate(&self) -> f64 {
+        self.estimator.get_raw_estimate(self.lg_config_k)
+    }
+
     /// Get upper bound for cardinality estimate
     pub fn upper_bound(&self, num_std_dev: NumStdDev) -> f64 {
         self.estimator
@@ -252,12 +263,14 @@ impl Array8 {
 
     /// Deserialize Array8 from HLL mode bytes
     ///
-    /// Expects full HLL preamble (40 bytes) followed by k bytes of data.
+    /// Expects full HLL preamble (40 bytes) followed by k bytes of data. If `rebuild` is set,
+    /// num_zeros and the KxQ registers in the preamble are recomputed from the data rather
+    /// than trusted.
     pub fn deserialize(
         mut cursor: SketchSlice,
         lg_config_k: u8,
-        compact: bool,
         ooo: bool,
+        rebuild: bool,
     ) -> Result<Self, Error> {
         let k = 1usize << lg_config_k;
 
@@ -278,12 +291,19 @@ impl Array8 {
 
         // Read byte array from offset HLL_BYTE_ARR_START
         let mut data = vec![0u8; k];
-        if !compact {
-            cursor
-                .read_exact(&mut data)
-                .map_err(insufficient_data("data"))?;
-        } else {
-            cursor.advance(k as u64);
+        cursor
+            .read_exact(&mut data)
+            .map_err(insufficient_data("data"))?;
+        if let Some(value) = data.iter().find(|&&value| value > 63) {
+            return Err(Error::deserial(format!(
+                "register values must be at most 63, got {value}"
+            )));
+        }
+        let actual_zeros = data.iter().filter(|&&value| value == 0).count();
+        if !rebuild && actual_zeros != num_zeros as usize {
+            return Err(Error::deserial(format!(
+                "num_zeros {num_zeros} does not match {actual_zeros} zero registers"
+            )));
         }
 
         // Create estimator and restore state
@@ -293,22 +313,32 @@ impl Array8 {
         estimator.set_kxq1(kxq1);
         estimator.set_out_of_order(ooo);
 
-        Ok(Self {
+        let mut array = Self {
             lg_config_k,
             bytes: data.into_boxed_slice(),
             num_zeros,
             estimator,
-        })
+        };
+        if rebuild {
+            array.rebuild_cached_values();
+        }
+        Ok(array)
     }
 
-    /// Serialize Array8 to bytes
+    /// Bytes allocated on the heap for the packed values
+    pub fn heap_size_bytes(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns the number of bytes written by [`Self::write_to`].
+    pub fn serialized_size_bytes(&self, lg_config_k: u8) -> usize {
+        HLL_PREAMBLE_SIZE + (1 << lg_config_k)
+    }
+
+    /// Serializes Array8 into `bytes`
     ///
     /// Produces full HLL preamble (40 bytes) followed by k bytes of data.
-    pub fn serialize(&self, lg_config_k: u8) -> Vec<u8> {
-        let k = 1 << lg_config_k;
-        let total_size = HLL_PREAMBLE_SIZE + k as usize;
-        let mut bytes = SketchBytes::with_capacity(total_size);
-
+    pub fn write_to(&self, lg_config_k: u8, bytes: &mut SketchBytes<'_>) {
         // Write standard header
         bytes.write_u8(HLL_PREINTS);
         bytes.write_u8(SERIAL_VERSION);
@@ -342,8 +372,6 @@ impl Array8 {
 
         // Write byte array
         bytes.write(&self.bytes);
-
-        bytes.into_bytes()
     }
 }
 
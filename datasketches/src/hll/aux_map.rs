@@ -221,6 +221,11 @@ impl AuxMap {
         self.lg_size = new_lg_size;
     }
 
+    /// Bytes allocated on the heap for the entry array
+    pub fn heap_size_bytes(&self) -> usize {
+        size_of_val(&*self.entries)
+    }
+
     /// Iterate over (slot, value) pairs without consuming the map
     pub fn iter(&self) -> impl Iterator<Item = (u32, u8)> + '_ {
         let config_k_mask = (1 << self.lg_config_k) - 1;
@@ -106,6 +106,11 @@ impl Container {
         self.coupons.len()
     }
 
+    /// Bytes allocated on the heap for the coupon array
+    pub fn heap_size_bytes(&self) -> usize {
+        size_of_val(&*self.coupons)
+    }
+
     /// Get cardinality estimate using cubic interpolation
     pub fn estimate(&self) -> f64 {
         let len = self.len as f64;
@@ -0,0 +1,134 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Register-level distance between HLL sketches
+//!
+//! Comparing the registers of two sketches is much cheaper than estimating both sketches, and
+//! is a useful drift signal: two snapshots of the same population have (nearly) identical
+//! registers, while materially different populations change many of them.
+
+use crate::hll::HllSketch;
+
+/// Distance metrics between the registers of two HLL sketches.
+///
+/// See [`register_distance`] for how the registers are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterDistance {
+    lg_k: u8,
+    num_changed: u64,
+    l1: u64,
+    max_diff: u8,
+}
+
+impl RegisterDistance {
+    /// Returns the log2 of the number of registers that were compared.
+    pub fn lg_k(&self) -> u8 {
+        self.lg_k
+    }
+
+    /// Returns the number of registers that were compared.
+    pub fn num_registers(&self) -> u64 {
+        1 << self.lg_k
+    }
+
+    /// Returns the number of registers whose values differ.
+    pub fn num_changed(&self) -> u64 {
+        self.num_changed
+    }
+
+    /// Returns the fraction of registers whose values differ, in `[0.0, 1.0]`.
+    pub fn fraction_changed(&self) -> f64 {
+        self.num_changed as f64 / self.num_registers() as f64
+    }
+
+    /// Returns the L1 distance, i.e., the sum of absolute differences of the register values.
+    pub fn l1(&self) -> u64 {
+        self.l1
+    }
+
+    /// Returns the largest absolute difference of any pair of registers.
+    pub fn max_diff(&self) -> u8 {
+        self.max_diff
+    }
+}
+
+/// Computes register-level distance metrics between two HLL sketches.
+///
+/// Sketches in List or Set mode are compared as if their coupons had been inserted into an HLL
+/// array. If the sketches have different `lg_config_k`, the registers of the larger sketch are
+/// folded down to the smaller `lg_config_k` first, in the same way as [`HllUnion`] does.
+///
+/// The target HLL types of the sketches do not need to match.
+///
+/// [`HllUnion`]: crate::hll::HllUnion
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::hll::HllSketch;
+/// # use datasketches::hll::HllType;
+/// # use datasketches::hll::register_distance;
+/// let mut a = HllSketch::new(10, HllType::Hll8);
+/// let mut b = HllSketch::new(10, HllType::Hll4);
+/// for i in 0..10_000 {
+///     a.update(i);
+///     b.update(i);
+/// }
+/// assert_eq!(register_distance(&a, &b).num_changed(), 0);
+///
+/// for i in 10_000..20_000 {
+///     b.update(i);
+/// }
+/// assert!(register_distance(&a, &b).fraction_changed() > 0.1);
+/// ```
+pub fn register_distance(a: &HllSketch, b: &HllSketch) -> RegisterDistance {
+    let lg_k = a.lg_config_k().min(b.lg_config_k());
+    let a_registers = registers(a, lg_k);
+    let b_registers = registers(b, lg_k);
+
+    let mut num_changed = 0;
+    let mut l1 = 0;
+    let mut max_diff = 0;
+    for (&x, &y) in a_registers.iter().zip(b_registers.iter()) {
+        let diff = x.abs_diff(y);
+        if diff > 0 {
+            num_changed += 1;
+            l1 += diff as u64;
+            max_diff = max_diff.max(diff);
+        }
+    }
+
+    RegisterDistance {
+        lg_k,
+        num_changed,
+        l1,
+        max_diff,
+    }
+}
+
+/// Materializes the registers of `sketch` at `lg_k`, which must not exceed its `lg_config_k`.
+fn registers(sketch: &HllSketch, lg_k: u8) -> Vec<u8> {
+    debug_assert!(lg_k <= sketch.lg_config_k());
+
+    let mask = (1u32 << lg_k) - 1;
+    let mut registers = vec![0u8; 1 << lg_k];
+    sketch.for_each_register(|slot, value| {
+        let register = &mut registers[(slot & mask) as usize];
+        *register = (*register).max(value);
+    });
+    registers
+}
@@ -145,7 +145,8 @@ impl HipEstimator {
 
     /// Get lower bound for cardinality estimate
     ///
-    /// Returns the lower confidence bound for the cardinality estimate.
+    /// Returns the lower confidence bound for the cardinality estimate, but never less than the
+    /// number of non-zero registers, as in datasketches-java and datasketches-cpp.
     ///
     /// # Arguments
     ///
@@ -162,8 +163,10 @@ impl HipEstimator {
     ) -> f64 {
         let estimate = self.estimate(lg_config_k, cur_min, num_at_cur_min);
         let rse = get_rel_err(lg_config_k, false, self.out_of_order, num_std_dev);
+        let k = 1u32 << lg_config_k;
+        let num_non_zeros = if cur_min == 0 { k - num_at_cur_min } else { k };
         // RSE is positive for lower bounds, so (1 + rse) > 1, making bound < estimate
-        estimate / (1.0 + rse)
+        (estimate / (1.0 + rse)).max(num_non_zeros as f64)
     }
 
     /// Get raw HLL estimate using standard HyperLogLog formula
@@ -171,7 +174,7 @@ impl HipEstimator {
     /// Formula: correctionFactor * k^2 / (kxq0 + kxq1)
     ///
     /// Uses lg_k-specific correction factors for small k.
-    fn get_raw_estimate(&self, lg_config_k: u8) -> f64 {
+    pub fn get_raw_estimate(&self, lg_config_k: u8) -> f64 {
         let k = (1 << lg_config_k) as f64;
 
         // Correction factors from empirical analysis
@@ -208,7 +211,7 @@ impl HipEstimator {
     /// This is the primary estimator used when in out-of-order mode.
     /// It uses cubic interpolation on raw HLL estimate, then blends
     /// with linear counting for small cardinalities.
-    fn get_composite_estimate(&self, lg_config_k: u8, cur_min: u8, num_at_cur_min: u32) -> f64 {
+    pub fn get_composite_estimate(&self, lg_config_k: u8, cur_min: u8, num_at_cur_min: u32) -> f64 {
         let raw_est = self.get_raw_estimate(lg_config_k);
 
         // Get composite interpolation table
@@ -311,6 +314,19 @@ impl HipEstimator {
     pub fn set_kxq1(&mut self, value: f64) {
         self.kxq1 = value;
     }
+
+    /// Recompute the KxQ registers from the given register values
+    pub fn rebuild_kxq(&mut self, values: impl IntoIterator<Item = u8>) {
+        self.kxq0 = 0.0;
+        self.kxq1 = 0.0;
+        for value in values {
+            if value < 32 {
+                self.kxq0 += inv_pow2(value);
+            } else {
+                self.kxq1 += inv_pow2(value);
+            }
+        }
+    }
 }
 
 /// Compute 1 / 2^value (inverse power of 2)
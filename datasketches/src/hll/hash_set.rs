@@ -96,13 +96,28 @@ impl HashSet {
     pub fn deserialize(
         mut cursor: SketchSlice,
         lg_arr: usize,
+        lg_config_k: u8,
         compact: bool,
     ) -> Result<Self, Error> {
+        // A set is promoted to an HLL array long before it holds k coupons.
+        if lg_arr > lg_config_k as usize {
+            return Err(Error::bad_preamble(format!(
+                "SET mode lg_arr must be at most lg_k = {lg_config_k}, got {lg_arr}"
+            )));
+        }
+
         // Read coupon count from bytes 8-11
         let coupon_count = cursor
             .read_u32_le()
             .map_err(insufficient_data("coupon_count"))?;
         let coupon_count = coupon_count as usize;
+        // keep a free slot, so that the next update of the sketch finds room for a new coupon
+        if coupon_count >= 1 << lg_arr {
+            return Err(Error::deserial(format!(
+                "SET mode coupon count {coupon_count} does not fit in an array of {}",
+                1 << lg_arr
+            )));
+        }
 
         if compact {
             // Compact mode: only couponCount coupons are stored
@@ -130,6 +145,12 @@ impl HashSet {
                     ))
                 })?;
             }
+            let num_coupons = coupons.iter().filter(|&&c| c != COUPON_EMPTY).count();
+            if num_coupons != coupon_count {
+                return Err(Error::deserial(format!(
+                    "SET mode coupon count {coupon_count} does not match {num_coupons} stored coupons"
+                )));
+            }
 
             Ok(Self {
                 container: Container::from_coupons(
@@ -141,18 +162,18 @@ impl HashSet {
         }
     }
 
-    /// Serialize a HashSet to bytes
-    pub fn serialize(&self, lg_config_k: u8, hll_type: HllType) -> Vec<u8> {
+    /// Returns the number of bytes written by [`Self::write_to`].
+    pub fn serialized_size_bytes(&self) -> usize {
+        // compact format: only the non-empty coupons are written
+        SET_PREAMBLE_SIZE + self.container.len() * 4
+    }
+
+    /// Serializes a HashSet into `bytes`
+    pub fn write_to(&self, lg_config_k: u8, hll_type: HllType, bytes: &mut SketchBytes<'_>) {
         let compact = true; // Always use compact format
         let coupon_count = self.container.len();
         let lg_arr = self.container.lg_size();
 
-        // Compute size
-        let array_size = if compact { coupon_count } else { 1 << lg_arr };
-        let total_size = SET_PREAMBLE_SIZE + (array_size * 4);
-
-        let mut bytes = SketchBytes::with_capacity(total_size);
-
         // Write preamble
         bytes.write_u8(HASH_SET_PREINTS);
         bytes.write_u8(SERIAL_VERSION);
@@ -197,7 +218,5 @@ impl HashSet {
                 bytes.write_u32_le(coupon);
             }
         }
-
-        bytes.into_bytes()
     }
 }
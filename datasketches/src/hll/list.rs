@@ -82,19 +82,38 @@ impl List {
         empty: bool,
         compact: bool,
     ) -> Result<Self, Error> {
-        // Compute array size
-        let array_size = if compact { coupon_count } else { 1 << lg_arr };
+        // The coupon count is a single byte, so a list never needs more than 2^8 slots.
+        if lg_arr > 8 {
+            return Err(Error::bad_preamble(format!(
+                "LIST mode lg_arr must be at most 8, got {lg_arr}"
+            )));
+        }
+        // Always allocate the full array, even for the compact format: a list without free
+        // slots would silently drop the next new coupon.
+        let array_size = 1 << lg_arr;
+        if coupon_count >= array_size {
+            return Err(Error::deserial(format!(
+                "LIST mode coupon count {coupon_count} does not fit in an array of {array_size}"
+            )));
+        }
+        let num_to_read = if compact { coupon_count } else { array_size };
 
         // Read coupons
         let mut coupons = vec![0u32; array_size];
         if !empty && coupon_count > 0 {
-            for (i, coupon) in coupons.iter_mut().enumerate() {
+            for (i, coupon) in coupons.iter_mut().take(num_to_read).enumerate() {
                 *coupon = cursor.read_u32_le().map_err(|_| {
                     Error::insufficient_data(format!(
                         "expect {coupon_count} coupons, failed at index {i}"
                     ))
                 })?;
             }
+            let num_coupons = coupons.iter().filter(|&&c| c != COUPON_EMPTY).count();
+            if num_coupons != coupon_count {
+                return Err(Error::deserial(format!(
+                    "LIST mode coupon count {coupon_count} does not match {num_coupons} stored coupons"
+                )));
+            }
         }
 
         Ok(Self {
@@ -102,18 +121,21 @@ impl List {
         })
     }
 
-    /// Serialize a List to bytes
-    pub fn serialize(&self, lg_config_k: u8, hll_type: HllType) -> Vec<u8> {
+    /// Returns the number of bytes written by [`Self::write_to`].
+    pub fn serialized_size_bytes(&self) -> usize {
+        // compact format: only the non-empty coupons are written
+        LIST_PREAMBLE_SIZE + self.container.len() * 4
+    }
+
+    /// Serializes a List into `bytes`
+    pub fn write_to(&self, lg_config_k: u8, hll_type: HllType, bytes: &mut SketchBytes<'_>) {
         let compact = true; // Always use compact format
         let empty = self.container.is_empty();
         let coupon_count = self.container.len();
         let lg_arr = self.container.lg_size();
 
-        // Compute size
+        // Number of coupons to write
         let array_size = if compact { coupon_count } else { 1 << lg_arr };
-        let total_size = LIST_PREAMBLE_SIZE + (array_size * 4);
-
-        let mut bytes = SketchBytes::with_capacity(total_size);
 
         // Write preamble
         bytes.write_u8(LIST_PREINTS);
@@ -152,7 +174,5 @@ impl List {
                 }
             }
         }
-
-        bytes.into_bytes()
     }
 }
@@ -61,6 +61,19 @@
 //! The union operation preserves cardinality estimation accuracy while enabling distributed
 //! computation patterns where sketches are built independently and merged later.
 //!
+//! [`estimate_union`] estimates the union of serialized sketches in a single call, for query
+//! engines that store sketches as opaque bytes.
+//!
+//! [`WrappedHllUnion`] keeps its result as a serialized `Hll8` image in a caller-provided buffer
+//! and merges sketches into it in place, so an aggregator holding many unions performs no
+//! allocation per merge.
+//!
+//! # Drift Detection
+//!
+//! [`register_distance`] compares the registers of two sketches (e.g., two snapshots of the same
+//! stream) without computing estimates, which makes it a cheap first check of whether the
+//! underlying populations differ materially.
+//!
 //! # Serialization
 //!
 //! Sketches can be serialized and deserialized while preserving all state, including:
@@ -115,6 +128,7 @@ mod composite_interpolation;
 mod container;
 mod coupon_mapping;
 mod cubic_interpolation;
+mod distance;
 mod estimator;
 mod harmonic_numbers;
 mod hash_set;
@@ -123,9 +137,15 @@ mod mode;
 mod serialization;
 mod sketch;
 mod union;
+mod wrapped_union;
 
+pub use self::distance::RegisterDistance;
+pub use self::distance::register_distance;
+pub(crate) use self::serialization::inspect;
 pub use self::sketch::HllSketch;
 pub use self::union::HllUnion;
+pub use self::union::estimate_union;
+pub use self::wrapped_union::WrappedHllUnion;
 
 /// Target HLL type.
 ///
@@ -181,8 +201,11 @@ fn pack_coupon(slot: u32, value: u8) -> u32 {
 fn coupon<H: Hash>(v: H) -> u32 {
     let mut hasher = MurmurHash3X64128::default();
     v.hash(&mut hasher);
-    let (lo, hi) = hasher.finish128();
+    coupon_of_hash(hasher.finish128())
+}
 
+/// Generate a coupon from the 128-bit hash of a value.
+fn coupon_of_hash((lo, hi): (u64, u64)) -> u32 {
     let addr26 = lo as u32 & KEY_MASK_26;
     let lz = hi.leading_zeros();
     let capped = lz.min(62);
@@ -20,6 +20,13 @@
 //! This module contains all constants related to the Apache DataSketches
 //! binary serialization format, shared across all sketch modes.
 
+use crate::codec::SketchSlice;
+use crate::codec::assert::ensure_serial_version_is;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
+use crate::error::Error;
+use crate::inspect::SketchInfo;
+
 /// Current serialization version
 pub const SERIAL_VERSION: u8 = 1;
 
@@ -29,6 +36,9 @@ pub const EMPTY_FLAG_MASK: u8 = 4;
 pub const COMPACT_FLAG_MASK: u8 = 8;
 /// Flag indicating out-of-order mode (HIP estimator invalid)
 pub const OUT_OF_ORDER_FLAG_MASK: u8 = 16;
+/// Flag set by datasketches-java unions indicating that cur_min, num_at_cur_min and the KxQ
+/// registers in the preamble are stale and must be rebuilt from the registers
+pub const REBUILD_CURMIN_NUM_KXQ_FLAG_MASK: u8 = 32;
 
 /// Preamble size for LIST mode (8 bytes = 2 ints)
 pub const LIST_PREINTS: u8 = 2;
@@ -91,3 +101,34 @@ pub const TGT_HLL8: u8 = 2;
 
 /// Size of a single coupon in bytes (u32)
 pub const COUPON_SIZE_BYTES: usize = 4;
+
+/// Reads the preamble of an image for [`inspect`](crate::inspect).
+pub(crate) fn inspect(
+    _preamble_ints: u8,
+    serial_version: u8,
+    mut cursor: SketchSlice<'_>,
+) -> Result<SketchInfo, Error> {
+    ensure_serial_version_is(SERIAL_VERSION, serial_version)?;
+    let lg_config_k = cursor.read_u8().map_err(insufficient_data("lg_config_k"))?;
+    cursor.read_u8().map_err(insufficient_data("lg_arr"))?;
+    let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+    let state = cursor.read_u8().map_err(insufficient_data("state"))?;
+    let mode_byte = cursor.read_u8().map_err(insufficient_data("mode"))?;
+
+    let mut info = SketchInfo::new(&Family::HLL, serial_version);
+    info.lg_k = Some(lg_config_k);
+    info.is_empty = flags & EMPTY_FLAG_MASK != 0;
+    info.is_estimation_mode = Some(extract_cur_mode(mode_byte) == CUR_MODE_HLL);
+    info.num_retained = match extract_cur_mode(mode_byte) {
+        CUR_MODE_LIST => Some(state as u64),
+        CUR_MODE_SET => {
+            let coupon_count = cursor
+                .read_u32_le()
+                .map_err(insufficient_data("coupon_count"))?;
+            Some(coupon_count as u64)
+        }
+        CUR_MODE_HLL => None,
+        mode => return Err(Error::bad_preamble(format!("invalid mode: {mode}"))),
+    };
+    Ok(info)
+}
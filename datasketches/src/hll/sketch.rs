@@ -20,15 +20,30 @@
 //! This module provides the main [`HllSketch`] struct, which is the primary interface
 //! for creating and using HLL sketches for cardinality estimation.
 
+use std::convert::Infallible;
+use std::fmt;
 use std::hash::Hash;
 
+use crate::codec::SketchBytes;
 use crate::codec::SketchSlice;
 use crate::codec::assert::ensure_serial_version_is;
 use crate::codec::assert::insufficient_data;
 use crate::codec::family::Family;
+use crate::codec::serialize_into_slice;
+use crate::common::CardinalityFamily;
+use crate::common::EstimateSummary;
+use crate::common::IntoSketchInput;
+use crate::common::MergeableSketch;
 use crate::common::NumStdDev;
+use crate::common::RawBytes;
+use crate::common::SerializableSketch;
+use crate::common::Sketch;
+use crate::common::SketchInput;
+use crate::common::Summary;
+use crate::common::estimates_within;
 use crate::error::Error;
 use crate::hll::HllType;
+use crate::hll::HllUnion;
 use crate::hll::RESIZE_DENOMINATOR;
 use crate::hll::RESIZE_NUMERATOR;
 use crate::hll::array4::Array4;
@@ -36,6 +51,9 @@ use crate::hll::array6::Array6;
 use crate::hll::array8::Array8;
 use crate::hll::container::Container;
 use crate::hll::coupon;
+use crate::hll::coupon_of_hash;
+use crate::hll::get_slot;
+use crate::hll::get_value;
 use crate::hll::hash_set::HashSet;
 use crate::hll::list::List;
 use crate::hll::mode::Mode;
@@ -48,6 +66,7 @@ use crate::hll::serialization::HASH_SET_PREINTS;
 use crate::hll::serialization::HLL_PREINTS;
 use crate::hll::serialization::LIST_PREINTS;
 use crate::hll::serialization::OUT_OF_ORDER_FLAG_MASK;
+use crate::hll::serialization::REBUILD_CURMIN_NUM_KXQ_FLAG_MASK;
 use crate::hll::serialization::SERIAL_VERSION;
 use crate::hll::serialization::TGT_HLL4;
 use crate::hll::serialization::TGT_HLL6;
@@ -75,6 +94,8 @@ impl HllSketch {
     ///   * lg_k=21: 2M buckets, ~0.4% relative error
     /// * `hll_type`: Target HLL array type (Hll4, Hll6, or Hll8)
     ///
+    /// The fallible version of this method is [`HllSketch::try_new`].
+    ///
     /// # Panics
     ///
     /// If lg_config_k is not in range `[4, 21]`
@@ -102,6 +123,32 @@ impl HllSketch {
         }
     }
 
+    /// Create a new HLL sketch.
+    ///
+    /// The panicking version of this method is [`HllSketch::new`].
+    ///
+    /// # Errors
+    ///
+    /// If lg_config_k is not in range `[4, 21]`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::hll::HllSketch;
+    /// # use datasketches::hll::HllType;
+    /// assert!(HllSketch::try_new(22, HllType::Hll8).is_err());
+    /// let sketch = HllSketch::try_new(12, HllType::Hll8).unwrap();
+    /// assert_eq!(sketch.lg_config_k(), 12);
+    /// ```
+    pub fn try_new(lg_config_k: u8, hll_type: HllType) -> Result<Self, Error> {
+        if !(4..=21).contains(&lg_config_k) {
+            return Err(Error::invalid_argument(format!(
+                "lg_config_k must be in [4, 21], got {lg_config_k}"
+            )));
+        }
+        Ok(Self::new(lg_config_k, hll_type))
+    }
+
     /// Create an HLL sketch directly from a Mode
     ///
     /// This is used internally (e.g., by union operations) to construct
@@ -175,6 +222,53 @@ impl HllSketch {
         self.update_with_coupon(coupon);
     }
 
+    /// Update the sketch with a value hashed like datasketches-java does, see
+    /// [`IntoSketchInput`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::hll::HllSketch;
+    /// # use datasketches::hll::HllType;
+    /// let mut sketch = HllSketch::new(12, HllType::Hll8);
+    /// sketch.update_input("apple");
+    /// sketch.update_input(7i32);
+    /// assert!(sketch.estimate() >= 2.0);
+    /// ```
+    pub fn update_input<T: IntoSketchInput>(&mut self, value: T) {
+        self.update(SketchInput(value));
+    }
+
+    /// Update the sketch with the raw bytes of a value.
+    ///
+    /// The bytes are hashed as-is, see [`RawBytes`], so the result does not depend on the
+    /// platform.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::hll::HllSketch;
+    /// # use datasketches::hll::HllType;
+    /// let mut sketch = HllSketch::new(12, HllType::Hll8);
+    /// sketch.update_bytes(b"apple");
+    /// assert!(sketch.estimate() >= 1.0);
+    /// ```
+    pub fn update_bytes(&mut self, bytes: &[u8]) {
+        self.update(RawBytes(bytes));
+    }
+
+    /// Update the sketch with a pre-computed 128-bit hash.
+    ///
+    /// `hash` must be the result of [`murmur3_128`](crate::hash::murmur3_128) with
+    /// [`DEFAULT_UPDATE_SEED`](crate::hash::DEFAULT_UPDATE_SEED), in which case this is
+    /// equivalent to [`update_bytes`](Self::update_bytes) with the same bytes.
+    ///
+    /// This is the pre-hashed fast path of HLL sketches: unlike theta sketches, the coupon of an
+    /// HLL update depends on both halves of the hash, so a single `u64` is not enough.
+    pub fn update_hash(&mut self, hash: (u64, u64)) {
+        self.update_with_coupon(coupon_of_hash(hash));
+    }
+
     /// Update the sketch with a raw coupon value
     ///
     /// Maintains all sketch invariants including mode transitions and estimator updates.
@@ -211,6 +305,11 @@ impl HllSketch {
 
     /// Get the current cardinality estimate
     ///
+    /// In HLL mode this is the HIP estimate while the sketch has only seen updates, and the
+    /// [composite estimate](Self::composite_estimate) once it has been built by a union, whose
+    /// registers no longer reflect the order of the updates, as in datasketches-java and
+    /// datasketches-cpp. Serialized images keep track of which one applies.
+    ///
     /// # Examples
     ///
     /// ```
@@ -233,7 +332,8 @@ impl HllSketch {
     /// Get upper bound for cardinality estimate
     ///
     /// Returns the upper confidence bound for the cardinality estimate based on
-    /// the number of standard deviations requested.
+    /// the number of standard deviations requested. The relative error is that of the
+    /// estimator behind [`estimate`](Self::estimate), HIP or composite.
     pub fn upper_bound(&self, num_std_dev: NumStdDev) -> f64 {
         match &self.mode {
             Mode::List { list, .. } => list.container().upper_bound(num_std_dev),
@@ -247,7 +347,9 @@ impl HllSketch {
     /// Get lower bound for cardinality estimate
     ///
     /// Returns the lower confidence bound for the cardinality estimate based on
-    /// the number of standard deviations requested.
+    /// the number of standard deviations requested. The relative error is that of the
+    /// estimator behind [`estimate`](Self::estimate), HIP or composite, and in HLL mode the
+    /// bound is never less than the number of non-zero registers, as in datasketches-java.
     pub fn lower_bound(&self, num_std_dev: NumStdDev) -> f64 {
         match &self.mode {
             Mode::List { list, .. } => list.container().lower_bound(num_std_dev),
@@ -258,8 +360,76 @@ impl HllSketch {
         }
     }
 
+    /// Get the composite cardinality estimate
+    ///
+    /// The composite estimator corrects the bias of the raw HLL estimate by interpolation and
+    /// switches to linear counting of the empty registers at low cardinalities. Unlike HIP, it
+    /// only depends on the registers, so it is what [`estimate`](Self::estimate) returns for
+    /// sketches built by a union; this method returns it for any sketch, e.g. to compare both
+    /// estimators. In list and set mode it is the coupon estimate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::hll::HllSketch;
+    /// # use datasketches::hll::HllType;
+    /// # use datasketches::hll::HllUnion;
+    /// let mut sketch = HllSketch::new(12, HllType::Hll8);
+    /// for i in 0..100_000 {
+    ///     sketch.update(i);
+    /// }
+    /// let (hip, composite) = (sketch.estimate(), sketch.composite_estimate());
+    /// assert!((composite - hip).abs() < 0.05 * hip);
+    ///
+    /// let mut union = HllUnion::new(12);
+    /// union.update(&sketch);
+    /// assert_eq!(union.to_sketch(HllType::Hll8).estimate(), composite);
+    /// ```
+    pub fn composite_estimate(&self) -> f64 {
+        match &self.mode {
+            Mode::List { list, .. } => list.container().estimate(),
+            Mode::Set { set, .. } => set.container().estimate(),
+            Mode::Array4(arr) => arr.composite_estimate(),
+            Mode::Array6(arr) => arr.composite_estimate(),
+            Mode::Array8(arr) => arr.composite_estimate(),
+        }
+    }
+
+    /// Get the raw HLL cardinality estimate
+    ///
+    /// This is the classic harmonic-mean estimate of the registers, without the bias correction
+    /// and linear counting of the [composite estimate](Self::composite_estimate). It overestimates
+    /// badly at low cardinalities and is meant for diagnostics. In list and set mode it is the
+    /// coupon estimate.
+    pub fn raw_estimate(&self) -> f64 {
+        match &self.mode {
+            Mode::List { list, .. } => list.container().estimate(),
+            Mode::Set { set, .. } => set.container().estimate(),
+            Mode::Array4(arr) => arr.raw_estimate(),
+            Mode::Array6(arr) => arr.raw_estimate(),
+            Mode::Array8(arr) => arr.raw_estimate(),
+        }
+    }
+
     /// Deserializes an HLL sketch from bytes
     ///
+    /// A round trip through [`serialize`](Self::serialize) and `deserialize` preserves the
+    /// estimate and bounds bit-for-bit in every mode and for every `lg_config_k` and
+    /// [`HllType`]. The decoded sketch also stays in lockstep with the original under further
+    /// updates and when fed to an [`HllUnion`](crate::hll::HllUnion).
+    ///
+    /// # Compatibility
+    ///
+    /// Reads the HLL_4, HLL_6 and HLL_8 images written by datasketches-java and datasketches-cpp
+    /// in every mode. This is checked against the serialization test corpus;
+    /// [`compat`](crate::compat) names the target releases.
+    ///
+    /// This includes the blobs that Druid and Pinot store through datasketches-java: HLL mode
+    /// images always carry the full register array, even when flagged compact, and images of
+    /// union gadgets may flag their num_at_cur_min and KxQ fields as stale, in which case these
+    /// are rebuilt from the registers so that the composite estimator sees the same state as
+    /// the Java sketch.
+    ///
     /// # Examples
     ///
     /// ```
@@ -301,7 +471,7 @@ impl HllSketch {
 
         // Verify lg_k range (4-21 are valid)
         if !(4..=21).contains(&lg_config_k) {
-            return Err(Error::deserial(format!(
+            return Err(Error::bad_preamble(format!(
                 "lg_k must be in [4; 21], got {lg_config_k}",
             )));
         }
@@ -311,20 +481,21 @@ impl HllSketch {
             TGT_HLL6 => HllType::Hll6,
             TGT_HLL8 => HllType::Hll8,
             hll_type => {
-                return Err(Error::deserial(format!("invalid HLL type: {hll_type}")));
+                return Err(Error::bad_preamble(format!("invalid HLL type: {hll_type}")));
             }
         };
 
         let empty = (flags & EMPTY_FLAG_MASK) != 0;
         let compact = (flags & COMPACT_FLAG_MASK) != 0;
         let ooo = (flags & OUT_OF_ORDER_FLAG_MASK) != 0;
+        let rebuild = (flags & REBUILD_CURMIN_NUM_KXQ_FLAG_MASK) != 0;
 
         // Deserialize based on mode
         let mode =
             match extract_cur_mode(mode_byte) {
                 CUR_MODE_LIST => {
                     if preamble_ints != LIST_PREINTS {
-                        return Err(Error::deserial(format!(
+                        return Err(Error::bad_preamble(format!(
                             "LIST mode preamble: expected {}, got {}",
                             LIST_PREINTS, preamble_ints,
                         )));
@@ -337,19 +508,19 @@ impl HllSketch {
                 }
                 CUR_MODE_SET => {
                     if preamble_ints != HASH_SET_PREINTS {
-                        return Err(Error::deserial(format!(
+                        return Err(Error::bad_preamble(format!(
                             "SET mode preamble: expected {}, got {}",
                             HASH_SET_PREINTS, preamble_ints
                         )));
                     }
 
                     let lg_arr = lg_arr as usize;
-                    let set = HashSet::deserialize(cursor, lg_arr, compact)?;
+                    let set = HashSet::deserialize(cursor, lg_arr, lg_config_k, compact)?;
                     Mode::Set { set, hll_type }
                 }
                 CUR_MODE_HLL => {
                     if preamble_ints != HLL_PREINTS {
-                        return Err(Error::deserial(format!(
+                        return Err(Error::bad_preamble(format!(
                             "HLL mode preamble: expected {}, got {}",
                             HLL_PREINTS, preamble_ints
                         )));
@@ -358,16 +529,16 @@ impl HllSketch {
                     match hll_type {
                         HllType::Hll4 => {
                             let cur_min = state;
-                            Array4::deserialize(cursor, cur_min, lg_config_k, compact, ooo)
+                            Array4::deserialize(cursor, cur_min, lg_config_k, ooo, rebuild)
                                 .map(Mode::Array4)?
                         }
-                        HllType::Hll6 => Array6::deserialize(cursor, lg_config_k, compact, ooo)
+                        HllType::Hll6 => Array6::deserialize(cursor, lg_config_k, ooo, rebuild)
                             .map(Mode::Array6)?,
-                        HllType::Hll8 => Array8::deserialize(cursor, lg_config_k, compact, ooo)
+                        HllType::Hll8 => Array8::deserialize(cursor, lg_config_k, ooo, rebuild)
                             .map(Mode::Array8)?,
                     }
                 }
-                mode => return Err(Error::deserial(format!("invalid mode: {mode}"))),
+                mode => return Err(Error::bad_preamble(format!("invalid mode: {mode}"))),
             };
 
         Ok(HllSketch { lg_config_k, mode })
@@ -387,12 +558,168 @@ impl HllSketch {
     /// assert!(decoded.estimate() >= 1.0);
     /// ```
     pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = SketchBytes::with_capacity(self.serialized_size_bytes());
+        self.write_to(&mut bytes);
+        bytes.into_bytes()
+    }
+
+    /// Returns the number of bytes [`HllSketch::serialize`] produces.
+    pub fn serialized_size_bytes(&self) -> usize {
         match &self.mode {
-            Mode::List { list, hll_type } => list.serialize(self.lg_config_k, *hll_type),
-            Mode::Set { set, hll_type } => set.serialize(self.lg_config_k, *hll_type),
-            Mode::Array4(arr) => arr.serialize(self.lg_config_k),
-            Mode::Array6(arr) => arr.serialize(self.lg_config_k),
-            Mode::Array8(arr) => arr.serialize(self.lg_config_k),
+            Mode::List { list, .. } => list.serialized_size_bytes(),
+            Mode::Set { set, .. } => set.serialized_size_bytes(),
+            Mode::Array4(arr) => arr.serialized_size_bytes(self.lg_config_k),
+            Mode::Array6(arr) => arr.serialized_size_bytes(self.lg_config_k),
+            Mode::Array8(arr) => arr.serialized_size_bytes(self.lg_config_k),
+        }
+    }
+
+    /// Returns true if `other` has the same lg_config_k as this sketch and their estimates
+    /// differ by at most `epsilon` relative to the larger one.
+    ///
+    /// The HLL type is ignored, since it only changes the representation of the registers. This
+    /// is meant for validating a migration between producers, e.g. comparing sketches built by
+    /// datasketches-java and by this crate from the same data, where estimates agree but the
+    /// serialized bytes may not.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `epsilon` is negative or NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::hll::HllSketch;
+    /// # use datasketches::hll::HllType;
+    /// let mut a = HllSketch::new(12, HllType::Hll4);
+    /// let mut b = HllSketch::new(12, HllType::Hll8);
+    /// for i in 0..10_000 {
+    ///     a.update(i);
+    ///     b.update(i);
+    /// }
+    /// assert!(a.approximately_equal(&b, 0.0));
+    /// b.update(-1);
+    /// assert!(a.approximately_equal(&b, 0.01));
+    /// assert!(!a.approximately_equal(&HllSketch::new(11, HllType::Hll4), 0.5));
+    /// ```
+    pub fn approximately_equal(&self, other: &HllSketch, epsilon: f64) -> bool {
+        self.lg_config_k == other.lg_config_k
+            && estimates_within(self.estimate(), other.estimate(), epsilon)
+    }
+
+    /// Returns the estimate and the bounds at `num_std_dev` standard deviations.
+    ///
+    /// The number of retained entries is the number of coupons while the sketch is in list or
+    /// set mode, and the number of registers once it has grown into an array.
+    pub fn estimate_summary(&self, num_std_dev: NumStdDev) -> EstimateSummary {
+        let retained = match &self.mode {
+            Mode::List { list, .. } => list.container().len() as u64,
+            Mode::Set { set, .. } => set.container().len() as u64,
+            Mode::Array4(_) | Mode::Array6(_) | Mode::Array8(_) => 1 << self.lg_config_k,
+        };
+        EstimateSummary {
+            estimate: self.estimate(),
+            lb: self.lower_bound(num_std_dev),
+            ub: self.upper_bound(num_std_dev),
+            retained,
+            family: CardinalityFamily::Hll,
+        }
+    }
+
+    /// Returns the number of bytes of memory the sketch occupies, including the full capacity
+    /// of its coupon table or register array rather than only the slots in use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::hll::HllSketch;
+    /// # use datasketches::hll::HllType;
+    /// let mut sketch = HllSketch::new(12, HllType::Hll8);
+    /// for i in 0..100_000 {
+    ///     sketch.update(i);
+    /// }
+    /// // 4096 one-byte registers
+    /// assert!(sketch.memory_usage_bytes() >= 4096);
+    /// ```
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>() + self.heap_size_bytes()
+    }
+
+    /// Calls `f` with the slot and value of every non-empty register.
+    ///
+    /// In coupon modes, slots keep all 26 bits of the coupon, so callers mask them to the
+    /// number of registers they fold into.
+    pub(super) fn for_each_register(&self, mut f: impl FnMut(u32, u8)) {
+        let mut from_container = |container: &Container| {
+            for coupon in container.iter() {
+                f(get_slot(coupon), get_value(coupon));
+            }
+        };
+        match &self.mode {
+            Mode::List { list, .. } => from_container(list.container()),
+            Mode::Set { set, .. } => from_container(set.container()),
+            Mode::Array4(arr) => {
+                for slot in 0..arr.num_registers() as u32 {
+                    f(slot, arr.get(slot));
+                }
+            }
+            Mode::Array6(arr) => {
+                for slot in 0..arr.num_registers() as u32 {
+                    f(slot, arr.get(slot));
+                }
+            }
+            Mode::Array8(arr) => {
+                for (slot, &value) in arr.values().iter().enumerate() {
+                    f(slot as u32, value);
+                }
+            }
+        }
+    }
+
+    pub(super) fn heap_size_bytes(&self) -> usize {
+        match &self.mode {
+            Mode::List { list, .. } => list.container().heap_size_bytes(),
+            Mode::Set { set, .. } => set.container().heap_size_bytes(),
+            Mode::Array4(arr) => arr.heap_size_bytes(),
+            Mode::Array6(arr) => arr.heap_size_bytes(),
+            Mode::Array8(arr) => arr.heap_size_bytes(),
+        }
+    }
+
+    /// Serializes the HLL sketch into the front of `buf`, returning the number of bytes written.
+    ///
+    /// The bytes are the same as those returned by [`HllSketch::serialize`]. The output is not
+    /// allocated, but a sketch in set mode still sorts its coupons in a temporary buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `buf` is shorter than [`HllSketch::serialized_size_bytes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::hll::HllSketch;
+    /// # use datasketches::hll::HllType;
+    /// # let mut sketch = HllSketch::new(10, HllType::Hll8);
+    /// # sketch.update("apple");
+    /// let mut buf = [0u8; 2048];
+    /// let len = sketch.serialize_into(&mut buf).unwrap();
+    /// let decoded = HllSketch::deserialize(&buf[..len]).unwrap();
+    /// assert_eq!(decoded.estimate(), sketch.estimate());
+    /// ```
+    pub fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        serialize_into_slice(buf, self.serialized_size_bytes(), |bytes| {
+            self.write_to(bytes)
+        })
+    }
+
+    fn write_to(&self, bytes: &mut SketchBytes<'_>) {
+        match &self.mode {
+            Mode::List { list, hll_type } => list.write_to(self.lg_config_k, *hll_type, bytes),
+            Mode::Set { set, hll_type } => set.write_to(self.lg_config_k, *hll_type, bytes),
+            Mode::Array4(arr) => arr.write_to(self.lg_config_k, bytes),
+            Mode::Array6(arr) => arr.write_to(self.lg_config_k, bytes),
+            Mode::Array8(arr) => arr.write_to(self.lg_config_k, bytes),
         }
     }
 }
@@ -447,3 +774,74 @@ fn promote_container_to_array(container: &Container, hll_type: HllType, lg_confi
         }
     }
 }
+
+impl fmt::Display for HllSketch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match &self.mode {
+            Mode::List { .. } => "LIST",
+            Mode::Set { .. } => "SET",
+            Mode::Array4(_) | Mode::Array6(_) | Mode::Array8(_) => "HLL",
+        };
+        let target_type = match self.target_type() {
+            HllType::Hll4 => "HLL_4",
+            HllType::Hll6 => "HLL_6",
+            HllType::Hll8 => "HLL_8",
+        };
+        Summary::new(f, "HLL SKETCH")
+            .field("lg config k", self.lg_config_k)
+            .field("hll target", target_type)
+            .field("current mode", mode)
+            .field("empty", self.is_empty())
+            .field("estimate", self.estimate())
+            .field("lower bound 95% conf", self.lower_bound(NumStdDev::Two))
+            .field("upper bound 95% conf", self.upper_bound(NumStdDev::Two))
+            .finish()
+    }
+}
+
+impl<I: Hash> Sketch<I> for HllSketch {
+    fn update(&mut self, item: I) {
+        HllSketch::update(self, item);
+    }
+
+    fn is_empty(&self) -> bool {
+        HllSketch::is_empty(self)
+    }
+}
+
+/// Merging is performed as by an [`HllUnion`](crate::hll::HllUnion) configured with the lg_k of
+/// this sketch, keeping the target HLL type of this sketch.
+impl MergeableSketch for HllSketch {
+    type Error = Infallible;
+
+    fn merge(&mut self, other: &Self) -> Result<(), Infallible> {
+        if other.is_empty() {
+            return Ok(());
+        }
+        let mut union = HllUnion::new(self.lg_config_k());
+        union.update(self);
+        union.update(other);
+        *self = union.to_sketch(self.target_type());
+        Ok(())
+    }
+}
+
+impl SerializableSketch for HllSketch {
+    type Error = Error;
+
+    fn serialize(&self) -> Vec<u8> {
+        HllSketch::serialize(self)
+    }
+
+    fn serialized_size_bytes(&self) -> usize {
+        HllSketch::serialized_size_bytes(self)
+    }
+
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        HllSketch::serialize_into(self, buf)
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        HllSketch::deserialize(bytes)
+    }
+}
@@ -27,15 +27,42 @@
 //! * Different lg_k values (automatically resizes as needed)
 //! * Different modes (List, Set, Array4/6/8)
 //! * Different target HLL types
+//!
+//! # Mixed lg_k
+//!
+//! As in datasketches-java, the union never rejects an input for its lg_k. Sketches in List or
+//! Set mode carry full-resolution coupons and are merged at whatever lg_k the union currently
+//! has. Sketches in HLL mode cannot be upsampled, so the union downsamples itself to the
+//! smallest lg_k among them, capped at `lg_max_k`:
+//!
+//! ```
+//! # use datasketches::hll::HllSketch;
+//! # use datasketches::hll::HllType;
+//! # use datasketches::hll::HllUnion;
+//! let mut old = HllSketch::new(14, HllType::Hll4);
+//! let mut new = HllSketch::new(11, HllType::Hll8);
+//! for i in 0..10_000 {
+//!     old.update(i);
+//!     new.update(i + 5_000);
+//! }
+//!
+//! let mut union = HllUnion::new(12);
+//! union.update(&old);
+//! assert_eq!(union.lg_config_k(), 12);
+//! union.update(&new);
+//! assert_eq!(union.lg_config_k(), 11);
+//! ```
 
 use std::hash::Hash;
 
 use crate::common::NumStdDev;
+use crate::error::Error;
 use crate::hll::HllSketch;
 use crate::hll::HllType;
 use crate::hll::array4::Array4;
 use crate::hll::array6::Array6;
 use crate::hll::array8::Array8;
+use crate::hll::container::Container;
 use crate::hll::mode::Mode;
 use crate::hll::pack_coupon;
 
@@ -63,6 +90,8 @@ impl HllUnion {
     ///   the maximum precision the union can handle. Input sketches with larger lg_k will be
     ///   down-sampled.
     ///
+    /// The fallible version of this method is [`HllUnion::try_new`].
+    ///
     /// # Panics
     ///
     /// Panics if `lg_max_k` is not in the range `[4, 21]`.
@@ -89,6 +118,22 @@ impl HllUnion {
         Self { lg_max_k, gadget }
     }
 
+    /// Create a new HLL Union.
+    ///
+    /// The panicking version of this method is [`HllUnion::new`].
+    ///
+    /// # Errors
+    ///
+    /// If `lg_max_k` is not in the range `[4, 21]`.
+    pub fn try_new(lg_max_k: u8) -> Result<Self, Error> {
+        if !(4..=21).contains(&lg_max_k) {
+            return Err(Error::invalid_argument(format!(
+                "lg_max_k must be in [4, 21], got {lg_max_k}"
+            )));
+        }
+        Ok(Self::new(lg_max_k))
+    }
+
     /// Update the union's gadget with a value
     ///
     /// This accepts any type that implements `Hash`. The value is hashed
@@ -150,6 +195,66 @@ impl HllUnion {
         }
     }
 
+    /// Update the union with every sketch of the given sequence
+    ///
+    /// Equivalent to calling [`update`](Self::update) for each sketch, for reducing many
+    /// per-partition sketches in one call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::hll::HllSketch;
+    /// # use datasketches::hll::HllType;
+    /// # use datasketches::hll::HllUnion;
+    /// let partitions: Vec<HllSketch> = (0..4)
+    ///     .map(|p| {
+    ///         let mut sketch = HllSketch::new(10, HllType::Hll8);
+    ///         sketch.update(p);
+    ///         sketch
+    ///     })
+    ///     .collect();
+    ///
+    /// let mut union = HllUnion::new(10);
+    /// union.union_many(&partitions);
+    /// assert!((union.estimate() - 4.0).abs() < 0.01);
+    /// ```
+    pub fn union_many<'a, I: IntoIterator<Item = &'a HllSketch>>(&mut self, sketches: I) {
+        for sketch in sketches {
+            self.update(sketch);
+        }
+    }
+
+    /// Update the union with a serialized sketch
+    ///
+    /// Equivalent to deserializing the image with [`HllSketch::deserialize`] and passing the
+    /// sketch to [`update`](Self::update). The decoded sketch is dropped right after it has been
+    /// merged, so a single union can fold any number of images while holding at most one of
+    /// them in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid HLL sketch. The union is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::hll::HllSketch;
+    /// # use datasketches::hll::HllType;
+    /// # use datasketches::hll::HllUnion;
+    /// let mut sketch = HllSketch::new(10, HllType::Hll4);
+    /// sketch.update("apple");
+    ///
+    /// let mut union = HllUnion::new(10);
+    /// union.update_serialized(&sketch.serialize()).unwrap();
+    /// assert!(union.update_serialized(&[1, 2, 3]).is_err());
+    /// assert!((union.estimate() - 1.0).abs() < 0.01);
+    /// ```
+    pub fn update_serialized(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let sketch = HllSketch::deserialize(bytes)?;
+        self.update(&sketch);
+        Ok(())
+    }
+
     /// Update union from a List or Set mode sketch
     fn update_from_list_or_set(
         &mut self,
@@ -330,6 +435,63 @@ impl HllUnion {
     pub fn lower_bound(&self, num_std_dev: NumStdDev) -> f64 {
         self.gadget.lower_bound(num_std_dev)
     }
+
+    /// Returns the number of bytes of memory the union occupies, including its internal sketch.
+    ///
+    /// See [`HllSketch::memory_usage_bytes`].
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>() + self.gadget.heap_size_bytes()
+    }
+}
+
+/// Returns the estimated number of distinct values in the union of the given serialized sketches.
+///
+/// This is the usual query of OLAP engines storing HLL sketches as opaque column values, as the
+/// `HLL_SKETCH_ESTIMATE(DS_HLL(...))` of Apache Druid: the images are merged one at a time into a
+/// single [`HllUnion`], see [`HllUnion::update_serialized`], without building a collection of
+/// intermediate sketches. The union is configured with the largest `lg_config_k` among the
+/// images, so no precision is lost to the union itself.
+///
+/// Returns 0.0 if there are no images. Use [`HllUnion::update_serialized`] directly to also get
+/// the bounds or the merged sketch.
+///
+/// # Errors
+///
+/// Returns an error if an image is not a valid HLL sketch.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::hll::HllSketch;
+/// # use datasketches::hll::HllType;
+/// # use datasketches::hll::estimate_union;
+/// let images: Vec<Vec<u8>> = (0..4)
+///     .map(|day| {
+///         let mut sketch = HllSketch::new(12, HllType::Hll4);
+///         for user in 0..1_000 {
+///             sketch.update(day * 500 + user);
+///         }
+///         sketch.serialize()
+///     })
+///     .collect();
+///
+/// let estimate = estimate_union(&images).unwrap();
+/// assert!((estimate - 2_500.0).abs() < 2_500.0 * 0.05);
+/// ```
+pub fn estimate_union<B: AsRef<[u8]>>(images: &[B]) -> Result<f64, Error> {
+    // lg_config_k is the fourth byte of the preamble, validated when the image is decoded
+    let lg_max_k = images
+        .iter()
+        .filter_map(|image| image.as_ref().get(3).copied())
+        .filter(|lg_k| (4..=21).contains(lg_k))
+        .max()
+        .unwrap_or(4);
+
+    let mut union = HllUnion::new(lg_max_k);
+    for image in images {
+        union.update_serialized(image.as_ref())?;
+    }
+    Ok(union.estimate())
 }
 
 /// Convert a coupon mode (List or Set) to Hll8 target type
@@ -365,7 +527,7 @@ fn merge_coupons_into_gadget(gadget: &mut HllSketch, src_mode: &Mode) {
             }
         }
         Mode::Set { set, .. } => {
-            for coupon in set.container().iter() {
+            for coupon in sorted_coupons(set.container()) {
                 gadget.update_with_coupon(coupon);
             }
         }
@@ -377,6 +539,18 @@ fn merge_coupons_into_gadget(gadget: &mut HllSketch, src_mode: &Mode) {
     }
 }
 
+/// Collect the coupons of a hash set in ascending order
+///
+/// The slot layout of a hash set depends on its insertion history, so two sets holding the
+/// same coupons (e.g. a sketch and its deserialized copy) may iterate differently. HIP
+/// accumulation in an array-mode gadget is order-dependent; merging in a canonical order
+/// keeps the union result independent of how the set was built.
+fn sorted_coupons(container: &Container) -> Vec<u32> {
+    let mut coupons: Vec<u32> = container.iter().collect();
+    coupons.sort_unstable();
+    coupons
+}
+
 /// Merge coupons from a List or Set mode into an Array8
 fn merge_coupons_into_mode(dst: &mut Array8, src_mode: &Mode) {
     match src_mode {
@@ -386,7 +560,7 @@ fn merge_coupons_into_mode(dst: &mut Array8, src_mode: &Mode) {
             }
         }
         Mode::Set { set, .. } => {
-            for coupon in set.container().iter() {
+            for coupon in sorted_coupons(set.container()) {
                 dst.update(coupon);
             }
         }
@@ -0,0 +1,341 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::hash::Hash;
+
+use crate::codec::family::Family;
+use crate::common::NumStdDev;
+use crate::error::Error;
+use crate::hll::HllSketch;
+use crate::hll::array8::Array8;
+use crate::hll::coupon;
+use crate::hll::estimator::HipEstimator;
+use crate::hll::get_slot;
+use crate::hll::get_value;
+use crate::hll::mode::Mode;
+use crate::hll::serialization::CUR_MODE_HLL;
+use crate::hll::serialization::HLL_PREAMBLE_SIZE;
+use crate::hll::serialization::HLL_PREINTS;
+use crate::hll::serialization::OUT_OF_ORDER_FLAG_MASK;
+use crate::hll::serialization::SERIAL_VERSION;
+use crate::hll::serialization::TGT_HLL8;
+use crate::hll::serialization::encode_mode_byte;
+
+// offsets into the HLL preamble
+const LG_CONFIG_K_BYTE: usize = 3;
+const FLAGS_BYTE: usize = 5;
+const CUR_MIN_BYTE: usize = 6;
+const MODE_BYTE: usize = 7;
+const HIP_ACCUM_DOUBLE: usize = 8;
+const KXQ0_DOUBLE: usize = 16;
+const KXQ1_DOUBLE: usize = 24;
+const NUM_AT_CUR_MIN_INT: usize = 32;
+const AUX_COUNT_INT: usize = 36;
+
+/// An HLL union that operates directly on the serialized image of its result.
+///
+/// The target buffer holds a dense [`HllType::Hll8`](crate::hll::HllType::Hll8) sketch with one
+/// byte per register, which is updated in place: merging a sketch or a value only raises
+/// registers and patches the estimator fields of the preamble, without allocating or
+/// deserializing the target. This suits aggregators that keep one union per key in a large
+/// arena, or in shared memory, and merge many small sketches into it; sparse inputs in list or
+/// set mode cost time proportional to their coupons only.
+///
+/// The buffer is a valid serialized sketch at any time, readable with
+/// [`HllSketch::deserialize`] or wrapped again later.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::hll::HllSketch;
+/// # use datasketches::hll::HllType;
+/// # use datasketches::hll::WrappedHllUnion;
+/// let mut arena = vec![0u8; WrappedHllUnion::required_bytes(12)];
+/// let mut union = WrappedHllUnion::initialize(&mut arena, 12).unwrap();
+///
+/// for partition in 0..4 {
+///     let mut sketch = HllSketch::new(12, HllType::Hll4);
+///     for i in 0..1000 {
+///         sketch.update(partition * 500 + i);
+///     }
+///     union.update(&sketch).unwrap();
+/// }
+/// assert!((union.estimate() - 2500.0).abs() < 100.0);
+///
+/// let result = HllSketch::deserialize(&arena).unwrap();
+/// assert!((result.estimate() - 2500.0).abs() < 100.0);
+/// ```
+#[derive(Debug)]
+pub struct WrappedHllUnion<'a> {
+    lg_config_k: u8,
+    bytes: &'a mut [u8],
+}
+
+impl<'a> WrappedHllUnion<'a> {
+    /// Returns the number of bytes of the image of a union with `2^lg_config_k` registers.
+    pub fn required_bytes(lg_config_k: u8) -> usize {
+        HLL_PREAMBLE_SIZE + (1 << lg_config_k)
+    }
+
+    /// Writes an empty union with `2^lg_config_k` registers to the front of `bytes` and wraps it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `lg_config_k` is not in `[4, 21]`, or if `bytes` is shorter than
+    /// [`required_bytes`](Self::required_bytes).
+    pub fn initialize(bytes: &'a mut [u8], lg_config_k: u8) -> Result<Self, Error> {
+        if !(4..=21).contains(&lg_config_k) {
+            return Err(Error::invalid_argument(format!(
+                "lg_config_k must be in [4, 21], got {lg_config_k}"
+            )));
+        }
+        let empty = HllSketch::from_mode(lg_config_k, Mode::Array8(Array8::new(lg_config_k)));
+        let len = empty.serialize_into(bytes)?;
+        Ok(Self {
+            lg_config_k,
+            bytes: &mut bytes[..len],
+        })
+    }
+
+    /// Wraps the serialized image of a dense `Hll8` sketch, e.g. a union initialized earlier.
+    ///
+    /// Trailing bytes after the image are left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` does not start with the image of an `Hll8` sketch in HLL
+    /// mode, or if its registers disagree with its number of zero registers. Sketches in list or
+    /// set mode, or of other HLL types, can be merged into a wrapped union but not wrapped
+    /// themselves.
+    pub fn wrap(bytes: &'a mut [u8]) -> Result<Self, Error> {
+        if bytes.len() < HLL_PREAMBLE_SIZE {
+            return Err(Error::insufficient_data(format!(
+                "HLL preamble needs {HLL_PREAMBLE_SIZE} bytes, got {}",
+                bytes.len()
+            )));
+        }
+        if bytes[0] != HLL_PREINTS {
+            return Err(Error::bad_preamble(format!(
+                "HLL mode preamble: expected {HLL_PREINTS}, got {}",
+                bytes[0]
+            )));
+        }
+        if bytes[1] != SERIAL_VERSION {
+            return Err(Error::unsupported_version(format!(
+                "unsupported serial version: expected {SERIAL_VERSION}, got {}",
+                bytes[1]
+            )));
+        }
+        Family::HLL.validate_id(bytes[2])?;
+        let lg_config_k = bytes[LG_CONFIG_K_BYTE];
+        if !(4..=21).contains(&lg_config_k) {
+            return Err(Error::bad_preamble(format!(
+                "lg_k must be in [4; 21], got {lg_config_k}",
+            )));
+        }
+        if bytes[MODE_BYTE] != encode_mode_byte(CUR_MODE_HLL, TGT_HLL8) {
+            return Err(Error::bad_preamble(
+                "only dense HLL_8 sketches can be wrapped",
+            ));
+        }
+        if bytes[CUR_MIN_BYTE] != 0 || read_u32(bytes, AUX_COUNT_INT) != 0 {
+            return Err(Error::bad_preamble(
+                "HLL_8 sketches have neither cur_min nor aux entries",
+            ));
+        }
+        let len = Self::required_bytes(lg_config_k);
+        if bytes.len() < len {
+            return Err(Error::insufficient_data(format!(
+                "HLL_8 sketch with lg_k {lg_config_k} needs {len} bytes, got {}",
+                bytes.len()
+            )));
+        }
+        // updates rely on the zero count and register range that `HllSketch::deserialize` checks
+        let registers = &bytes[HLL_PREAMBLE_SIZE..len];
+        if let Some(value) = registers.iter().find(|&&value| value > 63) {
+            return Err(Error::deserial(format!(
+                "register values must be at most 63, got {value}"
+            )));
+        }
+        let num_zeros = read_u32(bytes, NUM_AT_CUR_MIN_INT);
+        let actual_zeros = registers.iter().filter(|&&value| value == 0).count();
+        if actual_zeros != num_zeros as usize {
+            return Err(Error::deserial(format!(
+                "num_zeros {num_zeros} does not match {actual_zeros} zero registers"
+            )));
+        }
+        Ok(Self {
+            lg_config_k,
+            bytes: &mut bytes[..len],
+        })
+    }
+
+    /// Returns the log2 of the number of registers.
+    pub fn lg_config_k(&self) -> u8 {
+        self.lg_config_k
+    }
+
+    /// Returns true if no register is set.
+    pub fn is_empty(&self) -> bool {
+        self.num_zeros() == 1 << self.lg_config_k
+    }
+
+    /// Updates the union with a value, as [`HllSketch::update`] would.
+    pub fn update_value<T: Hash>(&mut self, value: T) {
+        let coupon = coupon(value);
+        let mut estimator = self.estimator();
+        let mut num_zeros = self.num_zeros();
+        let mask = (1u32 << self.lg_config_k) - 1;
+        let lg_config_k = self.lg_config_k;
+        let register = &mut self.bytes[HLL_PREAMBLE_SIZE + (get_slot(coupon) & mask) as usize];
+        raise(
+            register,
+            get_value(coupon),
+            lg_config_k,
+            &mut estimator,
+            &mut num_zeros,
+        );
+        self.store(&estimator, num_zeros);
+    }
+
+    /// Merges a sketch into the union.
+    ///
+    /// Sketches in list or set mode may have any `lg_config_k`; dense sketches are folded down
+    /// to the registers of the union and must have at least as many.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `sketch` is dense with a smaller `lg_config_k` than the union, which
+    /// would require downsampling the target in place. The union is left unchanged.
+    pub fn update(&mut self, sketch: &HllSketch) -> Result<(), Error> {
+        if sketch.is_empty() {
+            return Ok(());
+        }
+        let is_dense = matches!(
+            sketch.mode(),
+            Mode::Array4(_) | Mode::Array6(_) | Mode::Array8(_)
+        );
+        if is_dense && sketch.lg_config_k() < self.lg_config_k {
+            return Err(Error::invalid_argument(
+                "cannot merge a dense sketch with fewer registers into a wrapped union",
+            )
+            .with_context("lg_config_k", sketch.lg_config_k())
+            .with_context("union_lg_config_k", self.lg_config_k));
+        }
+
+        let mut estimator = self.estimator();
+        // the HIP accumulator does not survive a merge
+        estimator.set_out_of_order(true);
+        let mut num_zeros = self.num_zeros();
+        let mask = (1u32 << self.lg_config_k) - 1;
+        let lg_config_k = self.lg_config_k;
+        let registers = &mut self.bytes[HLL_PREAMBLE_SIZE..];
+        sketch.for_each_register(|slot, value| {
+            raise(
+                &mut registers[(slot & mask) as usize],
+                value,
+                lg_config_k,
+                &mut estimator,
+                &mut num_zeros,
+            );
+        });
+        self.store(&estimator, num_zeros);
+        Ok(())
+    }
+
+    /// Merges a serialized sketch into the union.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid HLL sketch, or under the conditions of
+    /// [`update`](Self::update). The union is left unchanged.
+    pub fn update_serialized(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.update(&HllSketch::deserialize(bytes)?)
+    }
+
+    /// Returns the cardinality estimate of the union.
+    pub fn estimate(&self) -> f64 {
+        self.estimator()
+            .estimate(self.lg_config_k, 0, self.num_zeros())
+    }
+
+    /// Returns the upper bound of the cardinality estimate.
+    pub fn upper_bound(&self, num_std_dev: NumStdDev) -> f64 {
+        self.estimator()
+            .upper_bound(self.lg_config_k, 0, self.num_zeros(), num_std_dev)
+    }
+
+    /// Returns the lower bound of the cardinality estimate.
+    pub fn lower_bound(&self, num_std_dev: NumStdDev) -> f64 {
+        self.estimator()
+            .lower_bound(self.lg_config_k, 0, self.num_zeros(), num_std_dev)
+    }
+
+    fn num_zeros(&self) -> u32 {
+        read_u32(self.bytes, NUM_AT_CUR_MIN_INT)
+    }
+
+    fn estimator(&self) -> HipEstimator {
+        let mut estimator = HipEstimator::new(self.lg_config_k);
+        estimator.set_out_of_order(self.bytes[FLAGS_BYTE] & OUT_OF_ORDER_FLAG_MASK != 0);
+        estimator.set_hip_accum(read_f64(self.bytes, HIP_ACCUM_DOUBLE));
+        estimator.set_kxq0(read_f64(self.bytes, KXQ0_DOUBLE));
+        estimator.set_kxq1(read_f64(self.bytes, KXQ1_DOUBLE));
+        estimator
+    }
+
+    fn store(&mut self, estimator: &HipEstimator, num_zeros: u32) {
+        if estimator.is_out_of_order() {
+            self.bytes[FLAGS_BYTE] |= OUT_OF_ORDER_FLAG_MASK;
+        }
+        write_f64(self.bytes, HIP_ACCUM_DOUBLE, estimator.hip_accum());
+        write_f64(self.bytes, KXQ0_DOUBLE, estimator.kxq0());
+        write_f64(self.bytes, KXQ1_DOUBLE, estimator.kxq1());
+        self.bytes[NUM_AT_CUR_MIN_INT..NUM_AT_CUR_MIN_INT + 4]
+            .copy_from_slice(&num_zeros.to_le_bytes());
+    }
+}
+
+/// Raises `register` to `value`, keeping the estimator and the count of zero registers in step.
+fn raise(
+    register: &mut u8,
+    value: u8,
+    lg_config_k: u8,
+    estimator: &mut HipEstimator,
+    num_zeros: &mut u32,
+) {
+    let old_value = *register;
+    if value > old_value {
+        estimator.update(lg_config_k, old_value, value);
+        if old_value == 0 {
+            *num_zeros -= 1;
+        }
+        *register = value;
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_f64(bytes: &[u8], offset: usize) -> f64 {
+    f64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn write_f64(bytes: &mut [u8], offset: usize, value: f64) {
+    bytes[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
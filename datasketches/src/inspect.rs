@@ -0,0 +1,160 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Preamble inspection of serialized sketches.
+
+use crate::codec::SketchSlice;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
+use crate::error::Error;
+
+/// A summary of a serialized sketch, read from its preamble by [`inspect`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SketchInfo {
+    pub(crate) family: &'static str,
+    pub(crate) family_id: u8,
+    pub(crate) serial_version: u8,
+    pub(crate) lg_k: Option<u8>,
+    pub(crate) k: Option<u16>,
+    pub(crate) is_empty: bool,
+    pub(crate) is_estimation_mode: Option<bool>,
+    pub(crate) num_retained: Option<u64>,
+}
+
+impl SketchInfo {
+    pub(crate) fn new(family: &Family, serial_version: u8) -> Self {
+        SketchInfo {
+            family: family.name,
+            family_id: family.id,
+            serial_version,
+            lg_k: None,
+            k: None,
+            is_empty: false,
+            is_estimation_mode: None,
+            num_retained: None,
+        }
+    }
+
+    /// Returns the family name, e.g. `"THETA"`, as used by datasketches-java and datasketches-cpp.
+    pub fn family(&self) -> &'static str {
+        self.family
+    }
+
+    /// Returns the family ID stored in the preamble.
+    pub fn family_id(&self) -> u8 {
+        self.family_id
+    }
+
+    /// Returns the serial version stored in the preamble.
+    pub fn serial_version(&self) -> u8 {
+        self.serial_version
+    }
+
+    /// Returns the log2 of the size parameter of families configured by it: the nominal entries
+    /// of updatable theta sketches, `lg_config_k` of HLL and CPC sketches, and the maximum map
+    /// size of frequent items sketches.
+    ///
+    /// Compact theta images do not record it.
+    pub fn lg_k(&self) -> Option<u8> {
+        self.lg_k
+    }
+
+    /// Returns the parameter k of KLL sketches and t-digests.
+    pub fn k(&self) -> Option<u16> {
+        self.k
+    }
+
+    /// Returns true if the sketch has seen no data.
+    pub fn is_empty(&self) -> bool {
+        self.is_empty
+    }
+
+    /// Returns whether the sketch has started to approximate, or `None` for families where the
+    /// notion does not apply.
+    ///
+    /// Theta sketches estimate once theta drops below 1, HLL sketches once they leave the list
+    /// and set modes, KLL sketches once they compact their first level, and frequent items
+    /// sketches once they purge their first counters.
+    pub fn is_estimation_mode(&self) -> Option<bool> {
+        self.is_estimation_mode
+    }
+
+    /// Returns the number of retained entries: hashes of theta sketches, coupons of HLL sketches
+    /// in list and set mode and of CPC sketches, items of KLL and frequent items sketches, and
+    /// centroids and buffered values of t-digests.
+    ///
+    /// Returns `None` for HLL sketches in HLL mode, CountMin sketches and Bloom filters.
+    pub fn num_retained(&self) -> Option<u64> {
+        self.num_retained
+    }
+}
+
+type Inspector = fn(u8, u8, SketchSlice<'_>) -> Result<SketchInfo, Error>;
+
+const INSPECTORS: &[(&Family, Inspector)] = &[
+    (&Family::QUICKSELECT, crate::theta::inspect_update),
+    (&Family::THETA, crate::theta::inspect_compact),
+    (&Family::HLL, crate::hll::inspect),
+    (&Family::FREQUENCY, crate::frequencies::inspect),
+    (&Family::KLL, crate::kll::inspect),
+    (&Family::CPC, crate::cpc::inspect),
+    (&Family::COUNTMIN, crate::countmin::inspect),
+    (&Family::TDIGEST, crate::tdigest::inspect),
+    (&Family::BLOOMFILTER, crate::bloom::inspect),
+];
+
+/// Reads the preamble of a serialized sketch of any family without deserializing it.
+///
+/// Storage systems can use it to catalog opaque sketch blobs: only the first few bytes are read
+/// and checked, so the call is cheap regardless of the size of the sketch, and a successful call
+/// does not guarantee that the payload itself is valid.
+///
+/// Images whose preamble does not start with a size, serial version and family ID, i.e.
+/// [`MultiKll`](crate::kll::MultiKll) images and the reference t-digest formats, are not
+/// recognized.
+///
+/// # Errors
+///
+/// Returns an error if the family ID is unknown, if the serial version is not supported, or if
+/// the bytes end before the preamble does.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::hll::HllSketch;
+/// # use datasketches::hll::HllType;
+/// let mut sketch = HllSketch::new(12, HllType::Hll8);
+/// sketch.update("apple");
+/// let info = datasketches::inspect(&sketch.serialize()).unwrap();
+/// assert_eq!(info.family(), "HLL");
+/// assert_eq!(info.lg_k(), Some(12));
+/// assert_eq!(info.num_retained(), Some(1));
+/// assert_eq!(info.is_estimation_mode(), Some(false));
+/// ```
+pub fn inspect(bytes: &[u8]) -> Result<SketchInfo, Error> {
+    let mut cursor = SketchSlice::new(bytes);
+    let preamble = cursor.read_u8().map_err(insufficient_data("preamble"))?;
+    let serial_version = cursor
+        .read_u8()
+        .map_err(insufficient_data("serial_version"))?;
+    let family_id = cursor.read_u8().map_err(insufficient_data("family_id"))?;
+    let (_, inspector) = INSPECTORS
+        .iter()
+        .find(|(family, _)| family.id == family_id)
+        .ok_or_else(|| Error::bad_preamble(format!("unknown family ID: {family_id}")))?;
+    inspector(preamble, serial_version, cursor)
+}
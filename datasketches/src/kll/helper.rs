@@ -0,0 +1,322 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Capacity computations and compaction primitives shared by the KLL sketches.
+//!
+//! The items of a sketch live in a single buffer. Level `i` occupies
+//! `items[levels[i]..levels[i + 1]]`, and the space in front of `levels[0]` is free.
+
+use std::cmp::Ordering;
+
+use crate::common::random::RandomSource;
+
+const POWERS_OF_THREE: [u64; 31] = [
+    1,
+    3,
+    9,
+    27,
+    81,
+    243,
+    729,
+    2187,
+    6561,
+    19683,
+    59049,
+    177147,
+    531441,
+    1594323,
+    4782969,
+    14348907,
+    43046721,
+    129140163,
+    387420489,
+    1162261467,
+    3486784401,
+    10460353203,
+    31381059609,
+    94143178827,
+    282429536481,
+    847288609443,
+    2541865828329,
+    7625597484987,
+    22876792454961,
+    68630377364883,
+    205891132094649,
+];
+
+pub(super) fn compare<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+    a.partial_cmp(b).expect("KLL items must be totally ordered")
+}
+
+/// Returns the capacity of the level at `height` in a sketch with `num_levels` levels.
+pub(super) fn level_capacity(k: u16, num_levels: usize, height: usize, m: u8) -> usize {
+    debug_assert!(height < num_levels);
+    let depth = num_levels - height - 1;
+    (m as usize).max(int_cap_aux(k as u64, depth) as usize)
+}
+
+/// Returns the total capacity of a sketch with `num_levels` levels.
+pub(super) fn compute_total_capacity(k: u16, m: u8, num_levels: usize) -> usize {
+    (0..num_levels)
+        .map(|height| level_capacity(k, num_levels, height, m))
+        .sum()
+}
+
+/// Returns an upper bound on the number of levels needed for a stream of `n` items.
+pub(super) fn ub_on_num_levels(n: u64) -> usize {
+    if n == 0 { 1 } else { 1 + n.ilog2() as usize }
+}
+
+// approximates k * (2/3)^depth with rounding
+fn int_cap_aux(k: u64, depth: usize) -> u64 {
+    assert!(depth <= 60, "depth must not exceed 60, got {depth}");
+    if depth <= 30 {
+        return int_cap_aux_aux(k, depth);
+    }
+    let half = depth / 2;
+    let rest = depth - half;
+    int_cap_aux_aux(int_cap_aux_aux(k, half), rest)
+}
+
+fn int_cap_aux_aux(k: u64, depth: usize) -> u64 {
+    let twok = k << 1; // for rounding at the end
+    let tmp = (twok << depth) / POWERS_OF_THREE[depth];
+    (tmp + 1) >> 1
+}
+
+/// Keeps a random half of the sorted items in `buf[start..start + length]`, moved to the front
+/// of that range.
+pub(super) fn randomly_halve_down<T: Copy>(
+    buf: &mut [T],
+    start: usize,
+    length: usize,
+    random: &mut RandomSource,
+) {
+    debug_assert!(length % 2 == 0);
+    let half_length = length / 2;
+    let mut j = start + random.next_bit();
+    for i in start..start + half_length {
+        buf[i] = buf[j];
+        j += 2;
+    }
+}
+
+/// Keeps a random half of the sorted items in `buf[start..start + length]`, moved to the back
+/// of that range.
+pub(super) fn randomly_halve_up<T: Copy>(
+    buf: &mut [T],
+    start: usize,
+    length: usize,
+    random: &mut RandomSource,
+) {
+    debug_assert!(length % 2 == 0);
+    let half_length = length / 2;
+    let mut j = start + length - 1 - random.next_bit();
+    for i in (start + half_length..start + length).rev() {
+        buf[i] = buf[j];
+        j = j.wrapping_sub(2);
+    }
+}
+
+/// Merges the sorted ranges `buf[start_a..start_a + len_a]` and `buf[start_b..start_b + len_b]`
+/// into `buf[start_c..]`.
+///
+/// The output may overlap the inputs as long as it never overtakes an unread item, which holds
+/// for the compaction layouts used in this module.
+pub(super) fn merge_sorted_in_place<T: Copy + PartialOrd>(
+    buf: &mut [T],
+    start_a: usize,
+    len_a: usize,
+    start_b: usize,
+    len_b: usize,
+    start_c: usize,
+) {
+    let lim_a = start_a + len_a;
+    let lim_b = start_b + len_b;
+    let (mut a, mut b) = (start_a, start_b);
+    for c in start_c..start_c + len_a + len_b {
+        if a == lim_a {
+            buf[c] = buf[b];
+            b += 1;
+        } else if b == lim_b || compare(&buf[a], &buf[b]) == Ordering::Less {
+            buf[c] = buf[a];
+            a += 1;
+        } else {
+            buf[c] = buf[b];
+            b += 1;
+        }
+    }
+}
+
+/// Merges the sorted slices `a` and `b` into `out`, which must have the combined length.
+pub(super) fn merge_sorted<T: Copy + PartialOrd>(a: &[T], b: &[T], out: &mut [T]) {
+    debug_assert_eq!(a.len() + b.len(), out.len());
+    let (mut i, mut j) = (0, 0);
+    for slot in out.iter_mut() {
+        if j == b.len() || (i < a.len() && compare(&a[i], &b[j]) == Ordering::Less) {
+            *slot = a[i];
+            i += 1;
+        } else {
+            *slot = b[j];
+            j += 1;
+        }
+    }
+}
+
+/// The outcome of [`general_compress`].
+pub(super) struct CompressResult {
+    pub final_num_levels: usize,
+    pub final_capacity: usize,
+    pub final_num_items: usize,
+}
+
+/// Compacts the levels described by `in_levels` until the items fit into the capacity of the
+/// resulting number of levels.
+///
+/// `items` is used as both the input and the output. `in_levels` and `out_levels` must have room
+/// for at least two more entries than the final number of levels.
+pub(super) fn general_compress<T: Copy + PartialOrd>(
+    k: u16,
+    m: u8,
+    num_levels_in: usize,
+    items: &mut [T],
+    in_levels: &mut [usize],
+    out_levels: &mut [usize],
+    is_level_zero_sorted: bool,
+    random: &mut RandomSource,
+) -> CompressResult {
+    assert!(num_levels_in > 0, "num_levels_in must be positive");
+    let mut current_num_levels = num_levels_in;
+    // decreases with each compaction
+    let mut current_item_count = in_levels[num_levels_in] - in_levels[0];
+    // increases if we add levels
+    let mut target_item_count = compute_total_capacity(k, m, current_num_levels);
+    out_levels[0] = 0;
+
+    let mut current_level = 0;
+    loop {
+        // if we are at the current top level, add an empty level above it for convenience,
+        // but do not increment the number of levels until later
+        if current_level == current_num_levels - 1 {
+            in_levels[current_level + 2] = in_levels[current_level + 1];
+        }
+
+        let raw_beg = in_levels[current_level];
+        let raw_lim = in_levels[current_level + 1];
+        let raw_pop = raw_lim - raw_beg;
+
+        if current_item_count < target_item_count
+            || raw_pop < level_capacity(k, current_num_levels, current_level, m)
+        {
+            // move the level over as is; data never moves upwards
+            debug_assert!(raw_beg >= out_levels[current_level]);
+            items.copy_within(raw_beg..raw_lim, out_levels[current_level]);
+            out_levels[current_level + 1] = out_levels[current_level] + raw_pop;
+        } else {
+            // the sketch is too full AND this level is too full, so we compact it;
+            // this can add a level and thus change the capacities
+            let pop_above = in_levels[current_level + 2] - raw_lim;
+            let odd_pop = raw_pop % 2 == 1;
+            let adj_beg = if odd_pop { raw_beg + 1 } else { raw_beg };
+            let adj_pop = if odd_pop { raw_pop - 1 } else { raw_pop };
+            let half_adj_pop = adj_pop / 2;
+
+            if odd_pop {
+                // move one item over
+                items[out_levels[current_level]] = items[raw_beg];
+                out_levels[current_level + 1] = out_levels[current_level] + 1;
+            } else {
+                out_levels[current_level + 1] = out_levels[current_level];
+            }
+
+            // level zero might not be sorted, so we must sort it if we wish to compact it
+            if current_level == 0 && !is_level_zero_sorted {
+                items[adj_beg..adj_beg + adj_pop].sort_unstable_by(compare);
+            }
+
+            if pop_above == 0 {
+                randomly_halve_up(items, adj_beg, adj_pop, random);
+            } else {
+                randomly_halve_down(items, adj_beg, adj_pop, random);
+                merge_sorted_in_place(
+                    items,
+                    adj_beg,
+                    half_adj_pop,
+                    raw_lim,
+                    pop_above,
+                    adj_beg + half_adj_pop,
+                );
+            }
+
+            // track the fact that we just eliminated some data
+            current_item_count -= half_adj_pop;
+            // adjust the boundaries of the level above
+            in_levels[current_level + 1] -= half_adj_pop;
+
+            // increment the number of levels if we just compacted the old top level,
+            // which creates some more capacity (the size of the new bottom level)
+            if current_level == current_num_levels - 1 {
+                current_num_levels += 1;
+                target_item_count += level_capacity(k, current_num_levels, 0, m);
+            }
+        }
+
+        // determine whether we have processed all levels, including any new ones
+        if current_level == current_num_levels - 1 {
+            break;
+        }
+        current_level += 1;
+    }
+
+    debug_assert_eq!(
+        out_levels[current_num_levels] - out_levels[0],
+        current_item_count
+    );
+    CompressResult {
+        final_num_levels: current_num_levels,
+        final_capacity: target_item_count,
+        final_num_items: current_item_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_capacities() {
+        assert_eq!(level_capacity(200, 1, 0, 8), 200);
+        assert_eq!(level_capacity(200, 2, 0, 8), 133);
+        assert_eq!(level_capacity(200, 2, 1, 8), 200);
+        assert_eq!(level_capacity(200, 20, 0, 8), 8);
+        assert_eq!(compute_total_capacity(200, 8, 1), 200);
+        assert_eq!(compute_total_capacity(200, 8, 2), 333);
+        assert_eq!(compute_total_capacity(8, 8, 3), 24);
+    }
+
+    #[test]
+    fn test_merge_sorted() {
+        let mut out = [0; 7];
+        merge_sorted(&[1, 4, 6], &[2, 3, 5, 7], &mut out);
+        assert_eq!(out, [1, 2, 3, 4, 5, 6, 7]);
+
+        let mut buf = [1, 3, 0, 0, 2, 4, 5];
+        // halved items at [0..2) merged with the level above at [4..7) into [2..7)
+        merge_sorted_in_place(&mut buf, 0, 2, 4, 3, 2);
+        assert_eq!(&buf[2..], &[1, 2, 3, 4, 5]);
+    }
+}
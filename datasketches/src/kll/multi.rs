@@ -0,0 +1,322 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::convert::Infallible;
+
+use crate::codec::SketchBytes;
+use crate::codec::SketchSlice;
+use crate::codec::assert::ensure_serial_version_is;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
+use crate::codec::serialize_into_slice;
+use crate::common::MergeableSketch;
+use crate::common::SerializableSketch;
+use crate::common::Sketch;
+use crate::error::Error;
+use crate::kll::KllSketch;
+use crate::kll::sketch::DEFAULT_K;
+
+const MULTI_SERIAL_VERSION: u8 = 1;
+
+/// A group of `D` KLL sketches that track the dimensions of `[f64; D]` rows.
+///
+/// This is convenient when metric tuples, such as (latency, payload size, queue depth), are
+/// always recorded together. Each dimension is an independent [`KllSketch`] with the same k, so
+/// the accuracy guarantees of a single sketch apply per dimension.
+///
+/// NaN values are ignored per dimension, so the dimensions may have seen different numbers of
+/// items.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::kll::MultiKll;
+/// let mut sketch = MultiKll::<3>::new(200);
+/// for i in 0..100 {
+///     let i = i as f64;
+///     sketch.update([i, i * 10.0, 100.0 - i]);
+/// }
+/// let medians = sketch.quantiles(0.5);
+/// assert_eq!(medians, [Some(49.0), Some(490.0), Some(50.0)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultiKll<const D: usize> {
+    sketches: [KllSketch<f64>; D],
+}
+
+impl<const D: usize> Default for MultiKll<D> {
+    fn default() -> Self {
+        MultiKll::new(DEFAULT_K)
+    }
+}
+
+impl<const D: usize> MultiKll<D> {
+    /// Creates a sketch group where every dimension uses the given value of k.
+    ///
+    /// The fallible version of this method is [`MultiKll::try_new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if D is zero or k is less than 8.
+    pub fn new(k: u16) -> Self {
+        assert!(D > 0, "number of dimensions must be positive");
+        MultiKll {
+            sketches: std::array::from_fn(|_| KllSketch::new(k)),
+        }
+    }
+
+    /// Creates a sketch group where every dimension uses the given value of k.
+    ///
+    /// The panicking version of this method is [`MultiKll::new`].
+    ///
+    /// # Errors
+    ///
+    /// If D is zero or k is less than 8.
+    pub fn try_new(k: u16) -> Result<Self, Error> {
+        if D == 0 {
+            return Err(Error::invalid_argument(
+                "number of dimensions must be positive",
+            ));
+        }
+        KllSketch::<f64>::try_new(k)?;
+        Ok(Self::new(k))
+    }
+
+    /// Returns parameter k that was used to configure the sketches.
+    pub fn k(&self) -> u16 {
+        self.sketches[0].k()
+    }
+
+    /// Returns true if none of the dimensions has seen any data.
+    pub fn is_empty(&self) -> bool {
+        self.sketches.iter().all(KllSketch::is_empty)
+    }
+
+    /// Returns the sketch of the given dimension.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than D.
+    pub fn dimension(&self, index: usize) -> &KllSketch<f64> {
+        &self.sketches[index]
+    }
+
+    /// Returns the sketches of all dimensions.
+    pub fn dimensions(&self) -> &[KllSketch<f64>; D] {
+        &self.sketches
+    }
+
+    /// Updates every dimension with the corresponding value of the row.
+    pub fn update(&mut self, row: [f64; D]) {
+        for (sketch, value) in self.sketches.iter_mut().zip(row) {
+            sketch.update(value);
+        }
+    }
+
+    /// Merges the given sketch group into this one, dimension by dimension.
+    pub fn merge(&mut self, other: &MultiKll<D>) {
+        for (sketch, other) in self.sketches.iter_mut().zip(&other.sketches) {
+            sketch.merge(other);
+        }
+    }
+
+    /// Resets all dimensions to the empty state.
+    pub fn reset(&mut self) {
+        self.sketches.iter_mut().for_each(KllSketch::reset);
+    }
+
+    /// Returns the approximate quantile of every dimension at the given normalized rank.
+    ///
+    /// An entry is `None` if the corresponding dimension is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rank is not in `[0.0, 1.0]`.
+    pub fn quantiles(&self, rank: f64) -> [Option<f64>; D] {
+        std::array::from_fn(|i| self.sketches[i].quantile(rank))
+    }
+
+    /// Returns the approximate normalized rank of every value of the row in its dimension.
+    ///
+    /// An entry is `None` if the corresponding dimension is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any value of the row is NaN.
+    pub fn ranks(&self, row: [f64; D]) -> [Option<f64>; D] {
+        std::array::from_fn(|i| self.sketches[i].rank(row[i]))
+    }
+
+    /// Serializes all dimensions to bytes.
+    ///
+    /// The format is a 4-byte header (serial version, family ID, 2 unused bytes), the number of
+    /// dimensions as a 32-bit integer, and then every dimension as a 32-bit length followed by
+    /// the compact KLL bytes of that dimension. All integers are little-endian.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::MultiKll;
+    /// let mut sketch = MultiKll::<2>::new(200);
+    /// sketch.update([1.0, 2.0]);
+    /// let bytes = sketch.serialize();
+    /// let decoded = MultiKll::<2>::deserialize(&bytes).unwrap();
+    /// assert_eq!(decoded.quantiles(0.5), [Some(1.0), Some(2.0)]);
+    /// ```
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = SketchBytes::with_capacity(self.serialized_size_bytes());
+        self.write_to(&mut bytes);
+        bytes.into_bytes()
+    }
+
+    /// Returns the number of bytes [`MultiKll::serialize`] produces.
+    pub fn serialized_size_bytes(&self) -> usize {
+        size_of::<u64>()
+            + self
+                .sketches
+                .iter()
+                .map(|sketch| size_of::<u32>() + sketch.serialized_size_bytes())
+                .sum::<usize>()
+    }
+
+    /// Serializes all dimensions into the front of `buf` without allocating, returning the
+    /// number of bytes written.
+    ///
+    /// The bytes are the same as those returned by [`MultiKll::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `buf` is shorter than [`MultiKll::serialized_size_bytes`].
+    pub fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        serialize_into_slice(buf, self.serialized_size_bytes(), |bytes| {
+            self.write_to(bytes)
+        })
+    }
+
+    fn write_to(&self, bytes: &mut SketchBytes<'_>) {
+        bytes.write_u8(MULTI_SERIAL_VERSION);
+        bytes.write_u8(Family::KLL.id);
+        bytes.write_u16_le(0); // unused
+        bytes.write_u32_le(D as u32);
+        for sketch in &self.sketches {
+            bytes.write_u32_le(sketch.serialized_size_bytes() as u32);
+            sketch.write_to(bytes);
+        }
+    }
+
+    /// Deserializes a sketch group from bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are malformed, if the number of dimensions is not D, or if
+    /// the dimensions were built with different values of k.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        let mut cursor = SketchSlice::new(bytes);
+
+        let serial_version = cursor
+            .read_u8()
+            .map_err(insufficient_data("serial_version"))?;
+        ensure_serial_version_is(MULTI_SERIAL_VERSION, serial_version)?;
+        let family_id = cursor.read_u8().map_err(insufficient_data("family_id"))?;
+        Family::KLL.validate_id(family_id)?;
+        cursor
+            .read_u16_le()
+            .map_err(insufficient_data("<unused>"))?;
+        let num_dimensions = cursor
+            .read_u32_le()
+            .map_err(insufficient_data("num_dimensions"))?;
+        if num_dimensions as usize != D || D == 0 {
+            return Err(Error::bad_preamble(format!(
+                "expected {D} dimensions, got {num_dimensions}"
+            )));
+        }
+
+        let mut sketches = Vec::with_capacity(D);
+        for index in 0..D {
+            let len = cursor
+                .read_u32_le()
+                .map_err(insufficient_data("dimension_length"))? as usize;
+            if len > cursor.remaining() {
+                return Err(Error::insufficient_data(format!(
+                    "dimension declares {len} bytes, but only {} bytes remain",
+                    cursor.remaining()
+                ))
+                .with_context("dimension", index));
+            }
+            let mut buf = vec![0; len];
+            cursor
+                .read_exact(&mut buf)
+                .map_err(insufficient_data("dimension"))?;
+            let sketch =
+                KllSketch::deserialize(&buf).map_err(|err| err.with_context("dimension", index))?;
+            if let Some(first) = sketches.first().map(KllSketch::k) {
+                if sketch.k() != first {
+                    return Err(Error::deserial(format!(
+                        "all dimensions must have the same k, got {first} and {}",
+                        sketch.k()
+                    ))
+                    .with_context("dimension", index));
+                }
+            }
+            sketches.push(sketch);
+        }
+
+        let sketches = sketches
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("exactly D sketches were deserialized"));
+        Ok(MultiKll { sketches })
+    }
+}
+
+impl<const D: usize> Sketch<[f64; D]> for MultiKll<D> {
+    fn update(&mut self, item: [f64; D]) {
+        MultiKll::update(self, item);
+    }
+
+    fn is_empty(&self) -> bool {
+        MultiKll::is_empty(self)
+    }
+}
+
+impl<const D: usize> MergeableSketch for MultiKll<D> {
+    type Error = Infallible;
+
+    fn merge(&mut self, other: &Self) -> Result<(), Infallible> {
+        MultiKll::merge(self, other);
+        Ok(())
+    }
+}
+
+impl<const D: usize> SerializableSketch for MultiKll<D> {
+    type Error = Error;
+
+    fn serialize(&self) -> Vec<u8> {
+        MultiKll::serialize(self)
+    }
+
+    fn serialized_size_bytes(&self) -> usize {
+        MultiKll::serialized_size_bytes(self)
+    }
+
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        MultiKll::serialize_into(self, buf)
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        MultiKll::deserialize(bytes)
+    }
+}
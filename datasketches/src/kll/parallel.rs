@@ -0,0 +1,72 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use rayon::prelude::*;
+
+use crate::kll::KllItem;
+use crate::kll::KllSketch;
+
+/// Merges KLL sketches in parallel on the rayon thread pool.
+///
+/// Every worker merges a share of the input into a copy of its first sketch, and the partial
+/// results are merged pairwise as a tree. As with [`KllSketch::merge`], the result uses the
+/// smallest k of the inputs for its error guarantees.
+///
+/// Returns `None` if `sketches` is empty.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::kll::KllSketch;
+/// # use datasketches::kll::parallel_merge;
+/// let sketches: Vec<_> = (0..10)
+///     .map(|i| {
+///         let mut sketch = KllSketch::<f64>::new(200);
+///         sketch.update(i as f64);
+///         sketch
+///     })
+///     .collect();
+/// let merged = parallel_merge(&sketches).unwrap();
+/// assert_eq!(merged.n(), 10);
+/// ```
+pub fn parallel_merge<T>(sketches: &[KllSketch<T>]) -> Option<KllSketch<T>>
+where
+    T: KllItem + Send + Sync,
+{
+    sketches
+        .par_iter()
+        .fold(
+            || None,
+            |merged: Option<KllSketch<T>>, sketch| match merged {
+                None => Some(sketch.clone()),
+                Some(mut merged) => {
+                    merged.merge(sketch);
+                    Some(merged)
+                }
+            },
+        )
+        .reduce(
+            || None,
+            |left, right| match (left, right) {
+                (Some(mut left), Some(right)) => {
+                    left.merge(&right);
+                    Some(left)
+                }
+                (left, right) => left.or(right),
+            },
+        )
+}
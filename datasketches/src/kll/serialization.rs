@@ -0,0 +1,129 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::time::Duration;
+
+use crate::codec::SketchBytes;
+use crate::codec::SketchSlice;
+use crate::error::Error;
+
+pub(super) const PREAMBLE_INTS_SHORT: u8 = 2; // for empty and single item
+pub(super) const PREAMBLE_INTS_FULL: u8 = 5;
+pub(super) const SERIAL_VERSION_1: u8 = 1; // for empty and the general case
+pub(super) const SERIAL_VERSION_2: u8 = 2; // for single item
+// deltas between checkpoints are specific to this library
+pub(super) const PREAMBLE_INTS_DELTA: u8 = 4;
+pub(super) const SERIAL_VERSION_DELTA: u8 = 3;
+pub(super) const DELTA_LEVEL_UNCHANGED: u8 = 0;
+pub(super) const DELTA_LEVEL_REPLACED: u8 = 1;
+pub(super) const FLAGS_IS_EMPTY: u8 = 1 << 0;
+pub(super) const FLAGS_IS_LEVEL_ZERO_SORTED: u8 = 1 << 1;
+pub(super) const FLAGS_IS_SINGLE_ITEM: u8 = 1 << 2;
+
+/// Trait for items that can be tracked by a [`KllSketch`](super::KllSketch).
+///
+/// Items must be totally ordered by [`PartialOrd`], except for values reported by
+/// [`is_unordered`](Self::is_unordered), which the sketch ignores on update.
+pub trait KllItem: Copy + PartialOrd {
+    /// Returns true if the item has no place in the order and must be ignored, e.g., NaN.
+    fn is_unordered(&self) -> bool {
+        false
+    }
+    /// Returns the size in bytes required to serialize the given item.
+    fn serialize_size(item: &Self) -> usize;
+    /// Serializes the item into the given byte buffer.
+    fn serialize_value(&self, bytes: &mut SketchBytes<'_>);
+    /// Deserializes an item from the given byte cursor.
+    fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error>;
+}
+
+macro_rules! impl_float {
+    ($name:ty, $read:ident, $write:ident) => {
+        /// Compatible with the serialization format of datasketches-java and datasketches-cpp.
+        impl KllItem for $name {
+            fn is_unordered(&self) -> bool {
+                self.is_nan()
+            }
+
+            fn serialize_size(_item: &Self) -> usize {
+                size_of::<$name>()
+            }
+
+            fn serialize_value(&self, bytes: &mut SketchBytes<'_>) {
+                bytes.$write(*self);
+            }
+
+            fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error> {
+                let value = cursor.$read().map_err(|_| {
+                    Error::insufficient_data(
+                        concat!("failed to read ", stringify!($name), " item bytes").to_string(),
+                    )
+                })?;
+                if value.is_nan() {
+                    return Err(Error::deserial("item must not be NaN"));
+                }
+                Ok(value)
+            }
+        }
+    };
+}
+
+impl_float!(f64, read_f64_le, write_f64_le);
+impl_float!(f32, read_f32_le, write_f32_le);
+
+/// Serialized as whole seconds (`u64`) followed by subsecond nanoseconds (`u32`), which other
+/// DataSketches implementations cannot read.
+///
+/// Sketches of durations report quantiles as durations, e.g. latency percentiles for SLOs.
+///
+/// # Examples
+///
+/// ```
+/// # use std::time::Duration;
+/// # use datasketches::kll::KllSketch;
+/// let mut sketch = KllSketch::<Duration>::new(200);
+/// for ms in 1..=100 {
+///     sketch.update(Duration::from_millis(ms));
+/// }
+/// let p99 = sketch.quantile(0.99).unwrap();
+/// assert_eq!(p99, Duration::from_millis(99));
+/// ```
+impl KllItem for Duration {
+    fn serialize_size(_item: &Self) -> usize {
+        size_of::<u64>() + size_of::<u32>()
+    }
+
+    fn serialize_value(&self, bytes: &mut SketchBytes<'_>) {
+        bytes.write_u64_le(self.as_secs());
+        bytes.write_u32_le(self.subsec_nanos());
+    }
+
+    fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error> {
+        let secs = cursor
+            .read_u64_le()
+            .map_err(|_| Error::insufficient_data("failed to read Duration item bytes"))?;
+        let nanos = cursor
+            .read_u32_le()
+            .map_err(|_| Error::insufficient_data("failed to read Duration item bytes"))?;
+        if nanos >= 1_000_000_000 {
+            return Err(Error::deserial(format!(
+                "subsecond nanoseconds must be less than 10^9, got {nanos}"
+            )));
+        }
+        Ok(Duration::new(secs, nanos))
+    }
+}
@@ -0,0 +1,1381 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::convert::Infallible;
+use std::fmt;
+
+use crate::codec::SketchBytes;
+use crate::codec::SketchSlice;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
+use crate::codec::serialize_into_slice;
+use crate::common::CdfPoint;
+use crate::common::MergeableSketch;
+use crate::common::SerializableSketch;
+use crate::common::Sketch;
+use crate::common::Summary;
+use crate::common::evenly_spaced_ranks;
+use crate::common::random::RandomSource;
+use crate::error::Error;
+use crate::inspect::SketchInfo;
+use crate::kll::helper::compare;
+use crate::kll::helper::compute_total_capacity;
+use crate::kll::helper::general_compress;
+use crate::kll::helper::level_capacity;
+use crate::kll::helper::merge_sorted;
+use crate::kll::helper::merge_sorted_in_place;
+use crate::kll::helper::randomly_halve_down;
+use crate::kll::helper::randomly_halve_up;
+use crate::kll::helper::ub_on_num_levels;
+use crate::kll::serialization::DELTA_LEVEL_REPLACED;
+use crate::kll::serialization::DELTA_LEVEL_UNCHANGED;
+use crate::kll::serialization::FLAGS_IS_EMPTY;
+use crate::kll::serialization::FLAGS_IS_LEVEL_ZERO_SORTED;
+use crate::kll::serialization::FLAGS_IS_SINGLE_ITEM;
+use crate::kll::serialization::KllItem;
+use crate::kll::serialization::PREAMBLE_INTS_DELTA;
+use crate::kll::serialization::PREAMBLE_INTS_FULL;
+use crate::kll::serialization::PREAMBLE_INTS_SHORT;
+use crate::kll::serialization::SERIAL_VERSION_1;
+use crate::kll::serialization::SERIAL_VERSION_2;
+use crate::kll::serialization::SERIAL_VERSION_DELTA;
+
+/// The default value of K if one is not specified.
+pub(super) const DEFAULT_K: u16 = 200;
+/// The minimum value of K.
+const MIN_K: u16 = DEFAULT_M as u16;
+/// The minimum level width. Other implementations only support this value.
+const DEFAULT_M: u8 = 8;
+/// A sketch cannot have more levels than this, see `level_capacity`.
+const MAX_NUM_LEVELS: usize = 61;
+
+/// KLL sketch for estimating quantiles and ranks.
+///
+/// See the [module level documentation](super) for more.
+#[derive(Debug, Clone)]
+pub struct KllSketch<T> {
+    k: u16,
+    m: u8,
+    min_k: u16,
+    n: u64,
+    num_levels: usize,
+    // level `i` occupies `items[levels[i]..levels[i + 1]]`; has `num_levels + 1` entries
+    levels: Vec<usize>,
+    // allocated on the first update; the slots in front of `levels[0]` are free
+    items: Vec<T>,
+    min_item: Option<T>,
+    max_item: Option<T>,
+    is_level_zero_sorted: bool,
+    random: RandomSource,
+}
+
+impl<T: KllItem> Default for KllSketch<T> {
+    fn default() -> Self {
+        KllSketch::new(DEFAULT_K)
+    }
+}
+
+impl<T: KllItem> KllSketch<T> {
+    /// Creates a KLL sketch with the given value of k.
+    ///
+    /// Larger values of k make the sketch more accurate, at the cost of size. The default value
+    /// of 200 results in a normalized rank error of about 1.65%.
+    ///
+    /// The fallible version of this method is [`KllSketch::try_new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if k is less than 8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// let sketch = KllSketch::<f64>::new(100);
+    /// assert_eq!(sketch.k(), 100);
+    /// ```
+    pub fn new(k: u16) -> Self {
+        assert!(k >= MIN_K, "k must be at least {MIN_K}, got {k}");
+        KllSketch {
+            k,
+            m: DEFAULT_M,
+            min_k: k,
+            n: 0,
+            num_levels: 1,
+            levels: vec![k as usize, k as usize],
+            items: vec![],
+            min_item: None,
+            max_item: None,
+            is_level_zero_sorted: false,
+            random: RandomSource::new(),
+        }
+    }
+
+    /// Creates a KLL sketch with the given value of k.
+    ///
+    /// The panicking version of this method is [`KllSketch::new`].
+    ///
+    /// # Errors
+    ///
+    /// If k is less than 8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// assert!(KllSketch::<f64>::try_new(4).is_err());
+    /// let sketch = KllSketch::<f64>::try_new(100).unwrap();
+    /// assert_eq!(sketch.k(), 100);
+    /// ```
+    pub fn try_new(k: u16) -> Result<Self, Error> {
+        if k < MIN_K {
+            return Err(Error::invalid_argument(format!(
+                "k must be at least {MIN_K}, got {k}"
+            )));
+        }
+        Ok(Self::new(k))
+    }
+
+    /// Creates a KLL sketch with the given value of k whose compactions draw their random bits
+    /// from a generator seeded with `seed`.
+    ///
+    /// Sketches created by [`new`](Self::new) seed their generator from per-process randomness,
+    /// so the retained items differ from run to run. With a fixed seed, the same updates and
+    /// merges in the same order always produce the same sketch, which makes property tests and
+    /// replayed streams reproducible. The seed is not part of the serialized image.
+    ///
+    /// The fallible version of this method is [`KllSketch::try_with_random_seed`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if k is less than 8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// let mut a = KllSketch::<f64>::with_random_seed(8, 42);
+    /// let mut b = KllSketch::<f64>::with_random_seed(8, 42);
+    /// for i in 0..1_000 {
+    ///     a.update(i as f64);
+    ///     b.update(i as f64);
+    /// }
+    /// assert_eq!(a.serialize(), b.serialize());
+    /// ```
+    pub fn with_random_seed(k: u16, seed: u64) -> Self {
+        let mut sketch = Self::new(k);
+        sketch.random = RandomSource::with_seed(seed);
+        sketch
+    }
+
+    /// Creates a KLL sketch with the given value of k whose compactions draw their random bits
+    /// from a generator seeded with `seed`.
+    ///
+    /// The panicking version of this method is [`KllSketch::with_random_seed`].
+    ///
+    /// # Errors
+    ///
+    /// If k is less than 8.
+    pub fn try_with_random_seed(k: u16, seed: u64) -> Result<Self, Error> {
+        let mut sketch = Self::try_new(k)?;
+        sketch.random = RandomSource::with_seed(seed);
+        Ok(sketch)
+    }
+
+    /// Returns parameter k that was used to configure this sketch.
+    pub fn k(&self) -> u16 {
+        self.k
+    }
+
+    /// Returns the smallest k among this sketch and the sketches in estimation mode merged
+    /// into it, which determines its [`normalized_rank_error`](Self::normalized_rank_error).
+    ///
+    /// This is `getMinK` in datasketches-java. It equals [`k`](Self::k) unless a sketch with a
+    /// smaller k was merged in.
+    pub fn min_k(&self) -> u16 {
+        self.min_k
+    }
+
+    /// Returns the length of the input stream.
+    pub fn n(&self) -> u64 {
+        self.n
+    }
+
+    /// Returns true if the sketch has not seen any data.
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Returns true if the sketch is in estimation mode, i.e., it has compacted some items.
+    pub fn is_estimation_mode(&self) -> bool {
+        self.num_levels > 1
+    }
+
+    /// Returns the number of items retained by the sketch.
+    pub fn num_retained(&self) -> usize {
+        self.levels[self.num_levels] - self.levels[0]
+    }
+
+    /// Returns the number of bytes of memory the sketch occupies, including the free slots in
+    /// front of the retained items.
+    ///
+    /// Memory owned by the items themselves, such as the contents of a `String`, is not counted.
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>()
+            + self.items.capacity() * size_of::<T>()
+            + self.levels.capacity() * size_of::<usize>()
+    }
+
+    /// Returns the minimum item seen by the sketch; `None` if the sketch is empty.
+    pub fn min_item(&self) -> Option<T> {
+        self.min_item
+    }
+
+    /// Returns the maximum item seen by the sketch; `None` if the sketch is empty.
+    pub fn max_item(&self) -> Option<T> {
+        self.max_item
+    }
+
+    /// Updates the sketch with the given item.
+    ///
+    /// Unordered items, such as NaN, are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// let mut sketch = KllSketch::<f64>::new(200);
+    /// sketch.update(1.0);
+    /// sketch.update(f64::NAN);
+    /// assert_eq!(sketch.n(), 1);
+    /// ```
+    pub fn update(&mut self, item: T) {
+        if item.is_unordered() {
+            return;
+        }
+        self.update_min_max(item, item);
+        self.internal_update(item);
+    }
+
+    /// Merges the given sketch into this one.
+    ///
+    /// The other sketch may have a different k. This sketch keeps its own k, which governs its
+    /// future compactions, but if the other sketch is in estimation mode its items already
+    /// carry the error of its k, so [`min_k`](Self::min_k) and therefore the reported
+    /// [`normalized_rank_error`](Self::normalized_rank_error) adopt the smaller of the two. A
+    /// sketch in exact mode adds no error and leaves them unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// let mut left = KllSketch::<f64>::new(200);
+    /// let mut right = KllSketch::<f64>::new(200);
+    /// left.update(1.0);
+    /// right.update(2.0);
+    /// left.merge(&right);
+    /// assert_eq!(left.n(), 2);
+    /// assert_eq!(left.max_item(), Some(2.0));
+    /// ```
+    ///
+    /// Merging a sketch with a smaller k:
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// let mut union = KllSketch::<f64>::new(400);
+    /// let mut small = KllSketch::<f64>::new(100);
+    /// for i in 0..100_000 {
+    ///     small.update(i as f64);
+    /// }
+    /// union.merge(&small);
+    /// assert_eq!(union.k(), 400);
+    /// assert_eq!(union.min_k(), 100);
+    /// assert_eq!(
+    ///     union.normalized_rank_error(false),
+    ///     small.normalized_rank_error(false)
+    /// );
+    /// ```
+    pub fn merge(&mut self, other: &KllSketch<T>) {
+        if other.is_empty() {
+            return;
+        }
+        debug_assert_eq!(self.m, other.m);
+
+        if let (Some(min), Some(max)) = (other.min_item, other.max_item) {
+            self.update_min_max(min, max);
+        }
+        let final_n = self.n + other.n;
+        for &item in other.level(0) {
+            self.internal_update(item);
+        }
+        if other.num_levels >= 2 {
+            self.merge_higher_levels(other, final_n);
+        }
+        self.n = final_n;
+        if other.is_estimation_mode() {
+            self.min_k = self.min_k.min(other.min_k);
+        }
+        debug_assert_eq!(self.total_weight(), Some(self.n));
+    }
+
+    /// Resets the sketch to the empty state.
+    pub fn reset(&mut self) {
+        let random = self.random.clone();
+        *self = KllSketch::new(self.k);
+        self.random = random;
+    }
+
+    /// Returns the approximate normalized rank of the given item, i.e., the fraction of the
+    /// input stream that is less than or equal to it; `None` if the sketch is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the item is unordered, e.g., NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// let mut sketch = KllSketch::<f64>::new(200);
+    /// for i in 1..=4 {
+    ///     sketch.update(i as f64);
+    /// }
+    /// assert_eq!(sketch.rank(2.0), Some(0.5));
+    /// ```
+    pub fn rank(&self, item: T) -> Option<f64> {
+        assert!(!item.is_unordered(), "item must not be unordered");
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(self.rank_in(&self.sorted_view(), item))
+    }
+
+    /// Returns the approximate item at the given normalized rank; `None` if the sketch is empty.
+    ///
+    /// The result is the smallest retained item whose rank is at least the given rank, except
+    /// that ranks 0.0 and 1.0 return the exact minimum and maximum items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rank is not in `[0.0, 1.0]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// let mut sketch = KllSketch::<f64>::new(200);
+    /// for i in 1..=4 {
+    ///     sketch.update(i as f64);
+    /// }
+    /// assert_eq!(sketch.quantile(0.5), Some(2.0));
+    /// assert_eq!(sketch.quantile(1.0), Some(4.0));
+    /// ```
+    pub fn quantile(&self, rank: f64) -> Option<T> {
+        assert!((0.0..=1.0).contains(&rank), "rank must be in [0.0, 1.0]");
+        if self.is_empty() {
+            return None;
+        }
+        if rank == 0.0 {
+            return self.min_item;
+        }
+        if rank == 1.0 {
+            return self.max_item;
+        }
+        Some(self.quantile_in(&self.sorted_view(), rank))
+    }
+
+    /// Returns the approximate cumulative distribution function at the given split points;
+    /// `None` if the sketch is empty.
+    ///
+    /// The result has one more entry than `split_points`: entry _i_ is the normalized rank of
+    /// `split_points[i]` as returned by [`rank`](Self::rank), i.e. the fraction of the input
+    /// stream at or below it, and the last entry is 1.0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `split_points` is not strictly increasing, or contains an unordered item,
+    /// e.g., NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// let mut sketch = KllSketch::<f64>::new(200);
+    /// for i in 1..=4 {
+    ///     sketch.update(i as f64);
+    /// }
+    /// assert_eq!(sketch.cdf(&[1.0, 3.0]), Some(vec![0.25, 0.75, 1.0]));
+    /// ```
+    pub fn cdf(&self, split_points: &[T]) -> Option<Vec<f64>> {
+        check_split_points(split_points);
+        if self.is_empty() {
+            return None;
+        }
+
+        let view = self.sorted_view();
+        let mut ranks = Vec::with_capacity(split_points.len() + 1);
+        ranks.extend(split_points.iter().map(|&item| self.rank_in(&view, item)));
+        ranks.push(1.0);
+        Some(ranks)
+    }
+
+    /// Returns the approximate probability mass function over the intervals delimited by the
+    /// given split points; `None` if the sketch is empty.
+    ///
+    /// The result has one more entry than `split_points`: the first entry is the fraction of the
+    /// input stream at or below `split_points[0]`, entry _i_ the fraction in
+    /// `(split_points[i - 1], split_points[i]]`, and the last entry the fraction above the last
+    /// split point. The entries sum to 1.0, which makes them suitable to render a histogram.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `split_points` is not strictly increasing, or contains an unordered item,
+    /// e.g., NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// let mut sketch = KllSketch::<f64>::new(200);
+    /// for i in 1..=4 {
+    ///     sketch.update(i as f64);
+    /// }
+    /// assert_eq!(sketch.pmf(&[1.0, 3.0]), Some(vec![0.25, 0.5, 0.25]));
+    /// ```
+    pub fn pmf(&self, split_points: &[T]) -> Option<Vec<f64>> {
+        let mut buckets = self.cdf(split_points)?;
+        for i in (1..buckets.len()).rev() {
+            buckets[i] -= buckets[i - 1];
+        }
+        Some(buckets)
+    }
+
+    /// Returns the normalized rank error of this sketch, with 99% confidence.
+    ///
+    /// Uses the smallest k among the sketches merged into this one; see
+    /// [`normalized_rank_error`](crate::kll::normalized_rank_error).
+    pub fn normalized_rank_error(&self, pmf: bool) -> f64 {
+        normalized_rank_error(self.min_k, pmf)
+    }
+
+    /// Returns `n` points of the approximate CDF of the input stream; `None` if the sketch is
+    /// empty.
+    ///
+    /// The points are the quantiles at `n` evenly spaced normalized ranks from 0.0 to 1.0, each
+    /// paired with its own rank as returned by [`rank`](Self::rank). The first and last points
+    /// hold the exact minimum and maximum items. Values are non-decreasing, and may repeat when
+    /// the sketch retains fewer than `n` distinct items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is less than 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// let mut sketch = KllSketch::<f64>::new(200);
+    /// for i in 1..=4 {
+    ///     sketch.update(i as f64);
+    /// }
+    /// let points = sketch.to_cdf_points(3).unwrap();
+    /// assert_eq!(points.len(), 3);
+    /// assert_eq!((points[0].value, points[0].rank), (1.0, 0.25));
+    /// assert_eq!((points[1].value, points[1].rank), (2.0, 0.5));
+    /// assert_eq!((points[2].value, points[2].rank), (4.0, 1.0));
+    /// ```
+    pub fn to_cdf_points(&self, n: usize) -> Option<Vec<CdfPoint<T>>> {
+        let ranks = evenly_spaced_ranks(n);
+        let (min_item, max_item) = (self.min_item?, self.max_item?);
+
+        let view = self.sorted_view();
+        let points = ranks
+            .map(|rank| {
+                let value = if rank == 0.0 {
+                    min_item
+                } else if rank == 1.0 {
+                    max_item
+                } else {
+                    self.quantile_in(&view, rank)
+                };
+                CdfPoint {
+                    value,
+                    rank: self.rank_in(&view, value),
+                }
+            })
+            .collect();
+        Some(points)
+    }
+
+    /// Serializes this sketch to bytes.
+    ///
+    /// The format is compatible with the compact KLL format of datasketches-java and
+    /// datasketches-cpp.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// # let mut sketch = KllSketch::<f64>::new(200);
+    /// # sketch.update(1.0);
+    /// let bytes = sketch.serialize();
+    /// let decoded = KllSketch::<f64>::deserialize(&bytes).unwrap();
+    /// assert_eq!(decoded.max_item(), Some(1.0));
+    /// ```
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = SketchBytes::with_capacity(self.serialized_size_bytes());
+        self.write_to(&mut bytes);
+        bytes.into_bytes()
+    }
+
+    /// Returns the number of bytes [`KllSketch::serialize`] produces.
+    pub fn serialized_size_bytes(&self) -> usize {
+        let items = self.retained_items();
+
+        // 8 bytes preamble for all cases
+        let mut total_size = size_of::<u64>();
+        if self.n == 1 {
+            total_size += T::serialize_size(&items[0]);
+        } else if !self.is_empty() {
+            // + 8 bytes n
+            // + 2 bytes min_k
+            // + 1 byte num_levels
+            // + 1 byte unused
+            // + 4 bytes per level offset
+            total_size += size_of::<u64>() + size_of::<u32>() * (1 + self.num_levels);
+            for item in [self.min_item, self.max_item].iter().flatten().chain(items) {
+                total_size += T::serialize_size(item);
+            }
+        }
+        total_size
+    }
+
+    /// Serializes this sketch into the front of `buf` without allocating, returning the number
+    /// of bytes written.
+    ///
+    /// The bytes are the same as those returned by [`KllSketch::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `buf` is shorter than [`KllSketch::serialized_size_bytes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// # let mut sketch = KllSketch::<f64>::new(200);
+    /// # sketch.update(1.0);
+    /// let mut buf = [0u8; 64];
+    /// let len = sketch.serialize_into(&mut buf).unwrap();
+    /// assert_eq!(&buf[..len], sketch.serialize());
+    /// ```
+    pub fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        serialize_into_slice(buf, self.serialized_size_bytes(), |bytes| {
+            self.write_to(bytes)
+        })
+    }
+
+    pub(super) fn write_to(&self, bytes: &mut SketchBytes<'_>) {
+        let is_single_item = self.n == 1;
+        let items = self.retained_items();
+
+        bytes.write_u8(if self.is_empty() || is_single_item {
+            PREAMBLE_INTS_SHORT
+        } else {
+            PREAMBLE_INTS_FULL
+        });
+        bytes.write_u8(if is_single_item {
+            SERIAL_VERSION_2
+        } else {
+            SERIAL_VERSION_1
+        });
+        bytes.write_u8(Family::KLL.id);
+        bytes.write_u8({
+            let mut flags = 0;
+            if self.is_empty() {
+                flags |= FLAGS_IS_EMPTY;
+            }
+            if self.is_level_zero_sorted {
+                flags |= FLAGS_IS_LEVEL_ZERO_SORTED;
+            }
+            if is_single_item {
+                flags |= FLAGS_IS_SINGLE_ITEM;
+            }
+            flags
+        });
+        bytes.write_u16_le(self.k);
+        bytes.write_u8(self.m);
+        bytes.write_u8(0); // unused
+        if self.is_empty() {
+            return;
+        }
+        if is_single_item {
+            items[0].serialize_value(bytes);
+            return;
+        }
+
+        bytes.write_u64_le(self.n);
+        bytes.write_u16_le(self.min_k);
+        bytes.write_u8(self.num_levels as u8);
+        bytes.write_u8(0); // unused
+        // the top boundary is implied by the capacity
+        for &offset in &self.levels[..self.num_levels] {
+            bytes.write_u32_le(offset as u32);
+        }
+        for item in [self.min_item, self.max_item].iter().flatten().chain(items) {
+            item.serialize_value(bytes);
+        }
+    }
+
+    /// Deserializes a sketch from bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid compact KLL sketch of this item type.
+    ///
+    /// # Compatibility
+    ///
+    /// `f32` and `f64` sketches written by `KllFloatsSketch` and `KllDoublesSketch` in
+    /// datasketches-java and by `kll_sketch` in datasketches-cpp are read as-is. This is checked
+    /// against the serialization test corpus; [`compat`](crate::compat) names the target releases.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// # let mut sketch = KllSketch::<f64>::new(200);
+    /// # for i in 0..1000 {
+    /// #     sketch.update(i as f64);
+    /// # }
+    /// # let bytes = sketch.serialize();
+    /// let decoded = KllSketch::<f64>::deserialize(&bytes).unwrap();
+    /// assert_eq!(decoded.n(), 1000);
+    /// ```
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        let mut cursor = SketchSlice::new(bytes);
+
+        let preamble_ints = cursor
+            .read_u8()
+            .map_err(insufficient_data("preamble_ints"))?;
+        let serial_version = cursor
+            .read_u8()
+            .map_err(insufficient_data("serial_version"))?;
+        let family_id = cursor.read_u8().map_err(insufficient_data("family_id"))?;
+        Family::KLL.validate_id(family_id)?;
+        let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+        let k = cursor.read_u16_le().map_err(insufficient_data("k"))?;
+        let m = cursor.read_u8().map_err(insufficient_data("m"))?;
+        cursor.read_u8().map_err(insufficient_data("<unused>"))?;
+
+        if k < MIN_K {
+            return Err(Error::bad_preamble(format!(
+                "k must be at least {MIN_K}, got {k}"
+            )));
+        }
+        if m != DEFAULT_M {
+            return Err(Error::bad_preamble(format!(
+                "m must be {DEFAULT_M}, got {m}"
+            )));
+        }
+
+        let is_empty = (flags & FLAGS_IS_EMPTY) != 0;
+        let is_single_item = (flags & FLAGS_IS_SINGLE_ITEM) != 0;
+        let (expected_preamble_ints, expected_serial_version) = if is_empty {
+            (PREAMBLE_INTS_SHORT, SERIAL_VERSION_1)
+        } else if is_single_item {
+            (PREAMBLE_INTS_SHORT, SERIAL_VERSION_2)
+        } else {
+            (PREAMBLE_INTS_FULL, SERIAL_VERSION_1)
+        };
+        if preamble_ints != expected_preamble_ints {
+            return Err(Error::bad_preamble(format!(
+                "invalid preamble ints: expected {expected_preamble_ints}, got {preamble_ints}"
+            )));
+        }
+        if serial_version != expected_serial_version {
+            return Err(Error::unsupported_version(format!(
+                "unsupported serial version: expected {expected_serial_version}, got {serial_version}"
+            )));
+        }
+
+        let mut sketch = KllSketch::new(k);
+        if is_empty {
+            return Ok(sketch);
+        }
+        if is_single_item {
+            let item = T::deserialize_value(&mut cursor)?;
+            sketch.update(item);
+            return Ok(sketch);
+        }
+
+        let n = cursor.read_u64_le().map_err(insufficient_data("n"))?;
+        let min_k = cursor.read_u16_le().map_err(insufficient_data("min_k"))?;
+        let num_levels = cursor.read_u8().map_err(insufficient_data("num_levels"))? as usize;
+        cursor.read_u8().map_err(insufficient_data("<unused>"))?;
+        if !(MIN_K..=k).contains(&min_k) {
+            return Err(Error::bad_preamble(format!(
+                "min_k must be in [{MIN_K}, {k}], got {min_k}"
+            )));
+        }
+        if !(1..=MAX_NUM_LEVELS).contains(&num_levels) {
+            return Err(Error::bad_preamble(format!(
+                "num_levels must be in [1, {MAX_NUM_LEVELS}], got {num_levels}"
+            )));
+        }
+
+        let capacity = compute_total_capacity(k, m, num_levels);
+        let mut levels = Vec::with_capacity(num_levels + 1);
+        for _ in 0..num_levels {
+            let offset = cursor.read_u32_le().map_err(insufficient_data("levels"))?;
+            levels.push(offset as usize);
+        }
+        levels.push(capacity);
+        if levels.windows(2).any(|w| w[0] > w[1]) {
+            return Err(Error::deserial(format!(
+                "level offsets must be non-decreasing and at most {capacity}"
+            )));
+        }
+
+        let min_item = T::deserialize_value(&mut cursor)?;
+        let max_item = T::deserialize_value(&mut cursor)?;
+        let mut items = vec![min_item; capacity];
+        for item in &mut items[levels[0]..] {
+            *item = T::deserialize_value(&mut cursor)?;
+        }
+
+        sketch.min_k = min_k;
+        sketch.n = n;
+        sketch.num_levels = num_levels;
+        sketch.levels = levels;
+        sketch.items = items;
+        sketch.min_item = Some(min_item);
+        sketch.max_item = Some(max_item);
+        sketch.is_level_zero_sorted = (flags & FLAGS_IS_LEVEL_ZERO_SORTED) != 0;
+        if sketch.num_retained() == 0 || sketch.total_weight() != Some(n) {
+            return Err(Error::deserial(format!(
+                "retained items do not add up to n = {n}"
+            )));
+        }
+        Ok(sketch)
+    }
+
+    /// Serializes the changes of this sketch since `base`, a copy of it taken at the previous
+    /// checkpoint.
+    ///
+    /// Levels that hold the same items as in `base` are written as a reference to it, so a
+    /// sketch updated for a short while since the last checkpoint writes little more than its
+    /// lowest levels; against an empty base the delta holds the whole sketch. Restore with
+    /// [`apply_delta`](Self::apply_delta) on the sketch restored from the previous checkpoint.
+    /// The format is specific to this library.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `base` has a different k.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::kll::KllSketch;
+    /// let mut sketch = KllSketch::<f64>::new(200);
+    /// for i in 0..100_000 {
+    ///     sketch.update(i as f64);
+    /// }
+    /// let mut restored = KllSketch::<f64>::deserialize(&sketch.serialize()).unwrap();
+    ///
+    /// // at every checkpoint, write the delta and keep a copy as the next base
+    /// let base = sketch.clone();
+    /// for i in 0..100 {
+    ///     sketch.update(i as f64);
+    /// }
+    /// let delta = sketch.serialize_delta(&base);
+    /// assert!(delta.len() < sketch.serialized_size_bytes() / 2);
+    ///
+    /// restored.apply_delta(&delta).unwrap();
+    /// assert_eq!(restored.serialize(), sketch.serialize());
+    /// ```
+    pub fn serialize_delta(&self, base: &KllSketch<T>) -> Vec<u8> {
+        assert_eq!(
+            self.k, base.k,
+            "base must have the same k, expected {}, got {}",
+            self.k, base.k
+        );
+        let mut bytes = SketchBytes::with_capacity(self.serialized_size_bytes() + 64);
+        bytes.write_u8(PREAMBLE_INTS_DELTA);
+        bytes.write_u8(SERIAL_VERSION_DELTA);
+        bytes.write_u8(Family::KLL.id);
+        bytes.write_u8({
+            let mut flags = 0;
+            if self.is_empty() {
+                flags |= FLAGS_IS_EMPTY;
+            }
+            if self.is_level_zero_sorted {
+                flags |= FLAGS_IS_LEVEL_ZERO_SORTED;
+            }
+            flags
+        });
+        bytes.write_u16_le(self.k);
+        bytes.write_u8(self.m);
+        bytes.write_u8(self.num_levels as u8);
+        bytes.write_u64_le(base.n);
+        bytes.write_u8(base.num_levels as u8);
+        if self.is_empty() {
+            return bytes.into_bytes();
+        }
+
+        bytes.write_u64_le(self.n);
+        bytes.write_u16_le(self.min_k);
+        for item in [self.min_item, self.max_item].iter().flatten() {
+            item.serialize_value(&mut bytes);
+        }
+        for level in 0..self.num_levels {
+            let items = self.level(level);
+            if level < base.num_levels && items == base.level(level) {
+                bytes.write_u8(DELTA_LEVEL_UNCHANGED);
+            } else {
+                bytes.write_u8(DELTA_LEVEL_REPLACED);
+                bytes.write_u32_le(items.len() as u32);
+                for item in items {
+                    item.serialize_value(&mut bytes);
+                }
+            }
+        }
+        bytes.into_bytes()
+    }
+
+    /// Applies a delta written by [`serialize_delta`](Self::serialize_delta), turning this
+    /// sketch into the one the delta was taken from.
+    ///
+    /// This sketch must be the base of the delta, typically restored from the previous
+    /// checkpoint and the deltas applied since. The sketch is left unchanged on error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid delta of this item type, or if the delta
+    /// was taken against a sketch with another k, n or number of levels than this one.
+    pub fn apply_delta(&mut self, delta: &[u8]) -> Result<(), Error> {
+        let mut cursor = SketchSlice::new(delta);
+        let preamble_ints = cursor
+            .read_u8()
+            .map_err(insufficient_data("preamble_ints"))?;
+        let serial_version = cursor
+            .read_u8()
+            .map_err(insufficient_data("serial_version"))?;
+        let family_id = cursor.read_u8().map_err(insufficient_data("family_id"))?;
+        Family::KLL.validate_id(family_id)?;
+        let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+        let k = cursor.read_u16_le().map_err(insufficient_data("k"))?;
+        let m = cursor.read_u8().map_err(insufficient_data("m"))?;
+        let num_levels = cursor.read_u8().map_err(insufficient_data("num_levels"))? as usize;
+        let base_n = cursor.read_u64_le().map_err(insufficient_data("base_n"))?;
+        let base_num_levels = cursor
+            .read_u8()
+            .map_err(insufficient_data("base_num_levels"))? as usize;
+        if preamble_ints != PREAMBLE_INTS_DELTA {
+            return Err(Error::bad_preamble(format!(
+                "invalid preamble ints: expected {PREAMBLE_INTS_DELTA}, got {preamble_ints}"
+            )));
+        }
+        if serial_version != SERIAL_VERSION_DELTA {
+            return Err(Error::unsupported_version(format!(
+                "unsupported serial version: expected {SERIAL_VERSION_DELTA}, got {serial_version}"
+            )));
+        }
+        if m != DEFAULT_M {
+            return Err(Error::bad_preamble(format!(
+                "m must be {DEFAULT_M}, got {m}"
+            )));
+        }
+        if k != self.k || base_n != self.n || base_num_levels != self.num_levels {
+            return Err(Error::invalid_argument(format!(
+                "delta was taken against a sketch with k = {k}, n = {base_n} and {base_num_levels} \
+                 levels, got k = {}, n = {} and {} levels",
+                self.k, self.n, self.num_levels
+            )));
+        }
+        if (flags & FLAGS_IS_EMPTY) != 0 {
+            self.reset();
+            return Ok(());
+        }
+
+        let n = cursor.read_u64_le().map_err(insufficient_data("n"))?;
+        let min_k = cursor.read_u16_le().map_err(insufficient_data("min_k"))?;
+        if !(MIN_K..=k).contains(&min_k) {
+            return Err(Error::bad_preamble(format!(
+                "min_k must be in [{MIN_K}, {k}], got {min_k}"
+            )));
+        }
+        if !(1..=MAX_NUM_LEVELS).contains(&num_levels) {
+            return Err(Error::bad_preamble(format!(
+                "num_levels must be in [1, {MAX_NUM_LEVELS}], got {num_levels}"
+            )));
+        }
+        let min_item = T::deserialize_value(&mut cursor)?;
+        let max_item = T::deserialize_value(&mut cursor)?;
+
+        let capacity = compute_total_capacity(k, m, num_levels);
+        let mut retained = Vec::new();
+        let mut sizes = Vec::with_capacity(num_levels);
+        for level in 0..num_levels {
+            let tag = cursor.read_u8().map_err(insufficient_data("level tag"))?;
+            match tag {
+                DELTA_LEVEL_UNCHANGED if level < self.num_levels => {
+                    retained.extend_from_slice(self.level(level));
+                    sizes.push(self.level(level).len());
+                }
+                DELTA_LEVEL_REPLACED => {
+                    let len = cursor
+                        .read_u32_le()
+                        .map_err(insufficient_data("level size"))?;
+                    if retained.len() + len as usize > capacity {
+                        return Err(Error::deserial(format!(
+                            "retained items exceed the capacity {capacity}"
+                        )));
+                    }
+                    for _ in 0..len {
+                        retained.push(T::deserialize_value(&mut cursor)?);
+                    }
+                    sizes.push(len as usize);
+                }
+                _ => {
+                    return Err(Error::deserial(format!(
+                        "invalid tag {tag} for level {level}"
+                    )));
+                }
+            }
+        }
+        if retained.len() > capacity {
+            return Err(Error::deserial(format!(
+                "retained items exceed the capacity {capacity}"
+            )));
+        }
+        if retained.is_empty() || weight_of_levels(sizes.iter().copied()) != Some(n) {
+            return Err(Error::deserial(format!(
+                "retained items do not add up to n = {n}"
+            )));
+        }
+
+        let mut levels = Vec::with_capacity(num_levels + 1);
+        let mut offset = capacity - retained.len();
+        for size in sizes {
+            levels.push(offset);
+            offset += size;
+        }
+        levels.push(capacity);
+        let mut items = vec![min_item; capacity - retained.len()];
+        items.extend(retained);
+
+        self.min_k = min_k;
+        self.n = n;
+        self.num_levels = num_levels;
+        self.levels = levels;
+        self.items = items;
+        self.min_item = Some(min_item);
+        self.max_item = Some(max_item);
+        self.is_level_zero_sorted = (flags & FLAGS_IS_LEVEL_ZERO_SORTED) != 0;
+        Ok(())
+    }
+
+    fn retained_items(&self) -> &[T] {
+        if self.items.is_empty() {
+            return &[];
+        }
+        &self.items[self.levels[0]..self.levels[self.num_levels]]
+    }
+
+    fn level(&self, level: usize) -> &[T] {
+        if self.items.is_empty() || level >= self.num_levels {
+            return &[];
+        }
+        &self.items[self.levels[level]..self.levels[level + 1]]
+    }
+
+    fn total_weight(&self) -> Option<u64> {
+        weight_of_levels((0..self.num_levels).map(|level| self.level(level).len()))
+    }
+
+    // retained items in ascending order with their cumulative weights
+    fn rank_in(&self, view: &[(T, u64)], item: T) -> f64 {
+        let index = view.partition_point(|(entry, _)| entry <= &item);
+        let weight = if index == 0 { 0 } else { view[index - 1].1 };
+        weight as f64 / self.n as f64
+    }
+
+    fn quantile_in(&self, view: &[(T, u64)], rank: f64) -> T {
+        // round away floating point noise before taking the ceiling, as datasketches-java does,
+        // so that `quantile(rank(x))` returns `x` for retained items
+        let weight = ((rank * self.n as f64 * 1e8).round() / 1e8).ceil() as u64;
+        let index = view.partition_point(|&(_, cumulative)| cumulative < weight);
+        view.get(index).unwrap_or(&view[view.len() - 1]).0
+    }
+
+    fn sorted_view(&self) -> Vec<(T, u64)> {
+        let mut view = Vec::with_capacity(self.num_retained());
+        for level in 0..self.num_levels {
+            let weight = 1u64 << level;
+            view.extend(self.level(level).iter().map(|&item| (item, weight)));
+        }
+        view.sort_by(|a, b| compare(&a.0, &b.0));
+
+        let mut cumulative = 0;
+        for entry in &mut view {
+            cumulative += entry.1;
+            entry.1 = cumulative;
+        }
+        view
+    }
+
+    fn update_min_max(&mut self, min: T, max: T) {
+        self.min_item = Some(match self.min_item {
+            Some(current) if current <= min => current,
+            _ => min,
+        });
+        self.max_item = Some(match self.max_item {
+            Some(current) if current >= max => current,
+            _ => max,
+        });
+    }
+
+    fn internal_update(&mut self, item: T) {
+        if self.items.is_empty() {
+            self.items = vec![item; self.levels[self.num_levels]];
+        }
+        if self.levels[0] == 0 {
+            self.compress_while_updating();
+        }
+        self.n += 1;
+        self.is_level_zero_sorted = false;
+        let index = self.levels[0] - 1;
+        self.levels[0] = index;
+        self.items[index] = item;
+    }
+
+    fn find_level_to_compact(&self) -> usize {
+        (0..self.num_levels)
+            .find(|&level| {
+                self.level(level).len() >= level_capacity(self.k, self.num_levels, level, self.m)
+            })
+            .expect("a full sketch must have a level to compact")
+    }
+
+    fn add_empty_top_level_to_completely_full_sketch(&mut self) {
+        debug_assert_eq!(self.levels[0], 0);
+        let cur_total_cap = self.levels[self.num_levels];
+        let delta_cap = level_capacity(self.k, self.num_levels + 1, 0, self.m);
+
+        // shift the current data up to make room for the new bottom level
+        let mut items = vec![self.items[0]; delta_cap];
+        items.extend_from_slice(&self.items);
+        self.items = items;
+
+        for offset in &mut self.levels {
+            *offset += delta_cap;
+        }
+        self.num_levels += 1;
+        self.levels.push(cur_total_cap + delta_cap);
+    }
+
+    fn compress_while_updating(&mut self) {
+        let level = self.find_level_to_compact();
+
+        // add the new top level right here; this shifts the data and the boundaries
+        if level == self.num_levels - 1 {
+            self.add_empty_top_level_to_completely_full_sketch();
+        }
+
+        let raw_beg = self.levels[level];
+        let raw_lim = self.levels[level + 1];
+        // +2 is OK because we already added a new top level if necessary
+        let pop_above = self.levels[level + 2] - raw_lim;
+        let raw_pop = raw_lim - raw_beg;
+        let odd_pop = raw_pop % 2 == 1;
+        let adj_beg = if odd_pop { raw_beg + 1 } else { raw_beg };
+        let adj_pop = if odd_pop { raw_pop - 1 } else { raw_pop };
+        let half_adj_pop = adj_pop / 2;
+
+        // level zero might not be sorted, so we must sort it if we wish to compact it
+        if level == 0 && !self.is_level_zero_sorted {
+            self.items[adj_beg..adj_beg + adj_pop].sort_unstable_by(compare);
+        }
+        if pop_above == 0 {
+            randomly_halve_up(&mut self.items, adj_beg, adj_pop, &mut self.random);
+        } else {
+            randomly_halve_down(&mut self.items, adj_beg, adj_pop, &mut self.random);
+            merge_sorted_in_place(
+                &mut self.items,
+                adj_beg,
+                half_adj_pop,
+                raw_lim,
+                pop_above,
+                adj_beg + half_adj_pop,
+            );
+        }
+
+        // adjust the boundaries of the level above
+        self.levels[level + 1] -= half_adj_pop;
+        if odd_pop {
+            // the current level now contains the one leftover item
+            self.levels[level] = self.levels[level + 1] - 1;
+            self.items[self.levels[level]] = self.items[raw_beg];
+        } else {
+            // the current level is now empty
+            self.levels[level] = self.levels[level + 1];
+        }
+
+        // shift the levels below up into the freed slots
+        if level > 0 {
+            let start = self.levels[0];
+            self.items.copy_within(start..raw_beg, start + half_adj_pop);
+        }
+        for offset in &mut self.levels[..level] {
+            *offset += half_adj_pop;
+        }
+    }
+
+    fn merge_higher_levels(&mut self, other: &KllSketch<T>, final_n: u64) {
+        let provisional_num_levels = self.num_levels.max(other.num_levels);
+        let ub = ub_on_num_levels(final_n).max(provisional_num_levels);
+        let mut work_levels = vec![0; ub + 2];
+        let mut out_levels = vec![0; ub + 2];
+
+        // the level zero data from the other sketch was already inserted into this one
+        let num_items = self.num_retained() + other.num_retained() - other.level(0).len();
+        let mut work_items = Vec::with_capacity(num_items);
+        work_items.extend_from_slice(self.level(0));
+        work_levels[1] = work_items.len();
+        for level in 1..provisional_num_levels {
+            let (ours, theirs) = (self.level(level), other.level(level));
+            let start = work_items.len();
+            work_items.extend_from_slice(ours);
+            work_items.extend_from_slice(theirs);
+            if !ours.is_empty() && !theirs.is_empty() {
+                merge_sorted(ours, theirs, &mut work_items[start..]);
+            }
+            work_levels[level + 1] = work_items.len();
+        }
+
+        // work_items is used as both the input and the output here
+        let result = general_compress(
+            self.k,
+            self.m,
+            provisional_num_levels,
+            &mut work_items,
+            &mut work_levels,
+            &mut out_levels,
+            self.is_level_zero_sorted,
+            &mut self.random,
+        );
+
+        let free_space_at_bottom = result.final_capacity - result.final_num_items;
+        let mut items = vec![work_items[0]; result.final_capacity];
+        items[free_space_at_bottom..]
+            .copy_from_slice(&work_items[out_levels[0]..out_levels[0] + result.final_num_items]);
+        let shift = free_space_at_bottom - out_levels[0];
+
+        self.items = items;
+        self.num_levels = result.final_num_levels;
+        self.levels = out_levels[..=result.final_num_levels]
+            .iter()
+            .map(|&offset| offset + shift)
+            .collect();
+    }
+}
+
+/// Returns the normalized rank error of a KLL sketch with parameter `k`, with 99% confidence.
+///
+/// With `pmf` set, returns the error of [`pmf`](KllSketch::pmf) intervals, which is larger than
+/// the error of single [`rank`](KllSketch::rank) and [`quantile`](KllSketch::quantile) queries.
+/// The values are the empirical fits of `getNormalizedRankError` in datasketches-java.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::kll::normalized_rank_error;
+/// assert!((normalized_rank_error(200, false) - 0.0133).abs() < 1e-4);
+/// assert!((normalized_rank_error(200, true) - 0.0165).abs() < 1e-4);
+/// ```
+pub fn normalized_rank_error(k: u16, pmf: bool) -> f64 {
+    let (a, b) = rank_error_fit(pmf);
+    a / (k as f64).powf(b)
+}
+
+/// Returns the smallest k whose [`normalized_rank_error`] is at most `epsilon`.
+///
+/// The result is at least the minimum k of 8, and saturates at `u16::MAX` when `epsilon` is
+/// too small to be achieved.
+///
+/// # Panics
+///
+/// Panics if `epsilon` is not in `(0.0, 1.0]`.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::kll::k_for_normalized_rank_error;
+/// # use datasketches::kll::normalized_rank_error;
+/// let k = k_for_normalized_rank_error(0.01, false);
+/// assert!(normalized_rank_error(k, false) <= 0.01);
+/// assert!(normalized_rank_error(k - 1, false) > 0.01);
+/// ```
+pub fn k_for_normalized_rank_error(epsilon: f64, pmf: bool) -> u16 {
+    assert!(
+        epsilon > 0.0 && epsilon <= 1.0,
+        "epsilon must be in (0.0, 1.0], got {epsilon}"
+    );
+    let (a, b) = rank_error_fit(pmf);
+    let estimate = (a / epsilon).powf(1.0 / b).ceil();
+    let mut k = estimate.clamp(MIN_K as f64, u16::MAX as f64) as u16;
+    // correct the rounding of the inverse
+    while k < u16::MAX && normalized_rank_error(k, pmf) > epsilon {
+        k += 1;
+    }
+    while k > MIN_K && normalized_rank_error(k - 1, pmf) <= epsilon {
+        k -= 1;
+    }
+    k
+}
+
+// sum of the item weights given the number of items per level, or `None` if it overflows a u64;
+// sizes are bounded by the capacity and levels by MAX_NUM_LEVELS, so the u128 sum cannot overflow
+fn weight_of_levels(sizes: impl Iterator<Item = usize>) -> Option<u64> {
+    let weight: u128 = sizes
+        .enumerate()
+        .map(|(level, size)| (size as u128) << level)
+        .sum();
+    u64::try_from(weight).ok()
+}
+
+// coefficients (a, b) of the fit a / k^b
+fn rank_error_fit(pmf: bool) -> (f64, f64) {
+    if pmf {
+        (2.446, 0.9433)
+    } else {
+        (2.296, 0.9723)
+    }
+}
+
+fn check_split_points<T: KllItem>(split_points: &[T]) {
+    assert!(
+        split_points.iter().all(|item| !item.is_unordered()),
+        "split_points must not contain unordered items"
+    );
+    assert!(
+        split_points.windows(2).all(|pair| pair[0] < pair[1]),
+        "split_points must be unique and monotonically increasing"
+    );
+}
+
+impl<T: KllItem + fmt::Display> fmt::Display for KllSketch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut summary = Summary::new(f, "KLL SKETCH");
+        summary
+            .field("k", self.k)
+            .field("min k", self.min_k)
+            .field("m", DEFAULT_M)
+            .field("n", self.n)
+            .field("num levels", self.num_levels)
+            .field("num retained items", self.num_retained())
+            .field("empty", self.is_empty())
+            .field("estimation mode", self.is_estimation_mode())
+            .field("level zero sorted", self.is_level_zero_sorted)
+            .field("normalized rank error", self.normalized_rank_error(false));
+        if let (Some(min), Some(max)) = (self.min_item, self.max_item) {
+            summary.field("min item", min).field("max item", max);
+        }
+        summary.finish()
+    }
+}
+
+impl<T: KllItem> Sketch<T> for KllSketch<T> {
+    fn update(&mut self, item: T) {
+        KllSketch::update(self, item);
+    }
+
+    fn is_empty(&self) -> bool {
+        KllSketch::is_empty(self)
+    }
+}
+
+impl<T: KllItem> MergeableSketch for KllSketch<T> {
+    type Error = Infallible;
+
+    fn merge(&mut self, other: &Self) -> Result<(), Infallible> {
+        KllSketch::merge(self, other);
+        Ok(())
+    }
+}
+
+impl<T: KllItem> SerializableSketch for KllSketch<T> {
+    type Error = Error;
+
+    fn serialize(&self) -> Vec<u8> {
+        KllSketch::serialize(self)
+    }
+
+    fn serialized_size_bytes(&self) -> usize {
+        KllSketch::serialized_size_bytes(self)
+    }
+
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        KllSketch::serialize_into(self, buf)
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        KllSketch::deserialize(bytes)
+    }
+}
+
+/// Reads the preamble of a single sketch image for [`inspect`](crate::inspect).
+pub(crate) fn inspect(
+    _preamble_ints: u8,
+    serial_version: u8,
+    mut cursor: SketchSlice<'_>,
+) -> Result<SketchInfo, Error> {
+    if serial_version != SERIAL_VERSION_1 && serial_version != SERIAL_VERSION_2 {
+        return Err(Error::unsupported_version(format!(
+            "unsupported serial version: expected {SERIAL_VERSION_1} or {SERIAL_VERSION_2}, got {serial_version}"
+        )));
+    }
+    let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+    let k = cursor.read_u16_le().map_err(insufficient_data("k"))?;
+    let m = cursor.read_u8().map_err(insufficient_data("m"))?;
+    cursor.read_u8().map_err(insufficient_data("<unused>"))?;
+
+    let mut info = SketchInfo::new(&Family::KLL, serial_version);
+    info.k = Some(k);
+    info.is_empty = (flags & FLAGS_IS_EMPTY) != 0;
+    if info.is_empty || (flags & FLAGS_IS_SINGLE_ITEM) != 0 {
+        info.is_estimation_mode = Some(false);
+        info.num_retained = Some(u64::from(!info.is_empty));
+        return Ok(info);
+    }
+
+    cursor.read_u64_le().map_err(insufficient_data("n"))?;
+    cursor.read_u16_le().map_err(insufficient_data("min_k"))?;
+    let num_levels = cursor.read_u8().map_err(insufficient_data("num_levels"))? as usize;
+    cursor.read_u8().map_err(insufficient_data("<unused>"))?;
+    let first_offset = cursor.read_u32_le().map_err(insufficient_data("levels"))? as usize;
+    if !(1..=MAX_NUM_LEVELS).contains(&num_levels) {
+        return Err(Error::bad_preamble(format!(
+            "num_levels must be in [1, {MAX_NUM_LEVELS}], got {num_levels}"
+        )));
+    }
+    let capacity = compute_total_capacity(k, m, num_levels);
+    info.is_estimation_mode = Some(num_levels > 1);
+    info.num_retained = Some(capacity.saturating_sub(first_offset) as u64);
+    Ok(info)
+}
@@ -0,0 +1,128 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A percentile tracker for application telemetry.
+
+use crate::error::Error;
+use crate::kll::KllSketch;
+
+/// Tracks the percentiles of a stream of measurements, e.g. request latencies.
+///
+/// A thin facade over a [`KllSketch<f64>`] with the default k of 200, which keeps percentiles
+/// within about 1.65% of rank with 99% confidence in a few kilobytes, however many values are
+/// recorded. Use the sketch directly, through [`sketch`](Self::sketch), for ranks, CDFs and
+/// other parameters.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::kll::PercentileTracker;
+/// let mut latencies = PercentileTracker::new();
+/// for ms in 1..=1000 {
+///     latencies.record(ms as f64);
+/// }
+/// let p99 = latencies.p(0.99).unwrap();
+/// assert!((970.0..=1000.0).contains(&p99));
+///
+/// // ship the snapshot, then reset for the next reporting interval
+/// let snapshot = latencies.snapshot();
+/// latencies.reset();
+/// let restored = PercentileTracker::from_snapshot(&snapshot).unwrap();
+/// assert_eq!(restored.p(0.99), Some(p99));
+/// assert_eq!(latencies.p(0.99), None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PercentileTracker {
+    sketch: KllSketch<f64>,
+}
+
+impl PercentileTracker {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a measurement. NaN is ignored.
+    pub fn record(&mut self, value: f64) {
+        self.sketch.update(value);
+    }
+
+    /// Returns the value at percentile `q`, e.g. `0.99` for p99, or `None` if nothing has been
+    /// recorded.
+    ///
+    /// `p(0.0)` and `p(1.0)` are the exact minimum and maximum.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not in `[0.0, 1.0]`.
+    pub fn p(&self, q: f64) -> Option<f64> {
+        self.sketch.quantile(q)
+    }
+
+    /// Returns the number of recorded measurements.
+    pub fn count(&self) -> u64 {
+        self.sketch.n()
+    }
+
+    /// Returns the smallest recorded measurement.
+    pub fn min(&self) -> Option<f64> {
+        self.sketch.min_item()
+    }
+
+    /// Returns the largest recorded measurement.
+    pub fn max(&self) -> Option<f64> {
+        self.sketch.max_item()
+    }
+
+    /// Adds the measurements of another tracker, e.g. of another thread or host.
+    pub fn merge(&mut self, other: &PercentileTracker) {
+        self.sketch.merge(&other.sketch);
+    }
+
+    /// Forgets all measurements.
+    pub fn reset(&mut self) {
+        self.sketch.reset();
+    }
+
+    /// Returns the measurements as bytes, in the serialization format of [`KllSketch`].
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.sketch.serialize()
+    }
+
+    /// Restores a tracker from [`snapshot`](Self::snapshot) bytes, or from any serialized
+    /// `KllSketch<f64>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid serialized `KllSketch<f64>`.
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            sketch: KllSketch::deserialize(bytes)?,
+        })
+    }
+
+    /// Returns the underlying sketch.
+    pub fn sketch(&self) -> &KllSketch<f64> {
+        &self.sketch
+    }
+}
+
+impl From<KllSketch<f64>> for PercentileTracker {
+    fn from(sketch: KllSketch<f64>) -> Self {
+        Self { sketch }
+    }
+}
@@ -30,15 +30,43 @@
 #[cfg(target_endian = "big")]
 compile_error!("datasketches does not support big-endian targets");
 
+#[cfg(feature = "arrow")]
+#[cfg_attr(docsrs, doc(cfg(feature = "arrow")))]
+pub mod arrow;
 pub mod bloom;
 pub mod codec;
 pub mod common;
+pub mod compat;
 pub mod countmin;
 pub mod cpc;
+pub mod envelope;
 pub mod error;
+pub mod family;
 pub mod frequencies;
+pub mod hash;
 pub mod hll;
+pub mod kll;
+#[cfg(feature = "perf")]
+#[cfg_attr(docsrs, doc(cfg(feature = "perf")))]
+pub mod perf;
+pub mod postgres;
+pub mod sampling;
+#[cfg(feature = "shared")]
+#[cfg_attr(docsrs, doc(cfg(feature = "shared")))]
+pub mod shared;
 pub mod tdigest;
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub mod testing;
 pub mod theta;
+pub mod util;
 
-mod hash;
+#[cfg(feature = "serde")]
+mod serde_impls;
+
+mod config;
+pub use self::config::Config;
+
+mod inspect;
+pub use self::inspect::SketchInfo;
+pub use self::inspect::inspect;
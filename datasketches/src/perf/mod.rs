@@ -0,0 +1,54 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Reusable workloads and throughput measurement for benchmarking sketches.
+//!
+//! This module is only available with the `perf` feature. It exposes the workload
+//! generators and timing helpers used by the crate's own benchmarks, so that sketch
+//! configurations can be compared on the hardware they will actually run on.
+//!
+//! All generators are infinite, seeded iterators: the same seed always yields the same
+//! stream, and [`Iterator::take`] bounds its length.
+//!
+//! * [`Zipf`] draws keys with a power-law skew, as in typical "heavy hitters" traffic.
+//! * [`Uniform`] draws keys uniformly at random, e.g. for distinct counting.
+//! * [`Weighted`] attaches a random weight to every key of another workload.
+//!
+//! [`Throughput::measure`] times a closure over a materialized workload, so the cost of
+//! generating the input is not part of the measurement.
+//!
+//! # Usage
+//!
+//! ```
+//! # use datasketches::perf::Throughput;
+//! # use datasketches::perf::Zipf;
+//! # use datasketches::theta::ThetaSketch;
+//! let items: Vec<u64> = Zipf::new(1_000_000, 1.1, 42).take(100_000).collect();
+//!
+//! let mut sketch = ThetaSketch::builder().lg_k(12).build();
+//! let throughput = Throughput::measure(&items, |item| sketch.update_quiet(item));
+//! assert_eq!(throughput.items(), 100_000);
+//! println!("theta lg_k=12: {throughput}");
+//! ```
+
+mod throughput;
+mod workload;
+
+pub use self::throughput::Throughput;
+pub use self::workload::Uniform;
+pub use self::workload::Weighted;
+pub use self::workload::Zipf;
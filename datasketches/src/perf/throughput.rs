@@ -0,0 +1,99 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::fmt;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Number of items processed over a measured wall-clock duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throughput {
+    items: u64,
+    elapsed: Duration,
+}
+
+impl Throughput {
+    /// Creates a throughput from a number of items and the time it took to process them.
+    pub fn new(items: u64, elapsed: Duration) -> Self {
+        Self { items, elapsed }
+    }
+
+    /// Calls `f` on every item of `items` and measures the elapsed wall-clock time.
+    ///
+    /// The items are passed by reference so that the workload can be generated once and
+    /// reused across configurations without its generation being timed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::hll::HllSketch;
+    /// # use datasketches::hll::HllType;
+    /// # use datasketches::perf::Throughput;
+    /// # use datasketches::perf::Uniform;
+    /// let items: Vec<u64> = Uniform::new(1 << 20, 7).take(10_000).collect();
+    /// let mut sketch = HllSketch::new(12, HllType::Hll8);
+    /// let throughput = Throughput::measure(&items, |item| sketch.update(item));
+    /// assert_eq!(throughput.items(), 10_000);
+    /// ```
+    pub fn measure<T, F>(items: &[T], mut f: F) -> Self
+    where
+        F: FnMut(&T),
+    {
+        let start = Instant::now();
+        for item in items {
+            f(std::hint::black_box(item));
+        }
+        Self::new(items.len() as u64, start.elapsed())
+    }
+
+    /// Returns the number of items processed.
+    pub fn items(&self) -> u64 {
+        self.items
+    }
+
+    /// Returns the measured wall-clock duration.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the number of items processed per second.
+    ///
+    /// Returns infinity if the measured duration is zero.
+    pub fn items_per_second(&self) -> f64 {
+        self.items as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Returns the average time spent per item, in nanoseconds.
+    ///
+    /// Returns NaN if no items were processed.
+    pub fn nanos_per_item(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.items as f64
+    }
+}
+
+impl fmt::Display for Throughput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} items in {:?} ({:.2} M items/s, {:.1} ns/item)",
+            self.items,
+            self.elapsed,
+            self.items_per_second() / 1e6,
+            self.nanos_per_item()
+        )
+    }
+}
@@ -0,0 +1,246 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::common::random::RandomSource;
+
+/// Keys drawn from a Zipf (power-law) distribution over `[0, num_keys)`.
+///
+/// Key `0` is the most frequent; the probability of key `i` is proportional to
+/// `1 / (i + 1)^exponent`. Sampling uses rejection-inversion, so memory use is constant
+/// regardless of `num_keys`.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::perf::Zipf;
+/// let keys: Vec<u64> = Zipf::new(1000, 1.2, 1).take(10_000).collect();
+/// assert!(keys.iter().all(|k| *k < 1000));
+/// let zeros = keys.iter().filter(|k| **k == 0).count();
+/// let nines = keys.iter().filter(|k| **k == 9).count();
+/// assert!(zeros > nines);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Zipf {
+    num_keys: u64,
+    exponent: f64,
+    h_integral_x1: f64,
+    h_integral_num_keys: f64,
+    s: f64,
+    random: RandomSource,
+}
+
+impl Zipf {
+    /// Creates a Zipf workload over `num_keys` keys with the given exponent and seed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_keys` is zero or `exponent` is not a positive finite number.
+    pub fn new(num_keys: u64, exponent: f64, seed: u64) -> Self {
+        assert!(num_keys > 0, "num_keys must be at least 1");
+        assert!(
+            exponent.is_finite() && exponent > 0.0,
+            "exponent must be positive and finite, got {exponent}"
+        );
+        let h_integral_x1 = h_integral(1.5, exponent) - 1.0;
+        let h_integral_num_keys = h_integral(num_keys as f64 + 0.5, exponent);
+        let s = 2.0 - h_integral_inverse(h_integral(2.5, exponent) - h(2.0, exponent), exponent);
+        Self {
+            num_keys,
+            exponent,
+            h_integral_x1,
+            h_integral_num_keys,
+            s,
+            random: RandomSource::with_seed(seed),
+        }
+    }
+
+    /// Returns the number of distinct keys the workload draws from.
+    pub fn num_keys(&self) -> u64 {
+        self.num_keys
+    }
+
+    /// Returns the exponent of the distribution.
+    pub fn exponent(&self) -> f64 {
+        self.exponent
+    }
+}
+
+impl Iterator for Zipf {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        // Rejection-inversion sampling, see W. Hörmann and G. Derflinger, "Rejection-inversion
+        // to generate variates from monotone discrete distributions" (1996).
+        loop {
+            let u = self.h_integral_num_keys
+                + self.random.next_f64() * (self.h_integral_x1 - self.h_integral_num_keys);
+            let x = h_integral_inverse(u, self.exponent);
+            let k = ((x + 0.5) as u64).clamp(1, self.num_keys);
+            let kf = k as f64;
+            if kf - x <= self.s || u >= h_integral(kf + 0.5, self.exponent) - h(kf, self.exponent) {
+                return Some(k - 1);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// `h(x) = 1 / x^exponent`
+fn h(x: f64, exponent: f64) -> f64 {
+    (-exponent * x.ln()).exp()
+}
+
+/// Integral of `h`, up to a constant.
+fn h_integral(x: f64, exponent: f64) -> f64 {
+    let log_x = x.ln();
+    helper2((1.0 - exponent) * log_x) * log_x
+}
+
+/// Inverse of `h_integral`.
+fn h_integral_inverse(x: f64, exponent: f64) -> f64 {
+    let t = (x * (1.0 - exponent)).max(-1.0);
+    (helper1(t) * x).exp()
+}
+
+/// `ln(1 + x) / x`, accurate near zero.
+fn helper1(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.ln_1p() / x
+    } else {
+        1.0 - x * (0.5 - x * (1.0 / 3.0 - 0.25 * x))
+    }
+}
+
+/// `(e^x - 1) / x`, accurate near zero.
+fn helper2(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.exp_m1() / x
+    } else {
+        1.0 + x * 0.5 * (1.0 + x / 3.0 * (1.0 + 0.25 * x))
+    }
+}
+
+/// Keys drawn uniformly at random from `[0, num_keys)`.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::perf::Uniform;
+/// let keys: Vec<u64> = Uniform::new(10, 1).take(1000).collect();
+/// assert!(keys.iter().all(|k| *k < 10));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Uniform {
+    num_keys: u64,
+    random: RandomSource,
+}
+
+impl Uniform {
+    /// Creates a uniform workload over `num_keys` keys with the given seed.
+    ///
+    /// Use `u64::MAX` for effectively unique keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_keys` is zero.
+    pub fn new(num_keys: u64, seed: u64) -> Self {
+        assert!(num_keys > 0, "num_keys must be at least 1");
+        Self {
+            num_keys,
+            random: RandomSource::with_seed(seed),
+        }
+    }
+
+    /// Returns the number of distinct keys the workload draws from.
+    pub fn num_keys(&self) -> u64 {
+        self.num_keys
+    }
+}
+
+impl Iterator for Uniform {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        Some(bounded(self.random.next_u64(), self.num_keys))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// Keys of another workload paired with weights drawn uniformly from `[1, max_weight]`.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::perf::Weighted;
+/// # use datasketches::perf::Zipf;
+/// let items: Vec<(u64, u64)> = Weighted::new(Zipf::new(100, 1.0, 1), 10, 2)
+///     .take(1000)
+///     .collect();
+/// assert!(items.iter().all(|(k, w)| *k < 100 && (1..=10).contains(w)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Weighted<I> {
+    keys: I,
+    max_weight: u64,
+    random: RandomSource,
+}
+
+impl<I: Iterator<Item = u64>> Weighted<I> {
+    /// Creates a weighted workload over the keys of `keys` with the given seed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_weight` is zero.
+    pub fn new(keys: I, max_weight: u64, seed: u64) -> Self {
+        assert!(max_weight > 0, "max_weight must be at least 1");
+        Self {
+            keys,
+            max_weight,
+            random: RandomSource::with_seed(seed),
+        }
+    }
+
+    /// Returns the largest weight the workload produces.
+    pub fn max_weight(&self) -> u64 {
+        self.max_weight
+    }
+}
+
+impl<I: Iterator<Item = u64>> Iterator for Weighted<I> {
+    type Item = (u64, u64);
+
+    fn next(&mut self) -> Option<(u64, u64)> {
+        let key = self.keys.next()?;
+        let weight = bounded(self.random.next_u64(), self.max_weight) + 1;
+        Some((key, weight))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+/// Maps a uniform 64-bit value onto `[0, bound)` without the bias of a modulo.
+fn bounded(value: u64, bound: u64) -> u64 {
+    ((value as u128 * bound as u128) >> 64) as u64
+}
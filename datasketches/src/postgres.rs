@@ -0,0 +1,172 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Helpers for storing sketches in PostgreSQL `bytea` columns.
+//!
+//! A Rust service that binds parameters in the binary protocol, as `tokio-postgres` and `sqlx`
+//! do, can store the output of `serialize` in a `bytea` column as is. [`to_bytea`] and
+//! [`from_bytea`] cover the text representation instead, as found in SQL literals, `COPY`
+//! files and `psql` output: [`to_bytea`] writes the hex format (`\x0103...`), and
+//! [`from_bytea`] reads both the hex format and the legacy escape format, selected on the
+//! server by the `bytea_output` setting.
+//!
+//! # Binary stability
+//!
+//! Stored sketches outlive the services that write them, so the formats are stable:
+//!
+//! * Every format listed by [`supported_formats`](crate::compat::supported_formats) keeps being
+//!   readable by every later release of this crate. A format is only ever added to the list.
+//! * Theta, HLL, CPC, KLL, frequent items, CountMin, t-digest and Bloom filter images are the
+//!   formats of datasketches-java and datasketches-cpp, so sketches written by those libraries,
+//!   e.g. by the `datasketches-postgresql` extension, can be read and merged too. [`MultiKll`]
+//!   images are specific to this crate.
+//! * Serializing a sketch produces the same bytes in every release of the same major version. A new
+//!   writable format, such as a new serial version, only becomes the default in a new major
+//!   release.
+//! * Images are little-endian and do not depend on the platform, including `wasm32`.
+//!
+//! The `serialization_stability` tests hold a blob of every writable format and check that
+//! they keep deserializing to the same sketches.
+//!
+//! [`MultiKll`]: crate::kll::MultiKll
+//!
+//! # Examples
+//!
+//! ```
+//! # use datasketches::hll::HllSketch;
+//! # use datasketches::hll::HllType;
+//! # use datasketches::postgres::from_bytea;
+//! # use datasketches::postgres::to_bytea;
+//! let mut sketch = HllSketch::new(10, HllType::Hll8);
+//! sketch.update("apple");
+//!
+//! // e.g. in `INSERT INTO visits VALUES ('2024-01-01', '\x0201...')`
+//! let literal = to_bytea(&sketch);
+//! assert!(literal.starts_with("\\x"));
+//!
+//! let decoded: HllSketch = from_bytea(&literal).unwrap();
+//! assert_eq!(decoded.estimate(), sketch.estimate());
+//! ```
+
+use std::fmt::Write;
+
+use crate::common::SerializableSketch;
+use crate::error::Error;
+
+/// Serializes a sketch into the hex text format of PostgreSQL `bytea` values.
+pub fn to_bytea<S: SerializableSketch>(sketch: &S) -> String {
+    encode_bytea(&sketch.serialize())
+}
+
+/// Deserializes a sketch from the text representation of a PostgreSQL `bytea` value, in the
+/// hex or escape format.
+///
+/// # Errors
+///
+/// Returns an error if the text is not a valid `bytea` value, see [`decode_bytea`], or if the
+/// bytes are not a valid sketch.
+pub fn from_bytea<S: SerializableSketch<Error = Error>>(text: &str) -> Result<S, Error> {
+    S::deserialize(&decode_bytea(text)?)
+}
+
+/// Encodes bytes in the hex text format of PostgreSQL `bytea` values: `\x` followed by two
+/// lowercase hex digits per byte.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::postgres::encode_bytea;
+/// assert_eq!(encode_bytea(&[0x01, 0xab]), "\\x01ab");
+/// ```
+pub fn encode_bytea(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(2 + 2 * bytes.len());
+    text.push_str("\\x");
+    for byte in bytes {
+        write!(text, "{byte:02x}").expect("writing to a String cannot fail");
+    }
+    text
+}
+
+/// Decodes the text representation of a PostgreSQL `bytea` value.
+///
+/// Text starting with `\x` is read in the hex format, where whitespace is allowed between
+/// pairs of hex digits of either case. Any other text is read in the escape format, where
+/// `\\` stands for a backslash, `\` followed by three octal digits for the byte they encode,
+/// and any other character for its UTF-8 bytes.
+///
+/// # Errors
+///
+/// Returns an error if the text has an odd number of hex digits, a character that is not a hex
+/// digit, or an invalid escape sequence.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::postgres::decode_bytea;
+/// assert_eq!(decode_bytea("\\x01AB ff").unwrap(), [0x01, 0xab, 0xff]);
+/// assert_eq!(decode_bytea("a\\000\\\\").unwrap(), [b'a', 0x00, b'\\']);
+/// ```
+pub fn decode_bytea(text: &str) -> Result<Vec<u8>, Error> {
+    match text.strip_prefix("\\x") {
+        Some(hex) => decode_hex(hex),
+        None => decode_escape(text),
+    }
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    let digit = |c: u8| {
+        (c as char).to_digit(16).map(|d| d as u8).ok_or_else(|| {
+            Error::invalid_argument(format!("invalid hex digit in bytea: {:?}", c as char))
+        })
+    };
+    let mut bytes = Vec::with_capacity(hex.len() / 2);
+    let mut chars = hex.bytes().filter(|c| !c.is_ascii_whitespace());
+    while let Some(high) = chars.next() {
+        let low = chars
+            .next()
+            .ok_or_else(|| Error::invalid_argument("odd number of hex digits in bytea"))?;
+        bytes.push((digit(high)? << 4) | digit(low)?);
+    }
+    Ok(bytes)
+}
+
+fn decode_escape(text: &str) -> Result<Vec<u8>, Error> {
+    let text = text.as_bytes();
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut i = 0;
+    while i < text.len() {
+        if text[i] != b'\\' {
+            bytes.push(text[i]);
+            i += 1;
+        } else if text.get(i + 1) == Some(&b'\\') {
+            bytes.push(b'\\');
+            i += 2;
+        } else {
+            let octal = text
+                .get(i + 1..i + 4)
+                .filter(|digits| {
+                    matches!(digits[0], b'0'..=b'3')
+                        && digits[1..].iter().all(|d| (b'0'..=b'7').contains(d))
+                })
+                .ok_or_else(|| {
+                    Error::invalid_argument(format!("invalid escape sequence in bytea at {i}"))
+                })?;
+            bytes.push(octal.iter().fold(0, |byte, d| (byte << 3) | (d - b'0')));
+            i += 4;
+        }
+    }
+    Ok(bytes)
+}
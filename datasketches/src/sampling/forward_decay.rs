@@ -0,0 +1,362 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::common::random::RandomSource;
+use crate::error::Error;
+
+/// Weighted sampling sketch with exponential forward decay.
+///
+/// The sketch retains up to `k` items with the largest priorities, where the priority of an
+/// item is its decayed weight divided by a uniform random number. Priorities are kept in log
+/// space, so timestamps may be arbitrarily far from each other without overflowing.
+///
+/// Timestamps and the half-life may use any unit, as long as it is the same for both.
+///
+/// See the [module level documentation](super) for more.
+#[derive(Debug, Clone)]
+pub struct ForwardDecaySketch<T> {
+    k: usize,
+    half_life: f64,
+    // decay rate per time unit, i.e., ln(2) / half_life
+    lambda: f64,
+    // the timestamp relative to which the decay is applied, set by the first update
+    landmark: Option<f64>,
+    n: u64,
+    // min-heap of the retained entries by priority
+    entries: BinaryHeap<Entry<T>>,
+    // the largest log priority that was not retained, or -inf if no entry was discarded
+    threshold: f64,
+    random: RandomSource,
+}
+
+#[derive(Debug, Clone)]
+struct Entry<T> {
+    // ln(weight) + lambda * (timestamp - landmark) - ln(u)
+    log_priority: f64,
+    item: T,
+    weight: f64,
+    timestamp: f64,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, so that the binary heap keeps the smallest priority on top
+        other.log_priority.total_cmp(&self.log_priority)
+    }
+}
+
+/// A sample retained by a [`ForwardDecaySketch`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecayedSample<'a, T> {
+    item: &'a T,
+    weight: f64,
+    timestamp: f64,
+    estimated_weight: f64,
+}
+
+impl<'a, T> DecayedSample<'a, T> {
+    /// Returns the sampled item.
+    pub fn item(&self) -> &'a T {
+        self.item
+    }
+
+    /// Returns the original, undecayed weight of the item.
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// Returns the timestamp of the item.
+    pub fn timestamp(&self) -> f64 {
+        self.timestamp
+    }
+
+    /// Returns the unbiased estimate of the decayed weight this sample represents.
+    ///
+    /// Summing the estimated weights of the samples that satisfy a predicate gives an unbiased
+    /// estimate of the decayed weight of all items that satisfy it.
+    pub fn estimated_weight(&self) -> f64 {
+        self.estimated_weight
+    }
+}
+
+impl<T> ForwardDecaySketch<T> {
+    /// Creates a sketch that retains up to `k` samples, where the weight of an item halves every
+    /// `half_life` time units.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero, or if `half_life` is not positive and finite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::sampling::ForwardDecaySketch;
+    /// let sketch = ForwardDecaySketch::<u64>::new(64, 3600.0);
+    /// assert_eq!(sketch.k(), 64);
+    /// assert_eq!(sketch.half_life(), 3600.0);
+    /// ```
+    pub fn new(k: usize, half_life: f64) -> Self {
+        assert!(k > 0, "k must be positive");
+        assert!(
+            half_life > 0.0 && half_life.is_finite(),
+            "half_life must be positive and finite, got {half_life}"
+        );
+        ForwardDecaySketch {
+            k,
+            half_life,
+            lambda: std::f64::consts::LN_2 / half_life,
+            landmark: None,
+            n: 0,
+            entries: BinaryHeap::with_capacity(k),
+            threshold: f64::NEG_INFINITY,
+            random: RandomSource::new(),
+        }
+    }
+
+    /// Creates a sketch like [`new`](Self::new) whose priorities are drawn from a generator
+    /// seeded with `seed`.
+    ///
+    /// Sketches created by [`new`](Self::new) seed their generator from per-process randomness,
+    /// so the retained samples differ from run to run. With a fixed seed, the same updates in
+    /// the same order always retain the same samples, which makes tests and replayed streams
+    /// reproducible.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`new`](Self::new).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::sampling::ForwardDecaySketch;
+    /// let mut a = ForwardDecaySketch::with_random_seed(4, 10.0, 7);
+    /// let mut b = ForwardDecaySketch::with_random_seed(4, 10.0, 7);
+    /// for i in 0..100 {
+    ///     a.update(i, 1.0, i as f64);
+    ///     b.update(i, 1.0, i as f64);
+    /// }
+    /// let items = |s: &ForwardDecaySketch<i32>| {
+    ///     let mut items: Vec<i32> = s.samples(100.0).map(|sample| *sample.item()).collect();
+    ///     items.sort();
+    ///     items
+    /// };
+    /// assert_eq!(items(&a), items(&b));
+    /// ```
+    pub fn with_random_seed(k: usize, half_life: f64, seed: u64) -> Self {
+        let mut sketch = Self::new(k, half_life);
+        sketch.random = RandomSource::with_seed(seed);
+        sketch
+    }
+
+    /// Returns the maximum number of samples.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the half-life of the item weights.
+    pub fn half_life(&self) -> f64 {
+        self.half_life
+    }
+
+    /// Returns the number of items the sketch has been updated with.
+    pub fn n(&self) -> u64 {
+        self.n
+    }
+
+    /// Returns true if the sketch has not seen any item.
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Returns the number of retained samples, which is at most k.
+    pub fn num_samples(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Updates the sketch with an item of the given weight that occurred at the given timestamp.
+    ///
+    /// Items with a weight that is not positive and finite, or with a timestamp that is not
+    /// finite, are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::sampling::ForwardDecaySketch;
+    /// let mut sketch = ForwardDecaySketch::new(8, 10.0);
+    /// sketch.update("a", 2.0, 0.0);
+    /// sketch.update("b", 0.0, 1.0);
+    /// assert_eq!(sketch.n(), 1);
+    /// ```
+    pub fn update(&mut self, item: T, weight: f64, timestamp: f64) {
+        if !(weight > 0.0 && weight.is_finite() && timestamp.is_finite()) {
+            return;
+        }
+        let landmark = *self.landmark.get_or_insert(timestamp);
+        // u is uniform in (0, 1], so that its logarithm is finite
+        let u = 1.0 - self.random.next_f64();
+        let log_priority = weight.ln() + self.lambda * (timestamp - landmark) - u.ln();
+        self.n += 1;
+        self.insert(Entry {
+            log_priority,
+            item,
+            weight,
+            timestamp,
+        });
+    }
+
+    /// Merges the given sketch into this one.
+    ///
+    /// The result is a sample of the union of both streams, retaining up to `k` samples of this
+    /// sketch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sketches have different half-lives.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::sampling::ForwardDecaySketch;
+    /// let mut left = ForwardDecaySketch::new(8, 10.0);
+    /// left.update("a", 1.0, 0.0);
+    /// let mut right = ForwardDecaySketch::new(8, 10.0);
+    /// right.update("b", 1.0, 5.0);
+    /// left.merge(&right).unwrap();
+    /// assert_eq!(left.num_samples(), 2);
+    /// ```
+    pub fn merge(&mut self, other: &ForwardDecaySketch<T>) -> Result<(), Error>
+    where
+        T: Clone,
+    {
+        if self.half_life != other.half_life {
+            return Err(Error::invalid_argument(format!(
+                "incompatible half-life: expected {}, got {}",
+                self.half_life, other.half_life
+            )));
+        }
+        let Some(other_landmark) = other.landmark else {
+            return Ok(());
+        };
+
+        // re-express the priorities of the other sketch relative to the landmark of this one
+        let landmark = *self.landmark.get_or_insert(other_landmark);
+        let shift = self.lambda * (other_landmark - landmark);
+        self.threshold = self.threshold.max(other.threshold + shift);
+        for entry in &other.entries {
+            self.insert(Entry {
+                log_priority: entry.log_priority + shift,
+                item: entry.item.clone(),
+                weight: entry.weight,
+                timestamp: entry.timestamp,
+            });
+        }
+        self.n += other.n;
+        Ok(())
+    }
+
+    /// Returns the retained samples, with weights estimated as of the given time, in no
+    /// particular order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::sampling::ForwardDecaySketch;
+    /// let mut sketch = ForwardDecaySketch::new(8, 10.0);
+    /// sketch.update("a", 4.0, 0.0);
+    /// // while fewer than k items are seen, the estimates are the exact decayed weights
+    /// let sample = sketch.samples(20.0).next().unwrap();
+    /// assert_eq!(*sample.item(), "a");
+    /// assert!((sample.estimated_weight() - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn samples(&self, now: f64) -> impl Iterator<Item = DecayedSample<'_, T>> + '_ {
+        let landmark = self.landmark.unwrap_or(now);
+        let log_decay_now = self.lambda * (now - landmark);
+        self.entries.iter().map(move |entry| {
+            let log_weight = entry.weight.ln() + self.lambda * (entry.timestamp - landmark);
+            DecayedSample {
+                item: &entry.item,
+                weight: entry.weight,
+                timestamp: entry.timestamp,
+                estimated_weight: (log_weight.max(self.threshold) - log_decay_now).exp(),
+            }
+        })
+    }
+
+    /// Returns the estimated total decayed weight of the stream as of the given time.
+    pub fn estimate_total_weight(&self, now: f64) -> f64 {
+        self.samples(now).map(|s| s.estimated_weight()).sum()
+    }
+
+    /// Returns the estimated decayed weight, as of the given time, of the items that satisfy the
+    /// predicate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::sampling::ForwardDecaySketch;
+    /// let mut sketch = ForwardDecaySketch::new(8, 10.0);
+    /// sketch.update("error", 1.0, 0.0);
+    /// sketch.update("ok", 1.0, 0.0);
+    /// let errors = sketch.estimate_subset_weight(0.0, |item| *item == "error");
+    /// assert!((errors - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn estimate_subset_weight(&self, now: f64, mut predicate: impl FnMut(&T) -> bool) -> f64 {
+        self.samples(now)
+            .filter(|s| predicate(s.item()))
+            .map(|s| s.estimated_weight())
+            .sum()
+    }
+
+    /// Resets the sketch to the empty state.
+    pub fn reset(&mut self) {
+        self.landmark = None;
+        self.n = 0;
+        self.entries.clear();
+        self.threshold = f64::NEG_INFINITY;
+    }
+
+    fn insert(&mut self, entry: Entry<T>) {
+        if self.entries.len() < self.k {
+            self.entries.push(entry);
+            return;
+        }
+        let mut smallest = self.entries.peek_mut().expect("a full sketch is not empty");
+        if entry.log_priority > smallest.log_priority {
+            let evicted = std::mem::replace(&mut *smallest, entry);
+            self.threshold = self.threshold.max(evicted.log_priority);
+        } else {
+            self.threshold = self.threshold.max(entry.log_priority);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Sampling sketches for retaining a representative subset of a weighted stream.
+//!
+//! # Forward Decay
+//!
+//! [`ForwardDecaySketch`] keeps a weighted sample without replacement that is biased towards
+//! recent items. It combines priority sampling, as described in
+//! [Priority sampling for estimation of arbitrary subset sums][priority] by Nick Duffield,
+//! Carsten Lund, and Mikkel Thorup, with exponential forward decay, as described in
+//! [Forward Decay: A Practical Time Decay Model for Streaming Systems][decay] by Graham Cormode,
+//! Vladislav Shkapenyuk, Divesh Srivastava, and Bojian Xu.
+//!
+//! The weight of an item halves every `half_life` time units after it arrived, so an item that
+//! is one half-life older than another is half as likely to be sampled, all else being equal.
+//! Since the decay is applied relative to a fixed landmark instead of to the current time, the
+//! relative priorities of the retained items never change, and sketches can be updated in any
+//! order of timestamps and merged.
+//!
+//! [priority]: https://arxiv.org/abs/cs/0509026
+//! [decay]: https://dimacs.rutgers.edu/~graham/pubs/papers/fwddecay.pdf
+//!
+//! # Usage
+//!
+//! ```
+//! # use datasketches::sampling::ForwardDecaySketch;
+//! // keep 16 samples, halving the weight of an item every 60 time units
+//! let mut sketch = ForwardDecaySketch::new(16, 60.0);
+//! for t in 0..1000 {
+//!     sketch.update(format!("event-{t}"), 1.0, t as f64);
+//! }
+//! assert_eq!(sketch.num_samples(), 16);
+//! // most of the samples come from the last few half-lives
+//! let recent = sketch
+//!     .samples(1000.0)
+//!     .filter(|s| s.timestamp() >= 700.0)
+//!     .count();
+//! assert!(recent >= 8);
+//! ```
+
+mod forward_decay;
+
+pub use self::forward_decay::DecayedSample;
+pub use self::forward_decay::ForwardDecaySketch;
@@ -0,0 +1,145 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Serde support for sketches, enabled by the `serde` feature.
+//!
+//! Every sketch is represented by its binary serialization form, written as a byte string, so
+//! a sketch embedded in application state round trips exactly and stays readable by the other
+//! datasketches implementations. Deserialization accepts a byte string or, for formats without
+//! a native bytes type such as JSON, a sequence of bytes.
+//!
+//! Sketches are deserialized with the default seed; sketches built with another seed should be
+//! stored as bytes and restored with the `deserialize_with_seed` constructors instead.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+use serde::de;
+use serde::de::SeqAccess;
+use serde::de::Visitor;
+
+use crate::bloom::BloomFilter;
+use crate::countmin::CountMinSketch;
+use crate::countmin::CountMinValue;
+use crate::cpc::CpcSketch;
+use crate::error::Error;
+use crate::frequencies::FrequentItemValue;
+use crate::frequencies::FrequentItemsSketch;
+use crate::hll::HllSketch;
+use crate::kll::KllItem;
+use crate::kll::KllSketch;
+use crate::kll::MultiKll;
+use crate::tdigest::TDigest;
+use crate::tdigest::TDigestMut;
+use crate::theta::CompactThetaSketch;
+use crate::theta::ThetaSketch;
+
+/// Decodes a sketch from the byte string produced by its `Serialize` implementation.
+struct SketchVisitor<T, F> {
+    expecting: &'static str,
+    decode: F,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T, F> Visitor<'de> for SketchVisitor<T, F>
+where
+    F: FnOnce(&[u8]) -> Result<T, Error>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.expecting)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<T, E> {
+        (self.decode)(bytes).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+fn deserialize_sketch<'de, D, T, F>(
+    deserializer: D,
+    expecting: &'static str,
+    decode: F,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    F: FnOnce(&[u8]) -> Result<T, Error>,
+{
+    deserializer.deserialize_bytes(SketchVisitor {
+        expecting,
+        decode,
+        marker: PhantomData,
+    })
+}
+
+macro_rules! impl_serde {
+    ($(impl$([$($generics:tt)*])? for $ty:ty, $expecting:literal, $serialize:expr, $decode:expr;)*) => {
+        $(
+            impl$(<$($generics)*>)? Serialize for $ty {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    let serialize: fn(&$ty) -> Vec<u8> = $serialize;
+                    serializer.serialize_bytes(&serialize(self))
+                }
+            }
+
+            impl<'de, $($($generics)*)?> Deserialize<'de> for $ty {
+                fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+                    deserialize_sketch(deserializer, $expecting, $decode)
+                }
+            }
+        )*
+    };
+}
+
+impl_serde! {
+    impl for BloomFilter, "a serialized bloom filter",
+        BloomFilter::serialize, BloomFilter::deserialize;
+    impl[T: CountMinValue] for CountMinSketch<T>, "a serialized CountMin sketch",
+        CountMinSketch::serialize, CountMinSketch::deserialize;
+    impl for CpcSketch, "a serialized CPC sketch",
+        CpcSketch::serialize, CpcSketch::deserialize;
+    impl[T: FrequentItemValue] for FrequentItemsSketch<T>, "a serialized frequent items sketch",
+        FrequentItemsSketch::serialize, FrequentItemsSketch::deserialize;
+    impl for HllSketch, "a serialized HLL sketch",
+        HllSketch::serialize, HllSketch::deserialize;
+    impl[T: KllItem] for KllSketch<T>, "a serialized KLL sketch",
+        KllSketch::serialize, KllSketch::deserialize;
+    impl[const D: usize] for MultiKll<D>, "a serialized multi-dimensional KLL sketch",
+        MultiKll::serialize, MultiKll::deserialize;
+    impl for TDigestMut, "a serialized t-digest",
+        |sketch| TDigestMut::serialize(&mut sketch.clone()),
+        |bytes| TDigestMut::deserialize(bytes, false);
+    impl for TDigest, "a serialized t-digest",
+        |sketch| TDigestMut::serialize(&mut sketch.clone().unfreeze()),
+        |bytes| TDigestMut::deserialize(bytes, false).map(TDigestMut::freeze);
+    impl for ThetaSketch, "a serialized updatable theta sketch",
+        ThetaSketch::serialize, ThetaSketch::deserialize;
+    impl for CompactThetaSketch, "a serialized compact theta sketch",
+        CompactThetaSketch::serialize, CompactThetaSketch::deserialize;
+}
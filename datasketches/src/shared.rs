@@ -0,0 +1,120 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Lock-free publication of read-mostly sketches.
+//!
+//! This module is only available with the `shared` feature. A telemetry exporter usually has
+//! one writer that keeps updating a sketch and many readers, e.g. metrics scrapers, that only
+//! need a recent, consistent view of it. [`SharedSketch`] holds the latest published copy of a
+//! sketch behind an atomically swapped pointer:
+//!
+//! * The writer owns the live sketch and calls [`SharedSketch::publish`] with an immutable copy
+//!   of it, e.g. from [`ThetaSketch::snapshot`](crate::theta::ThetaSketch::snapshot), whenever
+//!   readers should see the updates so far.
+//! * Readers call [`SharedSketch::load`] and query the returned [`Arc`] for as long as they
+//!   like.
+//!
+//! Neither side takes a lock: `load` never waits for `publish`, and `publish` never waits for
+//! readers to finish with the copy they loaded. A reader keeps the copy it loaded even after a
+//! newer one is published, and the old copy is dropped when the last reader releases it. Every
+//! load returns a whole published sketch, never a mix of two.
+//!
+//! # Usage
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # use std::thread;
+//! # use datasketches::shared::SharedSketch;
+//! # use datasketches::theta::CompactThetaSketch;
+//! # use datasketches::theta::ThetaSketch;
+//! let shared = Arc::new(SharedSketch::new(CompactThetaSketch::empty()));
+//!
+//! let writer = {
+//!     let shared = Arc::clone(&shared);
+//!     thread::spawn(move || {
+//!         let mut sketch = ThetaSketch::builder().build();
+//!         for i in 0..1_000 {
+//!             sketch.update(i);
+//!             if i % 100 == 99 {
+//!                 shared.publish(sketch.snapshot());
+//!             }
+//!         }
+//!     })
+//! };
+//!
+//! // a scraper reads whatever was published last
+//! let estimate = shared.load().estimate();
+//! assert!(estimate <= 1_000.0);
+//!
+//! writer.join().unwrap();
+//! assert_eq!(shared.load().estimate(), 1_000.0);
+//! ```
+
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+
+/// The latest published copy of a sketch, readable from any thread without locking.
+///
+/// See the [module level documentation](self) for the concurrency guarantees.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::hll::HllSketch;
+/// # use datasketches::hll::HllType;
+/// # use datasketches::shared::SharedSketch;
+/// let mut sketch = HllSketch::new(12, HllType::Hll8);
+/// let shared = SharedSketch::new(sketch.clone());
+///
+/// sketch.update("apple");
+/// let before = shared.load();
+/// shared.publish(sketch.clone());
+/// assert_eq!(before.estimate(), 0.0);
+/// assert!(shared.load().estimate() >= 1.0);
+/// ```
+#[derive(Debug)]
+pub struct SharedSketch<T> {
+    current: ArcSwap<T>,
+}
+
+impl<T> SharedSketch<T> {
+    /// Creates a shared sketch that publishes the given sketch.
+    pub fn new(sketch: T) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(sketch),
+        }
+    }
+
+    /// Replaces the published sketch, so that subsequent loads return it.
+    ///
+    /// Readers that loaded the previous sketch keep it until they drop it.
+    pub fn publish(&self, sketch: T) {
+        self.current.store(Arc::new(sketch));
+    }
+
+    /// Returns the latest published sketch.
+    pub fn load(&self) -> Arc<T> {
+        self.current.load_full()
+    }
+}
+
+impl<T: Default> Default for SharedSketch<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
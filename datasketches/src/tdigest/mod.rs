@@ -63,5 +63,6 @@
 mod serialization;
 
 mod sketch;
+pub(crate) use self::serialization::inspect;
 pub use self::sketch::TDigest;
 pub use self::sketch::TDigestMut;
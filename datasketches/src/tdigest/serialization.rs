@@ -15,6 +15,13 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use crate::codec::SketchSlice;
+use crate::codec::assert::ensure_serial_version_is;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
+use crate::error::Error;
+use crate::inspect::SketchInfo;
+
 pub(super) const PREAMBLE_LONGS_EMPTY_OR_SINGLE: u8 = 1;
 pub(super) const PREAMBLE_LONGS_MULTIPLE: u8 = 2;
 pub(super) const SERIAL_VERSION: u8 = 1;
@@ -25,3 +32,35 @@ pub(super) const FLAGS_REVERSE_MERGE: u8 = 1 << 2;
 pub(super) const COMPAT_DOUBLE: u32 = 1;
 /// the format of the reference implementation is using float (f32) precision
 pub(super) const COMPAT_FLOAT: u32 = 2;
+
+/// Reads the preamble of an image for [`inspect`](crate::inspect).
+pub(crate) fn inspect(
+    _preamble_longs: u8,
+    serial_version: u8,
+    mut cursor: SketchSlice<'_>,
+) -> Result<SketchInfo, Error> {
+    ensure_serial_version_is(SERIAL_VERSION, serial_version)?;
+    let k = cursor.read_u16_le().map_err(insufficient_data("k"))?;
+    let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+    cursor
+        .read_u16_le()
+        .map_err(insufficient_data("<unused>"))?;
+
+    let mut info = SketchInfo::new(&Family::TDIGEST, serial_version);
+    info.k = Some(k);
+    info.is_empty = (flags & FLAGS_IS_EMPTY) != 0;
+    info.num_retained = if info.is_empty {
+        Some(0)
+    } else if (flags & FLAGS_IS_SINGLE_VALUE) != 0 {
+        Some(1)
+    } else {
+        let num_centroids = cursor
+            .read_u32_le()
+            .map_err(insufficient_data("num_centroids"))?;
+        let num_buffered = cursor
+            .read_u32_le()
+            .map_err(insufficient_data("num_buffered"))?;
+        Some(num_centroids as u64 + num_buffered as u64)
+    };
+    Ok(info)
+}
@@ -16,8 +16,11 @@
 // under the License.
 
 use std::cmp::Ordering;
+use std::convert::Infallible;
 use std::convert::identity;
+use std::fmt;
 use std::num::NonZeroU64;
+use std::time::Duration;
 
 use crate::codec::SketchBytes;
 use crate::codec::SketchSlice;
@@ -25,6 +28,13 @@ use crate::codec::assert::ensure_preamble_longs_in;
 use crate::codec::assert::ensure_serial_version_is;
 use crate::codec::assert::insufficient_data;
 use crate::codec::family::Family;
+use crate::codec::serialize_into_slice;
+use crate::common::CdfPoint;
+use crate::common::MergeableSketch;
+use crate::common::SerializableSketch;
+use crate::common::Sketch;
+use crate::common::Summary;
+use crate::common::evenly_spaced_ranks;
 use crate::error::Error;
 use crate::tdigest::serialization::COMPAT_DOUBLE;
 use crate::tdigest::serialization::COMPAT_FLOAT;
@@ -182,6 +192,39 @@ impl TDigestMut {
         self.max = self.max.max(value);
     }
 
+    /// Updates the digest with a duration, in seconds.
+    ///
+    /// Digests of durations should only be updated with durations, so that
+    /// [`quantile_duration`](Self::quantile_duration) reports quantiles as durations, e.g.
+    /// latency percentiles for SLOs. The resolution of a duration is lost beyond the precision
+    /// of `f64`, which is about a nanosecond for a day.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use datasketches::tdigest::TDigestMut;
+    /// let mut sketch = TDigestMut::new(100);
+    /// for ms in 1..=1000 {
+    ///     sketch.update_duration(Duration::from_millis(ms));
+    /// }
+    /// let p99 = sketch.quantile_duration(0.99).unwrap();
+    /// assert!(p99.abs_diff(Duration::from_millis(990)) < Duration::from_millis(5));
+    /// ```
+    pub fn update_duration(&mut self, value: Duration) {
+        self.update(value.as_secs_f64());
+    }
+
+    /// Returns the approximate quantile of a digest of durations, see
+    /// [`update_duration`](Self::update_duration) and [`quantile`](Self::quantile).
+    ///
+    /// # Panics
+    ///
+    /// If rank is not in [0.0, 1.0].
+    pub fn quantile_duration(&mut self, rank: f64) -> Option<Duration> {
+        self.quantile(rank).map(seconds_to_duration)
+    }
+
     /// Returns parameter k (compression) that was used to configure this TDigest.
     pub fn k(&self) -> u16 {
         self.k
@@ -192,6 +235,14 @@ impl TDigestMut {
         self.centroids.is_empty() && self.buffer.is_empty()
     }
 
+    /// Returns the number of bytes of memory the TDigest occupies, including the capacity of
+    /// its centroid array and of its buffer of unmerged values.
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>()
+            + self.centroids.capacity() * size_of::<Centroid>()
+            + self.buffer.capacity() * size_of::<f64>()
+    }
+
     /// Returns minimum value seen by TDigest; `None` if TDigest is empty.
     pub fn min_value(&self) -> Option<f64> {
         if self.is_empty() {
@@ -277,6 +328,40 @@ impl TDigestMut {
         }
     }
 
+    /// Returns an immutable copy of this TDigest for reading while it keeps being updated.
+    ///
+    /// Unlike [`freeze`](Self::freeze), this keeps the sketch. Buffered values are merged into
+    /// the centroids in place first, as any query would do, so only the centroids are copied.
+    /// This keeps the time spent holding a lock around a live sketch short: take the snapshot
+    /// under the lock, release it, then query the copy while updates continue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::sync::Mutex;
+    /// # use datasketches::tdigest::TDigestMut;
+    /// let live = Mutex::new(TDigestMut::new(100));
+    /// for value in [1.0, 2.0, 3.0] {
+    ///     live.lock().unwrap().update(value);
+    /// }
+    ///
+    /// let snapshot = live.lock().unwrap().snapshot();
+    /// live.lock().unwrap().update(100.0);
+    /// assert_eq!(snapshot.total_weight(), 3);
+    /// assert_eq!(snapshot.max_value(), Some(3.0));
+    /// ```
+    pub fn snapshot(&mut self) -> TDigest {
+        self.compress();
+        TDigest {
+            k: self.k,
+            reverse_merge: self.reverse_merge,
+            min: self.min,
+            max: self.max,
+            centroids: self.centroids.clone(),
+            centroids_weight: self.centroids_weight,
+        }
+    }
+
     fn view(&mut self) -> TDigestView<'_> {
         self.compress(); // side effect
         TDigestView {
@@ -389,6 +474,29 @@ impl TDigestMut {
         self.view().quantile(rank)
     }
 
+    /// See [`TDigest::to_cdf_points`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::tdigest::TDigestMut;
+    /// # let mut sketch = TDigestMut::new(100);
+    /// # for value in [1.0, 2.0, 3.0] {
+    /// #     sketch.update(value);
+    /// # }
+    /// let points = sketch.to_cdf_points(5).unwrap();
+    /// assert_eq!(points.len(), 5);
+    /// ```
+    pub fn to_cdf_points(&mut self, n: usize) -> Option<Vec<CdfPoint<f64>>> {
+        let ranks = evenly_spaced_ranks(n);
+
+        if self.is_empty() {
+            return None;
+        }
+
+        self.view().cdf_points(ranks)
+    }
+
     /// Serializes this TDigest to bytes.
     ///
     /// # Examples
@@ -403,7 +511,49 @@ impl TDigestMut {
     /// ```
     pub fn serialize(&mut self) -> Vec<u8> {
         self.compress();
+        let mut bytes = SketchBytes::with_capacity(self.compressed_size_bytes());
+        self.write_compressed(&mut bytes);
+        bytes.into_bytes()
+    }
+
+    /// Returns the number of bytes [`TDigestMut::serialize`] produces.
+    ///
+    /// The size depends on the number of centroids, so this compresses buffered values first.
+    pub fn serialized_size_bytes(&mut self) -> usize {
+        self.compress();
+        self.compressed_size_bytes()
+    }
+
+    /// Serializes this TDigest into the front of `buf` without allocating, returning the number
+    /// of bytes written.
+    ///
+    /// The bytes are the same as those returned by [`TDigestMut::serialize`]. Buffered values are
+    /// compressed first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `buf` is shorter than [`TDigestMut::serialized_size_bytes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::tdigest::TDigestMut;
+    /// # let mut sketch = TDigestMut::new(100);
+    /// # sketch.update(1.0);
+    /// let mut buf = [0u8; 64];
+    /// let len = sketch.serialize_into(&mut buf).unwrap();
+    /// let decoded = TDigestMut::deserialize(&buf[..len], false).unwrap();
+    /// assert_eq!(decoded.max_value(), Some(1.0));
+    /// ```
+    pub fn serialize_into(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.compress();
+        serialize_into_slice(buf, self.compressed_size_bytes(), |bytes| {
+            self.write_compressed(bytes)
+        })
+    }
 
+    /// Returns the serialized size, given that buffered values have been compressed.
+    fn compressed_size_bytes(&self) -> usize {
         let mut total_size = 0;
         if self.is_empty() || self.is_single_value() {
             // 1 byte preamble
@@ -431,8 +581,12 @@ impl TDigestMut {
             // + (8+8) bytes per centroid
             total_size += self.centroids.len() * (size_of::<f64>() + size_of::<u64>());
         }
+        total_size
+    }
 
-        let mut bytes = SketchBytes::with_capacity(total_size);
+    /// Serializes into `bytes`, given that buffered values have been compressed.
+    fn write_compressed(&self, bytes: &mut SketchBytes<'_>) {
+        debug_assert!(self.buffer.is_empty());
         bytes.write_u8(match self.total_weight() {
             0 => PREAMBLE_LONGS_EMPTY_OR_SINGLE,
             1 => PREAMBLE_LONGS_EMPTY_OR_SINGLE,
@@ -456,11 +610,11 @@ impl TDigestMut {
         });
         bytes.write_u16_le(0); // unused
         if self.is_empty() {
-            return bytes.into_bytes();
+            return;
         }
         if self.is_single_value() {
             bytes.write_f64_le(self.min);
-            return bytes.into_bytes();
+            return;
         }
         bytes.write_u32_le(self.centroids.len() as u32);
         bytes.write_u32_le(0); // unused
@@ -470,7 +624,6 @@ impl TDigestMut {
             bytes.write_f64_le(centroid.mean);
             bytes.write_u64_le(centroid.weight.get());
         }
-        bytes.into_bytes()
     }
 
     /// Deserializes a TDigest from bytes.
@@ -514,7 +667,9 @@ impl TDigestMut {
         ensure_serial_version_is(SERIAL_VERSION, serial_version)?;
         let k = cursor.read_u16_le().map_err(insufficient_data("k"))?;
         if k < 10 {
-            return Err(Error::deserial(format!("k must be at least 10, got {k}")));
+            return Err(Error::bad_preamble(format!(
+                "k must be at least 10, got {k}"
+            )));
         }
         let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
         let is_empty = (flags & FLAGS_IS_EMPTY) != 0;
@@ -577,6 +732,14 @@ impl TDigestMut {
         };
         check_non_nan(min, "min")?;
         check_non_nan(max, "max")?;
+        let (centroid_size, value_size) = if is_f32 { (8, 4) } else { (16, 8) };
+        let required = (num_centroids as u64) * centroid_size + (num_buffered as u64) * value_size;
+        if (cursor.remaining() as u64) < required {
+            return Err(Error::insufficient_data(format!(
+                "expected {required} bytes for {num_centroids} centroids and {num_buffered} buffered values, got {}",
+                cursor.remaining()
+            )));
+        }
         let mut centroids = Vec::with_capacity(num_centroids);
         let mut centroids_weight = 0u64;
         for _ in 0..num_centroids {
@@ -594,7 +757,9 @@ impl TDigestMut {
             check_non_nan(mean, "centroid mean")?;
             check_finite(mean, "centroid")?;
             let weight = check_nonzero(weight, "centroid weight")?;
-            centroids_weight += weight.get();
+            centroids_weight = centroids_weight
+                .checked_add(weight.get())
+                .ok_or_else(|| Error::deserial("centroid weights overflow the total weight"))?;
             centroids.push(Centroid { mean, weight });
         }
         let mut buffer = Vec::with_capacity(num_buffered);
@@ -645,21 +810,23 @@ impl TDigestMut {
                 check_non_nan(max, "max in compat double format")?;
                 let k = cursor.read_f64_be().map_err(make_error("k"))? as u16;
                 if k < 10 {
-                    return Err(Error::deserial(format!(
+                    return Err(Error::bad_preamble(format!(
                         "k must be at least 10 in compat double format, got {k}"
                     )));
                 }
                 let num_centroids =
                     cursor.read_u32_be().map_err(make_error("num_centroids"))? as usize;
                 let mut total_weight = 0u64;
-                let mut centroids = Vec::with_capacity(num_centroids);
+                let mut centroids = Vec::with_capacity(num_centroids.min(cursor.remaining() / 16));
                 for _ in 0..num_centroids {
                     let weight = cursor.read_f64_be().map_err(make_error("weight"))? as u64;
                     let mean = cursor.read_f64_be().map_err(make_error("mean"))?;
                     let weight = check_nonzero(weight, "centroid weight in compat double format")?;
                     check_non_nan(mean, "centroid mean in compat double format")?;
                     check_finite(mean, "centroid mean in compat double format")?;
-                    total_weight += weight.get();
+                    total_weight = total_weight.checked_add(weight.get()).ok_or_else(|| {
+                        Error::deserial("centroid weights overflow the total weight")
+                    })?;
                     centroids.push(Centroid { mean, weight });
                 }
                 Ok(TDigestMut::make(
@@ -684,7 +851,7 @@ impl TDigestMut {
                 check_non_nan(max, "max in compat float format")?;
                 let k = cursor.read_f32_be().map_err(make_error("k"))? as u16;
                 if k < 10 {
-                    return Err(Error::deserial(format!(
+                    return Err(Error::bad_preamble(format!(
                         "k must be at least 10 in compat float format, got {k}"
                     )));
                 }
@@ -701,7 +868,9 @@ impl TDigestMut {
                     let weight = check_nonzero(weight, "centroid weight in compat float format")?;
                     check_non_nan(mean, "centroid mean in compat float format")?;
                     check_finite(mean, "centroid mean in compat float format")?;
-                    total_weight += weight.get();
+                    total_weight = total_weight.checked_add(weight.get()).ok_or_else(|| {
+                        Error::deserial("centroid weights overflow the total weight")
+                    })?;
                     centroids.push(Centroid { mean, weight });
                 }
                 Ok(TDigestMut::make(
@@ -714,7 +883,9 @@ impl TDigestMut {
                     vec![],
                 ))
             }
-            ty => Err(Error::deserial(format!("unknown TDigest compat type {ty}"))),
+            ty => Err(Error::bad_preamble(format!(
+                "unknown TDigest compat type {ty}"
+            ))),
         }
     }
 
@@ -798,6 +969,7 @@ impl TDigestMut {
 /// Immutable (frozen) T-Digest sketch for estimating quantiles and ranks.
 ///
 /// See the [module level documentation](super) for more.
+#[derive(Debug, Clone)]
 pub struct TDigest {
     k: u16,
 
@@ -820,6 +992,12 @@ impl TDigest {
         self.centroids.is_empty()
     }
 
+    /// Returns the number of bytes of memory the TDigest occupies, including the capacity of
+    /// its centroid array.
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>() + self.centroids.capacity() * size_of::<Centroid>()
+    }
+
     /// Returns minimum value seen by TDigest; `None` if TDigest is empty.
     pub fn min_value(&self) -> Option<f64> {
         if self.is_empty() {
@@ -977,6 +1155,44 @@ impl TDigest {
         self.view().quantile(rank)
     }
 
+    /// Returns the approximate quantile of a digest of durations, see
+    /// [`TDigestMut::update_duration`].
+    ///
+    /// # Panics
+    ///
+    /// If rank is not in [0.0, 1.0].
+    pub fn quantile_duration(&self, rank: f64) -> Option<Duration> {
+        self.quantile(rank).map(seconds_to_duration)
+    }
+
+    /// Returns `n` points of the approximate CDF of the input stream, suitable for plotting.
+    ///
+    /// The points are the quantiles at `n` evenly spaced normalized ranks from 0.0 to 1.0, each
+    /// paired with its own rank as returned by [`rank`](Self::rank). Values are non-decreasing.
+    ///
+    /// Returns `None` if TDigest is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is less than 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::tdigest::TDigestMut;
+    /// # let mut sketch = TDigestMut::new(100);
+    /// # for value in [1.0, 2.0, 3.0] {
+    /// #     sketch.update(value);
+    /// # }
+    /// let digest = sketch.freeze();
+    /// let points = digest.to_cdf_points(5).unwrap();
+    /// assert_eq!(points.first().unwrap().value, 1.0);
+    /// assert_eq!(points.last().unwrap().value, 3.0);
+    /// ```
+    pub fn to_cdf_points(&self, n: usize) -> Option<Vec<CdfPoint<f64>>> {
+        self.view().cdf_points(evenly_spaced_ranks(n))
+    }
+
     /// Converts this immutable TDigest into a mutable one.
     ///
     /// # Examples
@@ -1037,6 +1253,26 @@ impl TDigestView<'_> {
         Some(ranks)
     }
 
+    fn cdf_points(&self, ranks: impl Iterator<Item = f64>) -> Option<Vec<CdfPoint<f64>>> {
+        if self.centroids.is_empty() {
+            return None;
+        }
+
+        let points = ranks
+            .map(|rank| {
+                let value = match self.quantile(rank) {
+                    Some(value) => value,
+                    None => unreachable!("checked non-empty above"),
+                };
+                match self.rank(value) {
+                    Some(rank) => CdfPoint { value, rank },
+                    None => unreachable!("checked non-empty above"),
+                }
+            })
+            .collect();
+        Some(points)
+    }
+
     fn rank(&self, value: f64) -> Option<f64> {
         debug_assert!(!value.is_nan(), "value must not be NaN");
 
@@ -1212,6 +1448,12 @@ impl TDigestView<'_> {
     }
 }
 
+/// Converts a quantile of a digest of durations back to a duration, saturating values that no
+/// duration can hold, which only appear if the digest was also updated with other values.
+fn seconds_to_duration(seconds: f64) -> Duration {
+    Duration::try_from_secs_f64(seconds.max(0.0)).unwrap_or(Duration::MAX)
+}
+
 /// Checks the sequential validity of the given array of double values.
 /// They must be unique, monotonically increasing and not NaN.
 #[track_caller]
@@ -1338,3 +1580,86 @@ mod scale_function {
 const fn weighted_average(x1: f64, w1: f64, x2: f64, w2: f64) -> f64 {
     (x1 * w1 + x2 * w2) / (w1 + w2)
 }
+
+impl fmt::Display for TDigestMut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut summary = Summary::new(f, "TDIGEST");
+        summary
+            .field("k", self.k)
+            .field("reverse merge", self.reverse_merge)
+            .field("num centroids", self.centroids.len())
+            .field("num buffered", self.buffer.len())
+            .field("total weight", self.total_weight())
+            .field("empty", self.is_empty());
+        if !self.is_empty() {
+            summary.field("min", self.min).field("max", self.max);
+        }
+        summary.finish()
+    }
+}
+
+impl fmt::Display for TDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut summary = Summary::new(f, "TDIGEST");
+        summary
+            .field("k", self.k)
+            .field("reverse merge", self.reverse_merge)
+            .field("num centroids", self.centroids.len())
+            .field("total weight", self.total_weight())
+            .field("empty", self.is_empty());
+        if !self.is_empty() {
+            summary.field("min", self.min).field("max", self.max);
+        }
+        summary.finish()
+    }
+}
+
+impl Sketch<f64> for TDigestMut {
+    fn update(&mut self, item: f64) {
+        TDigestMut::update(self, item);
+    }
+
+    fn is_empty(&self) -> bool {
+        TDigestMut::is_empty(self)
+    }
+}
+
+impl MergeableSketch for TDigestMut {
+    type Error = Infallible;
+
+    fn merge(&mut self, other: &Self) -> Result<(), Infallible> {
+        TDigestMut::merge(self, other);
+        Ok(())
+    }
+}
+
+impl SerializableSketch for TDigestMut {
+    type Error = Error;
+
+    fn serialize(&self) -> Vec<u8> {
+        // compression does not change the summarized distribution
+        TDigestMut::serialize(&mut self.clone())
+    }
+
+    fn serialized_size_bytes(&self) -> usize {
+        if self.buffer.is_empty() {
+            self.compressed_size_bytes()
+        } else {
+            TDigestMut::serialized_size_bytes(&mut self.clone())
+        }
+    }
+
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        if self.buffer.is_empty() {
+            serialize_into_slice(buf, self.compressed_size_bytes(), |bytes| {
+                self.write_compressed(bytes)
+            })
+        } else {
+            TDigestMut::serialize_into(&mut self.clone(), buf)
+        }
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        TDigestMut::deserialize(bytes, false)
+    }
+}
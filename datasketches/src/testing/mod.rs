@@ -0,0 +1,213 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Randomly configured and filled sketches for property-testing code that stores sketches.
+//!
+//! This module is only available with the `testing` feature. [`SketchGenerator`] draws the
+//! parameters of a sketch from a bounded range and updates it with a random stream, so
+//! downstream crates can check that their storage layers, e.g. a database column or an object
+//! store, round-trip realistic serialized images, from empty sketches to sketches in
+//! estimation mode.
+//!
+//! Generators are seeded: the same seed always yields the same sequence of sketches, so a
+//! failing case can be replayed from its seed. This plugs into property-testing frameworks by
+//! drawing the seed from them.
+//!
+//! # Usage
+//!
+//! ```
+//! # use datasketches::testing::SketchGenerator;
+//! # use datasketches::theta::CompactThetaSketch;
+//! let mut generator = SketchGenerator::new(42);
+//! for _ in 0..10 {
+//!     let sketch = generator.theta().compact(true);
+//!     let bytes = sketch.serialize();
+//!     // store and load the bytes with the storage layer under test
+//!     let restored = CompactThetaSketch::deserialize(&bytes).unwrap();
+//!     assert_eq!(restored.serialize(), bytes);
+//! }
+//! ```
+
+use crate::bloom::BloomFilter;
+use crate::bloom::BloomFilterBuilder;
+use crate::common::random::RandomSource;
+use crate::countmin::CountMinSketch;
+use crate::cpc::CpcSketch;
+use crate::frequencies::FrequentItemsSketch;
+use crate::hll::HllSketch;
+use crate::hll::HllType;
+use crate::kll::KllSketch;
+use crate::tdigest::TDigestMut;
+use crate::theta::ThetaSketch;
+
+const DEFAULT_MAX_ITEMS: u64 = 10_000;
+
+/// A seeded source of randomly configured sketches filled with random streams.
+///
+/// Every method picks the parameters of the sketch within a bounded range that keeps sketches
+/// small enough for property tests, then updates it with between 0 and
+/// [`max_items`](Self::max_items) items drawn from a random number of distinct values. The
+/// ranges are documented on each method.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::hll::HllSketch;
+/// # use datasketches::testing::SketchGenerator;
+/// let mut a = SketchGenerator::new(7).max_items(1_000);
+/// let mut b = SketchGenerator::new(7).max_items(1_000);
+/// let sketch = a.hll();
+/// assert_eq!(sketch.serialize(), b.hll().serialize());
+/// let restored = HllSketch::deserialize(&sketch.serialize()).unwrap();
+/// assert_eq!(restored.estimate(), sketch.estimate());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SketchGenerator {
+    random: RandomSource,
+    max_items: u64,
+}
+
+impl SketchGenerator {
+    /// Creates a generator with the given seed that updates sketches with up to 10,000 items.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            random: RandomSource::with_seed(seed),
+            max_items: DEFAULT_MAX_ITEMS,
+        }
+    }
+
+    /// Sets the maximum number of items each sketch is updated with.
+    pub fn max_items(mut self, max_items: u64) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    /// Returns a theta sketch with lg_k in `[5, 12]` and a sampling probability of 1.0, or
+    /// one in `(0.5, 1.0]` one time in four.
+    pub fn theta(&mut self) -> ThetaSketch {
+        let mut builder = ThetaSketch::builder().lg_k(self.in_range(5, 12) as u8);
+        if self.below(4) == 0 {
+            let p = 1.0 - self.random.next_f64() / 2.0;
+            builder = builder.sampling_probability(p as f32);
+        }
+        let mut sketch = builder.build();
+        for item in self.stream() {
+            sketch.update_quiet(item);
+        }
+        sketch
+    }
+
+    /// Returns an HLL sketch of any [`HllType`] with lg_config_k in `[4, 12]`.
+    pub fn hll(&mut self) -> HllSketch {
+        let lg_config_k = self.in_range(4, 12) as u8;
+        let hll_type = match self.below(3) {
+            0 => HllType::Hll4,
+            1 => HllType::Hll6,
+            _ => HllType::Hll8,
+        };
+        let mut sketch = HllSketch::new(lg_config_k, hll_type);
+        for item in self.stream() {
+            sketch.update(item);
+        }
+        sketch
+    }
+
+    /// Returns a CPC sketch with lg_k in `[4, 12]`.
+    pub fn cpc(&mut self) -> CpcSketch {
+        let mut sketch = CpcSketch::new(self.in_range(4, 12) as u8);
+        for item in self.stream() {
+            sketch.update(item);
+        }
+        sketch
+    }
+
+    /// Returns a KLL sketch of doubles with k in `[8, 400]`.
+    ///
+    /// The compactions of the sketch are seeded from this generator, so the retained items are
+    /// reproducible as well.
+    pub fn kll(&mut self) -> KllSketch<f64> {
+        let k = self.in_range(8, 400) as u16;
+        let mut sketch = KllSketch::with_random_seed(k, self.random.next_u64());
+        for item in self.stream() {
+            sketch.update(item as f64);
+        }
+        sketch
+    }
+
+    /// Returns a t-digest with k in `[10, 400]`.
+    pub fn tdigest(&mut self) -> TDigestMut {
+        let mut sketch = TDigestMut::new(self.in_range(10, 400) as u16);
+        for item in self.stream() {
+            sketch.update(item as f64);
+        }
+        sketch
+    }
+
+    /// Returns a frequent items sketch of `i64` with a maximum map size in `[2^3, 2^10]`.
+    pub fn frequent_items(&mut self) -> FrequentItemsSketch<i64> {
+        let max_map_size = 1 << self.in_range(3, 10);
+        let mut sketch = FrequentItemsSketch::new(max_map_size);
+        for item in self.stream() {
+            sketch.update_with_count(item as i64, 1 + self.below(10));
+        }
+        sketch
+    }
+
+    /// Returns a Count-Min sketch of `u64` counters with 1 to 5 hashes and 3 to 256 buckets.
+    pub fn count_min(&mut self) -> CountMinSketch<u64> {
+        let num_hashes = self.in_range(1, 5) as u8;
+        let num_buckets = self.in_range(3, 256) as u32;
+        let mut sketch = CountMinSketch::new(num_hashes, num_buckets);
+        for item in self.stream() {
+            sketch.update_with_weight(item, 1 + self.below(10));
+        }
+        sketch
+    }
+
+    /// Returns a Bloom filter with 64 to 65,536 bits and 1 to 8 hashes.
+    pub fn bloom_filter(&mut self) -> BloomFilter {
+        let num_bits = self.in_range(64, 1 << 16);
+        let num_hashes = self.in_range(1, 8) as u16;
+        let mut filter = BloomFilterBuilder::with_size(num_bits, num_hashes).build();
+        for item in self.stream() {
+            filter.insert(item);
+        }
+        filter
+    }
+
+    /// Draws a stream of up to `max_items` items from a random number of distinct values, so
+    /// that streams range from empty to heavily duplicated.
+    fn stream(&mut self) -> Vec<u64> {
+        let num_items = self.below(self.max_items.saturating_add(1));
+        let num_distinct = self.in_range(1, num_items.max(1));
+        (0..num_items).map(|_| self.below(num_distinct)).collect()
+    }
+
+    /// Returns a value in `[0, n)`, or 0 if `n` is 0.
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            0
+        } else {
+            self.random.next_u64() % n
+        }
+    }
+
+    /// Returns a value in `[min, max]`.
+    fn in_range(&mut self, min: u64, max: u64) -> u64 {
+        min + self.below(max - min + 1)
+    }
+}
@@ -0,0 +1,161 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use crate::common::ResizeFactor;
+use crate::error::Error;
+use crate::hash::DEFAULT_UPDATE_SEED;
+use crate::hash::compute_seed_hash;
+use crate::theta::CompactThetaSketch;
+use crate::theta::HASH_TABLE_REBUILD_THRESHOLD;
+use crate::theta::MAX_THETA;
+use crate::theta::ThetaSketchView;
+use crate::theta::hash_table::ThetaHashTable;
+
+/// Set difference operator for Theta sketches.
+///
+/// Computes the entries of a sketch `a` that are not in a sketch `b`, the equivalent of
+/// `AnotB` in datasketches-java and `theta_a_not_b` in datasketches-cpp. Unlike
+/// [`ThetaUnion`](crate::theta::ThetaUnion) and
+/// [`ThetaIntersection`](crate::theta::ThetaIntersection), the operator is stateless.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::theta::ThetaANotB;
+/// # use datasketches::theta::ThetaSketch;
+/// let mut a = ThetaSketch::builder().build();
+/// let mut b = ThetaSketch::builder().build();
+/// for i in 0..1_000 {
+///     a.update(i);
+///     b.update(i + 600);
+/// }
+///
+/// let a_not_b = ThetaANotB::new_with_default_seed();
+/// let result = a_not_b.compute(&a, &b, true).unwrap();
+/// assert_eq!(result.estimate(), 600.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ThetaANotB {
+    seed: u64,
+    seed_hash: u16,
+}
+
+impl ThetaANotB {
+    /// Creates a new set difference operator for the given `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            seed_hash: compute_seed_hash(seed),
+        }
+    }
+
+    /// Creates a new set difference operator with the default seed.
+    pub fn new_with_default_seed() -> Self {
+        Self::new(DEFAULT_UPDATE_SEED)
+    }
+
+    /// Computes the entries of `a` that are not in `b`.
+    ///
+    /// The theta of the result is the smaller of the thetas of `a` and `b`. If `a` is empty
+    /// or retains nothing, or if `b` is empty, the result is computed from `a` alone without
+    /// looking at the entries of `b`. If both inputs are ordered, the entries are compared
+    /// in a single merge pass, otherwise the entries of `b` are loaded into a hash table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either sketch is not empty and its seed hash does not match the
+    /// seed of the operator.
+    pub fn compute<A, B>(&self, a: &A, b: &B, ordered: bool) -> Result<CompactThetaSketch, Error>
+    where
+        A: ThetaSketchView,
+        B: ThetaSketchView,
+    {
+        for sketch_seed_hash in [
+            (!a.is_empty()).then(|| a.seed_hash()),
+            (!b.is_empty()).then(|| b.seed_hash()),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if sketch_seed_hash != self.seed_hash {
+                return Err(Error::seed_hash_mismatch(self.seed_hash, sketch_seed_hash));
+            }
+        }
+
+        if a.is_empty() {
+            return Ok(CompactThetaSketch::from_parts(
+                vec![],
+                MAX_THETA,
+                self.seed_hash,
+                true,
+                true,
+            ));
+        }
+        let theta = if b.is_empty() {
+            a.theta64()
+        } else {
+            a.theta64().min(b.theta64())
+        };
+        let mut entries: Vec<u64> = if a.num_retained() == 0 {
+            vec![]
+        } else if b.is_empty() || b.num_retained() == 0 {
+            a.iter().filter(|&hash| hash < theta).collect()
+        } else if a.is_ordered() && b.is_ordered() {
+            let mut b_iter = b.iter().take_while(|&hash| hash < theta).peekable();
+            let mut entries = vec![];
+            for hash in a.iter().take_while(|&hash| hash < theta) {
+                while b_iter.next_if(|&b_hash| b_hash < hash).is_some() {}
+                if b_iter.next_if_eq(&hash).is_none() {
+                    entries.push(hash);
+                }
+            }
+            entries
+        } else {
+            let lg_size = ThetaHashTable::lg_size_from_count_for_rebuild(
+                b.num_retained(),
+                HASH_TABLE_REBUILD_THRESHOLD,
+            );
+            let mut table = ThetaHashTable::from_raw_parts(
+                lg_size,
+                lg_size - 1,
+                ResizeFactor::X1,
+                1.0,
+                theta,
+                self.seed,
+                false,
+            );
+            for hash in b.iter().filter(|&hash| hash < theta) {
+                table.insert_hash(hash);
+            }
+            a.iter()
+                .filter(|&hash| hash < theta && !table.contains_hash(hash))
+                .collect()
+        };
+
+        let empty = entries.is_empty() && theta == MAX_THETA;
+        let ordered = ordered || a.is_ordered() || empty;
+        if ordered && !a.is_ordered() {
+            entries.sort_unstable();
+        }
+        Ok(CompactThetaSketch::from_parts(
+            entries,
+            theta,
+            self.seed_hash,
+            ordered,
+            empty,
+        ))
+    }
+}
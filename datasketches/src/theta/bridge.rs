@@ -0,0 +1,141 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Approximate conversion of HLL and CPC sketches to theta sketches.
+
+use crate::common::random::RandomSource;
+use crate::cpc::CpcSketch;
+use crate::error::Error;
+use crate::hash::murmur3_128;
+use crate::hash::try_compute_seed_hash;
+use crate::hll::HllSketch;
+use crate::theta::CompactThetaSketch;
+use crate::theta::MAX_LG_K;
+use crate::theta::MAX_THETA;
+use crate::theta::MIN_LG_K;
+
+/// Converts an HLL sketch to an approximate compact theta sketch with up to `2^lg_k` entries.
+///
+/// HLL registers keep too few bits of the hashes of the items to recover them, so the entries of
+/// the result are synthetic: random hashes, drawn deterministically from the contents of
+/// `sketch`, in a number and under a theta that reproduce its estimate. The result can enter
+/// theta set operations with sketches of the given `seed`, but its entries never match those of
+/// another sketch. Unions with it therefore count its items as distinct from all others, which
+/// is exact for disjoint partitions and an overestimate otherwise, and intersections and set
+/// differences with it are meaningless.
+///
+/// # Errors
+///
+/// Returns an error if `lg_k` is not in `[5, 26]`, or if the 16-bit hash of `seed` is zero.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::hll::HllSketch;
+/// # use datasketches::hll::HllType;
+/// # use datasketches::hash::DEFAULT_UPDATE_SEED;
+/// # use datasketches::theta::ThetaSketch;
+/// # use datasketches::theta::ThetaUnion;
+/// # use datasketches::theta::hll_to_theta;
+/// // last year's partitions were stored as HLL, this year's as theta
+/// let mut archived = HllSketch::new(12, HllType::Hll4);
+/// let mut current = ThetaSketch::builder().build();
+/// for i in 0..50_000 {
+///     archived.update(i);
+///     current.update(50_000 + i);
+/// }
+///
+/// let bridged = hll_to_theta(&archived, 12, DEFAULT_UPDATE_SEED).unwrap();
+/// assert!((bridged.estimate() - archived.estimate()).abs() < 1.0);
+///
+/// let mut union = ThetaUnion::builder().build();
+/// union.update(&bridged).unwrap();
+/// union.update(&current).unwrap();
+/// assert!((union.result().estimate() - 100_000.0).abs() < 5_000.0);
+/// ```
+pub fn hll_to_theta(sketch: &HllSketch, lg_k: u8, seed: u64) -> Result<CompactThetaSketch, Error> {
+    if sketch.is_empty() {
+        return synthesize(0.0, &[], lg_k, seed);
+    }
+    synthesize(sketch.estimate(), &sketch.serialize(), lg_k, seed)
+}
+
+/// Converts a CPC sketch to an approximate compact theta sketch with up to `2^lg_k` entries.
+///
+/// As with [`hll_to_theta`], the entries of the result are synthetic and only its estimate
+/// carries over; see there for the set operations that remain meaningful.
+///
+/// # Errors
+///
+/// Returns an error if `lg_k` is not in `[5, 26]`, or if the 16-bit hash of `seed` is zero.
+pub fn cpc_to_theta(sketch: &CpcSketch, lg_k: u8, seed: u64) -> Result<CompactThetaSketch, Error> {
+    if sketch.is_empty() {
+        return synthesize(0.0, &[], lg_k, seed);
+    }
+    synthesize(sketch.estimate(), &sketch.serialize(), lg_k, seed)
+}
+
+/// Builds a compact sketch of random entries whose estimate is `estimate`, seeding the random
+/// source with a hash of `fingerprint` so that the same source always converts the same way.
+fn synthesize(
+    estimate: f64,
+    fingerprint: &[u8],
+    lg_k: u8,
+    seed: u64,
+) -> Result<CompactThetaSketch, Error> {
+    if !(MIN_LG_K..=MAX_LG_K).contains(&lg_k) {
+        return Err(Error::invalid_argument(format!(
+            "lg_k must be in [{MIN_LG_K}, {MAX_LG_K}], got {lg_k}"
+        )));
+    }
+    let seed_hash = try_compute_seed_hash(seed)?;
+    let count = estimate.round() as u64;
+    if count == 0 {
+        let empty = fingerprint.is_empty();
+        return Ok(CompactThetaSketch::from_parts(
+            vec![],
+            MAX_THETA,
+            seed_hash,
+            true,
+            empty,
+        ));
+    }
+
+    let k = 1u64 << lg_k;
+    let (num_entries, theta) = if count <= k {
+        (count as usize, MAX_THETA)
+    } else {
+        (
+            k as usize,
+            (MAX_THETA as f64 * (k as f64 / estimate)) as u64,
+        )
+    };
+    let mut random = RandomSource::with_seed(murmur3_128(fingerprint, seed).0);
+    let mut entries = Vec::with_capacity(num_entries);
+    while entries.len() < num_entries {
+        while entries.len() < num_entries {
+            // hashes are non-zero and below theta
+            let hash = 1 + random.next_u64() % (theta - 1);
+            entries.push(hash);
+        }
+        entries.sort_unstable();
+        entries.dedup();
+    }
+    Ok(CompactThetaSketch::from_parts(
+        entries, theta, seed_hash, true, false,
+    ))
+}
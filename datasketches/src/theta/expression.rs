@@ -0,0 +1,210 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::borrow::Cow;
+
+use crate::error::Error;
+use crate::theta::CompactThetaSketch;
+use crate::theta::ThetaANotB;
+use crate::theta::ThetaIntersection;
+use crate::theta::ThetaUnion;
+use crate::theta::ThetaUnionBuilder;
+
+/// A set expression over compact theta sketches, evaluated as a whole.
+///
+/// Expressions are built left to right from [`of`](Self::of) with [`union`](Self::union),
+/// [`intersect`](Self::intersect) and [`a_not_b`](Self::a_not_b), each of which accepts a sketch
+/// or another expression. Evaluation plans the order of the operations:
+///
+/// * chained unions and chained intersections are flattened into a single operator each, so no
+///   intermediate sketch is materialized between them;
+/// * the operands of an intersection are evaluated from the one that can retain the fewest entries
+///   up, and evaluation stops as soon as the intersection retains nothing, skipping the remaining
+///   operands, however expensive;
+/// * the right operand of a set difference is not evaluated when the left one is empty.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::theta::SetExpression;
+/// # use datasketches::theta::ThetaSketch;
+/// let segment = |range: std::ops::Range<u64>| {
+///     let mut sketch = ThetaSketch::builder().build();
+///     for i in range {
+///         sketch.update(i);
+///     }
+///     sketch.compact(true)
+/// };
+/// let (sports, news, music, churned) = (
+///     segment(0..600),
+///     segment(400..1000),
+///     segment(500..700),
+///     segment(550..600),
+/// );
+///
+/// // (sports ∪ news) ∩ music \ churned
+/// let estimate = SetExpression::of(&sports)
+///     .union(&news)
+///     .intersect(&music)
+///     .a_not_b(&churned)
+///     .estimate()
+///     .unwrap();
+/// assert_eq!(estimate, 150.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SetExpression<'a> {
+    node: Node<'a>,
+}
+
+#[derive(Debug, Clone)]
+enum Node<'a> {
+    Sketch(&'a CompactThetaSketch),
+    Union(Vec<Node<'a>>),
+    Intersection(Vec<Node<'a>>),
+    ANotB(Box<Node<'a>>, Box<Node<'a>>),
+}
+
+impl<'a> From<&'a CompactThetaSketch> for SetExpression<'a> {
+    fn from(sketch: &'a CompactThetaSketch) -> Self {
+        SetExpression::of(sketch)
+    }
+}
+
+impl<'a> SetExpression<'a> {
+    /// Creates an expression that evaluates to the given sketch.
+    pub fn of(sketch: &'a CompactThetaSketch) -> Self {
+        SetExpression {
+            node: Node::Sketch(sketch),
+        }
+    }
+
+    /// Returns the union of this expression and `other`.
+    pub fn union(self, other: impl Into<SetExpression<'a>>) -> Self {
+        let mut operands = match self.node {
+            Node::Union(operands) => operands,
+            node => vec![node],
+        };
+        match other.into().node {
+            Node::Union(other) => operands.extend(other),
+            node => operands.push(node),
+        }
+        SetExpression {
+            node: Node::Union(operands),
+        }
+    }
+
+    /// Returns the intersection of this expression and `other`.
+    pub fn intersect(self, other: impl Into<SetExpression<'a>>) -> Self {
+        let mut operands = match self.node {
+            Node::Intersection(operands) => operands,
+            node => vec![node],
+        };
+        match other.into().node {
+            Node::Intersection(other) => operands.extend(other),
+            node => operands.push(node),
+        }
+        SetExpression {
+            node: Node::Intersection(operands),
+        }
+    }
+
+    /// Returns the entries of this expression that are not in `other`.
+    pub fn a_not_b(self, other: impl Into<SetExpression<'a>>) -> Self {
+        SetExpression {
+            node: Node::ANotB(Box::new(self.node), Box::new(other.into().node)),
+        }
+    }
+
+    /// Evaluates the expression over sketches built with the default seed, with unions of the
+    /// default nominal entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a sketch was built with another seed.
+    pub fn evaluate(&self) -> Result<CompactThetaSketch, Error> {
+        self.evaluate_with(&ThetaUnion::builder())
+    }
+
+    /// Evaluates the expression, configuring every union like `union`; the seed of `union` also
+    /// applies to intersections and set differences.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the seed of `union` is invalid or a sketch was built with another
+    /// seed.
+    pub fn evaluate_with(&self, union: &ThetaUnionBuilder) -> Result<CompactThetaSketch, Error> {
+        Ok(self.node.evaluate(union)?.into_owned())
+    }
+
+    /// Evaluates the expression with [`evaluate`](Self::evaluate) and returns the estimate of
+    /// the result.
+    ///
+    /// # Errors
+    ///
+    /// See [`evaluate`](Self::evaluate).
+    pub fn estimate(&self) -> Result<f64, Error> {
+        Ok(self.evaluate()?.estimate())
+    }
+}
+
+impl<'a> Node<'a> {
+    /// Returns the most entries the node can evaluate to.
+    fn max_retained(&self) -> usize {
+        match self {
+            Node::Sketch(sketch) => sketch.num_retained(),
+            Node::Union(operands) => operands.iter().map(Node::max_retained).sum(),
+            Node::Intersection(operands) => {
+                operands.iter().map(Node::max_retained).min().unwrap_or(0)
+            }
+            Node::ANotB(a, _) => a.max_retained(),
+        }
+    }
+
+    fn evaluate(&self, builder: &ThetaUnionBuilder) -> Result<Cow<'a, CompactThetaSketch>, Error> {
+        match self {
+            Node::Sketch(sketch) => Ok(Cow::Borrowed(*sketch)),
+            Node::Union(operands) => {
+                let mut union = builder.clone().try_build()?;
+                for operand in operands {
+                    union.update(operand.evaluate(builder)?.as_ref())?;
+                }
+                Ok(Cow::Owned(union.result()))
+            }
+            Node::Intersection(operands) => {
+                let mut operands: Vec<&Node<'a>> = operands.iter().collect();
+                operands.sort_by_key(|operand| operand.max_retained());
+                let mut intersection = ThetaIntersection::new(builder.seed);
+                for operand in operands {
+                    if intersection.is_exhausted() {
+                        break;
+                    }
+                    intersection.update(operand.evaluate(builder)?.as_ref())?;
+                }
+                Ok(Cow::Owned(intersection.result()))
+            }
+            Node::ANotB(a, b) => {
+                let a = a.evaluate(builder)?;
+                if a.is_empty() {
+                    return Ok(a);
+                }
+                let b = b.evaluate(builder)?;
+                let a_not_b = ThetaANotB::new(builder.seed);
+                Ok(Cow::Owned(a_not_b.compute(a.as_ref(), b.as_ref(), true)?))
+            }
+        }
+    }
+}
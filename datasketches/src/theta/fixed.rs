@@ -0,0 +1,166 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::hash::Hash;
+
+use crate::common::ResizeFactor;
+use crate::common::Sketch;
+use crate::hash::DEFAULT_UPDATE_SEED;
+use crate::theta::CompactThetaSketch;
+use crate::theta::MAX_LG_K;
+use crate::theta::MIN_LG_K;
+use crate::theta::ThetaSketch;
+use crate::theta::ThetaSketchView;
+use crate::theta::ThetaUpdateOutcome;
+
+/// A [`ThetaSketch`] whose nominal size k = 2^`LG_K` is fixed at compile time.
+///
+/// `LG_K` is checked when the sketch type is instantiated, so an out-of-range value is a compile
+/// error rather than a panic. The hash table is allocated at its final size of 2^(`LG_K` + 1)
+/// slots on construction, as with [`ResizeFactor::X1`], so updates never resize it: the memory
+/// footprint is known up front and stays constant, at the price of allocating the full table
+/// for sketches that end up seeing few items.
+///
+/// Queries go through [`as_sketch`](Self::as_sketch), and the sketch can be fed to
+/// [`ThetaUnion`](crate::theta::ThetaUnion) and
+/// [`ThetaIntersection`](crate::theta::ThetaIntersection) directly.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::theta::FixedThetaSketch;
+/// let mut sketch = FixedThetaSketch::<10>::new();
+/// for i in 0..10_000 {
+///     sketch.update(i);
+/// }
+/// assert_eq!(sketch.as_sketch().table_size(), 2048);
+/// assert!((sketch.estimate() - 10_000.0).abs() < 1_000.0);
+/// ```
+///
+/// ```compile_fail
+/// # use datasketches::theta::FixedThetaSketch;
+/// // lg_k must be in [5, 26]
+/// let sketch = FixedThetaSketch::<4>::new();
+/// ```
+#[derive(Debug)]
+pub struct FixedThetaSketch<const LG_K: u8> {
+    sketch: ThetaSketch,
+}
+
+impl<const LG_K: u8> Default for FixedThetaSketch<LG_K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const LG_K: u8> FixedThetaSketch<LG_K> {
+    /// The nominal number of entries k.
+    pub const K: usize = {
+        assert!(
+            LG_K >= MIN_LG_K && LG_K <= MAX_LG_K,
+            "LG_K must be in [5, 26]"
+        );
+        1 << LG_K
+    };
+
+    /// Creates an empty sketch with the default seed.
+    pub fn new() -> Self {
+        Self::with_seed(DEFAULT_UPDATE_SEED)
+    }
+
+    /// Creates an empty sketch that hashes its input with the given seed.
+    pub fn with_seed(seed: u64) -> Self {
+        // evaluating K rejects an out-of-range LG_K at compile time
+        let _ = Self::K;
+        let sketch = ThetaSketch::builder()
+            .lg_k(LG_K)
+            .resize_factor(ResizeFactor::X1)
+            .seed(seed)
+            .build();
+        FixedThetaSketch { sketch }
+    }
+
+    /// Updates the sketch with a hashable value.
+    ///
+    /// See [`ThetaSketch::update`] for the returned outcome.
+    pub fn update<T: Hash>(&mut self, value: T) -> ThetaUpdateOutcome {
+        self.sketch.update(value)
+    }
+
+    /// Returns the cardinality estimate.
+    pub fn estimate(&self) -> f64 {
+        self.sketch.estimate()
+    }
+
+    /// Returns the underlying sketch, for queries and serialization.
+    pub fn as_sketch(&self) -> &ThetaSketch {
+        &self.sketch
+    }
+
+    /// Consumes the wrapper and returns the underlying sketch.
+    pub fn into_sketch(self) -> ThetaSketch {
+        self.sketch
+    }
+
+    /// Returns a compact copy of the sketch; see [`ThetaSketch::compact`].
+    pub fn compact(&self, ordered: bool) -> CompactThetaSketch {
+        self.sketch.compact(ordered)
+    }
+
+    /// Resets the sketch to the empty state, keeping the allocated table.
+    pub fn reset(&mut self) {
+        self.sketch.reset();
+    }
+}
+
+impl<const LG_K: u8> From<FixedThetaSketch<LG_K>> for ThetaSketch {
+    fn from(sketch: FixedThetaSketch<LG_K>) -> Self {
+        sketch.into_sketch()
+    }
+}
+
+impl<const LG_K: u8> ThetaSketchView for FixedThetaSketch<LG_K> {
+    fn seed_hash(&self) -> u16 {
+        self.sketch.seed_hash()
+    }
+
+    fn theta64(&self) -> u64 {
+        self.sketch.theta64()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.sketch.is_empty()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        self.sketch.iter()
+    }
+
+    fn num_retained(&self) -> usize {
+        self.sketch.num_retained()
+    }
+}
+
+impl<const LG_K: u8, I: Hash> Sketch<I> for FixedThetaSketch<LG_K> {
+    fn update(&mut self, item: I) {
+        FixedThetaSketch::update(self, item);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.sketch.is_empty()
+    }
+}
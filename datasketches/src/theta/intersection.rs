@@ -61,6 +61,11 @@ impl ThetaIntersection {
     /// The intersection can be viewed as starting from the "universe" set,
     /// and every update can reduce the current set to leave the overlapping
     /// subset only.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sketch is not empty and its seed hash does not
+    /// match the seed of the intersection, or if the sketch is corrupted.
     pub fn update<S: ThetaSketchView>(&mut self, sketch: &S) -> Result<(), Error> {
         let new_default_table = |table: &ThetaHashTable| {
             ThetaHashTable::from_raw_parts(
@@ -74,16 +79,17 @@ impl ThetaIntersection {
             )
         };
 
-        if self.table.is_empty() {
-            return Ok(());
-        }
-
+        // Check the seed hash before the terminal-empty shortcut so that a
+        // sketch built with a different seed is always reported.
         if !sketch.is_empty() && sketch.seed_hash() != self.table.seed_hash() {
-            return Err(Error::invalid_argument(format!(
-                "incompatible seed hash: expected {}, got {}",
+            return Err(Error::seed_hash_mismatch(
                 self.table.seed_hash(),
-                sketch.seed_hash()
-            )));
+                sketch.seed_hash(),
+            ));
+        }
+
+        if self.table.is_empty() {
+            return Ok(());
         }
 
         if sketch.is_empty() {
@@ -195,6 +201,60 @@ impl ThetaIntersection {
         Ok(())
     }
 
+    /// Updates the intersection with every sketch of the given sequence, stopping as soon as
+    /// the intersection retains no entries.
+    ///
+    /// Once no entries are retained after the first update, the estimate of the result is 0
+    /// whatever the remaining sketches hold, so they are not read at all, which keeps chains of
+    /// intersections over disjoint data cheap. The theta of the result is then the minimum over
+    /// the sketches read so far rather than over all of them; its upper bound still covers the
+    /// intersection of all the sketches, which is a subset of the intersection of the ones read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a sketch that is read is not empty and its seed hash does not match
+    /// the seed of the intersection, or if it is corrupted. The sketches preceding the failing
+    /// one remain intersected. Sketches after the early stop are not checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaIntersection;
+    /// # use datasketches::theta::ThetaSketch;
+    /// let sketches: Vec<_> = (0..3)
+    ///     .map(|p| {
+    ///         let mut sketch = ThetaSketch::builder().build();
+    ///         for i in 0..100 {
+    ///             sketch.update(p * 1_000 + i);
+    ///         }
+    ///         sketch.compact(true)
+    ///     })
+    ///     .collect();
+    ///
+    /// let mut intersection = ThetaIntersection::new_with_default_seed();
+    /// intersection.intersect_all(&sketches).unwrap();
+    /// assert_eq!(intersection.result().estimate(), 0.0);
+    /// ```
+    pub fn intersect_all<'a, S, I>(&mut self, sketches: I) -> Result<(), Error>
+    where
+        S: ThetaSketchView + 'a,
+        I: IntoIterator<Item = &'a S>,
+    {
+        for sketch in sketches {
+            if self.is_exhausted() {
+                break;
+            }
+            self.update(sketch)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if the intersection retains nothing, so that further updates cannot
+    /// change its estimate.
+    pub(crate) fn is_exhausted(&self) -> bool {
+        self.is_valid && self.table.num_retained() == 0
+    }
+
     /// Returns whether this operator has received at least one update.
     pub fn has_result(&self) -> bool {
         self.is_valid
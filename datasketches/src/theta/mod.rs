@@ -29,6 +29,14 @@
 //!
 //! * **ThetaSketch**: Mutable sketch for building from input data
 //! * **CompactThetaSketch**: Immutable sketch with compact memory layout
+//! * **ThetaUnion**: Union operator that also reports statistics over its inputs
+//! * **ThetaIntersection**: Intersection operator
+//! * **ThetaANotB**: Set difference operator
+//! * **SetExpression**: Set expressions over compact sketches, evaluated in a planned order
+//! * **WrappedCompactThetaSketch**: Read-only view that queries a serialized compact sketch in
+//!   place
+//! * **hll_to_theta** and **cpc_to_theta**: Approximate conversions of HLL and CPC sketches, so
+//!   partitions stored in those families can join theta unions
 //!
 //! # Usage
 //!
@@ -39,26 +47,52 @@
 //! assert!(sketch.estimate() >= 1.0);
 //! ```
 
-mod bit_pack;
+mod a_not_b;
+mod bridge;
+mod expression;
+mod fixed;
 mod hash_table;
 mod intersection;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod serialization;
 mod sketch;
+mod union;
+mod wrapper;
 
+pub use self::a_not_b::ThetaANotB;
+pub use self::bridge::cpc_to_theta;
+pub use self::bridge::hll_to_theta;
+pub use self::expression::SetExpression;
+pub use self::fixed::FixedThetaSketch;
 pub use self::intersection::ThetaIntersection;
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub use self::parallel::parallel_union;
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub use self::parallel::parallel_union_with;
+pub(crate) use self::serialization::inspect_compact;
+pub(crate) use self::serialization::inspect_update;
+pub use self::sketch::CompactFormat;
 pub use self::sketch::CompactThetaSketch;
 pub use self::sketch::ThetaSketch;
 pub use self::sketch::ThetaSketchBuilder;
 pub use self::sketch::ThetaSketchView;
+pub use self::sketch::ThetaUpdateOutcome;
+pub use self::union::ThetaUnion;
+pub use self::union::ThetaUnionBuilder;
+pub use self::union::ThetaUnionStats;
+pub use self::wrapper::WrappedCompactThetaSketch;
 
 /// Maximum theta value (signed max for compatibility with Java)
 const MAX_THETA: u64 = i64::MAX as u64;
 /// Minimum log2 of K
-const MIN_LG_K: u8 = 5;
+pub(crate) const MIN_LG_K: u8 = 5;
 /// Maximum log2 of K
-const MAX_LG_K: u8 = 26;
+pub(crate) const MAX_LG_K: u8 = 26;
 /// Default log2 of K
-const DEFAULT_LG_K: u8 = 12;
+pub(crate) const DEFAULT_LG_K: u8 = 12;
 /// Resize threshold (0.5 = 50% load factor)
 const HASH_TABLE_RESIZE_THRESHOLD: f64 = 0.5;
 /// Rebuild threshold (15/16 = 93.75% load factor)
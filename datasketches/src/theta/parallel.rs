@@ -0,0 +1,82 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use rayon::prelude::*;
+
+use crate::error::Error;
+use crate::theta::CompactThetaSketch;
+use crate::theta::ThetaUnion;
+use crate::theta::ThetaUnionBuilder;
+
+/// Unions compact theta sketches in parallel with a default [`ThetaUnion`].
+///
+/// See [`parallel_union_with`] for details.
+///
+/// # Errors
+///
+/// If a non-empty sketch was built with a seed other than the default one.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::theta::ThetaSketch;
+/// # use datasketches::theta::parallel_union;
+/// let sketches: Vec<_> = (0..100)
+///     .map(|i| {
+///         let mut sketch = ThetaSketch::builder().build();
+///         sketch.update(i);
+///         sketch.compact(true)
+///     })
+///     .collect();
+/// let result = parallel_union(&sketches).unwrap();
+/// assert_eq!(result.estimate(), 100.0);
+/// ```
+pub fn parallel_union(sketches: &[CompactThetaSketch]) -> Result<CompactThetaSketch, Error> {
+    parallel_union_with(ThetaUnion::builder(), sketches)
+}
+
+/// Unions compact theta sketches in parallel on the rayon thread pool.
+///
+/// Every worker folds a share of the input into its own union configured by `builder`, and the
+/// partial results are merged pairwise as a tree. The result is the same as feeding all sketches
+/// to a single union configured by `builder`, up to the estimation error of the sketches.
+///
+/// # Errors
+///
+/// If a non-empty sketch was built with a seed other than the one configured in `builder`.
+pub fn parallel_union_with(
+    builder: ThetaUnionBuilder,
+    sketches: &[CompactThetaSketch],
+) -> Result<CompactThetaSketch, Error> {
+    let union = sketches
+        .par_iter()
+        .try_fold(
+            || builder.clone().build(),
+            |mut union, sketch| {
+                union.update(sketch)?;
+                Ok::<_, Error>(union)
+            },
+        )
+        .try_reduce(
+            || builder.clone().build(),
+            |mut left, right| {
+                left.update(&right.result())?;
+                Ok(left)
+            },
+        )?;
+    Ok(union.result())
+}
@@ -15,16 +15,222 @@
 // specific language governing permissions and limitations
 // under the License.
 
-//! Binary serialization format constants for Theta sketches.
+//! Binary serialization format constants and image layout for Theta sketches.
+
+use crate::codec::SketchSlice;
+use crate::codec::assert::ensure_serial_version_is;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
+use crate::error::Error;
+use crate::inspect::SketchInfo;
+use crate::theta::MAX_LG_K;
+use crate::theta::MAX_THETA;
+use crate::theta::MIN_LG_K;
 
 pub(super) const UNCOMPRESSED_SERIAL_VERSION: u8 = 3;
 pub(super) const COMPRESSED_SERIAL_VERSION: u8 = 4;
 
+/// Preamble longs of the updatable (QuickSelect) format.
+pub(super) const UPDATE_PREAMBLE_LONGS: u8 = 3;
+
 pub(super) const V2_PREAMBLE_EMPTY: u8 = 1;
 pub(super) const V2_PREAMBLE_PRECISE: u8 = 2;
 pub(super) const V2_PREAMBLE_ESTIMATE: u8 = 3;
 
+pub(super) const FLAGS_IS_BIG_ENDIAN: u8 = 1 << 0;
 pub(super) const FLAGS_IS_READ_ONLY: u8 = 1 << 1;
 pub(super) const FLAGS_IS_EMPTY: u8 = 1 << 2;
 pub(super) const FLAGS_IS_COMPACT: u8 = 1 << 3;
 pub(super) const FLAGS_IS_ORDERED: u8 = 1 << 4;
+
+/// Validates a theta read from a serialized image.
+pub(super) fn check_theta(theta: u64) -> Result<u64, Error> {
+    if theta == 0 || theta > MAX_THETA {
+        return Err(Error::bad_preamble(format!(
+            "theta must be in [1, {MAX_THETA}], got {theta}"
+        )));
+    }
+    Ok(theta)
+}
+
+/// Returns the length of the preamble of an updatable image from its first 8 bytes.
+pub(super) fn update_preamble_len(_head: &[u8]) -> usize {
+    UPDATE_PREAMBLE_LONGS as usize * 8
+}
+
+/// Returns the length of an updatable image from its preamble.
+pub(super) fn update_image_len(preamble: &[u8], max_lg_cur_size: u8) -> usize {
+    let lg_cur_size = preamble[4];
+    if lg_cur_size > max_lg_cur_size {
+        return preamble.len();
+    }
+    preamble.len() + (8usize << lg_cur_size)
+}
+
+/// Returns the length of the preamble of a compact image from its first 8 bytes.
+pub(super) fn compact_preamble_len(head: &[u8]) -> usize {
+    let pre_longs = head[0] as usize;
+    if !(1..=3).contains(&pre_longs) {
+        return head.len();
+    }
+    match head[1] {
+        // serial version 1 always has 3 preamble longs
+        1 => 24,
+        // followed by 1 to 4 bytes of the number of entries
+        COMPRESSED_SERIAL_VERSION => pre_longs * 8 + head[4].min(4) as usize,
+        _ => pre_longs * 8,
+    }
+}
+
+/// Returns the length of a compact image from its preamble.
+pub(super) fn compact_image_len(preamble: &[u8]) -> usize {
+    let len = preamble.len();
+    let pre_longs = preamble[0] as usize;
+    let num_entries = || match preamble.get(8..12) {
+        Some(bytes) => u32::from_le_bytes(bytes.try_into().unwrap()) as usize,
+        None => 0,
+    };
+    match preamble[1] {
+        1 => len + num_entries().saturating_mul(8),
+        2 if pre_longs == V2_PREAMBLE_EMPTY as usize => len,
+        2 => len + num_entries().saturating_mul(8),
+        UNCOMPRESSED_SERIAL_VERSION if preamble[5] & FLAGS_IS_EMPTY != 0 => len,
+        // a single entry without the number of entries
+        UNCOMPRESSED_SERIAL_VERSION if pre_longs == 1 => len + 8,
+        UNCOMPRESSED_SERIAL_VERSION => len + num_entries().saturating_mul(8),
+        COMPRESSED_SERIAL_VERSION => {
+            let entry_bits = preamble[3] as usize;
+            let num_entries = preamble
+                .get(pre_longs * 8..)
+                .unwrap_or_default()
+                .iter()
+                .rev()
+                .fold(0usize, |n, &byte| (n << 8) | byte as usize);
+            len + num_entries.saturating_mul(entry_bits).div_ceil(8)
+        }
+        _ => len,
+    }
+}
+
+/// Reads the preamble of an updatable image for [`inspect`](crate::inspect).
+pub(crate) fn inspect_update(
+    _pre_longs: u8,
+    ser_ver: u8,
+    mut cursor: SketchSlice<'_>,
+) -> Result<SketchInfo, Error> {
+    ensure_serial_version_is(UNCOMPRESSED_SERIAL_VERSION, ser_ver)?;
+    let lg_nom_size = cursor
+        .read_u8()
+        .map_err(insufficient_data("lg_nom_longs"))?;
+    if !(MIN_LG_K..=MAX_LG_K).contains(&lg_nom_size) {
+        return Err(Error::deserial(format!(
+            "lg_nom_longs must be in [{MIN_LG_K}, {MAX_LG_K}], got {lg_nom_size}"
+        )));
+    }
+    cursor
+        .read_u8()
+        .map_err(insufficient_data("lg_arr_longs"))?;
+    let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+    cursor
+        .read_u16_le()
+        .map_err(insufficient_data("seed_hash"))?;
+    let num_entries = cursor
+        .read_u32_le()
+        .map_err(insufficient_data("num_entries"))?;
+    cursor.read_f32_le().map_err(insufficient_data("p"))?;
+    let theta = cursor
+        .read_u64_le()
+        .map_err(insufficient_data("theta_long"))?;
+
+    let mut info = SketchInfo::new(&Family::QUICKSELECT, ser_ver);
+    info.lg_k = Some(lg_nom_size);
+    info.is_empty = flags & FLAGS_IS_EMPTY != 0;
+    info.is_estimation_mode = Some(theta < MAX_THETA);
+    info.num_retained = Some(num_entries as u64);
+    Ok(info)
+}
+
+/// Reads the preamble of a compact image of any serial version for [`inspect`](crate::inspect).
+pub(crate) fn inspect_compact(
+    pre_longs: u8,
+    ser_ver: u8,
+    mut cursor: SketchSlice<'_>,
+) -> Result<SketchInfo, Error> {
+    let pre_longs = pre_longs & 0x3F;
+    let mut theta = MAX_THETA;
+    let mut num_entries = 0;
+    let mut empty = None;
+    match ser_ver {
+        1 => {
+            cursor.advance(5);
+            num_entries = cursor
+                .read_u32_le()
+                .map_err(insufficient_data("num_entries"))?;
+            cursor.advance(4);
+            theta = cursor
+                .read_u64_le()
+                .map_err(insufficient_data("theta_long"))?;
+        }
+        2 | UNCOMPRESSED_SERIAL_VERSION => {
+            cursor.advance(2);
+            let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+            cursor
+                .read_u16_le()
+                .map_err(insufficient_data("seed_hash"))?;
+            if ser_ver == UNCOMPRESSED_SERIAL_VERSION {
+                empty = Some(flags & FLAGS_IS_EMPTY != 0);
+            }
+            if ser_ver == UNCOMPRESSED_SERIAL_VERSION && pre_longs == 1 {
+                num_entries = u32::from(empty == Some(false));
+            } else if pre_longs > 1 {
+                num_entries = cursor
+                    .read_u32_le()
+                    .map_err(insufficient_data("num_entries"))?;
+                cursor.advance(4);
+                if pre_longs > 2 {
+                    theta = cursor
+                        .read_u64_le()
+                        .map_err(insufficient_data("theta_long"))?;
+                }
+            }
+        }
+        COMPRESSED_SERIAL_VERSION => {
+            cursor.read_u8().map_err(insufficient_data("entry_bits"))?;
+            let num_entries_bytes = cursor.read_u8().map_err(insufficient_data("num_entries"))?;
+            let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+            cursor
+                .read_u16_le()
+                .map_err(insufficient_data("seed_hash"))?;
+            empty = Some(flags & FLAGS_IS_EMPTY != 0);
+            if pre_longs > 1 {
+                theta = cursor
+                    .read_u64_le()
+                    .map_err(insufficient_data("theta_long"))?;
+            }
+            if num_entries_bytes > 4 {
+                return Err(Error::bad_preamble(format!(
+                    "num_entries_bytes must be at most 4, got {num_entries_bytes}"
+                )));
+            }
+            for i in 0..num_entries_bytes {
+                let byte = cursor
+                    .read_u8()
+                    .map_err(insufficient_data("num_entries_byte"))?;
+                num_entries |= (byte as u32) << (i * 8);
+            }
+        }
+        _ => {
+            return Err(Error::unsupported_version(format!(
+                "unsupported serial version: expected 1, 2, 3, or 4, got {ser_ver}",
+            )));
+        }
+    }
+    let theta = check_theta(theta)?;
+
+    let mut info = SketchInfo::new(&Family::THETA, ser_ver);
+    // older serial versions have no empty flag
+    info.is_empty = empty.unwrap_or(num_entries == 0 && theta == MAX_THETA);
+    info.is_estimation_mode = Some(theta < MAX_THETA);
+    info.num_retained = Some(num_entries as u64);
+    Ok(info)
+}
@@ -20,34 +20,54 @@
 //! This module provides ThetaSketch (mutable) and CompactThetaSketch (immutable)
 //! for cardinality estimation.
 
+use std::fmt;
 use std::hash::Hash;
+use std::io;
+use std::io::Read;
+use std::io::Write;
 
+use crate::Config;
 use crate::codec::SketchBytes;
 use crate::codec::SketchSlice;
+use crate::codec::assert::ensure_preamble_longs_in;
 use crate::codec::assert::ensure_preamble_longs_in_range;
+use crate::codec::assert::ensure_serial_version_is;
 use crate::codec::assert::insufficient_data;
 use crate::codec::family::Family;
+use crate::codec::read_image;
+use crate::codec::serialize_into_slice;
+use crate::codec::serialize_into_writer;
+use crate::common::CardinalityFamily;
+use crate::common::EstimateSummary;
+use crate::common::IntoSketchInput;
+use crate::common::MergeableSketch;
 use crate::common::NumStdDev;
+use crate::common::RawBytes;
 use crate::common::ResizeFactor;
+use crate::common::SerializableSketch;
+use crate::common::Sketch;
+use crate::common::SketchInput;
+use crate::common::Summary;
 use crate::common::binomial_bounds;
 use crate::common::canonical_double;
+use crate::common::estimates_within;
 use crate::error::Error;
 use crate::hash::DEFAULT_UPDATE_SEED;
 use crate::hash::compute_seed_hash;
-use crate::theta::DEFAULT_LG_K;
+use crate::hash::try_compute_seed_hash;
 use crate::theta::MAX_LG_K;
 use crate::theta::MAX_THETA;
 use crate::theta::MIN_LG_K;
-use crate::theta::bit_pack::BLOCK_WIDTH;
-use crate::theta::bit_pack::BitPacker;
-use crate::theta::bit_pack::BitUnpacker;
-use crate::theta::bit_pack::pack_bits_block;
-use crate::theta::bit_pack::unpack_bits_block;
 use crate::theta::hash_table::ThetaHashTable;
 use crate::theta::serialization;
 use crate::theta::serialization::V2_PREAMBLE_EMPTY;
 use crate::theta::serialization::V2_PREAMBLE_ESTIMATE;
 use crate::theta::serialization::V2_PREAMBLE_PRECISE;
+use crate::util::bitpack::BLOCK_WIDTH;
+use crate::util::bitpack::BitPacker;
+use crate::util::bitpack::BitUnpacker;
+use crate::util::bitpack::pack_bits_block;
+use crate::util::bitpack::unpack_bits_block;
 
 mod private {
     use super::*;
@@ -56,7 +76,9 @@ mod private {
     pub trait Sealed {}
 
     impl Sealed for ThetaSketch {}
+    impl<const LG_K: u8> Sealed for crate::theta::FixedThetaSketch<LG_K> {}
     impl Sealed for CompactThetaSketch {}
+    impl Sealed for crate::theta::WrappedCompactThetaSketch<'_> {}
 }
 
 /// Read-only view for Theta sketches.
@@ -74,7 +96,7 @@ pub trait ThetaSketchView: private::Sealed {
     fn is_empty(&self) -> bool;
 
     /// Returns an iterator over retained hash values.
-    fn iter<'a>(&'a self) -> impl Iterator<Item = u64> + 'a;
+    fn iter(&self) -> impl Iterator<Item = u64> + '_;
 
     /// Returns number of retained hash values.
     fn num_retained(&self) -> usize;
@@ -85,10 +107,39 @@ pub trait ThetaSketchView: private::Sealed {
     }
 }
 
+/// Outcome of updating a [`ThetaSketch`] with a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThetaUpdateOutcome {
+    /// The hash of the value was below theta and is now retained.
+    InsertedNew,
+    /// The hash of the value was below theta and was already retained.
+    Duplicate,
+    /// The hash of the value was at or above theta and was discarded.
+    ///
+    /// The sketch is still marked as non-empty, since a value was presented to it.
+    RejectedByTheta,
+}
+
+/// Layout of a serialized compact theta sketch, trading serialization speed for size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CompactFormat {
+    /// Hashes as plain 8-byte values (serial version 3), the fastest to write and to read.
+    #[default]
+    Uncompressed,
+    /// Hashes delta-encoded and bit-packed (serial version 4), typically around 30% smaller
+    /// but a few times slower to write and to read. Sketches that cannot be compressed, such
+    /// as empty or single-item ones, fall back to the uncompressed layout.
+    Compressed,
+}
+
 /// Mutable theta sketch for building from input data
 #[derive(Debug)]
 pub struct ThetaSketch {
     table: ThetaHashTable,
+    /// HIP accumulator, `None` unless enabled with [`ThetaSketchBuilder::hip_estimator`] and
+    /// the sketch has seen every update since it was built.
+    hip_accum: Option<f64>,
+    compact_format: CompactFormat,
 }
 
 impl ThetaSketch {
@@ -105,24 +156,67 @@ impl ThetaSketch {
         ThetaSketchBuilder::default()
     }
 
+    /// Creates an empty sketch with the default configuration.
+    ///
+    /// An empty sketch is the identity element of [`ThetaUnion`](crate::theta::ThetaUnion):
+    /// merging it into any union leaves the result unchanged, so it is a natural starting
+    /// value for folds. This is equivalent to `ThetaSketch::builder().build()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let sketch = ThetaSketch::empty();
+    /// assert!(sketch.is_empty());
+    /// assert_eq!(sketch.estimate(), 0.0);
+    /// ```
+    pub fn empty() -> Self {
+        Self::builder().build()
+    }
+
     /// Update the sketch with a hashable value.
     ///
+    /// Returns whether the value's hash was newly retained, was already retained, or was
+    /// screened out by theta. Use [`update_quiet`](Self::update_quiet) when the outcome is
+    /// not needed.
+    ///
     /// For `f32`/`f64` values, use `update_f32`/`update_f64` instead.
     ///
     /// # Examples
     ///
     /// ```
     /// # use datasketches::theta::ThetaSketch;
+    /// # use datasketches::theta::ThetaUpdateOutcome;
     /// let mut sketch = ThetaSketch::builder().build();
-    /// sketch.update("apple");
+    /// assert_eq!(sketch.update("apple"), ThetaUpdateOutcome::InsertedNew);
+    /// assert_eq!(sketch.update("apple"), ThetaUpdateOutcome::Duplicate);
     /// assert!(sketch.estimate() >= 1.0);
     /// ```
-    pub fn update<T: Hash>(&mut self, value: T) {
-        self.table.try_insert(value);
+    pub fn update<T: Hash>(&mut self, value: T) -> ThetaUpdateOutcome {
+        let theta = self.table.theta();
+        let outcome = self.table.insert(value);
+        self.update_hip(theta, outcome);
+        outcome
+    }
+
+    /// Update the sketch with a hashable value, discarding the outcome.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().build();
+    /// sketch.update_quiet("apple");
+    /// assert!(sketch.estimate() >= 1.0);
+    /// ```
+    pub fn update_quiet<T: Hash>(&mut self, value: T) {
+        self.update(value);
     }
 
     /// Update the sketch with a f64 value.
     ///
+    /// See [`update`](Self::update) for the returned outcome.
+    ///
     /// # Examples
     ///
     /// ```
@@ -131,14 +225,16 @@ impl ThetaSketch {
     /// sketch.update_f64(1.0);
     /// assert!(sketch.estimate() >= 1.0);
     /// ```
-    pub fn update_f64(&mut self, value: f64) {
+    pub fn update_f64(&mut self, value: f64) -> ThetaUpdateOutcome {
         // Canonicalize double for compatibility with Java
         let canonical = canonical_double(value);
-        self.update(canonical);
+        self.update(canonical)
     }
 
     /// Update the sketch with a f32 value.
     ///
+    /// See [`update`](Self::update) for the returned outcome.
+    ///
     /// # Examples
     ///
     /// ```
@@ -147,12 +243,106 @@ impl ThetaSketch {
     /// sketch.update_f32(1.0);
     /// assert!(sketch.estimate() >= 1.0);
     /// ```
-    pub fn update_f32(&mut self, value: f32) {
-        self.update_f64(value as f64);
+    pub fn update_f32(&mut self, value: f32) -> ThetaUpdateOutcome {
+        self.update_f64(value as f64)
+    }
+
+    /// Update the sketch with a value hashed like datasketches-java does, see
+    /// [`IntoSketchInput`].
+    ///
+    /// See [`update`](Self::update) for the returned outcome.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().build();
+    /// sketch.update_input("apple");
+    /// sketch.update_input(7i32);
+    /// assert_eq!(sketch.estimate(), 2.0);
+    /// ```
+    pub fn update_input<T: IntoSketchInput>(&mut self, value: T) -> ThetaUpdateOutcome {
+        self.update(SketchInput(value))
+    }
+
+    /// Update the sketch with the raw bytes of a value.
+    ///
+    /// The bytes are hashed as-is, see [`RawBytes`], so the result does not depend on the
+    /// platform. Prefer this over [`update`](Self::update) with a `&[u8]` when sketches built on
+    /// `wasm32` and 64-bit hosts are combined.
+    ///
+    /// See [`update`](Self::update) for the returned outcome.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().build();
+    /// sketch.update_bytes(b"apple");
+    /// assert!(sketch.estimate() >= 1.0);
+    /// ```
+    pub fn update_bytes(&mut self, bytes: &[u8]) -> ThetaUpdateOutcome {
+        self.update(RawBytes(bytes))
+    }
+
+    /// Update the sketch with a pre-computed 128-bit hash.
+    ///
+    /// `hash` must be the result of [`murmur3_128`](crate::hash::murmur3_128) with the seed of
+    /// this sketch, in which case this is equivalent to [`update_bytes`](Self::update_bytes)
+    /// with the same bytes.
+    ///
+    /// See [`update`](Self::update) for the returned outcome.
+    pub fn update_hash(&mut self, hash: (u64, u64)) -> ThetaUpdateOutcome {
+        self.update_prehashed(hash.0)
+    }
+
+    /// Update the sketch with the first 64 bits of a pre-computed hash.
+    ///
+    /// Theta sketches only use the first half `h1` of the 128-bit hash, so callers that feed the
+    /// same record to many sketches sharing a seed can compute
+    /// [`murmur3_128`](crate::hash::murmur3_128) once and pass `h1` to each of them. This is
+    /// equivalent to [`update_hash`](Self::update_hash) with `(h1, _)`.
+    ///
+    /// See [`update`](Self::update) for the returned outcome.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::hash::DEFAULT_UPDATE_SEED;
+    /// # use datasketches::hash::murmur3_128;
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut by_user = ThetaSketch::builder().build();
+    /// let mut by_page = ThetaSketch::builder().lg_k(10).build();
+    /// let (h1, _) = murmur3_128(b"user-42", DEFAULT_UPDATE_SEED);
+    /// by_user.update_prehashed(h1);
+    /// by_page.update_prehashed(h1);
+    /// assert_eq!(by_user.estimate(), by_page.estimate());
+    /// ```
+    pub fn update_prehashed(&mut self, hash: u64) -> ThetaUpdateOutcome {
+        // To make it compatible with Java version
+        let theta = self.table.theta();
+        let outcome = self.table.insert_hash(hash >> 1);
+        self.update_hip(theta, outcome);
+        outcome
+    }
+
+    /// Adds the inverse of the probability with which a new hash was retained, i.e. theta
+    /// before the insertion, to the HIP accumulator.
+    fn update_hip(&mut self, theta: u64, outcome: ThetaUpdateOutcome) {
+        if let Some(hip_accum) = &mut self.hip_accum {
+            if outcome == ThetaUpdateOutcome::InsertedNew {
+                *hip_accum += MAX_THETA as f64 / theta as f64;
+            }
+        }
     }
 
     /// Return cardinality estimate
     ///
+    /// This is the HIP estimate if the sketch was built with
+    /// [`ThetaSketchBuilder::hip_estimator`] and has not been deserialized since, and
+    /// `num_retained / theta` otherwise. Both are maintained as updates arrive, so reading the
+    /// estimate is constant time.
+    ///
     /// # Examples
     ///
     /// ```
@@ -165,11 +355,21 @@ impl ThetaSketch {
         if self.is_empty() {
             return 0.0;
         }
+        if let Some(hip_accum) = self.hip_accum {
+            return hip_accum;
+        }
         let num_retained = self.table.num_retained() as f64;
         let theta = self.table.theta() as f64 / MAX_THETA as f64;
         num_retained / theta
     }
 
+    /// Returns true if [`estimate`](Self::estimate) returns the HIP estimate.
+    ///
+    /// See [`ThetaSketchBuilder::hip_estimator`].
+    pub fn is_hip_estimator(&self) -> bool {
+        self.hip_accum.is_some()
+    }
+
     /// Return theta as a fraction (0.0 to 1.0)
     pub fn theta(&self) -> f64 {
         self.table.theta() as f64 / MAX_THETA as f64
@@ -196,35 +396,230 @@ impl ThetaSketch {
     }
 
     /// Return number of retained entries
+    ///
+    /// This is the equivalent of `getRetainedEntries` in datasketches-java.
     pub fn num_retained(&self) -> usize {
         self.table.num_retained()
     }
 
+    /// Returns true if `other` has the same lg_k and seed hash as this sketch and their
+    /// estimates differ by at most `epsilon` relative to the larger one.
+    ///
+    /// See [`HllSketch::approximately_equal`](crate::hll::HllSketch::approximately_equal).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `epsilon` is negative or NaN.
+    pub fn approximately_equal(&self, other: &ThetaSketch, epsilon: f64) -> bool {
+        self.lg_k() == other.lg_k()
+            && self.seed_hash() == other.seed_hash()
+            && estimates_within(self.estimate(), other.estimate(), epsilon)
+    }
+
+    /// Returns the estimate and the bounds at `num_std_dev` standard deviations.
+    ///
+    /// The number of retained entries is the number of retained hashes.
+    pub fn estimate_summary(&self, num_std_dev: NumStdDev) -> EstimateSummary {
+        EstimateSummary {
+            estimate: self.estimate(),
+            lb: self.lower_bound(num_std_dev),
+            ub: self.upper_bound(num_std_dev),
+            retained: self.num_retained() as u64,
+            family: CardinalityFamily::Theta,
+        }
+    }
+
+    /// Returns the number of bytes of memory the sketch occupies.
+    ///
+    /// This counts the whole hash table, which holds up to twice as many slots as retained
+    /// entries once it stops resizing, so it is the figure to use for memory budgets, unlike
+    /// [`num_retained`](Self::num_retained) or the serialized size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().lg_k(12).build();
+    /// for i in 0..100_000 {
+    ///     sketch.update(i);
+    /// }
+    /// assert!(sketch.memory_usage_bytes() > sketch.num_retained() * 8);
+    /// ```
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>() + self.table.heap_size_bytes()
+    }
+
     /// Return lg_k
     pub fn lg_k(&self) -> u8 {
         self.table.lg_nom_size()
     }
 
+    /// Return the resize factor used to grow the internal hash table.
+    pub fn resize_factor(&self) -> ResizeFactor {
+        self.table.resize_factor()
+    }
+
+    /// Return the number of slots currently allocated in the internal hash table.
+    ///
+    /// Each slot holds one 64-bit hash, so this is the dominant part of the memory footprint
+    /// of the sketch. The table starts small and grows by the configured
+    /// [`ResizeFactor`] until it reaches `2 * k` slots, see
+    /// [`ThetaSketchBuilder::resize_factor`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::common::ResizeFactor;
+    /// # use datasketches::theta::ThetaSketch;
+    /// let sketch = ThetaSketch::builder()
+    ///     .lg_k(12)
+    ///     .resize_factor(ResizeFactor::X1)
+    ///     .build();
+    /// assert_eq!(sketch.table_size(), 2 << 12);
+    /// ```
+    pub fn table_size(&self) -> usize {
+        self.table.num_slots()
+    }
+
+    /// Return the sampling probability p the sketch was built with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let sketch = ThetaSketch::builder().sampling_probability(0.25).build();
+    /// assert_eq!(sketch.sampling_probability(), 0.25);
+    /// ```
+    pub fn sampling_probability(&self) -> f32 {
+        self.table.sampling_probability()
+    }
+
     /// Trim the sketch to nominal size k
     pub fn trim(&mut self) {
         self.table.trim();
     }
 
     /// Reset the sketch to empty state
+    ///
+    /// The hash table shrinks back to its starting size, see
+    /// [`ThetaSketchBuilder::resize_factor`], releasing the memory of a grown table. Use
+    /// [`clear`](Self::clear) to keep it instead.
     pub fn reset(&mut self) {
         self.table.reset();
+        if let Some(hip_accum) = &mut self.hip_accum {
+            *hip_accum = 0.0;
+        }
+    }
+
+    /// Reset the sketch to empty state, keeping the hash table at its current size.
+    ///
+    /// Per-window sketches of a streaming job fill up to the same size every window. Clearing
+    /// them instead of calling [`reset`](Self::reset) or building new ones reuses the table,
+    /// so rotating windows neither reallocates nor rehashes while the table grows again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().lg_k(10).build();
+    /// for window in 0..3 {
+    ///     for i in 0..10_000 {
+    ///         sketch.update(window * 10_000 + i);
+    ///     }
+    ///     assert_eq!(sketch.table_size(), 2 << 10);
+    ///     sketch.clear();
+    ///     assert!(sketch.is_empty());
+    ///     assert_eq!(sketch.table_size(), 2 << 10);
+    /// }
+    /// ```
+    pub fn clear(&mut self) {
+        self.table.clear();
+        if let Some(hip_accum) = &mut self.hip_accum {
+            *hip_accum = 0.0;
+        }
+    }
+
+    /// Returns a copy of this sketch rebuilt with the smaller nominal size `2^lg_k`.
+    ///
+    /// The copy keeps the `2^lg_k` smallest retained hashes and lowers theta accordingly, so it
+    /// is the sketch that would have been built with `lg_k` from the same input. The seed,
+    /// resize factor and sampling probability are preserved. This trades accuracy for a smaller
+    /// footprint, e.g. before shipping large per-node sketches over the network.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lg_k` is less than 5 or greater than the [`lg_k`](Self::lg_k) of this sketch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().lg_k(14).build();
+    /// for i in 0..100_000 {
+    ///     sketch.update(i);
+    /// }
+    ///
+    /// let small = sketch.downsample(10);
+    /// assert_eq!(small.lg_k(), 10);
+    /// assert_eq!(small.num_retained(), 1 << 10);
+    /// assert!(small.theta() < sketch.theta());
+    /// assert!((small.estimate() - 100_000.0).abs() < 10_000.0);
+    /// ```
+    pub fn downsample(&self, lg_k: u8) -> ThetaSketch {
+        assert!(
+            (MIN_LG_K..=self.lg_k()).contains(&lg_k),
+            "lg_k must be in [{MIN_LG_K}, {}], got {lg_k}",
+            self.lg_k()
+        );
+        let mut table = ThetaHashTable::new(
+            lg_k,
+            self.table.resize_factor(),
+            self.table.sampling_probability(),
+            self.table.hash_seed(),
+        );
+        table.set_theta(self.table.theta());
+        table.reserve(self.num_retained());
+        for hash in self.iter() {
+            table.insert_hash(hash);
+        }
+        table.trim();
+        table.set_empty(self.is_empty());
+        ThetaSketch {
+            table,
+            hip_accum: self.hip_accum,
+            compact_format: self.compact_format,
+        }
     }
 
     /// Return iterator over hash values
     ///
+    /// Yields the [`num_retained`](Self::num_retained) retained 64-bit hashes in no particular
+    /// order. Every yielded hash is below [`theta64`](Self::theta64), so a retained set together
+    /// with theta is all that is needed to implement custom set operations or to convert into
+    /// another sketch format. Hashes of sketches built with different seeds are not comparable.
+    ///
     /// # Examples
     ///
+    /// Estimate the size of an intersection by hand:
+    ///
     /// ```
+    /// # use std::collections::HashSet;
     /// # use datasketches::theta::ThetaSketch;
-    /// # let mut sketch = ThetaSketch::builder().build();
-    /// # sketch.update("apple");
-    /// let mut iter = sketch.iter();
-    /// assert!(iter.next().is_some());
+    /// let mut a = ThetaSketch::builder().lg_k(10).build();
+    /// let mut b = ThetaSketch::builder().lg_k(10).build();
+    /// for i in 0..10_000 {
+    ///     a.update(i);
+    ///     b.update(i + 5_000);
+    /// }
+    ///
+    /// let theta64 = a.theta64().min(b.theta64());
+    /// let in_b: HashSet<u64> = b.iter().filter(|&h| h < theta64).collect();
+    /// let common = a
+    ///     .iter()
+    ///     .filter(|h| *h < theta64 && in_b.contains(h))
+    ///     .count();
+    /// let estimate = common as f64 / a.theta().min(b.theta());
+    /// assert!((estimate - 5_000.0).abs() < 1_000.0);
     /// ```
     pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
         self.table.iter()
@@ -265,6 +660,327 @@ impl ThetaSketch {
         CompactThetaSketch::from_parts(entries, theta, self.table.seed_hash(), ordered, empty)
     }
 
+    /// Returns an immutable copy of this sketch for reading while it keeps being updated.
+    ///
+    /// The copy is unordered, so taking it costs a single pass over the retained hashes and
+    /// no sort. This keeps the time spent holding a lock around a live sketch short: take the
+    /// snapshot under the lock, release it, then estimate, order or serialize the copy while
+    /// updates continue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::sync::Mutex;
+    /// # use datasketches::theta::ThetaSketch;
+    /// let live = Mutex::new(ThetaSketch::builder().build());
+    /// live.lock().unwrap().update("apple");
+    ///
+    /// let snapshot = live.lock().unwrap().snapshot();
+    /// live.lock().unwrap().update("banana");
+    /// assert_eq!(snapshot.estimate(), 1.0);
+    /// let bytes = snapshot.into_ordered().serialize();
+    /// # assert!(!bytes.is_empty());
+    /// ```
+    pub fn snapshot(&self) -> CompactThetaSketch {
+        self.compact(false)
+    }
+
+    /// Returns the layout [`serialize_compact`](Self::serialize_compact) writes.
+    ///
+    /// See [`ThetaSketchBuilder::compact_format`].
+    pub fn compact_format(&self) -> CompactFormat {
+        self.compact_format
+    }
+
+    /// Serializes the compact form of this sketch in the layout chosen with
+    /// [`ThetaSketchBuilder::compact_format`].
+    ///
+    /// Read the bytes back with [`CompactThetaSketch::deserialize`]. The uncompressed layout is
+    /// written unordered, which saves sorting the retained hashes.
+    pub fn serialize_compact(&self) -> Vec<u8> {
+        match self.compact_format {
+            CompactFormat::Uncompressed => self.compact(false).serialize(),
+            CompactFormat::Compressed => self.compact(true).serialize_compressed(),
+        }
+    }
+
+    /// Serializes this sketch in the updatable theta format of datasketches-java.
+    ///
+    /// This is the layout of `UpdateSketch.toByteArray()` (serial version 3, QuickSelect family):
+    /// the preamble carries lg_k, the resize factor, p and theta, followed by the whole hash
+    /// table, so [`deserialize`](Self::deserialize) restores a sketch that can keep being
+    /// updated. Use [`compact`](Self::compact) with [`CompactThetaSketch::serialize`] or
+    /// [`CompactThetaSketch::serialize_compressed`] for the smaller, read-only compact formats
+    /// (serial versions 3 and 4).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().build();
+    /// sketch.update("apple");
+    /// let bytes = sketch.serialize();
+    /// let mut decoded = ThetaSketch::deserialize(&bytes).unwrap();
+    /// decoded.update("banana");
+    /// assert_eq!(decoded.estimate(), 2.0);
+    /// ```
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = SketchBytes::with_capacity(self.serialized_size_bytes());
+        self.write_to(&mut bytes);
+        bytes.into_bytes()
+    }
+
+    /// Returns the number of bytes [`ThetaSketch::serialize`] produces.
+    pub fn serialized_size_bytes(&self) -> usize {
+        (serialization::UPDATE_PREAMBLE_LONGS as usize + self.table.slots().len()) * 8
+    }
+
+    /// Serializes this sketch into the front of `buf` without allocating, returning the number of
+    /// bytes written.
+    ///
+    /// The bytes are the same as those returned by [`ThetaSketch::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `buf` is shorter than [`ThetaSketch::serialized_size_bytes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().lg_k(5).build();
+    /// sketch.update("apple");
+    /// let mut buf = vec![0u8; sketch.serialized_size_bytes()];
+    /// let len = sketch.serialize_into(&mut buf).unwrap();
+    /// assert_eq!(&buf[..len], sketch.serialize());
+    /// ```
+    pub fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        serialize_into_slice(buf, self.serialized_size_bytes(), |bytes| {
+            self.write_to(bytes)
+        })
+    }
+
+    fn write_to(&self, bytes: &mut SketchBytes<'_>) {
+        let slots = self.table.slots();
+        let pre_longs = serialization::UPDATE_PREAMBLE_LONGS;
+
+        bytes.write_u8(pre_longs | (self.table.resize_factor().lg_value() << 6));
+        bytes.write_u8(serialization::UNCOMPRESSED_SERIAL_VERSION);
+        bytes.write_u8(Family::QUICKSELECT.id);
+        bytes.write_u8(self.table.lg_nom_size());
+        bytes.write_u8(self.table.lg_cur_size());
+        bytes.write_u8(if self.is_empty() {
+            serialization::FLAGS_IS_EMPTY
+        } else {
+            0
+        });
+        bytes.write_u16_le(self.table.seed_hash());
+        bytes.write_u32_le(self.table.num_retained() as u32);
+        bytes.write_f32_le(self.table.sampling_probability());
+        bytes.write_u64_le(self.table.theta());
+        for slot in slots {
+            bytes.write_u64_le(*slot);
+        }
+    }
+
+    /// Serializes this sketch in the updatable format into the given writer.
+    ///
+    /// The output is identical to [`serialize`](Self::serialize), but is written in chunks of a
+    /// few kilobytes, so large sketches can be written to files, compression streams or sockets
+    /// without building the whole byte vector in memory first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the writer fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().build();
+    /// sketch.update("apple");
+    /// let mut buf = Vec::new();
+    /// sketch.serialize_into_writer(&mut buf).unwrap();
+    /// assert_eq!(buf, sketch.serialize());
+    /// ```
+    pub fn serialize_into_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_into_writer(writer, |bytes| self.write_to(bytes))
+    }
+
+    /// Deserializes an updatable theta sketch built with the default seed.
+    ///
+    /// See [`deserialize_with_seed`](Self::deserialize_with_seed).
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        Self::deserialize_with_seed(bytes, DEFAULT_UPDATE_SEED)
+    }
+
+    /// Deserializes an updatable theta sketch using the provided seed.
+    ///
+    /// Accepts the output of [`serialize`](Self::serialize) and of `UpdateSketch.toByteArray()`
+    /// in datasketches-java. The seed is not stored in the image, only its hash, so the sketch
+    /// must be restored with the seed it was built with to keep hashing new values consistently.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid updatable theta sketch (compact images
+    /// must be read with [`CompactThetaSketch::deserialize`]), or if the sketch was built with a
+    /// seed whose hash differs from that of `seed`.
+    pub fn deserialize_with_seed(bytes: &[u8], seed: u64) -> Result<Self, Error> {
+        let mut cursor = SketchSlice::new(bytes);
+        let pre_longs_and_rf = cursor
+            .read_u8()
+            .map_err(insufficient_data("preamble_longs"))?;
+        let ser_ver = cursor
+            .read_u8()
+            .map_err(insufficient_data("serial_version"))?;
+        let family_id = cursor.read_u8().map_err(insufficient_data("family_id"))?;
+        let lg_nom_size = cursor
+            .read_u8()
+            .map_err(insufficient_data("lg_nom_longs"))?;
+        let lg_cur_size = cursor
+            .read_u8()
+            .map_err(insufficient_data("lg_arr_longs"))?;
+        let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+        let seed_hash = cursor
+            .read_u16_le()
+            .map_err(insufficient_data("seed_hash"))?;
+        let num_entries = cursor
+            .read_u32_le()
+            .map_err(insufficient_data("num_entries"))? as usize;
+        let sampling_probability = cursor.read_f32_le().map_err(insufficient_data("p"))?;
+        let theta = cursor
+            .read_u64_le()
+            .map_err(insufficient_data("theta_long"))?;
+
+        if family_id == Family::THETA.id {
+            return Err(Error::bad_preamble(
+                "compact theta sketch cannot be deserialized as an updatable sketch; \
+                 use CompactThetaSketch::deserialize",
+            ));
+        }
+        Family::QUICKSELECT.validate_id(family_id)?;
+        ensure_serial_version_is(serialization::UNCOMPRESSED_SERIAL_VERSION, ser_ver)?;
+        ensure_preamble_longs_in(
+            &[serialization::UPDATE_PREAMBLE_LONGS],
+            pre_longs_and_rf & 0x3F,
+        )?;
+        if flags & (serialization::FLAGS_IS_BIG_ENDIAN | serialization::FLAGS_IS_COMPACT) != 0 {
+            return Err(Error::bad_preamble(format!(
+                "unsupported flags for an updatable sketch: {flags:#010b}"
+            )));
+        }
+        if !(MIN_LG_K..=MAX_LG_K).contains(&lg_nom_size) {
+            return Err(Error::bad_preamble(format!(
+                "lg_nom_longs must be in [{MIN_LG_K}, {MAX_LG_K}], got {lg_nom_size}"
+            )));
+        }
+        if !(MIN_LG_K..=lg_nom_size + 1).contains(&lg_cur_size) {
+            return Err(Error::bad_preamble(format!(
+                "lg_arr_longs must be in [{MIN_LG_K}, {}], got {lg_cur_size}",
+                lg_nom_size + 1
+            )));
+        }
+        if !(sampling_probability > 0.0 && sampling_probability <= 1.0) {
+            return Err(Error::bad_preamble(format!(
+                "p must be in (0, 1], got {sampling_probability}"
+            )));
+        }
+        serialization::check_theta(theta)?;
+        let expected_seed_hash = try_compute_seed_hash(seed)?;
+        if seed_hash != expected_seed_hash {
+            return Err(Error::seed_hash_mismatch(expected_seed_hash, seed_hash));
+        }
+
+        let num_slots = 1usize << lg_cur_size;
+        if cursor.remaining() < num_slots * 8 {
+            return Err(Error::insufficient_data("entries"));
+        }
+        let resize_factor = match pre_longs_and_rf >> 6 {
+            0 => ResizeFactor::X1,
+            1 => ResizeFactor::X2,
+            2 => ResizeFactor::X4,
+            _ => ResizeFactor::X8,
+        };
+        let empty = (flags & serialization::FLAGS_IS_EMPTY) != 0;
+        let mut table = ThetaHashTable::from_raw_parts(
+            lg_cur_size,
+            lg_nom_size,
+            resize_factor,
+            sampling_probability,
+            theta,
+            seed,
+            empty,
+        );
+        let mut slots = Vec::with_capacity(num_slots);
+        for _ in 0..num_slots {
+            slots.push(cursor.read_u64_le().map_err(insufficient_data("entries"))?);
+        }
+        if !table.load_slots(slots) {
+            return Err(Error::deserial("corrupted: invalid retained hash value"));
+        }
+        if table.num_retained() != num_entries {
+            return Err(Error::deserial(format!(
+                "corrupted: expected {num_entries} retained entries, found {}",
+                table.num_retained()
+            )));
+        }
+        if table.is_over_capacity() {
+            return Err(Error::deserial(format!(
+                "corrupted: {num_entries} retained entries exceed the capacity of the table"
+            )));
+        }
+        if empty && num_entries > 0 {
+            return Err(Error::deserial(
+                "corrupted: empty sketch with retained entries",
+            ));
+        }
+        Ok(ThetaSketch {
+            table,
+            hip_accum: None,
+            compact_format: CompactFormat::default(),
+        })
+    }
+
+    /// Deserializes an updatable theta sketch from the given reader, using the default seed.
+    ///
+    /// See [`deserialize_from_reader_with_seed`](Self::deserialize_from_reader_with_seed).
+    pub fn deserialize_from_reader<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        Self::deserialize_from_reader_with_seed(reader, DEFAULT_UPDATE_SEED)
+    }
+
+    /// Deserializes an updatable theta sketch from the given reader using the provided seed.
+    ///
+    /// This accepts the same formats as [`deserialize_with_seed`](Self::deserialize_with_seed),
+    /// and reads no further than the end of the sketch, so the reader may continue with other
+    /// data. Wrap unbuffered readers in a [`BufReader`](std::io::BufReader).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the reader fails or the data is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// # let mut sketch = ThetaSketch::builder().seed(7).build();
+    /// # sketch.update("apple");
+    /// # let mut buf = Vec::new();
+    /// # sketch.serialize_into_writer(&mut buf).unwrap();
+    /// let mut reader = buf.as_slice();
+    /// let decoded = ThetaSketch::deserialize_from_reader_with_seed(&mut reader, 7).unwrap();
+    /// assert_eq!(decoded.estimate(), 1.0);
+    /// assert!(reader.is_empty());
+    /// ```
+    pub fn deserialize_from_reader_with_seed<R: Read>(
+        reader: &mut R,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        let bytes = read_image(reader, serialization::update_preamble_len, |preamble| {
+            serialization::update_image_len(preamble, MAX_LG_K + 1)
+        })?;
+        Self::deserialize_with_seed(&bytes, seed)
+    }
+
     /// Returns the approximate lower error bound given the specified number of Standard Deviations.
     ///
     /// # Arguments
@@ -339,6 +1055,26 @@ impl ThetaSketch {
     }
 }
 
+impl fmt::Display for ThetaSketch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Summary::new(f, "UPDATABLE THETA SKETCH")
+            .field("lg nominal size", self.lg_k())
+            .field("lg current size", self.table_size().trailing_zeros())
+            .field("num retained entries", self.num_retained())
+            .field("resize factor", self.resize_factor().value())
+            .field("sampling probability", self.sampling_probability())
+            .field("seed hash", format_args!("{:#06x}", self.seed_hash()))
+            .field("empty", self.is_empty())
+            .field("estimation mode", self.is_estimation_mode())
+            .field("theta (fraction)", self.theta())
+            .field("theta (raw 64-bit)", self.theta64())
+            .field("estimate", self.estimate())
+            .field("lower bound 95% conf", self.lower_bound(NumStdDev::Two))
+            .field("upper bound 95% conf", self.upper_bound(NumStdDev::Two))
+            .finish()
+    }
+}
+
 impl ThetaSketchView for ThetaSketch {
     fn seed_hash(&self) -> u16 {
         ThetaSketch::seed_hash(self)
@@ -352,7 +1088,7 @@ impl ThetaSketchView for ThetaSketch {
         ThetaSketch::is_empty(self)
     }
 
-    fn iter<'a>(&'a self) -> impl Iterator<Item = u64> + 'a {
+    fn iter(&self) -> impl Iterator<Item = u64> + '_ {
         ThetaSketch::iter(self)
     }
 
@@ -391,6 +1127,27 @@ impl CompactThetaSketch {
         }
     }
 
+    /// Creates an empty compact sketch for the default seed.
+    ///
+    /// Like [`ThetaSketch::empty`], this is the identity element of a union.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::CompactThetaSketch;
+    /// let sketch = CompactThetaSketch::empty();
+    /// assert!(sketch.is_empty());
+    /// assert_eq!(sketch.num_retained(), 0);
+    /// ```
+    pub fn empty() -> Self {
+        Self::empty_with_seed(DEFAULT_UPDATE_SEED)
+    }
+
+    /// Creates an empty compact sketch for the given seed.
+    pub fn empty_with_seed(seed: u64) -> Self {
+        Self::from_parts(vec![], MAX_THETA, compute_seed_hash(seed), true, true)
+    }
+
     /// Returns the cardinality estimate.
     pub fn estimate(&self) -> f64 {
         if self.is_empty() {
@@ -429,6 +1186,55 @@ impl CompactThetaSketch {
         self.entries.len()
     }
 
+    /// Returns true if `other` has the same seed hash as this sketch and their estimates differ
+    /// by at most `epsilon` relative to the larger one.
+    ///
+    /// Compact images do not record lg_k, so sketches compacted from differently sized theta
+    /// sketches compare equal as long as their estimates agree. See
+    /// [`HllSketch::approximately_equal`](crate::hll::HllSketch::approximately_equal).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `epsilon` is negative or NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::CompactThetaSketch;
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().lg_k(10).build();
+    /// for i in 0..100_000 {
+    ///     sketch.update(i);
+    /// }
+    /// let compact = sketch.compact(true);
+    /// let restored = CompactThetaSketch::deserialize(&sketch.compact(false).serialize()).unwrap();
+    /// assert!(compact.approximately_equal(&restored, 0.0));
+    /// assert!(!compact.approximately_equal(&CompactThetaSketch::empty(), 0.5));
+    /// ```
+    pub fn approximately_equal(&self, other: &CompactThetaSketch, epsilon: f64) -> bool {
+        self.seed_hash() == other.seed_hash()
+            && estimates_within(self.estimate(), other.estimate(), epsilon)
+    }
+
+    /// Returns the estimate and the bounds at `num_std_dev` standard deviations.
+    ///
+    /// The number of retained entries is the number of retained hashes.
+    pub fn estimate_summary(&self, num_std_dev: NumStdDev) -> EstimateSummary {
+        EstimateSummary {
+            estimate: self.estimate(),
+            lb: self.lower_bound(num_std_dev),
+            ub: self.upper_bound(num_std_dev),
+            retained: self.num_retained() as u64,
+            family: CardinalityFamily::Theta,
+        }
+    }
+
+    /// Returns the number of bytes of memory the sketch occupies, including any spare capacity
+    /// of its entry array.
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>() + self.entries.capacity() * size_of::<u64>()
+    }
+
     /// Returns true if retained entries are ordered (sorted ascending).
     pub fn is_ordered(&self) -> bool {
         self.ordered
@@ -440,10 +1246,110 @@ impl CompactThetaSketch {
     }
 
     /// Return iterator over retained hash values.
+    ///
+    /// Hashes are yielded in ascending order if the sketch [`is_ordered`](Self::is_ordered),
+    /// and are all below [`theta64`](Self::theta64).
     pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
         self.entries.iter().copied()
     }
 
+    /// Returns this sketch with its retained hashes sorted in ascending order.
+    ///
+    /// Use this to order a [`ThetaSketch::snapshot`] after releasing the lock it was taken
+    /// under.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().build();
+    /// for i in 0..10 {
+    ///     sketch.update(i);
+    /// }
+    /// let ordered = sketch.compact(false).into_ordered();
+    /// assert!(ordered.is_ordered());
+    /// assert_eq!(ordered.serialize(), sketch.compact(true).serialize());
+    /// ```
+    pub fn into_ordered(mut self) -> CompactThetaSketch {
+        if !self.ordered {
+            self.entries.sort_unstable();
+            self.ordered = true;
+        }
+        self
+    }
+
+    /// Returns a copy of this sketch reduced to at most `2^lg_k` retained hashes.
+    ///
+    /// Keeps the `2^lg_k` smallest hashes and lowers theta to the next one, like
+    /// [`ThetaSketch::downsample`]. Compact sketches do not record their nominal size, so a
+    /// sketch that already retains no more than `2^lg_k` hashes is returned unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lg_k` is not in `[5, 26]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().lg_k(12).build();
+    /// for i in 0..100_000 {
+    ///     sketch.update(i);
+    /// }
+    ///
+    /// let compact = sketch.compact(true).downsample(8);
+    /// assert_eq!(compact.num_retained(), 1 << 8);
+    /// assert!(compact.is_ordered());
+    /// assert!((compact.estimate() - 100_000.0).abs() < 30_000.0);
+    /// ```
+    pub fn downsample(&self, lg_k: u8) -> CompactThetaSketch {
+        assert!(
+            (MIN_LG_K..=MAX_LG_K).contains(&lg_k),
+            "lg_k must be in [{MIN_LG_K}, {MAX_LG_K}], got {lg_k}"
+        );
+        self.trim(1 << lg_k)
+    }
+
+    /// Returns a copy of this sketch reduced to exactly `k` retained hashes.
+    ///
+    /// Unlike [`downsample`](Self::downsample), `k` need not be a power of two, which bounds the
+    /// storage of rolled-up sketches precisely. The `k` smallest hashes are kept and theta is
+    /// lowered to the smallest discarded hash, as the Java implementation does. A sketch that
+    /// retains no more than `k` hashes is returned unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().lg_k(12).build();
+    /// for i in 0..100_000 {
+    ///     sketch.update(i);
+    /// }
+    ///
+    /// let trimmed = sketch.compact(true).trim(1000);
+    /// assert_eq!(trimmed.num_retained(), 1000);
+    /// assert!((trimmed.estimate() - 100_000.0).abs() < 20_000.0);
+    /// ```
+    pub fn trim(&self, k: usize) -> CompactThetaSketch {
+        assert!(k > 0, "k must be positive");
+        if self.entries.len() <= k {
+            return self.clone();
+        }
+
+        let mut entries = self.entries.clone();
+        let (_, kth, _) = entries.select_nth_unstable(k);
+        let theta = *kth;
+        entries.truncate(k);
+        if self.ordered {
+            entries.sort_unstable();
+        }
+        Self::from_parts(entries, theta, self.seed_hash, self.ordered, self.empty)
+    }
+
     /// Returns the approximate lower error bound given the specified number of Standard Deviations.
     pub fn lower_bound(&self, num_std_dev: NumStdDev) -> f64 {
         if !self.is_estimation_mode() {
@@ -497,10 +1403,60 @@ impl CompactThetaSketch {
             && (self.entries.len() != 1 || self.is_estimation_mode())
     }
 
+    /// Serializes this sketch in the given layout.
+    ///
+    /// [`CompactFormat::Uncompressed`] is [`serialize`](Self::serialize) and
+    /// [`CompactFormat::Compressed`] is [`serialize_compressed`](Self::serialize_compressed).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::CompactFormat;
+    /// # use datasketches::theta::CompactThetaSketch;
+    /// # use datasketches::theta::ThetaSketch;
+    /// # let mut sketch = ThetaSketch::builder().build();
+    /// # sketch.update("apple");
+    /// let compact = sketch.compact(true);
+    /// for format in [CompactFormat::Uncompressed, CompactFormat::Compressed] {
+    ///     let bytes = compact.serialize_with(format);
+    ///     let restored = CompactThetaSketch::deserialize(&bytes).unwrap();
+    ///     assert!(restored.iter().eq(compact.iter()));
+    /// }
+    /// ```
+    pub fn serialize_with(&self, format: CompactFormat) -> Vec<u8> {
+        match format {
+            CompactFormat::Uncompressed => self.serialize(),
+            CompactFormat::Compressed => self.serialize_compressed(),
+        }
+    }
+
     /// Serializes this sketch into the uncompressed compact theta format.
     pub fn serialize(&self) -> Vec<u8> {
-        let mut bytes = SketchBytes::with_capacity(64 + self.entries.len() * 8);
+        let mut bytes = SketchBytes::with_capacity(self.serialized_size_bytes());
+        self.write_to(&mut bytes);
+        bytes.into_bytes()
+    }
+
+    /// Returns the number of bytes [`CompactThetaSketch::serialize`] produces.
+    pub fn serialized_size_bytes(&self) -> usize {
+        (self.preamble_longs(false) as usize + self.entries.len()) * 8
+    }
+
+    /// Serializes this sketch into the front of `buf` without allocating, returning the number of
+    /// bytes written.
+    ///
+    /// The bytes are the same as those returned by [`CompactThetaSketch::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `buf` is shorter than [`CompactThetaSketch::serialized_size_bytes`].
+    pub fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        serialize_into_slice(buf, self.serialized_size_bytes(), |bytes| {
+            self.write_to(bytes)
+        })
+    }
 
+    fn write_to(&self, bytes: &mut SketchBytes<'_>) {
         let pre_longs = self.preamble_longs(false);
         bytes.write_u8(pre_longs);
         bytes.write_u8(serialization::UNCOMPRESSED_SERIAL_VERSION);
@@ -530,7 +1486,6 @@ impl CompactThetaSketch {
         for hash in self.entries.iter() {
             bytes.write_u64_le(*hash);
         }
-        bytes.into_bytes()
     }
 
     fn serialize_v4(&self) -> Vec<u8> {
@@ -594,7 +1549,7 @@ impl CompactThetaSketch {
                 packer.pack_value(delta, entry_bits);
                 i += 1;
             }
-            let bytes_used = packer.byte_used();
+            let bytes_used = packer.bytes_used();
             bytes.write(&block[0..bytes_used]);
         }
 
@@ -618,12 +1573,53 @@ impl CompactThetaSketch {
         bits.div_ceil(8) as u8
     }
 
+    /// Serializes this sketch in the uncompressed compact format into the given writer.
+    ///
+    /// The output is identical to [`serialize`](Self::serialize), but is written in chunks of a
+    /// few kilobytes, so large sketches can be written to files, compression streams or sockets
+    /// without building the whole byte vector in memory first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the writer fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// # let mut sketch = ThetaSketch::builder().build();
+    /// # sketch.update("apple");
+    /// let compact = sketch.compact(true);
+    /// let mut buf = Vec::new();
+    /// compact.serialize_into_writer(&mut buf).unwrap();
+    /// assert_eq!(buf, compact.serialize());
+    /// ```
+    pub fn serialize_into_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_into_writer(writer, |bytes| self.write_to(bytes))
+    }
+
     /// Deserializes a compact theta sketch from bytes.
+    ///
+    /// Both [`serialize`](Self::serialize) and [`serialize_compressed`](Self::serialize_compressed)
+    /// round trip losslessly: theta, the retained hashes, and therefore the estimate and bounds
+    /// are preserved bit-for-bit.
+    ///
+    /// # Compatibility
+    ///
+    /// Reads the compact and compressed compact images written by datasketches-java and
+    /// datasketches-cpp, as checked against the serialization test corpus;
+    /// [`compat`](crate::compat) names the target releases.
     pub fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
         Self::deserialize_with_seed(bytes, DEFAULT_UPDATE_SEED)
     }
 
     /// Deserializes a compact theta sketch from bytes using the provided expected seed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid compact theta sketch, or if the sketch is
+    /// not empty and was built with a seed whose hash differs from that of `seed`. Serial
+    /// version 1 does not record a seed hash, so such sketches are assumed to use `seed`.
     pub fn deserialize_with_seed(bytes: &[u8], seed: u64) -> Result<Self, Error> {
         let mut cursor = SketchSlice::new(bytes);
         let pre_longs = cursor
@@ -647,30 +1643,82 @@ impl CompactThetaSketch {
             2 => Self::deserialize_v2(pre_longs, cursor, seed),
             3 => Self::deserialize_v3(pre_longs, cursor, seed),
             4 => Self::deserialize_v4(pre_longs, cursor, seed),
-            _ => Err(Error::deserial(format!(
+            _ => Err(Error::unsupported_version(format!(
                 "unsupported serial version: expected 1, 2, 3, or 4, got {ser_ver}",
             ))),
         }
     }
 
+    /// Deserializes a compact theta sketch from the given reader, using the default seed.
+    ///
+    /// See [`deserialize_from_reader_with_seed`](Self::deserialize_from_reader_with_seed).
+    pub fn deserialize_from_reader<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        Self::deserialize_from_reader_with_seed(reader, DEFAULT_UPDATE_SEED)
+    }
+
+    /// Deserializes a compact theta sketch from the given reader using the provided seed.
+    ///
+    /// This accepts the same formats as [`deserialize_with_seed`](Self::deserialize_with_seed),
+    /// and reads no further than the end of the sketch, so the reader may continue with other
+    /// data. Wrap unbuffered readers in a [`BufReader`](std::io::BufReader).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the reader fails or the data is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::CompactThetaSketch;
+    /// # use datasketches::theta::ThetaSketch;
+    /// # let mut sketch = ThetaSketch::builder().build();
+    /// # sketch.update("apple");
+    /// # let bytes = sketch.compact(true).serialize_compressed();
+    /// let mut reader = bytes.as_slice();
+    /// let decoded = CompactThetaSketch::deserialize_from_reader(&mut reader).unwrap();
+    /// assert_eq!(decoded.estimate(), 1.0);
+    /// ```
+    pub fn deserialize_from_reader_with_seed<R: Read>(
+        reader: &mut R,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        let bytes = read_image(
+            reader,
+            serialization::compact_preamble_len,
+            serialization::compact_image_len,
+        )?;
+        Self::deserialize_with_seed(&bytes, seed)
+    }
+
     fn read_entries(
         cursor: &mut SketchSlice<'_>,
         num_entries: usize,
         theta: u64,
+        ordered: bool,
     ) -> Result<Vec<u64>, Error> {
+        // check before allocating, so that a corrupted count cannot exhaust memory
+        if cursor.remaining() / 8 < num_entries {
+            return Err(Error::insufficient_data(format!(
+                "entries: expected {num_entries} entries, got {} bytes",
+                cursor.remaining()
+            )));
+        }
         let mut entries = Vec::with_capacity(num_entries);
         for _ in 0..num_entries {
             let hash = cursor.read_u64_le().map_err(insufficient_data("entries"))?;
             if hash == 0 || hash >= theta {
                 return Err(Error::deserial("corrupted: invalid retained hash value"));
             }
+            if ordered && entries.last().is_some_and(|&last| last >= hash) {
+                return Err(Error::deserial("corrupted: ordered entries are not sorted"));
+            }
             entries.push(hash);
         }
         Ok(entries)
     }
 
     fn deserialize_v1(mut cursor: SketchSlice<'_>, expected_seed: u64) -> Result<Self, Error> {
-        let seed_hash = compute_seed_hash(expected_seed);
+        let seed_hash = try_compute_seed_hash(expected_seed)?;
         cursor.read_u8().map_err(insufficient_data("<unused>"))?;
         cursor
             .read_u32_le()
@@ -684,6 +1732,7 @@ impl CompactThetaSketch {
         let theta = cursor
             .read_u64_le()
             .map_err(insufficient_data("theta_long"))?;
+        let theta = serialization::check_theta(theta)?;
 
         let empty = num_entries == 0 && theta == MAX_THETA;
         if empty {
@@ -696,7 +1745,7 @@ impl CompactThetaSketch {
             });
         }
 
-        let entries = Self::read_entries(&mut cursor, num_entries, theta)?;
+        let entries = Self::read_entries(&mut cursor, num_entries, theta, true)?;
 
         Ok(Self {
             entries,
@@ -712,18 +1761,16 @@ impl CompactThetaSketch {
         mut cursor: SketchSlice<'_>,
         expected_seed: u64,
     ) -> Result<Self, Error> {
-        cursor.read_u8().map_err(insufficient_data("<unused>"))?;
         cursor
             .read_u16_le()
             .map_err(insufficient_data("<unused_u16>"))?;
+        let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
         let seed_hash = cursor
             .read_u16_le()
             .map_err(insufficient_data("seed_hash"))?;
-        let expected_seed_hash = compute_seed_hash(expected_seed);
+        let expected_seed_hash = try_compute_seed_hash(expected_seed)?;
         if seed_hash != expected_seed_hash {
-            return Err(Error::deserial(format!(
-                "incompatible seed hash: expected {expected_seed_hash}, got {seed_hash}",
-            )));
+            return Err(Error::seed_hash_mismatch(expected_seed_hash, seed_hash));
         }
 
         match pre_longs {
@@ -742,13 +1789,19 @@ impl CompactThetaSketch {
                 cursor
                     .read_u32_le()
                     .map_err(insufficient_data("<unused_u32>"))?;
-                let entries = Self::read_entries(&mut cursor, num_entries, MAX_THETA)?;
+                if num_entries > 0 && (flags & serialization::FLAGS_IS_EMPTY) != 0 {
+                    return Err(
+                        Error::deserial("empty flag is set on a sketch with entries")
+                            .with_context("num_entries", num_entries),
+                    );
+                }
+                let entries = Self::read_entries(&mut cursor, num_entries, MAX_THETA, true)?;
                 Ok(Self {
                     entries,
                     theta: MAX_THETA,
                     seed_hash,
                     ordered: true,
-                    empty: true,
+                    empty: num_entries == 0,
                 })
             }
             V2_PREAMBLE_ESTIMATE => {
@@ -762,8 +1815,9 @@ impl CompactThetaSketch {
                 let theta = cursor
                     .read_u64_le()
                     .map_err(insufficient_data("theta_long"))?;
+                let theta = serialization::check_theta(theta)?;
                 let empty = (num_entries == 0) && (theta == MAX_THETA);
-                let entries = Self::read_entries(&mut cursor, num_entries, theta)?;
+                let entries = Self::read_entries(&mut cursor, num_entries, theta, true)?;
                 Ok(Self {
                     entries,
                     theta,
@@ -790,15 +1844,14 @@ impl CompactThetaSketch {
             .map_err(insufficient_data("seed_hash"))?;
 
         let empty = (flags & serialization::FLAGS_IS_EMPTY) != 0;
+        let ordered = (flags & serialization::FLAGS_IS_ORDERED) != 0;
         let mut theta = MAX_THETA;
         let num_entries;
         let mut entries = vec![];
         if !empty {
-            let expected_seed_hash = compute_seed_hash(expected_seed);
+            let expected_seed_hash = try_compute_seed_hash(expected_seed)?;
             if seed_hash != expected_seed_hash {
-                return Err(Error::deserial(format!(
-                    "incompatible seed hash: expected {expected_seed_hash}, got {seed_hash}",
-                )));
+                return Err(Error::seed_hash_mismatch(expected_seed_hash, seed_hash));
             }
             if pre_longs == 1 {
                 num_entries = 1;
@@ -810,14 +1863,14 @@ impl CompactThetaSketch {
                     .read_u32_le()
                     .map_err(insufficient_data("<unused_u32>"))?;
                 if pre_longs > 2 {
-                    theta = cursor
+                    let theta_long = cursor
                         .read_u64_le()
                         .map_err(insufficient_data("theta_long"))?;
+                    theta = serialization::check_theta(theta_long)?;
                 }
             }
-            entries = Self::read_entries(&mut cursor, num_entries as usize, theta)?;
+            entries = Self::read_entries(&mut cursor, num_entries as usize, theta, ordered)?;
         }
-        let ordered = (flags & serialization::FLAGS_IS_ORDERED) != 0;
         Ok(Self {
             entries,
             theta,
@@ -840,22 +1893,26 @@ impl CompactThetaSketch {
             .map_err(insufficient_data("seed_hash"))?;
         let empty = (flags & serialization::FLAGS_IS_EMPTY) != 0;
         if !empty {
-            let expected_seed_hash = compute_seed_hash(expected_seed);
+            let expected_seed_hash = try_compute_seed_hash(expected_seed)?;
             if seed_hash != expected_seed_hash {
-                return Err(Error::deserial(format!(
-                    "incompatible seed hash: expected {expected_seed_hash}, got {seed_hash}",
-                )));
+                return Err(Error::seed_hash_mismatch(expected_seed_hash, seed_hash));
             }
         }
         let theta = if pre_longs > 1 {
-            cursor
+            let theta = cursor
                 .read_u64_le()
-                .map_err(insufficient_data("theta_long"))?
+                .map_err(insufficient_data("theta_long"))?;
+            serialization::check_theta(theta)?
         } else {
             MAX_THETA
         };
 
         // unpack num_entries
+        if num_entries_bytes > 4 {
+            return Err(Error::bad_preamble(format!(
+                "num_entries_bytes must be at most 4, got {num_entries_bytes}"
+            )));
+        }
         let mut num_entries = 0usize;
         for i in 0..num_entries_bytes {
             let entry_count_byte = cursor
@@ -863,6 +1920,21 @@ impl CompactThetaSketch {
                 .map_err(insufficient_data("num_entries_byte"))?;
             num_entries |= (entry_count_byte as usize) << ((i as usize) << 3);
         }
+        if num_entries > 0 && !(1..=63).contains(&entry_bits) {
+            return Err(Error::bad_preamble(format!(
+                "entry_bits must be in [1, 63], got {entry_bits}"
+            )));
+        }
+
+        // check before allocating, so that a corrupted count cannot exhaust memory
+        let packed_bits = num_entries * entry_bits as usize;
+        if cursor.remaining() < packed_bits.div_ceil(8) {
+            return Err(Error::insufficient_data(format!(
+                "delta_blocks: expected {} bytes, got {}",
+                packed_bits.div_ceil(8),
+                cursor.remaining()
+            )));
+        }
 
         // unpack blocks of BLOCK_WIDTH deltas
         let mut i = 0usize;
@@ -895,7 +1967,7 @@ impl CompactThetaSketch {
         // undo deltas
         let mut previous = 0;
         for e in &mut entries {
-            *e += previous;
+            *e = e.checked_add(previous).unwrap_or(0);
             previous = *e;
             if *e == 0 || *e >= theta {
                 return Err(Error::deserial("corrupted: invalid retained hash value"));
@@ -914,6 +1986,23 @@ impl CompactThetaSketch {
     }
 }
 
+impl fmt::Display for CompactThetaSketch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Summary::new(f, "COMPACT THETA SKETCH")
+            .field("num retained entries", self.num_retained())
+            .field("seed hash", format_args!("{:#06x}", self.seed_hash()))
+            .field("empty", self.is_empty())
+            .field("ordered", self.is_ordered())
+            .field("estimation mode", self.is_estimation_mode())
+            .field("theta (fraction)", self.theta())
+            .field("theta (raw 64-bit)", self.theta64())
+            .field("estimate", self.estimate())
+            .field("lower bound 95% conf", self.lower_bound(NumStdDev::Two))
+            .field("upper bound 95% conf", self.upper_bound(NumStdDev::Two))
+            .finish()
+    }
+}
+
 impl ThetaSketchView for CompactThetaSketch {
     fn seed_hash(&self) -> u16 {
         CompactThetaSketch::seed_hash(self)
@@ -927,7 +2016,7 @@ impl ThetaSketchView for CompactThetaSketch {
         CompactThetaSketch::is_empty(self)
     }
 
-    fn iter<'a>(&'a self) -> impl Iterator<Item = u64> + 'a {
+    fn iter(&self) -> impl Iterator<Item = u64> + '_ {
         CompactThetaSketch::iter(self)
     }
 
@@ -941,21 +2030,28 @@ impl ThetaSketchView for CompactThetaSketch {
 }
 
 /// Builder for ThetaSketch
+///
+/// Parameters that are not set default to the global [`Config`].
 #[derive(Debug)]
 pub struct ThetaSketchBuilder {
     lg_k: u8,
     resize_factor: ResizeFactor,
     sampling_probability: f32,
     seed: u64,
+    hip_estimator: bool,
+    compact_format: CompactFormat,
 }
 
 impl Default for ThetaSketchBuilder {
     fn default() -> Self {
+        let config = Config::global();
         Self {
-            lg_k: DEFAULT_LG_K,
-            resize_factor: ResizeFactor::X8,
+            lg_k: config.lg_k(),
+            resize_factor: config.resize_factor(),
             sampling_probability: 1.0,
-            seed: DEFAULT_UPDATE_SEED,
+            seed: config.seed(),
+            hip_estimator: false,
+            compact_format: CompactFormat::default(),
         }
     }
 }
@@ -965,7 +2061,8 @@ impl ThetaSketchBuilder {
     ///
     /// # Panics
     ///
-    /// If lg_k is not in range [5, 26]
+    /// [`build`](Self::build) panics if lg_k is not in range [5, 26];
+    /// [`try_build`](Self::try_build) returns an error instead.
     ///
     /// # Examples
     ///
@@ -973,20 +2070,38 @@ impl ThetaSketchBuilder {
     /// # use datasketches::theta::ThetaSketch;
     /// let sketch = ThetaSketch::builder().lg_k(12).build();
     /// assert_eq!(sketch.lg_k(), 12);
+    /// assert!(ThetaSketch::builder().lg_k(27).try_build().is_err());
     /// ```
     pub fn lg_k(mut self, lg_k: u8) -> Self {
-        assert!(
-            (MIN_LG_K..=MAX_LG_K).contains(&lg_k),
-            "lg_k must be in [{}, {}], got {}",
-            MIN_LG_K,
-            MAX_LG_K,
-            lg_k
-        );
         self.lg_k = lg_k;
         self
     }
 
-    /// Set resize factor.
+    /// Set resize factor, which controls how the internal hash table grows.
+    ///
+    /// The table starts at the smallest size reachable from its maximum of `2 * k` slots by
+    /// repeatedly dividing by the factor (but at least 32 slots), and grows by the factor
+    /// whenever it becomes half full. Smaller factors keep the footprint of sketches that see
+    /// few distinct values close to what they need, at the cost of more frequent rehashing
+    /// while the table grows. [`ResizeFactor::X1`] allocates the full table up front and
+    /// never rehashes for growth. The default is [`ResizeFactor::X8`] unless another one is set in
+    /// the global [`Config`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::common::ResizeFactor;
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder()
+    ///     .lg_k(12)
+    ///     .resize_factor(ResizeFactor::X2)
+    ///     .build();
+    /// assert_eq!(sketch.table_size(), 32);
+    /// for i in 0..100 {
+    ///     sketch.update(i);
+    /// }
+    /// assert_eq!(sketch.table_size(), 256);
+    /// ```
     pub fn resize_factor(mut self, factor: ResizeFactor) -> Self {
         self.resize_factor = factor;
         self
@@ -994,30 +2109,47 @@ impl ThetaSketchBuilder {
 
     /// Set sampling probability p.
     ///
-    /// The sampling probability controls the fraction of hashed values that are retained.
-    /// Must be greater than 0 to ensure valid theta values for bound calculations.
+    /// The sampling probability controls the fraction of hashed values that are retained:
+    /// the sketch starts with theta = p instead of 1.0, which bounds its size and update cost
+    /// on very high-volume streams at the price of accuracy. This is the equivalent of
+    /// `setP` on the datasketches-java builder. Must be greater than 0 to ensure valid theta
+    /// values for bound calculations.
+    ///
+    /// The compact serialization formats do not have a p field; p is carried by the
+    /// serialized theta instead. A sketch that was never updated compacts with theta = 1.0,
+    /// matching datasketches-java and datasketches-cpp, while a sketch that saw updates keeps
+    /// theta <= p even if every update was screened out.
     ///
     /// # Panics
     ///
-    /// Panics if p is not in range `(0.0, 1.0]`
+    /// [`build`](Self::build) panics if p is not in range `(0.0, 1.0]`;
+    /// [`try_build`](Self::try_build) returns an error instead.
     ///
     /// # Examples
     ///
     /// ```
     /// # use datasketches::theta::ThetaSketch;
     /// let _sketch = ThetaSketch::builder().sampling_probability(0.5).build();
+    /// assert!(ThetaSketch::builder().sampling_probability(0.0).try_build().is_err());
     /// ```
     pub fn sampling_probability(mut self, probability: f32) -> Self {
-        assert!(
-            (0.0..=1.0).contains(&probability) && probability > 0.0,
-            "sampling_probability must be in (0.0, 1.0], got {probability}"
-        );
         self.sampling_probability = probability;
         self
     }
 
     /// Set hash seed.
     ///
+    /// The 16-bit hash of the seed is stored in the serialized preamble. Deserialization,
+    /// [`ThetaUnion`](crate::theta::ThetaUnion) and
+    /// [`ThetaIntersection`](crate::theta::ThetaIntersection) reject non-empty sketches whose
+    /// seed hash does not match the seed they were configured with, since hashes produced
+    /// with different seeds cannot be combined.
+    ///
+    /// # Panics
+    ///
+    /// [`build`](Self::build) panics if the 16-bit hash of `seed` is zero, which is reserved;
+    /// [`try_build`](Self::try_build) returns an error instead.
+    ///
     /// # Examples
     ///
     /// ```
@@ -1029,8 +2161,75 @@ impl ThetaSketchBuilder {
         self
     }
 
+    /// Use the HIP (historical inverse probability) estimator.
+    ///
+    /// Every hash that is newly retained adds `1 / theta` to an accumulator, with theta taken
+    /// just before the insertion, and [`ThetaSketch::estimate`] returns the accumulated sum.
+    /// This takes into account the values that were retained before theta dropped, so the
+    /// estimate has a lower variance than `num_retained / theta`, around `1 / sqrt(2k)`
+    /// relative standard error instead of `1 / sqrt(k)`. The bounds are unaffected and remain
+    /// those of the standard estimator.
+    ///
+    /// The accumulator only describes the stream this sketch has seen, so it is not part of
+    /// any serialized image. A deserialized sketch always uses the standard estimator, and
+    /// the compact form, unions and intersections never use it. Disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder().lg_k(10).hip_estimator(true).build();
+    /// for i in 0..100_000 {
+    ///     sketch.update(i);
+    /// }
+    /// assert!(sketch.is_hip_estimator());
+    /// assert!((sketch.estimate() - 100_000.0).abs() < 5_000.0);
+    /// ```
+    pub fn hip_estimator(mut self, hip_estimator: bool) -> Self {
+        self.hip_estimator = hip_estimator;
+        self
+    }
+
+    /// Set the layout [`ThetaSketch::serialize_compact`] writes.
+    ///
+    /// [`CompactFormat::Uncompressed`], the default, suits pipelines that serialize and
+    /// deserialize often; [`CompactFormat::Compressed`] suits sketches that are stored for
+    /// long or sent over slow links. Deserialization detects the layout, so the choice can be
+    /// changed at any time without migrating stored images.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::CompactFormat;
+    /// # use datasketches::theta::CompactThetaSketch;
+    /// # use datasketches::theta::ThetaSketch;
+    /// let mut sketch = ThetaSketch::builder()
+    ///     .compact_format(CompactFormat::Compressed)
+    ///     .build();
+    /// for i in 0..10_000 {
+    ///     sketch.update(i);
+    /// }
+    /// let bytes = sketch.serialize_compact();
+    /// assert!(bytes.len() < sketch.compact(true).serialize().len());
+    /// let restored = CompactThetaSketch::deserialize(&bytes).unwrap();
+    /// assert_eq!(restored.estimate(), sketch.estimate());
+    /// ```
+    pub fn compact_format(mut self, format: CompactFormat) -> Self {
+        self.compact_format = format;
+        self
+    }
+
     /// Build the ThetaSketch.
     ///
+    /// The fallible version of this method is [`ThetaSketchBuilder::try_build`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of:
+    /// * lg_k is not in range [5, 26]
+    /// * the sampling probability is not in range `(0.0, 1.0]`
+    /// * the 16-bit hash of the configured seed is zero
+    ///
     /// # Examples
     ///
     /// ```
@@ -1039,6 +2238,18 @@ impl ThetaSketchBuilder {
     /// assert_eq!(sketch.lg_k(), 10);
     /// ```
     pub fn build(self) -> ThetaSketch {
+        assert!(
+            (MIN_LG_K..=MAX_LG_K).contains(&self.lg_k),
+            "lg_k must be in [{}, {}], got {}",
+            MIN_LG_K,
+            MAX_LG_K,
+            self.lg_k
+        );
+        assert!(
+            is_valid_sampling_probability(self.sampling_probability),
+            "sampling_probability must be in (0.0, 1.0], got {}",
+            self.sampling_probability
+        );
         let table = ThetaHashTable::new(
             self.lg_k,
             self.resize_factor,
@@ -1046,7 +2257,132 @@ impl ThetaSketchBuilder {
             self.seed,
         );
 
-        ThetaSketch { table }
+        ThetaSketch {
+            table,
+            hip_accum: self.hip_estimator.then_some(0.0),
+            compact_format: self.compact_format,
+        }
+    }
+
+    /// Build the ThetaSketch.
+    ///
+    /// The panicking version of this method is [`ThetaSketchBuilder::build`].
+    ///
+    /// # Errors
+    ///
+    /// Under the same conditions as [`build`](Self::build) panics, so that sketches configured
+    /// from user input can report misconfiguration instead of aborting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// let sketch = ThetaSketch::builder().lg_k(10).try_build().unwrap();
+    /// assert_eq!(sketch.lg_k(), 10);
+    /// assert!(ThetaSketch::builder().lg_k(4).try_build().is_err());
+    /// ```
+    pub fn try_build(self) -> Result<ThetaSketch, Error> {
+        if !(MIN_LG_K..=MAX_LG_K).contains(&self.lg_k) {
+            return Err(Error::invalid_argument(format!(
+                "lg_k must be in [{MIN_LG_K}, {MAX_LG_K}], got {}",
+                self.lg_k
+            )));
+        }
+        if !is_valid_sampling_probability(self.sampling_probability) {
+            return Err(Error::invalid_argument(format!(
+                "sampling_probability must be in (0.0, 1.0], got {}",
+                self.sampling_probability
+            )));
+        }
+        try_compute_seed_hash(self.seed)?;
+        Ok(self.build())
+    }
+}
+
+fn is_valid_sampling_probability(probability: f32) -> bool {
+    probability > 0.0 && probability <= 1.0
+}
+
+impl<I: Hash> Sketch<I> for ThetaSketch {
+    fn update(&mut self, item: I) {
+        self.update_quiet(item);
+    }
+
+    fn is_empty(&self) -> bool {
+        ThetaSketch::is_empty(self)
+    }
+}
+
+impl SerializableSketch for ThetaSketch {
+    type Error = Error;
+
+    fn serialize(&self) -> Vec<u8> {
+        ThetaSketch::serialize(self)
+    }
+
+    fn serialized_size_bytes(&self) -> usize {
+        ThetaSketch::serialized_size_bytes(self)
+    }
+
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        ThetaSketch::serialize_into(self, buf)
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        ThetaSketch::deserialize(bytes)
+    }
+}
+
+impl SerializableSketch for CompactThetaSketch {
+    type Error = Error;
+
+    fn serialize(&self) -> Vec<u8> {
+        CompactThetaSketch::serialize(self)
+    }
+
+    fn serialized_size_bytes(&self) -> usize {
+        CompactThetaSketch::serialized_size_bytes(self)
+    }
+
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        CompactThetaSketch::serialize_into(self, buf)
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        CompactThetaSketch::deserialize(bytes)
+    }
+}
+
+/// Merging keeps every entry of either sketch below the smaller theta, so, unlike
+/// [`ThetaUnion`](crate::theta::ThetaUnion), the result is not bounded to k entries. The result is
+/// ordered.
+impl MergeableSketch for CompactThetaSketch {
+    type Error = Error;
+
+    fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        if other.is_empty() {
+            return Ok(());
+        }
+        if self.is_empty() {
+            *self = other.clone();
+            return Ok(());
+        }
+        if self.seed_hash != other.seed_hash {
+            return Err(Error::seed_hash_mismatch(self.seed_hash, other.seed_hash));
+        }
+
+        let theta = self.theta.min(other.theta);
+        let mut entries: Vec<u64> = self
+            .entries
+            .iter()
+            .chain(&other.entries)
+            .copied()
+            .filter(|&hash| hash < theta)
+            .collect();
+        entries.sort_unstable();
+        entries.dedup();
+        *self = CompactThetaSketch::from_parts(entries, theta, self.seed_hash, true, false);
+        Ok(())
     }
 }
 
@@ -1190,8 +2526,7 @@ mod tests {
         let bytes = theta.compact(true).serialize();
 
         let err = CompactThetaSketch::deserialize_with_seed(&bytes, 8).unwrap_err();
-        assert_eq!(err.kind(), crate::error::ErrorKind::InvalidData);
-        assert!(err.message().contains("incompatible seed hash"));
+        assert_eq!(err.kind(), crate::error::ErrorKind::SeedHashMismatch);
     }
 
     #[test]
@@ -1202,7 +2537,7 @@ mod tests {
         bytes[2] = 0;
 
         let err = CompactThetaSketch::deserialize(&bytes).unwrap_err();
-        assert_eq!(err.kind(), crate::error::ErrorKind::InvalidData);
+        assert_eq!(err.kind(), crate::error::ErrorKind::BadPreamble);
         assert!(err.message().contains("invalid family"));
     }
 
@@ -1214,7 +2549,7 @@ mod tests {
         bytes[1] = 99;
 
         let err = CompactThetaSketch::deserialize(&bytes).unwrap_err();
-        assert_eq!(err.kind(), crate::error::ErrorKind::InvalidData);
+        assert_eq!(err.kind(), crate::error::ErrorKind::UnsupportedVersion);
         assert!(err.message().contains("unsupported serial version"));
     }
 
@@ -1228,7 +2563,7 @@ mod tests {
         bytes.pop();
 
         let err = CompactThetaSketch::deserialize(&bytes).unwrap_err();
-        assert_eq!(err.kind(), crate::error::ErrorKind::InvalidData);
+        assert_eq!(err.kind(), crate::error::ErrorKind::Truncated);
         assert!(err.message().contains("insufficient data"));
     }
 }
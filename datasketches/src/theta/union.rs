@@ -0,0 +1,459 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::Config;
+use crate::common::ResizeFactor;
+use crate::error::Error;
+use crate::error::ErrorKind;
+use crate::hash::try_compute_seed_hash;
+use crate::theta::CompactThetaSketch;
+use crate::theta::MAX_LG_K;
+use crate::theta::MAX_THETA;
+use crate::theta::MIN_LG_K;
+use crate::theta::ThetaSketchView;
+use crate::theta::WrappedCompactThetaSketch;
+use crate::theta::hash_table::ThetaHashTable;
+
+/// Stateful union operator for Theta sketches.
+///
+/// The union keeps at most `k` entries. Inputs may be in estimation mode or use a different
+/// nominal size, but must share the same seed.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::theta::ThetaSketch;
+/// # use datasketches::theta::ThetaUnion;
+/// let mut a = ThetaSketch::builder().build();
+/// a.update("apple");
+/// let mut b = ThetaSketch::builder().build();
+/// b.update("apple");
+/// b.update("banana");
+///
+/// let mut union = ThetaUnion::builder().build();
+/// union.update(&a).unwrap();
+/// union.update(&b.compact(true)).unwrap();
+/// assert_eq!(union.result().estimate(), 2.0);
+/// assert_eq!(union.stats().num_sketches(), 2);
+/// ```
+#[derive(Debug)]
+pub struct ThetaUnion {
+    table: ThetaHashTable,
+    union_theta: u64,
+    stats: ThetaUnionStats,
+}
+
+/// Statistics collected by a [`ThetaUnion`] over its input sketches.
+///
+/// The counters are cumulative since the union was created or last [reset](ThetaUnion::reset).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ThetaUnionStats {
+    num_sketches: u64,
+    num_entries: u64,
+    num_rejected: u64,
+    num_downsamples: u64,
+}
+
+impl ThetaUnionStats {
+    /// Returns the number of input sketches, including empty ones.
+    pub fn num_sketches(&self) -> u64 {
+        self.num_sketches
+    }
+
+    /// Returns the total number of retained entries across all input sketches.
+    pub fn num_entries(&self) -> u64 {
+        self.num_entries
+    }
+
+    /// Returns the number of input entries that were screened out by the union theta.
+    pub fn num_rejected(&self) -> u64 {
+        self.num_rejected
+    }
+
+    /// Returns the number of times the internal table was rebuilt to `k` entries, lowering theta.
+    pub fn num_downsamples(&self) -> u64 {
+        self.num_downsamples
+    }
+}
+
+impl ThetaUnion {
+    /// Creates a new builder for ThetaUnion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaUnion;
+    /// let union = ThetaUnion::builder().lg_k(10).build();
+    /// assert_eq!(union.lg_k(), 10);
+    /// ```
+    pub fn builder() -> ThetaUnionBuilder {
+        ThetaUnionBuilder::default()
+    }
+
+    /// Updates the union with a given sketch.
+    ///
+    /// Empty sketches are ignored (but counted in the statistics). A rejected sketch leaves the
+    /// union and its statistics unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the seed hash of the sketch does not match the seed of the union.
+    pub fn update<S: ThetaSketchView>(&mut self, sketch: &S) -> Result<(), Error> {
+        if !sketch.is_empty() && sketch.seed_hash() != self.table.seed_hash() {
+            return Err(Error::seed_hash_mismatch(
+                self.table.seed_hash(),
+                sketch.seed_hash(),
+            ));
+        }
+        self.stats.num_sketches += 1;
+        if sketch.is_empty() {
+            return Ok(());
+        }
+
+        let num_retained = sketch.num_retained() as u64;
+        self.stats.num_entries += num_retained;
+        self.table.set_empty(false);
+        self.union_theta = self.union_theta.min(sketch.theta64());
+        // grow once up front rather than rehashing repeatedly while inserting
+        self.table.reserve(sketch.num_retained());
+
+        for (index, hash) in sketch.iter().enumerate() {
+            if hash < self.union_theta && hash < self.table.theta() {
+                let theta = self.table.theta();
+                self.table.try_insert_hash(hash);
+                if self.table.theta() < theta {
+                    self.stats.num_downsamples += 1;
+                }
+            } else if sketch.is_ordered() {
+                // all the remaining entries are screened out as well
+                self.stats.num_rejected += num_retained - index as u64;
+                break;
+            } else {
+                self.stats.num_rejected += 1;
+            }
+        }
+        self.union_theta = self.union_theta.min(self.table.theta());
+        Ok(())
+    }
+
+    /// Updates the union with every sketch of the given sequence.
+    ///
+    /// This is the reduce step for merging many per-partition sketches at once. All inputs are
+    /// merged into the single hash table of the union, which grows at most once per input and
+    /// never beyond its maximum size, so the cost is dominated by the entries themselves.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the seed hash of a sketch does not match the seed of the union. The
+    /// sketches preceding the failing one remain merged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// # use datasketches::theta::ThetaUnion;
+    /// let partitions: Vec<ThetaSketch> = (0..10)
+    ///     .map(|p| {
+    ///         let mut sketch = ThetaSketch::builder().build();
+    ///         for i in 0..100 {
+    ///             sketch.update(p * 50 + i);
+    ///         }
+    ///         sketch
+    ///     })
+    ///     .collect();
+    ///
+    /// let mut union = ThetaUnion::builder().build();
+    /// union.union_many(&partitions).unwrap();
+    /// assert_eq!(union.result().estimate(), 550.0);
+    /// assert_eq!(union.stats().num_sketches(), 10);
+    /// ```
+    pub fn union_many<'a, S, I>(&mut self, sketches: I) -> Result<(), Error>
+    where
+        S: ThetaSketchView + 'a,
+        I: IntoIterator<Item = &'a S>,
+    {
+        for sketch in sketches {
+            self.update(sketch)?;
+        }
+        Ok(())
+    }
+
+    /// Updates the union with a serialized compact sketch.
+    ///
+    /// Images of serial version 3 and 4 are [wrapped](WrappedCompactThetaSketch) and merged
+    /// straight from `bytes`, without allocating a [`CompactThetaSketch`] for them, which is the
+    /// hot path of services that roll up stored sketches. Images of the older serial versions are
+    /// deserialized first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` is not a valid compact theta sketch, or if its seed hash does
+    /// not match the seed of the union. The union is unchanged on error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// # use datasketches::theta::ThetaUnion;
+    /// let mut sketch = ThetaSketch::builder().build();
+    /// sketch.update("apple");
+    /// sketch.update("banana");
+    ///
+    /// let mut union = ThetaUnion::builder().build();
+    /// union.update_bytes(&sketch.compact(true).serialize()).unwrap();
+    /// union.update_bytes(&sketch.compact(true).serialize_compressed()).unwrap();
+    /// assert!(union.update_bytes(&[1, 2, 3]).is_err());
+    /// assert_eq!(union.result().estimate(), 2.0);
+    /// ```
+    pub fn update_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let seed = self.table.hash_seed();
+        match WrappedCompactThetaSketch::wrap_with_seed(bytes, seed) {
+            Ok(wrapped) => self.update(&wrapped),
+            Err(err) if err.kind() == ErrorKind::UnsupportedVersion => {
+                self.update(&CompactThetaSketch::deserialize_with_seed(bytes, seed)?)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Updates the union with an optional sketch.
+    ///
+    /// `None` is treated like an empty sketch: it leaves the union unchanged and is not counted
+    /// in the statistics.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the seed hash of the sketch does not match the seed of the union.
+    pub fn update_opt<S: ThetaSketchView>(&mut self, sketch: Option<&S>) -> Result<(), Error> {
+        match sketch {
+            Some(sketch) => self.update(sketch),
+            None => Ok(()),
+        }
+    }
+
+    /// Consumes the union, merges in an optional sketch and returns the union.
+    ///
+    /// This is a fold step for reducing sequences that may contain missing sketches, with a
+    /// freshly built union as the identity element.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the seed hash of the sketch does not match the seed of the union.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// # use datasketches::theta::ThetaUnion;
+    /// let mut a = ThetaSketch::empty();
+    /// a.update("apple");
+    /// let mut b = ThetaSketch::empty();
+    /// b.update("banana");
+    /// let per_key = [Some(a), None, Some(b)];
+    ///
+    /// let union = per_key
+    ///     .iter()
+    ///     .try_fold(ThetaUnion::builder().build(), |union, sketch| {
+    ///         union.union_opt(sketch.as_ref())
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(union.result().estimate(), 2.0);
+    /// assert_eq!(union.stats().num_sketches(), 2);
+    /// ```
+    pub fn union_opt<S: ThetaSketchView>(mut self, sketch: Option<&S>) -> Result<Self, Error> {
+        self.update_opt(sketch)?;
+        Ok(self)
+    }
+
+    /// Returns the union result as a compact theta sketch (ordered).
+    pub fn result(&self) -> CompactThetaSketch {
+        self.result_with_ordered(true)
+    }
+
+    /// Returns the union result as a compact theta sketch.
+    ///
+    /// If `ordered` is true, retained hash values are sorted in ascending order.
+    pub fn result_with_ordered(&self, ordered: bool) -> CompactThetaSketch {
+        let seed_hash = self.table.seed_hash();
+        if self.table.is_empty() {
+            return CompactThetaSketch::from_parts(vec![], MAX_THETA, seed_hash, true, true);
+        }
+
+        let mut theta = self.union_theta.min(self.table.theta());
+        let mut entries: Vec<u64> = self.table.iter().filter(|&hash| hash < theta).collect();
+        let k = 1usize << self.table.lg_nom_size();
+        if entries.len() > k {
+            let (_, kth, _) = entries.select_nth_unstable(k);
+            theta = *kth;
+            entries.truncate(k);
+        }
+        if ordered {
+            entries.sort_unstable();
+        }
+        CompactThetaSketch::from_parts(entries, theta, seed_hash, ordered, false)
+    }
+
+    /// Returns the union result as a compact theta sketch, sorted if `ordered` is true.
+    ///
+    /// Same as [`result_with_ordered`](Self::result_with_ordered), named after
+    /// [`ThetaSketch::compact`](crate::theta::ThetaSketch::compact). Prefer ordered output when
+    /// the result feeds an intersection or another union: both stop scanning an ordered sketch
+    /// at the first hash above their theta.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datasketches::theta::ThetaSketch;
+    /// # use datasketches::theta::ThetaUnion;
+    /// let mut sketch = ThetaSketch::builder().build();
+    /// sketch.update("apple");
+    /// sketch.update("banana");
+    ///
+    /// let mut union = ThetaUnion::builder().build();
+    /// union.update(&sketch).unwrap();
+    /// let result = union.to_compact(true);
+    /// assert!(result.is_ordered());
+    /// assert_eq!(result.num_retained(), 2);
+    /// ```
+    pub fn to_compact(&self, ordered: bool) -> CompactThetaSketch {
+        self.result_with_ordered(ordered)
+    }
+
+    /// Returns the statistics collected over the input sketches.
+    pub fn stats(&self) -> ThetaUnionStats {
+        self.stats
+    }
+
+    /// Returns the configured log2 of the nominal number of entries.
+    pub fn lg_k(&self) -> u8 {
+        self.table.lg_nom_size()
+    }
+
+    /// Returns the number of bytes of memory the union occupies, counting its whole hash table
+    /// as in [`ThetaSketch::memory_usage_bytes`](crate::theta::ThetaSketch::memory_usage_bytes).
+    pub fn memory_usage_bytes(&self) -> usize {
+        size_of::<Self>() + self.table.heap_size_bytes()
+    }
+
+    /// Resets the union to the empty state, clearing the statistics.
+    pub fn reset(&mut self) {
+        self.table.reset();
+        self.union_theta = self.table.theta();
+        self.stats = ThetaUnionStats::default();
+    }
+}
+
+/// Builder for ThetaUnion
+///
+/// Parameters that are not set default to the global [`Config`].
+#[derive(Debug, Clone)]
+pub struct ThetaUnionBuilder {
+    lg_k: u8,
+    resize_factor: ResizeFactor,
+    sampling_probability: f32,
+    pub(super) seed: u64,
+}
+
+impl Default for ThetaUnionBuilder {
+    fn default() -> Self {
+        let config = Config::global();
+        Self {
+            lg_k: config.lg_k(),
+            resize_factor: config.resize_factor(),
+            sampling_probability: 1.0,
+            seed: config.seed(),
+        }
+    }
+}
+
+impl ThetaUnionBuilder {
+    /// Set lg_k (log2 of nominal size k).
+    ///
+    /// # Panics
+    ///
+    /// If lg_k is not in range [5, 26]
+    pub fn lg_k(mut self, lg_k: u8) -> Self {
+        assert!(
+            (MIN_LG_K..=MAX_LG_K).contains(&lg_k),
+            "lg_k must be in [{}, {}], got {}",
+            MIN_LG_K,
+            MAX_LG_K,
+            lg_k
+        );
+        self.lg_k = lg_k;
+        self
+    }
+
+    /// Set resize factor of the internal hash table.
+    pub fn resize_factor(mut self, factor: ResizeFactor) -> Self {
+        self.resize_factor = factor;
+        self
+    }
+
+    /// Set sampling probability p.
+    ///
+    /// # Panics
+    ///
+    /// Panics if p is not in range `(0.0, 1.0]`
+    pub fn sampling_probability(mut self, probability: f32) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&probability) && probability > 0.0,
+            "sampling_probability must be in (0.0, 1.0], got {probability}"
+        );
+        self.sampling_probability = probability;
+        self
+    }
+
+    /// Set hash seed. Input sketches must be built with the same seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Build the ThetaUnion.
+    ///
+    /// The fallible version of this method is [`ThetaUnionBuilder::try_build`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the 16-bit hash of the configured seed is zero.
+    pub fn build(self) -> ThetaUnion {
+        let table = ThetaHashTable::new(
+            self.lg_k,
+            self.resize_factor,
+            self.sampling_probability,
+            self.seed,
+        );
+        ThetaUnion {
+            union_theta: table.theta(),
+            table,
+            stats: ThetaUnionStats::default(),
+        }
+    }
+
+    /// Build the ThetaUnion.
+    ///
+    /// The panicking version of this method is [`ThetaUnionBuilder::build`].
+    ///
+    /// # Errors
+    ///
+    /// If the 16-bit hash of the configured seed is zero.
+    pub fn try_build(self) -> Result<ThetaUnion, Error> {
+        try_compute_seed_hash(self.seed)?;
+        Ok(self.build())
+    }
+}
@@ -0,0 +1,478 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::fmt;
+use std::slice::ChunksExact;
+
+use crate::codec::SketchSlice;
+use crate::codec::assert::ensure_preamble_longs_in_range;
+use crate::codec::assert::insufficient_data;
+use crate::codec::family::Family;
+use crate::common::NumStdDev;
+use crate::common::binomial_bounds;
+use crate::error::Error;
+use crate::hash::DEFAULT_UPDATE_SEED;
+use crate::hash::try_compute_seed_hash;
+use crate::theta::CompactThetaSketch;
+use crate::theta::MAX_THETA;
+use crate::theta::ThetaSketchView;
+use crate::theta::serialization;
+use crate::util::bitpack::BLOCK_WIDTH;
+use crate::util::bitpack::BitUnpacker;
+use crate::util::bitpack::unpack_bits_block;
+
+/// A read-only view of a serialized compact theta sketch.
+///
+/// The wrapper reads the preamble on construction and answers queries from the borrowed bytes
+/// in place, without allocating or copying the retained hashes. Bit-packed images (serial
+/// version 4) are decoded on the fly while iterating. It implements [`ThetaSketchView`], so it
+/// can be fed to [`ThetaUnion`](crate::theta::ThetaUnion) and
+/// [`ThetaIntersection`](crate::theta::ThetaIntersection) directly.
+///
+/// Only the current serial versions 3 and 4 can be wrapped; use
+/// [`CompactThetaSketch::deserialize`] for older images. Unlike deserialization, wrapping does
+/// not check the retained hashes themselves, so a corrupted payload goes unnoticed.
+///
+/// # Examples
+///
+/// ```
+/// # use datasketches::theta::ThetaSketch;
+/// # use datasketches::theta::WrappedCompactThetaSketch;
+/// let mut sketch = ThetaSketch::builder().build();
+/// for i in 0..100 {
+///     sketch.update(i);
+/// }
+/// let bytes = sketch.compact(true).serialize_compressed();
+///
+/// let wrapped = WrappedCompactThetaSketch::wrap(&bytes).unwrap();
+/// assert_eq!(wrapped.estimate(), 100.0);
+/// assert_eq!(wrapped.iter().count(), 100);
+/// ```
+#[derive(Clone, Copy)]
+pub struct WrappedCompactThetaSketch<'a> {
+    theta: u64,
+    seed_hash: u16,
+    ordered: bool,
+    empty: bool,
+    num_entries: usize,
+    entries: Entries<'a>,
+}
+
+#[derive(Clone, Copy)]
+enum Entries<'a> {
+    /// Little-endian 64-bit hashes (serial version 3).
+    Uncompressed(&'a [u8]),
+    /// Bit-packed deltas between ascending hashes (serial version 4).
+    Compressed { bytes: &'a [u8], entry_bits: u8 },
+}
+
+impl<'a> WrappedCompactThetaSketch<'a> {
+    /// Wraps a serialized compact theta sketch built with the default seed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a compact theta sketch of serial version 3 or 4,
+    /// or are too short for the number of entries it declares.
+    pub fn wrap(bytes: &'a [u8]) -> Result<Self, Error> {
+        Self::wrap_with_seed(bytes, DEFAULT_UPDATE_SEED)
+    }
+
+    /// Wraps a serialized compact theta sketch built with the provided seed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a compact theta sketch of serial version 3 or 4,
+    /// are too short for the number of entries it declares, or if the sketch is not empty and
+    /// was built with a seed whose hash differs from that of `seed`.
+    pub fn wrap_with_seed(bytes: &'a [u8], seed: u64) -> Result<Self, Error> {
+        let mut cursor = SketchSlice::new(bytes);
+        let pre_longs = cursor
+            .read_u8()
+            .map_err(insufficient_data("preamble_longs"))?;
+        let ser_ver = cursor
+            .read_u8()
+            .map_err(insufficient_data("serial_version"))?;
+        let family_id = cursor.read_u8().map_err(insufficient_data("family_id"))?;
+        Family::THETA.validate_id(family_id)?;
+        ensure_preamble_longs_in_range(
+            Family::THETA.min_pre_longs..=Family::THETA.max_pre_longs,
+            pre_longs,
+        )?;
+
+        match ser_ver {
+            serialization::UNCOMPRESSED_SERIAL_VERSION => {
+                Self::wrap_v3(pre_longs, bytes, cursor, seed)
+            }
+            serialization::COMPRESSED_SERIAL_VERSION => {
+                Self::wrap_v4(pre_longs, bytes, cursor, seed)
+            }
+            _ => Err(Error::unsupported_version(format!(
+                "unsupported serial version for wrapping: expected 3 or 4, got {ser_ver}",
+            ))),
+        }
+    }
+
+    fn wrap_v3(
+        pre_longs: u8,
+        bytes: &'a [u8],
+        mut cursor: SketchSlice<'_>,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        cursor
+            .read_u16_le()
+            .map_err(insufficient_data("<unused_u16>"))?;
+        let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+        let seed_hash = cursor
+            .read_u16_le()
+            .map_err(insufficient_data("seed_hash"))?;
+        let ordered = (flags & serialization::FLAGS_IS_ORDERED) != 0;
+        if (flags & serialization::FLAGS_IS_EMPTY) != 0 {
+            return Ok(Self::empty(seed_hash, ordered));
+        }
+        check_seed_hash(seed_hash, seed)?;
+
+        let mut theta = MAX_THETA;
+        let num_entries = if pre_longs == 1 {
+            1
+        } else {
+            let num_entries = cursor
+                .read_u32_le()
+                .map_err(insufficient_data("num_entries"))?;
+            cursor
+                .read_u32_le()
+                .map_err(insufficient_data("<unused_u32>"))?;
+            if pre_longs > 2 {
+                let theta_long = cursor
+                    .read_u64_le()
+                    .map_err(insufficient_data("theta_long"))?;
+                theta = serialization::check_theta(theta_long)?;
+            }
+            num_entries as usize
+        };
+
+        let len = num_entries
+            .checked_mul(8)
+            .ok_or_else(|| Error::deserial("num_entries overflows usize"))?;
+        let entries = remaining_bytes(bytes, &cursor, len, "entries")?;
+        Ok(Self {
+            theta,
+            seed_hash,
+            ordered,
+            empty: false,
+            num_entries,
+            entries: Entries::Uncompressed(entries),
+        })
+    }
+
+    fn wrap_v4(
+        pre_longs: u8,
+        bytes: &'a [u8],
+        mut cursor: SketchSlice<'_>,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        let entry_bits = cursor.read_u8().map_err(insufficient_data("entry_bits"))?;
+        let num_entries_bytes = cursor.read_u8().map_err(insufficient_data("num_entries"))?;
+        let flags = cursor.read_u8().map_err(insufficient_data("flags"))?;
+        let seed_hash = cursor
+            .read_u16_le()
+            .map_err(insufficient_data("seed_hash"))?;
+        let ordered = (flags & serialization::FLAGS_IS_ORDERED) != 0;
+        if (flags & serialization::FLAGS_IS_EMPTY) != 0 {
+            return Ok(Self::empty(seed_hash, ordered));
+        }
+        check_seed_hash(seed_hash, seed)?;
+
+        let theta = if pre_longs > 1 {
+            let theta = cursor
+                .read_u64_le()
+                .map_err(insufficient_data("theta_long"))?;
+            serialization::check_theta(theta)?
+        } else {
+            MAX_THETA
+        };
+
+        if num_entries_bytes > 4 {
+            return Err(Error::bad_preamble(format!(
+                "num_entries_bytes must be at most 4, got {num_entries_bytes}"
+            )));
+        }
+        let mut num_entries = 0usize;
+        for i in 0..num_entries_bytes {
+            let entry_count_byte = cursor
+                .read_u8()
+                .map_err(insufficient_data("num_entries_byte"))?;
+            num_entries |= (entry_count_byte as usize) << ((i as usize) << 3);
+        }
+        if num_entries > 0 && !(1..=63).contains(&entry_bits) {
+            return Err(Error::bad_preamble(format!(
+                "entry_bits must be in [1, 63], got {entry_bits}"
+            )));
+        }
+
+        let full_blocks = num_entries / BLOCK_WIDTH;
+        let tail_bits = (num_entries % BLOCK_WIDTH) * entry_bits as usize;
+        let len = full_blocks * entry_bits as usize + tail_bits.div_ceil(8);
+        let entries = remaining_bytes(bytes, &cursor, len, "delta_blocks")?;
+        Ok(Self {
+            theta,
+            seed_hash,
+            ordered,
+            empty: false,
+            num_entries,
+            entries: Entries::Compressed {
+                bytes: entries,
+                entry_bits,
+            },
+        })
+    }
+
+    fn empty(seed_hash: u16, ordered: bool) -> Self {
+        Self {
+            theta: MAX_THETA,
+            seed_hash,
+            ordered,
+            empty: true,
+            num_entries: 0,
+            entries: Entries::Uncompressed(&[]),
+        }
+    }
+
+    /// Returns the cardinality estimate.
+    pub fn estimate(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let num_retained = self.num_retained() as f64;
+        if self.theta == MAX_THETA {
+            return num_retained;
+        }
+        num_retained / self.theta()
+    }
+
+    /// Returns theta as a fraction (0.0 to 1.0).
+    pub fn theta(&self) -> f64 {
+        self.theta as f64 / MAX_THETA as f64
+    }
+
+    /// Returns theta as u64.
+    pub fn theta64(&self) -> u64 {
+        self.theta
+    }
+
+    /// Returns true if this sketch is empty.
+    pub fn is_empty(&self) -> bool {
+        self.empty
+    }
+
+    /// Returns true if this sketch is in estimation mode.
+    pub fn is_estimation_mode(&self) -> bool {
+        self.theta < MAX_THETA
+    }
+
+    /// Returns the number of retained entries.
+    pub fn num_retained(&self) -> usize {
+        self.num_entries
+    }
+
+    /// Returns true if retained entries are ordered (sorted ascending).
+    pub fn is_ordered(&self) -> bool {
+        self.ordered
+    }
+
+    /// Returns the 16-bit seed hash.
+    pub fn seed_hash(&self) -> u16 {
+        self.seed_hash
+    }
+
+    /// Return iterator over retained hash values, read from the wrapped bytes.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + 'a {
+        match self.entries {
+            Entries::Uncompressed(bytes) => EntryIter::Uncompressed(bytes.chunks_exact(8)),
+            Entries::Compressed { bytes, entry_bits } => EntryIter::Compressed(CompressedEntries {
+                bytes,
+                entry_bits,
+                num_entries: self.num_entries,
+                index: 0,
+                previous: 0,
+                block: [0; BLOCK_WIDTH],
+            }),
+        }
+    }
+
+    /// Returns the approximate lower error bound given the specified number of Standard Deviations.
+    pub fn lower_bound(&self, num_std_dev: NumStdDev) -> f64 {
+        if !self.is_estimation_mode() {
+            return self.num_retained() as f64;
+        }
+        binomial_bounds::lower_bound(self.num_retained() as u64, self.theta(), num_std_dev)
+            .expect("compact theta should always be valid")
+    }
+
+    /// Returns the approximate upper error bound given the specified number of Standard Deviations.
+    pub fn upper_bound(&self, num_std_dev: NumStdDev) -> f64 {
+        if !self.is_estimation_mode() {
+            return self.num_retained() as f64;
+        }
+        binomial_bounds::upper_bound(
+            self.num_retained() as u64,
+            self.theta(),
+            num_std_dev,
+            self.is_empty(),
+        )
+        .expect("compact theta should always be valid")
+    }
+
+    /// Copies the wrapped sketch into an owned [`CompactThetaSketch`].
+    pub fn to_compact(&self) -> CompactThetaSketch {
+        CompactThetaSketch::from_parts(
+            self.iter().collect(),
+            self.theta,
+            self.seed_hash,
+            self.ordered,
+            self.empty,
+        )
+    }
+}
+
+impl fmt::Debug for WrappedCompactThetaSketch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WrappedCompactThetaSketch")
+            .field("theta", &self.theta)
+            .field("seed_hash", &self.seed_hash)
+            .field("ordered", &self.ordered)
+            .field("empty", &self.empty)
+            .field("num_entries", &self.num_entries)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ThetaSketchView for WrappedCompactThetaSketch<'_> {
+    fn seed_hash(&self) -> u16 {
+        WrappedCompactThetaSketch::seed_hash(self)
+    }
+
+    fn theta64(&self) -> u64 {
+        WrappedCompactThetaSketch::theta64(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        WrappedCompactThetaSketch::is_empty(self)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        WrappedCompactThetaSketch::iter(self)
+    }
+
+    fn num_retained(&self) -> usize {
+        WrappedCompactThetaSketch::num_retained(self)
+    }
+
+    fn is_ordered(&self) -> bool {
+        WrappedCompactThetaSketch::is_ordered(self)
+    }
+}
+
+fn check_seed_hash(seed_hash: u16, seed: u64) -> Result<(), Error> {
+    let expected_seed_hash = try_compute_seed_hash(seed)?;
+    if seed_hash != expected_seed_hash {
+        return Err(Error::seed_hash_mismatch(expected_seed_hash, seed_hash));
+    }
+    Ok(())
+}
+
+/// Returns the next `len` bytes after the cursor position without copying them.
+fn remaining_bytes<'a>(
+    bytes: &'a [u8],
+    cursor: &SketchSlice<'_>,
+    len: usize,
+    tag: &'static str,
+) -> Result<&'a [u8], Error> {
+    let start = bytes.len() - cursor.remaining();
+    start
+        .checked_add(len)
+        .and_then(|end| bytes.get(start..end))
+        .ok_or_else(|| Error::insufficient_data(tag))
+}
+
+enum EntryIter<'a> {
+    Uncompressed(ChunksExact<'a, u8>),
+    Compressed(CompressedEntries<'a>),
+}
+
+impl Iterator for EntryIter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        match self {
+            EntryIter::Uncompressed(chunks) => chunks
+                .next()
+                .map(|chunk| u64::from_le_bytes(chunk.try_into().expect("chunk has 8 bytes"))),
+            EntryIter::Compressed(entries) => entries.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            EntryIter::Uncompressed(chunks) => chunks.size_hint(),
+            EntryIter::Compressed(entries) => {
+                let remaining = entries.num_entries - entries.index;
+                (remaining, Some(remaining))
+            }
+        }
+    }
+}
+
+/// Decodes bit-packed deltas one block of [`BLOCK_WIDTH`] entries at a time.
+struct CompressedEntries<'a> {
+    bytes: &'a [u8],
+    entry_bits: u8,
+    num_entries: usize,
+    index: usize,
+    previous: u64,
+    block: [u64; BLOCK_WIDTH],
+}
+
+impl Iterator for CompressedEntries<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.index == self.num_entries {
+            return None;
+        }
+        let offset = self.index % BLOCK_WIDTH;
+        if offset == 0 {
+            let bits = self.entry_bits as usize;
+            let start = self.index / BLOCK_WIDTH * bits;
+            if self.index + BLOCK_WIDTH <= self.num_entries {
+                unpack_bits_block(
+                    &mut self.block,
+                    &self.bytes[start..start + bits],
+                    self.entry_bits,
+                );
+            } else {
+                let mut unpacker = BitUnpacker::new(&self.bytes[start..]);
+                for slot in self.block.iter_mut().take(self.num_entries - self.index) {
+                    *slot = unpacker.unpack_value(self.entry_bits);
+                }
+            }
+        }
+        // corrupted deltas wrap around rather than panic; wrapping does not validate entries
+        self.previous = self.previous.wrapping_add(self.block[offset]);
+        self.index += 1;
+        Some(self.previous)
+    }
+}
@@ -0,0 +1,640 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Big-endian bit packing of unsigned integers of arbitrary width.
+//!
+//! Values are written most significant bit first, each immediately after the previous one, so
+//! `n` values of `bits` bits occupy `ceil(n * bits / 8)` bytes. This is the layout of the
+//! entries of compressed theta sketches (serial version 4) in datasketches-java and
+//! datasketches-cpp.
+//!
+//! [`BitPacker`] and [`BitUnpacker`] stream values one at a time with any width in `0..=64`.
+//! [`pack_bits_block`] and [`unpack_bits_block`] produce the same bytes for a block of
+//! [`BLOCK_WIDTH`] values with one unrolled routine per width, which is considerably faster for
+//! long runs of values of the same width.
+//!
+//! # Examples
+//!
+//! ```
+//! use datasketches::util::bitpack::BitPacker;
+//! use datasketches::util::bitpack::BitUnpacker;
+//!
+//! let mut bytes = [0u8; 2];
+//! let mut packer = BitPacker::new(&mut bytes);
+//! packer.pack_value(5, 3);
+//! packer.pack_value(300, 9);
+//! assert_eq!(packer.bytes_used(), 2);
+//!
+//! let mut unpacker = BitUnpacker::new(&bytes);
+//! assert_eq!(unpacker.unpack_value(3), 5);
+//! assert_eq!(unpacker.unpack_value(9), 300);
+//! assert!(unpacker.try_unpack_value(8).is_err());
+//! ```
+
+use crate::error::Error;
+
+/// Number of values packed by [`pack_bits_block`] and unpacked by [`unpack_bits_block`].
+pub const BLOCK_WIDTH: usize = 8;
+
+#[inline]
+fn low_bit_to_byte_mask(bits: u8) -> u8 {
+    if bits >= u8::BITS as u8 {
+        u8::MAX
+    } else {
+        (1u8 << bits) - 1
+    }
+}
+
+/// Packs values into a byte buffer with arbitrary bit widths.
+///
+/// Bytes that have not been written to must be zero when the packer starts on a partially
+/// filled byte, which holds for a freshly zeroed buffer.
+#[derive(Debug)]
+pub struct BitPacker<'a> {
+    bytes: &'a mut [u8],
+    byte_index: usize,
+    byte_bit_used: u8,
+}
+
+impl<'a> BitPacker<'a> {
+    /// Creates a packer that writes from the start of `bytes`.
+    pub fn new(bytes: &'a mut [u8]) -> Self {
+        BitPacker {
+            bytes,
+            byte_index: 0,
+            byte_bit_used: 0,
+        }
+    }
+
+    /// Returns the number of bytes written to so far, including a partially filled last byte.
+    pub fn bytes_used(&self) -> usize {
+        if self.byte_bit_used == 0 {
+            self.byte_index
+        } else {
+            self.byte_index + 1
+        }
+    }
+
+    /// Packs the low `bits` bits of `value`; higher bits are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is greater than 64 or if the buffer cannot hold `bits` more bits.
+    pub fn pack_value(&mut self, value: u64, bits: u8) {
+        self.try_pack_value(value, bits).unwrap();
+    }
+
+    /// Packs the low `bits` bits of `value`; higher bits are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with `ErrorKind::InvalidArgument`, without writing anything, if `bits`
+    /// is greater than 64 or if the buffer cannot hold `bits` more bits.
+    pub fn try_pack_value(&mut self, value: u64, bits: u8) -> Result<(), Error> {
+        check_bits(bits)?;
+        let bits_used = self.byte_index * 8 + self.byte_bit_used as usize;
+        if bits_used + bits as usize > self.bytes.len() * 8 {
+            return Err(Error::invalid_argument(format!(
+                "buffer of {} bytes cannot hold {bits} more bits after {bits_used}",
+                self.bytes.len()
+            )));
+        }
+        self.pack_unchecked(value, bits);
+        Ok(())
+    }
+
+    fn pack_unchecked(&mut self, value: u64, mut bits: u8) {
+        debug_assert!(self.byte_bit_used < 8, "offset must be in [0, 7]");
+
+        if self.byte_bit_used > 0 {
+            let remain_bits = 8 - self.byte_bit_used;
+            let remain_mask = low_bit_to_byte_mask(remain_bits);
+
+            // Fast path: there is enough space for remain byte to pack whole value.
+            if bits < remain_bits {
+                self.bytes[self.byte_index] |=
+                    ((value << (remain_bits - bits)) as u8) & remain_mask;
+                self.byte_bit_used += bits;
+                return;
+            }
+
+            // Pack highest remain_bits bit first.
+            self.bytes[self.byte_index] |= ((value >> (bits - remain_bits)) as u8) & remain_mask;
+            bits -= remain_bits;
+            self.byte_bit_used = 0;
+            self.byte_index += 1;
+        }
+
+        while bits >= 8 {
+            self.bytes[self.byte_index] = (value >> (bits - 8)) as u8;
+            self.byte_index += 1;
+            bits -= 8;
+        }
+
+        if bits > 0 {
+            self.bytes[self.byte_index] = (value << (8 - bits)) as u8;
+            self.byte_bit_used = bits;
+        }
+    }
+}
+
+/// Unpacks values from a byte buffer with arbitrary bit widths.
+#[derive(Debug, Clone)]
+pub struct BitUnpacker<'a> {
+    bytes: &'a [u8],
+    byte_index: usize,
+    byte_bit_used: u8,
+}
+
+impl<'a> BitUnpacker<'a> {
+    /// Creates an unpacker that reads from the start of `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            byte_index: 0,
+            byte_bit_used: 0,
+        }
+    }
+
+    /// Returns the number of bytes read from so far, including a partially consumed last byte.
+    pub fn bytes_used(&self) -> usize {
+        if self.byte_bit_used == 0 {
+            self.byte_index
+        } else {
+            self.byte_index + 1
+        }
+    }
+
+    /// Unpacks the next `bits` bits as an unsigned value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is greater than 64 or if fewer than `bits` bits remain.
+    pub fn unpack_value(&mut self, bits: u8) -> u64 {
+        self.try_unpack_value(bits).unwrap()
+    }
+
+    /// Unpacks the next `bits` bits as an unsigned value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without consuming anything, if `bits` is greater than 64
+    /// (`ErrorKind::InvalidArgument`) or if fewer than `bits` bits remain
+    /// (`ErrorKind::Truncated`).
+    pub fn try_unpack_value(&mut self, bits: u8) -> Result<u64, Error> {
+        check_bits(bits)?;
+        let bits_used = self.byte_index * 8 + self.byte_bit_used as usize;
+        if bits_used + bits as usize > self.bytes.len() * 8 {
+            return Err(Error::insufficient_data(format!(
+                "{bits} bits requested after {bits_used} of {}",
+                self.bytes.len() * 8
+            )));
+        }
+        Ok(self.unpack_unchecked(bits))
+    }
+
+    fn unpack_unchecked(&mut self, mut bits: u8) -> u64 {
+        if bits == 0 {
+            return 0;
+        }
+
+        let avail_bits = 8 - self.byte_bit_used;
+        let chunk_bits = avail_bits.min(bits);
+        let chunk_mask = low_bit_to_byte_mask(chunk_bits);
+
+        let mut value =
+            ((self.bytes[self.byte_index] >> (avail_bits - chunk_bits)) & chunk_mask) as u64;
+        // Use all remain bits for current byte.
+        if chunk_bits == avail_bits {
+            self.byte_index += 1;
+        }
+        self.byte_bit_used = (self.byte_bit_used + chunk_bits) & 7;
+        bits -= chunk_bits;
+
+        while bits >= 8 {
+            value = (value << 8) | self.bytes[self.byte_index] as u64;
+            self.byte_index += 1;
+            bits -= 8;
+        }
+
+        if bits > 0 {
+            value = (value << bits) | (self.bytes[self.byte_index] >> (8 - bits)) as u64;
+            self.byte_bit_used = bits;
+        }
+
+        value
+    }
+}
+
+fn check_bits(bits: u8) -> Result<(), Error> {
+    if bits > 64 {
+        return Err(Error::invalid_argument(format!(
+            "bits must be at most 64, got {bits}"
+        )));
+    }
+    Ok(())
+}
+
+/// Calls `$kernel::<BITS>($values, $bytes)` with `BITS` set to the runtime width `$bits` in
+/// `1..=63`, so that each width gets its own fully unrolled copy of the kernel.
+macro_rules! dispatch_bits {
+    ($bits:expr, $kernel:ident($values:expr, $bytes:expr)) => {
+        dispatch_bits!(@arms $bits, $kernel($values, $bytes); 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61 62 63)
+    };
+    (@arms $bits:expr, $kernel:ident($values:expr, $bytes:expr); $($width:literal)*) => {
+        match $bits {
+            $($width => $kernel::<$width>($values, $bytes),)*
+            _ => unreachable!(),
+        }
+    };
+}
+
+/// Expands `$body` once for each value index `$i` of a block, so that the kernels do not rely on
+/// the unrolling heuristics of the compiler for their outer loop.
+macro_rules! for_each_in_block {
+    ($i:ident => $body:block) => {
+        for_each_in_block!(@each $i => $body; 0 1 2 3 4 5 6 7)
+    };
+    (@each $i:ident => $body:block; $($n:literal)*) => {
+        $({
+            let $i: usize = $n;
+            $body
+        })*
+    };
+}
+
+/// Packs eight values of `BITS` bits into exactly `BITS` bytes.
+///
+/// Each byte is computed from the values overlapping it once the value covering its last bit is
+/// reached. All bounds are constants for a given `BITS`, so this compiles to the same
+/// straight-line shifts and masks as a hand-expanded kernel.
+#[inline(always)]
+fn pack_block<const BITS: usize>(values: &[u64; BLOCK_WIDTH], bytes: &mut [u8]) {
+    let bytes: &mut [u8; BITS] = (&mut bytes[..BITS]).try_into().unwrap();
+    let mask = (1u64 << BITS) - 1;
+    for_each_in_block!(i => {
+        for j in i * BITS / 8..(i + 1) * BITS / 8 {
+            let byte_end = (j + 1) * 8;
+            let mut packed = 0u64;
+            for k in j * 8 / BITS..=i {
+                let value_end = (k + 1) * BITS;
+                let value = values[k] & mask;
+                packed |= if value_end <= byte_end {
+                    value << (byte_end - value_end)
+                } else {
+                    value >> (value_end - byte_end)
+                };
+            }
+            bytes[j] = packed as u8;
+        }
+    });
+}
+
+/// Unpacks eight values of `BITS` bits from exactly `BITS` bytes.
+///
+/// Each value is computed independently from the bytes overlapping it.
+#[inline(always)]
+fn unpack_block<const BITS: usize>(values: &mut [u64; BLOCK_WIDTH], bytes: &[u8]) {
+    let bytes: &[u8; BITS] = bytes[..BITS].try_into().unwrap();
+    let mask = (1u64 << BITS) - 1;
+    for_each_in_block!(i => {
+        let value_end = (i + 1) * BITS;
+        let mut unpacked = 0u64;
+        for j in i * BITS / 8..value_end.div_ceil(8) {
+            let byte_end = (j + 1) * 8;
+            let byte = u64::from(bytes[j]);
+            unpacked |= if byte_end <= value_end {
+                byte << (value_end - byte_end)
+            } else {
+                byte >> (byte_end - value_end)
+            };
+        }
+        values[i] = unpacked & mask;
+    });
+}
+
+/// Packs a block of [`BLOCK_WIDTH`] values of `bits` bits each into the first `bits` bytes of
+/// `bytes`, producing the same bytes as [`BitPacker`].
+///
+/// # Panics
+///
+/// * Panics if `values.len()` is not equal to `BLOCK_WIDTH`.
+/// * Panics if `bits` is not in the range `1..=63`.
+/// * Panics if `bytes.len()` is less than `bits`.
+pub fn pack_bits_block(values: &[u64], bytes: &mut [u8], bits: u8) {
+    let values: &[u64; BLOCK_WIDTH] = values.try_into().expect("values length must be 8");
+    assert!(
+        (1..=63).contains(&bits),
+        "wrong number of bits in pack_bits_block8: {bits}"
+    );
+    assert!(bytes.len() >= bits as usize, "output buffer too small");
+    dispatch_bits!(bits, pack_block(values, bytes))
+}
+
+/// Unpacks a block of [`BLOCK_WIDTH`] values of `bits` bits each from the first `bits` bytes of
+/// `bytes`, reading the same layout as [`BitUnpacker`].
+///
+/// # Panics
+///
+/// * Panics if `values.len()` is not equal to `BLOCK_WIDTH`.
+/// * Panics if `bits` is not in the range `1..=63`.
+/// * Panics if `bytes.len()` is less than `bits`.
+pub fn unpack_bits_block(values: &mut [u64], bytes: &[u8], bits: u8) {
+    let values: &mut [u64; BLOCK_WIDTH] = values.try_into().expect("values length must be 8");
+    assert!(
+        (1..=63).contains(&bits),
+        "wrong number of bits in unpack_bits_block8: {bits}"
+    );
+    assert!(bytes.len() >= bits as usize, "output buffer too small");
+    dispatch_bits!(bits, unpack_block(values, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // inverse golden ratio (0.618.. of max uint64_t)
+    const INVERSE_GOLDEN_RATIO: u64 = 0x9e37_79b9_7f4a_7c13;
+
+    #[test]
+    fn pack_unpack_bits() {
+        let mut value = 0xaa55_aa55_aa55_aa55u64; // arbitrary starting value
+        for _m in 0..10000 {
+            for bits in 1u8..=63 {
+                let n = 8usize;
+                let mask = (1u64 << bits) - 1;
+                let mut input = vec![0u64; n];
+                for item in &mut input {
+                    *item = value & mask;
+                    value = value.wrapping_add(INVERSE_GOLDEN_RATIO);
+                }
+
+                let mut bytes = vec![0u8; n * size_of::<u64>()];
+                let mut packer = BitPacker::new(&mut bytes);
+                for i in 0..n {
+                    packer.pack_value(input[i], bits);
+                }
+
+                let mut output = vec![0u64; n];
+                let mut unpacker = BitUnpacker::new(&bytes);
+                for item in &mut output {
+                    *item = unpacker.unpack_value(bits);
+                }
+
+                for i in 0..n {
+                    assert_eq!(input[i], output[i]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pack_unpack_blocks() {
+        let mut value = 0xaa55_aa55_aa55_aa55u64; // arbitrary starting value
+        for _n in 0..10000 {
+            for bits in 1u8..=63 {
+                let mask = (1u64 << bits) - 1;
+                let mut input = vec![0u64; BLOCK_WIDTH];
+                for item in &mut input {
+                    *item = value & mask;
+                    value = value.wrapping_add(INVERSE_GOLDEN_RATIO);
+                }
+
+                let mut bytes = vec![0u8; bits as usize];
+                pack_bits_block(&input, &mut bytes, bits);
+
+                let mut output = vec![0u64; BLOCK_WIDTH];
+                unpack_bits_block(&mut output, &bytes, bits);
+
+                for i in 0..BLOCK_WIDTH {
+                    assert_eq!(input[i], output[i]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pack_bits_unpack_blocks() {
+        let mut value = 0u64; // arbitrary starting value
+        for _m in 0..10000 {
+            for bits in 1u8..=63 {
+                let mask = (1u64 << bits) - 1;
+                let mut input = vec![0u64; BLOCK_WIDTH];
+                for item in &mut input {
+                    *item = value & mask;
+                    value = value.wrapping_add(INVERSE_GOLDEN_RATIO);
+                }
+
+                let mut bytes = vec![0u8; bits as usize];
+                let mut packer = BitPacker::new(&mut bytes);
+                for i in 0..BLOCK_WIDTH {
+                    packer.pack_value(input[i], bits);
+                }
+
+                let mut output = vec![0u64; BLOCK_WIDTH];
+                unpack_bits_block(&mut output, &bytes, bits);
+
+                for i in 0..BLOCK_WIDTH {
+                    assert_eq!(input[i], output[i]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pack_blocks_unpack_bits() {
+        let mut value = 111u64; // arbitrary starting value
+        for _m in 0..10000 {
+            for bits in 1u8..=63 {
+                let mask = (1u64 << bits) - 1;
+                let mut input = vec![0u64; BLOCK_WIDTH];
+                for item in &mut input {
+                    *item = value & mask;
+                    value = value.wrapping_add(INVERSE_GOLDEN_RATIO);
+                }
+
+                let mut bytes = vec![0u8; bits as usize];
+                pack_bits_block(&input, &mut bytes, bits);
+
+                let mut output = vec![0u64; BLOCK_WIDTH];
+                let mut unpacker = BitUnpacker::new(&bytes);
+                for item in &mut output {
+                    *item = unpacker.unpack_value(bits);
+                }
+
+                for i in 0..BLOCK_WIDTH {
+                    assert_eq!(input[i], output[i]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pack_unpack_bits_64() {
+        let n = 8usize;
+        let mut value = 0xaa55_aa55_aa55_aa55u64;
+        let mut input = vec![0u64; n];
+        for item in &mut input {
+            *item = value;
+            value = value.wrapping_add(INVERSE_GOLDEN_RATIO);
+        }
+
+        let mut bytes = vec![0u8; n * size_of::<u64>()];
+        let mut packer = BitPacker::new(&mut bytes);
+        for &v in &input {
+            packer.pack_value(v, 64);
+        }
+        assert_eq!(packer.byte_index, 64);
+        assert_eq!(packer.byte_bit_used, 0);
+
+        let mut output = vec![0u64; n];
+        let mut unpacker = BitUnpacker::new(&bytes);
+        for item in &mut output {
+            *item = unpacker.unpack_value(64);
+        }
+        assert_eq!(unpacker.byte_index, 64);
+        assert_eq!(unpacker.byte_bit_used, 0);
+        assert_eq!(input, output);
+    }
+
+    #[test]
+    fn pack_unpack_bits_zero_width() {
+        let mut bytes = vec![0xabu8; 8];
+        let before = bytes.clone();
+
+        // Create packer starting at byte 3, bit 5 (simulating initial state)
+        let mut packer = BitPacker::new(&mut bytes);
+        // Pack 0 bits - should not change anything
+        packer.pack_value(0xdead_beef, 0);
+        // Since packer starts at 0, we just verify the bytes are unchanged
+        assert_eq!(packer.byte_index, 0);
+        assert_eq!(packer.byte_bit_used, 0);
+        assert_eq!(bytes, before);
+
+        let mut unpacker = BitUnpacker::new(&bytes);
+        let decoded = unpacker.unpack_value(0);
+        assert_eq!(decoded, 0);
+        assert_eq!(unpacker.byte_index, 0);
+        assert_eq!(unpacker.byte_bit_used, 0);
+    }
+
+    #[test]
+    fn pack_unpack_bits_cursor_alignment() {
+        let mut value = 0x0123_4567_89ab_cdefu64;
+        for bits in 1u8..=63 {
+            let mask = (1u64 << bits) - 1;
+            let mut input = [0u64; 8];
+            for item in &mut input {
+                *item = value & mask;
+                value = value.wrapping_add(INVERSE_GOLDEN_RATIO);
+            }
+
+            let mut bytes = vec![0u8; bits as usize];
+            let mut packer = BitPacker::new(&mut bytes);
+            for &v in &input {
+                packer.pack_value(v, bits);
+            }
+            assert_eq!(packer.byte_index, bits as usize);
+            assert_eq!(packer.byte_bit_used, 0);
+
+            let mut output = [0u64; 8];
+            let mut unpacker = BitUnpacker::new(&bytes);
+            for item in &mut output {
+                *item = unpacker.unpack_value(bits);
+            }
+            assert_eq!(unpacker.byte_index, bits as usize);
+            assert_eq!(unpacker.byte_bit_used, 0);
+            assert_eq!(input, output);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "values length must be 8")]
+    fn pack_bits_block_rejects_invalid_values_len() {
+        let input = [0u64; BLOCK_WIDTH + 1];
+        let mut bytes = [0u8; 1];
+        pack_bits_block(&input, &mut bytes, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "values length must be 8")]
+    fn unpack_bits_block_rejects_invalid_values_len() {
+        let mut output = [0u64; BLOCK_WIDTH - 1];
+        let bytes = [0u8; 1];
+        unpack_bits_block(&mut output, &bytes, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong number of bits in pack_bits_block8")]
+    fn pack_bits_block8_rejects_zero_bits() {
+        let input = [0u64; BLOCK_WIDTH];
+        let mut bytes = [0u8; 1];
+        pack_bits_block(&input, &mut bytes, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong number of bits in unpack_bits_block8")]
+    fn unpack_bits_block8_rejects_64_bits() {
+        let mut output = [0u64; BLOCK_WIDTH];
+        let bytes = [0u8; 64];
+        unpack_bits_block(&mut output, &bytes, 64);
+    }
+
+    #[test]
+    #[should_panic(expected = "output buffer too small")]
+    fn pack_bits_block_rejects_buffer_too_small() {
+        let input = [0u64; BLOCK_WIDTH];
+        let mut bytes = [0u8; 1];
+        pack_bits_block(&input, &mut bytes, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "output buffer too small")]
+    fn unpack_bits_block_rejects_buffer_too_small() {
+        let mut output = [0u64; BLOCK_WIDTH];
+        let bytes = [0u8; 1];
+        unpack_bits_block(&mut output, &bytes, 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn packer_panics_on_buffer_overflow() {
+        // Buffer is too small to hold 8 values of 8 bits each (needs 8 bytes)
+        let mut bytes = [0u8; 4];
+        let mut packer = BitPacker::new(&mut bytes);
+        // First 4 values fit (4 bytes)
+        for i in 0..4 {
+            packer.pack_value(i as u64, 8);
+        }
+        // This should panic - trying to write beyond buffer
+        packer.pack_value(0xdead_beef, 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unpacker_panics_on_buffer_underflow() {
+        // Buffer only has 4 bytes, but we try to unpack 8 values of 8 bits each
+        let bytes = [0xabu8; 4];
+        let mut unpacker = BitUnpacker::new(&bytes);
+        // First 4 values succeed (4 bytes)
+        for _ in 0..4 {
+            unpacker.unpack_value(8);
+        }
+        // This should panic - trying to read beyond buffer
+        unpacker.unpack_value(8);
+    }
+}
@@ -0,0 +1,144 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+#![cfg(feature = "arrow")]
+
+use arrow_array::Array;
+use arrow_array::BinaryArray;
+use datasketches::arrow::from_binary_array;
+use datasketches::arrow::hll_estimate_array;
+use datasketches::arrow::hll_union_array;
+use datasketches::arrow::theta_estimate_array;
+use datasketches::arrow::theta_union_array;
+use datasketches::arrow::to_binary_array;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::hll::HllUnion;
+use datasketches::kll::KllSketch;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+use datasketches::theta::ThetaUnion;
+
+fn theta_sketch(start: u64, count: u64) -> ThetaSketch {
+    let mut sketch = ThetaSketch::builder().build();
+    for i in start..start + count {
+        sketch.update(i);
+    }
+    sketch
+}
+
+#[test]
+fn test_round_trip_with_nulls() {
+    let sketches: Vec<Option<KllSketch<f64>>> = vec![
+        Some(KllSketch::new(200)),
+        None,
+        Some({
+            let mut sketch = KllSketch::new(100);
+            for i in 0..1_000 {
+                sketch.update(i as f64);
+            }
+            sketch
+        }),
+    ];
+    let column = to_binary_array(sketches.iter().map(Option::as_ref));
+    assert_eq!(column.len(), 3);
+    assert_eq!(column.null_count(), 1);
+
+    let decoded = from_binary_array::<KllSketch<f64>>(&column).unwrap();
+    assert!(decoded[0].as_ref().unwrap().is_empty());
+    assert!(decoded[1].is_none());
+    assert_eq!(decoded[2].as_ref().unwrap().n(), 1_000);
+    assert_eq!(decoded[2].as_ref().unwrap().k(), 100);
+
+    let corrupted = BinaryArray::from_iter([Some(&b"oops"[..])]);
+    assert!(from_binary_array::<KllSketch<f64>>(&corrupted).is_err());
+}
+
+#[test]
+fn test_theta_kernels_mixed_formats() {
+    let update = theta_sketch(0, 10_000);
+    let compact = theta_sketch(5_000, 10_000).compact(true);
+    let compressed = theta_sketch(10_000, 10_000).compact(true);
+    let column = BinaryArray::from_iter([
+        Some(update.serialize()),
+        None,
+        Some(compact.serialize()),
+        Some(compressed.serialize_compressed()),
+    ]);
+
+    let mut union = ThetaUnion::builder().build();
+    union.update(&update).unwrap();
+    union.update(&compact).unwrap();
+    union.update(&compressed).unwrap();
+    let expected = union.result();
+
+    let result = CompactThetaSketch::deserialize(&theta_union_array(&column).unwrap()).unwrap();
+    assert_eq!(result.estimate(), expected.estimate());
+    assert_eq!(
+        result.iter().collect::<Vec<_>>(),
+        expected.iter().collect::<Vec<_>>()
+    );
+
+    let estimates = theta_estimate_array(&column).unwrap();
+    assert_eq!(estimates.len(), 4);
+    assert_eq!(estimates.value(0), update.estimate());
+    assert!(estimates.is_null(1));
+    assert_eq!(estimates.value(2), compact.estimate());
+    assert_eq!(estimates.value(3), compressed.estimate());
+}
+
+#[test]
+fn test_theta_kernels_empty_and_invalid() {
+    let column = BinaryArray::from_iter([None::<&[u8]>]);
+    let result = CompactThetaSketch::deserialize(&theta_union_array(&column).unwrap()).unwrap();
+    assert!(result.is_empty());
+
+    let column = BinaryArray::from_iter([Some(&[1u8, 3, 3][..])]);
+    assert!(theta_union_array(&column).is_err());
+    assert!(theta_estimate_array(&column).is_err());
+}
+
+#[test]
+fn test_hll_kernels() {
+    let sketches: Vec<HllSketch> = [(12, HllType::Hll4), (10, HllType::Hll8)]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (lg_k, hll_type))| {
+            let mut sketch = HllSketch::new(lg_k, hll_type);
+            for v in 0..5_000 {
+                sketch.update(i * 2_500 + v);
+            }
+            sketch
+        })
+        .collect();
+    let column = to_binary_array([Some(&sketches[0]), None, Some(&sketches[1])]);
+
+    let mut union = HllUnion::new(12);
+    union.union_many(&sketches);
+    let result = HllSketch::deserialize(&hll_union_array(&column, HllType::Hll8).unwrap()).unwrap();
+    assert_eq!(result.target_type(), HllType::Hll8);
+    assert_eq!(result.estimate(), union.estimate());
+
+    let estimates = hll_estimate_array(&column).unwrap();
+    assert_eq!(estimates.value(0), sketches[0].estimate());
+    assert!(estimates.is_null(1));
+    assert_eq!(estimates.value(2), sketches[1].estimate());
+
+    let column = BinaryArray::from_iter([Some(&[0u8; 3][..])]);
+    assert!(hll_union_array(&column, HllType::Hll4).is_err());
+    assert!(hll_estimate_array(&column).is_err());
+}
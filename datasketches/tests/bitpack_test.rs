@@ -0,0 +1,114 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::error::ErrorKind;
+use datasketches::util::bitpack::BLOCK_WIDTH;
+use datasketches::util::bitpack::BitPacker;
+use datasketches::util::bitpack::BitUnpacker;
+use datasketches::util::bitpack::pack_bits_block;
+use datasketches::util::bitpack::unpack_bits_block;
+
+#[test]
+fn test_stream_mixed_widths() {
+    let values = [
+        (1u64, 1u8),
+        (0, 0),
+        (0x1f, 5),
+        (u64::MAX, 64),
+        (0x1234, 13),
+        (7, 3),
+    ];
+    let total_bits: usize = values.iter().map(|&(_, bits)| bits as usize).sum();
+    let mut bytes = vec![0u8; total_bits.div_ceil(8)];
+    let mut packer = BitPacker::new(&mut bytes);
+    for (value, bits) in values {
+        packer.pack_value(value, bits);
+    }
+    assert_eq!(packer.bytes_used(), total_bits.div_ceil(8));
+
+    let mut unpacker = BitUnpacker::new(&bytes);
+    for (value, bits) in values {
+        assert_eq!(unpacker.unpack_value(bits), value);
+    }
+    assert_eq!(unpacker.bytes_used(), bytes.len());
+}
+
+#[test]
+fn test_high_bits_are_ignored() {
+    let mut bytes = [0u8; 1];
+    let mut packer = BitPacker::new(&mut bytes);
+    packer.pack_value(0xff, 3);
+    packer.pack_value(0, 5);
+    assert_eq!(bytes, [0b1110_0000]);
+}
+
+#[test]
+fn test_try_pack_out_of_space() {
+    let mut bytes = [0u8; 2];
+    let mut packer = BitPacker::new(&mut bytes);
+    packer.pack_value(0x3ff, 10);
+    let err = packer.try_pack_value(0x7f, 7).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    // a failed call writes nothing, so a narrower value still fits
+    packer.try_pack_value(0x3f, 6).unwrap();
+    assert_eq!(bytes, [0xff, 0xff]);
+
+    let mut packer = BitPacker::new(&mut bytes);
+    let err = packer.try_pack_value(0, 65).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+}
+
+#[test]
+fn test_try_unpack_truncated() {
+    let bytes = [0xa5u8];
+    let mut unpacker = BitUnpacker::new(&bytes);
+    assert_eq!(unpacker.unpack_value(4), 0xa);
+    let err = unpacker.try_unpack_value(5).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Truncated);
+    assert_eq!(unpacker.try_unpack_value(4).unwrap(), 0x5);
+    assert_eq!(unpacker.try_unpack_value(0).unwrap(), 0);
+}
+
+#[test]
+#[should_panic(expected = "bits must be at most 64")]
+fn test_unpack_too_wide() {
+    BitUnpacker::new(&[0u8; 16]).unpack_value(65);
+}
+
+#[test]
+fn test_block_matches_stream() {
+    for bits in 1u8..=63 {
+        let mask = (1u64 << bits) - 1;
+        let values: Vec<u64> = (0..BLOCK_WIDTH as u64)
+            .map(|i| (i + 1).wrapping_mul(0x9e37_79b9_7f4a_7c13) & mask)
+            .collect();
+
+        let mut block = vec![0u8; bits as usize];
+        pack_bits_block(&values, &mut block, bits);
+
+        let mut streamed = vec![0u8; bits as usize];
+        let mut packer = BitPacker::new(&mut streamed);
+        for &value in &values {
+            packer.pack_value(value, bits);
+        }
+        assert_eq!(block, streamed, "bits = {bits}");
+
+        let mut unpacked = vec![0u64; BLOCK_WIDTH];
+        unpack_bits_block(&mut unpacked, &block, bits);
+        assert_eq!(unpacked, values, "bits = {bits}");
+    }
+}
@@ -50,3 +50,49 @@ pub fn serialization_test_data(sub_dir: &str, name: &str) -> PathBuf {
 
     path
 }
+
+/// The implementation that generated a file in the cross-language serialization corpus.
+#[allow(dead_code)] // false-positive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Java,
+    Cpp,
+}
+
+#[allow(dead_code)] // false-positive
+impl Language {
+    fn sub_dir(self) -> &'static str {
+        match self {
+            Language::Java => "java_generated_files",
+            Language::Cpp => "cpp_generated_files",
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            Language::Java => "java",
+            Language::Cpp => "cpp",
+        }
+    }
+}
+
+/// The stream lengths for which the corpus holds a `{family}_n{n}_{language}.sk` file.
+#[allow(dead_code)] // false-positive
+pub const CORPUS_NS: [usize; 8] = [0, 1, 10, 100, 1000, 10_000, 100_000, 1_000_000];
+
+/// Returns the path of the corpus file `{name}_{language}.sk`.
+#[allow(dead_code)] // false-positive
+pub fn corpus_file(language: Language, name: &str) -> PathBuf {
+    serialization_test_data(
+        language.sub_dir(),
+        &format!("{name}_{}.sk", language.suffix()),
+    )
+}
+
+/// Returns the corpus files `{family}_n{n}_{language}.sk` for each `n`, paired with `n`.
+#[allow(dead_code)] // false-positive
+pub fn corpus_files(language: Language, family: &str, ns: &[usize]) -> Vec<(usize, PathBuf)> {
+    ns.iter()
+        .map(|&n| (n, corpus_file(language, &format!("{family}_n{n}"))))
+        .collect()
+}
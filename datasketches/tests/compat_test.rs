@@ -0,0 +1,140 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::compat::TARGET_CPP_RELEASE;
+use datasketches::compat::TARGET_JAVA_RELEASE;
+use datasketches::compat::supported_formats;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::kll::KllSketch;
+use datasketches::kll::MultiKll;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::ThetaSketch;
+
+fn assert_writable(family: &str, serial_version: u8, family_id: u8) {
+    let found = supported_formats().iter().any(|format| {
+        format.family() == family
+            && format.family_id() == family_id
+            && format.serial_version() == Some(serial_version)
+            && format.can_read()
+            && format.can_write()
+    });
+    assert!(found, "{family} v{serial_version} (id {family_id}) missing");
+}
+
+// sketch images start with preamble size, serial version and family ID
+fn assert_image_writable(family: &str, bytes: &[u8]) {
+    assert_writable(family, bytes[1], bytes[2]);
+}
+
+#[test]
+fn test_written_formats_are_listed() {
+    let mut theta = ThetaSketch::builder().build();
+    assert_image_writable("QUICKSELECT", &theta.serialize());
+    for i in 0..1000 {
+        theta.update(i);
+    }
+    let compact = theta.compact(true);
+    assert_image_writable("THETA", &compact.serialize());
+    assert_image_writable("THETA", &compact.serialize_compressed());
+
+    for hll_type in [HllType::Hll4, HllType::Hll6, HllType::Hll8] {
+        let mut hll = HllSketch::new(10, hll_type);
+        for i in 0..10_000 {
+            hll.update(i);
+            if i == 0 || i == 100 || i == 9999 {
+                assert_image_writable("HLL", &hll.serialize());
+            }
+        }
+    }
+
+    let mut frequencies = FrequentItemsSketch::<i64>::new(8);
+    assert_image_writable("FREQUENCY", &frequencies.serialize());
+    frequencies.update(1);
+    assert_image_writable("FREQUENCY", &frequencies.serialize());
+
+    let mut kll = KllSketch::<f64>::new(200);
+    for i in 0..1000 {
+        assert_image_writable("KLL", &kll.serialize());
+        kll.update(i as f64);
+    }
+    let mut multi = MultiKll::<2>::new(200);
+    multi.update([1.0, 2.0]);
+    let bytes = multi.serialize();
+    assert_writable("KLL", bytes[0], bytes[1]);
+
+    let mut cpc = CpcSketch::new(11);
+    for i in 0..1000 {
+        cpc.update(i);
+    }
+    assert_image_writable("CPC", &cpc.serialize());
+
+    let mut countmin = CountMinSketch::<i64>::new(3, 5);
+    countmin.update(1);
+    assert_image_writable("COUNTMIN", &countmin.serialize());
+
+    let mut tdigest = TDigestMut::new(100);
+    for i in 0..1000 {
+        tdigest.update(i as f64);
+    }
+    assert_image_writable("TDIGEST", &tdigest.serialize());
+
+    let bloom = BloomFilterBuilder::with_accuracy(1000, 0.01).build();
+    assert_image_writable("BLOOMFILTER", &bloom.serialize());
+}
+
+#[test]
+fn test_formats_are_consistent() {
+    let formats = supported_formats();
+    for format in formats {
+        assert!(format.can_read() || format.can_write(), "{format:?}");
+        assert!(
+            formats.iter().all(|other| other.family() != format.family()
+                || other.family_id() == format.family_id())
+        );
+        let same = formats.iter().filter(|other| {
+            other.family() == format.family()
+                && other.serial_version() == format.serial_version()
+                && other.mode() == format.mode()
+        });
+        assert_eq!(same.count(), 1, "{format:?} listed twice");
+    }
+
+    let theta_read: Vec<_> = formats
+        .iter()
+        .filter(|format| format.family() == "THETA" && format.can_read())
+        .map(|format| format.serial_version())
+        .collect();
+    assert_eq!(theta_read, [Some(1), Some(2), Some(3), Some(4)]);
+}
+
+#[test]
+fn test_corpus_generator_pins_target_releases() {
+    let script = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../tools/generate_serialization_test_data.py"
+    ))
+    .unwrap();
+    for release in [TARGET_JAVA_RELEASE, TARGET_CPP_RELEASE] {
+        let pin = format!("branch = \"{release}\"");
+        assert!(script.contains(&pin), "generator does not pin {release}");
+    }
+}
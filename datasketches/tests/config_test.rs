@@ -0,0 +1,72 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::Config;
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::common::ResizeFactor;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+use datasketches::theta::ThetaUnion;
+
+// The configuration is process-wide, so everything touching it runs in this single test.
+#[test]
+fn test_global_config_reaches_builders() {
+    assert_eq!(Config::global(), Config::default());
+    let defaults = ThetaSketch::builder().build();
+    assert_eq!(defaults.lg_k(), 12);
+
+    let config = Config::new()
+        .with_lg_k(10)
+        .with_seed(42)
+        .with_resize_factor(ResizeFactor::X1);
+    config.set_global();
+    assert_eq!(Config::global(), config);
+
+    let mut sketch = ThetaSketch::builder().build();
+    assert_eq!(sketch.lg_k(), 10);
+    // X1 allocates the full table of 2 * k slots up front
+    assert_eq!(sketch.table_size(), 2048);
+    sketch.update("apple");
+
+    // the seed applies too, so default deserialization no longer matches
+    let bytes = sketch.compact(true).serialize();
+    assert!(CompactThetaSketch::deserialize(&bytes).is_err());
+    let restored = CompactThetaSketch::deserialize_with_seed(&bytes, Config::global().seed());
+    assert_eq!(restored.unwrap().estimate(), 1.0);
+
+    let mut union = ThetaUnion::builder().build();
+    assert_eq!(union.lg_k(), 10);
+    union.update(&sketch).unwrap();
+    assert_eq!(union.result().estimate(), 1.0);
+
+    // explicit builder calls still win
+    assert_eq!(ThetaSketch::builder().lg_k(14).build().lg_k(), 14);
+
+    let filter = BloomFilterBuilder::with_size(1024, 3).build();
+    assert_eq!(filter.seed(), 42);
+    let filter = BloomFilterBuilder::with_size(1024, 3).seed(7).build();
+    assert_eq!(filter.seed(), 7);
+
+    Config::default().set_global();
+    assert_eq!(ThetaSketch::builder().build().lg_k(), 12);
+}
+
+#[test]
+#[should_panic(expected = "lg_k must be in [5, 26], got 30")]
+fn test_invalid_lg_k() {
+    let _ = Config::new().with_lg_k(30);
+}
@@ -15,7 +15,12 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use datasketches::common::MergeableSketch;
+use datasketches::countmin::CountMinAggregation;
+use datasketches::countmin::CountMinChangeDetector;
 use datasketches::countmin::CountMinSketch;
+use datasketches::countmin::CountMinWrapper;
+use datasketches::error::ErrorKind;
 
 #[test]
 fn test_init_defaults() {
@@ -42,6 +47,35 @@ fn test_parameter_suggestions() {
     let buckets = CountMinSketch::<i64>::suggest_num_buckets(0.1);
     let sketch = CountMinSketch::<i64>::new(3, buckets);
     assert!(sketch.relative_error() <= 0.1);
+
+    // suggestions always make a valid sketch
+    assert_eq!(CountMinSketch::<i64>::suggest_num_buckets(0.95), 3);
+    assert_eq!(CountMinSketch::<i64>::suggest_num_buckets(10.0), 3);
+    assert_eq!(CountMinSketch::<i64>::suggest_num_hashes(0.0), 1);
+    let sketch = CountMinSketch::<i64>::new(
+        CountMinSketch::<i64>::suggest_num_hashes(0.0),
+        CountMinSketch::<i64>::suggest_num_buckets(10.0),
+    );
+    assert_eq!(sketch.num_buckets(), 3);
+}
+
+#[test]
+fn test_with_error() {
+    let sketch = CountMinSketch::<i64>::with_error(0.05, 0.99).unwrap();
+    assert_eq!(sketch.num_buckets(), 55);
+    assert_eq!(sketch.num_hashes(), 5);
+    assert!(sketch.relative_error() <= 0.05);
+
+    for relative_error in [0.0, -0.1, f64::NAN] {
+        assert!(CountMinSketch::<i64>::try_suggest_num_buckets(relative_error).is_err());
+        assert!(CountMinSketch::<i64>::with_error(relative_error, 0.99).is_err());
+    }
+    for confidence in [0.0, 1.0, -0.5, 1.5, f64::NAN] {
+        assert!(CountMinSketch::<i64>::try_suggest_num_hashes(confidence).is_err());
+        assert!(CountMinSketch::<i64>::with_error(0.05, confidence).is_err());
+    }
+    // a tiny error asks for a table larger than the supported limit
+    assert!(CountMinSketch::<i64>::with_error(1e-12, 0.99).is_err());
 }
 
 #[test]
@@ -229,6 +263,63 @@ fn test_merge_incompatible() {
     left.merge(&right);
 }
 
+#[test]
+#[should_panic(expected = "cannot merge CountMin sketches built with different seeds")]
+fn test_merge_different_seeds() {
+    let mut left = CountMinSketch::<i64>::with_seed(3, 64, 1);
+    let right = CountMinSketch::<i64>::with_seed(3, 64, 2);
+    left.merge(&right);
+}
+
+#[test]
+fn test_seeds_select_independent_hash_families() {
+    let mut left = CountMinSketch::<u64>::with_seed(3, 64, 1);
+    let mut right = CountMinSketch::<u64>::with_seed(3, 64, 2);
+    assert_ne!(left.hash_seeds(), right.hash_seeds());
+    assert_ne!(left.seed_hash(), right.seed_hash());
+    left.update("apple");
+    right.update("apple");
+    assert_ne!(left.serialize(), right.serialize());
+
+    // the seed is checked when images are read back, and when sketches are merged
+    let bytes = left.serialize();
+    let err = CountMinSketch::<u64>::deserialize_with_seed(&bytes, 2).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SeedHashMismatch);
+    let decoded = CountMinSketch::<u64>::deserialize_with_seed(&bytes, 1).unwrap();
+    assert_eq!(decoded.hash_seeds(), left.hash_seeds());
+    assert!(MergeableSketch::merge(&mut left, &right).is_err());
+}
+
+#[test]
+fn test_upper_bound_saturates() {
+    // relative error e / 3 ~= 0.9 pushes the bound past the range of u64
+    let mut sketch = CountMinSketch::<u64>::with_seed(3, 3, 123);
+    sketch.update_with_weight("x", u64::MAX / 4 * 3);
+    assert_eq!(sketch.lower_bound("x"), u64::MAX / 4 * 3);
+    assert_eq!(sketch.upper_bound("x"), u64::MAX);
+
+    let mut sketch = CountMinSketch::<i8>::with_seed(3, 3, 123);
+    sketch.update_with_weight("x", 100);
+    assert_eq!(sketch.upper_bound("x"), i8::MAX);
+}
+
+#[test]
+fn test_is_compatible() {
+    let sketch = CountMinSketch::<i64>::with_seed(3, 64, 7);
+    assert!(sketch.is_compatible(&CountMinSketch::with_seed(3, 64, 7)));
+    assert!(!sketch.is_compatible(&CountMinSketch::with_seed(2, 64, 7)));
+    assert!(!sketch.is_compatible(&CountMinSketch::with_seed(3, 32, 7)));
+    assert!(!sketch.is_compatible(&CountMinSketch::with_seed(3, 64, 8)));
+    assert!(
+        !sketch.is_compatible(&CountMinSketch::with_seed_and_aggregation(
+            3,
+            64,
+            7,
+            CountMinAggregation::Max
+        ))
+    );
+}
+
 #[test]
 fn test_increment_single_key_like_rust_count_min_sketch() {
     let mut sketch = CountMinSketch::<i64>::new(4, 32);
@@ -248,3 +339,361 @@ fn test_increment_multi_like_rust_count_min_sketch() {
         assert!(sketch.estimate(key) >= 9_000);
     }
 }
+
+#[test]
+fn test_max_aggregation() {
+    let mut sketch = CountMinSketch::<u64>::with_aggregation(4, 256, CountMinAggregation::Max);
+    assert_eq!(sketch.aggregation(), CountMinAggregation::Max);
+    assert!(sketch.is_empty());
+    for i in 0..100u64 {
+        sketch.update_with_weight(i, i * 10);
+        sketch.update_with_weight(i, i);
+    }
+    assert!(!sketch.is_empty());
+    for i in 0..100u64 {
+        let estimate = sketch.estimate(i);
+        assert!(estimate >= i * 10);
+        assert_eq!(sketch.upper_bound(i), estimate);
+        assert_eq!(sketch.lower_bound(i), 0);
+    }
+    // Heaviest key can never be overestimated.
+    assert_eq!(sketch.estimate(99u64), 990);
+}
+
+#[test]
+fn test_min_aggregation() {
+    let mut sketch = CountMinSketch::<i64>::with_aggregation(4, 256, CountMinAggregation::Min);
+    assert!(sketch.is_empty());
+    assert_eq!(sketch.estimate("unseen"), i64::MAX);
+    for i in 0..100i64 {
+        sketch.update_with_weight(i, i + 5);
+        sketch.update_with_weight(i, i - 5);
+    }
+    for i in 0..100i64 {
+        let estimate = sketch.estimate(i);
+        assert!(estimate <= i - 5);
+        assert_eq!(sketch.lower_bound(i), estimate);
+        assert_eq!(sketch.upper_bound(i), i64::MAX);
+    }
+    // Lightest key can never be underestimated.
+    assert_eq!(sketch.estimate(0i64), -5);
+}
+
+#[test]
+fn test_min_aggregation_records_zero_weight() {
+    let mut sketch = CountMinSketch::<u32>::with_aggregation(3, 64, CountMinAggregation::Min);
+    sketch.update_with_weight("a", 0);
+    assert!(!sketch.is_empty());
+    assert_eq!(sketch.estimate("a"), 0);
+}
+
+#[test]
+fn test_aggregation_merge() {
+    let mut left = CountMinSketch::<u64>::with_aggregation(3, 64, CountMinAggregation::Max);
+    let mut right = CountMinSketch::<u64>::with_aggregation(3, 64, CountMinAggregation::Max);
+    left.update_with_weight("a", 3);
+    right.update_with_weight("a", 8);
+    right.update_with_weight("b", 2);
+    left.merge(&right);
+    assert_eq!(left.estimate("a"), 8);
+    assert!(left.estimate("b") >= 2);
+    assert_eq!(left.total_weight(), 13);
+}
+
+#[test]
+#[should_panic]
+fn test_aggregation_merge_mismatch() {
+    let mut left = CountMinSketch::<u64>::with_aggregation(3, 64, CountMinAggregation::Max);
+    let right = CountMinSketch::<u64>::with_aggregation(3, 64, CountMinAggregation::Min);
+    left.merge(&right);
+}
+
+#[test]
+fn test_min_aggregation_halve_keeps_empty_cells() {
+    let mut sketch = CountMinSketch::<u64>::with_aggregation(3, 64, CountMinAggregation::Min);
+    sketch.update_with_weight("a", 10);
+    sketch.halve();
+    assert_eq!(sketch.estimate("a"), 5);
+    assert_eq!(sketch.estimate("unseen"), u64::MAX);
+}
+
+#[test]
+fn test_aggregation_serialize_deserialize() {
+    for aggregation in [
+        CountMinAggregation::Sum,
+        CountMinAggregation::Max,
+        CountMinAggregation::Min,
+    ] {
+        let empty = CountMinSketch::<i64>::with_aggregation(3, 32, aggregation);
+        let decoded = CountMinSketch::<i64>::deserialize(&empty.serialize()).unwrap();
+        assert_eq!(decoded, empty);
+
+        let mut sketch = CountMinSketch::<i64>::with_aggregation(3, 32, aggregation);
+        for i in 0..100i64 {
+            sketch.update_with_weight(i, i - 50);
+        }
+        let decoded = CountMinSketch::<i64>::deserialize(&sketch.serialize()).unwrap();
+        assert_eq!(decoded.aggregation(), aggregation);
+        assert_eq!(decoded, sketch);
+    }
+}
+
+#[test]
+fn test_deserialize_unknown_aggregation() {
+    let mut bytes = CountMinSketch::<i64>::new(3, 32).serialize();
+    bytes[4] = 9;
+    let err = CountMinSketch::<i64>::deserialize(&bytes).unwrap_err();
+    assert!(err.message().contains("unknown aggregation id"));
+}
+
+#[test]
+fn test_wrapper_matches_sketch() {
+    for aggregation in [
+        CountMinAggregation::Sum,
+        CountMinAggregation::Max,
+        CountMinAggregation::Min,
+    ] {
+        let mut sketch = CountMinSketch::<i64>::with_seed_and_aggregation(3, 32, 7, aggregation);
+        for i in 0..100i64 {
+            sketch.update_with_weight(i, i % 10);
+        }
+        let bytes = sketch.serialize();
+        let wrapper = CountMinWrapper::<i64>::with_seed(&bytes, 7).unwrap();
+        assert!(!wrapper.is_empty());
+        assert_eq!(wrapper.num_hashes(), 3);
+        assert_eq!(wrapper.num_buckets(), 32);
+        assert_eq!(wrapper.aggregation(), aggregation);
+        assert_eq!(wrapper.total_weight(), sketch.total_weight());
+        assert_eq!(wrapper.relative_error(), sketch.relative_error());
+        for i in 0..200i64 {
+            assert_eq!(wrapper.estimate(i), sketch.estimate(i));
+            assert_eq!(wrapper.lower_bound(i), sketch.lower_bound(i));
+            assert_eq!(wrapper.upper_bound(i), sketch.upper_bound(i));
+        }
+    }
+}
+
+#[test]
+fn test_wrapper_empty() {
+    for aggregation in [
+        CountMinAggregation::Sum,
+        CountMinAggregation::Max,
+        CountMinAggregation::Min,
+    ] {
+        let sketch = CountMinSketch::<u32>::with_aggregation(2, 5, aggregation);
+        let bytes = sketch.serialize();
+        let wrapper = CountMinWrapper::<u32>::new(&bytes).unwrap();
+        assert!(wrapper.is_empty());
+        assert_eq!(wrapper.total_weight(), 0);
+        assert_eq!(wrapper.estimate("apple"), sketch.estimate("apple"));
+        assert_eq!(wrapper.upper_bound("apple"), sketch.upper_bound("apple"));
+    }
+}
+
+#[test]
+fn test_wrapper_rejects_invalid_images() {
+    let mut sketch = CountMinSketch::<u64>::with_seed(3, 32, 123);
+    sketch.update("apple");
+    let bytes = sketch.serialize();
+
+    let err = CountMinWrapper::<u64>::new(&bytes).unwrap_err();
+    assert!(err.message().contains("incompatible seed hash"));
+
+    let err = CountMinWrapper::<u64>::with_seed(&bytes[..bytes.len() - 1], 123).unwrap_err();
+    assert!(err.message().contains("counts"));
+
+    // Counters are validated against the value type up front.
+    let mut sketch = CountMinSketch::<u64>::new(3, 32);
+    sketch.update_with_weight("apple", 1000);
+    let bytes = sketch.serialize();
+    let err = CountMinWrapper::<u8>::new(&bytes).unwrap_err();
+    assert!(err.message().contains("out of range"));
+}
+
+#[test]
+fn test_stream_round_trip() {
+    let empty = CountMinSketch::<u32>::new(3, 16);
+    let mut small = CountMinSketch::<i64>::with_seed(4, 64, 7);
+    small.update_with_weight("apple", 3);
+    // larger than the chunk handed to the writer at once
+    let mut large = CountMinSketch::<u64>::new(5, 4096);
+    for i in 0..10_000u64 {
+        large.update(i);
+    }
+
+    let mut stream = Vec::new();
+    empty.serialize_into_writer(&mut stream).unwrap();
+    small.serialize_into_writer(&mut stream).unwrap();
+    large.serialize_into_writer(&mut stream).unwrap();
+    assert_eq!(
+        stream,
+        [empty.serialize(), small.serialize(), large.serialize()].concat()
+    );
+
+    let mut reader = stream.as_slice();
+    let decoded = CountMinSketch::<u32>::deserialize_from_reader(&mut reader).unwrap();
+    assert!(decoded.is_empty());
+    let decoded = CountMinSketch::<i64>::deserialize_from_reader_with_seed(&mut reader, 7).unwrap();
+    assert_eq!(decoded.estimate("apple"), small.estimate("apple"));
+    let decoded = CountMinSketch::<u64>::deserialize_from_reader(&mut reader).unwrap();
+    assert_eq!(decoded.serialize(), large.serialize());
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn test_stream_truncated() {
+    let mut sketch = CountMinSketch::<i64>::new(2, 8);
+    sketch.update("apple");
+    let bytes = sketch.serialize();
+    for len in 0..bytes.len() {
+        assert!(CountMinSketch::<i64>::deserialize_from_reader(&mut &bytes[..len]).is_err());
+    }
+}
+
+#[test]
+fn test_f64_weights() {
+    let mut sketch = CountMinSketch::<f64>::with_seed(3, 128, 123);
+    assert!(sketch.is_empty());
+    sketch.update_with_weight("x", 1.5);
+    sketch.update_with_weight("x", 0.25);
+    sketch.update("y");
+    assert_eq!(sketch.estimate("x"), 1.75);
+    assert_eq!(sketch.total_weight(), 2.75);
+    assert_eq!(sketch.lower_bound("x"), 1.75);
+    assert_eq!(
+        sketch.upper_bound("x"),
+        1.75 + sketch.relative_error() * 2.75
+    );
+
+    let mut other = CountMinSketch::<f64>::with_seed(3, 128, 123);
+    other.update_with_weight("x", 0.5);
+    sketch.merge(&other);
+    assert_eq!(sketch.estimate("x"), 2.25);
+
+    let decoded = CountMinSketch::<f64>::deserialize_with_seed(&sketch.serialize(), 123).unwrap();
+    assert_eq!(decoded, sketch);
+
+    let mut max = CountMinSketch::<f64>::with_aggregation(3, 128, CountMinAggregation::Max);
+    max.update_with_weight("latency", 12.5);
+    max.update_with_weight("latency", 3.0);
+    assert_eq!(max.estimate("latency"), 12.5);
+}
+
+#[test]
+#[should_panic(expected = "weight must not be NaN")]
+fn test_f64_nan_weight_panics() {
+    let mut sketch = CountMinSketch::<f64>::new(3, 128);
+    sketch.update_with_weight("x", f64::NAN);
+}
+
+#[test]
+fn test_u32_counters_match_u64() {
+    let mut narrow = CountMinSketch::<u32>::with_seed(4, 256, 123);
+    let mut wide = CountMinSketch::<u64>::with_seed(4, 256, 123);
+    for i in 0..10_000u32 {
+        narrow.update_with_weight(i % 500, i % 7);
+        wide.update_with_weight(i % 500, (i % 7) as u64);
+    }
+    assert_eq!(narrow.total_weight() as u64, wide.total_weight());
+    for i in 0..500u32 {
+        assert_eq!(narrow.estimate(i) as u64, wide.estimate(i));
+    }
+    // images hold 64-bit cells regardless of the counter type
+    assert_eq!(
+        CountMinSketch::<u64>::deserialize_with_seed(&narrow.serialize(), 123).unwrap(),
+        wide
+    );
+}
+
+#[test]
+fn test_f64_metering_error_bound() {
+    // a metering workload: fractional dollar amounts per customer
+    let relative_error = 0.005;
+    let buckets = CountMinSketch::<f64>::suggest_num_buckets(relative_error);
+    let hashes = CountMinSketch::<f64>::suggest_num_hashes(0.99);
+    let mut sketch = CountMinSketch::<f64>::with_seed(hashes, buckets, 123);
+    assert!(sketch.relative_error() <= relative_error);
+
+    let mut truth = vec![0.0; 2_000];
+    for i in 0..20_000usize {
+        let customer = (i * 7919) % truth.len();
+        let amount = 0.01 * ((i % 997) as f64 + 1.0);
+        truth[customer] += amount;
+        sketch.update_with_weight(customer, amount);
+    }
+    let total: f64 = truth.iter().sum();
+    assert!((sketch.total_weight() - total).abs() < 1e-6 * total);
+
+    let max_error = relative_error * sketch.total_weight();
+    let mut violations = 0;
+    for (customer, &amount) in truth.iter().enumerate() {
+        let estimate = sketch.estimate(customer);
+        assert!(estimate >= amount - 1e-9 * total);
+        assert!(sketch.upper_bound(customer) >= estimate);
+        if estimate - amount > max_error {
+            violations += 1;
+        }
+    }
+    assert!(violations <= truth.len() / 100);
+}
+
+#[test]
+fn test_memory_usage() {
+    let sketch = CountMinSketch::<u64>::new(3, 1024);
+    let counters = 3 * 1024 * size_of::<u64>();
+    assert!(sketch.memory_usage_bytes() >= size_of::<CountMinSketch<u64>>() + counters);
+}
+
+#[test]
+fn test_estimate_delta() {
+    let mut previous = CountMinSketch::<u64>::new(5, 1024);
+    let mut current = CountMinSketch::<u64>::new(5, 1024);
+    for i in 0..2_000u64 {
+        previous.update_with_weight(i, 10);
+        current.update_with_weight(i, 10 + i % 2);
+    }
+    current.update_with_weight(7u64, 3_000);
+
+    let delta = current.estimate_delta(&previous, 7u64);
+    assert!((delta - 3_001.0).abs() <= 20.0, "{delta}");
+    // decreases are negative, even with unsigned counters
+    assert!(previous.estimate_delta(&current, 7u64) < -2_900.0);
+    for i in 100..200u64 {
+        let delta = current.estimate_delta(&previous, i);
+        assert!((delta - (i % 2) as f64).abs() <= 20.0, "{i}: {delta}");
+    }
+}
+
+#[test]
+#[should_panic(expected = "different configurations")]
+fn test_estimate_delta_incompatible() {
+    let previous = CountMinSketch::<u64>::new(5, 1024);
+    let current = CountMinSketch::<u64>::new(5, 512);
+    current.estimate_delta(&previous, 1);
+}
+
+#[test]
+fn test_change_detector() {
+    let mut previous = CountMinSketch::<i64>::new(5, 1024);
+    let mut current = CountMinSketch::<i64>::new(5, 1024);
+    for i in 0..500i64 {
+        previous.update_with_weight(i, 100);
+        current.update_with_weight(i, 100);
+    }
+    current.update_with_weight(3i64, 10_000);
+    current.update_with_weight(4i64, -5_000);
+    current.update_with_weight(1_000i64, 2_000);
+
+    let detector = CountMinChangeDetector::try_new(&previous, &current).unwrap();
+    let changes = detector.heavy_changes(0..2_000i64, 1_000.0);
+    let items: Vec<i64> = changes.iter().map(|(item, _)| *item).collect();
+    assert_eq!(items, vec![3, 4, 1_000]);
+    assert!(changes[1].1 < 0.0);
+
+    let other = CountMinSketch::<i64>::new(5, 512);
+    let err = CountMinChangeDetector::try_new(&previous, &other).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    let max = CountMinSketch::<i64>::with_aggregation(5, 1024, CountMinAggregation::Max);
+    let err = CountMinChangeDetector::try_new(&max, &max).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+}
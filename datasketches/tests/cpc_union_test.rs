@@ -179,3 +179,28 @@ fn test_lg_k_too_small() {
 fn test_lg_k_too_large() {
     let _ = CpcSketch::new(27);
 }
+
+#[test]
+fn test_union_many() {
+    let partitions: Vec<CpcSketch> = (0..20)
+        .map(|p| {
+            let mut sketch = CpcSketch::new(11);
+            for i in 0..(p * 100) {
+                sketch.update(p * 10_000 + i);
+            }
+            sketch
+        })
+        .collect();
+
+    let mut sequential = CpcUnion::new(11);
+    for sketch in &partitions {
+        sequential.update(sketch);
+    }
+    let mut bulk = CpcUnion::new(11);
+    bulk.union_many(&partitions);
+
+    assert_eq!(
+        bulk.to_sketch().serialize(),
+        sequential.to_sketch().serialize()
+    );
+}
@@ -15,6 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use datasketches::common::CardinalityFamily;
 use datasketches::common::NumStdDev;
 use datasketches::cpc::CpcSketch;
 use googletest::assert_that;
@@ -60,3 +61,57 @@ fn test_many_values() {
     assert_that!(sketch.estimate(), le(sketch.upper_bound(NumStdDev::One)));
     assert!(sketch.validate());
 }
+
+#[test]
+fn test_update_bytes_hashes_raw_bytes() {
+    let mut from_bytes = CpcSketch::new(10);
+    let mut from_values = CpcSketch::new(10);
+    for i in 0u64..5000 {
+        from_bytes.update_bytes(&i.to_le_bytes());
+        from_values.update(i);
+    }
+    assert_eq!(from_bytes.serialize(), from_values.serialize());
+}
+
+#[test]
+fn test_memory_usage() {
+    let mut sketch = CpcSketch::new(11);
+    assert!(sketch.memory_usage_bytes() >= size_of::<CpcSketch>());
+    for i in 0..10_000 {
+        sketch.update(i);
+    }
+    // the sliding window takes one byte per column once the sketch is dense
+    assert!(sketch.memory_usage_bytes() >= size_of::<CpcSketch>() + (1 << 11));
+}
+
+#[test]
+fn test_approximately_equal() {
+    let mut a = CpcSketch::new(11);
+    let mut b = CpcSketch::new(11);
+    for i in 0..10_000 {
+        a.update(i);
+        b.update(i);
+    }
+    assert!(a.approximately_equal(&b, 0.0));
+    let restored = CpcSketch::deserialize(&a.serialize()).unwrap();
+    assert!(restored.approximately_equal(&a, RELATIVE_ERROR_FOR_LG_K_11));
+    b.update(-1);
+    assert!(a.approximately_equal(&b, 0.01));
+    assert!(!a.approximately_equal(&CpcSketch::new(11), 0.5));
+    assert!(!a.approximately_equal(&CpcSketch::new(10), 1.0));
+    assert!(!a.approximately_equal(&CpcSketch::with_seed(11, 7), 1.0));
+}
+
+#[test]
+fn test_estimate_summary() {
+    let mut sketch = CpcSketch::new(10);
+    for i in 0..5_000 {
+        sketch.update(i);
+    }
+    let summary = sketch.estimate_summary(NumStdDev::Three);
+    assert_eq!(summary.family, CardinalityFamily::Cpc);
+    assert_eq!(summary.estimate, sketch.estimate());
+    assert_eq!(summary.lb, sketch.lower_bound(NumStdDev::Three));
+    assert_eq!(summary.ub, sketch.upper_bound(NumStdDev::Three));
+    assert_eq!(summary.retained, sketch.num_coupons() as u64);
+}
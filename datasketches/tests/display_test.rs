@@ -0,0 +1,122 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::kll::KllSketch;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::ThetaSketch;
+
+// returns the value of the field with the given label, checking the overall layout
+fn field(summary: &str, label: &str) -> String {
+    let lines: Vec<_> = summary.lines().collect();
+    assert!(lines[0].starts_with("### ") && lines[0].ends_with(" SUMMARY:"));
+    assert_eq!(lines[lines.len() - 1], "### END SKETCH SUMMARY");
+    lines
+        .iter()
+        .find_map(|line| {
+            let (name, value) = line.split_once(": ")?;
+            (name.trim() == label).then(|| value.to_string())
+        })
+        .unwrap_or_else(|| panic!("no field {label:?} in\n{summary}"))
+}
+
+#[test]
+fn test_theta() {
+    let mut sketch = ThetaSketch::builder().lg_k(10).build();
+    for i in 0..10_000 {
+        sketch.update(i);
+    }
+    let summary = sketch.to_string();
+    assert!(summary.starts_with("### UPDATABLE THETA SKETCH SUMMARY:\n"));
+    assert_eq!(field(&summary, "lg nominal size"), "10");
+    assert_eq!(field(&summary, "estimation mode"), "true");
+    assert_eq!(field(&summary, "estimate"), sketch.estimate().to_string());
+
+    let compact = sketch.compact(true);
+    let summary = compact.to_string();
+    assert!(summary.starts_with("### COMPACT THETA SKETCH SUMMARY:\n"));
+    assert_eq!(field(&summary, "ordered"), "true");
+    assert_eq!(
+        field(&summary, "num retained entries"),
+        compact.num_retained().to_string()
+    );
+}
+
+#[test]
+fn test_hll() {
+    let mut sketch = HllSketch::new(12, HllType::Hll6);
+    let summary = sketch.to_string();
+    assert_eq!(field(&summary, "current mode"), "LIST");
+    assert_eq!(field(&summary, "empty"), "true");
+    for i in 0..100_000 {
+        sketch.update(i);
+    }
+    let summary = sketch.to_string();
+    assert_eq!(field(&summary, "lg config k"), "12");
+    assert_eq!(field(&summary, "hll target"), "HLL_6");
+    assert_eq!(field(&summary, "current mode"), "HLL");
+    assert_eq!(field(&summary, "estimate"), sketch.estimate().to_string());
+}
+
+#[test]
+fn test_kll() {
+    let mut sketch = KllSketch::<f64>::new(200);
+    assert!(!sketch.to_string().contains("min item"));
+    for i in 1..=1000 {
+        sketch.update(i as f64);
+    }
+    let summary = sketch.to_string();
+    assert_eq!(field(&summary, "k"), "200");
+    assert_eq!(field(&summary, "n"), "1000");
+    assert_eq!(field(&summary, "min item"), "1");
+    assert_eq!(field(&summary, "max item"), "1000");
+}
+
+#[test]
+fn test_other_families() {
+    let mut frequencies = FrequentItemsSketch::<i64>::new(64);
+    frequencies.update_with_count(7, 3);
+    let summary = frequencies.to_string();
+    assert_eq!(field(&summary, "num active items"), "1");
+    assert_eq!(field(&summary, "total weight"), "3");
+
+    let mut cpc = CpcSketch::new(11);
+    cpc.update("apple");
+    assert_eq!(field(&cpc.to_string(), "num coupons"), "1");
+
+    let mut countmin = CountMinSketch::<u64>::new(3, 64);
+    countmin.update_with_weight("apple", 5);
+    assert_eq!(field(&countmin.to_string(), "total weight"), "5");
+
+    let mut tdigest = TDigestMut::new(100);
+    assert!(!tdigest.to_string().contains("min"));
+    tdigest.update(1.5);
+    tdigest.update(2.5);
+    let summary = tdigest.to_string();
+    assert_eq!(field(&summary, "min"), "1.5");
+    assert_eq!(field(&summary, "max"), "2.5");
+    assert_eq!(field(&tdigest.freeze().to_string(), "total weight"), "2");
+
+    let mut bloom = BloomFilterBuilder::with_accuracy(1000, 0.01).build();
+    bloom.insert("apple");
+    assert_eq!(field(&bloom.to_string(), "empty"), "false");
+}
@@ -0,0 +1,214 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::envelope::ItemType;
+use datasketches::envelope::SketchEnvelope;
+use datasketches::envelope::merge_envelope;
+use datasketches::error::ErrorKind;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+
+/// Ships an envelope through its serialized form, as a producer and a consumer would.
+fn ship(bytes: Vec<u8>, item_type: Option<ItemType>) -> SketchEnvelope {
+    let envelope = SketchEnvelope::new(bytes, item_type).unwrap();
+    let shipped = SketchEnvelope::deserialize(&envelope.serialize()).unwrap();
+    assert_eq!(shipped, envelope);
+    shipped
+}
+
+#[test]
+fn test_merge_theta_update_and_compact() {
+    let mut left = ThetaSketch::builder().lg_k(12).build();
+    let mut right = ThetaSketch::builder().lg_k(11).build();
+    for i in 0..1000 {
+        left.update(i);
+        right.update(i + 500);
+    }
+    let left = ship(left.serialize(), None);
+    let right = ship(right.compact(true).serialize(), None);
+    assert_eq!(left.info().lg_k(), Some(12));
+
+    let merged = merge_envelope(&left, &right).unwrap();
+    assert_eq!(merged.info().family(), "THETA");
+    let sketch = CompactThetaSketch::deserialize(merged.sketch_bytes()).unwrap();
+    assert_eq!(sketch.estimate(), 1500.0);
+}
+
+#[test]
+fn test_theta_lg_k_out_of_range_is_rejected() {
+    let mut sketch = ThetaSketch::builder().lg_k(12).build();
+    sketch.update(1);
+    let mut bytes = sketch.serialize();
+    bytes[3] = 30;
+    let err = SketchEnvelope::new(bytes, None).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let envelope = ship(sketch.serialize(), None);
+    let mut bytes = envelope.serialize();
+    let offset = bytes.len() - envelope.sketch_bytes().len();
+    bytes[offset + 3] = 30;
+    let err = SketchEnvelope::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_merge_hll_keeps_left_type() {
+    let mut left = HllSketch::new(10, HllType::Hll4);
+    let mut right = HllSketch::new(12, HllType::Hll8);
+    for i in 0..100 {
+        left.update(i);
+        right.update(i + 50);
+    }
+    let merged = merge_envelope(
+        &ship(left.serialize(), None),
+        &ship(right.serialize(), None),
+    )
+    .unwrap();
+    assert_eq!(merged.info().lg_k(), Some(10));
+    let sketch = HllSketch::deserialize(merged.sketch_bytes()).unwrap();
+    assert_eq!(sketch.target_type(), HllType::Hll4);
+    assert!((sketch.estimate() - 150.0).abs() < 5.0);
+}
+
+#[test]
+fn test_merge_cpc_with_seed() {
+    let mut left = CpcSketch::with_seed(10, 42);
+    let mut right = CpcSketch::with_seed(10, 42);
+    for i in 0..100 {
+        left.update(i);
+        right.update(i + 100);
+    }
+    let left = SketchEnvelope::with_seed(left.serialize(), None, 42).unwrap();
+    let right = SketchEnvelope::with_seed(right.serialize(), None, 42).unwrap();
+    let merged = merge_envelope(&left, &right).unwrap();
+    assert_eq!(merged.seed(), 42);
+    let sketch = CpcSketch::deserialize_with_seed(merged.sketch_bytes(), 42).unwrap();
+    assert!((sketch.estimate() - 200.0).abs() < 5.0);
+
+    let default_seed = SketchEnvelope::new(CpcSketch::new(10).serialize(), None).unwrap();
+    let err = merge_envelope(&left, &default_seed).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+}
+
+#[test]
+fn test_merge_item_typed_families() {
+    let mut left = FrequentItemsSketch::<String>::new(16);
+    let mut right = FrequentItemsSketch::<String>::new(16);
+    left.update_with_count("apple".to_string(), 3);
+    right.update_with_count("apple".to_string(), 2);
+    let merged = merge_envelope(
+        &ship(left.serialize(), Some(ItemType::String)),
+        &ship(right.serialize(), Some(ItemType::String)),
+    )
+    .unwrap();
+    assert_eq!(merged.item_type(), Some(ItemType::String));
+    let sketch = FrequentItemsSketch::<String>::deserialize(merged.sketch_bytes()).unwrap();
+    assert_eq!(sketch.estimate(&"apple".to_string()), 5);
+
+    let mut left = CountMinSketch::<u64>::new(3, 64);
+    let mut right = CountMinSketch::<u64>::new(3, 64);
+    left.update("apple");
+    right.update("apple");
+    let merged = merge_envelope(
+        &ship(left.serialize(), Some(ItemType::U64)),
+        &ship(right.serialize(), Some(ItemType::U64)),
+    )
+    .unwrap();
+    let sketch = CountMinSketch::<u64>::deserialize(merged.sketch_bytes()).unwrap();
+    assert_eq!(sketch.estimate("apple"), 2);
+
+    let other_shape = ship(
+        CountMinSketch::<u64>::new(3, 128).serialize(),
+        Some(ItemType::U64),
+    );
+    let err = merge_envelope(&merged, &other_shape).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+}
+
+#[test]
+fn test_merge_tdigest_and_bloom() {
+    let mut left = TDigestMut::new(100);
+    let mut right = TDigestMut::new(100);
+    left.update(1.0);
+    right.update(2.0);
+    let merged = merge_envelope(
+        &ship(left.serialize(), None),
+        &ship(right.serialize(), None),
+    )
+    .unwrap();
+    let sketch = TDigestMut::deserialize(merged.sketch_bytes(), false).unwrap();
+    assert_eq!(sketch.total_weight(), 2);
+
+    let mut left = BloomFilterBuilder::with_size(512, 3).build();
+    let mut right = BloomFilterBuilder::with_size(512, 3).build();
+    left.insert("apple");
+    right.insert("pear");
+    let merged = merge_envelope(
+        &ship(left.serialize(), None),
+        &ship(right.serialize(), None),
+    )
+    .unwrap();
+    assert_eq!(merged.info().family(), "BLOOMFILTER");
+}
+
+#[test]
+fn test_item_type_must_fit_family() {
+    let kll = datasketches::kll::KllSketch::<f64>::new(200).serialize();
+    let err = SketchEnvelope::new(kll.clone(), None).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    let err = SketchEnvelope::new(kll, Some(ItemType::String)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+
+    let hll = HllSketch::new(10, HllType::Hll8).serialize();
+    let err = SketchEnvelope::new(hll, Some(ItemType::U64)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+
+    let frequencies = FrequentItemsSketch::<i64>::new(16).serialize();
+    let err = SketchEnvelope::new(frequencies, Some(ItemType::F64)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+}
+
+#[test]
+fn test_merge_rejects_different_families() {
+    let hll = ship(HllSketch::new(10, HllType::Hll8).serialize(), None);
+    let cpc = ship(CpcSketch::new(10).serialize(), None);
+    let err = merge_envelope(&hll, &cpc).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+}
+
+#[test]
+fn test_deserialize_rejects_inconsistent_header() {
+    let envelope = ship(HllSketch::new(10, HllType::Hll8).serialize(), None);
+    let mut bytes = envelope.serialize();
+    bytes[2] = 16;
+    assert!(SketchEnvelope::deserialize(&bytes).is_err());
+
+    let mut bytes = envelope.serialize();
+    bytes[4] = 200;
+    let err = SketchEnvelope::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadPreamble);
+
+    let bytes = envelope.serialize();
+    assert!(SketchEnvelope::deserialize(&bytes[..12]).is_err());
+}
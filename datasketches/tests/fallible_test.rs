@@ -0,0 +1,391 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::bloom::BloomFilter;
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::cpc::CpcUnion;
+use datasketches::cpc::CpcWrapper;
+use datasketches::error::Error;
+use datasketches::error::ErrorKind;
+use datasketches::family::deserialize_any;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::frequencies::ShardedFrequentItems;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::hll::HllUnion;
+use datasketches::kll::KllSketch;
+use datasketches::kll::MultiKll;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+use datasketches::theta::ThetaUnion;
+
+/// A seed whose 16-bit seed hash is zero, which is reserved.
+const ZERO_HASH_SEED: u64 = 50541;
+
+#[test]
+fn test_try_new_rejects_invalid_arguments() {
+    let err = KllSketch::<f64>::try_new(7).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    assert!(MultiKll::<0>::try_new(200).is_err());
+    assert!(MultiKll::<2>::try_new(7).is_err());
+
+    assert!(HllSketch::try_new(3, HllType::Hll4).is_err());
+    assert!(HllSketch::try_new(22, HllType::Hll8).is_err());
+    assert!(HllUnion::try_new(22).is_err());
+
+    assert!(CpcSketch::try_new(3).is_err());
+    assert!(CpcSketch::try_with_seed(11, ZERO_HASH_SEED).is_err());
+    assert!(CpcUnion::try_new(27).is_err());
+    assert!(CpcUnion::try_with_seed(11, ZERO_HASH_SEED).is_err());
+
+    assert!(CountMinSketch::<i64>::try_new(0, 128).is_err());
+    assert!(CountMinSketch::<i64>::try_new(4, 2).is_err());
+    assert!(CountMinSketch::<i64>::try_with_seed(4, 128, ZERO_HASH_SEED).is_err());
+
+    assert!(FrequentItemsSketch::<i64>::try_new(100).is_err());
+    assert!(FrequentItemsSketch::<i64>::try_new(1 << 31).is_err());
+    assert!(ShardedFrequentItems::<i64>::try_new(0, 64).is_err());
+    assert!(ShardedFrequentItems::<i64>::try_new(4, 63).is_err());
+
+    assert!(TDigestMut::try_new(9).is_err());
+
+    assert!(BloomFilterBuilder::try_with_accuracy(0, 0.01).is_err());
+    assert!(BloomFilterBuilder::try_with_accuracy(100, 1.5).is_err());
+    assert!(BloomFilterBuilder::try_with_size(0, 3).is_err());
+    assert!(BloomFilterBuilder::try_with_size(64, 0).is_err());
+
+    let err = ThetaSketch::builder()
+        .seed(ZERO_HASH_SEED)
+        .try_build()
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    assert!(
+        ThetaUnion::builder()
+            .seed(ZERO_HASH_SEED)
+            .try_build()
+            .is_err()
+    );
+}
+
+#[test]
+fn test_try_new_accepts_valid_arguments() {
+    assert_eq!(KllSketch::<f64>::try_new(8).unwrap().k(), 8);
+    assert_eq!(MultiKll::<3>::try_new(100).unwrap().k(), 100);
+    assert_eq!(
+        HllSketch::try_new(12, HllType::Hll6).unwrap().lg_config_k(),
+        12
+    );
+    assert!(HllUnion::try_new(21).is_ok());
+    assert_eq!(CpcSketch::try_new(26).unwrap().lg_k(), 26);
+    assert_eq!(CpcUnion::try_with_seed(4, 7).unwrap().lg_k(), 4);
+    assert_eq!(
+        CountMinSketch::<u64>::try_with_seed(4, 128, 7)
+            .unwrap()
+            .seed(),
+        7
+    );
+    assert_eq!(
+        FrequentItemsSketch::<i64>::try_new(1 << 30)
+            .unwrap()
+            .lg_max_map_size(),
+        30
+    );
+    assert_eq!(
+        ShardedFrequentItems::<i64>::try_new(4, 64)
+            .unwrap()
+            .num_shards(),
+        4
+    );
+    let filter = BloomFilterBuilder::try_with_size(1000, 3).unwrap().build();
+    assert!(filter.is_empty());
+    assert_eq!(ThetaSketch::builder().try_build().unwrap().lg_k(), 12);
+    assert!(ThetaUnion::builder().try_build().is_ok());
+}
+
+#[test]
+fn test_zero_hash_seed_is_an_error_when_deserializing() {
+    let mut sketch = ThetaSketch::builder().build();
+    sketch.update(1);
+    let bytes = sketch.compact(true).serialize();
+    assert!(CompactThetaSketch::deserialize_with_seed(&bytes, ZERO_HASH_SEED).is_err());
+
+    let mut sketch = CpcSketch::new(10);
+    sketch.update(1);
+    assert!(CpcSketch::deserialize_with_seed(&sketch.serialize(), ZERO_HASH_SEED).is_err());
+
+    let sketch = CountMinSketch::<i64>::new(3, 16);
+    assert!(
+        CountMinSketch::<i64>::deserialize_with_seed(&sketch.serialize(), ZERO_HASH_SEED).is_err()
+    );
+}
+
+/// Feeds every truncation and a set of single-byte corruptions of `bytes` to `decode`.
+///
+/// Decoding corrupted input may succeed or fail, but must never panic or abort.
+fn check_corruptions(bytes: &[u8], decode: impl Fn(&[u8])) {
+    for len in 0..bytes.len() {
+        decode(&bytes[..len]);
+    }
+    let mut corrupted = bytes.to_vec();
+    for pos in 0..bytes.len() {
+        for value in [0x00, 0x01, 0x3F, 0x7F, 0x80, 0xFF] {
+            corrupted[pos] = value;
+            decode(&corrupted);
+        }
+        corrupted[pos] = bytes[pos];
+    }
+}
+
+#[test]
+fn test_corrupted_theta_images() {
+    let mut sketch = ThetaSketch::builder().lg_k(5).build();
+    for i in 0..100 {
+        sketch.update(i);
+    }
+    check_corruptions(&sketch.serialize(), |b| {
+        let _ = ThetaSketch::deserialize(b).map(|s| s.estimate());
+    });
+    let compact = sketch.compact(true);
+    check_corruptions(&compact.serialize(), |b| {
+        let _ = CompactThetaSketch::deserialize(b).map(|s| s.serialize_compressed());
+    });
+    check_corruptions(&compact.serialize_compressed(), |b| {
+        let _ = CompactThetaSketch::deserialize(b).map(|s| s.serialize_compressed());
+    });
+}
+
+#[test]
+fn test_corrupted_hll_images() {
+    for hll_type in [HllType::Hll4, HllType::Hll6, HllType::Hll8] {
+        for (lg_k, n) in [(6, 1), (6, 100), (6, 1000), (10, 100)] {
+            let mut sketch = HllSketch::new(lg_k, hll_type);
+            for i in 0..n {
+                sketch.update(i);
+            }
+            check_corruptions(&sketch.serialize(), |b| {
+                let _ = HllSketch::deserialize(b).map(|mut s| {
+                    for i in 0..10 {
+                        s.update(n + i);
+                    }
+                    s.estimate()
+                });
+            });
+        }
+    }
+}
+
+#[test]
+fn test_corrupted_cpc_images() {
+    for n in [0, 5, 100, 3000] {
+        let mut sketch = CpcSketch::new(5);
+        for i in 0..n {
+            sketch.update(i);
+        }
+        check_corruptions(&sketch.serialize(), |b| {
+            let _ = CpcSketch::deserialize(b).map(|s| s.estimate());
+            let _ = CpcWrapper::new(b).map(|s| s.estimate());
+        });
+    }
+}
+
+#[test]
+fn test_corrupted_frequencies_images() {
+    let mut sketch = FrequentItemsSketch::<i64>::new(8);
+    for i in 0..20 {
+        sketch.update(i % 7);
+    }
+    check_corruptions(&sketch.serialize(), |b| {
+        let _ = FrequentItemsSketch::<i64>::deserialize(b).map(|s| s.num_active_items());
+    });
+}
+
+#[test]
+fn test_corrupted_quantiles_images() {
+    let mut sketch = KllSketch::<f64>::new(8);
+    for i in 0..50 {
+        sketch.update(i as f64);
+    }
+    check_corruptions(&sketch.serialize(), |b| {
+        let _ = KllSketch::<f64>::deserialize(b).map(|s| s.quantile(0.5));
+    });
+
+    let mut digest = TDigestMut::new(10);
+    for i in 0..100 {
+        digest.update(i as f64);
+    }
+    check_corruptions(&digest.serialize(), |b| {
+        let _ = TDigestMut::deserialize(b, false).map(|mut s| s.quantile(0.5));
+    });
+}
+
+#[test]
+fn test_corrupted_countmin_and_bloom_images() {
+    let mut sketch = CountMinSketch::<i64>::new(2, 4);
+    sketch.update(1);
+    check_corruptions(&sketch.serialize(), |b| {
+        let _ = CountMinSketch::<i64>::deserialize(b).map(|s| s.estimate(1));
+    });
+
+    let mut filter = BloomFilterBuilder::with_size(128, 3).build();
+    filter.insert(1);
+    check_corruptions(&filter.serialize(), |b| {
+        let _ = BloomFilter::deserialize(b).map(|f| f.contains(&1));
+    });
+}
+
+#[test]
+fn test_deserialize_error_kinds() {
+    let mut theta = ThetaSketch::builder().seed(7).build();
+    let mut cpc = CpcSketch::with_seed(10, 7);
+    let mut hll = HllSketch::new(10, HllType::Hll8);
+    let mut kll = KllSketch::<f64>::new(200);
+    for i in 0..1000 {
+        theta.update(i);
+        cpc.update(i);
+        hll.update(i);
+        kll.update(i as f64);
+    }
+    let compact = theta.compact(true).serialize();
+    let countmin = CountMinSketch::<i64>::with_seed(3, 16, 7).serialize();
+
+    let err = CompactThetaSketch::deserialize(&compact).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SeedHashMismatch);
+    let err = ThetaSketch::deserialize(&theta.serialize()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SeedHashMismatch);
+    let err = CpcSketch::deserialize(&cpc.serialize()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SeedHashMismatch);
+    let err = CountMinSketch::<i64>::deserialize(&countmin).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SeedHashMismatch);
+
+    let mut bad_family = hll.serialize();
+    bad_family[2] = 0;
+    let err = HllSketch::deserialize(&bad_family).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadPreamble);
+    let mut bad_lg_k = cpc.serialize();
+    bad_lg_k[3] = 30;
+    let err = CpcSketch::deserialize_with_seed(&bad_lg_k, 7).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadPreamble);
+
+    let mut bad_version = kll.serialize();
+    bad_version[1] = 9;
+    let err = KllSketch::<f64>::deserialize(&bad_version).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnsupportedVersion);
+    let mut bad_version = countmin.clone();
+    bad_version[1] = 9;
+    let err = CountMinSketch::<i64>::deserialize_with_seed(&bad_version, 7).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnsupportedVersion);
+
+    type Decode = fn(&[u8]) -> Result<(), Error>;
+    let images: [(&[u8], Decode); 5] = [
+        (&compact, |b| {
+            CompactThetaSketch::deserialize_with_seed(b, 7).map(drop)
+        }),
+        (&cpc.serialize(), |b| {
+            CpcSketch::deserialize_with_seed(b, 7).map(drop)
+        }),
+        (&hll.serialize(), |b| HllSketch::deserialize(b).map(drop)),
+        (&kll.serialize(), |b| {
+            KllSketch::<f64>::deserialize(b).map(drop)
+        }),
+        (&countmin, |b| {
+            CountMinSketch::<i64>::deserialize_with_seed(b, 7).map(drop)
+        }),
+    ];
+    for (bytes, decode) in images {
+        for len in 0..bytes.len() {
+            let err = decode(&bytes[..len]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Truncated, "len {len}: {err}");
+        }
+    }
+}
+
+#[test]
+fn test_deserialize_rejects_out_of_range_headers() {
+    let mut sketch = ThetaSketch::builder().lg_k(5).build();
+    for i in 0..1000 {
+        sketch.update(i);
+    }
+    let compact = sketch.compact(true);
+
+    // theta above the maximum would let retained hashes use all 64 bits
+    let mut bytes = compact.serialize();
+    bytes[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+    let err = CompactThetaSketch::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadPreamble);
+
+    let mut bytes = compact.serialize();
+    let (first, second) = (24..32, 32..40);
+    let entry = bytes[first.clone()].to_vec();
+    bytes.copy_within(second.clone(), first.start);
+    bytes[second].copy_from_slice(&entry);
+    let err = CompactThetaSketch::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.message().contains("not sorted"), "{err}");
+
+    let mut sketch = HllSketch::new(12, HllType::Hll8);
+    for i in 0..100 {
+        sketch.update(i);
+    }
+    let mut bytes = sketch.serialize();
+    bytes[4] = 200;
+    let err = HllSketch::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadPreamble);
+
+    // an HLL4 array with two exceptions for the same slot
+    let mut sketch = HllSketch::new(4, HllType::Hll4);
+    for i in 0..1000 {
+        sketch.update(i);
+    }
+    let mut bytes = sketch.serialize();
+    bytes.truncate(40 + 8);
+    bytes[36..40].copy_from_slice(&2u32.to_le_bytes());
+    let coupon = 3 | (20 << 26);
+    bytes.extend_from_slice(&u32::to_le_bytes(coupon));
+    bytes.extend_from_slice(&u32::to_le_bytes(coupon));
+    let err = HllSketch::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.message().contains("duplicate aux slot"), "{err}");
+
+    let mut bytes = BloomFilterBuilder::with_size(128, 3).build().serialize();
+    bytes[16..20].copy_from_slice(&i32::MAX.to_le_bytes());
+    let err = BloomFilter::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadPreamble);
+}
+
+#[test]
+fn test_empty_images_cannot_ask_for_huge_tables() {
+    // an empty image is a bare header, so nothing but the header bounds the table size
+    let mut bytes = CountMinSketch::<i64>::new(4, 64).serialize();
+    assert_eq!(bytes.len(), 16);
+    bytes[8..12].copy_from_slice(&((1u32 << 28) - 1).to_le_bytes());
+    let err = CountMinSketch::<i64>::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = CountMinSketch::<i64>::deserialize_from_reader(&mut bytes.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let mut bytes = BloomFilterBuilder::with_size(128, 3).build().serialize();
+    assert_eq!(bytes.len(), 24);
+    let num_longs = (BloomFilterBuilder::MAX_NUM_BITS / 64) as i32;
+    bytes[16..20].copy_from_slice(&num_longs.to_le_bytes());
+    let err = BloomFilter::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = deserialize_any(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
@@ -0,0 +1,107 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::cpc::CpcSketch;
+use datasketches::error::ErrorKind;
+use datasketches::family::AnySketch;
+use datasketches::family::Family;
+use datasketches::family::deserialize_any;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::kll::KllSketch;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::ThetaSketch;
+
+#[test]
+fn test_family_ids() {
+    for id in 0..=u8::MAX {
+        if let Some(family) = Family::from_id(id) {
+            assert_eq!(family.id(), id);
+            assert_eq!(expected_name(family), family.name());
+        }
+    }
+    assert_eq!(Family::from_id(3), Some(Family::Theta));
+    assert_eq!(Family::from_id(21), Some(Family::BloomFilter));
+    assert_eq!(Family::from_id(1), None);
+}
+
+fn expected_name(family: Family) -> &'static str {
+    match family {
+        Family::QuickSelect => "QUICKSELECT",
+        Family::Theta => "THETA",
+        Family::Hll => "HLL",
+        Family::Frequency => "FREQUENCY",
+        Family::Kll => "KLL",
+        Family::Cpc => "CPC",
+        Family::CountMin => "COUNTMIN",
+        Family::TDigest => "TDIGEST",
+        Family::BloomFilter => "BLOOMFILTER",
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_deserialize_any() {
+    let mut theta = ThetaSketch::builder().build();
+    let mut hll = HllSketch::new(10, HllType::Hll4);
+    let mut cpc = CpcSketch::new(10);
+    let mut tdigest = TDigestMut::new(100);
+    let mut bloom = BloomFilterBuilder::with_size(1024, 3).build();
+    for i in 0..1000 {
+        theta.update(i);
+        hll.update(i);
+        cpc.update(i);
+        tdigest.update(i as f64);
+        bloom.insert(i);
+    }
+
+    match deserialize_any(&theta.serialize()).unwrap() {
+        AnySketch::UpdateTheta(sketch) => assert_eq!(sketch.estimate(), theta.estimate()),
+        other => panic!("unexpected {:?}", other.family()),
+    }
+    match deserialize_any(&theta.compact(true).serialize()).unwrap() {
+        AnySketch::CompactTheta(sketch) => assert_eq!(sketch.estimate(), theta.estimate()),
+        other => panic!("unexpected {:?}", other.family()),
+    }
+    match deserialize_any(&hll.serialize()).unwrap() {
+        AnySketch::Hll(sketch) => assert_eq!(sketch, hll),
+        other => panic!("unexpected {:?}", other.family()),
+    }
+    match deserialize_any(&cpc.serialize()).unwrap() {
+        AnySketch::Cpc(sketch) => assert_eq!(sketch.estimate(), cpc.estimate()),
+        other => panic!("unexpected {:?}", other.family()),
+    }
+    match deserialize_any(&tdigest.serialize()).unwrap() {
+        AnySketch::TDigest(mut sketch) => assert_eq!(sketch.quantile(0.5), tdigest.quantile(0.5)),
+        other => panic!("unexpected {:?}", other.family()),
+    }
+    match deserialize_any(&bloom.serialize()).unwrap() {
+        AnySketch::BloomFilter(filter) => assert_eq!(filter, bloom),
+        other => panic!("unexpected {:?}", other.family()),
+    }
+}
+
+#[test]
+fn test_deserialize_any_errors() {
+    let mut kll = KllSketch::<f64>::new(200);
+    kll.update(1.0);
+    let err = deserialize_any(&kll.serialize()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+
+    assert!(deserialize_any(&[]).is_err());
+    assert!(deserialize_any(&[1, 1, 99, 0]).is_err());
+}
@@ -0,0 +1,101 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::error::ErrorKind;
+use datasketches::frequencies::DecayedFrequentItems;
+use datasketches::frequencies::ErrorType;
+
+#[test]
+fn test_decay_halves_counts() {
+    let mut sketch = DecayedFrequentItems::new(64, 0.5);
+    assert!(sketch.is_empty());
+    sketch.update_with_weight(1, 8.0);
+    sketch.update(2);
+    assert_eq!(sketch.total_weight(), 9.0);
+
+    sketch.decay();
+    assert_eq!(sketch.estimate(&1), 4.0);
+    assert_eq!(sketch.estimate(&2), 0.5);
+    assert_eq!(sketch.total_weight(), 4.5);
+    assert_eq!(sketch.maximum_error(), 0.0);
+
+    sketch.advance(2);
+    assert_eq!(sketch.estimate(&1), 1.0);
+    assert_eq!(sketch.estimate(&2), 0.125);
+}
+
+#[test]
+fn test_items_below_resolution_are_dropped() {
+    let mut sketch = DecayedFrequentItems::new(64, 0.5);
+    sketch.update(1);
+    sketch.advance(20);
+    assert_eq!(sketch.num_active_items(), 1);
+    sketch.decay();
+    assert!(sketch.is_empty());
+    assert_eq!(sketch.estimate(&1), 0.0);
+}
+
+#[test]
+fn test_trending_items_overtake_old_heavy_hitters() {
+    let mut sketch = DecayedFrequentItems::new(64, 0.9);
+    for tick in 0..100 {
+        // item 0 dominates early, item 1 late, the rest is noise
+        let hot = if tick < 50 { 0 } else { 1 };
+        sketch.update_with_weight(hot, 20.0);
+        for noise in 0..100 {
+            sketch.update(100 + (tick * 7 + noise) % 200);
+        }
+        sketch.decay();
+    }
+    let top = sketch.top_k(1, ErrorType::NoFalsePositives);
+    assert_eq!(*top[0].item(), 1);
+    assert!(sketch.estimate(&1) > sketch.estimate(&0));
+
+    // the true decayed count of item 1 lies within the bounds
+    let truth: f64 = (1..=50).map(|t| 20.0 * 0.9f64.powi(t)).sum();
+    assert!(sketch.lower_bound(&1) <= truth);
+    assert!(sketch.upper_bound(&1) >= truth - 1e-3);
+    for row in sketch.frequent_items(ErrorType::NoFalseNegatives) {
+        assert!(row.lower_bound() <= row.estimate());
+        assert!(row.estimate() <= row.upper_bound());
+    }
+}
+
+#[test]
+fn test_merge_adds_decayed_counts() {
+    let mut left = DecayedFrequentItems::new(64, 0.5);
+    let mut right = DecayedFrequentItems::new(64, 0.5);
+    left.update_with_weight("a", 4.0);
+    left.decay();
+    right.update_with_weight("a", 1.0);
+    left.merge(&right);
+    assert_eq!(left.estimate(&"a"), 3.0);
+    left.reset();
+    assert!(left.is_empty());
+}
+
+#[test]
+fn test_invalid_decay_factor() {
+    for factor in [0.0, -0.5, 1.5, f64::NAN] {
+        let err = DecayedFrequentItems::<u64>::try_new(64, factor).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    }
+    assert_eq!(
+        DecayedFrequentItems::<u64>::new(64, 1.0).decay_factor(),
+        1.0
+    );
+}
@@ -0,0 +1,98 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::frequencies::ErrorType;
+use datasketches::frequencies::FrequentItemsWithPayload;
+
+#[test]
+fn test_payload_replaced_on_update() {
+    let mut sketch = FrequentItemsWithPayload::<u64, u64>::new(64);
+    sketch.update(1, 10);
+    sketch.update_with_count(1, 3, 20);
+    sketch.update(2, 30);
+    assert_eq!(sketch.payload(&1), Some(&20));
+    assert_eq!(sketch.payload(&2), Some(&30));
+    assert_eq!(sketch.payload(&3), None);
+    assert_eq!(sketch.sketch().estimate(&1), 4);
+
+    // zero counts change nothing
+    sketch.update_with_count(1, 0, 99);
+    sketch.update_with_count(3, 0, 99);
+    assert_eq!(sketch.payload(&1), Some(&20));
+    assert_eq!(sketch.payload(&3), None);
+}
+
+#[test]
+fn test_payloads_follow_purges() {
+    let mut sketch = FrequentItemsWithPayload::<u64, String>::new(16);
+    for round in 0..100 {
+        sketch.update_with_count(0, 10, format!("heavy-{round}"));
+        for i in 0..5 {
+            let item = round * 5 + i + 1;
+            sketch.update(item, format!("light-{item}"));
+        }
+    }
+    assert!(sketch.sketch().maximum_error() > 0);
+
+    let tracked: Vec<_> = sketch.iter().collect();
+    assert_eq!(tracked.len(), sketch.sketch().num_active_items());
+    for (row, payload) in tracked {
+        let expected = if **row.item() == 0 {
+            "heavy-99".to_string()
+        } else {
+            format!("light-{}", row.item())
+        };
+        assert_eq!(*payload, expected);
+    }
+    assert_eq!(sketch.payload(&1), None);
+
+    let rows = sketch.frequent_items(ErrorType::NoFalsePositives);
+    assert_eq!(*rows[0].0.item(), 0);
+    assert_eq!(rows[0].1, "heavy-99");
+}
+
+#[test]
+fn test_update_with_combines_payloads() {
+    let mut sketch = FrequentItemsWithPayload::<&str, Vec<u32>>::new(64);
+    for (i, item) in ["a", "b", "a", "a"].into_iter().enumerate() {
+        sketch.update_with(item, 1, |seen| {
+            let mut seen = seen.unwrap_or_default();
+            seen.push(i as u32);
+            seen
+        });
+    }
+    assert_eq!(sketch.payload(&"a"), Some(&vec![0, 2, 3]));
+    assert_eq!(sketch.payload(&"b"), Some(&vec![1]));
+}
+
+#[test]
+fn test_merge_keeps_own_payloads() {
+    let mut left = FrequentItemsWithPayload::<u64, &str>::new(64);
+    let mut right = FrequentItemsWithPayload::<u64, &str>::new(64);
+    left.update(1, "left");
+    right.update(1, "right");
+    right.update(2, "right");
+    left.merge(&right);
+    assert_eq!(left.payload(&1), Some(&"left"));
+    assert_eq!(left.payload(&2), Some(&"right"));
+    assert_eq!(left.sketch().estimate(&1), 2);
+
+    left.reset();
+    assert!(left.is_empty());
+    assert_eq!(left.payload(&1), None);
+    assert_eq!(left.into_sketch().num_active_items(), 0);
+}
@@ -18,10 +18,17 @@
 mod common;
 
 use std::fs;
+use std::io;
+use std::io::Read;
+use std::io::Write;
 
-use common::serialization_test_data;
+use common::CORPUS_NS;
+use common::Language;
+use common::corpus_file;
+use common::corpus_files;
 use datasketches::error::ErrorKind;
 use datasketches::frequencies::FrequentItemsSketch;
+use proptest::prelude::*;
 
 #[test]
 fn test_longs_round_trip() {
@@ -52,10 +59,7 @@ fn test_items_round_trip() {
 
 #[test]
 fn test_java_frequent_longs_compatibility() {
-    let test_cases = [0, 1, 10, 100, 1000, 10000, 100000, 1000000];
-    for n in test_cases {
-        let filename = format!("frequent_long_n{}_java.sk", n);
-        let path = serialization_test_data("java_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Java, "frequent_long", &CORPUS_NS) {
         let bytes = fs::read(&path).unwrap();
         let sketch = FrequentItemsSketch::<i64>::deserialize(&bytes).unwrap();
         assert_eq!(sketch.is_empty(), n == 0);
@@ -64,13 +68,13 @@ fn test_java_frequent_longs_compatibility() {
         } else {
             assert_eq!(sketch.maximum_error(), 0);
         }
-        assert_eq!(sketch.total_weight(), n);
+        assert_eq!(sketch.total_weight(), n as u64);
     }
 }
 
 #[test]
 fn test_java_frequent_strings_ascii() {
-    let path = serialization_test_data("java_generated_files", "frequent_string_ascii_java.sk");
+    let path = corpus_file(Language::Java, "frequent_string_ascii");
     let bytes = fs::read(&path).unwrap();
     let sketch = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap();
     assert!(!sketch.is_empty());
@@ -96,7 +100,7 @@ fn test_java_frequent_strings_ascii() {
 
 #[test]
 fn test_java_frequent_strings_utf8() {
-    let path = serialization_test_data("java_generated_files", "frequent_string_utf8_java.sk");
+    let path = corpus_file(Language::Java, "frequent_string_utf8");
     let bytes = fs::read(&path).unwrap();
     let sketch = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap();
     assert!(!sketch.is_empty());
@@ -113,15 +117,12 @@ fn test_java_frequent_strings_utf8() {
 
 #[test]
 fn test_cpp_frequent_longs_compatibility() {
-    let test_cases = [0, 1, 10, 100, 1000, 10000, 100000, 1000000];
-    for n in test_cases {
-        let filename = format!("frequent_long_n{}_cpp.sk", n);
-        let path = serialization_test_data("cpp_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Cpp, "frequent_long", &CORPUS_NS) {
         let bytes = fs::read(&path).unwrap();
         let sketch = FrequentItemsSketch::<i64>::deserialize(&bytes);
         if cfg!(windows) {
             if let Err(err) = sketch {
-                assert_eq!(err.kind(), ErrorKind::InvalidData);
+                assert_eq!(err.kind(), ErrorKind::Truncated);
                 assert!(
                     err.message().contains("insufficient data"),
                     "expected insufficient data error, got: {err}"
@@ -136,16 +137,13 @@ fn test_cpp_frequent_longs_compatibility() {
         } else {
             assert_eq!(sketch.maximum_error(), 0);
         }
-        assert_eq!(sketch.total_weight(), n);
+        assert_eq!(sketch.total_weight(), n as u64);
     }
 }
 
 #[test]
 fn test_cpp_frequent_strings_compatibility() {
-    let test_cases = [0, 1, 10, 100, 1000, 10000, 100000, 1000000];
-    for n in test_cases {
-        let filename = format!("frequent_string_n{}_cpp.sk", n);
-        let path = serialization_test_data("cpp_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Cpp, "frequent_string", &CORPUS_NS) {
         let bytes = fs::read(&path).unwrap();
         let sketch = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap();
         assert_eq!(sketch.is_empty(), n == 0);
@@ -154,13 +152,13 @@ fn test_cpp_frequent_strings_compatibility() {
         } else {
             assert_eq!(sketch.maximum_error(), 0);
         }
-        assert_eq!(sketch.total_weight(), n);
+        assert_eq!(sketch.total_weight(), n as u64);
     }
 }
 
 #[test]
 fn test_cpp_frequent_strings_ascii() {
-    let path = serialization_test_data("cpp_generated_files", "frequent_string_ascii_cpp.sk");
+    let path = corpus_file(Language::Cpp, "frequent_string_ascii");
     let bytes = fs::read(&path).unwrap();
     let sketch = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap();
     assert!(!sketch.is_empty());
@@ -186,7 +184,7 @@ fn test_cpp_frequent_strings_ascii() {
 
 #[test]
 fn test_cpp_frequent_strings_utf8() {
-    let path = serialization_test_data("cpp_generated_files", "frequent_string_utf8_cpp.sk");
+    let path = corpus_file(Language::Cpp, "frequent_string_utf8");
     let bytes = fs::read(&path).unwrap();
     let sketch = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap();
     assert!(!sketch.is_empty());
@@ -200,3 +198,270 @@ fn test_cpp_frequent_strings_utf8() {
     assert_eq!(sketch.estimate(&"шщъыь".to_string()), 6);
     assert_eq!(sketch.estimate(&"эюя".to_string()), 7);
 }
+
+/// Lays out a non-empty image the way `LongsSketch` and `ItemsSketch<Long>` in
+/// datasketches-java do: four preamble longs, then the counts, then the items.
+fn java_longs_image(
+    lg_max: u8,
+    lg_cur: u8,
+    stream_weight: u64,
+    offset: u64,
+    rows: &[(i64, u64)],
+) -> Vec<u8> {
+    let mut bytes = vec![4, 1, 10, lg_max, lg_cur, 0, 0, 0];
+    bytes.extend_from_slice(&(rows.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&stream_weight.to_le_bytes());
+    bytes.extend_from_slice(&offset.to_le_bytes());
+    for (_, count) in rows {
+        bytes.extend_from_slice(&count.to_le_bytes());
+    }
+    for (item, _) in rows {
+        bytes.extend_from_slice(&item.to_le_bytes());
+    }
+    bytes
+}
+
+#[test]
+fn test_java_longs_image_layout() {
+    let rows = [(-7, 5), (42, 3), (i64::MAX, 1)];
+    let bytes = java_longs_image(5, 3, 9, 0, &rows);
+    let sketch = FrequentItemsSketch::<i64>::deserialize(&bytes).unwrap();
+    assert_eq!(sketch.num_active_items(), 3);
+    assert_eq!(sketch.total_weight(), 9);
+    assert_eq!(sketch.maximum_error(), 0);
+    for (item, count) in rows {
+        assert_eq!(sketch.estimate(&item), count);
+    }
+
+    // a purged image carries its offset and the weight of the purged items
+    let bytes = java_longs_image(3, 3, 20, 2, &rows);
+    let sketch = FrequentItemsSketch::<i64>::deserialize(&bytes).unwrap();
+    assert_eq!(sketch.total_weight(), 20);
+    assert_eq!(sketch.maximum_error(), 2);
+    assert_eq!(sketch.estimate(&42), 5);
+    assert_eq!(sketch.lower_bound(&42), 3);
+
+    let restored = FrequentItemsSketch::<i64>::deserialize(&sketch.serialize()).unwrap();
+    assert_eq!(restored.total_weight(), 20);
+    assert_eq!(restored.estimate(&-7), 7);
+
+    // Java marks empty images with either bit of its empty flag mask
+    for flags in [1, 4, 5] {
+        let bytes = [1, 1, 10, 3, 3, flags, 0, 0];
+        let sketch = FrequentItemsSketch::<i64>::deserialize(&bytes).unwrap();
+        assert!(sketch.is_empty());
+    }
+}
+
+#[test]
+fn test_deserialize_rejects_offset_and_weights_beyond_total_weight() {
+    let rows = [(-7, 5), (42, 3)];
+
+    // an offset of u64::MAX would overflow every estimate of the restored sketch
+    let bytes = java_longs_image(3, 3, 20, u64::MAX, &rows);
+    let err = FrequentItemsSketch::<i64>::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.message().contains("offset exceeds"), "{err}");
+
+    let bytes = java_longs_image(3, 3, 7, 0, &rows);
+    let err = FrequentItemsSketch::<i64>::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.message().contains("weights exceed"), "{err}");
+
+    let bytes = java_longs_image(3, 3, 8, 8, &rows);
+    let sketch = FrequentItemsSketch::<i64>::deserialize(&bytes).unwrap();
+    assert_eq!(sketch.upper_bound(&-7), 13);
+}
+
+#[test]
+fn test_string_item_layout() {
+    // a single item keeps the layout deterministic: 4 preamble longs, one count, one item
+    let item = "héllo, 世界 🦀".to_string();
+    let mut sketch = FrequentItemsSketch::new(8);
+    sketch.update_with_count(item.clone(), 3);
+
+    let bytes = sketch.serialize();
+    let utf8 = item.as_bytes();
+    assert_eq!(bytes.len(), 32 + 8 + 4 + utf8.len());
+    assert_eq!(&bytes[32..40], &3u64.to_le_bytes());
+    assert_eq!(&bytes[40..44], &(utf8.len() as u32).to_le_bytes());
+    assert_eq!(&bytes[44..], utf8);
+}
+
+#[test]
+fn test_empty_string_sketch_round_trip() {
+    let sketch = FrequentItemsSketch::<String>::new(64);
+    let bytes = sketch.serialize();
+    assert_eq!(bytes.len(), 8);
+
+    let restored = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap();
+    assert!(restored.is_empty());
+    assert_eq!(restored.total_weight(), 0);
+    assert_eq!(restored.num_active_items(), 0);
+    assert_eq!(restored.serialize(), bytes);
+}
+
+#[test]
+fn test_string_item_invalid_utf8() {
+    let mut sketch = FrequentItemsSketch::new(8);
+    sketch.update_with_count("abc".to_string(), 1);
+
+    let mut bytes = sketch.serialize();
+    bytes[45] = 0xff;
+    let err = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.message().contains("invalid UTF-8"), "{err}");
+}
+
+#[test]
+fn test_string_item_length_exceeds_input() {
+    let mut sketch = FrequentItemsSketch::new(8);
+    sketch.update_with_count("abc".to_string(), 1);
+
+    let mut bytes = sketch.serialize();
+    bytes[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+    let err = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Truncated);
+    assert!(err.message().contains("insufficient data"), "{err}");
+}
+
+#[test]
+fn test_stream_round_trip_large_sketch() {
+    let mut sketch = FrequentItemsSketch::new(1 << 12);
+    for i in 0..100_000u64 {
+        sketch.update_with_count(format!("item-{}", i % 5000), i % 7 + 1);
+    }
+
+    let mut buf = Vec::new();
+    sketch.serialize_into_writer(&mut buf).unwrap();
+    assert_eq!(buf, sketch.serialize());
+
+    let restored =
+        FrequentItemsSketch::<String>::deserialize_from_reader(&mut buf.as_slice()).unwrap();
+    assert_eq!(restored.serialize(), buf);
+}
+
+#[test]
+fn test_stream_round_trip_empty() {
+    let sketch = FrequentItemsSketch::<i64>::new(64);
+    let mut buf = Vec::new();
+    sketch.serialize_into_writer(&mut buf).unwrap();
+    assert_eq!(buf, sketch.serialize());
+
+    let restored =
+        FrequentItemsSketch::<i64>::deserialize_from_reader(&mut buf.as_slice()).unwrap();
+    assert!(restored.is_empty());
+    assert_eq!(restored.lg_max_map_size(), 6);
+}
+
+#[test]
+fn test_deserialize_from_reader_stops_at_end_of_sketch() {
+    let mut first = FrequentItemsSketch::<u64>::new(64);
+    first.update_with_count(1, 10);
+    let mut second = FrequentItemsSketch::<u64>::new(64);
+    second.update_with_count(2, 20);
+
+    let mut buf = Vec::new();
+    first.serialize_into_writer(&mut buf).unwrap();
+    second.serialize_into_writer(&mut buf).unwrap();
+
+    let mut reader = buf.as_slice();
+    let decoded_first = FrequentItemsSketch::<u64>::deserialize_from_reader(&mut reader).unwrap();
+    let decoded_second = FrequentItemsSketch::<u64>::deserialize_from_reader(&mut reader).unwrap();
+    assert!(reader.is_empty());
+    assert_eq!(decoded_first.estimate(&1), 10);
+    assert_eq!(decoded_second.estimate(&2), 20);
+}
+
+/// Yields at most one byte per read call, like a slow socket.
+struct OneByteReader<'a>(&'a [u8]);
+
+impl Read for OneByteReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.0.len()).min(1);
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn test_deserialize_from_reader_short_reads() {
+    let mut sketch = FrequentItemsSketch::new(64);
+    sketch.update_with_count("short reads".to_string(), 3);
+    let bytes = sketch.serialize();
+
+    let restored =
+        FrequentItemsSketch::<String>::deserialize_from_reader(&mut OneByteReader(&bytes)).unwrap();
+    assert_eq!(restored.estimate(&"short reads".to_string()), 3);
+}
+
+#[test]
+fn test_deserialize_from_reader_truncated() {
+    let mut sketch = FrequentItemsSketch::new(64);
+    sketch.update_with_count("abc".to_string(), 1);
+    let bytes = sketch.serialize();
+
+    for len in 0..bytes.len() {
+        let err =
+            FrequentItemsSketch::<String>::deserialize_from_reader(&mut &bytes[..len]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Truncated);
+        assert!(err.message().contains("insufficient data"), "{err}");
+    }
+}
+
+struct FailingIo;
+
+impl Read for FailingIo {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other("connection reset"))
+    }
+}
+
+impl Write for FailingIo {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("connection reset"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_stream_io_errors() {
+    let err = FrequentItemsSketch::<i64>::deserialize_from_reader(&mut FailingIo).unwrap_err();
+    assert!(err.message().contains("connection reset"), "{err}");
+
+    let mut sketch = FrequentItemsSketch::<i64>::new(64);
+    sketch.update(1);
+    let err = sketch.serialize_into_writer(&mut FailingIo).unwrap_err();
+    assert_eq!(err.to_string(), "connection reset");
+}
+
+proptest! {
+    #[test]
+    fn test_unicode_string_items_round_trip(
+        items in proptest::collection::vec(any::<String>(), 0..64),
+    ) {
+        let mut sketch = FrequentItemsSketch::new(64);
+        for (i, item) in items.iter().enumerate() {
+            sketch.update_with_count(item.clone(), i as u64 + 1);
+        }
+
+        let bytes = sketch.serialize();
+        let restored = FrequentItemsSketch::<String>::deserialize(&bytes).unwrap();
+        prop_assert_eq!(restored.total_weight(), sketch.total_weight());
+        prop_assert_eq!(restored.num_active_items(), sketch.num_active_items());
+        for item in &items {
+            prop_assert_eq!(restored.estimate(item), sketch.estimate(item));
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_bytes_never_panic(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+        let _ = FrequentItemsSketch::<String>::deserialize(&bytes);
+        let _ = FrequentItemsSketch::<String>::deserialize_from_reader(&mut bytes.as_slice());
+    }
+}
@@ -0,0 +1,140 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::collections::HashMap;
+use std::thread;
+
+use datasketches::frequencies::ErrorType;
+use datasketches::frequencies::ShardedFrequentItems;
+
+#[test]
+fn test_sharded_exact_mode() {
+    let sketch = ShardedFrequentItems::<u64>::new(4, 64);
+    assert!(sketch.is_empty());
+    for i in 0..20 {
+        sketch.update_with_count(i, i + 1);
+    }
+    assert!(!sketch.is_empty());
+    assert_eq!(sketch.num_shards(), 4);
+    assert_eq!(sketch.total_weight(), (1..=20).sum());
+    assert_eq!(sketch.maximum_error(), 0);
+    for i in 0..20 {
+        assert_eq!(sketch.estimate(&i), i + 1);
+        assert_eq!(sketch.lower_bound(&i), i + 1);
+        assert_eq!(sketch.upper_bound(&i), i + 1);
+    }
+    assert_eq!(sketch.estimate(&100), 0);
+
+    let rows = sketch.frequent_items(ErrorType::NoFalsePositives);
+    assert_eq!(rows.len(), 20);
+    assert_eq!(*rows[0].item(), 19);
+    assert!(rows.windows(2).all(|w| w[0].estimate() >= w[1].estimate()));
+}
+
+#[test]
+fn test_sharded_concurrent_updates_match_true_counts() {
+    let num_threads = 8;
+    let sketch = ShardedFrequentItems::<u64>::new(num_threads, 32);
+    thread::scope(|s| {
+        for t in 0..num_threads as u64 {
+            let sketch = &sketch;
+            s.spawn(move || {
+                for i in 0..10_000u64 {
+                    // A few heavy items shared by all threads and a long tail per thread.
+                    let item = if i % 2 == 0 {
+                        i % 5
+                    } else {
+                        1_000 + t * 10_000 + i
+                    };
+                    sketch.update(item);
+                }
+            });
+        }
+    });
+
+    let mut truth = HashMap::new();
+    for t in 0..num_threads as u64 {
+        for i in 0..10_000u64 {
+            let item = if i % 2 == 0 {
+                i % 5
+            } else {
+                1_000 + t * 10_000 + i
+            };
+            *truth.entry(item).or_insert(0u64) += 1;
+        }
+    }
+
+    assert_eq!(sketch.total_weight(), num_threads as u64 * 10_000);
+    let max_error = sketch.maximum_error();
+    assert!(max_error > 0);
+    for (item, count) in &truth {
+        assert!(sketch.lower_bound(item) <= *count);
+        assert!(sketch.upper_bound(item) >= *count);
+        assert!(sketch.upper_bound(item) - sketch.lower_bound(item) <= max_error);
+    }
+
+    // Every item above the maximum error must be reported without false negatives.
+    let rows = sketch.frequent_items(ErrorType::NoFalseNegatives);
+    for (item, count) in &truth {
+        if *count > max_error {
+            assert!(rows.iter().any(|row| row.item() == item), "missing {item}");
+        }
+    }
+    let rows = sketch.frequent_items(ErrorType::NoFalsePositives);
+    for row in &rows {
+        assert!(truth[row.item()] > max_error);
+    }
+    for heavy in 0..5 {
+        assert!(rows.iter().any(|row| *row.item() == heavy));
+    }
+}
+
+#[test]
+fn test_sharded_to_sketch_and_into_shards() {
+    let sketch = ShardedFrequentItems::<u64>::new(3, 64);
+    for i in 0..30 {
+        sketch.update_with_count(i, 2);
+    }
+
+    let merged = sketch.to_sketch();
+    assert_eq!(merged.total_weight(), 60);
+    for i in 0..30 {
+        assert_eq!(merged.estimate(&i), 2);
+    }
+
+    let shards = sketch.into_shards();
+    assert_eq!(shards.len(), 3);
+    assert_eq!(
+        shards.iter().map(|s| s.num_active_items()).sum::<usize>(),
+        30
+    );
+    assert!(shards.iter().all(|s| !s.is_empty()));
+}
+
+#[test]
+fn test_sharded_try_update_overflow() {
+    let sketch = ShardedFrequentItems::<u64>::new(1, 8);
+    sketch.try_update_with_count(1, u64::MAX).unwrap();
+    assert!(sketch.try_update_with_count(1, 1).is_err());
+    assert_eq!(sketch.total_weight(), u64::MAX);
+}
+
+#[test]
+#[should_panic(expected = "num_shards must be positive")]
+fn test_sharded_zero_shards() {
+    let _ = ShardedFrequentItems::<u64>::new(0, 8);
+}
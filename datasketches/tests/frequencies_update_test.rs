@@ -15,8 +15,10 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use datasketches::error::ErrorKind;
 use datasketches::frequencies::ErrorType;
 use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::frequencies::PurgeStrategy;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct TestItem(i32);
@@ -53,7 +55,10 @@ fn test_capacity_and_epsilon_helpers() {
     let expected = 3.5 / 1024.0;
     assert!((epsilon - expected).abs() < 1e-12);
 
-    let apriori = FrequentItemsSketch::<i64>::apriori_error(10, 10_000);
+    let epsilon = FrequentItemsSketch::<i64>::epsilon_for_map_size(1024);
+    assert!((epsilon - expected).abs() < 1e-12);
+
+    let apriori = FrequentItemsSketch::<i64>::apriori_error(1024, 10_000);
     assert!((apriori - expected * 10_000.0).abs() < 1e-9);
 
     let items: FrequentItemsSketch<i32> = FrequentItemsSketch::new(1024);
@@ -479,6 +484,101 @@ fn test_longs_reset() {
     assert_eq!(sketch.lg_max_map_size(), 3);
 }
 
+#[test]
+fn test_top_k_sorted_by_lower_bound() {
+    let mut sketch: FrequentItemsSketch<i64> = FrequentItemsSketch::new(64);
+    for item in 1..=10 {
+        sketch.update_with_count(item, item as u64);
+    }
+
+    let rows = sketch.top_k(3, ErrorType::NoFalsePositives);
+    let items: Vec<i64> = rows.iter().map(|row| *row.item()).collect();
+    assert_eq!(items, vec![10, 9, 8]);
+    assert_eq!(rows[0].lower_bound(), 10);
+
+    let rows = sketch.top_k(100, ErrorType::NoFalseNegatives);
+    assert_eq!(rows.len(), 10);
+    assert!(
+        rows.windows(2)
+            .all(|w| w[0].lower_bound() >= w[1].lower_bound())
+    );
+
+    assert!(sketch.top_k(0, ErrorType::NoFalseNegatives).is_empty());
+}
+
+#[test]
+fn test_top_k_estimation_mode() {
+    let mut sketch: FrequentItemsSketch<i64> = FrequentItemsSketch::new(8);
+    sketch.update_with_count(1, 10);
+    for item in 2..=6 {
+        sketch.update(item);
+    }
+    sketch.update_with_count(7, 15);
+    for item in 8..=12 {
+        sketch.update(item);
+    }
+    assert!(sketch.maximum_error() > 0);
+
+    let rows = sketch.top_k(5, ErrorType::NoFalsePositives);
+    assert_eq!(rows, sketch.frequent_items(ErrorType::NoFalsePositives));
+
+    let rows = sketch.top_k(1, ErrorType::NoFalseNegatives);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].item(), &7);
+}
+
+#[test]
+fn test_iter_rows() {
+    let mut sketch: FrequentItemsSketch<String> = FrequentItemsSketch::new(64);
+    assert_eq!(sketch.iter().count(), 0);
+
+    sketch.update_with_count("a".to_string(), 3);
+    sketch.update("b".to_string());
+
+    let mut rows: Vec<_> = sketch.iter().map(|row| row.cloned()).collect();
+    rows.sort_by(|a, b| a.item().cmp(b.item()));
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].item(), "a");
+    assert_eq!(rows[0].estimate(), 3);
+    assert_eq!(rows[1].item(), "b");
+    assert_eq!(rows[1].lower_bound(), 1);
+    assert_eq!(rows[1].upper_bound(), 1);
+}
+
+#[test]
+fn test_try_update_with_count_overflow() {
+    let mut sketch = FrequentItemsSketch::<i64>::new(8);
+    sketch.try_update_with_count(1, u64::MAX - 10).unwrap();
+    sketch.try_update_with_count(2, 0).unwrap();
+    for i in 3..10 {
+        sketch.try_update_with_count(i, 1).unwrap();
+    }
+
+    let err = sketch.try_update_with_count(1, 4).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    assert_eq!(sketch.total_weight(), u64::MAX - 3);
+    assert!(sketch.upper_bound(&1) <= sketch.total_weight());
+
+    sketch.try_update_with_count(1, 3).unwrap();
+    assert_eq!(sketch.total_weight(), u64::MAX);
+    assert!(sketch.lower_bound(&1) >= u64::MAX - 10);
+    assert!(sketch.try_update_with_count(2, 1).is_err());
+}
+
+#[test]
+#[should_panic(expected = "total weight of the stream overflows u64")]
+fn test_update_with_count_overflow_panics() {
+    let mut sketch = FrequentItemsSketch::<i64>::new(8);
+    sketch.update_with_count(1, u64::MAX);
+    sketch.update(2);
+}
+
+#[test]
+#[should_panic(expected = "max_map_size must be power of 2")]
+fn test_apriori_error_invalid_map_size_panics() {
+    FrequentItemsSketch::<i64>::apriori_error(1000, 10_000);
+}
+
 #[test]
 #[should_panic(expected = "max_map_size must be power of 2")]
 fn test_longs_invalid_map_size_panics() {
@@ -490,3 +590,397 @@ fn test_longs_invalid_map_size_panics() {
 fn test_items_invalid_map_size_panics() {
     let _ = FrequentItemsSketch::<String>::new(6);
 }
+
+#[test]
+fn test_shrink_to_fit() {
+    let mut sketch: FrequentItemsSketch<String> = FrequentItemsSketch::new(1024);
+    sketch.shrink_to_fit();
+    assert_eq!(sketch.lg_cur_map_size(), 3);
+
+    for i in 0..10 {
+        sketch.update_with_count(format!("heavy-{i}"), 1_000);
+    }
+    // one purge evicts all the light items seen so far
+    for i in 0..780 {
+        sketch.update(format!("light-{i}"));
+    }
+    assert_eq!(sketch.lg_cur_map_size(), 10);
+    let num_active = sketch.num_active_items();
+    let rows = sorted_rows(&sketch);
+    let total_weight = sketch.total_weight();
+    let maximum_error = sketch.maximum_error();
+
+    sketch.shrink_to_fit();
+    let lg_cur_map_size = sketch.lg_cur_map_size();
+    assert!(lg_cur_map_size < 10);
+    assert!(sketch.current_map_capacity() >= num_active);
+    assert!(sketch.current_map_capacity() < 2 * num_active.max(6));
+    assert_eq!(sketch.num_active_items(), num_active);
+    assert_eq!(sketch.total_weight(), total_weight);
+    assert_eq!(sketch.maximum_error(), maximum_error);
+    assert_eq!(sorted_rows(&sketch), rows);
+
+    // shrinking again is a no-op, and the shrunk map survives a round trip
+    sketch.shrink_to_fit();
+    assert_eq!(sketch.lg_cur_map_size(), lg_cur_map_size);
+    let restored = FrequentItemsSketch::<String>::deserialize(&sketch.serialize()).unwrap();
+    assert_eq!(restored.lg_cur_map_size(), lg_cur_map_size);
+    assert_eq!(sorted_rows(&restored), rows);
+
+    // the map grows back as items arrive
+    for i in 0..2_000 {
+        sketch.update(format!("late-{i}"));
+    }
+    assert_eq!(sketch.lg_cur_map_size(), 10);
+    assert!(sketch.estimate(&"heavy-0".to_string()) >= 1_000);
+}
+
+#[test]
+fn test_with_initial_capacity() {
+    let mut presized = FrequentItemsSketch::<i64>::with_initial_capacity(1024, 10);
+    assert_eq!(presized.lg_cur_map_size(), 10);
+    assert_eq!(presized.current_map_capacity(), 768);
+    let mut grown = FrequentItemsSketch::<i64>::new(1024);
+    for i in 0..500 {
+        presized.update_with_count(i, i as u64 + 1);
+        grown.update_with_count(i, i as u64 + 1);
+    }
+    assert_eq!(presized.lg_cur_map_size(), 10);
+    assert_eq!(presized.total_weight(), grown.total_weight());
+    for i in 0..500 {
+        assert_eq!(presized.estimate(&i), grown.estimate(&i));
+    }
+
+    // sizes below the minimum are raised to it
+    let small = FrequentItemsSketch::<i64>::with_initial_capacity(1024, 0);
+    assert_eq!(small.lg_cur_map_size(), 3);
+
+    let err = FrequentItemsSketch::<i64>::try_with_initial_capacity(1024, 11).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    assert!(FrequentItemsSketch::<i64>::try_with_initial_capacity(1000, 3).is_err());
+}
+
+#[test]
+#[should_panic(expected = "initial_lg_size must not exceed lg of max_map_size 6, got 7")]
+fn test_with_initial_capacity_too_large_panics() {
+    FrequentItemsSketch::<i64>::with_initial_capacity(64, 7);
+}
+
+fn sorted_rows(sketch: &FrequentItemsSketch<String>) -> Vec<(String, u64, u64, u64)> {
+    let mut rows: Vec<_> = sketch
+        .frequent_items(ErrorType::NoFalsePositives)
+        .into_iter()
+        .map(|row| {
+            let (lower_bound, upper_bound) = (row.lower_bound(), row.upper_bound());
+            (row.item().clone(), row.estimate(), lower_bound, upper_bound)
+        })
+        .collect();
+    rows.sort();
+    rows
+}
+
+#[test]
+fn test_exact_median_purge() {
+    let mut sketch: FrequentItemsSketch<i64> =
+        FrequentItemsSketch::with_purge_strategy(8, PurgeStrategy::ExactMedian);
+    assert_eq!(sketch.purge_strategy(), PurgeStrategy::ExactMedian);
+    // the seventh item overflows the capacity of 6 and purges the median count of 4
+    for i in 1..=7 {
+        sketch.update_with_count(i, i as u64);
+    }
+    assert_eq!(sketch.maximum_error(), 4);
+    assert_eq!(sketch.num_active_items(), 3);
+    for i in 5..=7 {
+        assert_eq!(sketch.lower_bound(&i), i as u64 - 4);
+        assert_eq!(sketch.upper_bound(&i), i as u64);
+    }
+
+    sketch.reset();
+    assert_eq!(sketch.purge_strategy(), PurgeStrategy::ExactMedian);
+    assert_eq!(
+        FrequentItemsSketch::<i64>::new(8).purge_strategy(),
+        PurgeStrategy::Sampled
+    );
+    assert!(
+        FrequentItemsSketch::<i64>::try_with_purge_strategy(7, PurgeStrategy::Sampled).is_err()
+    );
+}
+
+#[test]
+fn test_exact_median_purge_is_independent_of_map_layout() {
+    let mut sketch: FrequentItemsSketch<i64> =
+        FrequentItemsSketch::with_purge_strategy(64, PurgeStrategy::ExactMedian);
+    for i in 0..10 {
+        sketch.update_with_count(i, 100 + i as u64);
+    }
+    for i in 10..60 {
+        sketch.update(i);
+    }
+    // same counters in a smaller map with a different slot layout
+    let mut shrunk = sketch.clone();
+    shrunk.shrink_to_fit();
+    assert!(shrunk.lg_cur_map_size() < sketch.lg_cur_map_size());
+
+    for i in 0..5_000 {
+        let item = (i * 7919) % 300;
+        sketch.update_with_count(item, 1 + (i % 5) as u64);
+        shrunk.update_with_count(item, 1 + (i % 5) as u64);
+    }
+    assert_eq!(sketch.maximum_error(), shrunk.maximum_error());
+    assert_eq!(sketch.num_active_items(), shrunk.num_active_items());
+    for i in 0..300 {
+        assert_eq!(sketch.lower_bound(&i), shrunk.lower_bound(&i));
+    }
+}
+
+#[test]
+fn test_merge_owned_matches_merge() {
+    let build = |seed: u64| {
+        let mut sketch: FrequentItemsSketch<String> = FrequentItemsSketch::new(32);
+        for i in 0..2_000u64 {
+            let item = (i * 31 + seed * 17) % 200;
+            sketch.update_with_count(format!("item-{item}"), 1 + i % 3);
+        }
+        sketch
+    };
+    let left = build(1);
+    let right = build(2);
+    assert!(left.maximum_error() > 0);
+    assert!(right.maximum_error() > 0);
+
+    let mut by_ref = left.clone();
+    by_ref.merge(&right);
+    let owned = left.clone().merge_owned(right.clone());
+
+    assert_eq!(
+        owned.total_weight(),
+        left.total_weight() + right.total_weight()
+    );
+    assert!(owned.maximum_error() >= left.maximum_error() + right.maximum_error());
+    assert_eq!(owned.maximum_error(), by_ref.maximum_error());
+    assert_eq!(owned.num_active_items(), by_ref.num_active_items());
+    for i in 0..200 {
+        let item = format!("item-{i}");
+        assert_eq!(owned.lower_bound(&item), by_ref.lower_bound(&item));
+        assert_eq!(owned.upper_bound(&item), by_ref.upper_bound(&item));
+    }
+
+    let empty: FrequentItemsSketch<String> = FrequentItemsSketch::new(32);
+    let merged = empty.clone().merge_owned(left.clone());
+    assert_eq!(merged.total_weight(), left.total_weight());
+    assert_eq!(merged.maximum_error(), left.maximum_error());
+    assert_eq!(
+        left.clone().merge_owned(empty).total_weight(),
+        left.total_weight()
+    );
+}
+
+#[test]
+fn test_merge_saturates_near_u64_max() {
+    let mut heavy: FrequentItemsSketch<i64> = FrequentItemsSketch::new(8);
+    heavy.update_with_count(1, u64::MAX - 1);
+    let mut other = FrequentItemsSketch::new(8);
+    other.update_with_count(1, 3);
+    other.update_with_count(2, 5);
+
+    let mut by_ref = heavy.clone();
+    by_ref.merge(&other);
+    let owned = heavy.merge_owned(other);
+    for merged in [by_ref, owned] {
+        assert_eq!(merged.total_weight(), u64::MAX);
+        assert_eq!(merged.estimate(&1), u64::MAX);
+        assert_eq!(merged.lower_bound(&2), 5);
+        assert!(
+            merged
+                .frequent_items(ErrorType::NoFalseNegatives)
+                .iter()
+                .all(|row| row.upper_bound() >= row.lower_bound())
+        );
+    }
+}
+
+#[test]
+fn test_frequent_items_iter_matches_frequent_items() {
+    let mut sketch: FrequentItemsSketch<String> = FrequentItemsSketch::new(16);
+    for i in 0..1_000u64 {
+        if i % 3 == 0 {
+            sketch.update_with_count(format!("item-{}", i % 4), 20);
+        } else {
+            sketch.update(format!("item-{i}"));
+        }
+    }
+    assert!(sketch.maximum_error() > 0);
+
+    for error_type in [ErrorType::NoFalseNegatives, ErrorType::NoFalsePositives] {
+        let mut expected = sketch.frequent_items(error_type);
+        let mut actual: Vec<_> = sketch
+            .frequent_items_iter(error_type)
+            .map(|row| row.cloned())
+            .collect();
+        assert!(!actual.is_empty());
+        expected.sort_by(|a, b| a.item().cmp(b.item()));
+        actual.sort_by(|a, b| a.item().cmp(b.item()));
+        assert_eq!(actual, expected, "{error_type:?}");
+    }
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_row_serde() {
+    use datasketches::frequencies::Row;
+
+    let mut sketch: FrequentItemsSketch<String> = FrequentItemsSketch::new(8);
+    sketch.update_with_count("apple".to_string(), 3);
+    let rows = sketch.frequent_items(ErrorType::NoFalsePositives);
+    let json = serde_json::to_string(&rows).unwrap();
+    assert_eq!(
+        json,
+        r#"[{"item":"apple","estimate":3,"upper_bound":3,"lower_bound":3,"guaranteed":true}]"#
+    );
+    let decoded: Vec<Row<String>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, rows);
+
+    let borrowed: Vec<_> = sketch.iter().collect();
+    assert_eq!(serde_json::to_string(&borrowed).unwrap(), json);
+}
+
+#[test]
+fn test_memory_usage_counts_map_capacity() {
+    let mut sketch = FrequentItemsSketch::<u64>::new(64);
+    let empty = sketch.memory_usage_bytes();
+    sketch.update(1);
+    // a single item does not change the footprint until the map grows
+    assert_eq!(sketch.memory_usage_bytes(), empty);
+    for i in 0..10_000 {
+        sketch.update(i);
+    }
+    assert!(sketch.memory_usage_bytes() > empty);
+    assert!(sketch.memory_usage_bytes() >= 64 * (size_of::<Option<u64>>() + 8 + 2));
+}
+
+#[test]
+fn test_clear_keeps_map() {
+    let mut sketch =
+        FrequentItemsSketch::<i64>::with_purge_strategy(64, PurgeStrategy::ExactMedian);
+    for i in 0..1_000 {
+        sketch.update(i % 100);
+    }
+    assert!(sketch.maximum_error() > 0);
+    let lg_cur_map_size = sketch.lg_cur_map_size();
+
+    sketch.clear();
+    assert!(sketch.is_empty());
+    assert_eq!(sketch.total_weight(), 0);
+    assert_eq!(sketch.maximum_error(), 0);
+    assert_eq!(sketch.lg_cur_map_size(), lg_cur_map_size);
+    assert_eq!(sketch.purge_strategy(), PurgeStrategy::ExactMedian);
+
+    sketch.update_with_count(7, 3);
+    assert_eq!(sketch.estimate(&7), 3);
+    assert_eq!(sketch.num_active_items(), 1);
+    let restored = FrequentItemsSketch::<i64>::deserialize(&sketch.serialize()).unwrap();
+    assert_eq!(restored.estimate(&7), 3);
+}
+
+#[test]
+fn test_merge_many_bounds_hold() {
+    let mut exact = std::collections::HashMap::new();
+    let partitions: Vec<FrequentItemsSketch<i64>> = (0..16u64)
+        .map(|p| {
+            let mut sketch = FrequentItemsSketch::new(64);
+            for i in 0..3_000u64 {
+                let item = ((i % 97) * (i % 13) + p) as i64;
+                sketch.update_with_count(item, 1 + i % 3);
+                *exact.entry(item).or_insert(0u64) += 1 + i % 3;
+            }
+            sketch
+        })
+        .collect();
+    let refs: Vec<_> = partitions.iter().collect();
+
+    let mut merged = FrequentItemsSketch::new(64);
+    merged.merge_many(&refs);
+    let mut sequential = FrequentItemsSketch::new(64);
+    for sketch in &partitions {
+        sequential.merge(sketch);
+    }
+
+    assert_eq!(merged.total_weight(), exact.values().sum::<u64>());
+    assert_eq!(merged.total_weight(), sequential.total_weight());
+    assert!(merged.num_active_items() <= merged.maximum_map_capacity());
+    assert!(merged.maximum_error() <= sequential.maximum_error());
+    for (item, &count) in &exact {
+        assert!(merged.lower_bound(item) <= count, "{item}");
+        assert!(merged.upper_bound(item) >= count, "{item}");
+    }
+}
+
+#[test]
+fn test_merge_many_without_purge_is_exact() {
+    let mut merged: FrequentItemsSketch<i64> = FrequentItemsSketch::new(64);
+    merged.update_with_count(1, 5);
+    let mut other = FrequentItemsSketch::new(8);
+    other.update_with_count(1, 3);
+    other.update_with_count(2, 4);
+    let empty = FrequentItemsSketch::new(8);
+
+    merged.merge_many(&[&other, &empty]);
+    assert_eq!(merged.total_weight(), 12);
+    assert_eq!(merged.maximum_error(), 0);
+    assert_eq!(merged.estimate(&1), 8);
+    assert_eq!(merged.estimate(&2), 4);
+
+    merged.merge_many(&[]);
+    assert_eq!(merged.total_weight(), 12);
+}
+
+#[test]
+fn test_merge_many_saturates_near_u64_max() {
+    let mut merged: FrequentItemsSketch<i64> = FrequentItemsSketch::new(8);
+    merged.update_with_count(1, u64::MAX - 10);
+    let mut heavy = FrequentItemsSketch::new(8);
+    heavy.update_with_count(1, u64::MAX - 10);
+    let mut light = FrequentItemsSketch::new(8);
+    for i in 2..20 {
+        light.update_with_count(i, 7);
+    }
+    assert!(light.maximum_error() > 0);
+
+    merged.merge_many(&[&heavy, &light]);
+    assert_eq!(merged.total_weight(), u64::MAX);
+    assert!(merged.lower_bound(&1) >= u64::MAX - 10 - merged.maximum_error());
+    assert_eq!(merged.upper_bound(&1), u64::MAX);
+    assert!(merged.num_active_items() <= merged.maximum_map_capacity());
+}
+
+#[test]
+fn test_rows_flag_guaranteed_items() {
+    let mut sketch: FrequentItemsSketch<i64> = FrequentItemsSketch::new(8);
+    sketch.update_with_count(0, 1_000);
+    for i in 1..=100 {
+        sketch.update_with_count(i, 1 + i as u64 % 5);
+    }
+    let threshold = sketch.threshold();
+    assert!(threshold > 0);
+    assert_eq!(threshold, sketch.maximum_error());
+
+    let rows = sketch.frequent_items(ErrorType::NoFalseNegatives);
+    assert!(rows.iter().any(|row| row.is_guaranteed()));
+    for row in &rows {
+        assert_eq!(row.is_guaranteed(), row.lower_bound() > threshold);
+    }
+    assert!(
+        sketch
+            .frequent_items(ErrorType::NoFalsePositives)
+            .iter()
+            .all(|row| row.is_guaranteed())
+    );
+
+    let custom = threshold + 500;
+    let rows = sketch.frequent_items_with_threshold(ErrorType::NoFalseNegatives, custom);
+    assert_eq!(rows.len(), 1);
+    assert!(rows[0].is_guaranteed());
+    for row in sketch.iter() {
+        assert_eq!(row.is_guaranteed(), row.lower_bound() > threshold);
+    }
+}
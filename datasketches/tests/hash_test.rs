@@ -0,0 +1,169 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::common::SketchInput;
+use datasketches::common::canonical_double_bytes;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::hash::DEFAULT_UPDATE_SEED;
+use datasketches::hash::murmur3_128;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::theta::ThetaSketch;
+
+#[test]
+fn test_murmur3_128_reference_vector() {
+    let key = "The quick brown fox jumps over the lazy dog";
+    assert_eq!(
+        murmur3_128(key.as_bytes(), 0),
+        (0xe34bbc7bbc071b6c, 0x7a433ca9c49a9347)
+    );
+}
+
+#[test]
+fn test_theta_update_hash_matches_update_bytes() {
+    for seed in [DEFAULT_UPDATE_SEED, 7] {
+        let mut from_bytes = ThetaSketch::builder().lg_k(5).seed(seed).build();
+        let mut from_hashes = ThetaSketch::builder().lg_k(5).seed(seed).build();
+        for i in 0u64..1000 {
+            let bytes = i.to_le_bytes();
+            let expected = from_bytes.update_bytes(&bytes);
+            assert_eq!(from_hashes.update_hash(murmur3_128(&bytes, seed)), expected);
+        }
+        assert_eq!(
+            from_bytes.compact(true).serialize(),
+            from_hashes.compact(true).serialize()
+        );
+    }
+}
+
+#[test]
+fn test_hll_update_hash_matches_update_bytes() {
+    let mut from_bytes = HllSketch::new(10, HllType::Hll4);
+    let mut from_hashes = HllSketch::new(10, HllType::Hll4);
+    for i in 0u64..5000 {
+        let bytes = i.to_le_bytes();
+        from_bytes.update_bytes(&bytes);
+        from_hashes.update_hash(murmur3_128(&bytes, DEFAULT_UPDATE_SEED));
+    }
+    assert_eq!(from_bytes.serialize(), from_hashes.serialize());
+}
+
+#[test]
+fn test_cpc_update_hash_matches_update_bytes() {
+    for seed in [DEFAULT_UPDATE_SEED, 7] {
+        let mut from_bytes = CpcSketch::with_seed(10, seed);
+        let mut from_hashes = CpcSketch::with_seed(10, seed);
+        for i in 0u64..5000 {
+            let bytes = i.to_le_bytes();
+            from_bytes.update_bytes(&bytes);
+            from_hashes.update_hash(murmur3_128(&bytes, seed));
+        }
+        assert_eq!(from_bytes.serialize(), from_hashes.serialize());
+    }
+}
+
+#[test]
+fn test_theta_update_prehashed_shares_one_hash() {
+    let mut small = ThetaSketch::builder().lg_k(5).build();
+    let mut large = ThetaSketch::builder().lg_k(12).build();
+    let mut reference = ThetaSketch::builder().lg_k(5).build();
+    for i in 0u64..1000 {
+        let bytes = i.to_le_bytes();
+        let (h1, _) = murmur3_128(&bytes, DEFAULT_UPDATE_SEED);
+        let expected = reference.update_bytes(&bytes);
+        assert_eq!(small.update_prehashed(h1), expected);
+        large.update_prehashed(h1);
+    }
+    assert_eq!(
+        small.compact(true).serialize(),
+        reference.compact(true).serialize()
+    );
+    assert_eq!(large.num_retained(), 1000);
+}
+
+#[test]
+fn test_sketch_input_matches_java_bytes() {
+    // integers are widened to a Java long
+    let mut theta = ThetaSketch::builder().build();
+    let mut theta_ref = ThetaSketch::builder().build();
+    let mut hll = HllSketch::new(12, HllType::Hll8);
+    let mut hll_ref = HllSketch::new(12, HllType::Hll8);
+    let mut cpc = CpcSketch::new(11);
+    let mut cpc_ref = CpcSketch::new(11);
+    let mut countmin = CountMinSketch::<u64>::new(3, 64);
+    for value in [7i16, -1, i16::MIN] {
+        let bytes = (value as i64).to_le_bytes();
+        let hash = murmur3_128(&bytes, DEFAULT_UPDATE_SEED);
+        assert_eq!(theta.update_input(value), theta_ref.update_hash(hash));
+        hll.update_input(value as i32);
+        hll_ref.update_hash(hash);
+        cpc.update_input(value as isize);
+        cpc_ref.update_hash(hash);
+        countmin.update_input(value as i32);
+        assert_eq!(countmin.estimate(SketchInput(&bytes[..])), 1);
+    }
+    assert!(theta.iter().eq(theta_ref.iter()));
+    assert_eq!(hll.serialize(), hll_ref.serialize());
+    assert_eq!(cpc.serialize(), cpc_ref.serialize());
+
+    // strings hash as their UTF-8 bytes, with or without ownership
+    let mut from_str = ThetaSketch::builder().build();
+    from_str.update_input("apple");
+    let mut from_string = ThetaSketch::builder().build();
+    from_string.update_input("apple".to_string());
+    let mut from_bytes = ThetaSketch::builder().build();
+    from_bytes.update_bytes(b"apple");
+    assert!(from_str.iter().eq(from_bytes.iter()));
+    assert!(from_string.iter().eq(from_bytes.iter()));
+
+    // doubles are canonicalized like update_f64
+    for (value, canonical) in [
+        (-0.0, 0.0),
+        (f64::from_bits(0x7ff8_0000_0000_0001), f64::NAN),
+    ] {
+        let mut input = ThetaSketch::builder().build();
+        input.update_input(value);
+        let mut reference = ThetaSketch::builder().build();
+        reference.update_f64(canonical);
+        assert!(input.iter().eq(reference.iter()));
+    }
+    let mut from_f32 = HllSketch::new(12, HllType::Hll8);
+    from_f32.update_input(1.5f32);
+    let mut from_f64 = HllSketch::new(12, HllType::Hll8);
+    from_f64.update_input(1.5f64);
+    assert_eq!(from_f32.serialize(), from_f64.serialize());
+}
+
+#[test]
+fn test_canonical_double_bytes() {
+    assert_eq!(canonical_double_bytes(1.5), 1.5f64.to_le_bytes());
+    assert_eq!(canonical_double_bytes(-0.0), 0.0f64.to_le_bytes());
+    let java_nan = 0x7ff8_0000_0000_0000u64.to_le_bytes();
+    for nan in [f64::NAN, -f64::NAN, f64::from_bits(0xfff0_0000_0000_0001)] {
+        assert_eq!(canonical_double_bytes(nan), java_nan);
+    }
+
+    for value in [-0.0, 0.0, 1.5, f64::NAN, f64::INFINITY] {
+        let hash = murmur3_128(&canonical_double_bytes(value), DEFAULT_UPDATE_SEED);
+        let mut cpc = CpcSketch::new(10);
+        cpc.update_f64(value);
+        let mut cpc_ref = CpcSketch::new(10);
+        cpc_ref.update_hash(hash);
+        assert_eq!(cpc.serialize(), cpc_ref.serialize());
+    }
+}
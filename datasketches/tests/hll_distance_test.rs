@@ -0,0 +1,91 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::hll::HllUnion;
+use datasketches::hll::register_distance;
+
+fn sketch_with_range(lg_k: u8, hll_type: HllType, start: u64, count: u64) -> HllSketch {
+    let mut sketch = HllSketch::new(lg_k, hll_type);
+    for i in start..start + count {
+        sketch.update(i);
+    }
+    sketch
+}
+
+#[test]
+fn test_identical_sketches() {
+    for count in [0, 10, 300, 100_000] {
+        let a = sketch_with_range(12, HllType::Hll8, 0, count);
+        let distance = register_distance(&a, &a.clone());
+        assert_eq!(distance.num_registers(), 4096);
+        assert_eq!(distance.num_changed(), 0);
+        assert_eq!(distance.l1(), 0);
+        assert_eq!(distance.max_diff(), 0);
+        assert_eq!(distance.fraction_changed(), 0.0);
+    }
+}
+
+#[test]
+fn test_same_stream_across_types_and_modes() {
+    // the same stream yields identical registers regardless of the target type or mode
+    for count in [5, 500, 50_000] {
+        let a = sketch_with_range(11, HllType::Hll4, 0, count);
+        let b = sketch_with_range(11, HllType::Hll6, 0, count);
+        let c = sketch_with_range(11, HllType::Hll8, 0, count);
+        assert_eq!(register_distance(&a, &b).num_changed(), 0);
+        assert_eq!(register_distance(&b, &c).num_changed(), 0);
+        assert_eq!(register_distance(&a, &c).l1(), 0);
+    }
+}
+
+#[test]
+fn test_disjoint_populations() {
+    let a = sketch_with_range(10, HllType::Hll8, 0, 50_000);
+    let b = sketch_with_range(10, HllType::Hll8, 1_000_000, 50_000);
+    let distance = register_distance(&a, &b);
+    assert!(distance.fraction_changed() > 0.5);
+    assert!(distance.l1() >= distance.num_changed());
+    assert!(distance.max_diff() > 0);
+
+    // a small drift changes far fewer registers than a different population
+    let c = sketch_with_range(10, HllType::Hll8, 0, 50_500);
+    assert!(register_distance(&a, &c).fraction_changed() < distance.fraction_changed());
+}
+
+#[test]
+fn test_symmetric() {
+    let a = sketch_with_range(10, HllType::Hll4, 0, 20_000);
+    let b = sketch_with_range(10, HllType::Hll6, 10_000, 20_000);
+    assert_eq!(register_distance(&a, &b), register_distance(&b, &a));
+}
+
+#[test]
+fn test_different_lg_k_matches_union_downsampling() {
+    let large = sketch_with_range(12, HllType::Hll8, 0, 20_000);
+    let small = sketch_with_range(10, HllType::Hll8, 0, 20_000);
+
+    let distance = register_distance(&large, &small);
+    assert_eq!(distance.lg_k(), 10);
+    assert_eq!(distance.num_changed(), 0);
+
+    let mut union = HllUnion::new(10);
+    union.update(&large);
+    let folded = union.to_sketch(HllType::Hll8);
+    assert_eq!(register_distance(&folded, &small).num_changed(), 0);
+}
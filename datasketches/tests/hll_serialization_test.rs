@@ -20,8 +20,13 @@ mod common;
 use std::fs;
 use std::path::PathBuf;
 
+use common::CORPUS_NS;
+use common::Language;
+use common::corpus_files;
 use common::serialization_test_data;
+use datasketches::common::NumStdDev;
 use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
 
 fn test_sketch_file(path: PathBuf, expected_cardinality: usize, expected_lg_k: u8) {
     let expected = expected_cardinality as f64;
@@ -104,66 +109,42 @@ fn test_sketch_file(path: PathBuf, expected_cardinality: usize, expected_lg_k: u
 
 #[test]
 fn test_java_hll4_compatibility() {
-    let test_cases = [0, 1, 10, 100, 1000, 10000, 100000, 1000000];
-
-    for n in test_cases {
-        let filename = format!("hll4_n{}_java.sk", n);
-        let path = serialization_test_data("java_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Java, "hll4", &CORPUS_NS) {
         test_sketch_file(path, n, 12);
     }
 }
 
 #[test]
 fn test_java_hll6_compatibility() {
-    let test_cases = [0, 1, 10, 100, 1000, 10000, 100000, 1000000];
-
-    for n in test_cases {
-        let filename = format!("hll6_n{}_java.sk", n);
-        let path = serialization_test_data("java_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Java, "hll6", &CORPUS_NS) {
         test_sketch_file(path, n, 12);
     }
 }
 
 #[test]
 fn test_java_hll8_compatibility() {
-    let test_cases = [0, 1, 10, 100, 1000, 10000, 100000, 1000000];
-
-    for n in test_cases {
-        let filename = format!("hll8_n{}_java.sk", n);
-        let path = serialization_test_data("java_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Java, "hll8", &CORPUS_NS) {
         test_sketch_file(path, n, 12);
     }
 }
 
 #[test]
 fn test_cpp_hll4_compatibility() {
-    let test_cases = [0, 1, 10, 100, 1000, 10000, 100000, 1000000];
-
-    for n in test_cases {
-        let filename = format!("hll4_n{}_cpp.sk", n);
-        let path = serialization_test_data("cpp_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Cpp, "hll4", &CORPUS_NS) {
         test_sketch_file(path, n, 12);
     }
 }
 
 #[test]
 fn test_cpp_hll6_compatibility() {
-    let test_cases = [0, 1, 10, 100, 1000, 10000, 100000, 1000000];
-
-    for n in test_cases {
-        let filename = format!("hll6_n{}_cpp.sk", n);
-        let path = serialization_test_data("cpp_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Cpp, "hll6", &CORPUS_NS) {
         test_sketch_file(path, n, 12);
     }
 }
 
 #[test]
 fn test_cpp_hll8_compatibility() {
-    let test_cases = [0, 1, 10, 100, 1000, 10000, 100000, 1000000];
-
-    for n in test_cases {
-        let filename = format!("hll8_n{}_cpp.sk", n);
-        let path = serialization_test_data("cpp_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Cpp, "hll8", &CORPUS_NS) {
         test_sketch_file(path, n, 12);
     }
 }
@@ -195,3 +176,52 @@ fn test_estimate_accuracy() {
         assert!(error_pct < 2., "Error too high: {:.3}%", error_pct);
     }
 }
+
+const FLAGS_BYTE: usize = 5;
+const OUT_OF_ORDER_FLAG: u8 = 16;
+const REBUILD_CURMIN_NUM_KXQ_FLAG: u8 = 32;
+
+/// Serializes an out-of-order HLL mode sketch, as a datasketches-java union would.
+fn out_of_order_image(hll_type: HllType) -> Vec<u8> {
+    let mut sketch = HllSketch::new(10, hll_type);
+    for i in 0..10_000 {
+        sketch.update(i);
+    }
+    let mut bytes = sketch.serialize();
+    bytes[FLAGS_BYTE] |= OUT_OF_ORDER_FLAG;
+    bytes
+}
+
+#[test]
+fn test_rebuild_curmin_num_kxq_flag() {
+    for hll_type in [HllType::Hll4, HllType::Hll6, HllType::Hll8] {
+        let bytes = out_of_order_image(hll_type);
+        let expected = HllSketch::deserialize(&bytes).unwrap();
+
+        // a Java union gadget leaves kxq0, kxq1 and num_at_cur_min stale
+        let mut stale = bytes.clone();
+        stale[FLAGS_BYTE] |= REBUILD_CURMIN_NUM_KXQ_FLAG;
+        stale[16..36].fill(0);
+        let sketch = HllSketch::deserialize(&stale).unwrap();
+        assert_eq!(sketch.estimate(), expected.estimate(), "{hll_type:?}");
+        assert_eq!(
+            sketch.upper_bound(NumStdDev::Two),
+            expected.upper_bound(NumStdDev::Two),
+            "{hll_type:?}"
+        );
+    }
+}
+
+#[test]
+fn test_compact_flag_in_hll_mode() {
+    // datasketches-java and datasketches-cpp write the full register array in compact images
+    for hll_type in [HllType::Hll4, HllType::Hll6, HllType::Hll8] {
+        let bytes = out_of_order_image(hll_type);
+        let expected = HllSketch::deserialize(&bytes).unwrap();
+
+        let mut compact = bytes.clone();
+        compact[FLAGS_BYTE] |= 8;
+        let sketch = HllSketch::deserialize(&compact).unwrap();
+        assert_eq!(sketch.estimate(), expected.estimate(), "{hll_type:?}");
+    }
+}
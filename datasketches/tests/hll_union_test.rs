@@ -31,6 +31,7 @@ use datasketches::common::NumStdDev;
 use datasketches::hll::HllSketch;
 use datasketches::hll::HllType;
 use datasketches::hll::HllUnion;
+use datasketches::hll::estimate_union;
 
 #[test]
 fn test_union_basic_operations() {
@@ -584,3 +585,122 @@ fn test_union_validation() {
     union.reset();
     assert_eq!(union.lg_max_k(), 15, "lg_max_k should persist after reset");
 }
+
+#[test]
+fn test_union_many() {
+    let partitions: Vec<HllSketch> = (0..50)
+        .map(|p| {
+            let mut sketch = HllSketch::new(12, HllType::Hll4);
+            for i in 0..(p * 40) {
+                sketch.update(p * 1000 + i);
+            }
+            sketch
+        })
+        .collect();
+
+    let mut sequential = HllUnion::new(12);
+    for sketch in &partitions {
+        sequential.update(sketch);
+    }
+    let mut bulk = HllUnion::new(12);
+    bulk.union_many(&partitions);
+
+    assert_eq!(bulk.estimate(), sequential.estimate());
+    assert_eq!(
+        bulk.to_sketch(HllType::Hll8).serialize(),
+        sequential.to_sketch(HllType::Hll8).serialize()
+    );
+}
+
+#[test]
+fn test_union_mixed_lg_k_rollup() {
+    // a fleet whose configuration changed over time: the rollup lands on the smallest lg_k
+    // among the sketches in HLL mode, regardless of the order they arrive in
+    let configs = [
+        (14, HllType::Hll4, 0..20_000),
+        (11, HllType::Hll6, 10_000..30_000),
+        (12, HllType::Hll8, 25_000..40_000),
+        (10, HllType::Hll8, 39_990..40_000),
+    ];
+    let sketches: Vec<HllSketch> = configs
+        .into_iter()
+        .map(|(lg_k, hll_type, range)| {
+            let mut sketch = HllSketch::new(lg_k, hll_type);
+            for i in range {
+                sketch.update(i);
+            }
+            sketch
+        })
+        .collect();
+    // the lg_k=10 sketch is still in list mode and does not constrain the result
+    assert_eq!(sketches[3].estimate().round(), 10.0);
+
+    for order in [[0, 1, 2, 3], [3, 2, 1, 0], [2, 3, 0, 1], [1, 0, 3, 2]] {
+        let mut union = HllUnion::new(12);
+        for i in order {
+            union.update(&sketches[i]);
+        }
+        assert_eq!(union.lg_config_k(), 11, "order {order:?}");
+
+        for hll_type in [HllType::Hll4, HllType::Hll6, HllType::Hll8] {
+            let result = union.to_sketch(hll_type);
+            assert_eq!(result.lg_config_k(), 11);
+            let estimate = result.estimate();
+            assert!(
+                (estimate - 40_000.0).abs() < 40_000.0 * 0.1,
+                "order {order:?}: expected ~40000, got {estimate}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_estimate_union_of_images() {
+    let sketches: Vec<HllSketch> = [
+        (12, HllType::Hll4, 0..5_000),
+        (14, HllType::Hll8, 2_500..10_000),
+        (10, HllType::Hll6, 9_990..10_000),
+    ]
+    .into_iter()
+    .map(|(lg_k, hll_type, range)| {
+        let mut sketch = HllSketch::new(lg_k, hll_type);
+        for i in range {
+            sketch.update(i);
+        }
+        sketch
+    })
+    .collect();
+    let images: Vec<Vec<u8>> = sketches.iter().map(HllSketch::serialize).collect();
+
+    let mut expected = HllUnion::new(14);
+    expected.union_many(&sketches);
+    assert_eq!(estimate_union(&images).unwrap(), expected.estimate());
+    let slices: Vec<&[u8]> = images.iter().map(Vec::as_slice).collect();
+    assert_eq!(estimate_union(&slices).unwrap(), expected.estimate());
+
+    let mut union = HllUnion::new(14);
+    for image in &images {
+        union.update_serialized(image).unwrap();
+    }
+    assert_eq!(
+        union.to_sketch(HllType::Hll8).serialize(),
+        expected.to_sketch(HllType::Hll8).serialize()
+    );
+}
+
+#[test]
+fn test_estimate_union_of_no_or_invalid_images() {
+    assert_eq!(estimate_union::<&[u8]>(&[]).unwrap(), 0.0);
+    let empty = HllSketch::new(12, HllType::Hll8).serialize();
+    assert_eq!(estimate_union(&[&empty]).unwrap(), 0.0);
+
+    let mut sketch = HllSketch::new(12, HllType::Hll8);
+    sketch.update("apple");
+    let image = sketch.serialize();
+    assert!(estimate_union(&[&image[..], &image[..4]]).is_err());
+    assert!(estimate_union(&[&[0u8; 2][..]]).is_err());
+
+    let mut union = HllUnion::new(12);
+    assert!(union.update_serialized(&image[..image.len() - 1]).is_err());
+    assert!(union.is_empty());
+}
@@ -15,6 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use datasketches::common::CardinalityFamily;
 use datasketches::common::NumStdDev;
 use datasketches::hll::HllSketch;
 use datasketches::hll::HllType;
@@ -354,3 +355,132 @@ fn test_bounds_empty_sketch() {
     assert!(upper >= 0.0, "Upper bound should be non-negative");
     assert!(lower <= upper, "Lower bound should be <= upper bound");
 }
+
+#[test]
+fn test_update_bytes_hashes_raw_bytes() {
+    let mut from_bytes = HllSketch::new(10, HllType::Hll8);
+    let mut from_values = HllSketch::new(10, HllType::Hll8);
+    for i in 0u64..5000 {
+        from_bytes.update_bytes(&i.to_le_bytes());
+        from_values.update(i);
+    }
+    assert_eq!(from_bytes.serialize(), from_values.serialize());
+}
+
+#[test]
+fn test_memory_usage() {
+    for (hll_type, registers) in [
+        (HllType::Hll4, 1 << 11),
+        (HllType::Hll6, 3 << 10),
+        (HllType::Hll8, 1 << 12),
+    ] {
+        let mut sketch = HllSketch::new(12, hll_type);
+        let sparse = sketch.memory_usage_bytes();
+        assert!(sparse >= size_of::<HllSketch>());
+        for i in 0..100_000 {
+            sketch.update(i);
+        }
+        let dense = sketch.memory_usage_bytes();
+        assert!(dense >= size_of::<HllSketch>() + registers, "{hll_type:?}");
+        assert!(dense > sparse, "{hll_type:?}");
+    }
+}
+
+#[test]
+fn test_approximately_equal() {
+    let mut hll4 = HllSketch::new(11, HllType::Hll4);
+    let mut hll8 = HllSketch::new(11, HllType::Hll8);
+    assert!(hll4.approximately_equal(&hll8, 0.0));
+    for i in 0..50_000 {
+        hll4.update(i);
+        hll8.update(i);
+    }
+    assert!(hll4.approximately_equal(&hll8, 0.0));
+    let restored = HllSketch::deserialize(&hll4.serialize()).unwrap();
+    assert!(restored.approximately_equal(&hll8, 0.0));
+
+    let mut more = hll8.clone();
+    for i in 50_000..55_000 {
+        more.update(i);
+    }
+    assert!(!hll4.approximately_equal(&more, 0.01));
+    assert!(hll4.approximately_equal(&more, 0.2));
+    assert!(!hll4.approximately_equal(&HllSketch::new(12, HllType::Hll4), 1.0));
+    assert!(!hll4.approximately_equal(&HllSketch::new(11, HllType::Hll4), 0.5));
+}
+
+#[test]
+#[should_panic(expected = "epsilon must be non-negative")]
+fn test_approximately_equal_rejects_negative_epsilon() {
+    let sketch = HllSketch::new(11, HllType::Hll4);
+    sketch.approximately_equal(&sketch, -0.1);
+}
+
+#[test]
+fn test_estimate_summary() {
+    let mut sketch = HllSketch::new(8, HllType::Hll4);
+    for i in 0..10 {
+        sketch.update(i);
+    }
+    let summary = sketch.estimate_summary(NumStdDev::Two);
+    assert_eq!(summary.family, CardinalityFamily::Hll);
+    assert_eq!(summary.estimate, sketch.estimate());
+    assert_eq!(summary.lb, sketch.lower_bound(NumStdDev::Two));
+    assert_eq!(summary.ub, sketch.upper_bound(NumStdDev::Two));
+    assert_eq!(summary.retained, 10);
+
+    // once in HLL mode, every register is retained
+    for i in 10..10_000 {
+        sketch.update(i);
+    }
+    assert_eq!(sketch.estimate_summary(NumStdDev::One).retained, 256);
+}
+
+#[test]
+fn test_composite_and_raw_estimates() {
+    for hll_type in [HllType::Hll4, HllType::Hll6, HllType::Hll8] {
+        let mut sketch = HllSketch::new(10, hll_type);
+        for i in 0..10 {
+            sketch.update(i);
+        }
+        // list mode has a single estimator
+        assert_eq!(sketch.composite_estimate(), sketch.estimate());
+        assert_eq!(sketch.raw_estimate(), sketch.estimate());
+
+        for i in 10..50_000 {
+            sketch.update(i);
+        }
+        let (hip, composite) = (sketch.estimate(), sketch.composite_estimate());
+        assert!((composite - 50_000.0).abs() < 0.1 * 50_000.0, "{composite}");
+        assert!((hip - 50_000.0).abs() < 0.1 * 50_000.0, "{hip}");
+        assert!((sketch.raw_estimate() - 50_000.0).abs() < 0.2 * 50_000.0);
+
+        // a union of overlapping sketches can only use the composite estimator
+        let mut other = HllSketch::new(10, hll_type);
+        for i in 25_000..50_000 {
+            other.update(i);
+        }
+        let mut union = datasketches::hll::HllUnion::new(10);
+        union.update(&sketch);
+        union.update(&other);
+        let merged = union.to_sketch(HllType::Hll8);
+        assert_eq!(merged.estimate(), merged.composite_estimate());
+        assert_eq!(merged.composite_estimate(), composite);
+        assert!(merged.lower_bound(NumStdDev::Two) <= composite);
+        assert!(merged.upper_bound(NumStdDev::Two) >= composite);
+    }
+}
+
+#[test]
+fn test_lower_bound_at_least_non_zero_registers() {
+    // with 16 registers, the relative error at 3 standard deviations is 0.88, which pulls the
+    // lower bound below the number of registers that were hit by the first few dozen items
+    let mut sketch = HllSketch::new(4, HllType::Hll8);
+    for i in 0..20 {
+        sketch.update(i);
+    }
+    let lower = sketch.lower_bound(NumStdDev::Three);
+    assert!(lower > sketch.estimate() / (1.0 + 0.882303765));
+    assert_eq!(lower.fract(), 0.0);
+    assert!((1.0..=16.0).contains(&lower));
+}
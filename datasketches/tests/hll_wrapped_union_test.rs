@@ -0,0 +1,171 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::common::NumStdDev;
+use datasketches::error::ErrorKind;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::hll::HllUnion;
+use datasketches::hll::WrappedHllUnion;
+use datasketches::hll::register_distance;
+
+fn sketch(lg_k: u8, hll_type: HllType, range: std::ops::Range<u64>) -> HllSketch {
+    let mut sketch = HllSketch::new(lg_k, hll_type);
+    for i in range {
+        sketch.update(i);
+    }
+    sketch
+}
+
+#[test]
+fn test_empty_union_is_a_valid_image() {
+    let mut buf = vec![0xffu8; WrappedHllUnion::required_bytes(10) + 8];
+    let union = WrappedHllUnion::initialize(&mut buf, 10).unwrap();
+    assert!(union.is_empty());
+    assert_eq!(union.estimate(), 0.0);
+    assert_eq!(union.lg_config_k(), 10);
+
+    let len = WrappedHllUnion::required_bytes(10);
+    let decoded = HllSketch::deserialize(&buf[..len]).unwrap();
+    assert_eq!(decoded.lg_config_k(), 10);
+    assert_eq!(decoded.target_type(), HllType::Hll8);
+    assert_eq!(decoded.estimate(), 0.0);
+    // trailing bytes are not touched
+    assert_eq!(buf[len..], [0xff; 8]);
+}
+
+#[test]
+fn test_matches_heap_union() {
+    let inputs = [
+        // sparse inputs in list and set mode, one of them with a smaller lg_k
+        sketch(12, HllType::Hll4, 0..5),
+        sketch(8, HllType::Hll6, 100..112),
+        sketch(12, HllType::Hll8, 1_000..1_300),
+        // dense inputs of every type, one of them with a larger lg_k
+        sketch(12, HllType::Hll4, 0..20_000),
+        sketch(12, HllType::Hll6, 10_000..30_000),
+        sketch(14, HllType::Hll8, 25_000..60_000),
+    ];
+
+    let mut buf = vec![0u8; WrappedHllUnion::required_bytes(12)];
+    let mut wrapped = WrappedHllUnion::initialize(&mut buf, 12).unwrap();
+    let mut heap = HllUnion::new(12);
+    for input in &inputs {
+        wrapped.update(input).unwrap();
+        heap.update(input);
+    }
+    let estimate = wrapped.estimate();
+    assert!(wrapped.lower_bound(NumStdDev::Two) <= estimate);
+    assert!(wrapped.upper_bound(NumStdDev::Two) >= estimate);
+
+    let expected = heap.to_sketch(HllType::Hll8);
+    let result = HllSketch::deserialize(&buf).unwrap();
+    assert_eq!(register_distance(&result, &expected).num_changed(), 0);
+    assert!((result.estimate() - expected.estimate()).abs() < 1e-6 * expected.estimate());
+    assert!((estimate - 60_000.0).abs() < 60_000.0 * 0.05);
+}
+
+#[test]
+fn test_update_value_keeps_hip_until_merge() {
+    let mut buf = vec![0u8; WrappedHllUnion::required_bytes(12)];
+    let mut union = WrappedHllUnion::initialize(&mut buf, 12).unwrap();
+    let mut reference = HllSketch::new(12, HllType::Hll8);
+    for i in 0..10_000 {
+        union.update_value(i);
+        reference.update(i);
+    }
+    let image = HllSketch::deserialize(&buf).unwrap();
+    assert_eq!(register_distance(&image, &reference).num_changed(), 0);
+    let mut union = WrappedHllUnion::wrap(&mut buf).unwrap();
+    // the HIP estimate is kept up to date while only values arrive
+    assert!((union.estimate() - reference.estimate()).abs() < 10_000.0 * 0.01);
+
+    // a merge switches to the composite estimator, which is recorded in the image
+    union.update(&sketch(12, HllType::Hll8, 0..10)).unwrap();
+    let merged = HllSketch::deserialize(&buf).unwrap();
+    assert!((merged.estimate() - 10_000.0).abs() < 10_000.0 * 0.05);
+}
+
+#[test]
+fn test_update_serialized() {
+    let mut buf = vec![0u8; WrappedHllUnion::required_bytes(10)];
+    let mut union = WrappedHllUnion::initialize(&mut buf, 10).unwrap();
+    let image = sketch(10, HllType::Hll4, 0..3).serialize();
+    union.update_serialized(&image).unwrap();
+    assert!(union.update_serialized(&[1, 2, 3]).is_err());
+    assert!((union.estimate() - 3.0).abs() < 0.01);
+}
+
+#[test]
+fn test_rejects_dense_sketch_with_fewer_registers() {
+    let mut buf = vec![0u8; WrappedHllUnion::required_bytes(12)];
+    let mut union = WrappedHllUnion::initialize(&mut buf, 12).unwrap();
+    let small = sketch(10, HllType::Hll8, 0..10_000);
+    let err = union.update(&small).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    assert!(union.is_empty());
+}
+
+#[test]
+fn test_wrap_rejects_other_images() {
+    let mut sparse = sketch(10, HllType::Hll8, 0..3).serialize();
+    assert!(WrappedHllUnion::wrap(&mut sparse).is_err());
+    let mut hll4 = sketch(10, HllType::Hll4, 0..10_000).serialize();
+    let err = WrappedHllUnion::wrap(&mut hll4).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadPreamble);
+
+    let mut image = sketch(10, HllType::Hll8, 0..10_000).serialize();
+    assert!(WrappedHllUnion::wrap(&mut image).is_ok());
+    let len = image.len();
+    assert!(WrappedHllUnion::wrap(&mut image[..len - 1]).is_err());
+    assert!(WrappedHllUnion::wrap(&mut image[..10]).is_err());
+
+    let mut wrong_version = image.clone();
+    wrong_version[1] = 9;
+    let err = WrappedHllUnion::wrap(&mut wrong_version).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnsupportedVersion);
+
+    let mut short = vec![0u8; 100];
+    assert!(WrappedHllUnion::initialize(&mut short, 10).is_err());
+    assert!(WrappedHllUnion::initialize(&mut short, 3).is_err());
+}
+
+#[test]
+fn test_wrap_checks_registers() {
+    let mut buf = vec![0u8; WrappedHllUnion::required_bytes(10)];
+    let mut union = WrappedHllUnion::initialize(&mut buf, 10).unwrap();
+    union.update_value(1);
+
+    // a stored zero count that disagrees with the registers
+    let mut image = buf.clone();
+    image[32..36].copy_from_slice(&0u32.to_le_bytes());
+    let err = WrappedHllUnion::wrap(&mut image).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(HllSketch::deserialize(&image).is_err());
+
+    // a register above 63, with the zero count left in step
+    let mut image = buf.clone();
+    let register = image[40..].iter().position(|&value| value != 0).unwrap();
+    image[40 + register] = 255;
+    let err = WrappedHllUnion::wrap(&mut image).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(HllSketch::deserialize(&image).is_err());
+
+    let mut union = WrappedHllUnion::wrap(&mut buf).unwrap();
+    union.update_value(2);
+    assert!((union.estimate() - 2.0).abs() < 0.01);
+}
@@ -0,0 +1,196 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::error::ErrorKind;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::inspect;
+use datasketches::kll::KllSketch;
+use datasketches::kll::MultiKll;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::ThetaSketch;
+
+#[test]
+fn test_theta() {
+    for n in [0, 1, 100, 10_000] {
+        let mut sketch = ThetaSketch::builder().lg_k(10).build();
+        for i in 0..n {
+            sketch.update(i);
+        }
+        let info = inspect(&sketch.serialize()).unwrap();
+        assert_eq!(info.family(), "QUICKSELECT");
+        assert_eq!(info.serial_version(), 3);
+        assert_eq!(info.lg_k(), Some(10));
+        assert_eq!(info.is_empty(), sketch.is_empty());
+        assert_eq!(info.is_estimation_mode(), Some(sketch.is_estimation_mode()));
+        assert_eq!(info.num_retained(), Some(sketch.num_retained() as u64));
+
+        let compact = sketch.compact(true);
+        for bytes in [compact.serialize(), compact.serialize_compressed()] {
+            let info = inspect(&bytes).unwrap();
+            assert_eq!(info.family(), "THETA");
+            assert_eq!(info.serial_version(), bytes[1]);
+            assert_eq!(info.lg_k(), None);
+            assert_eq!(info.is_empty(), compact.is_empty());
+            assert_eq!(
+                info.is_estimation_mode(),
+                Some(compact.is_estimation_mode())
+            );
+            assert_eq!(info.num_retained(), Some(compact.num_retained() as u64));
+        }
+    }
+}
+
+#[test]
+fn test_hll() {
+    for hll_type in [HllType::Hll4, HllType::Hll6, HllType::Hll8] {
+        let mut sketch = HllSketch::new(10, hll_type);
+        let info = inspect(&sketch.serialize()).unwrap();
+        assert_eq!(info.family(), "HLL");
+        assert_eq!(info.lg_k(), Some(10));
+        assert!(info.is_empty());
+        assert_eq!(info.num_retained(), Some(0));
+
+        // list mode, set mode, then HLL mode
+        for (n, num_retained) in [(5, Some(5)), (50, Some(50)), (10_000, None)] {
+            sketch = HllSketch::new(10, hll_type);
+            for i in 0..n {
+                sketch.update(i);
+            }
+            let info = inspect(&sketch.serialize()).unwrap();
+            assert!(!info.is_empty());
+            assert_eq!(info.is_estimation_mode(), Some(num_retained.is_none()));
+            assert_eq!(info.num_retained(), num_retained);
+        }
+    }
+}
+
+#[test]
+fn test_kll() {
+    for n in [0, 1, 100, 10_000] {
+        let mut sketch = KllSketch::<f64>::new(100);
+        for i in 0..n {
+            sketch.update(i as f64);
+        }
+        let info = inspect(&sketch.serialize()).unwrap();
+        assert_eq!(info.family(), "KLL");
+        assert_eq!(info.serial_version(), if n == 1 { 2 } else { 1 });
+        assert_eq!(info.k(), Some(100));
+        assert_eq!(info.is_empty(), n == 0);
+        assert_eq!(info.is_estimation_mode(), Some(sketch.is_estimation_mode()));
+        assert_eq!(info.num_retained(), Some(sketch.num_retained() as u64));
+    }
+}
+
+#[test]
+fn test_frequencies() {
+    let mut sketch = FrequentItemsSketch::<i64>::new(16);
+    let info = inspect(&sketch.serialize()).unwrap();
+    assert_eq!(info.family(), "FREQUENCY");
+    assert_eq!(info.lg_k(), Some(4));
+    assert!(info.is_empty());
+    assert_eq!(info.num_retained(), Some(0));
+
+    for i in 0..100 {
+        // keep a heavy hitter so that purges never empty the map
+        sketch.update_with_count(0, 10);
+        sketch.update(i);
+        let info = inspect(&sketch.serialize()).unwrap();
+        assert!(!info.is_empty());
+        assert_eq!(info.is_estimation_mode(), Some(sketch.maximum_error() > 0));
+        assert_eq!(info.num_retained(), Some(sketch.num_active_items() as u64));
+    }
+    let info = inspect(&sketch.serialize()).unwrap();
+    assert_eq!(info.is_estimation_mode(), Some(true));
+}
+
+#[test]
+fn test_cpc() {
+    for n in [0, 1, 100, 10_000] {
+        let mut sketch = CpcSketch::new(10);
+        for i in 0..n {
+            sketch.update(i);
+        }
+        let info = inspect(&sketch.serialize()).unwrap();
+        assert_eq!(info.family(), "CPC");
+        assert_eq!(info.lg_k(), Some(10));
+        assert_eq!(info.is_empty(), n == 0);
+        assert_eq!(info.num_retained(), Some(sketch.num_coupons() as u64));
+    }
+}
+
+#[test]
+fn test_tdigest() {
+    for n in [0, 1, 10_000] {
+        let mut sketch = TDigestMut::new(100);
+        for i in 0..n {
+            sketch.update(i as f64);
+        }
+        let info = inspect(&sketch.serialize()).unwrap();
+        assert_eq!(info.family(), "TDIGEST");
+        assert_eq!(info.k(), Some(100));
+        assert_eq!(info.is_empty(), n == 0);
+        let num_retained = info.num_retained().unwrap();
+        assert!(num_retained <= n, "{num_retained}");
+        assert_eq!(num_retained == 0, n == 0);
+    }
+}
+
+#[test]
+fn test_countmin_and_bloom() {
+    let mut countmin = CountMinSketch::<u64>::new(3, 64);
+    assert!(inspect(&countmin.serialize()).unwrap().is_empty());
+    countmin.update("apple");
+    let info = inspect(&countmin.serialize()).unwrap();
+    assert_eq!(info.family(), "COUNTMIN");
+    assert!(!info.is_empty());
+    assert_eq!(info.num_retained(), None);
+
+    let mut bloom = BloomFilterBuilder::with_accuracy(1000, 0.01).build();
+    assert!(inspect(&bloom.serialize()).unwrap().is_empty());
+    bloom.insert("apple");
+    let info = inspect(&bloom.serialize()).unwrap();
+    assert_eq!(info.family(), "BLOOMFILTER");
+    assert!(!info.is_empty());
+    assert_eq!(info.is_estimation_mode(), None);
+}
+
+#[test]
+fn test_errors() {
+    let mut sketch = HllSketch::new(10, HllType::Hll4);
+    sketch.update(1);
+    let bytes = sketch.serialize();
+    for len in 0..8 {
+        let err = inspect(&bytes[..len]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Truncated, "{len}: {err}");
+    }
+
+    let mut bytes = sketch.serialize();
+    bytes[1] = 9;
+    let err = inspect(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnsupportedVersion);
+    bytes[2] = 99;
+    let err = inspect(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadPreamble);
+
+    let multi = MultiKll::<2>::new(200);
+    assert!(inspect(&multi.serialize()).is_err());
+}
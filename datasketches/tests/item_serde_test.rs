@@ -0,0 +1,114 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use std::fmt::Debug;
+
+use datasketches::codec::ItemSerde;
+use datasketches::codec::SketchBytes;
+use datasketches::codec::SketchSlice;
+use datasketches::error::ErrorKind;
+use datasketches::frequencies::ErrorType;
+use datasketches::frequencies::FrequentItemsSketch;
+
+fn round_trip<T: ItemSerde + PartialEq + Debug>(items: &[T]) {
+    let mut bytes = SketchBytes::with_capacity(0);
+    for item in items {
+        item.serialize_value(&mut bytes);
+    }
+    let bytes = bytes.into_bytes();
+    assert_eq!(bytes.len(), items.iter().map(T::serialize_size).sum());
+
+    let mut cursor = SketchSlice::new(&bytes);
+    for item in items {
+        assert_eq!(&T::deserialize_value(&mut cursor).unwrap(), item);
+    }
+    assert_eq!(cursor.remaining(), 0);
+    let err = T::deserialize_value(&mut cursor).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Truncated);
+
+    let mut reader = bytes.as_slice();
+    for item in items {
+        assert_eq!(&T::read_value(&mut reader).unwrap(), item);
+    }
+    assert!(reader.is_empty());
+    let err = T::read_value(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Truncated);
+}
+
+#[test]
+fn test_primitive_round_trip() {
+    round_trip(&[i8::MIN, 0, i8::MAX]);
+    round_trip(&[0u8, 1, u8::MAX]);
+    round_trip(&[i16::MIN, -1, i16::MAX]);
+    round_trip(&[0u16, u16::MAX]);
+    round_trip(&[i32::MIN, 42, i32::MAX]);
+    round_trip(&[0u32, u32::MAX]);
+    round_trip(&[i64::MIN, -42, i64::MAX]);
+    round_trip(&[0u64, u64::MAX]);
+    round_trip(&[f32::MIN, -0.0, 1.5, f32::INFINITY]);
+    round_trip(&[f64::MIN_POSITIVE, 0.1, f64::NEG_INFINITY]);
+}
+
+#[test]
+fn test_length_framed_round_trip() {
+    round_trip(&[String::new(), "apple".to_string(), "日本".to_string()]);
+    round_trip(&[vec![], vec![0u8, 1, 2], vec![0xff; 300]]);
+}
+
+#[test]
+fn test_length_framing_layout() {
+    let mut bytes = SketchBytes::with_capacity(0);
+    vec![7u8, 8].serialize_value(&mut bytes);
+    "ab".to_string().serialize_value(&mut bytes);
+    assert_eq!(
+        bytes.into_bytes(),
+        [2, 0, 0, 0, 7, 8, 2, 0, 0, 0, b'a', b'b']
+    );
+}
+
+#[test]
+fn test_truncated_payload() {
+    let bytes = [5, 0, 0, 0, 1, 2];
+    let err = Vec::<u8>::deserialize_value(&mut SketchSlice::new(&bytes)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Truncated);
+    let err = Vec::<u8>::read_value(&mut bytes.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Truncated);
+
+    let bytes = [2, 0, 0, 0, 0xc3, 0x28];
+    let err = String::deserialize_value(&mut SketchSlice::new(&bytes)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_frequent_byte_items() {
+    let mut sketch = FrequentItemsSketch::<Vec<u8>>::new(8);
+    sketch.update_with_count(b"apple".to_vec(), 5);
+    sketch.update_with_count(b"banana".to_vec(), 3);
+    let decoded = FrequentItemsSketch::<Vec<u8>>::deserialize(&sketch.serialize()).unwrap();
+    assert_eq!(decoded.estimate(&b"apple".to_vec()), 5);
+    assert_eq!(decoded.frequent_items(ErrorType::NoFalsePositives).len(), 2);
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn test_uuid_round_trip() {
+    let id = uuid::Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+    round_trip(&[uuid::Uuid::nil(), id, uuid::Uuid::max()]);
+
+    let mut bytes = SketchBytes::with_capacity(0);
+    id.serialize_value(&mut bytes);
+    assert_eq!(bytes.into_bytes(), id.as_bytes());
+}
@@ -0,0 +1,126 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::error::ErrorKind;
+use datasketches::kll::KllSketch;
+use datasketches::kll::MultiKll;
+
+fn latency_rows(n: u64) -> MultiKll<3> {
+    let mut sketch = MultiKll::<3>::new(200);
+    for i in 0..n {
+        let i = i as f64;
+        sketch.update([i, 2.0 * i, -i]);
+    }
+    sketch
+}
+
+#[test]
+fn test_empty() {
+    let sketch = MultiKll::<2>::default();
+    assert!(sketch.is_empty());
+    assert_eq!(sketch.k(), 200);
+    assert_eq!(sketch.quantiles(0.5), [None, None]);
+    assert_eq!(sketch.ranks([0.0, 0.0]), [None, None]);
+}
+
+#[test]
+#[should_panic(expected = "number of dimensions must be positive")]
+fn test_zero_dimensions() {
+    MultiKll::<0>::new(200);
+}
+
+#[test]
+fn test_dimensions_match_independent_sketches() {
+    let sketch = latency_rows(100);
+    let mut first = KllSketch::<f64>::new(200);
+    for i in 0..100 {
+        first.update(i as f64);
+    }
+
+    assert!(!sketch.is_empty());
+    assert_eq!(sketch.dimension(0).n(), 100);
+    assert_eq!(sketch.dimensions().len(), 3);
+    assert_eq!(sketch.quantiles(0.5)[0], first.quantile(0.5));
+    assert_eq!(sketch.quantiles(0.5), [Some(49.0), Some(98.0), Some(-50.0)]);
+    assert_eq!(sketch.quantiles(1.0), [Some(99.0), Some(198.0), Some(0.0)]);
+    assert_eq!(
+        sketch.ranks([49.0, 98.0, -50.0]),
+        [Some(0.5), Some(0.5), Some(0.5)]
+    );
+}
+
+#[test]
+fn test_nan_per_dimension() {
+    let mut sketch = MultiKll::<2>::new(200);
+    sketch.update([1.0, f64::NAN]);
+    sketch.update([2.0, f64::NAN]);
+    assert_eq!(sketch.dimension(0).n(), 2);
+    assert!(sketch.dimension(1).is_empty());
+    assert_eq!(sketch.quantiles(1.0), [Some(2.0), None]);
+}
+
+#[test]
+fn test_merge_and_reset() {
+    let mut sketch = latency_rows(10_000);
+    let other = latency_rows(10_000);
+    sketch.merge(&other);
+    for dimension in sketch.dimensions() {
+        assert_eq!(dimension.n(), 20_000);
+    }
+    let medians = sketch.quantiles(0.5);
+    assert!((4500.0..=5500.0).contains(&medians[0].unwrap()));
+
+    sketch.reset();
+    assert!(sketch.is_empty());
+}
+
+#[test]
+fn test_serialize_round_trip() {
+    for n in [0, 1, 1000, 50_000] {
+        let sketch = latency_rows(n);
+        let bytes = sketch.serialize();
+        let decoded = MultiKll::<3>::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.k(), 200);
+        for rank in [0.0, 0.1, 0.5, 0.99, 1.0] {
+            assert_eq!(decoded.quantiles(rank), sketch.quantiles(rank));
+        }
+        for (decoded, original) in decoded.dimensions().iter().zip(sketch.dimensions()) {
+            assert_eq!(decoded.serialize(), original.serialize());
+        }
+    }
+}
+
+#[test]
+fn test_deserialize_invalid() {
+    let bytes = latency_rows(1000).serialize();
+
+    let err = MultiKll::<2>::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadPreamble);
+
+    for len in 0..bytes.len() {
+        assert!(MultiKll::<3>::deserialize(&bytes[..len]).is_err());
+    }
+
+    // dimensions built with a different k each
+    let mut mixed = vec![1, 15, 0, 0, 2, 0, 0, 0];
+    for k in [200, 100] {
+        let dimension = KllSketch::<f64>::new(k).serialize();
+        mixed.extend_from_slice(&(dimension.len() as u32).to_le_bytes());
+        mixed.extend_from_slice(&dimension);
+    }
+    assert!(MultiKll::<2>::deserialize(&mixed).is_err());
+}
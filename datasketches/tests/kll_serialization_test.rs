@@ -0,0 +1,84 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use common::CORPUS_NS;
+use common::Language;
+use common::corpus_files;
+use datasketches::kll::KllItem;
+use datasketches::kll::KllSketch;
+
+// the corpus sketches are built with k=200 from the values 1..=n
+fn test_sketch_file<T: KllItem + std::fmt::Debug>(
+    path: PathBuf,
+    n: usize,
+    to_item: fn(usize) -> T,
+) {
+    let bytes = fs::read(&path).unwrap();
+    let sketch = KllSketch::<T>::deserialize(&bytes).unwrap();
+    let name = path.display();
+
+    assert_eq!(sketch.k(), 200, "{name}");
+    assert_eq!(sketch.n(), n as u64, "{name}");
+    assert_eq!(sketch.is_empty(), n == 0, "{name}");
+    assert_eq!(sketch.is_estimation_mode(), n > 200, "{name}");
+    if n > 0 {
+        assert_eq!(sketch.min_item(), Some(to_item(1)), "{name}");
+        assert_eq!(sketch.max_item(), Some(to_item(n)), "{name}");
+    } else {
+        assert_eq!(sketch.min_item(), None, "{name}");
+    }
+
+    let decoded = KllSketch::<T>::deserialize(&sketch.serialize()).unwrap();
+    assert_eq!(decoded.n(), sketch.n(), "{name}");
+    assert_eq!(decoded.num_retained(), sketch.num_retained(), "{name}");
+    for rank in [0.0, 0.01, 0.5, 0.99, 1.0] {
+        assert_eq!(decoded.quantile(rank), sketch.quantile(rank), "{name}");
+    }
+}
+
+#[test]
+fn test_java_kll_float_compatibility() {
+    for (n, path) in corpus_files(Language::Java, "kll_float", &CORPUS_NS) {
+        test_sketch_file(path, n, |i| i as f32);
+    }
+}
+
+#[test]
+fn test_java_kll_double_compatibility() {
+    for (n, path) in corpus_files(Language::Java, "kll_double", &CORPUS_NS) {
+        test_sketch_file(path, n, |i| i as f64);
+    }
+}
+
+#[test]
+fn test_cpp_kll_float_compatibility() {
+    for (n, path) in corpus_files(Language::Cpp, "kll_float", &CORPUS_NS) {
+        test_sketch_file(path, n, |i| i as f32);
+    }
+}
+
+#[test]
+fn test_cpp_kll_double_compatibility() {
+    for (n, path) in corpus_files(Language::Cpp, "kll_double", &CORPUS_NS) {
+        test_sketch_file(path, n, |i| i as f64);
+    }
+}
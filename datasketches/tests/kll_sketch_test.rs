@@ -0,0 +1,664 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::time::Duration;
+
+use datasketches::error::ErrorKind;
+use datasketches::kll::KllSketch;
+use datasketches::kll::PercentileTracker;
+use datasketches::kll::k_for_normalized_rank_error;
+use datasketches::kll::normalized_rank_error;
+
+// normalized rank error of k=200 with 99% confidence, with some slack
+const RANK_EPS_FOR_K_200: f64 = 0.0175;
+
+fn sketch_with_range(k: u16, start: u64, count: u64) -> KllSketch<f64> {
+    let mut sketch = KllSketch::new(k);
+    for i in start..start + count {
+        sketch.update(i as f64);
+    }
+    sketch
+}
+
+#[test]
+fn test_empty() {
+    let sketch = KllSketch::<f64>::default();
+    assert_eq!(sketch.k(), 200);
+    assert!(sketch.is_empty());
+    assert!(!sketch.is_estimation_mode());
+    assert_eq!(sketch.n(), 0);
+    assert_eq!(sketch.num_retained(), 0);
+    assert_eq!(sketch.min_item(), None);
+    assert_eq!(sketch.max_item(), None);
+    assert_eq!(sketch.rank(0.0), None);
+    assert_eq!(sketch.quantile(0.5), None);
+}
+
+#[test]
+#[should_panic(expected = "k must be at least 8")]
+fn test_k_too_small() {
+    KllSketch::<f64>::new(7);
+}
+
+#[test]
+fn test_one_item() {
+    let mut sketch = KllSketch::<f64>::new(200);
+    sketch.update(1.0);
+    assert!(!sketch.is_empty());
+    assert_eq!(sketch.n(), 1);
+    assert_eq!(sketch.num_retained(), 1);
+    assert_eq!(sketch.rank(0.5), Some(0.0));
+    assert_eq!(sketch.rank(1.0), Some(1.0));
+    assert_eq!(sketch.rank(2.0), Some(1.0));
+    assert_eq!(sketch.quantile(0.0), Some(1.0));
+    assert_eq!(sketch.quantile(0.5), Some(1.0));
+    assert_eq!(sketch.quantile(1.0), Some(1.0));
+}
+
+#[test]
+fn test_nan_ignored() {
+    let mut sketch = KllSketch::<f64>::new(200);
+    sketch.update(f64::NAN);
+    assert!(sketch.is_empty());
+    sketch.update(0.0);
+    sketch.update(f64::NAN);
+    assert_eq!(sketch.n(), 1);
+}
+
+#[test]
+fn test_exact_mode() {
+    let n = 200;
+    let sketch = sketch_with_range(200, 0, n);
+    assert!(!sketch.is_estimation_mode());
+    assert_eq!(sketch.num_retained(), n as usize);
+    assert_eq!(sketch.min_item(), Some(0.0));
+    assert_eq!(sketch.max_item(), Some((n - 1) as f64));
+    for i in 0..n {
+        let rank = (i + 1) as f64 / n as f64;
+        assert_eq!(sketch.rank(i as f64), Some(rank));
+        assert_eq!(sketch.quantile(rank), Some(i as f64));
+    }
+}
+
+#[test]
+fn test_estimation_mode() {
+    let n = 100_000;
+    let sketch = sketch_with_range(200, 0, n);
+    assert!(sketch.is_estimation_mode());
+    assert_eq!(sketch.n(), n);
+    assert!(sketch.num_retained() < 1000, "{}", sketch.num_retained());
+    assert_eq!(sketch.min_item(), Some(0.0));
+    assert_eq!(sketch.max_item(), Some((n - 1) as f64));
+    assert_eq!(sketch.quantile(0.0), Some(0.0));
+    assert_eq!(sketch.quantile(1.0), Some((n - 1) as f64));
+
+    for i in (0..n).step_by(1000) {
+        let true_rank = (i + 1) as f64 / n as f64;
+        let rank = sketch.rank(i as f64).unwrap();
+        assert!(
+            (rank - true_rank).abs() < RANK_EPS_FOR_K_200,
+            "rank of {i}: {rank} vs {true_rank}"
+        );
+    }
+
+    let mut previous = f64::NEG_INFINITY;
+    for i in 0..=100 {
+        let rank = i as f64 / 100.0;
+        let quantile = sketch.quantile(rank).unwrap();
+        assert!(quantile >= previous, "quantiles must be monotonic");
+        previous = quantile;
+        let true_rank = quantile / n as f64;
+        assert!((rank - true_rank).abs() < RANK_EPS_FOR_K_200);
+    }
+}
+
+#[test]
+fn test_small_k() {
+    let n = 10_000;
+    let sketch = sketch_with_range(8, 0, n);
+    assert_eq!(sketch.n(), n);
+    assert_eq!(sketch.min_item(), Some(0.0));
+    assert_eq!(sketch.max_item(), Some((n - 1) as f64));
+    let median = sketch.quantile(0.5).unwrap();
+    assert!((0.0..n as f64).contains(&median));
+}
+
+#[test]
+fn test_f32_items() {
+    let mut sketch = KllSketch::<f32>::new(200);
+    for i in 0..1000 {
+        sketch.update(i as f32);
+    }
+    let median = sketch.quantile(0.5).unwrap();
+    assert!((450.0..=550.0).contains(&median));
+}
+
+#[test]
+fn test_merge() {
+    let n = 10_000;
+    let mut left = sketch_with_range(200, 0, n);
+    let right = sketch_with_range(200, n, n);
+    left.merge(&right);
+
+    assert_eq!(left.n(), 2 * n);
+    assert_eq!(left.min_item(), Some(0.0));
+    assert_eq!(left.max_item(), Some((2 * n - 1) as f64));
+    let median = left.quantile(0.5).unwrap();
+    assert!((median / (2 * n) as f64 - 0.5).abs() < RANK_EPS_FOR_K_200);
+}
+
+#[test]
+fn test_merge_into_empty_and_with_empty() {
+    let sketch = sketch_with_range(200, 0, 1000);
+
+    let mut empty = KllSketch::<f64>::new(200);
+    empty.merge(&sketch);
+    assert_eq!(empty.n(), 1000);
+    assert_eq!(empty.min_item(), Some(0.0));
+    assert_eq!(empty.max_item(), Some(999.0));
+
+    let mut copy = sketch.clone();
+    copy.merge(&KllSketch::new(200));
+    assert_eq!(copy.n(), 1000);
+    assert_eq!(copy.num_retained(), sketch.num_retained());
+}
+
+#[test]
+fn test_merge_different_k() {
+    let n = 10_000;
+    let mut small = sketch_with_range(100, 0, n);
+    let large = sketch_with_range(400, n, n);
+    small.merge(&large);
+    assert_eq!(small.n(), 2 * n);
+    assert_eq!(small.max_item(), Some((2 * n - 1) as f64));
+
+    let mut large = sketch_with_range(400, 0, n);
+    large.merge(&sketch_with_range(100, n, n));
+    assert_eq!(large.n(), 2 * n);
+    assert_eq!(large.min_item(), Some(0.0));
+    assert_eq!((large.k(), large.min_k()), (400, 100));
+    let epsilon = large.normalized_rank_error(false);
+    assert_eq!(epsilon, normalized_rank_error(100, false));
+    for i in (0..2 * n).step_by(1_000) {
+        let rank = large.rank(i as f64).unwrap();
+        let true_rank = (i + 1) as f64 / (2 * n) as f64;
+        assert!((rank - true_rank).abs() <= epsilon, "{i}: {rank}");
+    }
+}
+
+#[test]
+fn test_merge_exact_mode_keeps_min_k() {
+    let mut sketch = sketch_with_range(400, 0, 10_000);
+    let exact = sketch_with_range(100, 0, 50);
+    assert!(!exact.is_estimation_mode());
+    sketch.merge(&exact);
+    assert_eq!(sketch.min_k(), 400);
+
+    let mut empty = KllSketch::<f64>::new(400);
+    empty.merge(&sketch_with_range(100, 0, 10_000));
+    assert_eq!(empty.min_k(), 100);
+    let decoded = KllSketch::<f64>::deserialize(&empty.serialize()).unwrap();
+    assert_eq!(decoded.min_k(), 100);
+}
+
+#[test]
+fn test_merge_many_stays_compact() {
+    let mut union = KllSketch::<f64>::new(200);
+    for i in 0..100 {
+        union.merge(&sketch_with_range(200, i * 1000, 1000));
+    }
+    assert_eq!(union.n(), 100_000);
+    assert!(union.num_retained() < 1000, "{}", union.num_retained());
+    let median = union.quantile(0.5).unwrap();
+    assert!((median / 100_000.0 - 0.5).abs() < RANK_EPS_FOR_K_200);
+}
+
+#[test]
+fn test_reset() {
+    let mut sketch = sketch_with_range(200, 0, 1000);
+    sketch.reset();
+    assert!(sketch.is_empty());
+    assert_eq!(sketch.k(), 200);
+    assert_eq!(sketch.num_retained(), 0);
+    sketch.update(1.0);
+    assert_eq!(sketch.quantile(0.5), Some(1.0));
+}
+
+#[test]
+fn test_serialize_layout() {
+    let sketch = KllSketch::<f64>::new(200);
+    assert_eq!(sketch.serialize(), [2, 1, 15, 1, 200, 0, 8, 0]);
+
+    let mut sketch = KllSketch::<f64>::new(200);
+    sketch.update(1.0);
+    let bytes = sketch.serialize();
+    assert_eq!(&bytes[..8], &[2, 2, 15, 4, 200, 0, 8, 0]);
+    assert_eq!(&bytes[8..], &1.0f64.to_le_bytes());
+
+    let sketch = sketch_with_range(200, 0, 10);
+    let bytes = sketch.serialize();
+    assert_eq!(&bytes[..4], &[5, 1, 15, 0]);
+    // preamble + one level offset + min + max + items
+    assert_eq!(bytes.len(), 20 + 4 + 8 * 2 + 8 * 10);
+}
+
+#[test]
+fn test_serialize_round_trip() {
+    for n in [0, 1, 10, 200, 201, 100_000] {
+        let sketch = sketch_with_range(200, 0, n);
+        let bytes = sketch.serialize();
+        let decoded = KllSketch::<f64>::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.k(), sketch.k());
+        assert_eq!(decoded.n(), sketch.n());
+        assert_eq!(decoded.num_retained(), sketch.num_retained());
+        assert_eq!(decoded.min_item(), sketch.min_item());
+        assert_eq!(decoded.max_item(), sketch.max_item());
+        for i in 0..=10 {
+            let rank = i as f64 / 10.0;
+            assert_eq!(decoded.quantile(rank), sketch.quantile(rank));
+        }
+        assert_eq!(decoded.serialize(), bytes);
+    }
+}
+
+#[test]
+fn test_deserialize_then_update_and_merge() {
+    let sketch = sketch_with_range(200, 0, 5000);
+    let mut decoded = KllSketch::<f64>::deserialize(&sketch.serialize()).unwrap();
+    for i in 5000..10_000 {
+        decoded.update(i as f64);
+    }
+    decoded.merge(&sketch);
+    assert_eq!(decoded.n(), 15_000);
+    assert_eq!(decoded.max_item(), Some(9999.0));
+}
+
+#[test]
+fn test_f32_serialize_round_trip() {
+    let mut sketch = KllSketch::<f32>::new(200);
+    for i in 0..1000 {
+        sketch.update(i as f32);
+    }
+    let bytes = sketch.serialize();
+    let decoded = KllSketch::<f32>::deserialize(&bytes).unwrap();
+    assert_eq!(decoded.n(), 1000);
+    assert_eq!(decoded.quantile(0.5), sketch.quantile(0.5));
+}
+
+#[test]
+fn test_deserialize_invalid() {
+    let bytes = sketch_with_range(200, 0, 1000).serialize();
+
+    for len in 0..bytes.len() {
+        assert!(KllSketch::<f64>::deserialize(&bytes[..len]).is_err());
+    }
+
+    let mut wrong_family = bytes.clone();
+    wrong_family[2] = 3;
+    let err = KllSketch::<f64>::deserialize(&wrong_family).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadPreamble);
+
+    let mut wrong_m = bytes.clone();
+    wrong_m[6] = 4;
+    assert!(KllSketch::<f64>::deserialize(&wrong_m).is_err());
+
+    let mut wrong_n = bytes.clone();
+    wrong_n[8] ^= 1;
+    assert!(KllSketch::<f64>::deserialize(&wrong_n).is_err());
+
+    let mut nan_item = bytes;
+    let len = nan_item.len();
+    nan_item[len - 8..].copy_from_slice(&f64::NAN.to_le_bytes());
+    assert!(KllSketch::<f64>::deserialize(&nan_item).is_err());
+}
+
+#[test]
+fn test_deserialize_total_weight_overflow() {
+    // k = 200 with 61 levels has a capacity of 1001 items; one item at level 59 and 16 at
+    // level 60 weigh 2^59 + 2^64, which wraps around to n = 2^59 in a u64
+    let mut bytes = vec![5, 1, 15, 0];
+    bytes.extend_from_slice(&200u16.to_le_bytes());
+    bytes.extend_from_slice(&[8, 0]);
+    bytes.extend_from_slice(&(1u64 << 59).to_le_bytes());
+    bytes.extend_from_slice(&200u16.to_le_bytes());
+    bytes.extend_from_slice(&[61, 0]);
+    for level in 0..61 {
+        let offset: u32 = if level < 60 { 984 } else { 985 };
+        bytes.extend_from_slice(&offset.to_le_bytes());
+    }
+    for _ in 0..19 {
+        bytes.extend_from_slice(&1.0f64.to_le_bytes());
+    }
+
+    let err = KllSketch::<f64>::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_to_cdf_points() {
+    assert!(KllSketch::<f64>::new(200).to_cdf_points(10).is_none());
+
+    let sketch = sketch_with_range(200, 0, 100_000);
+    let points = sketch.to_cdf_points(11).unwrap();
+    assert_eq!(points.len(), 11);
+    assert_eq!(points[0].value, 0.0);
+    assert_eq!(points[10].value, 99_999.0);
+    assert_eq!(points[10].rank, 1.0);
+    for (i, point) in points.iter().enumerate() {
+        assert_eq!(sketch.rank(point.value), Some(point.rank));
+        let expected = i as f64 / 10.0;
+        assert!(
+            (point.rank - expected).abs() <= RANK_EPS_FOR_K_200,
+            "{point:?}"
+        );
+    }
+    assert!(points.windows(2).all(|w| w[0].value <= w[1].value));
+    assert!(points.windows(2).all(|w| w[0].rank <= w[1].rank));
+
+    // fewer distinct items than points
+    let mut sketch = KllSketch::<f32>::new(200);
+    sketch.update(7.0);
+    let points = sketch.to_cdf_points(4).unwrap();
+    assert!(points.iter().all(|p| p.value == 7.0 && p.rank == 1.0));
+}
+
+#[test]
+#[should_panic(expected = "n must be at least 2")]
+fn test_to_cdf_points_too_few() {
+    sketch_with_range(200, 0, 10).to_cdf_points(1);
+}
+
+#[test]
+fn test_normalized_rank_error() {
+    assert!((normalized_rank_error(200, false) - 0.0133).abs() < 1e-4);
+    assert!((normalized_rank_error(200, true) - 0.0165).abs() < 1e-4);
+    assert!(normalized_rank_error(100, false) > normalized_rank_error(200, false));
+
+    for pmf in [false, true] {
+        for epsilon in [0.1, 0.05, 0.0133, 0.01, 0.001] {
+            let k = k_for_normalized_rank_error(epsilon, pmf);
+            assert!(normalized_rank_error(k, pmf) <= epsilon);
+            assert!(k == 8 || normalized_rank_error(k - 1, pmf) > epsilon);
+        }
+    }
+    assert_eq!(k_for_normalized_rank_error(1.0, false), 8);
+    assert_eq!(k_for_normalized_rank_error(1e-9, false), u16::MAX);
+
+    let mut sketch = KllSketch::<f64>::new(400);
+    let mut other = KllSketch::<f64>::new(200);
+    for i in 0..10_000 {
+        other.update(i as f64);
+    }
+    assert_eq!(
+        sketch.normalized_rank_error(true),
+        normalized_rank_error(400, true)
+    );
+    sketch.merge(&other);
+    assert_eq!(
+        sketch.normalized_rank_error(true),
+        normalized_rank_error(200, true)
+    );
+}
+
+#[test]
+#[should_panic(expected = "epsilon must be in (0.0, 1.0]")]
+fn test_k_for_normalized_rank_error_rejects_zero() {
+    k_for_normalized_rank_error(0.0, false);
+}
+
+#[test]
+fn test_cdf_and_pmf() {
+    let empty = KllSketch::<f64>::new(200);
+    assert!(empty.cdf(&[1.0]).is_none());
+    assert!(empty.pmf(&[1.0]).is_none());
+
+    let sketch = sketch_with_range(200, 0, 100_000);
+    assert_eq!(sketch.cdf(&[]), Some(vec![1.0]));
+    assert_eq!(sketch.pmf(&[]), Some(vec![1.0]));
+    assert_eq!(sketch.cdf(&[-1.0, 1e6]), Some(vec![0.0, 1.0, 1.0]));
+
+    let split_points: Vec<f64> = (1..10).map(|i| i as f64 * 10_000.0).collect();
+    let cdf = sketch.cdf(&split_points).unwrap();
+    let pmf = sketch.pmf(&split_points).unwrap();
+    assert_eq!(cdf.len(), 10);
+    assert_eq!(pmf.len(), 10);
+    assert_eq!(cdf[9], 1.0);
+    for (i, &point) in split_points.iter().enumerate() {
+        assert_eq!(Some(cdf[i]), sketch.rank(point));
+        let expected = (i + 1) as f64 / 10.0;
+        assert!((cdf[i] - expected).abs() <= RANK_EPS_FOR_K_200, "{cdf:?}");
+    }
+    for mass in &pmf {
+        assert!((mass - 0.1).abs() <= 2.0 * RANK_EPS_FOR_K_200, "{pmf:?}");
+    }
+    assert!((pmf.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+}
+
+#[test]
+#[should_panic(expected = "split_points must be unique and monotonically increasing")]
+fn test_cdf_unsorted_split_points() {
+    sketch_with_range(200, 0, 10).cdf(&[2.0, 1.0]);
+}
+
+#[test]
+#[should_panic(expected = "split_points must be unique and monotonically increasing")]
+fn test_pmf_duplicate_split_points() {
+    sketch_with_range(200, 0, 10).pmf(&[1.0, 1.0]);
+}
+
+#[test]
+#[should_panic(expected = "split_points must not contain unordered items")]
+fn test_pmf_nan_split_point() {
+    // rejected even when the sketch is empty
+    KllSketch::<f64>::new(200).pmf(&[f64::NAN]);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_cdf_points_serde() {
+    use datasketches::common::CdfPoint;
+
+    let mut sketch = KllSketch::<f64>::new(200);
+    for i in 1..=4 {
+        sketch.update(i as f64);
+    }
+    let points = sketch.to_cdf_points(2).unwrap();
+    let json = serde_json::to_string(&points).unwrap();
+    assert_eq!(
+        json,
+        r#"[{"value":1.0,"rank":0.25},{"value":4.0,"rank":1.0}]"#
+    );
+    let decoded: Vec<CdfPoint<f64>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, points);
+}
+
+#[test]
+fn test_durations() {
+    let mut sketch = KllSketch::<Duration>::new(200);
+    let mut other = KllSketch::<Duration>::new(200);
+    for us in 0..10_000 {
+        sketch.update(Duration::from_micros(us));
+        other.update(Duration::new(us, 999_999_999));
+    }
+    sketch.merge(&other);
+    assert_eq!(sketch.n(), 20_000);
+    assert_eq!(sketch.min_item(), Some(Duration::ZERO));
+    assert_eq!(sketch.max_item(), Some(Duration::new(9_999, 999_999_999)));
+    let p25 = sketch.quantile(0.25).unwrap();
+    assert!(p25 < Duration::from_millis(10));
+
+    let bytes = sketch.serialize();
+    let decoded = KllSketch::<Duration>::deserialize(&bytes).unwrap();
+    assert_eq!(decoded.quantile(0.25), Some(p25));
+    assert_eq!(decoded.serialize(), bytes);
+
+    // the nanoseconds of the single item of a short image
+    let mut single = KllSketch::<Duration>::new(200);
+    single.update(Duration::from_secs(1));
+    let mut bytes = single.serialize();
+    let len = bytes.len();
+    bytes[len - 4..].copy_from_slice(&1_000_000_000u32.to_le_bytes());
+    let err = KllSketch::<Duration>::deserialize(&bytes).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_memory_usage() {
+    let mut sketch = KllSketch::<f64>::new(200);
+    assert!(sketch.memory_usage_bytes() >= size_of::<KllSketch<f64>>());
+    for i in 0..100_000 {
+        sketch.update(i as f64);
+    }
+    let used = sketch.memory_usage_bytes();
+    assert!(used >= size_of::<KllSketch<f64>>() + sketch.num_retained() * 8);
+}
+
+#[test]
+fn test_percentile_tracker() {
+    let mut tracker = PercentileTracker::new();
+    assert_eq!(tracker.p(0.5), None);
+    assert_eq!(tracker.count(), 0);
+
+    for i in 0..100_000 {
+        tracker.record(i as f64);
+    }
+    tracker.record(f64::NAN);
+    assert_eq!(tracker.count(), 100_000);
+    assert_eq!(tracker.min(), Some(0.0));
+    assert_eq!(tracker.max(), Some(99_999.0));
+    assert_eq!(tracker.p(0.0), Some(0.0));
+    let p50 = tracker.p(0.5).unwrap();
+    assert!((p50 - 50_000.0).abs() < 100_000.0 * 0.02, "{p50}");
+
+    let mut other = PercentileTracker::new();
+    other.record(1e9);
+    tracker.merge(&other);
+    assert_eq!(tracker.p(1.0), Some(1e9));
+
+    // snapshots are plain KLL images
+    let snapshot = tracker.snapshot();
+    let sketch = KllSketch::<f64>::deserialize(&snapshot).unwrap();
+    assert_eq!(sketch.n(), 100_001);
+    let restored = PercentileTracker::from(sketch);
+    assert_eq!(restored.p(0.9), tracker.p(0.9));
+    assert_eq!(restored.sketch().k(), 200);
+    assert!(PercentileTracker::from_snapshot(&snapshot[..4]).is_err());
+
+    tracker.reset();
+    assert_eq!(tracker.count(), 0);
+    assert_eq!(tracker.max(), None);
+}
+
+#[test]
+fn test_random_seed_is_reproducible() {
+    let build = |seed: u64| {
+        let mut sketch = KllSketch::<f64>::with_random_seed(8, seed);
+        for i in 0..10_000 {
+            sketch.update(i as f64);
+        }
+        let mut other = KllSketch::<f64>::with_random_seed(8, seed);
+        for i in 10_000..20_000 {
+            other.update(i as f64);
+        }
+        sketch.merge(&other);
+        sketch.serialize()
+    };
+    assert_eq!(build(1), build(1));
+    assert_ne!(build(1), build(2));
+
+    assert!(KllSketch::<f64>::try_with_random_seed(4, 1).is_err());
+    let sketch = KllSketch::<f64>::try_with_random_seed(200, 1).unwrap();
+    assert_eq!(sketch.k(), 200);
+}
+
+#[test]
+fn test_delta_checkpoints() {
+    let mut sketch = KllSketch::<f64>::with_random_seed(100, 7);
+    let mut restored = KllSketch::<f64>::new(100);
+    let mut base = sketch.clone();
+    let mut n = 0u64;
+    for batch in [0, 1, 10, 1_000, 5, 100_000, 3, 0, 50_000] {
+        for _ in 0..batch {
+            sketch.update((n * 7_919 % 100_003) as f64);
+            n += 1;
+        }
+        let delta = sketch.serialize_delta(&base);
+        restored.apply_delta(&delta).unwrap();
+        assert_eq!(restored.serialize(), sketch.serialize(), "after {n} items");
+        base = sketch.clone();
+    }
+    assert_eq!(restored.n(), n);
+
+    // a delta over a few updates skips the unchanged upper levels
+    sketch.update(1.0);
+    assert!(sketch.serialize_delta(&base).len() < sketch.serialized_size_bytes() / 4);
+
+    sketch.reset();
+    restored
+        .apply_delta(&sketch.serialize_delta(&base))
+        .unwrap();
+    assert!(restored.is_empty());
+}
+
+#[test]
+fn test_apply_delta_total_weight_overflow() {
+    // same levels as in test_deserialize_total_weight_overflow, against an empty base
+    let mut delta = vec![4, 3, 15, 0];
+    delta.extend_from_slice(&200u16.to_le_bytes());
+    delta.extend_from_slice(&[8, 61]);
+    delta.extend_from_slice(&0u64.to_le_bytes());
+    delta.push(1);
+    delta.extend_from_slice(&(1u64 << 59).to_le_bytes());
+    delta.extend_from_slice(&200u16.to_le_bytes());
+    delta.extend_from_slice(&1.0f64.to_le_bytes());
+    delta.extend_from_slice(&1.0f64.to_le_bytes());
+    for level in 0..61 {
+        let len: u32 = match level {
+            59 => 1,
+            60 => 16,
+            _ => 0,
+        };
+        delta.push(1);
+        delta.extend_from_slice(&len.to_le_bytes());
+        for _ in 0..len {
+            delta.extend_from_slice(&1.0f64.to_le_bytes());
+        }
+    }
+
+    let mut sketch = KllSketch::<f64>::new(200);
+    let err = sketch.apply_delta(&delta).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(sketch.is_empty());
+}
+
+#[test]
+fn test_apply_delta_to_wrong_base() {
+    let base = sketch_with_range(200, 0, 1_000);
+    let mut sketch = base.clone();
+    sketch.update(1_000.0);
+    let delta = sketch.serialize_delta(&base);
+
+    let mut other = sketch_with_range(200, 0, 999);
+    let err = other.apply_delta(&delta).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    assert_eq!(other.n(), 999);
+
+    let mut restored = base.clone();
+    assert!(restored.apply_delta(&delta[..delta.len() - 1]).is_err());
+    assert_eq!(restored.n(), 1_000);
+    assert!(restored.apply_delta(&sketch.serialize()).is_err());
+}
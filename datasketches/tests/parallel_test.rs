@@ -0,0 +1,132 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+#![cfg(feature = "rayon")]
+
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::kll::KllSketch;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+use datasketches::theta::ThetaUnion;
+use datasketches::theta::parallel_union;
+use datasketches::theta::parallel_union_with;
+
+fn theta_sketches(count: u64, per_sketch: u64) -> Vec<CompactThetaSketch> {
+    (0..count)
+        .map(|i| {
+            let mut sketch = ThetaSketch::builder().build();
+            for j in 0..per_sketch {
+                sketch.update(i * per_sketch / 2 + j);
+            }
+            sketch.compact(true)
+        })
+        .collect()
+}
+
+#[test]
+fn test_theta_parallel_union_empty() {
+    let result = parallel_union(&[]).unwrap();
+    assert!(result.is_empty());
+    assert_eq!(result.estimate(), 0.0);
+}
+
+#[test]
+fn test_theta_parallel_union_exact_mode() {
+    let sketches = theta_sketches(1000, 4);
+    let result = parallel_union(&sketches).unwrap();
+    assert!(!result.is_estimation_mode());
+    // sketch i covers [2i, 2i + 4), so the union covers [0, 2002)
+    assert_eq!(result.estimate(), 2002.0);
+}
+
+#[test]
+fn test_theta_parallel_union_matches_serial() {
+    let sketches = theta_sketches(2000, 1000);
+
+    let mut union = ThetaUnion::builder().build();
+    for sketch in &sketches {
+        union.update(sketch).unwrap();
+    }
+    let serial = union.result();
+
+    let parallel = parallel_union(&sketches).unwrap();
+    assert!(parallel.is_estimation_mode());
+    assert_eq!(parallel.theta64(), serial.theta64());
+    assert_eq!(parallel.num_retained(), serial.num_retained());
+    assert_eq!(parallel.estimate(), serial.estimate());
+}
+
+#[test]
+fn test_theta_parallel_union_with_builder() {
+    let sketches = theta_sketches(100, 1000);
+    let result = parallel_union_with(ThetaUnion::builder().lg_k(5), &sketches).unwrap();
+    assert!(result.num_retained() <= 32);
+
+    let exact = 100 * 1000 / 2 + 1000 / 2;
+    let estimate = result.estimate();
+    assert!((estimate - exact as f64).abs() < exact as f64 * 0.5);
+}
+
+#[test]
+fn test_theta_parallel_union_seed_mismatch() {
+    let mut sketches = theta_sketches(100, 10);
+    let mut other = ThetaSketch::builder().seed(123).build();
+    other.update(1);
+    sketches.push(other.compact(true));
+    assert!(parallel_union(&sketches).is_err());
+}
+
+#[test]
+fn test_kll_parallel_merge() {
+    assert!(datasketches::kll::parallel_merge::<f64>(&[]).is_none());
+
+    let sketches: Vec<_> = (0..1000)
+        .map(|i| {
+            let mut sketch = KllSketch::<f64>::new(200);
+            for j in 0..100 {
+                sketch.update((i * 100 + j) as f64);
+            }
+            sketch
+        })
+        .collect();
+    let merged = datasketches::kll::parallel_merge(&sketches).unwrap();
+    assert_eq!(merged.n(), 100_000);
+    assert_eq!(merged.min_item(), Some(0.0));
+    assert_eq!(merged.max_item(), Some(99_999.0));
+
+    let median = merged.quantile(0.5).unwrap();
+    assert!((median - 50_000.0).abs() < 100_000.0 * 0.02);
+}
+
+#[test]
+fn test_frequencies_parallel_merge() {
+    assert!(datasketches::frequencies::parallel_merge::<u64>(&[]).is_none());
+
+    let sketches: Vec<_> = (0..1000)
+        .map(|i| {
+            let mut sketch = FrequentItemsSketch::<u64>::new(64);
+            sketch.update_with_count(0, 10);
+            sketch.update(i + 1);
+            sketch
+        })
+        .collect();
+    let merged = datasketches::frequencies::parallel_merge(&sketches).unwrap();
+    assert_eq!(merged.total_weight(), 11_000);
+    assert!(merged.lower_bound(&0) <= 10_000);
+    assert!(merged.upper_bound(&0) >= 10_000);
+    assert!(merged.estimate(&0) >= 10_000 - merged.maximum_error());
+}
@@ -0,0 +1,113 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+#![cfg(feature = "perf")]
+
+use std::time::Duration;
+
+use datasketches::perf::Throughput;
+use datasketches::perf::Uniform;
+use datasketches::perf::Weighted;
+use datasketches::perf::Zipf;
+
+#[test]
+fn test_zipf_is_reproducible() {
+    let a: Vec<u64> = Zipf::new(1000, 1.1, 7).take(1000).collect();
+    let b: Vec<u64> = Zipf::new(1000, 1.1, 7).take(1000).collect();
+    let c: Vec<u64> = Zipf::new(1000, 1.1, 8).take(1000).collect();
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn test_zipf_frequencies() {
+    let n = 200_000;
+    for exponent in [0.5, 1.0, 1.5] {
+        let mut counts = vec![0u64; 100];
+        for key in Zipf::new(100, exponent, 1).take(n) {
+            counts[key as usize] += 1;
+        }
+        let norm: f64 = (1..=100).map(|i| (i as f64).powf(-exponent)).sum();
+        for key in [0usize, 1, 9, 99] {
+            let expected = n as f64 * ((key + 1) as f64).powf(-exponent) / norm;
+            let actual = counts[key] as f64;
+            // within 5 standard deviations of a binomial count
+            let tolerance = 5.0 * expected.sqrt();
+            assert!(
+                (actual - expected).abs() <= tolerance,
+                "exponent {exponent}, key {key}: expected {expected}, got {actual}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_zipf_single_key() {
+    assert!(Zipf::new(1, 1.0, 3).take(100).all(|key| key == 0));
+}
+
+#[test]
+#[should_panic(expected = "exponent must be positive")]
+fn test_zipf_invalid_exponent() {
+    Zipf::new(10, 0.0, 1);
+}
+
+#[test]
+fn test_uniform_range_and_spread() {
+    let mut counts = [0u64; 16];
+    for key in Uniform::new(16, 5).take(160_000) {
+        counts[key as usize] += 1;
+    }
+    for count in counts {
+        assert!((9_500..=10_500).contains(&count), "count {count}");
+    }
+}
+
+#[test]
+fn test_weighted() {
+    let items: Vec<(u64, u64)> = Weighted::new(Uniform::new(10, 1), 3, 2)
+        .take(10_000)
+        .collect();
+    assert!(items.iter().all(|(key, _)| *key < 10));
+    for weight in 1..=3 {
+        assert!(items.iter().any(|(_, w)| *w == weight));
+    }
+    assert!(items.iter().all(|(_, w)| (1..=3).contains(w)));
+
+    // finite key streams end the weighted stream
+    assert_eq!(Weighted::new(0..5u64, 1, 1).count(), 5);
+}
+
+#[test]
+fn test_throughput_measure() {
+    let items: Vec<u64> = Uniform::new(u64::MAX, 1).take(1000).collect();
+    let mut sum = 0u64;
+    let throughput = Throughput::measure(&items, |item| sum = sum.wrapping_add(*item));
+    assert_eq!(throughput.items(), 1000);
+    assert_eq!(sum, items.iter().fold(0u64, |acc, x| acc.wrapping_add(*x)));
+}
+
+#[test]
+fn test_throughput_rates() {
+    let throughput = Throughput::new(2_000_000, Duration::from_secs(2));
+    assert_eq!(throughput.items_per_second(), 1_000_000.0);
+    assert_eq!(throughput.nanos_per_item(), 1000.0);
+    assert_eq!(
+        throughput.to_string(),
+        "2000000 items in 2s (1.00 M items/s, 1000.0 ns/item)"
+    );
+}
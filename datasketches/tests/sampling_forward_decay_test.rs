@@ -0,0 +1,212 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::error::ErrorKind;
+use datasketches::sampling::ForwardDecaySketch;
+
+fn decayed_total(timestamps: impl Iterator<Item = f64>, half_life: f64, now: f64) -> f64 {
+    timestamps.map(|t| ((t - now) / half_life).exp2()).sum()
+}
+
+#[test]
+fn test_empty() {
+    let sketch = ForwardDecaySketch::<u64>::new(16, 10.0);
+    assert!(sketch.is_empty());
+    assert_eq!(sketch.n(), 0);
+    assert_eq!(sketch.num_samples(), 0);
+    assert_eq!(sketch.samples(0.0).count(), 0);
+    assert_eq!(sketch.estimate_total_weight(0.0), 0.0);
+}
+
+#[test]
+#[should_panic(expected = "k must be positive")]
+fn test_zero_k() {
+    ForwardDecaySketch::<u64>::new(0, 10.0);
+}
+
+#[test]
+#[should_panic(expected = "half_life must be positive and finite")]
+fn test_invalid_half_life() {
+    ForwardDecaySketch::<u64>::new(16, 0.0);
+}
+
+#[test]
+fn test_invalid_updates_ignored() {
+    let mut sketch = ForwardDecaySketch::new(16, 10.0);
+    sketch.update(1, 0.0, 0.0);
+    sketch.update(2, -1.0, 0.0);
+    sketch.update(3, f64::NAN, 0.0);
+    sketch.update(4, f64::INFINITY, 0.0);
+    sketch.update(5, 1.0, f64::NAN);
+    sketch.update(6, 1.0, f64::NEG_INFINITY);
+    assert!(sketch.is_empty());
+}
+
+#[test]
+fn test_exact_below_k() {
+    let half_life = 10.0;
+    let mut sketch = ForwardDecaySketch::new(100, half_life);
+    for t in 0..50 {
+        sketch.update(t, 2.0, t as f64);
+    }
+    assert_eq!(sketch.n(), 50);
+    assert_eq!(sketch.num_samples(), 50);
+
+    let now = 60.0;
+    for sample in sketch.samples(now) {
+        assert_eq!(sample.weight(), 2.0);
+        assert_eq!(sample.timestamp(), *sample.item() as f64);
+        let expected = 2.0 * ((sample.timestamp() - now) / half_life).exp2();
+        assert!((sample.estimated_weight() - expected).abs() < 1e-9 * expected);
+    }
+    let expected = 2.0 * decayed_total((0..50).map(|t| t as f64), half_life, now);
+    assert!((sketch.estimate_total_weight(now) - expected).abs() < 1e-9 * expected);
+}
+
+#[test]
+fn test_biased_towards_recent_items() {
+    let half_life = 100.0;
+    let mut sketch = ForwardDecaySketch::new(100, half_life);
+    for t in 0..10_000 {
+        sketch.update(t, 1.0, t as f64);
+    }
+    assert_eq!(sketch.num_samples(), 100);
+
+    // the last five half-lives carry about 97% of the decayed weight
+    let recent = sketch
+        .samples(10_000.0)
+        .filter(|s| s.timestamp() >= 9500.0)
+        .count();
+    assert!(recent >= 85, "only {recent} recent samples");
+}
+
+#[test]
+fn test_estimates_with_large_timestamps() {
+    // epoch seconds with a one-minute half-life would overflow without log space priorities
+    let start = 1.7e9;
+    let half_life = 60.0;
+    let mut sketch = ForwardDecaySketch::new(1000, half_life);
+    for i in 0..20_000u64 {
+        sketch.update(i, 1.0, start + i as f64 * 0.1);
+    }
+
+    let now = start + 2000.0;
+    let expected = decayed_total((0..20_000).map(|i| start + i as f64 * 0.1), half_life, now);
+    let estimate = sketch.estimate_total_weight(now);
+    assert!(estimate.is_finite());
+    assert!(
+        (estimate - expected).abs() < 0.15 * expected,
+        "{estimate} vs {expected}"
+    );
+
+    let even = sketch.estimate_subset_weight(now, |i| i % 2 == 0);
+    assert!((even - expected / 2.0).abs() < 0.2 * expected / 2.0);
+}
+
+#[test]
+fn test_out_of_order_timestamps() {
+    let mut sketch = ForwardDecaySketch::new(10, 10.0);
+    sketch.update("late", 1.0, 100.0);
+    sketch.update("early", 1.0, 0.0);
+    let early = sketch.estimate_subset_weight(100.0, |item| *item == "early");
+    assert!((early - 2f64.powi(-10)).abs() < 1e-12);
+}
+
+#[test]
+fn test_merge() {
+    let half_life = 50.0;
+    let mut left = ForwardDecaySketch::new(500, half_life);
+    let mut right = ForwardDecaySketch::new(500, half_life);
+    for t in 0..10_000u64 {
+        if t % 2 == 0 {
+            left.update(t, 1.0, t as f64);
+        } else {
+            right.update(t, 1.0, t as f64 + 0.5);
+        }
+    }
+    left.merge(&right).unwrap();
+    assert_eq!(left.n(), 10_000);
+    assert_eq!(left.num_samples(), 500);
+
+    let now = 10_000.0;
+    let expected = decayed_total(
+        (0..10_000u64).map(|t| t as f64 + if t % 2 == 0 { 0.0 } else { 0.5 }),
+        half_life,
+        now,
+    );
+    let estimate = left.estimate_total_weight(now);
+    assert!(
+        (estimate - expected).abs() < 0.15 * expected,
+        "{estimate} vs {expected}"
+    );
+}
+
+#[test]
+fn test_merge_empty_and_into_empty() {
+    let mut sketch = ForwardDecaySketch::new(4, 10.0);
+    sketch.update(1, 1.0, 5.0);
+
+    let mut empty = ForwardDecaySketch::new(4, 10.0);
+    empty.merge(&sketch).unwrap();
+    assert_eq!(empty.n(), 1);
+    assert_eq!(
+        empty.estimate_total_weight(5.0),
+        sketch.estimate_total_weight(5.0)
+    );
+
+    sketch.merge(&ForwardDecaySketch::new(4, 10.0)).unwrap();
+    assert_eq!(sketch.n(), 1);
+}
+
+#[test]
+fn test_merge_incompatible_half_life() {
+    let mut left = ForwardDecaySketch::<u64>::new(4, 10.0);
+    let right = ForwardDecaySketch::<u64>::new(4, 20.0);
+    let err = left.merge(&right).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+}
+
+#[test]
+fn test_reset() {
+    let mut sketch = ForwardDecaySketch::new(4, 10.0);
+    for t in 0..10 {
+        sketch.update(t, 1.0, t as f64);
+    }
+    sketch.reset();
+    assert!(sketch.is_empty());
+    assert_eq!(sketch.num_samples(), 0);
+    sketch.update(1, 1.0, 1000.0);
+    assert!((sketch.estimate_total_weight(1000.0) - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_random_seed_is_reproducible() {
+    let samples = |seed: u64| {
+        let mut sketch = ForwardDecaySketch::with_random_seed(16, 50.0, seed);
+        for i in 0..1_000u64 {
+            sketch.update(i, 1.0 + (i % 7) as f64, i as f64);
+        }
+        let mut samples: Vec<(u64, f64)> = sketch
+            .samples(1_000.0)
+            .map(|sample| (*sample.item(), sample.estimated_weight()))
+            .collect();
+        samples.sort_by_key(|(item, _)| *item);
+        samples
+    };
+    assert_eq!(samples(3), samples(3));
+    assert_ne!(samples(3), samples(4));
+}
@@ -0,0 +1,183 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+#![cfg(feature = "serde")]
+
+use std::fmt::Debug;
+
+use datasketches::bloom::BloomFilter;
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::common::EstimateSummary;
+use datasketches::common::NumStdDev;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::kll::KllSketch;
+use datasketches::kll::MultiKll;
+use datasketches::tdigest::TDigest;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::value::BytesDeserializer;
+
+// round trips through JSON (a sequence of bytes) and a native byte string, returning the result
+// of the latter
+fn round_trip<T>(sketch: &T, binary: &[u8]) -> T
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+{
+    let json = serde_json::to_string(sketch).unwrap();
+    let expected_json = serde_json::to_string(binary).unwrap();
+    assert_eq!(json, expected_json);
+
+    let decoded: T = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        serde_json::to_string(&decoded).unwrap(),
+        expected_json,
+        "JSON round trip changed the sketch"
+    );
+
+    T::deserialize(BytesDeserializer::<serde::de::value::Error>::new(binary)).unwrap()
+}
+
+fn assert_round_trip<T>(sketch: &T, binary: &[u8])
+where
+    T: Serialize + for<'de> Deserialize<'de> + PartialEq + Debug,
+{
+    assert_eq!(&round_trip(sketch, binary), sketch);
+}
+
+#[test]
+fn test_round_trip_all_sketches() {
+    let mut bloom = BloomFilterBuilder::with_accuracy(100, 0.01).build();
+    bloom.insert("apple");
+    assert_round_trip(&bloom, &bloom.serialize());
+
+    let mut countmin = CountMinSketch::<i64>::new(3, 16);
+    countmin.update("apple");
+    assert_round_trip(&countmin, &countmin.serialize());
+
+    let mut hll = HllSketch::new(10, HllType::Hll6);
+    for i in 0..1000 {
+        hll.update(i);
+    }
+    assert_round_trip(&hll, &hll.serialize());
+
+    let mut cpc = CpcSketch::new(10);
+    for i in 0..1000 {
+        cpc.update(i);
+    }
+    let decoded = round_trip(&cpc, &cpc.serialize());
+    assert_eq!(decoded.estimate(), cpc.estimate());
+
+    let mut frequencies = FrequentItemsSketch::<String>::new(8);
+    frequencies.update("apple".to_string());
+    let decoded = round_trip(&frequencies, &frequencies.serialize());
+    assert_eq!(decoded.estimate(&"apple".to_string()), 1);
+
+    let mut kll = KllSketch::<f64>::new(200);
+    for i in 0..1000 {
+        kll.update(i as f64);
+    }
+    let decoded = round_trip(&kll, &kll.serialize());
+    assert_eq!(decoded.quantile(0.5), kll.quantile(0.5));
+
+    let mut multi = MultiKll::<2>::new(200);
+    multi.update([1.0, 2.0]);
+    let decoded = round_trip(&multi, &multi.serialize());
+    assert_eq!(decoded.quantiles(0.5), multi.quantiles(0.5));
+
+    let mut tdigest = TDigestMut::new(100);
+    for i in 0..1000 {
+        tdigest.update(i as f64);
+    }
+    // the inherent method takes `&mut self`, so it must be named while `Serialize` is in scope
+    let binary = TDigestMut::serialize(&mut tdigest);
+    let mut decoded = round_trip(&tdigest, &binary);
+    assert_eq!(decoded.quantile(0.5), tdigest.quantile(0.5));
+    let frozen = tdigest.freeze();
+    let decoded: TDigest = round_trip(&frozen, &binary);
+    assert_eq!(decoded.quantile(0.5), frozen.quantile(0.5));
+
+    let mut theta = ThetaSketch::builder().build();
+    for i in 0..1000 {
+        theta.update(i);
+    }
+    let decoded = round_trip(&theta, &theta.serialize());
+    assert_eq!(decoded.estimate(), theta.estimate());
+    let compact = theta.compact(true);
+    let decoded: CompactThetaSketch = round_trip(&compact, &compact.serialize());
+    assert_eq!(decoded.estimate(), compact.estimate());
+}
+
+#[test]
+fn test_embedded_in_application_state() {
+    #[derive(Serialize, Deserialize)]
+    struct State {
+        name: String,
+        visitors: HllSketch,
+    }
+
+    let mut visitors = HllSketch::new(12, HllType::Hll8);
+    visitors.update("alice");
+    visitors.update("bob");
+    let state = State {
+        name: "home".to_string(),
+        visitors,
+    };
+
+    let json = serde_json::to_string(&state).unwrap();
+    let decoded: State = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded.name, "home");
+    assert_eq!(decoded.visitors, state.visitors);
+}
+
+#[test]
+fn test_estimate_summary() {
+    let mut sketch = CpcSketch::new(10);
+    for i in 0..100 {
+        sketch.update(i);
+    }
+    let summary = sketch.estimate_summary(NumStdDev::Two);
+
+    let json = serde_json::to_value(summary).unwrap();
+    assert_eq!(json["family"], "Cpc");
+    assert_eq!(json["retained"], sketch.num_coupons());
+    assert_eq!(json["estimate"], sketch.estimate());
+    let decoded: EstimateSummary = serde_json::from_value(json).unwrap();
+    assert_eq!(decoded, summary);
+}
+
+#[test]
+fn test_invalid_input() {
+    let err = serde_json::from_str::<BloomFilter>("[1, 2, 3]").unwrap_err();
+    assert!(err.to_string().contains("insufficient data"), "{err}");
+
+    // serde_json hands the raw bytes of a string to the sketch decoder
+    let err = serde_json::from_str::<KllSketch<f64>>(r#""not bytes""#).unwrap_err();
+    assert!(err.to_string().contains("invalid family"), "{err}");
+
+    let err = serde_json::from_str::<KllSketch<f64>>("{}").unwrap_err();
+    assert!(err.to_string().contains("a serialized KLL sketch"), "{err}");
+
+    let err = serde_json::from_str::<HllSketch>("[1, 256]").unwrap_err();
+    assert!(err.to_string().contains("u8"), "{err}");
+}
@@ -0,0 +1,363 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Estimates must survive a serialization round trip bit-for-bit, and blobs written by earlier
+//! releases must keep deserializing.
+
+mod common;
+
+use std::fs;
+
+use common::test_data;
+use datasketches::bloom::BloomFilter;
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::common::NumStdDev;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::hll::HllUnion;
+use datasketches::kll::KllSketch;
+use datasketches::postgres::decode_bytea;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+
+const HLL_TYPES: [HllType; 3] = [HllType::Hll4, HllType::Hll6, HllType::Hll8];
+const STD_DEVS: [NumStdDev; 3] = [NumStdDev::One, NumStdDev::Two, NumStdDev::Three];
+
+/// Counts spanning empty, LIST, SET and HLL modes for the lg_k values below.
+const COUNTS: [u64; 9] = [0, 1, 7, 8, 50, 200, 1_000, 5_000, 50_000];
+
+fn assert_hll_bits_eq(expected: &HllSketch, actual: &HllSketch, context: &str) {
+    assert_eq!(
+        expected.estimate().to_bits(),
+        actual.estimate().to_bits(),
+        "estimate differs: {context}"
+    );
+    for num_std_dev in STD_DEVS {
+        assert_eq!(
+            expected.upper_bound(num_std_dev).to_bits(),
+            actual.upper_bound(num_std_dev).to_bits(),
+            "upper bound differs: {context}"
+        );
+        assert_eq!(
+            expected.lower_bound(num_std_dev).to_bits(),
+            actual.lower_bound(num_std_dev).to_bits(),
+            "lower bound differs: {context}"
+        );
+    }
+}
+
+fn round_trip_hll(sketch: &HllSketch) -> HllSketch {
+    HllSketch::deserialize(&sketch.serialize()).unwrap()
+}
+
+#[test]
+fn test_hll_round_trip_estimate_is_bit_identical() {
+    for lg_k in [4, 8, 10, 12] {
+        for hll_type in HLL_TYPES {
+            for n in COUNTS {
+                let mut sketch = HllSketch::new(lg_k, hll_type);
+                for i in 0..n {
+                    sketch.update(i);
+                }
+                let context = format!("lg_k={lg_k} type={hll_type:?} n={n}");
+                let decoded = round_trip_hll(&sketch);
+                assert_hll_bits_eq(&sketch, &decoded, &context);
+                assert_eq!(sketch.serialize(), decoded.serialize(), "{context}");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_hll_round_trip_then_update_stays_in_lockstep() {
+    for lg_k in [4, 8, 10, 12] {
+        for hll_type in HLL_TYPES {
+            for n in COUNTS {
+                let mut sketch = HllSketch::new(lg_k, hll_type);
+                for i in 0..n {
+                    sketch.update(i);
+                }
+                let mut decoded = round_trip_hll(&sketch);
+                for i in n..n + 2_000 {
+                    sketch.update(i);
+                    decoded.update(i);
+                }
+                let context = format!("lg_k={lg_k} type={hll_type:?} n={n}");
+                assert_hll_bits_eq(&sketch, &decoded, &context);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_hll_union_of_round_tripped_sketches_is_bit_identical() {
+    for lg_k in [8, 10] {
+        for hll_type in HLL_TYPES {
+            for (n1, n2) in [
+                (5, 100),
+                (100, 100),
+                (100, 1_000),
+                (1_000, 100),
+                (50_000, 200),
+            ] {
+                let mut a = HllSketch::new(lg_k, hll_type);
+                let mut b = HllSketch::new(lg_k + 1, hll_type);
+                for i in 0..n1 {
+                    a.update(i);
+                }
+                for i in 0..n2 {
+                    b.update(i * 7 + 1);
+                }
+
+                let mut union = HllUnion::new(lg_k + 2);
+                union.update(&a);
+                union.update(&b);
+                let mut decoded_union = HllUnion::new(lg_k + 2);
+                decoded_union.update(&round_trip_hll(&a));
+                decoded_union.update(&round_trip_hll(&b));
+
+                let context = format!("lg_k={lg_k} type={hll_type:?} n1={n1} n2={n2}");
+                for target in HLL_TYPES {
+                    assert_hll_bits_eq(
+                        &union.to_sketch(target),
+                        &decoded_union.to_sketch(target),
+                        &context,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn assert_theta_bits_eq(expected: &CompactThetaSketch, actual: &CompactThetaSketch, context: &str) {
+    assert_eq!(
+        expected.theta64(),
+        actual.theta64(),
+        "theta differs: {context}"
+    );
+    assert_eq!(
+        expected.estimate().to_bits(),
+        actual.estimate().to_bits(),
+        "estimate differs: {context}"
+    );
+    for num_std_dev in STD_DEVS {
+        assert_eq!(
+            expected.upper_bound(num_std_dev).to_bits(),
+            actual.upper_bound(num_std_dev).to_bits(),
+            "upper bound differs: {context}"
+        );
+        assert_eq!(
+            expected.lower_bound(num_std_dev).to_bits(),
+            actual.lower_bound(num_std_dev).to_bits(),
+            "lower bound differs: {context}"
+        );
+    }
+}
+
+#[test]
+fn test_theta_round_trip_estimate_is_bit_identical() {
+    for lg_k in [5, 9, 12] {
+        for p in [1.0, 0.5, 0.01] {
+            for n in COUNTS {
+                let mut sketch = ThetaSketch::builder()
+                    .lg_k(lg_k)
+                    .sampling_probability(p)
+                    .build();
+                for i in 0..n {
+                    sketch.update_quiet(i);
+                }
+                for ordered in [true, false] {
+                    let compact = sketch.compact(ordered);
+                    let context = format!("lg_k={lg_k} p={p} n={n} ordered={ordered}");
+                    assert_eq!(
+                        sketch.estimate().to_bits(),
+                        compact.estimate().to_bits(),
+                        "compaction changed estimate: {context}"
+                    );
+
+                    let decoded = CompactThetaSketch::deserialize(&compact.serialize()).unwrap();
+                    assert_theta_bits_eq(&compact, &decoded, &context);
+                    let decoded =
+                        CompactThetaSketch::deserialize(&compact.serialize_compressed()).unwrap();
+                    assert_theta_bits_eq(&compact, &decoded, &context);
+                }
+            }
+        }
+    }
+}
+
+/// Builds the sketch stored under `name` in the golden blobs, for the deterministic families.
+fn golden_sketch(name: &str) -> Option<Vec<u8>> {
+    let theta = || {
+        let mut sketch = ThetaSketch::builder().lg_k(10).build();
+        for i in 0..5_000 {
+            sketch.update(i);
+        }
+        sketch
+    };
+    let hll = |hll_type, n| {
+        let mut sketch = HllSketch::new(10, hll_type);
+        for i in 0..n {
+            sketch.update(i);
+        }
+        sketch.serialize()
+    };
+    let bytes = match name {
+        "theta_update" => theta().serialize(),
+        "theta_compact" => theta().compact(true).serialize(),
+        "theta_compressed" => theta().compact(true).serialize_compressed(),
+        "hll_list" => hll(HllType::Hll8, 5),
+        "hll_set" => hll(HllType::Hll8, 100),
+        "hll4" => hll(HllType::Hll4, 5_000),
+        "hll6" => hll(HllType::Hll6, 5_000),
+        "hll8" => hll(HllType::Hll8, 5_000),
+        "cpc" => {
+            let mut sketch = CpcSketch::new(10);
+            for i in 0..5_000 {
+                sketch.update(i);
+            }
+            sketch.serialize()
+        }
+        "frequencies_i64" => {
+            let mut sketch = FrequentItemsSketch::<i64>::new(16);
+            for i in 0..1_000 {
+                sketch.update(i % 37 * (i % 5));
+            }
+            sketch.serialize()
+        }
+        "frequencies_string" => {
+            let mut sketch = FrequentItemsSketch::<String>::new(8);
+            for i in 0..100 {
+                sketch.update_with_count(format!("item-{}", i % 13), i);
+            }
+            sketch.serialize()
+        }
+        "countmin" => {
+            let mut sketch = CountMinSketch::<u64>::new(3, 64);
+            for i in 0..1_000 {
+                sketch.update(i % 100);
+            }
+            sketch.serialize()
+        }
+        "tdigest" => {
+            let mut sketch = TDigestMut::new(100);
+            for i in 0..10_000 {
+                sketch.update(i as f64);
+            }
+            sketch.serialize()
+        }
+        "bloom" => {
+            let mut filter = BloomFilterBuilder::with_size(512, 3).build();
+            for i in 0..50 {
+                filter.insert(i);
+            }
+            filter.serialize()
+        }
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+/// Builds the sketches stored under `name` in the golden blobs, for the randomized families.
+fn golden_random_sketch(name: &str) -> Option<Vec<u8>> {
+    let bytes = match name {
+        "kll_f64" => {
+            let mut sketch = KllSketch::<f64>::new(20);
+            for i in 0..1_000 {
+                sketch.update(i as f64);
+            }
+            sketch.serialize()
+        }
+        "kll_f32_single" => {
+            let mut sketch = KllSketch::<f32>::new(200);
+            sketch.update(1.5);
+            sketch.serialize()
+        }
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+/// Deserializes a golden blob and serializes it again.
+fn reserialize(name: &str, bytes: &[u8]) -> Vec<u8> {
+    match name {
+        "theta_update" => ThetaSketch::deserialize(bytes).unwrap().serialize(),
+        "theta_compact" => CompactThetaSketch::deserialize(bytes).unwrap().serialize(),
+        "theta_compressed" => CompactThetaSketch::deserialize(bytes)
+            .unwrap()
+            .serialize_compressed(),
+        "hll_list" | "hll_set" | "hll4" | "hll6" | "hll8" => {
+            HllSketch::deserialize(bytes).unwrap().serialize()
+        }
+        "cpc" => CpcSketch::deserialize(bytes).unwrap().serialize(),
+        "frequencies_i64" => FrequentItemsSketch::<i64>::deserialize(bytes)
+            .unwrap()
+            .serialize(),
+        "frequencies_string" => FrequentItemsSketch::<String>::deserialize(bytes)
+            .unwrap()
+            .serialize(),
+        "countmin" => CountMinSketch::<u64>::deserialize(bytes)
+            .unwrap()
+            .serialize(),
+        "tdigest" => TDigestMut::deserialize(bytes, false).unwrap().serialize(),
+        "bloom" => BloomFilter::deserialize(bytes).unwrap().serialize(),
+        "kll_f64" => KllSketch::<f64>::deserialize(bytes).unwrap().serialize(),
+        "kll_f32_single" => KllSketch::<f32>::deserialize(bytes).unwrap().serialize(),
+        _ => panic!("unknown golden blob: {name}"),
+    }
+}
+
+#[test]
+fn test_golden_blobs_keep_deserializing() {
+    // one blob per writable format, as `name \x...` lines in the bytea text format, so that
+    // they can be pasted into SQL; never regenerate them, only append new ones
+    let golden = fs::read_to_string(test_data("bytea_golden.txt")).unwrap();
+    let mut count = 0;
+    for line in golden.lines().filter(|line| !line.starts_with('#')) {
+        let (name, text) = line.split_once(' ').unwrap();
+        let bytes = decode_bytea(text).unwrap();
+        assert_eq!(reserialize(name, &bytes), bytes, "{name}");
+        if let Some(fresh) = golden_sketch(name) {
+            assert_eq!(fresh, bytes, "{name} is no longer serialized the same way");
+        } else {
+            let fresh = golden_random_sketch(name).unwrap();
+            assert_eq!(fresh[..8], bytes[..8], "{name} preamble changed");
+        }
+        count += 1;
+    }
+    assert_eq!(count, 16);
+}
+
+#[test]
+fn test_bytea_text_formats() {
+    use datasketches::postgres::encode_bytea;
+
+    assert_eq!(encode_bytea(&[0x00, 0xab, 0x7f]), "\\x00ab7f");
+    assert_eq!(decode_bytea("\\x00AB7f").unwrap(), vec![0x00, 0xab, 0x7f]);
+    assert_eq!(
+        decode_bytea("\\x00 ab\n7f").unwrap(),
+        vec![0x00, 0xab, 0x7f]
+    );
+    assert_eq!(decode_bytea("a\\\\b\\001").unwrap(), b"a\\b\x01".to_vec());
+    assert!(decode_bytea("\\x0ab").is_err());
+    assert!(decode_bytea("\\xzz").is_err());
+    assert!(decode_bytea("\\9").is_err());
+}
@@ -0,0 +1,207 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::codec::SketchBytes;
+use datasketches::codec::SketchSlice;
+use datasketches::common::SerializableSketch;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::error::Error;
+use datasketches::error::ErrorKind;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::kll::KllItem;
+use datasketches::kll::KllSketch;
+use datasketches::kll::MultiKll;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::ThetaSketch;
+
+const FILL: u8 = 0xA5;
+
+fn assert_serialize_into<S: SerializableSketch>(sketch: &S, context: &str) {
+    let expected = sketch.serialize();
+    let size = sketch.serialized_size_bytes();
+    assert_eq!(size, expected.len(), "{context}: serialized size");
+
+    // the exact size and a larger buffer both work, and bytes past the image are untouched
+    for extra in [0, 13] {
+        let mut buf = vec![FILL; size + extra];
+        let len = sketch.serialize_into(&mut buf).unwrap();
+        assert_eq!(len, size, "{context}: written length");
+        assert_eq!(&buf[..len], expected.as_slice(), "{context}: bytes");
+        assert!(buf[len..].iter().all(|&b| b == FILL), "{context}: tail");
+    }
+
+    if size > 0 {
+        let mut buf = vec![FILL; size - 1];
+        let err = sketch.serialize_into(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{context}: {err}");
+    }
+}
+
+#[test]
+fn test_hll_serialize_into() {
+    for hll_type in [HllType::Hll4, HllType::Hll6, HllType::Hll8] {
+        // list, set and dense modes
+        for n in [0, 1, 5, 100, 10_000] {
+            let mut sketch = HllSketch::new(10, hll_type);
+            for i in 0..n {
+                sketch.update(i);
+            }
+            assert_serialize_into(&sketch, &format!("{hll_type:?} n={n}"));
+        }
+    }
+
+    // enough updates at a small lg_k to need the aux map of HLL4
+    let mut sketch = HllSketch::new(4, HllType::Hll4);
+    for i in 0..100_000 {
+        sketch.update(i);
+    }
+    assert_serialize_into(&sketch, "Hll4 with aux map");
+}
+
+#[test]
+fn test_cpc_serialize_into() {
+    // empty, sparse, hybrid, pinned and sliding flavors
+    for n in [0, 1, 50, 200, 2_000, 20_000] {
+        let mut sketch = CpcSketch::new(8);
+        for i in 0..n {
+            sketch.update(i);
+        }
+        assert_serialize_into(&sketch, &format!("cpc n={n}"));
+    }
+}
+
+#[test]
+fn test_theta_serialize_into() {
+    for n in [0, 1, 10, 10_000] {
+        let mut sketch = ThetaSketch::builder().lg_k(8).build();
+        for i in 0..n {
+            sketch.update(i);
+        }
+        assert_serialize_into(&sketch, &format!("theta n={n}"));
+        assert_serialize_into(&sketch.compact(true), &format!("compact ordered n={n}"));
+        assert_serialize_into(&sketch.compact(false), &format!("compact unordered n={n}"));
+    }
+}
+
+#[test]
+fn test_kll_serialize_into() {
+    for n in [0, 1, 10, 10_000] {
+        let mut sketch = KllSketch::<f64>::new(200);
+        let mut multi = MultiKll::<3>::new(200);
+        for i in 0..n {
+            sketch.update(i as f64);
+            multi.update([i as f64, -(i as f64), 0.5]);
+        }
+        assert_serialize_into(&sketch, &format!("kll n={n}"));
+        assert_serialize_into(&multi, &format!("multi kll n={n}"));
+    }
+}
+
+#[test]
+fn test_frequencies_serialize_into() {
+    for n in [0, 1, 10, 10_000] {
+        let mut longs = FrequentItemsSketch::<i64>::new(64);
+        let mut strings = FrequentItemsSketch::<String>::new(64);
+        for i in 0..n {
+            longs.update(i % 100);
+            strings.update(format!("item-{}", i % 100));
+        }
+        assert_serialize_into(&longs, &format!("i64 n={n}"));
+        assert_serialize_into(&strings, &format!("String n={n}"));
+    }
+}
+
+#[test]
+fn test_bloom_and_countmin_serialize_into() {
+    let mut filter = BloomFilterBuilder::with_accuracy(1000, 0.01).build();
+    assert_serialize_into(&filter, "empty bloom");
+    filter.insert("apple");
+    assert_serialize_into(&filter, "bloom");
+
+    let mut sketch = CountMinSketch::<i64>::new(3, 64);
+    assert_serialize_into(&sketch, "empty countmin");
+    sketch.update("apple");
+    assert_serialize_into(&sketch, "countmin");
+}
+
+#[test]
+fn test_tdigest_serialize_into() {
+    for n in [0, 1, 10, 10_000] {
+        let mut sketch = TDigestMut::new(100);
+        for i in 0..n {
+            sketch.update(i as f64);
+        }
+        // through the trait, buffered values are compressed on a copy
+        assert_serialize_into(&sketch, &format!("tdigest n={n}"));
+
+        let size = sketch.serialized_size_bytes();
+        let mut buf = vec![0u8; size];
+        assert_eq!(sketch.serialize_into(&mut buf).unwrap(), size);
+        assert_eq!(buf, sketch.serialize());
+    }
+}
+
+#[test]
+fn test_serialize_into_reused_buffer() {
+    let mut buf = vec![0u8; 4096];
+    for n in [1000, 1, 100] {
+        let mut sketch = KllSketch::<f64>::new(200);
+        for i in 0..n {
+            sketch.update(i as f64);
+        }
+        let len = sketch.serialize_into(&mut buf).unwrap();
+        let decoded = KllSketch::<f64>::deserialize(&buf[..len]).unwrap();
+        assert_eq!(decoded.n(), n);
+    }
+}
+
+// an item that reports a smaller size than it writes
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct Undersized(u32);
+
+impl KllItem for Undersized {
+    fn serialize_size(_item: &Self) -> usize {
+        2
+    }
+
+    fn serialize_value(&self, bytes: &mut SketchBytes<'_>) {
+        bytes.write_u32_le(self.0);
+    }
+
+    fn deserialize_value(cursor: &mut SketchSlice<'_>) -> Result<Self, Error> {
+        cursor
+            .read_u32_le()
+            .map(Undersized)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "failed to read item"))
+    }
+}
+
+#[test]
+fn test_serialize_into_rejects_inconsistent_item_size() {
+    let mut sketch = KllSketch::new(200);
+    sketch.update(Undersized(1));
+    sketch.update(Undersized(2));
+
+    let size = sketch.serialized_size_bytes();
+    let mut buf = vec![0u8; size + 64];
+    let err = sketch.serialize_into(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+}
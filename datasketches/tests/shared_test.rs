@@ -0,0 +1,82 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+#![cfg(feature = "shared")]
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+use datasketches::shared::SharedSketch;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+
+#[test]
+fn test_load_keeps_previous_sketch() {
+    let shared = SharedSketch::new(CompactThetaSketch::empty());
+    let mut sketch = ThetaSketch::builder().build();
+    sketch.update("apple");
+    shared.publish(sketch.snapshot());
+
+    let first = shared.load();
+    sketch.update("banana");
+    shared.publish(sketch.snapshot());
+    assert_eq!(first.estimate(), 1.0);
+    assert_eq!(shared.load().estimate(), 2.0);
+    assert_eq!(Arc::strong_count(&first), 1);
+}
+
+#[test]
+fn test_concurrent_publish_and_load() {
+    const NUM_ITEMS: u64 = 2_000;
+
+    let shared = Arc::new(SharedSketch::new(CompactThetaSketch::empty()));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let shared = Arc::clone(&shared);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                let mut last = 0;
+                let mut loads = 0;
+                while !done.load(Ordering::Acquire) || loads == 0 {
+                    let sketch = shared.load();
+                    // every load is a whole published sketch, and publications are seen in order
+                    assert_eq!(sketch.estimate(), sketch.num_retained() as f64);
+                    assert!(sketch.num_retained() >= last);
+                    last = sketch.num_retained();
+                    loads += 1;
+                }
+                last
+            })
+        })
+        .collect();
+
+    let mut sketch = ThetaSketch::builder().build();
+    for i in 0..NUM_ITEMS {
+        sketch.update(i);
+        shared.publish(sketch.snapshot());
+    }
+    done.store(true, Ordering::Release);
+
+    for reader in readers {
+        assert!(reader.join().unwrap() <= NUM_ITEMS as usize);
+    }
+    assert_eq!(shared.load().estimate(), NUM_ITEMS as f64);
+}
@@ -0,0 +1,202 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::fmt::Debug;
+
+use datasketches::bloom::BloomFilterBuilder;
+use datasketches::common::MergeableSketch;
+use datasketches::common::SerializableSketch;
+use datasketches::common::Sketch;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::cpc::CpcUnion;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::hll::HllUnion;
+use datasketches::kll::KllSketch;
+use datasketches::kll::MultiKll;
+use datasketches::tdigest::TDigestMut;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+use datasketches::theta::ThetaUnion;
+
+// a generic aggregation operator: feeds two partitions, merges them, and ships the result
+fn aggregate<S, I>(mut left: S, mut right: S, items: impl IntoIterator<Item = I>) -> S
+where
+    S: Sketch<I> + MergeableSketch + SerializableSketch,
+    <S as MergeableSketch>::Error: Debug,
+    <S as SerializableSketch>::Error: Debug,
+{
+    assert!(left.is_empty());
+    for (i, item) in items.into_iter().enumerate() {
+        if i % 2 == 0 {
+            left.update(item);
+        } else {
+            right.update(item);
+        }
+    }
+    left.merge(&right).unwrap();
+    assert!(!left.is_empty());
+    S::deserialize(&left.serialize()).unwrap()
+}
+
+#[test]
+fn test_generic_aggregation() {
+    let hll = aggregate(
+        HllSketch::new(12, HllType::Hll8),
+        HllSketch::new(12, HllType::Hll8),
+        0..1000u64,
+    );
+    assert!((hll.estimate() - 1000.0).abs() < 30.0);
+
+    let cpc = aggregate(CpcSketch::new(11), CpcSketch::new(11), 0..1000u64);
+    assert!((cpc.estimate() - 1000.0).abs() < 30.0);
+
+    let countmin = aggregate(
+        CountMinSketch::<u64>::new(3, 64),
+        CountMinSketch::<u64>::new(3, 64),
+        ["a", "b", "a", "c", "a"],
+    );
+    assert!(countmin.estimate("a") >= 3);
+    assert_eq!(countmin.total_weight(), 5);
+
+    let frequencies = aggregate(
+        FrequentItemsSketch::<i64>::new(8),
+        FrequentItemsSketch::<i64>::new(8),
+        [1, 2, 1, 1],
+    );
+    assert_eq!(frequencies.estimate(&1), 3);
+
+    let kll = aggregate(
+        KllSketch::<f64>::new(200),
+        KllSketch::<f64>::new(200),
+        (0..100).map(f64::from),
+    );
+    assert_eq!(kll.n(), 100);
+
+    let multi = aggregate(
+        MultiKll::<2>::new(200),
+        MultiKll::<2>::new(200),
+        [[1.0, 10.0], [2.0, 20.0]],
+    );
+    assert_eq!(multi.quantiles(1.0), [Some(2.0), Some(20.0)]);
+
+    let mut tdigest = aggregate(
+        TDigestMut::new(100),
+        TDigestMut::new(100),
+        (0..100).map(f64::from),
+    );
+    assert_eq!(tdigest.total_weight(), 100);
+    assert_eq!(tdigest.quantile(1.0), Some(99.0));
+
+    let bloom = aggregate(
+        BloomFilterBuilder::with_accuracy(100, 0.01).build(),
+        BloomFilterBuilder::with_accuracy(100, 0.01).build(),
+        ["apple", "banana"],
+    );
+    assert!(bloom.contains(&"apple") && bloom.contains(&"banana"));
+}
+
+#[test]
+fn test_theta_traits() {
+    let mut sketch = ThetaSketch::builder().build();
+    Sketch::update(&mut sketch, "apple");
+    let decoded = <ThetaSketch as SerializableSketch>::deserialize(&sketch.serialize()).unwrap();
+    assert_eq!(decoded.estimate(), 1.0);
+
+    let mut left = ThetaSketch::builder().lg_k(5).build();
+    let mut right = ThetaSketch::builder().lg_k(5).build();
+    for i in 0..1000 {
+        left.update(i);
+        right.update(i + 500);
+    }
+    let mut merged = left.compact(true);
+    merged.merge(&right.compact(false)).unwrap();
+
+    let theta = left.theta64().min(right.theta64());
+    let mut expected: Vec<u64> = left
+        .iter()
+        .chain(right.iter())
+        .filter(|&h| h < theta)
+        .collect();
+    expected.sort_unstable();
+    expected.dedup();
+    assert_eq!(merged.theta64(), theta);
+    assert!(merged.is_ordered());
+    assert_eq!(merged.iter().collect::<Vec<_>>(), expected);
+
+    // without the k bound, the estimate is at least as accurate as a union of size k
+    let mut union = ThetaUnion::builder().lg_k(5).build();
+    union.update(&left).unwrap();
+    union.update(&right).unwrap();
+    assert!(merged.num_retained() >= union.result().num_retained());
+
+    let mut empty = CompactThetaSketch::empty();
+    empty.merge(&merged).unwrap();
+    assert_eq!(empty.iter().collect::<Vec<_>>(), expected);
+    merged.merge(&CompactThetaSketch::empty()).unwrap();
+    assert_eq!(merged.iter().collect::<Vec<_>>(), expected);
+
+    let mut other_seed = ThetaSketch::builder().seed(123).build();
+    other_seed.update(1);
+    assert!(merged.merge(&other_seed.compact(true)).is_err());
+}
+
+#[test]
+fn test_merge_matches_union() {
+    let mut left = HllSketch::new(10, HllType::Hll4);
+    let mut right = HllSketch::new(12, HllType::Hll4);
+    for i in 0..5000 {
+        left.update(i);
+        right.update(i + 2500);
+    }
+    let mut union = HllUnion::new(10);
+    union.update(&left);
+    union.update(&right);
+    let mut merged = left.clone();
+    merged.merge(&right).unwrap();
+    assert_eq!(merged.target_type(), HllType::Hll4);
+    assert_eq!(merged.estimate(), union.estimate());
+
+    let mut left = CpcSketch::new(11);
+    let mut right = CpcSketch::new(11);
+    for i in 0..5000 {
+        left.update(i);
+        right.update(i + 2500);
+    }
+    let mut union = CpcUnion::new(11);
+    union.update(&left);
+    union.update(&right);
+    let mut merged = left.clone();
+    merged.merge(&right).unwrap();
+    assert_eq!(merged.estimate(), union.to_sketch().estimate());
+}
+
+#[test]
+fn test_incompatible_merges_are_errors() {
+    let mut left = CountMinSketch::<i64>::new(3, 64);
+    assert!(MergeableSketch::merge(&mut left, &CountMinSketch::new(3, 32)).is_err());
+    assert!(MergeableSketch::merge(&mut left, &CountMinSketch::with_seed(3, 64, 1)).is_err());
+
+    let mut left = BloomFilterBuilder::with_accuracy(100, 0.01).build();
+    let right = BloomFilterBuilder::with_accuracy(100, 0.01).seed(1).build();
+    assert!(MergeableSketch::merge(&mut left, &right).is_err());
+
+    let mut left = CpcSketch::new(11);
+    assert!(left.merge(&CpcSketch::with_seed(11, 1)).is_err());
+}
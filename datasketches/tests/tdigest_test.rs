@@ -15,6 +15,8 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::time::Duration;
+
 use datasketches::tdigest::TDigestMut;
 use googletest::assert_that;
 use googletest::prelude::eq;
@@ -237,3 +239,81 @@ fn test_estimate_repeat_values() {
     }
     assert_eq!(tdigest.quantile(0.9), Some(1.0));
 }
+
+#[test]
+fn test_to_cdf_points() {
+    let mut tdigest = TDigestMut::new(100);
+    assert!(tdigest.to_cdf_points(5).is_none());
+
+    for i in 0..10_000 {
+        tdigest.update(i as f64);
+    }
+    let points = tdigest.to_cdf_points(5).unwrap();
+    assert_eq!(points.len(), 5);
+    assert_that!(points[0].value, eq(0.0));
+    assert_that!(points[4].value, eq(9_999.0));
+    for (i, point) in points.iter().enumerate() {
+        // t-digest has no rank error guarantee; sequential input is among its harder cases
+        assert_that!(point.rank, near(i as f64 / 4.0, 0.02));
+        assert_that!(tdigest.rank(point.value), eq(Some(point.rank)));
+    }
+
+    let digest = tdigest.freeze();
+    assert_eq!(digest.to_cdf_points(5), Some(points));
+}
+
+#[test]
+fn test_durations() {
+    let mut tdigest = TDigestMut::new(100);
+    assert_eq!(tdigest.quantile_duration(0.5), None);
+
+    for ms in 0..=1000 {
+        tdigest.update_duration(Duration::from_millis(ms));
+    }
+    assert_eq!(tdigest.quantile_duration(0.0), Some(Duration::ZERO));
+    assert_eq!(tdigest.quantile_duration(1.0), Some(Duration::from_secs(1)));
+    assert_that!(tdigest.quantile(0.5).unwrap(), near(0.5, 0.01));
+
+    let digest = tdigest.clone().freeze();
+    assert_eq!(
+        digest.quantile_duration(0.9),
+        tdigest.quantile_duration(0.9)
+    );
+
+    // values no duration can hold saturate
+    tdigest.update(-1.0);
+    assert_eq!(tdigest.quantile_duration(0.0), Some(Duration::ZERO));
+    tdigest.update(1e30);
+    assert_eq!(tdigest.quantile_duration(1.0), Some(Duration::MAX));
+}
+
+#[test]
+fn test_memory_usage() {
+    let mut digest = TDigestMut::new(100);
+    for i in 0..10_000 {
+        digest.update(i as f64);
+    }
+    assert!(digest.memory_usage_bytes() > size_of::<TDigestMut>());
+    let frozen = digest.freeze();
+    assert!(frozen.memory_usage_bytes() > size_of_val(&frozen));
+}
+
+#[test]
+fn test_snapshot_of_live_sketch() {
+    let mut digest = TDigestMut::new(100);
+    for i in 0..1_000 {
+        digest.update(i as f64);
+    }
+    let snapshot = digest.snapshot();
+    assert_eq!(snapshot.total_weight(), 1_000);
+    assert_eq!(snapshot.quantile(0.5), digest.quantile(0.5));
+
+    // the live sketch keeps being updated without affecting the snapshot
+    for i in 1_000..2_000 {
+        digest.update(i as f64);
+    }
+    assert_eq!(digest.total_weight(), 2_000);
+    assert_eq!(snapshot.total_weight(), 1_000);
+    assert_eq!(snapshot.max_value(), Some(999.0));
+    assert_eq!(digest.freeze().max_value(), Some(1_999.0));
+}
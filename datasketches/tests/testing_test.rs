@@ -0,0 +1,107 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+#![cfg(feature = "testing")]
+
+use datasketches::bloom::BloomFilter;
+use datasketches::countmin::CountMinSketch;
+use datasketches::cpc::CpcSketch;
+use datasketches::frequencies::FrequentItemsSketch;
+use datasketches::hll::HllSketch;
+use datasketches::kll::KllSketch;
+use datasketches::tdigest::TDigestMut;
+use datasketches::testing::SketchGenerator;
+use datasketches::theta::CompactThetaSketch;
+
+#[test]
+fn test_generator_is_reproducible() {
+    let images = |seed: u64| {
+        let mut generator = SketchGenerator::new(seed).max_items(2_000);
+        vec![
+            generator.theta().compact(true).serialize(),
+            generator.hll().serialize(),
+            generator.cpc().serialize(),
+            generator.kll().serialize(),
+            generator.tdigest().serialize(),
+            generator.frequent_items().serialize(),
+            generator.count_min().serialize(),
+            generator.bloom_filter().serialize(),
+        ]
+    };
+    assert_eq!(images(1), images(1));
+    assert_ne!(images(1), images(2));
+}
+
+#[test]
+fn test_generated_sketches_round_trip() {
+    let mut generator = SketchGenerator::new(3).max_items(5_000);
+    let mut num_empty = 0;
+    for _ in 0..20 {
+        let theta = generator.theta().compact(true);
+        num_empty += theta.is_empty() as usize;
+        let bytes = theta.serialize();
+        assert_eq!(
+            CompactThetaSketch::deserialize(&bytes).unwrap().serialize(),
+            bytes
+        );
+
+        let hll = generator.hll();
+        let restored = HllSketch::deserialize(&hll.serialize()).unwrap();
+        assert_eq!(restored.estimate(), hll.estimate());
+
+        let cpc = generator.cpc();
+        let restored = CpcSketch::deserialize(&cpc.serialize()).unwrap();
+        assert_eq!(restored.estimate(), cpc.estimate());
+
+        let kll = generator.kll();
+        assert!(kll.k() >= 8 && kll.k() <= 400);
+        let bytes = kll.serialize();
+        assert_eq!(
+            KllSketch::<f64>::deserialize(&bytes).unwrap().serialize(),
+            bytes
+        );
+
+        let mut tdigest = generator.tdigest();
+        let bytes = tdigest.serialize();
+        assert_eq!(
+            TDigestMut::deserialize(&bytes, false).unwrap().serialize(),
+            bytes
+        );
+
+        let frequent = generator.frequent_items();
+        let restored = FrequentItemsSketch::<i64>::deserialize(&frequent.serialize()).unwrap();
+        assert_eq!(restored.total_weight(), frequent.total_weight());
+
+        let count_min = generator.count_min();
+        let bytes = count_min.serialize();
+        assert_eq!(
+            CountMinSketch::<u64>::deserialize(&bytes)
+                .unwrap()
+                .serialize(),
+            bytes
+        );
+
+        let filter = generator.bloom_filter();
+        let bytes = filter.serialize();
+        assert_eq!(BloomFilter::deserialize(&bytes).unwrap().serialize(), bytes);
+    }
+    assert!(num_empty < 20);
+
+    let mut empty = SketchGenerator::new(3).max_items(0);
+    assert!(empty.theta().is_empty());
+    assert!(empty.kll().is_empty());
+}
@@ -0,0 +1,126 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use datasketches::error::ErrorKind;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaANotB;
+use datasketches::theta::ThetaSketch;
+
+fn sketch_with_range(start: u64, count: u64) -> ThetaSketch {
+    let mut sketch = ThetaSketch::builder().build();
+    for i in 0..count {
+        sketch.update(start + i);
+    }
+    sketch
+}
+
+#[test]
+fn test_empty_inputs() {
+    let a_not_b = ThetaANotB::new_with_default_seed();
+    let empty = ThetaSketch::builder().build();
+    let a = sketch_with_range(0, 10);
+
+    let result = a_not_b.compute(&empty, &a, true).unwrap();
+    assert!(result.is_empty());
+    assert_eq!(result.estimate(), 0.0);
+
+    let result = a_not_b.compute(&a, &empty, true).unwrap();
+    assert!(!result.is_empty());
+    assert!(result.is_ordered());
+    assert_eq!(result.estimate(), 10.0);
+
+    let result = a_not_b.compute(&a, &a, true).unwrap();
+    assert!(result.is_empty());
+    assert_eq!(result.estimate(), 0.0);
+}
+
+#[test]
+fn test_exact_half_overlap() {
+    let a = sketch_with_range(0, 1_000);
+    let b = sketch_with_range(500, 1_000);
+    let a_not_b = ThetaANotB::new_with_default_seed();
+
+    for (ordered_inputs, ordered) in [(false, false), (false, true), (true, false), (true, true)] {
+        let result = if ordered_inputs {
+            a_not_b.compute(&a.compact(true), &b.compact(true), ordered)
+        } else {
+            a_not_b.compute(&a, &b, ordered)
+        }
+        .unwrap();
+        assert!(!result.is_empty());
+        assert_eq!(result.estimate(), 500.0);
+        assert_eq!(result.is_ordered(), ordered || ordered_inputs);
+        if result.is_ordered() {
+            let entries: Vec<u64> = result.iter().collect();
+            assert!(entries.is_sorted());
+        }
+    }
+}
+
+#[test]
+fn test_estimation_mode() {
+    let a = sketch_with_range(0, 10_000);
+    let b = sketch_with_range(5_000, 20_000);
+    let a_not_b = ThetaANotB::new_with_default_seed();
+
+    let unordered = a_not_b.compute(&a, &b, false).unwrap();
+    let ordered = a_not_b
+        .compute(&a.compact(true), &b.compact(true), true)
+        .unwrap();
+    assert_eq!(unordered.theta64(), a.theta64().min(b.theta64()));
+    assert_eq!(ordered.theta64(), unordered.theta64());
+    let mut expected: Vec<u64> = unordered.iter().collect();
+    expected.sort_unstable();
+    assert_eq!(ordered.iter().collect::<Vec<_>>(), expected);
+    assert!((unordered.estimate() - 5_000.0).abs() < 5_000.0 * 0.05);
+}
+
+#[test]
+fn test_b_without_retained_entries() {
+    let a = sketch_with_range(0, 100);
+    let b = {
+        let mut sketch = ThetaSketch::builder().sampling_probability(0.001).build();
+        sketch.update("x");
+        sketch
+    };
+    assert_eq!(b.num_retained(), 0);
+
+    let result = ThetaANotB::new_with_default_seed()
+        .compute(&a, &b, true)
+        .unwrap();
+    assert_eq!(result.theta64(), b.theta64());
+    assert_eq!(
+        result.num_retained(),
+        a.iter().filter(|&hash| hash < b.theta64()).count()
+    );
+}
+
+#[test]
+fn test_seed_mismatch() {
+    let a = sketch_with_range(0, 10);
+    let mut b = ThetaSketch::builder().seed(123).build();
+    let a_not_b = ThetaANotB::new_with_default_seed();
+    assert!(a_not_b.compute(&a, &b, true).is_ok());
+
+    b.update("x");
+    let err = a_not_b.compute(&a, &b, true).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SeedHashMismatch);
+    assert!(a_not_b.compute(&b, &a, true).is_err());
+    assert!(ThetaANotB::new(123).compute(&b, &b, true).is_ok());
+
+    let empty = CompactThetaSketch::empty();
+    assert!(a_not_b.compute(&empty, &b, true).is_err());
+}
@@ -0,0 +1,131 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::cpc::CpcSketch;
+use datasketches::error::ErrorKind;
+use datasketches::hash::DEFAULT_UPDATE_SEED;
+use datasketches::hll::HllSketch;
+use datasketches::hll::HllType;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+use datasketches::theta::ThetaUnion;
+use datasketches::theta::cpc_to_theta;
+use datasketches::theta::hll_to_theta;
+
+fn hll(range: std::ops::Range<u64>) -> HllSketch {
+    let mut sketch = HllSketch::new(12, HllType::Hll8);
+    for i in range {
+        sketch.update(i);
+    }
+    sketch
+}
+
+fn cpc(range: std::ops::Range<u64>) -> CpcSketch {
+    let mut sketch = CpcSketch::new(11);
+    for i in range {
+        sketch.update(i);
+    }
+    sketch
+}
+
+#[test]
+fn test_empty_sources() {
+    let theta = hll_to_theta(&HllSketch::new(12, HllType::Hll4), 12, DEFAULT_UPDATE_SEED).unwrap();
+    assert!(theta.is_empty());
+    let theta = cpc_to_theta(&CpcSketch::new(11), 12, DEFAULT_UPDATE_SEED).unwrap();
+    assert!(theta.is_empty());
+    assert_eq!(theta.estimate(), 0.0);
+}
+
+#[test]
+fn test_estimates_carry_over() {
+    for n in [1, 100, 3_000, 200_000] {
+        let source = hll(0..n);
+        let theta = hll_to_theta(&source, 12, DEFAULT_UPDATE_SEED).unwrap();
+        assert!(!theta.is_empty());
+        assert!(theta.num_retained() <= 4096);
+        assert_eq!(theta.is_estimation_mode(), source.estimate() > 4096.0);
+        let error = (theta.estimate() - source.estimate()).abs();
+        assert!(
+            error < 1.0,
+            "n={n}: {} vs {}",
+            theta.estimate(),
+            source.estimate()
+        );
+
+        let source = cpc(0..n);
+        let theta = cpc_to_theta(&source, 12, DEFAULT_UPDATE_SEED).unwrap();
+        let error = (theta.estimate() - source.estimate()).abs();
+        assert!(
+            error < 1.0,
+            "n={n}: {} vs {}",
+            theta.estimate(),
+            source.estimate()
+        );
+    }
+}
+
+#[test]
+fn test_conversion_is_deterministic_and_round_trips() {
+    let source = hll(0..10_000);
+    let a = hll_to_theta(&source, 10, DEFAULT_UPDATE_SEED).unwrap();
+    let b = hll_to_theta(&source, 10, DEFAULT_UPDATE_SEED).unwrap();
+    assert_eq!(a.serialize(), b.serialize());
+    assert!(a.is_ordered());
+
+    let decoded = CompactThetaSketch::deserialize(&a.serialize()).unwrap();
+    assert_eq!(decoded.estimate(), a.estimate());
+
+    // other sources draw other entries
+    let other = hll_to_theta(&hll(10_000..20_000), 10, DEFAULT_UPDATE_SEED).unwrap();
+    assert!(a.iter().all(|hash| !other.iter().any(|h| h == hash)));
+}
+
+#[test]
+fn test_union_of_disjoint_partitions() {
+    let mut native = ThetaSketch::builder().build();
+    for i in 0..40_000u64 {
+        native.update(i);
+    }
+    let from_hll = hll_to_theta(&hll(40_000..80_000), 12, DEFAULT_UPDATE_SEED).unwrap();
+    let from_cpc = cpc_to_theta(&cpc(80_000..120_000), 12, DEFAULT_UPDATE_SEED).unwrap();
+
+    let mut union = ThetaUnion::builder().build();
+    union.update(&native).unwrap();
+    union.update(&from_hll).unwrap();
+    union.update(&from_cpc).unwrap();
+    let estimate = union.result().estimate();
+    assert!(
+        (estimate - 120_000.0).abs() < 120_000.0 * 0.05,
+        "{estimate}"
+    );
+}
+
+#[test]
+fn test_seed_and_lg_k_are_validated() {
+    let source = hll(0..10);
+    let theta = hll_to_theta(&source, 12, 42).unwrap();
+    assert!(CompactThetaSketch::deserialize(&theta.serialize()).is_err());
+    assert!(CompactThetaSketch::deserialize_with_seed(&theta.serialize(), 42).is_ok());
+
+    for lg_k in [4, 27] {
+        let err = hll_to_theta(&source, lg_k, DEFAULT_UPDATE_SEED).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+        let err = cpc_to_theta(&cpc(0..10), lg_k, DEFAULT_UPDATE_SEED).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    }
+}
@@ -15,6 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use datasketches::error::ErrorKind;
 use datasketches::theta::CompactThetaSketch;
 use datasketches::theta::ThetaIntersection;
 use datasketches::theta::ThetaSketch;
@@ -98,6 +99,35 @@ fn test_seed_mismatch_behaviour() {
     assert!(i.update(&one_other_seed).is_err());
 }
 
+#[test]
+fn test_seed_mismatch_rejected_in_terminal_empty_state() {
+    let empty = ThetaSketch::builder().seed(1).build();
+    let mut one_other_seed = ThetaSketch::builder().seed(2).build();
+    one_other_seed.update("value");
+
+    let mut i = ThetaIntersection::new(1);
+    i.update(&empty).unwrap();
+    let err = i.update(&one_other_seed).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SeedHashMismatch);
+    assert!(i.result().is_empty());
+}
+
+#[test]
+fn test_seed_mismatch_from_deserialized_sketch() {
+    let mut sketch = ThetaSketch::builder().seed(2).build();
+    sketch.update("value");
+    let bytes = sketch.compact(true).serialize();
+    let compact = CompactThetaSketch::deserialize_with_seed(&bytes, 2).unwrap();
+
+    let mut i = ThetaIntersection::new_with_default_seed();
+    assert!(i.update(&compact).is_err());
+    assert!(!i.has_result());
+
+    let mut i = ThetaIntersection::new(2);
+    i.update(&compact).unwrap();
+    assert_eq!(i.result().estimate(), 1.0);
+}
+
 #[test]
 fn test_terminal_empty_state_ignores_future_updates() {
     let empty = ThetaSketch::builder().build();
@@ -314,3 +344,33 @@ fn test_seed_mismatch_non_empty_returns_error() {
     let mut i = ThetaIntersection::new(123);
     assert!(i.update(&s).is_err());
 }
+
+#[test]
+fn test_intersect_all() {
+    let sketches: Vec<CompactThetaSketch> = (0..4)
+        .map(|p| sketch_with_range(p * 100, 1_000).compact(true))
+        .collect();
+    let mut i = ThetaIntersection::new_with_default_seed();
+    i.intersect_all(&sketches).unwrap();
+    assert_eq!(i.result().estimate(), 700.0);
+}
+
+#[test]
+fn test_intersect_all_stops_once_nothing_is_retained() {
+    let a = sketch_with_range(0, 1_000);
+    let b = sketch_with_range(1_000, 1_000);
+    let mismatched = {
+        let mut sketch = ThetaSketch::builder().seed(123).build();
+        sketch.update("x");
+        sketch
+    };
+
+    let mut i = ThetaIntersection::new_with_default_seed();
+    i.intersect_all([&a, &b, &mismatched]).unwrap();
+    assert!(i.has_result());
+    assert_eq!(i.result().num_retained(), 0);
+    assert_eq!(i.result().estimate(), 0.0);
+
+    let mut i = ThetaIntersection::new_with_default_seed();
+    assert!(i.intersect_all([&a, &mismatched, &b]).is_err());
+}
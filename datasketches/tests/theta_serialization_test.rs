@@ -18,10 +18,19 @@
 mod common;
 
 use std::fs;
+use std::io;
+use std::io::Read;
 use std::path::PathBuf;
 
-use common::serialization_test_data;
+use common::CORPUS_NS;
+use common::Language;
+use common::corpus_file;
+use common::corpus_files;
+use datasketches::common::ResizeFactor;
+use datasketches::error::ErrorKind;
+use datasketches::theta::CompactFormat;
 use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
 use googletest::assert_that;
 use googletest::prelude::near;
 
@@ -68,49 +77,370 @@ fn test_sketch_file(path: PathBuf, expected_cardinality: usize, use_compressed_r
         "Estimates differ after round-trip for {}",
         path.display()
     );
+
+    // Streaming reads stop at the end of the image.
+    let mut stream = bytes.clone();
+    stream.extend_from_slice(b"trailer");
+    let mut reader = stream.as_slice();
+    let streamed = CompactThetaSketch::deserialize_from_reader(&mut reader).unwrap();
+    assert_eq!(reader, b"trailer", "{}", path.display());
+    assert_eq!(streamed.estimate(), estimate1, "{}", path.display());
 }
 
 #[test]
 fn test_java_compatibility() {
-    let test_cases = [0, 1, 10, 100, 1000, 10_000, 100_000, 1_000_000];
-
-    for n in test_cases {
-        let filename = format!("theta_n{}_java.sk", n);
-        let path = serialization_test_data("java_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Java, "theta", &CORPUS_NS) {
         test_sketch_file(path, n, false);
     }
 
-    let compressed_test_cases = [10, 100, 1000, 10_000, 100_000, 1_000_000];
-
-    for n in compressed_test_cases {
-        let filename = format!("theta_compressed_n{}_java.sk", n);
-        let path = serialization_test_data("java_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Java, "theta_compressed", &CORPUS_NS[2..]) {
         test_sketch_file(path, n, true);
     }
 
-    let path =
-        serialization_test_data("java_generated_files", "theta_non_empty_no_entries_java.sk");
+    let path = corpus_file(Language::Java, "theta_non_empty_no_entries");
     test_sketch_file(path, 0, false);
 }
 
 #[test]
-fn test_cpp_compatibility() {
-    let test_cases = [0, 1, 10, 100, 1000, 10_000, 100_000, 1_000_000];
+fn test_java_update_sketch_compatibility() {
+    for (n, path) in corpus_files(Language::Java, "theta_update", &CORPUS_NS) {
+        let bytes = fs::read(&path).unwrap();
+        let mut sketch = ThetaSketch::deserialize(&bytes).unwrap();
+        let expected = n as f64;
+        assert_that!(sketch.estimate(), near(expected, expected * 0.03));
 
-    for n in test_cases {
-        let filename = format!("theta_n{}_cpp.sk", n);
-        let path = serialization_test_data("cpp_generated_files", &filename);
-        test_sketch_file(path, n, false);
+        // the restored hash table must find every retained hash where Java put it, so that
+        // replaying the stream only sees duplicates
+        let (estimate, num_retained) = (sketch.estimate(), sketch.num_retained());
+        for i in 0..n as i64 {
+            sketch.update_input(i);
+        }
+        assert_eq!(sketch.estimate(), estimate, "{}", path.display());
+        assert_eq!(sketch.num_retained(), num_retained, "{}", path.display());
+
+        let restored = ThetaSketch::deserialize(&sketch.serialize()).unwrap();
+        assert_eq!(restored.estimate(), estimate, "{}", path.display());
     }
+}
 
-    let compressed_test_cases = [10, 100, 1000, 10_000, 100_000, 1_000_000];
+#[test]
+fn test_cpp_compatibility() {
+    for (n, path) in corpus_files(Language::Cpp, "theta", &CORPUS_NS) {
+        test_sketch_file(path, n, false);
+    }
 
-    for n in compressed_test_cases {
-        let filename = format!("theta_compressed_n{}_cpp.sk", n);
-        let path = serialization_test_data("cpp_generated_files", &filename);
+    for (n, path) in corpus_files(Language::Cpp, "theta_compressed", &CORPUS_NS[2..]) {
         test_sketch_file(path, n, true);
     }
 
-    let path = serialization_test_data("cpp_generated_files", "theta_non_empty_no_entries_cpp.sk");
+    let path = corpus_file(Language::Cpp, "theta_non_empty_no_entries");
     test_sketch_file(path, 0, false);
 }
+
+#[test]
+fn test_update_sketch_round_trip() {
+    for resize_factor in [ResizeFactor::X1, ResizeFactor::X2, ResizeFactor::X8] {
+        for p in [1.0, 0.5] {
+            for n in [0, 1, 100, 1000, 20_000] {
+                let mut sketch = ThetaSketch::builder()
+                    .lg_k(9)
+                    .resize_factor(resize_factor)
+                    .sampling_probability(p)
+                    .build();
+                for i in 0..n {
+                    sketch.update(i);
+                }
+                let bytes = sketch.serialize();
+                let mut decoded = ThetaSketch::deserialize(&bytes).unwrap();
+                assert_eq!(decoded.serialize(), bytes);
+                assert_eq!(decoded.lg_k(), 9);
+                assert_eq!(decoded.resize_factor(), resize_factor);
+                assert_eq!(decoded.sampling_probability(), p);
+                assert_eq!(decoded.is_empty(), sketch.is_empty());
+                assert_eq!(decoded.theta64(), sketch.theta64());
+                assert_eq!(decoded.estimate(), sketch.estimate());
+
+                // The restored sketch keeps growing exactly like the original.
+                for i in n..n + 5000 {
+                    sketch.update(i);
+                    decoded.update(i);
+                }
+                assert_eq!(decoded.serialize(), sketch.serialize());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_update_sketch_java_layout() {
+    // Default Java UpdateSketch: nominal entries 4096, resize factor X8, p = 1.
+    let sketch = ThetaSketch::builder().build();
+    let seed_hash = sketch.seed_hash().to_le_bytes();
+    let bytes = sketch.serialize();
+    let mut expected = vec![
+        0xC3, // preamble longs 3, resize factor X8 in the top two bits
+        3,    // serial version
+        2,    // family: QuickSelect
+        12,   // lg nominal entries
+        7,    // lg current table size: starting sub-multiple of lg_k + 1 for X8
+        4,    // flags: empty
+        seed_hash[0],
+        seed_hash[1],
+    ];
+    expected.extend_from_slice(&0u32.to_le_bytes()); // retained entries
+    expected.extend_from_slice(&1.0f32.to_le_bytes()); // p
+    expected.extend_from_slice(&(i64::MAX as u64).to_le_bytes()); // theta
+    expected.extend_from_slice(&[0; 128 * 8]); // empty hash table
+    assert_eq!(bytes, expected);
+
+    // A table laid out by Java's probing is accepted as-is.
+    let hash = 0x1234_5678u64;
+    let mut image = expected.clone();
+    image[5] = 0;
+    image[8..12].copy_from_slice(&1u32.to_le_bytes());
+    let slot = 24 + (hash & 127) as usize * 8;
+    image[slot..slot + 8].copy_from_slice(&hash.to_le_bytes());
+    let decoded = ThetaSketch::deserialize(&image).unwrap();
+    assert_eq!(decoded.iter().collect::<Vec<_>>(), vec![hash]);
+    assert_eq!(decoded.serialize(), image);
+}
+
+#[test]
+fn test_update_sketch_deserialize_errors() {
+    let mut sketch = ThetaSketch::builder().lg_k(5).seed(7).build();
+    for i in 0..10 {
+        sketch.update(i);
+    }
+    let bytes = sketch.serialize();
+
+    let err = ThetaSketch::deserialize(&bytes).unwrap_err();
+    assert!(err.message().contains("incompatible seed hash"));
+    assert!(ThetaSketch::deserialize_with_seed(&bytes, 7).is_ok());
+
+    let err = ThetaSketch::deserialize_with_seed(&bytes[..bytes.len() - 1], 7).unwrap_err();
+    assert!(err.message().contains("entries"));
+
+    let compact = sketch.compact(true).serialize();
+    let err = ThetaSketch::deserialize_with_seed(&compact, 7).unwrap_err();
+    assert!(err.message().contains("CompactThetaSketch::deserialize"));
+
+    // A retained count that disagrees with the table is rejected.
+    let mut corrupted = bytes.clone();
+    corrupted[8] += 1;
+    let err = ThetaSketch::deserialize_with_seed(&corrupted, 7).unwrap_err();
+    assert!(err.message().contains("retained entries"));
+
+    // A hash moved away from its probe sequence is rejected.
+    let hashes: Vec<u64> = sketch.iter().collect();
+    let mut corrupted = bytes.clone();
+    let slots = &mut corrupted[24..];
+    let occupied = slots
+        .chunks_exact(8)
+        .position(|slot| slot != [0; 8])
+        .unwrap();
+    let free = (0..slots.len() / 8)
+        .find(|&i| slots[i * 8..i * 8 + 8] == [0; 8] && i != occupied)
+        .unwrap();
+    let moved: [u8; 8] = slots[occupied * 8..occupied * 8 + 8].try_into().unwrap();
+    slots[occupied * 8..occupied * 8 + 8].fill(0);
+    slots[free * 8..free * 8 + 8].copy_from_slice(&moved);
+    assert!(hashes.contains(&u64::from_le_bytes(moved)));
+    let err = ThetaSketch::deserialize_with_seed(&corrupted, 7).unwrap_err();
+    assert!(err.message().contains("invalid retained hash"));
+}
+
+fn legacy_image(serial_version: u8, pre_longs: u8, entries: &[u64], theta: u64) -> Vec<u8> {
+    let seed_hash = ThetaSketch::builder().build().seed_hash();
+    let mut bytes = vec![pre_longs, serial_version, 3, 0];
+    if serial_version == 1 {
+        bytes.extend_from_slice(&[0; 4]);
+    } else {
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(&seed_hash.to_le_bytes());
+    }
+    if pre_longs > 1 {
+        bytes.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+    }
+    if pre_longs > 2 {
+        bytes.extend_from_slice(&theta.to_le_bytes());
+    }
+    for entry in entries {
+        bytes.extend_from_slice(&entry.to_le_bytes());
+    }
+    bytes
+}
+
+#[test]
+fn test_serial_version_2_precise_image() {
+    let mut sketch = ThetaSketch::builder().build();
+    for i in 0..3 {
+        sketch.update(i);
+    }
+    let compact = sketch.compact(true);
+    let entries: Vec<u64> = compact.iter().collect();
+
+    // datasketches-java writes exact-mode v2 sketches with two preamble longs and no theta
+    let restored = CompactThetaSketch::deserialize(&legacy_image(2, 2, &entries, 0)).unwrap();
+    assert!(!restored.is_empty());
+    assert_eq!(restored.num_retained(), 3);
+    assert_eq!(restored.estimate(), 3.0);
+    assert_eq!(restored.serialize(), compact.serialize());
+
+    let restored = CompactThetaSketch::deserialize(&legacy_image(2, 2, &[], 0)).unwrap();
+    assert!(restored.is_empty());
+
+    let mut flagged_empty = legacy_image(2, 2, &entries, 0);
+    flagged_empty[5] = 4;
+    let err = CompactThetaSketch::deserialize(&flagged_empty).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_stream_round_trip() {
+    let mut images = vec![];
+    for n in [0, 1, 10, 100_000] {
+        let mut sketch = ThetaSketch::builder().lg_k(12).build();
+        for i in 0..n {
+            sketch.update(i);
+        }
+        let mut update_bytes = Vec::new();
+        sketch.serialize_into_writer(&mut update_bytes).unwrap();
+        assert_eq!(update_bytes, sketch.serialize());
+        images.push((true, update_bytes));
+
+        for compact in [sketch.compact(true), sketch.compact(false)] {
+            let mut compact_bytes = Vec::new();
+            compact.serialize_into_writer(&mut compact_bytes).unwrap();
+            assert_eq!(compact_bytes, compact.serialize());
+            images.push((false, compact_bytes));
+            images.push((false, compact.serialize_compressed()));
+        }
+    }
+    // serial versions 1 and 2 of datasketches-java
+    let max_theta = i64::MAX as u64;
+    images.push((false, legacy_image(1, 3, &[], max_theta)));
+    images.push((false, legacy_image(1, 3, &[5, 7], 1 << 40)));
+    images.push((false, legacy_image(2, 1, &[], max_theta)));
+    images.push((false, legacy_image(2, 2, &[5, 7, 9], max_theta)));
+    images.push((false, legacy_image(2, 3, &[5, 7], 1 << 40)));
+
+    let stream: Vec<u8> = images.iter().flat_map(|(_, bytes)| bytes.clone()).collect();
+    let mut reader = stream.as_slice();
+    for (updatable, bytes) in &images {
+        let before = reader.len();
+        let estimate = if *updatable {
+            ThetaSketch::deserialize_from_reader(&mut reader)
+                .unwrap()
+                .estimate()
+        } else {
+            CompactThetaSketch::deserialize_from_reader(&mut reader)
+                .unwrap()
+                .estimate()
+        };
+        assert_eq!(before - reader.len(), bytes.len());
+        let expected = if *updatable {
+            ThetaSketch::deserialize(bytes).unwrap().estimate()
+        } else {
+            CompactThetaSketch::deserialize(bytes).unwrap().estimate()
+        };
+        assert_eq!(estimate, expected);
+    }
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn test_stream_truncated() {
+    let mut sketch = ThetaSketch::builder().lg_k(5).build();
+    for i in 0..100 {
+        sketch.update(i);
+    }
+    let update_bytes = sketch.serialize();
+    for len in 0..update_bytes.len() {
+        assert!(ThetaSketch::deserialize_from_reader(&mut &update_bytes[..len]).is_err());
+    }
+    for compact_bytes in [
+        sketch.compact(true).serialize(),
+        sketch.compact(true).serialize_compressed(),
+    ] {
+        for len in 0..compact_bytes.len() {
+            let mut reader = &compact_bytes[..len];
+            assert!(CompactThetaSketch::deserialize_from_reader(&mut reader).is_err());
+        }
+    }
+}
+
+struct FailingIo;
+
+impl Read for FailingIo {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other("connection reset"))
+    }
+}
+
+impl io::Write for FailingIo {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("connection reset"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_stream_io_errors() {
+    let err = CompactThetaSketch::deserialize_from_reader(&mut FailingIo).unwrap_err();
+    assert!(err.message().contains("connection reset"), "{err}");
+
+    let mut sketch = ThetaSketch::builder().build();
+    sketch.update(1);
+    let err = sketch.serialize_into_writer(&mut FailingIo).unwrap_err();
+    assert_eq!(err.to_string(), "connection reset");
+    let err = sketch
+        .compact(true)
+        .serialize_into_writer(&mut FailingIo)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "connection reset");
+}
+
+#[test]
+fn test_serialize_compact_formats() {
+    let mut fast = ThetaSketch::builder().lg_k(10).build();
+    let mut small = ThetaSketch::builder()
+        .lg_k(10)
+        .compact_format(CompactFormat::Compressed)
+        .build();
+    assert_eq!(fast.compact_format(), CompactFormat::Uncompressed);
+    assert_eq!(small.compact_format(), CompactFormat::Compressed);
+
+    // both fall back to the uncompressed layout while a sketch cannot be compressed
+    assert_eq!(fast.serialize_compact(), small.serialize_compact());
+
+    for i in 0..10_000 {
+        fast.update(i);
+        small.update(i);
+    }
+    let fast_bytes = fast.serialize_compact();
+    let small_bytes = small.serialize_compact();
+    assert_eq!(fast_bytes[1], 3);
+    assert_eq!(small_bytes[1], 4);
+    assert!(small_bytes.len() < fast_bytes.len());
+
+    let from_fast = CompactThetaSketch::deserialize(&fast_bytes).unwrap();
+    let from_small = CompactThetaSketch::deserialize(&small_bytes).unwrap();
+    assert!(!from_fast.is_ordered());
+    assert!(from_small.is_ordered());
+    assert_eq!(from_fast.estimate(), fast.estimate());
+    assert!(from_fast.into_ordered().iter().eq(from_small.iter()));
+
+    let compact = small.compact(true);
+    assert_eq!(
+        compact.serialize_with(CompactFormat::Uncompressed),
+        compact.serialize()
+    );
+    assert_eq!(
+        compact.serialize_with(CompactFormat::Compressed),
+        small_bytes
+    );
+}
@@ -0,0 +1,151 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::error::ErrorKind;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::SetExpression;
+use datasketches::theta::ThetaANotB;
+use datasketches::theta::ThetaIntersection;
+use datasketches::theta::ThetaSketch;
+use datasketches::theta::ThetaUnion;
+
+fn segment(range: std::ops::Range<u64>) -> CompactThetaSketch {
+    segment_with_seed(range, 9001)
+}
+
+fn segment_with_seed(range: std::ops::Range<u64>, seed: u64) -> CompactThetaSketch {
+    let mut sketch = ThetaSketch::builder().lg_k(10).seed(seed).build();
+    for i in range {
+        sketch.update(i);
+    }
+    sketch.compact(true)
+}
+
+#[test]
+fn test_matches_operators_in_estimation_mode() {
+    let a = segment(0..20_000);
+    let b = segment(10_000..30_000);
+    let c = segment(15_000..40_000);
+    let d = segment(18_000..19_000);
+
+    let mut union = ThetaUnion::builder().build();
+    union.update(&a).unwrap();
+    union.update(&b).unwrap();
+    let mut intersection = ThetaIntersection::new_with_default_seed();
+    intersection.update(&c).unwrap();
+    intersection.update(&union.result()).unwrap();
+    let expected = ThetaANotB::new_with_default_seed()
+        .compute(&intersection.result(), &d, true)
+        .unwrap();
+
+    let result = SetExpression::of(&a)
+        .union(&b)
+        .intersect(&c)
+        .a_not_b(&d)
+        .evaluate()
+        .unwrap();
+    assert_eq!(result.estimate(), expected.estimate());
+    assert_eq!(result.theta(), expected.theta());
+    assert!(result.is_ordered());
+}
+
+#[test]
+fn test_nested_expressions() {
+    let a = segment(0..100);
+    let b = segment(50..150);
+    let c = segment(100..200);
+    let d = segment(0..1000);
+
+    // d \ (a ∪ (b ∩ c))
+    let inner = SetExpression::of(&b).intersect(&c);
+    let estimate = SetExpression::of(&d)
+        .a_not_b(SetExpression::of(&a).union(inner))
+        .estimate()
+        .unwrap();
+    assert_eq!(estimate, 850.0);
+
+    // unions and intersections of expressions are flattened
+    let estimate = SetExpression::of(&a)
+        .intersect(SetExpression::of(&b).intersect(&d))
+        .union(SetExpression::of(&c).union(&a))
+        .estimate()
+        .unwrap();
+    assert_eq!(estimate, 200.0);
+}
+
+#[test]
+fn test_exhausted_intersection_skips_remaining_operands() {
+    let a = segment(0..100);
+    let b = segment(1000..1100);
+    let c = segment(0..2000);
+    let foreign = segment_with_seed(0..5000, 42);
+
+    // the foreign sketch is the largest operand and is never read
+    let result = SetExpression::of(&foreign)
+        .intersect(&c)
+        .intersect(&a)
+        .intersect(&b)
+        .evaluate()
+        .unwrap();
+    assert_eq!(result.estimate(), 0.0);
+
+    // but is reported when it must be read
+    let err = SetExpression::of(&foreign)
+        .intersect(&a)
+        .evaluate()
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SeedHashMismatch);
+}
+
+#[test]
+fn test_difference_of_empty_skips_right_operand() {
+    let empty = CompactThetaSketch::empty();
+    let a = segment(0..100);
+    let foreign = segment_with_seed(0..100, 42);
+
+    let result = SetExpression::of(&empty)
+        .a_not_b(SetExpression::of(&a).union(&foreign))
+        .evaluate()
+        .unwrap();
+    assert!(result.is_empty());
+
+    let err = SetExpression::of(&a)
+        .a_not_b(&foreign)
+        .evaluate()
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SeedHashMismatch);
+}
+
+#[test]
+fn test_evaluate_with_union_config() {
+    let a = segment_with_seed(0..100, 42);
+    let b = segment_with_seed(50..150, 42);
+    let union = ThetaUnion::builder().seed(42).lg_k(5);
+
+    let result = SetExpression::of(&a)
+        .union(&b)
+        .evaluate_with(&union)
+        .unwrap();
+    assert!(result.num_retained() <= 64);
+    assert!(result.theta() < 1.0);
+
+    let result = SetExpression::of(&a)
+        .intersect(&b)
+        .evaluate_with(&union)
+        .unwrap();
+    assert_eq!(result.estimate(), 50.0);
+}
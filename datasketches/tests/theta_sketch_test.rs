@@ -15,8 +15,15 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use datasketches::common::CardinalityFamily;
 use datasketches::common::NumStdDev;
+use datasketches::common::ResizeFactor;
+use datasketches::error::ErrorKind;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::FixedThetaSketch;
 use datasketches::theta::ThetaSketch;
+use datasketches::theta::ThetaUnion;
+use datasketches::theta::ThetaUpdateOutcome;
 
 #[test]
 fn test_basic_update() {
@@ -288,3 +295,616 @@ fn test_compact_preserves_logical_non_empty_after_screened_update() {
     assert_eq!(compact.num_retained(), 0);
     assert_eq!(compact.theta64(), sketch.theta64());
 }
+
+#[test]
+fn test_sampling_probability_starts_theta_at_p() {
+    let sketch = ThetaSketch::builder()
+        .lg_k(12)
+        .sampling_probability(0.25)
+        .build();
+    assert_eq!(sketch.sampling_probability(), 0.25);
+    assert_eq!(sketch.theta64(), (i64::MAX as f64 * 0.25) as u64);
+    assert_eq!(ThetaSketch::builder().build().sampling_probability(), 1.0);
+}
+
+#[test]
+fn test_sampling_probability_serialization_round_trip() {
+    // Never updated: compacts to theta = 1.0 regardless of p.
+    let empty = ThetaSketch::builder().sampling_probability(0.5).build();
+    let bytes = empty.compact(true).serialize();
+    assert_eq!(bytes[0], 1);
+    let decoded = CompactThetaSketch::deserialize(&bytes).unwrap();
+    assert!(decoded.is_empty());
+    assert!(!decoded.is_estimation_mode());
+    assert_eq!(decoded.theta(), 1.0);
+
+    // Updated in exact range: theta = p is kept, so the estimate is scaled by 1/p.
+    let mut sketch = ThetaSketch::builder()
+        .lg_k(12)
+        .sampling_probability(0.5)
+        .build();
+    for i in 0..1000 {
+        sketch.update(i);
+    }
+    assert!(sketch.num_retained() < 1000);
+    let compact = sketch.compact(true);
+    for bytes in [compact.serialize(), compact.serialize_compressed()] {
+        let decoded = CompactThetaSketch::deserialize(&bytes).unwrap();
+        assert!(!decoded.is_empty());
+        assert_eq!(decoded.theta64(), sketch.theta64());
+        assert_eq!(decoded.num_retained(), sketch.num_retained());
+        assert_eq!(decoded.estimate(), sketch.estimate());
+    }
+}
+
+#[test]
+fn test_sampling_probability_serialization_screened_only() {
+    let mut sketch = ThetaSketch::builder()
+        .lg_k(12)
+        .sampling_probability(0.001)
+        .build();
+    let screened = (0u64..)
+        .find(|i| {
+            let mut probe = ThetaSketch::builder().sampling_probability(0.001).build();
+            probe.update(*i);
+            probe.num_retained() == 0
+        })
+        .unwrap();
+    sketch.update(screened);
+
+    let bytes = sketch.compact(true).serialize();
+    let decoded = CompactThetaSketch::deserialize(&bytes).unwrap();
+    assert!(!decoded.is_empty());
+    assert!(decoded.is_estimation_mode());
+    assert_eq!(decoded.num_retained(), 0);
+    assert_eq!(decoded.theta64(), sketch.theta64());
+    assert_eq!(decoded.estimate(), 0.0);
+}
+
+#[test]
+fn test_custom_seed_serialization_round_trip() {
+    let mut sketch = ThetaSketch::builder().lg_k(5).seed(4242).build();
+    for i in 0..1000 {
+        sketch.update(i);
+    }
+    let default_seed_hash = ThetaSketch::builder().build().seed_hash();
+    assert_ne!(sketch.seed_hash(), default_seed_hash);
+
+    let compact = sketch.compact(true);
+    for bytes in [compact.serialize(), compact.serialize_compressed()] {
+        assert_eq!(u16::from_le_bytes([bytes[6], bytes[7]]), sketch.seed_hash());
+
+        let decoded = CompactThetaSketch::deserialize_with_seed(&bytes, 4242).unwrap();
+        assert_eq!(decoded.seed_hash(), sketch.seed_hash());
+        assert_eq!(decoded.estimate(), sketch.estimate());
+
+        let err = CompactThetaSketch::deserialize(&bytes).unwrap_err();
+        assert!(err.message().contains("incompatible seed hash"));
+    }
+}
+
+#[test]
+fn test_update_outcome() {
+    let mut sketch = ThetaSketch::builder().lg_k(5).build();
+    assert_eq!(sketch.update("a"), ThetaUpdateOutcome::InsertedNew);
+    assert_eq!(sketch.update("a"), ThetaUpdateOutcome::Duplicate);
+    assert_eq!(sketch.update_f64(1.0), ThetaUpdateOutcome::InsertedNew);
+    assert_eq!(sketch.update_f32(1.0), ThetaUpdateOutcome::Duplicate);
+
+    // Once in estimation mode, some values are screened out by theta.
+    let mut rejected = 0;
+    let mut inserted = 0;
+    for i in 0..10_000 {
+        match sketch.update(i) {
+            ThetaUpdateOutcome::InsertedNew => inserted += 1,
+            ThetaUpdateOutcome::Duplicate => unreachable!("all values are distinct"),
+            ThetaUpdateOutcome::RejectedByTheta => rejected += 1,
+        }
+    }
+    assert!(sketch.is_estimation_mode());
+    assert!(rejected > 0);
+    assert!(inserted >= sketch.num_retained());
+}
+
+#[test]
+fn test_update_outcome_rejected_by_sampling() {
+    let mut sketch = ThetaSketch::builder().sampling_probability(0.001).build();
+    let outcomes: Vec<_> = (0..100).map(|i| sketch.update(i)).collect();
+    let rejected = outcomes
+        .iter()
+        .filter(|o| **o == ThetaUpdateOutcome::RejectedByTheta)
+        .count();
+    assert_eq!(100 - rejected, sketch.num_retained());
+    assert!(!sketch.is_empty());
+}
+
+#[test]
+fn test_update_quiet() {
+    let mut sketch = ThetaSketch::builder().build();
+    sketch.update_quiet("a");
+    sketch.update_quiet("a");
+    assert_eq!(sketch.num_retained(), 1);
+}
+
+#[test]
+fn test_resize_factor_growth() {
+    let lg_k = 10;
+    let full_size = 2 << lg_k;
+    for (factor, initial_size) in [
+        (ResizeFactor::X1, full_size),
+        (ResizeFactor::X2, 32),
+        (ResizeFactor::X4, 32),
+        (ResizeFactor::X8, 32),
+    ] {
+        let mut sketch = ThetaSketch::builder()
+            .lg_k(lg_k)
+            .resize_factor(factor)
+            .build();
+        assert_eq!(sketch.resize_factor(), factor);
+        assert_eq!(sketch.table_size(), initial_size);
+
+        let mut sizes = vec![sketch.table_size()];
+        for i in 0..10_000 {
+            sketch.update(i);
+            if sketch.table_size() != *sizes.last().unwrap() {
+                sizes.push(sketch.table_size());
+            }
+        }
+        // the table grows by exactly the factor until it reaches its full size
+        for pair in sizes.windows(2) {
+            assert_eq!(pair[1], pair[0] * factor.value());
+        }
+        assert_eq!(*sizes.last().unwrap(), full_size);
+
+        // the estimate does not depend on how the table grew
+        let mut reference = ThetaSketch::builder().lg_k(lg_k).build();
+        for i in 0..10_000 {
+            reference.update(i);
+        }
+        assert_eq!(sketch.estimate(), reference.estimate());
+
+        sketch.reset();
+        assert_eq!(sketch.table_size(), initial_size);
+    }
+}
+
+#[test]
+fn test_iterator_matches_compact_and_theta() {
+    let mut sketch = ThetaSketch::builder().lg_k(5).build();
+    for i in 0..1000 {
+        sketch.update(i);
+    }
+    assert!(sketch.is_estimation_mode());
+    assert!(sketch.iter().all(|hash| hash < sketch.theta64()));
+
+    let mut hashes: Vec<u64> = sketch.iter().collect();
+    hashes.sort_unstable();
+    hashes.dedup();
+    assert_eq!(hashes.len(), sketch.num_retained());
+
+    let compact = sketch.compact(true);
+    assert_eq!(compact.iter().collect::<Vec<_>>(), hashes);
+    assert_eq!(
+        sketch.num_retained() as f64 / sketch.theta(),
+        sketch.estimate()
+    );
+}
+
+#[test]
+fn test_update_bytes_hashes_raw_bytes() {
+    let mut from_bytes = ThetaSketch::builder().build();
+    let mut from_values = ThetaSketch::builder().build();
+    for i in 0u64..1000 {
+        from_bytes.update_bytes(&i.to_le_bytes());
+        from_values.update(i);
+    }
+    let mut bytes_hashes: Vec<u64> = from_bytes.iter().collect();
+    let mut value_hashes: Vec<u64> = from_values.iter().collect();
+    bytes_hashes.sort_unstable();
+    value_hashes.sort_unstable();
+    assert_eq!(bytes_hashes, value_hashes);
+
+    // unlike hashing a slice, no platform-dependent length prefix is hashed
+    let mut from_slice = ThetaSketch::builder().build();
+    from_slice.update(&b"apple"[..]);
+    let mut from_raw = ThetaSketch::builder().build();
+    from_raw.update_bytes(b"apple");
+    assert_ne!(
+        from_slice.iter().collect::<Vec<_>>(),
+        from_raw.iter().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_downsample() {
+    let mut sketch = ThetaSketch::builder().lg_k(12).seed(7).build();
+    let mut reference = ThetaSketch::builder().lg_k(9).seed(7).build();
+    for i in 0..50_000 {
+        sketch.update(i);
+        reference.update(i);
+    }
+
+    let small = sketch.downsample(9);
+    assert_eq!(small.lg_k(), 9);
+    assert_eq!(small.seed_hash(), sketch.seed_hash());
+    assert_eq!(small.num_retained(), 1 << 9);
+    assert!(small.theta64() < sketch.theta64());
+    // the k smallest hashes of the input, which a trimmed sketch of the same lg_k also keeps
+    reference.trim();
+    let mut expected: Vec<u64> = reference.iter().collect();
+    let mut actual: Vec<u64> = small.iter().collect();
+    expected.sort_unstable();
+    actual.sort_unstable();
+    assert_eq!(actual, expected);
+    assert_eq!(small.theta64(), reference.theta64());
+
+    // the downsampled sketch keeps accepting updates
+    let mut small = small;
+    for i in 50_000..100_000 {
+        small.update(i);
+    }
+    assert!((small.estimate() - 100_000.0).abs() < 15_000.0);
+
+    // the same lg_k trims the table to k entries
+    let same = sketch.downsample(12);
+    assert_eq!(same.num_retained(), 1 << 12);
+    sketch.trim();
+    assert_eq!(same.theta64(), sketch.theta64());
+
+    let mut exact = ThetaSketch::builder().lg_k(12).build();
+    for i in 0..100 {
+        exact.update(i);
+    }
+    let exact_small = exact.downsample(5);
+    assert_eq!(exact_small.num_retained(), 32);
+    assert!(exact_small.is_estimation_mode());
+    assert!(ThetaSketch::builder().build().downsample(5).is_empty());
+}
+
+#[test]
+fn test_compact_downsample() {
+    let mut sketch = ThetaSketch::builder().lg_k(12).build();
+    for i in 0..50_000 {
+        sketch.update(i);
+    }
+    let updatable = sketch.downsample(9);
+
+    for ordered in [false, true] {
+        let compact = sketch.compact(ordered).downsample(9);
+        assert_eq!(compact.is_ordered(), ordered);
+        assert_eq!(compact.num_retained(), 1 << 9);
+        assert_eq!(compact.theta64(), updatable.theta64());
+        let mut actual: Vec<u64> = compact.iter().collect();
+        if ordered {
+            assert!(actual.is_sorted());
+        }
+        let mut expected: Vec<u64> = updatable.iter().collect();
+        actual.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(actual, expected);
+    }
+
+    sketch.trim();
+    let compact = sketch.compact(true);
+    let unchanged = compact.downsample(12);
+    assert_eq!(unchanged.num_retained(), compact.num_retained());
+    assert_eq!(unchanged.theta64(), compact.theta64());
+    assert!(CompactThetaSketch::empty().downsample(5).is_empty());
+}
+
+#[test]
+fn test_compact_trim() {
+    let mut sketch = ThetaSketch::builder().lg_k(12).build();
+    for i in 0..50_000 {
+        sketch.update(i);
+    }
+
+    for ordered in [false, true] {
+        let compact = sketch.compact(ordered);
+        let trimmed = compact.trim(1000);
+        assert_eq!(trimmed.is_ordered(), ordered);
+        assert_eq!(trimmed.num_retained(), 1000);
+
+        let mut all: Vec<u64> = compact.iter().collect();
+        all.sort_unstable();
+        let mut kept: Vec<u64> = trimmed.iter().collect();
+        kept.sort_unstable();
+        assert_eq!(kept, all[..1000]);
+        assert_eq!(trimmed.theta64(), all[1000]);
+        assert!((trimmed.estimate() - 50_000.0).abs() < 50_000.0 * 0.15);
+    }
+
+    let compact = sketch.compact(true);
+    let unchanged = compact.trim(compact.num_retained());
+    assert_eq!(unchanged.num_retained(), compact.num_retained());
+    assert_eq!(unchanged.theta64(), compact.theta64());
+    assert!(CompactThetaSketch::empty().trim(1).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "k must be positive")]
+fn test_compact_trim_rejects_zero() {
+    CompactThetaSketch::empty().trim(0);
+}
+
+#[test]
+#[should_panic(expected = "lg_k must be in [5, 10], got 11")]
+fn test_downsample_rejects_larger_lg_k() {
+    ThetaSketch::builder().lg_k(10).build().downsample(11);
+}
+
+#[test]
+fn test_fixed_sketch_matches_dynamic_sketch() {
+    let mut fixed = FixedThetaSketch::<9>::new();
+    let mut dynamic = ThetaSketch::builder().lg_k(9).build();
+    assert_eq!(FixedThetaSketch::<9>::K, 512);
+    assert_eq!(fixed.as_sketch().resize_factor(), ResizeFactor::X1);
+    assert_eq!(fixed.as_sketch().table_size(), 1024);
+
+    for i in 0..5_000 {
+        assert_eq!(fixed.update(i), dynamic.update(i));
+        assert_eq!(fixed.as_sketch().table_size(), 1024);
+    }
+    assert_eq!(fixed.estimate(), dynamic.estimate());
+    assert_eq!(fixed.as_sketch().theta64(), dynamic.theta64());
+    assert_eq!(
+        fixed.compact(true).iter().collect::<Vec<_>>(),
+        dynamic.compact(true).iter().collect::<Vec<_>>()
+    );
+
+    let mut union = ThetaUnion::builder().lg_k(9).build();
+    union.update(&fixed).unwrap();
+    let mut expected = ThetaUnion::builder().lg_k(9).build();
+    expected.update(&dynamic).unwrap();
+    assert_eq!(union.result().estimate(), expected.result().estimate());
+
+    fixed.reset();
+    assert!(fixed.as_sketch().is_empty());
+    assert_eq!(fixed.as_sketch().table_size(), 1024);
+    let sketch: ThetaSketch = fixed.into();
+    assert_eq!(sketch.lg_k(), 9);
+}
+
+#[test]
+fn test_fixed_sketch_with_seed() {
+    let mut fixed = FixedThetaSketch::<5>::with_seed(7);
+    fixed.update("apple");
+    let mut dynamic = ThetaSketch::builder().lg_k(5).seed(7).build();
+    dynamic.update("apple");
+    assert_eq!(fixed.as_sketch().seed_hash(), dynamic.seed_hash());
+    assert_eq!(
+        fixed.as_sketch().iter().collect::<Vec<_>>(),
+        dynamic.iter().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_hip_estimator_exact_mode() {
+    let mut sketch = ThetaSketch::builder().hip_estimator(true).build();
+    assert!(sketch.is_hip_estimator());
+    assert_eq!(sketch.estimate(), 0.0);
+    for i in 0..1_000 {
+        sketch.update(i);
+        sketch.update(i);
+    }
+    assert_eq!(sketch.estimate(), 1_000.0);
+
+    sketch.reset();
+    assert_eq!(sketch.estimate(), 0.0);
+    sketch.update("apple");
+    assert_eq!(sketch.estimate(), 1.0);
+
+    let sampled = {
+        let mut sketch = ThetaSketch::builder()
+            .sampling_probability(0.5)
+            .hip_estimator(true)
+            .build();
+        for i in 0..1_000 {
+            sketch.update(i);
+        }
+        sketch
+    };
+    assert_eq!(sampled.estimate(), sampled.num_retained() as f64 / 0.5);
+}
+
+#[test]
+fn test_hip_estimator_is_more_accurate() {
+    let n = 20_000;
+    let trials = 50;
+    let (mut hip_error, mut standard_error) = (0.0, 0.0);
+    for trial in 0..trials {
+        let mut hip = ThetaSketch::builder().lg_k(8).hip_estimator(true).build();
+        let mut standard = ThetaSketch::builder().lg_k(8).build();
+        for i in 0..n {
+            let value = (trial * n + i) as u64;
+            hip.update(value);
+            standard.update(value);
+        }
+        assert_eq!(hip.theta64(), standard.theta64());
+        hip_error += (hip.estimate() / n as f64 - 1.0).powi(2);
+        standard_error += (standard.estimate() / n as f64 - 1.0).powi(2);
+    }
+    assert!(
+        hip_error < standard_error,
+        "HIP {hip_error} vs standard {standard_error}"
+    );
+}
+
+#[test]
+fn test_hip_estimator_not_serialized() {
+    let mut sketch = ThetaSketch::builder().lg_k(8).hip_estimator(true).build();
+    for i in 0..10_000 {
+        sketch.update(i);
+    }
+    let decoded = ThetaSketch::deserialize(&sketch.serialize()).unwrap();
+    assert!(!decoded.is_hip_estimator());
+    assert_eq!(
+        decoded.estimate(),
+        decoded.num_retained() as f64 / decoded.theta()
+    );
+    assert_eq!(
+        sketch.compact(false).estimate(),
+        decoded.compact(false).estimate()
+    );
+}
+
+#[test]
+fn test_memory_usage_counts_table_capacity() {
+    let mut sketch = ThetaSketch::builder().lg_k(12).build();
+    let empty = sketch.memory_usage_bytes();
+    assert!(empty >= size_of::<ThetaSketch>());
+    for i in 0..100_000u64 {
+        sketch.update(i);
+    }
+    // the table holds 2^(lg_k + 1) slots once it stops resizing
+    assert!(sketch.memory_usage_bytes() >= size_of::<ThetaSketch>() + (8 << 13));
+    assert!(sketch.memory_usage_bytes() > sketch.num_retained() * 8);
+    assert!(sketch.memory_usage_bytes() > empty);
+
+    let compact = sketch.compact(true);
+    assert!(compact.memory_usage_bytes() >= compact.num_retained() * 8);
+    assert!(compact.memory_usage_bytes() < sketch.memory_usage_bytes());
+
+    let mut union = ThetaUnion::builder().lg_k(12).build();
+    let before = union.memory_usage_bytes();
+    union.update(&sketch).unwrap();
+    assert!(union.memory_usage_bytes() > before);
+}
+
+#[test]
+fn test_builder_validation() {
+    for lg_k in [0, 4, 27] {
+        let err = ThetaSketch::builder().lg_k(lg_k).try_build().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+        assert!(err.message().contains("lg_k must be in [5, 26]"));
+    }
+    for p in [0.0, -0.5, 1.5, f32::NAN] {
+        let err = ThetaSketch::builder()
+            .sampling_probability(p)
+            .try_build()
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+        assert!(err.message().contains("sampling_probability"));
+    }
+
+    let sketch = ThetaSketch::builder()
+        .lg_k(26)
+        .sampling_probability(1.0)
+        .try_build()
+        .unwrap();
+    assert_eq!(sketch.lg_k(), 26);
+}
+
+#[test]
+#[should_panic(expected = "lg_k must be in [5, 26], got 4")]
+fn test_builder_build_rejects_lg_k() {
+    ThetaSketch::builder().lg_k(4).build();
+}
+
+#[test]
+fn test_approximately_equal() {
+    let mut a = ThetaSketch::builder().lg_k(10).build();
+    let mut b = ThetaSketch::builder().lg_k(10).build();
+    assert!(a.approximately_equal(&b, 0.0));
+    for i in 0..20_000 {
+        a.update(i);
+        b.update(i);
+    }
+    assert!(a.approximately_equal(&b, 0.0));
+    b.update(-1);
+    assert!(a.approximately_equal(&b, 0.01));
+    assert!(!a.approximately_equal(&ThetaSketch::builder().lg_k(11).build(), 1.0));
+    assert!(!a.approximately_equal(&ThetaSketch::builder().lg_k(10).seed(7).build(), 1.0));
+
+    let compact = a.compact(true);
+    let restored = CompactThetaSketch::deserialize(&compact.serialize()).unwrap();
+    assert!(compact.approximately_equal(&restored, 0.0));
+    assert!(compact.approximately_equal(&b.compact(false), 0.01));
+    assert!(!compact.approximately_equal(&CompactThetaSketch::empty_with_seed(7), 1.0));
+}
+
+#[test]
+fn test_clear_keeps_table() {
+    let mut sketch = ThetaSketch::builder()
+        .lg_k(9)
+        .sampling_probability(0.5)
+        .hip_estimator(true)
+        .build();
+    let initial_table_size = sketch.table_size();
+    for i in 0..5_000 {
+        sketch.update(i);
+    }
+    let table_size = sketch.table_size();
+    assert!(table_size > initial_table_size);
+    let memory = sketch.memory_usage_bytes();
+
+    sketch.clear();
+    assert!(sketch.is_empty());
+    assert_eq!(sketch.num_retained(), 0);
+    assert_eq!(sketch.theta(), 0.5);
+    assert_eq!(sketch.estimate(), 0.0);
+    assert_eq!(sketch.table_size(), table_size);
+    assert_eq!(sketch.memory_usage_bytes(), memory);
+
+    // a cleared sketch builds the same result as a fresh one
+    let mut fresh = ThetaSketch::builder()
+        .lg_k(9)
+        .sampling_probability(0.5)
+        .build();
+    for i in 10_000..20_000 {
+        sketch.update(i);
+        fresh.update(i);
+    }
+    let mut hashes: Vec<u64> = sketch.iter().collect();
+    let mut fresh_hashes: Vec<u64> = fresh.iter().collect();
+    hashes.sort_unstable();
+    fresh_hashes.sort_unstable();
+    assert_eq!(hashes, fresh_hashes);
+    assert_eq!(sketch.theta(), fresh.theta());
+
+    sketch.reset();
+    assert_eq!(sketch.table_size(), initial_table_size);
+}
+
+#[test]
+fn test_snapshot_of_live_sketch() {
+    let mut sketch = ThetaSketch::builder().lg_k(9).build();
+    for i in 0..5_000 {
+        sketch.update(i);
+    }
+    let snapshot = sketch.snapshot();
+    assert_eq!(snapshot.estimate(), sketch.estimate());
+    assert_eq!(snapshot.num_retained(), sketch.num_retained());
+    assert!(!snapshot.is_ordered());
+
+    // the live sketch keeps being updated without affecting the snapshot
+    let estimate = snapshot.estimate();
+    for i in 5_000..10_000 {
+        sketch.update(i);
+    }
+    assert_eq!(snapshot.estimate(), estimate);
+    assert_ne!(sketch.estimate(), estimate);
+
+    let ordered = snapshot.into_ordered();
+    assert!(ordered.is_ordered());
+    assert_eq!(ordered.estimate(), estimate);
+    assert!(ordered.iter().is_sorted());
+}
+
+#[test]
+fn test_estimate_summary() {
+    let mut sketch = ThetaSketch::builder().lg_k(9).build();
+    for i in 0..5_000 {
+        sketch.update(i);
+    }
+    let summary = sketch.estimate_summary(NumStdDev::Two);
+    assert_eq!(summary.family, CardinalityFamily::Theta);
+    assert_eq!(summary.estimate, sketch.estimate());
+    assert_eq!(summary.lb, sketch.lower_bound(NumStdDev::Two));
+    assert_eq!(summary.ub, sketch.upper_bound(NumStdDev::Two));
+    assert_eq!(summary.retained, sketch.num_retained() as u64);
+    assert_eq!(
+        sketch.compact(true).estimate_summary(NumStdDev::Two),
+        summary
+    );
+}
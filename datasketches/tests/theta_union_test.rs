@@ -0,0 +1,347 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::error::ErrorKind;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaSketch;
+use datasketches::theta::ThetaUnion;
+
+fn sketch_with_range(lg_k: u8, start: u64, count: u64) -> ThetaSketch {
+    let mut sketch = ThetaSketch::builder().lg_k(lg_k).build();
+    for i in 0..count {
+        sketch.update(start + i);
+    }
+    sketch
+}
+
+#[test]
+fn test_empty_union() {
+    let union = ThetaUnion::builder().build();
+    let result = union.result();
+    assert!(result.is_empty());
+    assert_eq!(result.estimate(), 0.0);
+    assert_eq!(union.stats().num_sketches(), 0);
+}
+
+#[test]
+fn test_union_with_empty_sketch() {
+    let mut union = ThetaUnion::builder().build();
+    let empty = ThetaSketch::builder().build();
+    union.update(&empty).unwrap();
+
+    assert!(union.result().is_empty());
+    assert_eq!(union.stats().num_sketches(), 1);
+    assert_eq!(union.stats().num_entries(), 0);
+}
+
+#[test]
+fn test_exact_mode_overlap() {
+    let a = sketch_with_range(12, 0, 1000);
+    let b = sketch_with_range(12, 500, 1000);
+
+    let mut union = ThetaUnion::builder().lg_k(12).build();
+    union.update(&a).unwrap();
+    union.update(&b.compact(false)).unwrap();
+
+    let result = union.result();
+    assert!(!result.is_empty());
+    assert!(!result.is_estimation_mode());
+    assert!(result.is_ordered());
+    assert_eq!(result.estimate(), 1500.0);
+
+    let stats = union.stats();
+    assert_eq!(stats.num_sketches(), 2);
+    assert_eq!(stats.num_entries(), 2000);
+    assert_eq!(stats.num_rejected(), 0);
+    assert_eq!(stats.num_downsamples(), 0);
+}
+
+#[test]
+fn test_estimation_mode() {
+    let a = sketch_with_range(12, 0, 10000);
+    let b = sketch_with_range(12, 5000, 10000);
+
+    let mut union = ThetaUnion::builder().lg_k(12).build();
+    union.update(&a).unwrap();
+    union.update(&b).unwrap();
+
+    let result = union.result_with_ordered(false);
+    assert!(result.is_estimation_mode());
+    assert!(!result.is_ordered());
+    assert!(result.num_retained() <= 4096);
+    let estimate = result.estimate();
+    assert!((estimate - 15000.0).abs() / 15000.0 < 0.05, "{estimate}");
+
+    let stats = union.stats();
+    assert_eq!(stats.num_sketches(), 2);
+    assert_eq!(
+        stats.num_entries(),
+        (a.num_retained() + b.num_retained()) as u64
+    );
+    assert!(stats.num_rejected() > 0);
+}
+
+#[test]
+fn test_to_compact_ordering() {
+    let a = sketch_with_range(12, 0, 10000);
+    let b = sketch_with_range(12, 5000, 10000);
+
+    let mut union = ThetaUnion::builder().lg_k(12).build();
+    union.update(&a).unwrap();
+    union.update(&b).unwrap();
+
+    let ordered = union.to_compact(true);
+    assert!(ordered.is_ordered());
+    assert!(ordered.iter().collect::<Vec<_>>().is_sorted());
+    let unordered = union.to_compact(false);
+    assert!(!unordered.is_ordered());
+    assert_eq!(ordered.theta64(), unordered.theta64());
+    let mut hashes: Vec<u64> = unordered.iter().collect();
+    hashes.sort_unstable();
+    assert_eq!(hashes, ordered.iter().collect::<Vec<_>>());
+
+    // an ordered union result can be fed back into another union
+    let mut outer = ThetaUnion::builder().lg_k(12).build();
+    outer.update(&ordered).unwrap();
+    outer.update(&sketch_with_range(12, 20000, 1000)).unwrap();
+    let estimate = outer.to_compact(true).estimate();
+    assert!((estimate - 16000.0).abs() / 16000.0 < 0.05, "{estimate}");
+
+    assert!(ThetaUnion::builder().build().to_compact(false).is_empty());
+}
+
+#[test]
+fn test_downsampling_to_smaller_k() {
+    let a = sketch_with_range(12, 0, 4000);
+
+    let mut union = ThetaUnion::builder().lg_k(8).build();
+    union.update(&a).unwrap();
+
+    let result = union.result();
+    assert!(result.is_estimation_mode());
+    assert!(result.num_retained() <= 256);
+    assert!(union.stats().num_downsamples() > 0);
+    let estimate = result.estimate();
+    assert!((estimate - 4000.0).abs() / 4000.0 < 0.2, "{estimate}");
+}
+
+#[test]
+fn test_ordered_input_early_stop_counts_rejected() {
+    let small = sketch_with_range(5, 0, 1000).compact(true);
+    let large = sketch_with_range(12, 0, 1000).compact(true);
+    assert!(small.is_estimation_mode());
+    assert!(!large.is_estimation_mode());
+
+    let mut union = ThetaUnion::builder().lg_k(12).build();
+    union.update(&small).unwrap();
+    union.update(&large).unwrap();
+
+    let stats = union.stats();
+    assert_eq!(stats.num_entries(), 1000 + small.num_retained() as u64);
+    // every entry of the large sketch at or above the small sketch theta is rejected
+    let expected_rejected = large.iter().filter(|&h| h >= small.theta64()).count() as u64;
+    assert_eq!(stats.num_rejected(), expected_rejected);
+    assert_eq!(union.result().num_retained(), small.num_retained());
+}
+
+#[test]
+fn test_seed_mismatch() {
+    let mut sketch = ThetaSketch::builder().seed(123).build();
+    sketch.update(1);
+
+    let mut union = ThetaUnion::builder().build();
+    union.update(&ThetaSketch::empty()).unwrap();
+    let stats = union.stats();
+    let err = union.update(&sketch).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SeedHashMismatch);
+    assert_eq!(union.stats(), stats);
+    assert_eq!(union.stats().num_sketches(), 1);
+
+    let mut union = ThetaUnion::builder().seed(123).build();
+    union.update(&sketch).unwrap();
+    assert_eq!(union.result().estimate(), 1.0);
+}
+
+#[test]
+fn test_reset() {
+    let mut union = ThetaUnion::builder().lg_k(5).build();
+    union.update(&sketch_with_range(5, 0, 1000)).unwrap();
+    assert!(!union.result().is_empty());
+
+    union.reset();
+    assert!(union.result().is_empty());
+    assert_eq!(union.stats().num_sketches(), 0);
+
+    union.update(&sketch_with_range(5, 0, 10)).unwrap();
+    assert_eq!(union.result().estimate(), 10.0);
+}
+
+#[test]
+fn test_empty_is_union_identity() {
+    let sketch = sketch_with_range(5, 0, 1000);
+
+    let mut plain = ThetaUnion::builder().lg_k(5).build();
+    plain.update(&sketch).unwrap();
+
+    let mut with_identity = ThetaUnion::builder().lg_k(5).build();
+    with_identity.update(&CompactThetaSketch::empty()).unwrap();
+    with_identity.update(&sketch).unwrap();
+    with_identity.update(&ThetaSketch::empty()).unwrap();
+
+    let expected: Vec<u64> = plain.result().iter().collect();
+    let actual: Vec<u64> = with_identity.result().iter().collect();
+    assert_eq!(actual, expected);
+    assert_eq!(with_identity.result().theta64(), plain.result().theta64());
+
+    // empty inputs are accepted whatever their seed
+    with_identity
+        .update(&CompactThetaSketch::empty_with_seed(123))
+        .unwrap();
+    assert!(CompactThetaSketch::empty_with_seed(123).is_empty());
+}
+
+#[test]
+fn test_union_opt_fold() {
+    let per_key = [
+        Some(sketch_with_range(12, 0, 10)),
+        None,
+        Some(sketch_with_range(12, 5, 10)),
+        None,
+    ];
+    let union = per_key
+        .iter()
+        .try_fold(ThetaUnion::builder().build(), |union, sketch| {
+            union.union_opt(sketch.as_ref())
+        })
+        .unwrap();
+    assert_eq!(union.result().estimate(), 15.0);
+    assert_eq!(union.stats().num_sketches(), 2);
+
+    let all_missing: [Option<ThetaSketch>; 2] = [None, None];
+    let union = all_missing
+        .iter()
+        .try_fold(ThetaUnion::builder().build(), |union, sketch| {
+            union.union_opt(sketch.as_ref())
+        })
+        .unwrap();
+    assert!(union.result().is_empty());
+    assert_eq!(union.stats().num_sketches(), 0);
+}
+
+#[test]
+fn test_update_opt_seed_mismatch() {
+    let mut sketch = ThetaSketch::builder().seed(123).build();
+    sketch.update(1);
+
+    let mut union = ThetaUnion::builder().build();
+    union.update_opt::<ThetaSketch>(None).unwrap();
+    assert!(union.update_opt(Some(&sketch)).is_err());
+    assert!(
+        ThetaUnion::builder()
+            .build()
+            .union_opt(Some(&sketch))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_union_many_matches_sequential_updates() {
+    let partitions: Vec<ThetaSketch> = (0..200)
+        .map(|p| sketch_with_range(12, p * 100, 300))
+        .collect();
+
+    let mut sequential = ThetaUnion::builder().build();
+    for sketch in &partitions {
+        sequential.update(sketch).unwrap();
+    }
+    let mut bulk = ThetaUnion::builder().build();
+    bulk.union_many(&partitions).unwrap();
+
+    let expected = sequential.result();
+    let actual = bulk.result();
+    assert!(actual.is_estimation_mode());
+    assert_eq!(actual.theta64(), expected.theta64());
+    assert_eq!(
+        actual.iter().collect::<Vec<_>>(),
+        expected.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(bulk.stats(), sequential.stats());
+
+    let compacts: Vec<CompactThetaSketch> = partitions.iter().map(|s| s.compact(true)).collect();
+    let mut from_compact = ThetaUnion::builder().build();
+    from_compact.union_many(&compacts).unwrap();
+    assert_eq!(from_compact.result().theta64(), expected.theta64());
+}
+
+#[test]
+fn test_union_many_seed_mismatch() {
+    let mut other_seed = ThetaSketch::builder().seed(123).build();
+    other_seed.update(1);
+    let sketches = [sketch_with_range(12, 0, 10), other_seed];
+
+    let mut union = ThetaUnion::builder().build();
+    assert!(union.union_many(&sketches).is_err());
+    assert_eq!(union.result().estimate(), 10.0);
+}
+
+#[test]
+fn test_update_bytes_matches_update() {
+    let a = sketch_with_range(10, 0, 5_000).compact(true);
+    let b = sketch_with_range(12, 2_500, 5_000).compact(false);
+
+    let mut expected = ThetaUnion::builder().lg_k(11).build();
+    expected.update(&a).unwrap();
+    expected.update(&b).unwrap();
+
+    let mut union = ThetaUnion::builder().lg_k(11).build();
+    union.update_bytes(&a.serialize()).unwrap();
+    union.update_bytes(&b.serialize()).unwrap();
+    assert!(union.result().iter().eq(expected.result().iter()));
+    assert_eq!(union.result().theta64(), expected.result().theta64());
+    assert_eq!(union.stats(), expected.stats());
+
+    let mut compressed = ThetaUnion::builder().lg_k(11).build();
+    compressed.update_bytes(&a.serialize_compressed()).unwrap();
+    compressed
+        .update_bytes(&b.clone().into_ordered().serialize_compressed())
+        .unwrap();
+    assert!(compressed.result().iter().eq(expected.result().iter()));
+}
+
+#[test]
+fn test_update_bytes_errors_leave_union_unchanged() {
+    let mut union = ThetaUnion::builder().build();
+    union
+        .update_bytes(&sketch_with_range(12, 0, 10).compact(true).serialize())
+        .unwrap();
+
+    let mut other_seed = ThetaSketch::builder().seed(123).build();
+    other_seed.update(1);
+    assert!(
+        union
+            .update_bytes(&other_seed.compact(true).serialize())
+            .is_err()
+    );
+    assert!(
+        union
+            .update_bytes(&sketch_with_range(12, 0, 10).serialize())
+            .is_err()
+    );
+    assert!(union.update_bytes(&[]).is_err());
+    assert_eq!(union.result().estimate(), 10.0);
+    assert_eq!(union.stats().num_sketches(), 1);
+}
@@ -0,0 +1,150 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datasketches::common::NumStdDev;
+use datasketches::theta::CompactThetaSketch;
+use datasketches::theta::ThetaIntersection;
+use datasketches::theta::ThetaSketch;
+use datasketches::theta::ThetaUnion;
+use datasketches::theta::WrappedCompactThetaSketch;
+
+fn build_sketch(lg_k: u8, n: u64, offset: u64) -> ThetaSketch {
+    let mut sketch = ThetaSketch::builder().lg_k(lg_k).build();
+    for i in 0..n {
+        sketch.update(i + offset);
+    }
+    sketch
+}
+
+fn assert_wrapped_matches(wrapped: &WrappedCompactThetaSketch<'_>, compact: &CompactThetaSketch) {
+    assert_eq!(wrapped.is_empty(), compact.is_empty());
+    assert_eq!(wrapped.theta64(), compact.theta64());
+    assert_eq!(wrapped.seed_hash(), compact.seed_hash());
+    assert_eq!(wrapped.num_retained(), compact.num_retained());
+    assert_eq!(wrapped.is_estimation_mode(), compact.is_estimation_mode());
+    assert_eq!(wrapped.estimate(), compact.estimate());
+    for num_std_dev in [NumStdDev::One, NumStdDev::Two, NumStdDev::Three] {
+        assert_eq!(
+            wrapped.lower_bound(num_std_dev),
+            compact.lower_bound(num_std_dev)
+        );
+        assert_eq!(
+            wrapped.upper_bound(num_std_dev),
+            compact.upper_bound(num_std_dev)
+        );
+    }
+    assert_eq!(
+        wrapped.iter().collect::<Vec<_>>(),
+        compact.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        wrapped.iter().size_hint(),
+        (compact.num_retained(), Some(compact.num_retained()))
+    );
+}
+
+#[test]
+fn test_wrap_matches_compact() {
+    // Counts cover empty, single-item, exact and estimation mode, and partial delta blocks.
+    for n in [0, 1, 2, 7, 8, 9, 100, 1000, 10_000] {
+        let sketch = build_sketch(9, n, 0);
+        for ordered in [true, false] {
+            let compact = sketch.compact(ordered);
+            let bytes = compact.serialize();
+            let wrapped = WrappedCompactThetaSketch::wrap(&bytes).unwrap();
+            assert_eq!(wrapped.is_ordered(), compact.is_ordered());
+            assert_wrapped_matches(&wrapped, &compact);
+
+            let bytes = compact.serialize_compressed();
+            let wrapped = WrappedCompactThetaSketch::wrap(&bytes).unwrap();
+            assert_wrapped_matches(&wrapped, &compact);
+        }
+    }
+}
+
+#[test]
+fn test_wrap_to_compact() {
+    let compact = build_sketch(10, 5000, 0).compact(true);
+    let bytes = compact.serialize_compressed();
+    let wrapped = WrappedCompactThetaSketch::wrap(&bytes).unwrap();
+    let copied = wrapped.to_compact();
+    assert_eq!(copied.serialize_compressed(), bytes);
+}
+
+#[test]
+fn test_wrapped_set_operations() {
+    let a = build_sketch(10, 10_000, 0).compact(true);
+    let b = build_sketch(10, 10_000, 5_000).compact(false);
+    let a_bytes = a.serialize_compressed();
+    let b_bytes = b.serialize();
+    let a_wrapped = WrappedCompactThetaSketch::wrap(&a_bytes).unwrap();
+    let b_wrapped = WrappedCompactThetaSketch::wrap(&b_bytes).unwrap();
+
+    let mut union = ThetaUnion::builder().lg_k(10).build();
+    union.update(&a).unwrap();
+    union.update(&b).unwrap();
+    let mut wrapped_union = ThetaUnion::builder().lg_k(10).build();
+    wrapped_union.update(&a_wrapped).unwrap();
+    wrapped_union.update(&b_wrapped).unwrap();
+    assert_eq!(wrapped_union.result().estimate(), union.result().estimate());
+
+    let mut intersection = ThetaIntersection::new_with_default_seed();
+    intersection.update(&a).unwrap();
+    intersection.update(&b).unwrap();
+    let mut wrapped_intersection = ThetaIntersection::new_with_default_seed();
+    wrapped_intersection.update(&a_wrapped).unwrap();
+    wrapped_intersection.update(&b_wrapped).unwrap();
+    assert_eq!(
+        wrapped_intersection.result().estimate(),
+        intersection.result().estimate()
+    );
+}
+
+#[test]
+fn test_wrap_with_seed() {
+    let mut sketch = ThetaSketch::builder().seed(42).build();
+    sketch.update("apple");
+    let bytes = sketch.compact(true).serialize();
+
+    let wrapped = WrappedCompactThetaSketch::wrap_with_seed(&bytes, 42).unwrap();
+    assert_eq!(wrapped.num_retained(), 1);
+    let err = WrappedCompactThetaSketch::wrap(&bytes).unwrap_err();
+    assert!(err.message().contains("incompatible seed hash"));
+}
+
+#[test]
+fn test_wrap_rejects_truncated_images() {
+    let compact = build_sketch(9, 1000, 0).compact(true);
+    for bytes in [compact.serialize(), compact.serialize_compressed()] {
+        assert!(WrappedCompactThetaSketch::wrap(&bytes).is_ok());
+        for len in [0, 4, 8, bytes.len() - 1] {
+            assert!(
+                WrappedCompactThetaSketch::wrap(&bytes[..len]).is_err(),
+                "wrapping {len} of {} bytes should fail",
+                bytes.len()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_wrap_rejects_legacy_serial_version() {
+    let mut bytes = build_sketch(9, 10, 0).compact(true).serialize();
+    bytes[1] = 2;
+    let err = WrappedCompactThetaSketch::wrap(&bytes).unwrap_err();
+    assert!(err.message().contains("unsupported serial version"));
+}
@@ -71,7 +71,7 @@ def generate_java_files(workspace_dir, project_dir):
 
     # 4. Clone repository
     repo_url = "https://github.com/apache/datasketches-java.git"
-    branch = "9.0.0" # FIXME: temporarily use fixed branch until mvn issue is resolved
+    branch = "9.0.0" # keep in sync with TARGET_JAVA_RELEASE in datasketches/src/compat.rs
     run_command([
         "git", "clone",
         "--depth", "1",
@@ -81,7 +81,14 @@ def generate_java_files(workspace_dir, project_dir):
         str(temp_dir)
     ])
 
-    # 5. Run Maven to generate files
+    # 5. Add the generators of this repository, e.g. for updatable theta sketches, which
+    # datasketches-java does not write to the corpus itself
+    java_test_dir = temp_dir / "src" / "test" / "java" / "org" / "apache" / "datasketches" / "theta"
+    for file_path in (workspace_dir / "tools" / "java").glob("*.java"):
+        shutil.copy2(file_path, java_test_dir)
+        print(f"Added generator: {file_path.name}")
+
+    # 6. Run Maven to generate files
     mvn_cmd = ["mvn", "test", "-P", "generate-java-files"]
     use_shell = False
     if os.name == 'nt': # Windows
@@ -90,7 +97,7 @@ def generate_java_files(workspace_dir, project_dir):
 
     run_command(mvn_cmd, cwd=temp_dir, shell=use_shell)
 
-    # 6. Copy generated files
+    # 7. Copy generated files
     generated_files_dir = temp_dir / "serialization_test_data" / "java_generated_files"
 
     if not generated_files_dir.exists():
@@ -133,7 +140,7 @@ def generate_cpp_files(workspace_dir, project_root):
 
     # 4. Clone repository
     repo_url = "https://github.com/apache/datasketches-cpp.git"
-    branch = "master"
+    branch = "5.2.0" # keep in sync with TARGET_CPP_RELEASE in datasketches/src/compat.rs
     run_command([
         "git", "clone",
         "--depth", "1",
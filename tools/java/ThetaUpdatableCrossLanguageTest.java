@@ -0,0 +1,50 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+package org.apache.datasketches.theta;
+
+import static org.apache.datasketches.common.TestUtil.GENERATE_JAVA_FILES;
+import static org.apache.datasketches.common.TestUtil.javaPath;
+
+import java.io.IOException;
+import java.nio.file.Files;
+
+import org.testng.annotations.Test;
+
+/**
+ * Writes updatable theta sketches for the serialization test corpus of datasketches-rust.
+ *
+ * <p>datasketches-java only writes compact theta images to the corpus. This test is copied into
+ * the datasketches-java checkout by tools/generate_serialization_test_data.py so that the
+ * updatable (QuickSelect) layout read by ThetaSketch::deserialize is covered as well.</p>
+ */
+public class ThetaUpdatableCrossLanguageTest {
+
+  @Test(groups = {GENERATE_JAVA_FILES})
+  public void generateUpdatableBinaries() throws IOException {
+    final int[] nArr = {0, 1, 10, 100, 1000, 10_000, 100_000, 1_000_000};
+    for (final int n : nArr) {
+      final UpdateSketch sketch = UpdateSketch.builder().build();
+      for (int i = 0; i < n; i++) {
+        sketch.update(i);
+      }
+      Files.write(javaPath.resolve("theta_update_n" + n + "_java.sk"), sketch.toByteArray());
+    }
+  }
+}
@@ -51,7 +51,10 @@ struct CommandTest {
 
 impl CommandTest {
     fn run(self) {
-        run_command(make_test_cmd(self.no_capture, &[]));
+        run_command(make_test_cmd(
+            self.no_capture,
+            &["perf", "shared", "testing"],
+        ));
     }
 }
 
@@ -67,6 +70,7 @@ impl CommandLint {
         run_command(make_clippy_cmd(self.fix));
         run_command(make_format_cmd(self.fix));
         run_command(make_docs_cmd());
+        run_command(make_wasm_check_cmd());
         run_command(make_taplo_cmd(self.fix));
         run_command(make_typos_cmd());
         run_command(make_hawkeye_cmd(self.fix));
@@ -153,6 +157,19 @@ fn make_docs_cmd() -> StdCommand {
     cmd
 }
 
+fn make_wasm_check_cmd() -> StdCommand {
+    let mut cmd = find_command("cargo");
+    cmd.args([
+        "check",
+        "--package",
+        "datasketches",
+        "--all-features",
+        "--target",
+        "wasm32-unknown-unknown",
+    ]);
+    cmd
+}
+
 fn make_hawkeye_cmd(fix: bool) -> StdCommand {
     ensure_installed("hawkeye", "hawkeye");
     let mut cmd = find_command("hawkeye");